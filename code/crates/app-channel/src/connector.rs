@@ -82,6 +82,7 @@ where
                 height,
                 round,
                 timeout,
+                cancel,
                 reply_to,
             } => {
                 let (reply, rx) = oneshot::channel();
@@ -91,6 +92,27 @@ where
                         height,
                         round,
                         timeout,
+                        cancel,
+                        reply,
+                    })
+                    .await?;
+
+                reply_to.send(rx.await?)?;
+            }
+
+            HostMsg::ReadyToPropose {
+                height,
+                round,
+                value,
+                reply_to,
+            } => {
+                let (reply, rx) = oneshot::channel();
+
+                self.sender
+                    .send(AppMsg::ReadyToPropose {
+                        height,
+                        round,
+                        value,
                         reply,
                     })
                     .await?;
@@ -6,12 +6,13 @@ use tokio::sync::mpsc;
 use tokio::sync::oneshot;
 
 use malachitebft_engine::consensus::Msg as ConsensusActorMsg;
+use malachitebft_engine::host::CancellationToken;
 use malachitebft_engine::network::Msg as NetworkActorMsg;
 
 use crate::app::types::core::{CommitCertificate, Context, Round, ValueId};
 use crate::app::types::streaming::StreamMessage;
 use crate::app::types::sync::DecidedValue;
-use crate::app::types::{LocallyProposedValue, PeerId, ProposedValue};
+use crate::app::types::{LocallyProposedValue, PeerId, ProposeDecision, ProposedValue};
 
 pub type Reply<T> = oneshot::Sender<T>;
 
@@ -56,10 +57,36 @@ pub enum AppMsg<Ctx: Context> {
         round: Round,
         /// Maximum time allowed for the application to respond
         timeout: Duration,
+        /// Cancelled once consensus has left the propose step of that round,
+        /// in which case the value will not be proposed and the application
+        /// can stop building it
+        cancel: CancellationToken,
         /// Channel for sending back the value just built to consensus
         reply: Reply<LocallyProposedValue<Ctx>>,
     },
 
+    /// Notifies the application that the value it has just built in reply to [`AppMsg::GetValue`]
+    /// is about to be proposed, giving it a final say on that value.
+    ///
+    /// The application MUST respond with either:
+    /// - [`ProposeDecision::Accept`] to propose the value as built
+    /// - [`ProposeDecision::Replace`] to propose another value instead, after having
+    ///   streamed its proposal parts by sending [`NetworkMsg::PublishProposalPart`] messages
+    /// - [`ProposeDecision::Reject`] to not propose any value at this round
+    ///
+    /// If the application does not respond before the propose timeout elapses,
+    /// the value is proposed as built.
+    ReadyToPropose {
+        /// Height which consensus is at
+        height: Ctx::Height,
+        /// Round which consensus is at
+        round: Round,
+        /// The value which is about to be proposed
+        value: Ctx::Value,
+        /// Channel for sending back the decision of the application
+        reply: Reply<ProposeDecision<Ctx>>,
+    },
+
     /// Requests the application to re-stream a proposal that it has already seen.
     ///
     /// The application MUST re-publish again all the proposal parts pertaining
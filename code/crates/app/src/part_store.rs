@@ -3,7 +3,7 @@ use std::sync::Arc;
 
 use derive_where::derive_where;
 
-use malachitebft_core_types::{Context, Round, ValueId};
+use malachitebft_core_types::{Context, ProposalPart, Round, ValueId};
 
 // This is a temporary store implementation for proposal parts
 //
@@ -13,16 +13,20 @@ use malachitebft_core_types::{Context, Round, ValueId};
 
 type Key<Height> = (Height, Round);
 
+/// The sequence number of a part within the stream of parts of a proposal, starting at 0
+pub type Sequence = u64;
+
 #[derive_where(Clone, Debug, Default)]
 pub struct Entry<Ctx: Context> {
     pub value_id: Option<ValueId<Ctx>>,
-    pub parts: Vec<Arc<<Ctx as Context>::ProposalPart>>,
+    pub parts: BTreeMap<Sequence, Arc<<Ctx as Context>::ProposalPart>>,
 }
 type Store<Ctx> = BTreeMap<Key<<Ctx as Context>::Height>, Entry<Ctx>>;
 
 #[derive_where(Clone, Debug)]
 pub struct PartStore<Ctx: Context> {
     store: Store<Ctx>,
+    max_parts_per_value: usize,
 }
 
 impl<Ctx: Context> Default for PartStore<Ctx> {
@@ -33,24 +37,78 @@ impl<Ctx: Context> Default for PartStore<Ctx> {
 
 impl<Ctx: Context> PartStore<Ctx> {
     pub fn new() -> Self {
+        Self::with_max_parts_per_value(usize::MAX)
+    }
+
+    /// Create a store which buffers at most `max_parts_per_value` parts for any given height and round.
+    pub fn with_max_parts_per_value(max_parts_per_value: usize) -> Self {
         Self {
             store: Default::default(),
+            max_parts_per_value,
         }
     }
 
+    pub fn max_parts_per_value(&self) -> usize {
+        self.max_parts_per_value
+    }
+
     /// Return all the parts for the given height and round, sorted by sequence in ascending order
     pub fn all_parts(&self, height: Ctx::Height, round: Round) -> Vec<Arc<Ctx::ProposalPart>> {
         self.store
             .get(&(height, round))
-            .map(|entry| &entry.parts)
-            .cloned()
+            .map(|entry| entry.parts.values().cloned().collect())
             .unwrap_or_default()
     }
 
-    pub fn store(&mut self, height: Ctx::Height, round: Round, proposal_part: Ctx::ProposalPart) {
+    /// Whether all the parts for the given height and round have been received, ie.
+    /// - the parts have sequences `0..N` without any gap,
+    /// - the first part is the only one marked as first,
+    /// - the last part is the only one marked as last.
+    pub fn is_complete(&self, height: Ctx::Height, round: Round) -> bool {
+        let Some(entry) = self.store.get(&(height, round)) else {
+            return false;
+        };
+
+        let count = entry.parts.len();
+
+        // Sequences are unique and sorted, so there is no gap iff the last one is `N - 1`
+        let Some(last_sequence) = entry.parts.keys().next_back() else {
+            return false;
+        };
+
+        if *last_sequence != count as Sequence - 1 {
+            return false;
+        }
+
+        entry
+            .parts
+            .values()
+            .enumerate()
+            .all(|(i, part)| part.is_first() == (i == 0) && part.is_last() == (i == count - 1))
+    }
+
+    /// Store the given part with the given sequence number.
+    ///
+    /// The part is dropped and `false` is returned if a part with the same sequence
+    /// was already stored for that height and round, or if the maximum number of parts
+    /// for that height and round has already been reached.
+    pub fn store(
+        &mut self,
+        height: Ctx::Height,
+        round: Round,
+        sequence: Sequence,
+        proposal_part: Ctx::ProposalPart,
+    ) -> bool {
         let existing = self.store.entry((height, round)).or_default();
 
-        existing.parts.push(Arc::new(proposal_part));
+        if existing.parts.len() >= self.max_parts_per_value
+            || existing.parts.contains_key(&sequence)
+        {
+            return false;
+        }
+
+        existing.parts.insert(sequence, Arc::new(proposal_part));
+        true
     }
 
     pub fn store_value_id(&mut self, height: Ctx::Height, round: Round, value_id: ValueId<Ctx>) {
@@ -72,7 +130,7 @@ impl<Ctx: Context> PartStore<Ctx> {
         for entry in self.store.values() {
             if let Some(ref id) = entry.value_id {
                 if value_id == id {
-                    return entry.parts.clone();
+                    return entry.parts.values().cloned().collect();
                 }
             }
         }
@@ -9,6 +9,7 @@ use tracing::Span;
 use malachitebft_engine::consensus::{Consensus, ConsensusCodec, ConsensusParams, ConsensusRef};
 use malachitebft_engine::host::HostRef;
 use malachitebft_engine::network::{Network, NetworkRef};
+use malachitebft_engine::signer::Signer;
 use malachitebft_engine::sync::{Params as SyncParams, Sync, SyncCodec, SyncRef};
 use malachitebft_engine::util::events::TxEvent;
 use malachitebft_engine::wal::{Wal, WalCodec, WalRef};
@@ -68,12 +69,16 @@ where
         address,
         threshold_params: Default::default(),
         value_payload,
+        proposal_grace: cfg.consensus.timeouts.proposal_grace_period.is_some(),
     };
 
+    let signer = Signer::local(ctx.clone());
+
     Consensus::spawn(
         ctx,
         consensus_params,
         cfg.consensus.timeouts,
+        signer,
         network,
         host,
         wal,
@@ -123,6 +128,7 @@ where
     let params = SyncParams {
         status_update_interval: config.status_update_interval,
         request_timeout: config.request_timeout,
+        batch_size: config.batch_size,
     };
 
     let metrics = sync::Metrics::register(registry);
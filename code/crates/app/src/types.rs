@@ -3,7 +3,7 @@
 pub use malachitebft_core_consensus::{
     ConsensusMsg, ProposedValue, SignedConsensusMsg, ValuePayload,
 };
-pub use malachitebft_engine::host::LocallyProposedValue;
+pub use malachitebft_engine::host::{LocallyProposedValue, ProposeDecision};
 pub use malachitebft_peer::PeerId;
 
 pub mod core {
@@ -31,6 +31,7 @@ pub mod sync {
 pub mod codec {
     pub use malachitebft_codec::Codec;
     pub use malachitebft_engine::consensus::ConsensusCodec;
+    pub use malachitebft_engine::network::NetworkCodec;
     pub use malachitebft_engine::sync::SyncCodec;
     pub use malachitebft_engine::wal::WalCodec;
 }
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use malachitebft_app::part_store::PartStore;
+use malachitebft_core_types::Round;
+use malachitebft_test::{
+    Address, Height, ProposalData, ProposalFin, ProposalInit, ProposalPart, Signature, TestContext,
+};
+
+const HEIGHT: Height = Height::new(1);
+const ROUND: Round = Round::new(0);
+
+fn init() -> ProposalPart {
+    ProposalPart::Init(ProposalInit::new(HEIGHT, ROUND, Address::new([1; 20])))
+}
+
+fn data(factor: u64) -> ProposalPart {
+    ProposalPart::Data(ProposalData::new(factor))
+}
+
+fn fin() -> ProposalPart {
+    ProposalPart::Fin(ProposalFin::new(Signature::test()))
+}
+
+fn store_parts(parts: impl IntoIterator<Item = (u64, ProposalPart)>) -> PartStore<TestContext> {
+    let mut store = PartStore::new();
+
+    for (sequence, part) in parts {
+        assert!(store.store(HEIGHT, ROUND, sequence, part));
+    }
+
+    store
+}
+
+#[test]
+fn complete_in_order() {
+    let store = store_parts([(0, init()), (1, data(1)), (2, data(2)), (3, fin())]);
+
+    assert!(store.is_complete(HEIGHT, ROUND));
+    assert_eq!(
+        store.all_parts(HEIGHT, ROUND),
+        [init(), data(1), data(2), fin()].map(Arc::new)
+    );
+}
+
+#[test]
+fn complete_out_of_order() {
+    let mut store = store_parts([(3, fin()), (1, data(1))]);
+    assert!(!store.is_complete(HEIGHT, ROUND));
+
+    assert!(store.store(HEIGHT, ROUND, 0, init()));
+    assert!(!store.is_complete(HEIGHT, ROUND));
+
+    assert!(store.store(HEIGHT, ROUND, 2, data(2)));
+    assert!(store.is_complete(HEIGHT, ROUND));
+
+    // Parts are returned in order of sequence
+    assert_eq!(
+        store.all_parts(HEIGHT, ROUND),
+        [init(), data(1), data(2), fin()].map(Arc::new)
+    );
+}
+
+#[test]
+fn incomplete_with_gap() {
+    let store = store_parts([(0, init()), (1, data(1)), (3, fin())]);
+    assert!(!store.is_complete(HEIGHT, ROUND));
+}
+
+#[test]
+fn incomplete_without_init() {
+    let store = store_parts([(1, data(1)), (2, fin())]);
+    assert!(!store.is_complete(HEIGHT, ROUND));
+}
+
+#[test]
+fn incomplete_without_fin() {
+    let store = store_parts([(0, init()), (1, data(1)), (2, data(2))]);
+    assert!(!store.is_complete(HEIGHT, ROUND));
+}
+
+#[test]
+fn incomplete_when_fin_is_not_last() {
+    let store = store_parts([(0, init()), (1, fin()), (2, data(1))]);
+    assert!(!store.is_complete(HEIGHT, ROUND));
+}
+
+#[test]
+fn incomplete_with_multiple_fins() {
+    let store = store_parts([(0, init()), (1, fin()), (2, fin())]);
+    assert!(!store.is_complete(HEIGHT, ROUND));
+}
+
+#[test]
+fn incomplete_when_empty() {
+    let store = PartStore::<TestContext>::new();
+    assert!(!store.is_complete(HEIGHT, ROUND));
+}
+
+#[test]
+fn duplicate_sequence_is_dropped() {
+    let mut store = store_parts([(0, init()), (1, data(1))]);
+
+    assert!(!store.store(HEIGHT, ROUND, 1, data(2)));
+    assert_eq!(
+        store.all_parts(HEIGHT, ROUND),
+        [init(), data(1)].map(Arc::new)
+    );
+}
+
+#[test]
+fn regossiped_part_is_stored_once() {
+    let mut store = PartStore::<TestContext>::new();
+
+    assert!(store.store(HEIGHT, ROUND, 0, init()));
+    assert!(!store.store(HEIGHT, ROUND, 0, init()));
+    assert_eq!(store.all_parts(HEIGHT, ROUND).len(), 1);
+
+    // The same sequence at another round is a different part
+    assert!(store.store(HEIGHT, ROUND.increment(), 0, init()));
+    assert_eq!(store.all_parts(HEIGHT, ROUND).len(), 1);
+    assert_eq!(store.all_parts(HEIGHT, ROUND.increment()).len(), 1);
+}
+
+#[test]
+fn parts_beyond_the_limit_are_dropped() {
+    let mut store = PartStore::<TestContext>::with_max_parts_per_value(3);
+
+    assert!(store.store(HEIGHT, ROUND, 0, init()));
+    assert!(store.store(HEIGHT, ROUND, 1, data(1)));
+    assert!(store.store(HEIGHT, ROUND, 2, data(2)));
+
+    // The proposal consists of 4 parts, so the last one never fits and the value is never assembled
+    assert!(!store.store(HEIGHT, ROUND, 3, fin()));
+    assert!(!store.is_complete(HEIGHT, ROUND));
+    assert_eq!(
+        store.all_parts(HEIGHT, ROUND),
+        [init(), data(1), data(2)].map(Arc::new)
+    );
+
+    // The limit applies to each height and round separately
+    assert!(store.store(HEIGHT, ROUND.increment(), 0, init()));
+    assert!(store.store(HEIGHT, ROUND.increment(), 1, data(1)));
+    assert!(store.store(HEIGHT, ROUND.increment(), 2, fin()));
+    assert!(store.is_complete(HEIGHT, ROUND.increment()));
+}
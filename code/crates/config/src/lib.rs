@@ -6,7 +6,7 @@ use std::time::Duration;
 
 use bytesize::ByteSize;
 use config as config_rs;
-use malachitebft_core_types::TimeoutKind;
+use malachitebft_core_types::{Round, TimeoutKind};
 use multiaddr::Multiaddr;
 use serde::{Deserialize, Serialize};
 
@@ -75,6 +75,10 @@ pub struct P2pConfig {
 
     /// The maximum size of messages to send over RPC
     pub rpc_max_size: ByteSize,
+
+    /// The encoding of the messages sent over the network
+    #[serde(default)]
+    pub encoding: MessageEncoding,
 }
 
 impl Default for P2pConfig {
@@ -87,6 +91,7 @@ impl Default for P2pConfig {
             protocol: Default::default(),
             rpc_max_size: ByteSize::mib(10),
             pubsub_max_size: ByteSize::mib(4),
+            encoding: Default::default(),
         }
     }
 }
@@ -117,6 +122,10 @@ pub struct DiscoveryConfig {
     #[serde(default)]
     #[serde(with = "humantime_serde")]
     pub ephemeral_connection_timeout: Duration,
+
+    /// Peers to always stay connected to, which do not count towards the number of outbound peers
+    #[serde(default)]
+    pub persistent_peers: Vec<Multiaddr>,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -212,6 +221,33 @@ impl FromStr for TransportProtocol {
     }
 }
 
+/// The encoding of the messages sent over the network.
+/// All the nodes of a network must use the same encoding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageEncoding {
+    /// Protocol Buffers
+    #[default]
+    Protobuf,
+
+    /// Compact fixed-layout binary encoding
+    Compact,
+}
+
+impl FromStr for MessageEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "protobuf" => Ok(Self::Protobuf),
+            "compact" => Ok(Self::Compact),
+            e => Err(format!(
+                "unknown message encoding: {e}, available: protobuf, compact"
+            )),
+        }
+    }
+}
+
 /// The type of pub-sub protocol.
 /// If multiple protocols are configured in the configuration file, the first one from this list
 /// will be used.
@@ -350,6 +386,18 @@ pub struct MempoolConfig {
 
     /// Maximum number of transactions to gossip at once in a batch
     pub gossip_batch_size: usize,
+
+    /// Maximum total size of the transactions in the mempool,
+    /// the oldest transactions are evicted first when exceeded.
+    /// No limit if zero.
+    #[serde(default)]
+    pub max_mempool_bytes: ByteSize,
+
+    /// Time after which a transaction pending in the mempool expires.
+    /// Transactions never expire if zero.
+    #[serde(default)]
+    #[serde(with = "humantime_serde")]
+    pub tx_ttl: Duration,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -364,6 +412,16 @@ pub struct SyncConfig {
     /// Timeout duration for sync requests
     #[serde(with = "humantime_serde")]
     pub request_timeout: Duration,
+
+    /// Maximum number of heights for which values are requested at once
+    #[serde(default = "SyncConfig::default_batch_size")]
+    pub batch_size: usize,
+}
+
+impl SyncConfig {
+    fn default_batch_size() -> usize {
+        5
+    }
 }
 
 impl Default for SyncConfig {
@@ -372,6 +430,7 @@ impl Default for SyncConfig {
             enabled: true,
             status_update_interval: Duration::from_secs(10),
             request_timeout: Duration::from_secs(10),
+            batch_size: Self::default_batch_size(),
         }
     }
 }
@@ -440,6 +499,12 @@ pub struct TimeoutConfig {
     /// the vote synchronization protocol.
     #[serde(with = "humantime_serde")]
     pub timeout_step: Duration,
+
+    /// How long we keep waiting for a late proposal after timeout_prevote elapsed,
+    /// when we already have +2/3 prevotes for its value, before precommitting nil.
+    /// Disabled when not set.
+    #[serde(default, with = "humantime_serde")]
+    pub proposal_grace_period: Option<Duration>,
 }
 
 impl TimeoutConfig {
@@ -451,6 +516,7 @@ impl TimeoutConfig {
             TimeoutKind::Commit => self.timeout_commit,
             TimeoutKind::PrevoteTimeLimit => self.timeout_step,
             TimeoutKind::PrecommitTimeLimit => self.timeout_step,
+            TimeoutKind::ProposalGrace => self.proposal_grace_period.unwrap_or_default(),
         }
     }
 
@@ -462,6 +528,21 @@ impl TimeoutConfig {
             TimeoutKind::Commit => None,
             TimeoutKind::PrevoteTimeLimit => None,
             TimeoutKind::PrecommitTimeLimit => None,
+            TimeoutKind::ProposalGrace => None,
+        }
+    }
+
+    /// The duration of the given timeout in the given round, ie. `base + delta * round`.
+    ///
+    /// Timeouts which do not increase with the round, eg. `Commit`, always use their base duration,
+    /// as do all timeouts in round 0 and in the nil round.
+    pub fn duration_for_round(&self, step: TimeoutKind, round: Round) -> Duration {
+        let base = self.timeout_duration(step);
+        let rounds = round.as_u32().unwrap_or(0);
+
+        match self.delta_duration(step) {
+            Some(delta) => base.saturating_add(delta.saturating_mul(rounds)),
+            None => base,
         }
     }
 }
@@ -477,6 +558,7 @@ impl Default for TimeoutConfig {
             timeout_precommit_delta: Duration::from_millis(500),
             timeout_commit: Duration::from_secs(0),
             timeout_step: Duration::from_secs(30),
+            proposal_grace_period: None,
         }
     }
 }
@@ -539,6 +621,21 @@ pub struct TestConfig {
     pub max_retain_blocks: usize,
     #[serde(default)]
     pub vote_extensions: VoteExtensionsConfig,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default = "TestConfig::default_max_parts_per_value")]
+    pub max_parts_per_value: usize,
+    #[serde(default)]
+    pub report_build_progress: bool,
+    #[serde(default, with = "humantime_serde")]
+    pub start_height_delay: Duration,
+    /// Whether to propose an empty block when no transactions are available
+    #[serde(default = "TestConfig::default_create_empty_blocks")]
+    pub create_empty_blocks: bool,
+    /// Whether to keep building a value once consensus has moved past its round,
+    /// so that consensus can propose it in the next round of the height where we are the proposer
+    #[serde(default)]
+    pub reuse_late_values: bool,
 }
 
 impl Default for TestConfig {
@@ -550,10 +647,26 @@ impl Default for TestConfig {
             exec_time_per_tx: Duration::from_millis(1),
             max_retain_blocks: 1000,
             vote_extensions: VoteExtensionsConfig::default(),
+            seed: None,
+            max_parts_per_value: Self::default_max_parts_per_value(),
+            report_build_progress: false,
+            start_height_delay: Duration::ZERO,
+            create_empty_blocks: Self::default_create_empty_blocks(),
+            reuse_late_values: false,
         }
     }
 }
 
+impl TestConfig {
+    fn default_max_parts_per_value() -> usize {
+        10_000
+    }
+
+    fn default_create_empty_blocks() -> bool {
+        true
+    }
+}
+
 #[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct LoggingConfig {
     pub log_level: LogLevel,
@@ -636,6 +749,7 @@ mod tests {
         let file = include_str!("../../../examples/channel/config.toml");
         let config = toml::from_str::<Config>(file).unwrap();
         assert_eq!(config.consensus.timeouts, TimeoutConfig::default());
+        assert_eq!(config.consensus.p2p.encoding, MessageEncoding::Protobuf);
         assert_eq!(config.test, TestConfig::default());
 
         let tmp_file = std::env::temp_dir().join("informalsystems-malachitebft-config.toml");
@@ -668,6 +782,56 @@ mod tests {
         assert_eq!(t.timeout_duration(TimeoutKind::Commit), t.timeout_commit);
     }
 
+    #[test]
+    fn timeout_durations_per_round() {
+        let t = TimeoutConfig {
+            timeout_propose: Duration::from_secs(3),
+            timeout_propose_delta: Duration::from_millis(500),
+            timeout_prevote: Duration::from_secs(1),
+            timeout_prevote_delta: Duration::from_millis(250),
+            timeout_precommit: Duration::from_secs(2),
+            timeout_precommit_delta: Duration::from_millis(100),
+            timeout_commit: Duration::from_secs(1),
+            timeout_step: Duration::from_secs(30),
+            proposal_grace_period: Some(Duration::from_millis(200)),
+        };
+
+        let at = |kind, round| t.duration_for_round(kind, Round::new(round));
+
+        // Round 0 uses the base durations
+        assert_eq!(at(TimeoutKind::Propose, 0), Duration::from_secs(3));
+        assert_eq!(at(TimeoutKind::Prevote, 0), Duration::from_secs(1));
+        assert_eq!(at(TimeoutKind::Precommit, 0), Duration::from_secs(2));
+
+        assert_eq!(at(TimeoutKind::Propose, 1), Duration::from_millis(3500));
+        assert_eq!(at(TimeoutKind::Prevote, 1), Duration::from_millis(1250));
+        assert_eq!(at(TimeoutKind::Precommit, 1), Duration::from_millis(2100));
+
+        assert_eq!(at(TimeoutKind::Propose, 5), Duration::from_millis(5500));
+        assert_eq!(at(TimeoutKind::Prevote, 5), Duration::from_millis(2250));
+        assert_eq!(at(TimeoutKind::Precommit, 5), Duration::from_millis(2500));
+
+        // Timeouts without a delta do not grow with the round
+        assert_eq!(at(TimeoutKind::Commit, 5), Duration::from_secs(1));
+        assert_eq!(
+            at(TimeoutKind::PrevoteTimeLimit, 5),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            at(TimeoutKind::PrecommitTimeLimit, 5),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            at(TimeoutKind::ProposalGrace, 5),
+            Duration::from_millis(200)
+        );
+
+        assert_eq!(
+            t.duration_for_round(TimeoutKind::Propose, Round::Nil),
+            Duration::from_secs(3)
+        );
+    }
+
     #[test]
     fn runtime_multi_threaded() {
         assert_eq!(
@@ -151,13 +151,28 @@ where
     /// Resume with: [`resume::Continue`]`
     PersistTimeout(Timeout, resume::Continue),
 
+    /// Durably persist the state of consensus before voting at the given height and round,
+    /// eg. by syncing the Write-Ahead Log to disk, so that we can never cast
+    /// a conflicting vote after a crash.
+    ///
+    /// This effect is always performed before the vote is signed and broadcast.
+    /// If the state could not be persisted, resume with `false` and the vote
+    /// will be neither signed nor broadcast.
+    ///
+    /// Resume with: [`resume::Persisted`]
+    PersistBeforeVote(Ctx::Height, Round, VoteType, resume::Persisted),
+
     /// Sign a vote with this node's private key
     ///
+    /// If the vote could not be signed, resume with `None` and the vote will not be emitted.
+    ///
     /// Resume with: [`resume::SignedVote`]
     SignVote(Ctx::Vote, resume::SignedVote),
 
     /// Sign a proposal with this node's private key
     ///
+    /// If the proposal could not be signed, resume with `None` and the proposal will not be emitted.
+    ///
     /// Resume with: [`resume::SignedProposal`]
     SignProposal(Ctx::Proposal, resume::SignedProposal),
 
@@ -203,11 +218,14 @@ where
     /// Resume execution with the validity of the signature
     SignatureValidity(bool),
 
-    /// Resume execution with the signed vote
-    SignedVote(SignedMessage<Ctx, Ctx::Vote>),
+    /// Resume execution with whether the state was durably persisted
+    Persisted(bool),
+
+    /// Resume execution with the signed vote, or `None` if the vote could not be signed
+    SignedVote(Option<SignedMessage<Ctx, Ctx::Vote>>),
 
-    /// Resume execution with the signed proposal
-    SignedProposal(SignedMessage<Ctx, Ctx::Proposal>),
+    /// Resume execution with the signed proposal, or `None` if the proposal could not be signed
+    SignedProposal(Option<SignedMessage<Ctx, Ctx::Proposal>>),
 
     /// Resume execution with the result of the verification of the [`CommitCertificate`]
     CertificateValidity(Result<(), CertificateError<Ctx>>),
@@ -249,11 +267,22 @@ pub mod resume {
         }
     }
 
+    #[derive(Debug, Default)]
+    pub struct Persisted;
+
+    impl<Ctx: Context> Resumable<Ctx> for Persisted {
+        type Value = bool;
+
+        fn resume_with(self, value: Self::Value) -> Resume<Ctx> {
+            Resume::Persisted(value)
+        }
+    }
+
     #[derive(Debug, Default)]
     pub struct SignedVote;
 
     impl<Ctx: Context> Resumable<Ctx> for SignedVote {
-        type Value = SignedMessage<Ctx, Ctx::Vote>;
+        type Value = Option<SignedMessage<Ctx, Ctx::Vote>>;
 
         fn resume_with(self, value: Self::Value) -> Resume<Ctx> {
             Resume::SignedVote(value)
@@ -264,7 +293,7 @@ pub mod resume {
     pub struct SignedProposal;
 
     impl<Ctx: Context> Resumable<Ctx> for SignedProposal {
-        type Value = SignedMessage<Ctx, Ctx::Proposal>;
+        type Value = Option<SignedMessage<Ctx, Ctx::Proposal>>;
 
         fn resume_with(self, a: Self::Value) -> Resume<Ctx> {
             Resume::SignedProposal(a)
@@ -371,6 +371,35 @@ impl<Ctx: Context> FullProposalKeeper<Ctx> {
         }
     }
 
+    /// Set the validity of the given value, wherever it was stored with [`Validity::Unknown`],
+    /// ie. at its round and at the rounds of the proposals whose pol_round is that round.
+    pub fn update_validity(&mut self, new_value: &ProposedValue<Ctx>) {
+        let first_key = (new_value.height, new_value.round);
+
+        let entries = self
+            .keeper
+            .range_mut(first_key..)
+            .take_while(|((height, _), _)| *height == new_value.height);
+
+        for (_, proposals) in entries {
+            for entry in proposals {
+                let validity = match entry {
+                    Entry::Full(p) if p.proposal.value().id() == new_value.value.id() => {
+                        &mut p.validity
+                    }
+                    Entry::ValueOnly(value, validity, _) if value.id() == new_value.value.id() => {
+                        validity
+                    }
+                    _ => continue,
+                };
+
+                if validity.is_unknown() {
+                    *validity = new_value.validity;
+                }
+            }
+        }
+    }
+
     pub fn remove_full_proposals(&mut self, last_height: Ctx::Height) {
         // Keep last two decided heights
         debug!(%last_height, "Removing proposals, keep the last two");
@@ -16,7 +16,7 @@ mod vote_set;
 
 use proposal::on_proposal;
 use propose::on_propose;
-use proposed_value::on_proposed_value;
+use proposed_value::{on_proposed_value, on_value_validated};
 use start_height::reset_and_start_height;
 use sync::on_commit_certificate;
 use timeout::on_timeout_elapsed;
@@ -57,6 +57,7 @@ where
         Input::ProposedValue(value, origin) => {
             on_proposed_value(co, state, metrics, value, origin).await
         }
+        Input::ValueValidated(value) => on_value_validated(co, state, metrics, value).await,
         Input::CommitCertificate(certificate) => {
             on_commit_certificate(co, state, metrics, certificate).await
         }
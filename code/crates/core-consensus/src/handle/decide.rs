@@ -6,13 +6,13 @@ pub async fn decide<Ctx>(
     metrics: &Metrics,
     consensus_round: Round,
     proposal: SignedProposal<Ctx>,
+    certificate: CommitCertificate<Ctx>,
 ) -> Result<(), Error<Ctx>>
 where
     Ctx: Context,
 {
     let height = proposal.height();
     let proposal_round = proposal.round();
-    let value = proposal.value();
 
     // We only decide proposals for the current height
     assert_eq!(height, state.driver.height());
@@ -46,23 +46,7 @@ where
         }
     }
 
-    // Look for an existing certificate
-    let certificate = state
-        .driver
-        .get_certificate(proposal_round, value.id())
-        .cloned()
-        .unwrap_or_else(|| {
-            // Restore the commits. Note that they will be removed from `state`
-            let commits = state.restore_precommits(height, proposal_round, value);
-            // TODO: should we verify we have 2/3rd commits?
-            CommitCertificate::new(height, proposal_round, value.id(), commits)
-        });
-
     perform!(co, Effect::Decide(certificate, Default::default()));
 
-    // Reinitialize to remove any previous round or equivocating precommits.
-    // TODO: Revise when evidence module is added.
-    state.signed_precommits.clear();
-
     Ok(())
 }
@@ -7,6 +7,7 @@ use crate::types::SignedConsensusMsg;
 use crate::util::pretty::PrettyVal;
 use malachitebft_core_driver::Input as DriverInput;
 use malachitebft_core_driver::Output as DriverOutput;
+use malachitebft_core_driver::PrevoteReason;
 
 #[async_recursion]
 pub async fn apply_driver_input<Ctx>(
@@ -37,7 +38,8 @@ where
             );
         }
 
-        DriverInput::Proposal(proposal, _validity) => {
+        DriverInput::Proposal(proposal, validity)
+        | DriverInput::ProposalValidated(proposal, validity) => {
             if proposal.height() != state.driver.height() {
                 warn!(
                     "Ignoring proposal for height {}, current height: {}",
@@ -48,10 +50,14 @@ where
                 return Ok(());
             }
 
-            perform!(
-                co,
-                Effect::CancelTimeout(Timeout::propose(proposal.round()), Default::default())
-            );
+            // Keep the propose timeout running until the validity of the proposal is known,
+            // so that we prevote nil if it does not become known in time.
+            if !validity.is_unknown() {
+                perform!(
+                    co,
+                    Effect::CancelTimeout(Timeout::propose(proposal.round()), Default::default())
+                );
+            }
         }
 
         DriverInput::Vote(vote) => {
@@ -125,6 +131,16 @@ where
                     Default::default()
                 )
             );
+            // If we were waiting for a late proposal, it either arrived in time or we gave up on it
+            if state.params.proposal_grace {
+                perform!(
+                    co,
+                    Effect::CancelTimeout(
+                        Timeout::proposal_grace(state.driver.round()),
+                        Default::default()
+                    )
+                );
+            }
             perform!(
                 co,
                 Effect::ScheduleTimeout(
@@ -194,7 +210,10 @@ where
                 "Proposing value"
             );
 
-            let signed_proposal = sign_proposal(co, proposal).await?;
+            let Some(signed_proposal) = sign_proposal(co, proposal).await? else {
+                warn!("Failed to sign our proposal, not emitting it");
+                return Ok(());
+            };
 
             if signed_proposal.pol_round().is_defined() {
                 perform!(
@@ -235,8 +254,27 @@ where
                 "Voting",
             );
 
+            if vote.vote_type() == VoteType::Prevote && vote.value().is_nil() {
+                log_nil_prevote_reason(state, vote.round());
+            }
+
+            // Persist our state before signing the vote, so that we can never sign
+            // a conflicting vote after a crash. If that fails, we must not vote.
+            let persisted = perform!(co,
+                Effect::PersistBeforeVote(vote.height(), vote.round(), vote.vote_type(), Default::default()),
+                Resume::Persisted(persisted) => persisted
+            );
+
+            if !persisted {
+                warn!("Failed to persist our state, not emitting our vote");
+                return Ok(());
+            }
+
             let extended_vote = extend_vote(vote, state);
-            let signed_vote = sign_vote(co, extended_vote).await?;
+            let Some(signed_vote) = sign_vote(co, extended_vote).await? else {
+                warn!("Failed to sign our vote, not emitting it");
+                return Ok(());
+            };
 
             on_vote(co, state, metrics, signed_vote.clone()).await?;
 
@@ -248,7 +286,7 @@ where
             Ok(())
         }
 
-        DriverOutput::Decide(consensus_round, proposal) => {
+        DriverOutput::Decide(consensus_round, proposal, certificate) => {
             info!(
                 round = %consensus_round,
                 height = %proposal.height(),
@@ -257,7 +295,12 @@ where
             );
 
             // Store value decided on for retrieval when timeout commit elapses
-            state.store_decision(state.driver.height(), consensus_round, proposal.clone());
+            state.store_decision(
+                state.driver.height(),
+                consensus_round,
+                proposal.clone(),
+                certificate,
+            );
 
             perform!(
                 co,
@@ -311,3 +354,22 @@ fn extend_vote<Ctx: Context>(vote: Ctx::Vote, state: &mut State<Ctx>) -> Ctx::Vo
         vote
     }
 }
+
+/// Explain why we withheld our support for the proposal of the given round, if we know why.
+fn log_nil_prevote_reason<Ctx>(state: &State<Ctx>, round: Round)
+where
+    Ctx: Context,
+{
+    match state.driver.nil_prevote_reason(round) {
+        Some(PrevoteReason::LockedOnDifferentValue(locked_round)) => {
+            warn!(%round, %locked_round, "Prevoting nil, as we are locked on a different value");
+        }
+        Some(PrevoteReason::InvalidProposal) => {
+            info!(%round, "Prevoting nil, as the proposal is invalid");
+        }
+        Some(PrevoteReason::TimeoutPropose) => {
+            info!(%round, "Prevoting nil, as we did not receive a proposal in time");
+        }
+        None => (),
+    }
+}
@@ -33,6 +33,20 @@ where
         return Ok(());
     }
 
+    // Drop proposals we have already seen at the current height, before verifying their signature.
+    if state.is_duplicate_proposal(&signed_proposal) {
+        debug!(
+            consensus.height = %consensus_height,
+            proposal.height = %proposal_height,
+            proposer = %proposer_address,
+            "Received duplicate proposal, dropping"
+        );
+
+        metrics.duplicate_messages.inc();
+
+        return Ok(());
+    }
+
     if !verify_signed_proposal(co, state, &signed_proposal).await? {
         return Ok(());
     }
@@ -64,6 +78,9 @@ where
 
     debug_assert_eq!(proposal_height, consensus_height);
 
+    // Only record proposals with a valid signature, see `on_vote`
+    state.store_seen_proposal(&signed_proposal);
+
     // Store the proposal in the full proposal keeper
     state.store_proposal(signed_proposal.clone());
 
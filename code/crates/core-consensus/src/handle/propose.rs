@@ -38,6 +38,15 @@ where
         return Ok(());
     }
 
+    if !state.driver.step_is_propose() {
+        warn!(
+            "Ignoring propose value for round {round}, current step: {:?}",
+            state.driver.step()
+        );
+
+        return Ok(());
+    }
+
     metrics.consensus_start();
 
     state.store_value(&ProposedValue {
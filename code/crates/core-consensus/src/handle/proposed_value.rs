@@ -3,8 +3,6 @@ use crate::prelude::*;
 use crate::handle::driver::apply_driver_input;
 use crate::types::ProposedValue;
 
-use super::signature::sign_proposal;
-
 #[tracing::instrument(
     skip_all,
     fields(
@@ -55,8 +53,10 @@ where
         );
 
         // TODO: Keep unsigned proposals in keeper.
-        // For now we keep all happy by signing all "implicit" proposals with this node's key
-        let signed_proposal = sign_proposal(co, proposal).await?;
+        // For now we keep all happy by signing all "implicit" proposals with this node's key.
+        // These are never sent to our peers, so they are signed with the signing provider
+        // of the context rather than with the signer of our own votes and proposals.
+        let signed_proposal = state.ctx.signing_provider().sign_proposal(proposal);
 
         state.store_proposal(signed_proposal);
     }
@@ -80,3 +80,55 @@ where
 
     Ok(())
 }
+
+#[tracing::instrument(
+    skip_all,
+    fields(
+        height = %proposed_value.height,
+        round = %proposed_value.round,
+        validity = ?proposed_value.validity,
+        id = %proposed_value.value.id()
+    )
+)]
+pub async fn on_value_validated<Ctx>(
+    co: &Co<Ctx>,
+    state: &mut State<Ctx>,
+    metrics: &Metrics,
+    proposed_value: ProposedValue<Ctx>,
+) -> Result<(), Error<Ctx>>
+where
+    Ctx: Context,
+{
+    if proposed_value.validity.is_unknown() {
+        warn!("Received a validated value whose validity is still unknown, ignoring");
+        return Ok(());
+    }
+
+    if state.driver.height() > proposed_value.height {
+        debug!("Received validity of a value for lower height, dropping");
+        return Ok(());
+    }
+
+    if state.driver.height() < proposed_value.height {
+        debug!("Received validity of a value for higher height, queuing for later");
+
+        state.buffer_input(proposed_value.height, Input::ValueValidated(proposed_value));
+
+        return Ok(());
+    }
+
+    state.update_value_validity(&proposed_value);
+
+    let proposals = state.full_proposals_for_value(&proposed_value);
+    for signed_proposal in proposals {
+        apply_driver_input(
+            co,
+            state,
+            metrics,
+            DriverInput::ProposalValidated(signed_proposal, proposed_value.validity),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
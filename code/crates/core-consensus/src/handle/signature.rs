@@ -18,7 +18,11 @@ where
     Ok(valid)
 }
 
-pub async fn sign_vote<Ctx>(co: &Co<Ctx>, vote: Ctx::Vote) -> Result<SignedVote<Ctx>, Error<Ctx>>
+/// Sign the given vote, returning `None` if the signer failed to sign it.
+pub async fn sign_vote<Ctx>(
+    co: &Co<Ctx>,
+    vote: Ctx::Vote,
+) -> Result<Option<SignedVote<Ctx>>, Error<Ctx>>
 where
     Ctx: Context,
 {
@@ -30,10 +34,11 @@ where
     Ok(signed_vote)
 }
 
+/// Sign the given proposal, returning `None` if the signer failed to sign it.
 pub async fn sign_proposal<Ctx>(
     co: &Co<Ctx>,
     proposal: Ctx::Proposal,
-) -> Result<SignedProposal<Ctx>, Error<Ctx>>
+) -> Result<Option<SignedProposal<Ctx>>, Error<Ctx>>
 where
     Ctx: Context,
 {
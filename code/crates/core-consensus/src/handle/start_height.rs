@@ -18,7 +18,12 @@ where
 
     metrics.step_end(state.driver.step());
 
-    state.driver.move_to_height(height, validator_set);
+    state
+        .driver
+        .move_to_height(height, validator_set)
+        .map_err(Error::DriverProcess)?;
+
+    state.clear_seen_messages();
 
     debug_assert_eq!(state.driver.height(), height);
     debug_assert_eq!(state.driver.round(), Round::Nil);
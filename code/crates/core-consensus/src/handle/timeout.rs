@@ -26,6 +26,18 @@ where
         return Ok(());
     }
 
+    if state.driver.is_stale_timeout(&timeout) {
+        debug!(
+            %height,
+            %round,
+            step = ?timeout.kind,
+            current_step = ?state.driver.step(),
+            "Ignoring timeout for a step we already left",
+        );
+
+        return Ok(());
+    }
+
     info!(
         step = ?timeout.kind,
         %timeout.round,
@@ -44,12 +56,12 @@ where
             on_step_limit_timeout(co, state, metrics, timeout.round).await?;
         }
         TimeoutKind::Commit => {
-            let proposal = state
+            let (proposal, certificate) = state
                 .decision
                 .remove(&(height, round))
                 .ok_or_else(|| Error::DecidedValueNotFound(height, round))?;
 
-            decide(co, state, metrics, round, proposal).await?;
+            decide(co, state, metrics, round, proposal, certificate).await?;
         }
         _ => {}
     }
@@ -32,6 +32,20 @@ where
         return Ok(());
     }
 
+    // Drop votes we have already seen at the current height, before verifying their signature.
+    if state.is_duplicate_vote(&signed_vote) {
+        debug!(
+            consensus.height = %consensus_height,
+            vote.height = %vote_height,
+            validator = %validator_address,
+            "Received duplicate vote, dropping"
+        );
+
+        metrics.duplicate_messages.inc();
+
+        return Ok(());
+    }
+
     if !verify_signed_vote(co, state, &signed_vote).await? {
         return Ok(());
     }
@@ -75,6 +89,11 @@ where
 
     debug_assert_eq!(consensus_height, vote_height);
 
+    // Only record votes with a valid signature, so that a forged copy of a vote
+    // cannot get the genuine one dropped as a duplicate.
+    // Votes buffered above are recorded when they are replayed.
+    state.store_seen_vote(signed_vote.clone());
+
     // Append the vote to the Write-ahead Log
     perform!(
         co,
@@ -84,11 +103,6 @@ where
         )
     );
 
-    // Store the non-nil Precommits.
-    if signed_vote.vote_type() == VoteType::Precommit && signed_vote.value().is_val() {
-        state.store_signed_precommit(signed_vote.clone());
-    }
-
     apply_driver_input(co, state, metrics, DriverInput::Vote(signed_vote)).await?;
 
     Ok(())
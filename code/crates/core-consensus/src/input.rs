@@ -33,6 +33,10 @@ where
     /// The origin denotes whether the value was received via consensus or Sync.
     ProposedValue(ProposedValue<Ctx>, ValueOrigin),
 
+    /// The application has determined the validity of a value it previously
+    /// reported with [`Validity::Unknown`](malachitebft_core_types::Validity::Unknown).
+    ValueValidated(ProposedValue<Ctx>),
+
     /// Received a commit certificate from Sync
     CommitCertificate(CommitCertificate<Ctx>),
 
@@ -8,6 +8,7 @@ pub use state::State;
 
 mod error;
 pub use error::Error;
+pub use malachitebft_core_driver::Error as DriverError;
 
 mod params;
 pub use params::{Params, ThresholdParams};
@@ -22,4 +22,10 @@ pub struct Params<Ctx: Context> {
 
     /// The messages required to deliver proposals
     pub value_payload: ValuePayload,
+
+    /// Whether to wait for a late proposal before precommitting nil
+    /// when we already have a polka for its value, see [`Driver::set_proposal_grace`].
+    ///
+    /// [`Driver::set_proposal_grace`]: malachitebft_core_driver::Driver::set_proposal_grace
+    pub proposal_grace: bool,
 }
@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::BTreeMap;
 use tracing::{debug, warn};
 
 use malachitebft_core_driver::Driver;
@@ -6,8 +6,25 @@ use malachitebft_core_types::*;
 
 use crate::input::Input;
 use crate::util::max_queue::MaxQueue;
+use crate::util::seen_cache::SeenCache;
 use crate::{FullProposal, FullProposalKeeper, Params, ProposedValue};
 
+/// A decided proposal, along with the commit certificate for its value
+pub type Decision<Ctx> = (SignedProposal<Ctx>, CommitCertificate<Ctx>);
+
+/// How many votes and proposals are remembered per height to detect duplicates
+const SEEN_CACHE_CAPACITY: usize = 10_000;
+
+/// The fields of a signed proposal at the current height which identify it as a duplicate:
+/// its round, POL round, value id, proposer and signature.
+pub type SeenProposal<Ctx> = (
+    Round,
+    Round,
+    ValueId<Ctx>,
+    <Ctx as Context>::Address,
+    Signature<Ctx>,
+);
+
 /// The state maintained by consensus for processing a [`Input`][crate::Input].
 pub struct State<Ctx>
 where
@@ -28,11 +45,14 @@ where
     /// The proposals to decide on.
     pub full_proposal_keeper: FullProposalKeeper<Ctx>,
 
-    /// Store Precommit votes to be sent along the decision to the host
-    pub signed_precommits: BTreeMap<(Ctx::Height, Round), BTreeSet<SignedVote<Ctx>>>,
+    /// Decision per height, along with its commit certificate
+    pub decision: BTreeMap<(Ctx::Height, Round), Decision<Ctx>>,
 
-    /// Decision per height
-    pub decision: BTreeMap<(Ctx::Height, Round), SignedProposal<Ctx>>,
+    /// Votes seen at the current height, used to drop duplicates before verifying their signature
+    pub seen_votes: SeenCache<SignedVote<Ctx>>,
+
+    /// Proposals seen at the current height, used to drop duplicates before verifying their signature
+    pub seen_proposals: SeenCache<SeenProposal<Ctx>>,
 }
 
 impl<Ctx> State<Ctx>
@@ -40,7 +60,7 @@ where
     Ctx: Context,
 {
     pub fn new(ctx: Ctx, params: Params<Ctx>) -> Self {
-        let driver = Driver::new(
+        let mut driver = Driver::new(
             ctx.clone(),
             params.initial_height,
             params.initial_validator_set.clone(),
@@ -48,14 +68,17 @@ where
             params.threshold_params,
         );
 
+        driver.set_proposal_grace(params.proposal_grace);
+
         Self {
             ctx,
             driver,
             params,
             input_queue: Default::default(),
             full_proposal_keeper: Default::default(),
-            signed_precommits: Default::default(),
             decision: Default::default(),
+            seen_votes: SeenCache::new(SEEN_CACHE_CAPACITY),
+            seen_proposals: SeenCache::new(SEEN_CACHE_CAPACITY),
         }
     }
 
@@ -76,24 +99,16 @@ where
     }
 
     pub fn get_proposer(&self, height: Ctx::Height, round: Round) -> &Ctx::Address {
-        self.ctx
-            .select_proposer(self.validator_set(), height, round)
-            .address()
+        self.driver.select_proposer(height, round).address()
     }
 
-    pub fn store_signed_precommit(&mut self, precommit: SignedVote<Ctx>) {
-        assert_eq!(precommit.vote_type(), VoteType::Precommit);
-
-        let height = precommit.height();
-        let round = precommit.round();
-
-        self.signed_precommits
-            .entry((height, round))
-            .or_default()
-            .insert(precommit);
-    }
-
-    pub fn store_decision(&mut self, height: Ctx::Height, round: Round, proposal: Ctx::Proposal) {
+    pub fn store_decision(
+        &mut self,
+        height: Ctx::Height,
+        round: Round,
+        proposal: Ctx::Proposal,
+        certificate: CommitCertificate<Ctx>,
+    ) {
         if let Some(full_proposal) = self.full_proposal_keeper.full_proposal_at_round_and_value(
             &height,
             proposal.round(),
@@ -101,31 +116,11 @@ where
         ) {
             self.decision.insert(
                 (self.driver.height(), round),
-                full_proposal.proposal.clone(),
+                (full_proposal.proposal.clone(), certificate),
             );
         }
     }
 
-    pub fn restore_precommits(
-        &mut self,
-        height: Ctx::Height,
-        round: Round,
-        value: &Ctx::Value,
-    ) -> Vec<SignedVote<Ctx>> {
-        // Get the commits for the height and round.
-        let commits_for_height_and_round = self
-            .signed_precommits
-            .remove(&(height, round))
-            .unwrap_or_default();
-
-        // Keep the commits for the specified value.
-        // For now, we ignore equivocating votes if present.
-        commits_for_height_and_round
-            .into_iter()
-            .filter(|c| c.value() == &NilOrVal::Val(value.id()))
-            .collect()
-    }
-
     pub fn restore_votes(&mut self, height: Ctx::Height, round: Round) -> Vec<SignedVote<Ctx>> {
         // TODO optimization - get votes for all rounds higher than or equal to `round`
         if height != self.driver.height() {
@@ -169,6 +164,14 @@ where
         self.full_proposal_keeper.store_value(new_value);
     }
 
+    /// Set the validity of a value which was stored with [`Validity::Unknown`].
+    pub fn update_value_validity(&mut self, value: &ProposedValue<Ctx>) {
+        // Values for higher height should have been cached for future processing
+        assert_eq!(value.height, self.driver.height());
+
+        self.full_proposal_keeper.update_validity(value);
+    }
+
     pub fn remove_full_proposals(&mut self, height: Ctx::Height) {
         debug!(%height, "Pruning full proposals");
         self.full_proposal_keeper.remove_full_proposals(height)
@@ -179,6 +182,49 @@ where
         self.input_queue.push(height, input);
     }
 
+    /// Whether the given vote has already been seen at the current height.
+    pub fn is_duplicate_vote(&self, signed_vote: &SignedVote<Ctx>) -> bool {
+        signed_vote.height() == self.height()
+            && self.round().is_defined()
+            && self.seen_votes.contains(signed_vote)
+    }
+
+    /// Whether the given proposal has already been seen at the current height.
+    pub fn is_duplicate_proposal(&self, signed_proposal: &SignedProposal<Ctx>) -> bool {
+        signed_proposal.height() == self.height()
+            && self.round().is_defined()
+            && self
+                .seen_proposals
+                .contains(&seen_proposal(signed_proposal))
+    }
+
+    /// Record a vote for the current height with a valid signature as seen.
+    pub fn store_seen_vote(&mut self, signed_vote: SignedVote<Ctx>) {
+        self.seen_votes.insert(signed_vote);
+    }
+
+    /// Record a proposal for the current height with a valid signature as seen.
+    pub fn store_seen_proposal(&mut self, signed_proposal: &SignedProposal<Ctx>) {
+        self.seen_proposals.insert(seen_proposal(signed_proposal));
+    }
+
+    /// Forget about the votes and proposals seen so far, eg. when moving to a new height.
+    pub fn clear_seen_messages(&mut self) {
+        self.seen_votes.clear();
+        self.seen_proposals.clear();
+    }
+
+    /// The height of the inputs currently buffered for later processing, if any.
+    ///
+    /// If this is higher than the current height, then our peers have already moved past it.
+    pub fn buffered_height(&self) -> Option<Ctx::Height> {
+        if self.input_queue.is_empty() {
+            None
+        } else {
+            Some(*self.input_queue.highest_index())
+        }
+    }
+
     pub fn print_state(&self) {
         if let Some(per_round) = self.driver.votes().per_round(self.driver.round()) {
             warn!(
@@ -221,3 +267,13 @@ where
         }
     }
 }
+
+fn seen_proposal<Ctx: Context>(signed_proposal: &SignedProposal<Ctx>) -> SeenProposal<Ctx> {
+    (
+        signed_proposal.round(),
+        signed_proposal.pol_round(),
+        signed_proposal.value().id(),
+        signed_proposal.validator_address().clone(),
+        signed_proposal.signature.clone(),
+    )
+}
@@ -79,6 +79,11 @@ impl<I, T> MaxQueue<I, T> {
         }
     }
 
+    /// Returns the highest index observed so far.
+    pub fn highest_index(&self) -> &I {
+        &self.highest_index
+    }
+
     /// Returns an iterator over references to the values in the queue.
     ///
     /// # Returns
@@ -159,5 +164,6 @@ mod tests {
         assert_eq!(queue.len(), 1);
         assert!(!queue.is_empty());
         assert_eq!(queue.to_vec(), vec!["three"]);
+        assert_eq!(queue.highest_index(), &3);
     }
 }
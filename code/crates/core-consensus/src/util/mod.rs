@@ -1,2 +1,3 @@
 pub mod max_queue;
 pub mod pretty;
+pub mod seen_cache;
@@ -0,0 +1,124 @@
+use std::collections::{BTreeSet, VecDeque};
+
+/// A bounded cache of the values seen so far, used to detect duplicates.
+///
+/// When the cache is full, the values which were seen the earliest are evicted first.
+/// An evicted value is not reported as a duplicate if it is seen again, which means that
+/// a value is only ever reported as a duplicate if an identical copy of it was seen before.
+#[derive(Clone, Debug)]
+pub struct SeenCache<T> {
+    /// The maximum number of values retained in the cache.
+    capacity: usize,
+
+    /// The values currently in the cache.
+    seen: BTreeSet<T>,
+
+    /// The values currently in the cache, in insertion order.
+    order: VecDeque<T>,
+}
+
+impl<T> SeenCache<T>
+where
+    T: Clone + Ord,
+{
+    /// Creates an empty cache which retains at most `capacity` values.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            seen: BTreeSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Returns whether the given value is in the cache, ie. it was seen before.
+    pub fn contains(&self, value: &T) -> bool {
+        self.seen.contains(value)
+    }
+
+    /// Records the given value as seen.
+    ///
+    /// # Returns
+    /// - `true` if the value was not in the cache, ie. it is seen for the first time,
+    ///   or was evicted from the cache since it was last seen.
+    /// - `false` if the value is a duplicate.
+    pub fn insert(&mut self, value: T) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+
+        if self.seen.contains(&value) {
+            return false;
+        }
+
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+
+        self.seen.insert(value.clone());
+        self.order.push_back(value);
+
+        true
+    }
+
+    /// Returns how many values are stored in the cache.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Removes all values from the cache.
+    pub fn clear(&mut self) {
+        self.seen.clear();
+        self.order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seen_cache() {
+        let mut cache = SeenCache::new(2);
+
+        assert!(cache.is_empty());
+
+        assert!(!cache.contains(&"one"));
+        assert!(cache.insert("one"));
+        assert!(cache.contains(&"one"));
+        assert!(!cache.insert("one"));
+        assert_eq!(cache.len(), 1);
+
+        assert!(cache.insert("two"));
+        assert!(!cache.insert("two"));
+        assert_eq!(cache.len(), 2);
+
+        // Evicts "one"
+        assert!(cache.insert("three"));
+        assert_eq!(cache.len(), 2);
+
+        // "one" was evicted, so it is not reported as a duplicate
+        assert!(!cache.contains(&"one"));
+        assert!(cache.insert("one"));
+        assert!(!cache.insert("three"));
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert!(cache.insert("three"));
+    }
+
+    #[test]
+    fn test_seen_cache_zero_capacity() {
+        let mut cache = SeenCache::new(0);
+
+        assert!(cache.insert("one"));
+        assert!(cache.insert("one"));
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+//! Drive the consensus state machine of a single node, without any network.
+
+// Each test binary only uses some of these helpers
+#![allow(dead_code)]
+
+use malachitebft_core_types::{Round, SigningProvider, VoteType};
+use malachitebft_metrics::Metrics;
+use malachitebft_test::utils::validators::make_validators;
+use malachitebft_test::{
+    Ed25519Provider, Height, PrivateKey, TestContext, Validator, ValidatorSet, ValueId, Vote,
+};
+
+use informalsystems_malachitebft_core_consensus::{
+    process, Effect, Error, Input, Params, Resumable, Resume, SignedConsensusMsg, State,
+    ValuePayload,
+};
+
+/// Whether the signer of the node under test manages to sign its messages
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Signer {
+    #[default]
+    Working,
+    Failing,
+}
+
+/// Whether the node under test manages to persist its state before voting
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Persistence {
+    #[default]
+    Working,
+    Failing,
+}
+
+/// How the environment of the node under test behaves
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Env {
+    pub signer: Signer,
+    pub persistence: Persistence,
+}
+
+impl Env {
+    pub fn failing_signer() -> Self {
+        Self {
+            signer: Signer::Failing,
+            ..Self::default()
+        }
+    }
+
+    pub fn failing_persistence() -> Self {
+        Self {
+            persistence: Persistence::Failing,
+            ..Self::default()
+        }
+    }
+}
+
+/// The effects performed by consensus which matter to the tests, in order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    VerifySignature,
+    PersistBeforeVote(Height, Round, VoteType),
+    SignVote(Vote),
+    Persist(SignedConsensusMsg<TestContext>),
+    Publish(SignedConsensusMsg<TestContext>),
+    Decide(Height, Round, ValueId),
+}
+
+/// Setup the state of the validator at the given index, for the first height
+pub fn setup_validator(
+    validators: &[(Validator, PrivateKey)],
+    index: usize,
+) -> (State<TestContext>, Metrics, Ed25519Provider, ValidatorSet) {
+    let validator_set = ValidatorSet::new(validators.iter().map(|(v, _)| v.clone()));
+    let (validator, key) = &validators[index];
+
+    let params = Params {
+        initial_height: Height::new(1),
+        initial_validator_set: validator_set.clone(),
+        address: validator.address,
+        threshold_params: Default::default(),
+        value_payload: ValuePayload::ProposalAndParts,
+        proposal_grace: false,
+    };
+
+    let state = State::new(TestContext::new(key.clone()), params);
+    let provider = Ed25519Provider::new(key.clone());
+
+    (state, Metrics::new(), provider, validator_set)
+}
+
+/// Setup the state of the only validator of the network, for the first height
+pub fn setup() -> (State<TestContext>, Metrics, Ed25519Provider, ValidatorSet) {
+    setup_validator(&make_validators([1]), 0)
+}
+
+/// Process the given inputs in order, returning the events they led to
+pub fn run_all(
+    state: &mut State<TestContext>,
+    metrics: &Metrics,
+    provider: &Ed25519Provider,
+    env: Env,
+    inputs: impl IntoIterator<Item = Input<TestContext>>,
+) -> Vec<Event> {
+    let mut events = Vec::new();
+
+    for input in inputs {
+        run(state, metrics, provider, env, input, &mut events).expect("process succeeded");
+    }
+
+    events
+}
+
+pub fn run(
+    state: &mut State<TestContext>,
+    metrics: &Metrics,
+    provider: &Ed25519Provider,
+    env: Env,
+    input: Input<TestContext>,
+    events: &mut Vec<Event>,
+) -> Result<(), Box<Error<TestContext>>> {
+    let validator_set = state.validator_set().clone();
+
+    process!(
+        input: input,
+        state: state,
+        metrics: metrics,
+        with: effect => handle_effect(effect, &validator_set, provider, env, events)
+    )
+}
+
+/// Handle the effects of consensus without any network: published messages are recorded,
+/// but never delivered to anyone, including ourselves.
+pub fn handle_effect(
+    effect: Effect<TestContext>,
+    validator_set: &ValidatorSet,
+    provider: &Ed25519Provider,
+    env: Env,
+    events: &mut Vec<Event>,
+) -> Result<Resume<TestContext>, ()> {
+    match effect {
+        Effect::GetValidatorSet(_, r) => Ok(r.resume_with(Some(validator_set.clone()))),
+        Effect::VerifySignature(_, _, r) => {
+            events.push(Event::VerifySignature);
+            Ok(r.resume_with(true))
+        }
+        Effect::VerifyCertificate(_, _, _, r) => Ok(r.resume_with(Ok(()))),
+        Effect::PersistBeforeVote(height, round, vote_type, r) => {
+            events.push(Event::PersistBeforeVote(height, round, vote_type));
+            Ok(r.resume_with(env.persistence == Persistence::Working))
+        }
+        Effect::SignVote(vote, r) => {
+            events.push(Event::SignVote(vote.clone()));
+
+            match env.signer {
+                Signer::Working => Ok(r.resume_with(Some(provider.sign_vote(vote)))),
+                Signer::Failing => Ok(r.resume_with(None)),
+            }
+        }
+        Effect::SignProposal(proposal, r) => match env.signer {
+            Signer::Working => Ok(r.resume_with(Some(provider.sign_proposal(proposal)))),
+            Signer::Failing => Ok(r.resume_with(None)),
+        },
+        Effect::PersistMessage(msg, r) => {
+            events.push(Event::Persist(msg));
+            Ok(r.resume_with(()))
+        }
+        Effect::Publish(msg, r) => {
+            events.push(Event::Publish(msg));
+            Ok(r.resume_with(()))
+        }
+        Effect::Decide(certificate, r) => {
+            events.push(Event::Decide(
+                certificate.height,
+                certificate.round,
+                certificate.value_id,
+            ));
+            Ok(r.resume_with(()))
+        }
+        Effect::ResetTimeouts(r)
+        | Effect::CancelAllTimeouts(r)
+        | Effect::CancelTimeout(_, r)
+        | Effect::ScheduleTimeout(_, r)
+        | Effect::StartRound(_, _, _, r)
+        | Effect::GetValue(_, _, _, r)
+        | Effect::RestreamValue(_, _, _, _, _, r)
+        | Effect::GetVoteSet(_, _, r)
+        | Effect::SendVoteSetResponse(_, _, _, _, r)
+        | Effect::PersistTimeout(_, r) => Ok(r.resume_with(())),
+    }
+}
+
+/// The messages published, in order
+pub fn published(events: &[Event]) -> Vec<SignedConsensusMsg<TestContext>> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Publish(msg) => Some(msg.clone()),
+            _ => None,
+        })
+        .collect()
+}
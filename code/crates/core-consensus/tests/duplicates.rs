@@ -0,0 +1,169 @@
+mod common;
+
+use malachitebft_core_types::{NilOrVal, Round, SignedProposal, SignedVote, SigningProvider};
+use malachitebft_metrics::Metrics;
+use malachitebft_test::utils::validators::make_validators;
+use malachitebft_test::{
+    Ed25519Provider, Height, Proposal, TestContext, ValidatorSet, Value, ValueId, Vote,
+};
+
+use informalsystems_malachitebft_core_consensus::{Input, State};
+
+use common::{run_all, setup_validator, Env, Event};
+
+const DUPLICATES: usize = 1000;
+
+/// Counts the signature verifications performed while processing the given inputs
+fn count_verifications(
+    state: &mut State<TestContext>,
+    metrics: &Metrics,
+    provider: &Ed25519Provider,
+    inputs: impl IntoIterator<Item = Input<TestContext>>,
+) -> usize {
+    run_all(state, metrics, provider, Env::default(), inputs)
+        .iter()
+        .filter(|event| **event == Event::VerifySignature)
+        .count()
+}
+
+/// Setup the state of the first of three validators, along with the signers of all of them
+fn setup() -> (
+    State<TestContext>,
+    Metrics,
+    [Ed25519Provider; 3],
+    ValidatorSet,
+) {
+    let validators = make_validators([1, 1, 1]);
+    let (state, metrics, _, validator_set) = setup_validator(&validators, 0);
+    let signers = validators.map(|(_, key)| Ed25519Provider::new(key));
+
+    (state, metrics, signers, validator_set)
+}
+
+fn prevote(
+    signer: &Ed25519Provider,
+    validator_set: &ValidatorSet,
+    index: usize,
+) -> SignedVote<TestContext> {
+    signer.sign_vote(Vote::new_prevote(
+        Height::new(1),
+        Round::new(0),
+        NilOrVal::Val(ValueId::new(42)),
+        validator_set.validators[index].address,
+    ))
+}
+
+#[test]
+fn duplicate_votes_are_verified_once() {
+    let (mut state, metrics, [signer1, signer2, _], validator_set) = setup();
+
+    count_verifications(
+        &mut state,
+        &metrics,
+        &signer1,
+        [Input::StartHeight(Height::new(1), validator_set.clone())],
+    );
+
+    let vote = prevote(&signer2, &validator_set, 1);
+
+    let verifications = count_verifications(
+        &mut state,
+        &metrics,
+        &signer1,
+        (0..DUPLICATES).map(|_| Input::Vote(vote.clone())),
+    );
+
+    assert_eq!(verifications, 1);
+    assert_eq!(metrics.duplicate_messages.get(), DUPLICATES as u64 - 1);
+}
+
+#[test]
+fn duplicate_proposals_are_verified_once() {
+    let (mut state, metrics, signers, validator_set) = setup();
+
+    count_verifications(
+        &mut state,
+        &metrics,
+        &signers[0],
+        [Input::StartHeight(Height::new(1), validator_set.clone())],
+    );
+
+    let proposer = *state.get_proposer(Height::new(1), Round::new(0));
+    let index = validator_set
+        .validators
+        .iter()
+        .position(|v| v.address == proposer)
+        .unwrap();
+
+    let proposal: SignedProposal<TestContext> = signers[index].sign_proposal(Proposal::new(
+        Height::new(1),
+        Round::new(0),
+        Value::new(42),
+        Round::Nil,
+        proposer,
+    ));
+
+    let verifications = count_verifications(
+        &mut state,
+        &metrics,
+        &signers[0],
+        (0..DUPLICATES).map(|_| Input::Proposal(proposal.clone())),
+    );
+
+    assert_eq!(verifications, 1);
+    assert_eq!(metrics.duplicate_messages.get(), DUPLICATES as u64 - 1);
+}
+
+#[test]
+fn votes_buffered_before_start_are_not_duplicates() {
+    let (mut state, metrics, [signer1, signer2, _], validator_set) = setup();
+
+    let vote = prevote(&signer2, &validator_set, 1);
+
+    // The driver has not started yet, so the vote is verified and buffered every time
+    let verifications = count_verifications(
+        &mut state,
+        &metrics,
+        &signer1,
+        [Input::Vote(vote.clone()), Input::Vote(vote.clone())],
+    );
+
+    assert_eq!(verifications, 2);
+
+    // Buffered votes are replayed when starting the height, and must not be dropped as duplicates
+    let verifications = count_verifications(
+        &mut state,
+        &metrics,
+        &signer1,
+        [Input::StartHeight(Height::new(1), validator_set.clone())],
+    );
+
+    assert_eq!(verifications, 1);
+    assert_eq!(metrics.duplicate_messages.get(), 1);
+}
+
+#[test]
+fn seen_votes_are_forgotten_at_the_next_height() {
+    let (mut state, metrics, [signer1, signer2, _], validator_set) = setup();
+
+    count_verifications(
+        &mut state,
+        &metrics,
+        &signer1,
+        [Input::StartHeight(Height::new(1), validator_set.clone())],
+    );
+
+    let vote = prevote(&signer2, &validator_set, 1);
+    count_verifications(&mut state, &metrics, &signer1, [Input::Vote(vote)]);
+
+    assert_eq!(state.seen_votes.len(), 1);
+
+    count_verifications(
+        &mut state,
+        &metrics,
+        &signer1,
+        [Input::StartHeight(Height::new(2), validator_set.clone())],
+    );
+
+    assert!(state.seen_votes.is_empty());
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use malachitebft_core_types::{Round, Timeout, Vote as _, VoteType};
+use malachitebft_test::{Height, TestContext, Value};
+
+use informalsystems_malachitebft_core_consensus::{Input, SignedConsensusMsg, ValueToPropose};
+
+use common::{run_all, setup, Env, Event};
+
+fn value_to_propose() -> ValueToPropose<TestContext> {
+    ValueToPropose {
+        height: Height::new(1),
+        round: Round::new(0),
+        valid_round: Round::Nil,
+        value: Value::new(42),
+        extension: None,
+    }
+}
+
+#[test]
+fn state_is_persisted_before_signing_and_publishing_votes() {
+    let (mut state, metrics, provider, validator_set) = setup();
+
+    let events = run_all(
+        &mut state,
+        &metrics,
+        &provider,
+        Env::default(),
+        [
+            Input::StartHeight(Height::new(1), validator_set),
+            Input::Propose(value_to_propose()),
+        ],
+    );
+
+    let votes: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::SignVote(vote) => Some(vote.clone()),
+            _ => None,
+        })
+        .collect();
+
+    // Our prevote and precommit
+    assert_eq!(votes.len(), 2);
+
+    for vote in votes {
+        let position = |expected: &Event| events.iter().position(|e| e == expected);
+
+        let persisted = position(&Event::PersistBeforeVote(
+            vote.height(),
+            vote.round(),
+            vote.vote_type(),
+        ))
+        .expect("state persisted");
+
+        let signed = position(&Event::SignVote(vote.clone())).expect("vote signed");
+
+        let published = events
+            .iter()
+            .position(
+                |e| matches!(e, Event::Publish(SignedConsensusMsg::Vote(v)) if v.message == vote),
+            )
+            .expect("vote published");
+
+        assert!(
+            persisted < signed,
+            "vote signed before persisting: {vote:?}"
+        );
+        assert!(
+            signed < published,
+            "vote published before signing: {vote:?}"
+        );
+    }
+}
+
+#[test]
+fn vote_is_not_emitted_when_persistence_fails() {
+    let (mut state, metrics, provider, validator_set) = setup();
+
+    let events = run_all(
+        &mut state,
+        &metrics,
+        &provider,
+        Env::failing_persistence(),
+        [
+            Input::StartHeight(Height::new(1), validator_set),
+            Input::TimeoutElapsed(Timeout::propose(Round::new(0))),
+        ],
+    );
+
+    assert_eq!(
+        events,
+        vec![Event::PersistBeforeVote(
+            Height::new(1),
+            Round::new(0),
+            VoteType::Prevote
+        )]
+    );
+}
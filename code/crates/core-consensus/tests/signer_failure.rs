@@ -0,0 +1,152 @@
+mod common;
+
+use malachitebft_core_types::{Round, Timeout, Validity, ValueOrigin};
+use malachitebft_metrics::Metrics;
+use malachitebft_test::utils::validators::make_validators;
+use malachitebft_test::{Ed25519Provider, Height, TestContext, ValidatorSet, Value};
+
+use informalsystems_malachitebft_core_consensus::{
+    Input, ProposedValue, SignedConsensusMsg, State, ValueToPropose,
+};
+
+use common::{run_all, setup_validator, Env};
+
+/// Returns the messages published while processing the given inputs
+fn published(
+    state: &mut State<TestContext>,
+    metrics: &Metrics,
+    provider: &Ed25519Provider,
+    env: Env,
+    inputs: impl IntoIterator<Item = Input<TestContext>>,
+) -> Vec<SignedConsensusMsg<TestContext>> {
+    common::published(&run_all(state, metrics, provider, env, inputs))
+}
+
+/// Setup the state of the proposer for the first round of the first height
+fn setup() -> (State<TestContext>, Metrics, Ed25519Provider, ValidatorSet) {
+    let validators = make_validators([1, 1, 1]);
+
+    let (state, ..) = setup_validator(&validators, 0);
+    let proposer = *state.get_proposer(Height::new(1), Round::new(0));
+    let index = validators
+        .iter()
+        .position(|(v, _)| v.address == proposer)
+        .unwrap();
+
+    setup_validator(&validators, index)
+}
+
+fn value_to_propose() -> ValueToPropose<TestContext> {
+    ValueToPropose {
+        height: Height::new(1),
+        round: Round::new(0),
+        valid_round: Round::Nil,
+        value: Value::new(42),
+        extension: None,
+    }
+}
+
+#[test]
+fn proposal_and_vote_are_published_when_signer_works() {
+    let (mut state, metrics, provider, validator_set) = setup();
+
+    let published = published(
+        &mut state,
+        &metrics,
+        &provider,
+        Env::default(),
+        [
+            Input::StartHeight(Height::new(1), validator_set),
+            Input::Propose(value_to_propose()),
+        ],
+    );
+
+    assert_eq!(published.len(), 2);
+    assert!(published
+        .iter()
+        .any(|msg| matches!(msg, SignedConsensusMsg::Proposal(_))));
+    assert!(published
+        .iter()
+        .any(|msg| matches!(msg, SignedConsensusMsg::Vote(_))));
+}
+
+#[test]
+fn proposal_is_not_published_when_signer_fails() {
+    let (mut state, metrics, provider, validator_set) = setup();
+
+    let published = published(
+        &mut state,
+        &metrics,
+        &provider,
+        Env::failing_signer(),
+        [
+            Input::StartHeight(Height::new(1), validator_set),
+            Input::Propose(value_to_propose()),
+        ],
+    );
+
+    assert!(published.is_empty());
+}
+
+#[test]
+fn vote_is_not_published_when_signer_fails() {
+    let (mut state, metrics, provider, validator_set) = setup();
+
+    // Failing to sign our prevote must not prevent the round from moving on
+    let published = published(
+        &mut state,
+        &metrics,
+        &provider,
+        Env::failing_signer(),
+        [
+            Input::StartHeight(Height::new(1), validator_set),
+            Input::TimeoutElapsed(Timeout::propose(Round::new(0))),
+        ],
+    );
+
+    assert!(published.is_empty());
+
+    // We have moved on to the prevote step, so the timeout is not processed again
+    let published = self::published(
+        &mut state,
+        &metrics,
+        &provider,
+        Env::default(),
+        [Input::TimeoutElapsed(Timeout::propose(Round::new(0)))],
+    );
+
+    assert!(published.is_empty());
+}
+
+#[test]
+fn value_from_sync_is_proposed_when_signer_fails() {
+    let (mut state, metrics, provider, validator_set) = setup();
+
+    let proposer = *state.get_proposer(Height::new(1), Round::new(0));
+
+    let proposed_value = ProposedValue {
+        height: Height::new(1),
+        round: Round::new(0),
+        valid_round: Round::Nil,
+        proposer,
+        value: Value::new(42),
+        validity: Validity::Valid,
+        extension: None,
+    };
+
+    // The implicit proposal for a value received from sync does not go through our signer
+    published(
+        &mut state,
+        &metrics,
+        &provider,
+        Env::failing_signer(),
+        [
+            Input::StartHeight(Height::new(1), validator_set),
+            Input::ProposedValue(proposed_value, ValueOrigin::Sync),
+        ],
+    );
+
+    assert!(state
+        .full_proposal_at_round_and_value(&Height::new(1), Round::new(0), &Value::new(42))
+        .is_some());
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use malachitebft_core_types::{Round, Timeout};
+use malachitebft_metrics::Metrics;
+use malachitebft_test::{Ed25519Provider, Height, TestContext, ValidatorSet, Value};
+
+use informalsystems_malachitebft_core_consensus::{
+    Input, SignedConsensusMsg, State, ValueToPropose,
+};
+
+use common::{published, run, run_all, setup, Env, Event};
+
+fn value_to_propose(height: Height) -> ValueToPropose<TestContext> {
+    ValueToPropose {
+        height,
+        round: Round::new(0),
+        valid_round: Round::Nil,
+        value: Value::new(height.as_u64()),
+        extension: None,
+    }
+}
+
+/// Run a height from start to decision, without any message coming from the network
+fn run_height(
+    state: &mut State<TestContext>,
+    metrics: &Metrics,
+    provider: &Ed25519Provider,
+    validator_set: &ValidatorSet,
+    height: Height,
+) -> Vec<Event> {
+    run_all(
+        state,
+        metrics,
+        provider,
+        Env::default(),
+        [
+            Input::StartHeight(height, validator_set.clone()),
+            Input::Propose(value_to_propose(height)),
+            Input::TimeoutElapsed(Timeout::commit(Round::new(0))),
+        ],
+    )
+}
+
+#[test]
+fn single_node_decides_without_gossip() {
+    let (mut state, metrics, provider, validator_set) = setup();
+
+    for height in 1..=3 {
+        let height = Height::new(height);
+        let events = run_height(&mut state, &metrics, &provider, &validator_set, height);
+
+        let value_id = value_to_propose(height).value.id();
+
+        assert_eq!(
+            events.last(),
+            Some(&Event::Decide(height, Round::new(0), value_id)),
+            "no decision at height {height}"
+        );
+    }
+}
+
+#[test]
+fn own_messages_are_applied_before_being_published() {
+    let (mut state, metrics, provider, validator_set) = setup();
+
+    let events = run_height(
+        &mut state,
+        &metrics,
+        &provider,
+        &validator_set,
+        Height::new(1),
+    );
+
+    let published = published(&events);
+
+    // Our proposal, prevote and precommit
+    assert_eq!(published.len(), 3);
+
+    for msg in published {
+        let persisted = events
+            .iter()
+            .position(|e| e == &Event::Persist(msg.clone()));
+        let publish = events
+            .iter()
+            .position(|e| e == &Event::Publish(msg.clone()));
+
+        // Votes are persisted to the WAL when applied to the driver
+        if let SignedConsensusMsg::Vote(_) = msg {
+            assert!(
+                persisted.expect("vote persisted") < publish.expect("vote published"),
+                "vote applied after being published: {msg:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn echoed_own_messages_are_dropped() {
+    let (mut state, metrics, provider, validator_set) = setup();
+
+    let mut events = Vec::new();
+
+    run(
+        &mut state,
+        &metrics,
+        &provider,
+        Env::default(),
+        Input::StartHeight(Height::new(1), validator_set),
+        &mut events,
+    )
+    .expect("process succeeded");
+
+    run(
+        &mut state,
+        &metrics,
+        &provider,
+        Env::default(),
+        Input::Propose(value_to_propose(Height::new(1))),
+        &mut events,
+    )
+    .expect("process succeeded");
+
+    let published = published(&events);
+    assert!(!published.is_empty());
+
+    let echoes = published.len() as u64;
+
+    // Gossip delivers our own messages back to us
+    let mut echo_events = Vec::new();
+
+    for msg in published {
+        let input = match msg {
+            SignedConsensusMsg::Vote(vote) => Input::Vote(vote),
+            SignedConsensusMsg::Proposal(proposal) => Input::Proposal(proposal),
+        };
+
+        run(
+            &mut state,
+            &metrics,
+            &provider,
+            Env::default(),
+            input,
+            &mut echo_events,
+        )
+        .expect("process succeeded");
+    }
+
+    assert_eq!(metrics.duplicate_messages.get(), echoes);
+    assert!(echo_events.is_empty());
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use malachitebft_core_types::{
+    NilOrVal, Round, SignedProposal, SigningProvider, Timeout, Validity, ValueOrigin, Vote as _,
+    VoteType,
+};
+use malachitebft_metrics::Metrics;
+use malachitebft_test::utils::validators::make_validators;
+use malachitebft_test::{
+    Address, Ed25519Provider, Height, Proposal, TestContext, ValidatorSet, Value, ValueId,
+};
+
+use informalsystems_malachitebft_core_consensus::{
+    Input, ProposedValue, SignedConsensusMsg, State,
+};
+
+use common::{published, run_all, setup_validator, Env, Event};
+
+/// Setup the state of a validator which is not the proposer of the first round of the first height,
+/// along with the address and signer of that proposer
+fn setup() -> (
+    State<TestContext>,
+    Metrics,
+    Ed25519Provider,
+    ValidatorSet,
+    (Address, Ed25519Provider),
+) {
+    let validators = make_validators([1, 1, 1]);
+
+    let (state, ..) = setup_validator(&validators, 0);
+    let proposer = *state.get_proposer(Height::new(1), Round::new(0));
+    let index = validators
+        .iter()
+        .position(|(v, _)| v.address == proposer)
+        .unwrap();
+
+    let (state, metrics, provider, validator_set) =
+        setup_validator(&validators, (index + 1) % validators.len());
+
+    let proposer_signer = Ed25519Provider::new(validators[index].1.clone());
+
+    (
+        state,
+        metrics,
+        provider,
+        validator_set,
+        (proposer, proposer_signer),
+    )
+}
+
+fn proposal(proposer: Address, signer: &Ed25519Provider) -> SignedProposal<TestContext> {
+    signer.sign_proposal(Proposal::new(
+        Height::new(1),
+        Round::new(0),
+        Value::new(42),
+        Round::Nil,
+        proposer,
+    ))
+}
+
+fn proposed_value(proposer: Address, validity: Validity) -> ProposedValue<TestContext> {
+    ProposedValue {
+        height: Height::new(1),
+        round: Round::new(0),
+        valid_round: Round::Nil,
+        proposer,
+        value: Value::new(42),
+        validity,
+        extension: None,
+    }
+}
+
+/// The values of the prevotes we published
+fn prevotes(events: &[Event]) -> Vec<NilOrVal<ValueId>> {
+    published(events)
+        .into_iter()
+        .filter_map(|msg| match msg {
+            SignedConsensusMsg::Vote(vote) if vote.vote_type() == VoteType::Prevote => {
+                Some(*vote.value())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Receive the proposal for the first round, along with its value of unknown validity
+fn receive_unknown_value(
+    state: &mut State<TestContext>,
+    metrics: &Metrics,
+    provider: &Ed25519Provider,
+    validator_set: ValidatorSet,
+    (proposer, signer): &(Address, Ed25519Provider),
+) -> Vec<Event> {
+    run_all(
+        state,
+        metrics,
+        provider,
+        Env::default(),
+        [
+            Input::StartHeight(Height::new(1), validator_set),
+            Input::Proposal(proposal(*proposer, signer)),
+            Input::ProposedValue(
+                proposed_value(*proposer, Validity::Unknown),
+                ValueOrigin::Consensus,
+            ),
+        ],
+    )
+}
+
+#[test]
+fn prevote_waits_for_the_validity_of_the_value() {
+    let (mut state, metrics, provider, validator_set, proposer) = setup();
+
+    let events = receive_unknown_value(&mut state, &metrics, &provider, validator_set, &proposer);
+    assert!(prevotes(&events).is_empty());
+
+    let events = run_all(
+        &mut state,
+        &metrics,
+        &provider,
+        Env::default(),
+        [Input::ValueValidated(proposed_value(
+            proposer.0,
+            Validity::Valid,
+        ))],
+    );
+
+    assert_eq!(prevotes(&events), vec![NilOrVal::Val(ValueId::new(42))]);
+}
+
+#[test]
+fn value_validated_as_invalid_is_prevoted_nil() {
+    let (mut state, metrics, provider, validator_set, proposer) = setup();
+
+    let events = receive_unknown_value(&mut state, &metrics, &provider, validator_set, &proposer);
+    assert!(prevotes(&events).is_empty());
+
+    let events = run_all(
+        &mut state,
+        &metrics,
+        &provider,
+        Env::default(),
+        [Input::ValueValidated(proposed_value(
+            proposer.0,
+            Validity::Invalid,
+        ))],
+    );
+
+    assert_eq!(prevotes(&events), vec![NilOrVal::Nil]);
+}
+
+#[test]
+fn value_validated_after_the_propose_timeout_does_not_change_our_prevote() {
+    let (mut state, metrics, provider, validator_set, proposer) = setup();
+
+    receive_unknown_value(&mut state, &metrics, &provider, validator_set, &proposer);
+
+    let events = run_all(
+        &mut state,
+        &metrics,
+        &provider,
+        Env::default(),
+        [
+            Input::TimeoutElapsed(Timeout::propose(Round::new(0))),
+            Input::ValueValidated(proposed_value(proposer.0, Validity::Valid)),
+        ],
+    );
+
+    assert_eq!(prevotes(&events), vec![NilOrVal::Nil]);
+}
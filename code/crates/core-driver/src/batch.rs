@@ -0,0 +1,80 @@
+//! Support for processing a batch of inputs at once, eg. when replaying
+//! a large number of votes at startup, coalescing the redundant outputs.
+
+use alloc::vec::Vec;
+
+use malachitebft_core_types::{Context, Timeout};
+
+use crate::{Driver, Error, Input, Output};
+
+impl<Ctx> Driver<Ctx>
+where
+    Ctx: Context,
+{
+    /// Process the given inputs in order, as with [`Driver::process`],
+    /// and return their outputs once the whole batch has been applied.
+    ///
+    /// Because the outputs are only acted upon after the last input has been processed,
+    /// those which have been superseded in the meantime are coalesced as follows:
+    /// - [`Output::ScheduleTimeout`] for a round lower than the one we are at after the batch
+    ///   is dropped, as that timeout would be ignored once elapsed anyway
+    /// - [`Output::ScheduleTimeout`] for the same round and kind as an earlier one is dropped,
+    ///   so that a single timer is started for each timeout
+    /// - [`Output::NewRound`] is only kept for the highest round, as it supersedes the others
+    ///
+    /// All other outputs, in particular votes, proposals and decisions, are returned as is,
+    /// in the order they were produced.
+    ///
+    /// An input which fails to be processed is skipped, as it would be when passed to
+    /// [`Driver::process`] on its own, and the remaining inputs are still processed.
+    /// The errors are returned along with the outputs, in the order of their inputs.
+    pub fn process_batch(
+        &mut self,
+        inputs: Vec<Input<Ctx>>,
+    ) -> (Vec<Output<Ctx>>, Vec<Error<Ctx>>) {
+        let mut outputs = Vec::new();
+        let mut errors = Vec::new();
+
+        for input in inputs {
+            match self.process(input) {
+                Ok(output) => outputs.extend(output),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        (self.coalesce_outputs(outputs), errors)
+    }
+
+    /// Drop the outputs of a batch which are superseded, see [`Driver::process_batch`].
+    fn coalesce_outputs(&self, outputs: Vec<Output<Ctx>>) -> Vec<Output<Ctx>> {
+        let round = self.round();
+
+        let new_round = outputs
+            .iter()
+            .filter_map(|output| match output {
+                Output::NewRound(_, round) => Some(*round),
+                _ => None,
+            })
+            .max();
+
+        let mut timeouts: Vec<Timeout> = Vec::new();
+
+        outputs
+            .into_iter()
+            .filter(|output| match output {
+                Output::ScheduleTimeout(timeout) => {
+                    if timeout.round < round || timeouts.contains(timeout) {
+                        return false;
+                    }
+
+                    timeouts.push(*timeout);
+                    true
+                }
+
+                Output::NewRound(_, r) => Some(*r) == new_round,
+
+                _ => true,
+            })
+            .collect()
+    }
+}
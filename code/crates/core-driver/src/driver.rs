@@ -1,20 +1,26 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::sync::Arc;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt;
 
 use malachitebft_core_state_machine::input::Input as RoundInput;
-use malachitebft_core_state_machine::output::Output as RoundOutput;
+use malachitebft_core_state_machine::output::{Output as RoundOutput, PrevoteReason};
 use malachitebft_core_state_machine::state::{RoundValue, State as RoundState, Step};
 use malachitebft_core_state_machine::state_machine::Info;
 use malachitebft_core_types::{
-    CommitCertificate, Context, Proposal, Round, SignedProposal, SignedVote, Timeout, TimeoutKind,
-    Validator, ValidatorSet, Validity, ValueId, Vote,
+    CommitCertificate, Context, Height, NilOrVal, Proposal, Round, SignedProposal, SignedVote,
+    SigningProviderExt, Timeout, TimeoutKind, Validator, ValidatorSet, Validity, Value, ValueId,
+    Vote, VoteType,
 };
-use malachitebft_core_votekeeper::keeper::VoteKeeper;
+use malachitebft_core_votekeeper::keeper::{Output as VKOutput, VoteKeeper};
+use malachitebft_core_votekeeper::tally::RoundTally;
 
 use crate::input::Input;
 use crate::output::Output;
 use crate::proposal_keeper::{EvidenceMap, ProposalKeeper};
+use crate::proposer::{ProposerSelectionHook, ProposerSelector};
+use crate::replay::WalEntry;
 use crate::Error;
 use crate::ThresholdParams;
 
@@ -25,8 +31,7 @@ where
 {
     /// The context of the consensus engine,
     /// for defining the concrete data types and signature scheme.
-    #[allow(dead_code)]
-    ctx: Ctx,
+    pub(crate) ctx: Ctx,
 
     /// The address of the node.
     address: Ctx::Address,
@@ -34,6 +39,10 @@ where
     /// Quorum thresholds
     threshold_params: ThresholdParams,
 
+    /// Whether to wait for a late proposal before precommitting nil,
+    /// see [`Driver::set_proposal_grace`].
+    proposal_grace: bool,
+
     /// The validator set at the current height
     validator_set: Ctx::ValidatorSet,
 
@@ -55,6 +64,22 @@ where
     /// The pending inputs to be processed next, if any.
     /// The first element of the tuple is the round at which that input has been emitted.
     pending_inputs: Vec<(Round, RoundInput<Ctx>)>,
+
+    /// The inputs accepted at the current height, to be persisted in the write-ahead log,
+    /// if recording them is enabled, see [`Driver::set_record_wal_entries`].
+    pub(crate) wal_entries: Option<Vec<WalEntry<Ctx>>>,
+
+    /// The rounds and types of the votes we have signed at the current height.
+    pub(crate) signed_votes: BTreeSet<(Round, VoteType)>,
+
+    /// Why we prevoted nil in each round of the current height where we did.
+    nil_prevote_reasons: BTreeMap<Round, PrevoteReason>,
+
+    /// The proposer selector set by the application, if any.
+    pub(crate) proposer_selector: Option<Arc<dyn ProposerSelector<Ctx>>>,
+
+    /// The hook called with every proposer selected by the proposer selector, if any.
+    pub(crate) proposer_selection_hook: Option<ProposerSelectionHook<Ctx>>,
 }
 
 impl<Ctx> Driver<Ctx>
@@ -74,7 +99,7 @@ where
         address: Ctx::Address,
         threshold_params: ThresholdParams,
     ) -> Self {
-        let proposal_keeper = ProposalKeeper::new();
+        let proposal_keeper = ProposalKeeper::new(validator_set.clone());
         let vote_keeper = VoteKeeper::new(validator_set.clone(), threshold_params);
         let round_state = RoundState::new(height, Round::Nil);
 
@@ -82,6 +107,7 @@ where
             ctx,
             address,
             threshold_params,
+            proposal_grace: false,
             validator_set,
             proposal_keeper,
             vote_keeper,
@@ -89,14 +115,29 @@ where
             proposer: None,
             pending_inputs: vec![],
             certificates: vec![],
+            wal_entries: None,
+            signed_votes: BTreeSet::new(),
+            nil_prevote_reasons: BTreeMap::new(),
+            proposer_selector: None,
+            proposer_selection_hook: None,
         }
     }
 
     /// Reset votes, round state, pending input
     /// and move to new height with the given validator set.
-    pub fn move_to_height(&mut self, height: Ctx::Height, validator_set: Ctx::ValidatorSet) {
+    ///
+    /// Fails if the given validator set is empty.
+    pub fn move_to_height(
+        &mut self,
+        height: Ctx::Height,
+        validator_set: Ctx::ValidatorSet,
+    ) -> Result<(), Error<Ctx>> {
+        if validator_set.count() == 0 {
+            return Err(Error::EmptyValidatorSet(height));
+        }
+
         // Reset the proposal keeper
-        let proposal_keeper = ProposalKeeper::new();
+        let proposal_keeper = ProposalKeeper::new(validator_set.clone());
 
         // Reset the vote keeper
         let vote_keeper = VoteKeeper::new(validator_set.clone(), self.threshold_params);
@@ -110,6 +151,81 @@ where
         self.round_state = round_state;
         self.pending_inputs = vec![];
         self.certificates = vec![];
+        if let Some(entries) = &mut self.wal_entries {
+            entries.clear();
+        }
+        self.signed_votes = BTreeSet::new();
+        self.nil_prevote_reasons = BTreeMap::new();
+
+        Ok(())
+    }
+
+    /// Move to the height following the current one with the given validator set.
+    ///
+    /// Fails if the given validator set is empty.
+    pub fn move_to_next_height(
+        &mut self,
+        validator_set: Ctx::ValidatorSet,
+    ) -> Result<(), Error<Ctx>> {
+        self.move_to_height(self.height().increment(), validator_set)
+    }
+
+    /// Replace the validator set of the current height, eg. when it is rotated in the middle of it.
+    ///
+    /// The new set is used for proposer selection in the following rounds and for verifying
+    /// certificates, while the vote keeper and proposal keeper drop the votes and proposals of
+    /// the validators which are not part of it anymore, see [`VoteKeeper::update_validator_set`]
+    /// and [`ProposalKeeper::update_validator_set`].
+    ///
+    /// The proposer of the current round is kept, unless it is not part of the new set anymore,
+    /// in which case the proposer of the round is selected again from the new set.
+    ///
+    /// Fails if the given validator set is empty.
+    pub fn update_validator_set(
+        &mut self,
+        validator_set: Ctx::ValidatorSet,
+    ) -> Result<(), Error<Ctx>> {
+        if validator_set.count() == 0 {
+            return Err(Error::EmptyValidatorSet(self.height()));
+        }
+
+        self.proposal_keeper
+            .update_validator_set(validator_set.clone());
+
+        self.vote_keeper.update_validator_set(validator_set.clone());
+
+        self.validator_set = validator_set;
+
+        let rotated_out = self
+            .proposer
+            .as_ref()
+            .is_some_and(|proposer| self.validator_set.get_by_address(proposer).is_none());
+
+        if rotated_out {
+            let proposer = self.select_proposer(self.height(), self.round());
+            self.proposer = Some(proposer.address().clone());
+        }
+
+        Ok(())
+    }
+
+    /// Enable or disable the grace period for late proposals, which is disabled by default.
+    ///
+    /// When enabled and the prevote timeout elapses while we already have a polka for a value
+    /// but not the proposal itself, the driver outputs a [`TimeoutKind::ProposalGrace`] timeout
+    /// instead of precommitting nil right away. If the proposal arrives before that timeout
+    /// elapses, we precommit its value, otherwise we precommit nil once it elapses.
+    pub fn set_proposal_grace(&mut self, enabled: bool) {
+        self.proposal_grace = enabled;
+    }
+
+    /// Set whether to record the inputs accepted at the current height,
+    /// see [`Driver::to_wal_entries`].
+    ///
+    /// Disabled by default, since every input, including every vote, is then kept in memory
+    /// until the next height. Only enable it if these entries are to be persisted.
+    pub fn set_record_wal_entries(&mut self, enabled: bool) {
+        self.wal_entries = enabled.then(Vec::new);
     }
 
     /// Return the height of the consensus.
@@ -157,6 +273,30 @@ where
         &self.vote_keeper
     }
 
+    /// Return the votes tallied for the given round of the current height, if any.
+    pub fn round_tally(&self, round: Round) -> Option<RoundTally<Ctx>> {
+        self.vote_keeper.round_tally(round)
+    }
+
+    /// Return the votes tallied for all the rounds of the current height, in increasing order.
+    pub fn tallies(&self) -> Vec<RoundTally<Ctx>> {
+        self.vote_keeper.tallies()
+    }
+
+    /// Return why we prevoted nil in the given round of the current height,
+    /// or `None` if we did not prevote nil in that round.
+    ///
+    /// This tells apart a nil prevote because we are locked on a conflicting value
+    /// from one because the proposal was invalid or did not arrive in time.
+    pub fn nil_prevote_reason(&self, round: Round) -> Option<PrevoteReason> {
+        self.nil_prevote_reasons.get(&round).copied()
+    }
+
+    /// Return a reference to the proposal keeper
+    pub fn proposals(&self) -> &ProposalKeeper<Ctx> {
+        &self.proposal_keeper
+    }
+
     /// Return the state for the current round.
     pub fn round_state(&self) -> &RoundState<Ctx> {
         &self.round_state
@@ -172,7 +312,8 @@ where
         &self.validator_set
     }
 
-    /// Return recorded evidence of equivocation for this height.
+    /// Return recorded evidence of misbehavior by proposers for this height,
+    /// ie. equivocating and invalid proposals.
     pub fn evidence(&self) -> &EvidenceMap<Ctx> {
         self.proposal_keeper.evidence()
     }
@@ -203,10 +344,19 @@ where
     }
 
     /// Process the given input, returning the outputs to be broadcast to the network.
+    ///
+    /// Inputs which are successfully processed are recorded in the write-ahead log if enabled,
+    /// see [`Driver::set_record_wal_entries`].
     pub fn process(&mut self, msg: Input<Ctx>) -> Result<Vec<Output<Ctx>>, Error<Ctx>> {
+        let signed_vote = self.own_vote(&msg);
+        let entry = self.wal_entries.is_some().then(|| msg.clone());
+
         let round_output = match self.apply(msg)? {
             Some(msg) => msg,
-            None => return Ok(Vec::new()),
+            None => {
+                self.record_input(signed_vote, entry);
+                return Ok(Vec::new());
+            }
         };
 
         let mut outputs = vec![];
@@ -224,6 +374,9 @@ where
             }
         }
 
+        self.record_input(signed_vote, entry);
+        self.filter_signed_votes(&mut outputs);
+
         Ok(outputs)
     }
 
@@ -243,10 +396,45 @@ where
                 outputs.push(Output::GetValue(height, round, timeout));
             }
 
-            RoundOutput::Decision(round, proposal) => outputs.push(Output::Decide(round, proposal)),
+            RoundOutput::Decision(round, proposal) => {
+                let certificate = self.commit_certificate(&proposal);
+                outputs.push(Output::Decide(round, proposal, certificate))
+            }
         }
     }
 
+    /// Build the commit certificate for the given decided proposal.
+    ///
+    /// If we received a certificate for that value, return it as is.
+    /// Otherwise, build one from the precommits for that value we received at the round
+    /// of the proposal, which is where the quorum that led to the decision was reached.
+    fn commit_certificate(&self, proposal: &Ctx::Proposal) -> CommitCertificate<Ctx> {
+        let round = proposal.round();
+        let value_id = proposal.value().id();
+
+        if let Some(certificate) = self.get_certificate(round, value_id.clone()) {
+            return certificate.clone();
+        }
+
+        let precommits = self
+            .vote_keeper
+            .per_round(round)
+            .map(|per_round| {
+                per_round
+                    .received_votes()
+                    .iter()
+                    .filter(|vote| {
+                        vote.vote_type() == VoteType::Precommit
+                            && vote.value() == &NilOrVal::Val(value_id.clone())
+                    })
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        CommitCertificate::new(self.height(), round, value_id, precommits)
+    }
+
     /// Apply the given input to the state machine, returning the output, if any.
     fn apply(&mut self, input: Input<Ctx>) -> Result<Option<RoundOutput<Ctx>>, Error<Ctx>> {
         match input {
@@ -256,6 +444,9 @@ where
             }
             Input::ProposeValue(round, value) => self.apply_propose_value(round, value),
             Input::Proposal(proposal, validity) => self.apply_proposal(proposal, validity),
+            Input::ProposalValidated(proposal, validity) => {
+                self.apply_proposal_validated(proposal, validity)
+            }
             Input::Vote(vote) => self.apply_vote(vote),
             Input::TimeoutElapsed(timeout) => self.apply_timeout(timeout),
         }
@@ -272,6 +463,8 @@ where
             });
         }
 
+        self.verify_certificate(&certificate)?;
+
         let round = certificate.round;
 
         match self.store_and_multiplex_certificate(certificate) {
@@ -280,6 +473,17 @@ where
         }
     }
 
+    /// Verify the certificate against the validator set for this height:
+    /// - all signatures must be valid
+    /// - all signers must be in the validator set, and sign only once
+    /// - the signers must hold a quorum of the voting power
+    fn verify_certificate(&self, certificate: &CommitCertificate<Ctx>) -> Result<(), Error<Ctx>> {
+        self.ctx
+            .signing_provider()
+            .verify_certificate(certificate, &self.validator_set, self.threshold_params)
+            .map_err(Error::InvalidCertificate)
+    }
+
     fn apply_new_round(
         &mut self,
         height: Ctx::Height,
@@ -287,6 +491,13 @@ where
         proposer: Ctx::Address,
     ) -> Result<Option<RoundOutput<Ctx>>, Error<Ctx>> {
         if self.height() == height {
+            if round < self.round() {
+                return Err(Error::PastRound {
+                    current: self.round(),
+                    received: round,
+                });
+            }
+
             // If it's a new round for same height, just reset the round, keep the valid and locked values
             self.round_state.round = round;
         } else {
@@ -304,6 +515,14 @@ where
         round: Round,
         value: Ctx::Value,
     ) -> Result<Option<RoundOutput<Ctx>>, Error<Ctx>> {
+        if round != self.round() || self.step() != Step::Propose {
+            return Err(Error::WrongStep {
+                round,
+                current_round: self.round(),
+                step: self.step(),
+            });
+        }
+
         self.apply_input(round, RoundInput::ProposeValue(value))
     }
 
@@ -321,7 +540,93 @@ where
 
         let round = proposal.round();
 
-        match self.store_and_multiplex_proposal(proposal, validity) {
+        if self
+            .validator_set
+            .get_by_address(proposal.validator_address())
+            .is_none()
+        {
+            return Err(Error::ValidatorNotFound(
+                proposal.validator_address().clone(),
+            ));
+        }
+
+        // We only know the proposer of the current round, once it has been started
+        if round == self.round() && self.step() != Step::Unstarted {
+            if let Some(proposer) = &self.proposer {
+                if proposer != proposal.validator_address() {
+                    return Err(Error::ProposerMismatch {
+                        expected: proposer.clone(),
+                        actual: proposal.validator_address().clone(),
+                    });
+                }
+            }
+        }
+
+        if proposal.validator_address() == &self.address {
+            if let Some((existing, _)) = self
+                .proposal_keeper
+                .get_proposal_and_validity_for_round(round)
+            {
+                if existing.validator_address() == &self.address
+                    && existing.value() != proposal.value()
+                {
+                    return Err(Error::SelfEquivocation(self.height(), round));
+                }
+            }
+        }
+
+        // A proposal signed by the proposer of a higher round shows that its proposer is
+        // at that round, so it counts towards skipping to it, along with the votes.
+        let skip_round = if round > self.round()
+            && proposal.validator_address() != &self.address
+            && self.select_proposer(self.height(), round).address() == proposal.validator_address()
+        {
+            self.vote_keeper
+                .apply_proposal_round(proposal.validator_address(), round, self.round())
+        } else {
+            None
+        };
+
+        // The proposal is stored either way, so that it gets re-applied once we skip to its round
+        if let Some(round_input) = self.store_and_multiplex_proposal(proposal, validity) {
+            return self.apply_input(round, round_input);
+        }
+
+        match skip_round {
+            Some(VKOutput::SkipRound(skip_round)) => {
+                self.apply_input(round, RoundInput::SkipRound(skip_round))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn apply_proposal_validated(
+        &mut self,
+        proposal: SignedProposal<Ctx>,
+        validity: Validity,
+    ) -> Result<Option<RoundOutput<Ctx>>, Error<Ctx>> {
+        if self.height() != proposal.height() {
+            return Err(Error::InvalidProposalHeight {
+                proposal_height: proposal.height(),
+                consensus_height: self.height(),
+            });
+        }
+
+        // Ignore proposals we never received, or whose validity is already known
+        if !self.proposal_keeper.resolve_validity(&proposal, validity) {
+            return Ok(None);
+        }
+
+        let round = proposal.round();
+
+        // The value may have been re-proposed with a different POL round in the meantime,
+        // in which case the latest proposal is the one to consider, eg. for `ProposalAndPolkaPrevious`.
+        let latest = self
+            .proposal_keeper
+            .get_proposal_and_validity_for_round(round)
+            .map_or(proposal.message, |(latest, _)| latest.message.clone());
+
+        match self.multiplex_proposal(latest, validity) {
             Some(round_input) => self.apply_input(round, round_input),
             None => Ok(None),
         }
@@ -338,17 +643,21 @@ where
             });
         }
 
-        if self
-            .validator_set
-            .get_by_address(vote.validator_address())
-            .is_none()
-        {
-            return Err(Error::ValidatorNotFound(vote.validator_address().clone()));
-        }
-
         let vote_round = vote.round();
 
-        let Some(output) = self.vote_keeper.apply_vote(vote, self.round()) else {
+        if vote.validator_address() == &self.address {
+            let existing = self
+                .vote_keeper
+                .per_round(vote_round)
+                .and_then(|per_round| per_round.get_vote(vote.vote_type(), &self.address));
+
+            if existing.is_some_and(|existing| existing.value() != vote.value()) {
+                return Err(Error::SelfEquivocation(self.height(), vote_round));
+            }
+        }
+
+        // Votes from validators outside of the validator set are rejected by the vote keeper
+        let Some(output) = self.vote_keeper.apply_vote(vote, self.round())? else {
             return Ok(None);
         };
 
@@ -362,11 +671,28 @@ where
     }
 
     fn apply_timeout(&mut self, timeout: Timeout) -> Result<Option<RoundOutput<Ctx>>, Error<Ctx>> {
+        // A timeout scheduled in an earlier round, or for a step we have since left,
+        // is ignored rather than reported as an error, as timers may fire at any time.
+        if self.is_stale_timeout(&timeout) {
+            return Ok(None);
+        }
+
         let input = match timeout.kind {
             TimeoutKind::Propose => RoundInput::TimeoutPropose,
+            TimeoutKind::Prevote if self.should_wait_for_late_proposal(timeout.round) => {
+                return Ok(Some(RoundOutput::ScheduleTimeout(Timeout::proposal_grace(
+                    timeout.round,
+                ))));
+            }
             TimeoutKind::Prevote => RoundInput::TimeoutPrevote,
             TimeoutKind::Precommit => RoundInput::TimeoutPrecommit,
 
+            // Once the grace period is over, precommit nil as we would have done
+            // when the prevote timeout elapsed (L61). If the proposal arrived in the meantime,
+            // we already precommitted its value and moved past the prevote step,
+            // so the state machine ignores this input.
+            TimeoutKind::ProposalGrace => RoundInput::TimeoutPrevote,
+
             // The driver never receives a commit or time limit timeout, so we can just ignore it.
             TimeoutKind::Commit => return Ok(None),
             TimeoutKind::PrevoteTimeLimit => return Ok(None),
@@ -376,6 +702,50 @@ where
         self.apply_input(timeout.round, input)
     }
 
+    /// Whether the given timeout is stale, ie. it was scheduled for another round than
+    /// the current one, or for a step which is no longer relevant:
+    /// - the propose timeout is only relevant in the propose step
+    /// - the prevote timeout and the proposal grace period are only relevant in the prevote step
+    /// - the precommit timeout is relevant in any step of the round until we commit
+    ///
+    /// The commit and time limit timeouts are not handled by the driver and are never stale.
+    pub fn is_stale_timeout(&self, timeout: &Timeout) -> bool {
+        match timeout.kind {
+            TimeoutKind::Commit
+            | TimeoutKind::PrevoteTimeLimit
+            | TimeoutKind::PrecommitTimeLimit => false,
+
+            _ if timeout.round != self.round() => true,
+
+            TimeoutKind::Propose => !self.step_is_propose(),
+            TimeoutKind::Prevote | TimeoutKind::ProposalGrace => !self.step_is_prevote(),
+            TimeoutKind::Precommit => self.step_is_commit(),
+        }
+    }
+
+    /// Whether to wait for a late proposal instead of precommitting nil when the prevote timeout
+    /// for the given round elapses, ie. if the grace period is enabled and we are still
+    /// in the prevote step with a polka for a value, but we are merely missing the proposal.
+    fn should_wait_for_late_proposal(&self, round: Round) -> bool {
+        if !self.proposal_grace || round != self.round() || self.step() != Step::Prevote {
+            return false;
+        }
+
+        let has_polka_value = self.vote_keeper.per_round(round).is_some_and(|per_round| {
+            per_round
+                .emitted_outputs()
+                .iter()
+                .any(|output| matches!(output, VKOutput::PolkaValue(_)))
+        });
+
+        let missing_proposal = self
+            .proposal_keeper
+            .get_proposal_and_validity_for_round(round)
+            .map_or(true, |(_, validity)| validity.is_unknown());
+
+        has_polka_value && missing_proposal
+    }
+
     /// Apply the input, update the state.
     fn apply_input(
         &mut self,
@@ -395,6 +765,11 @@ where
         // Update state
         self.round_state = transition.next_state;
 
+        if let Some(reason) = transition.nil_prevote_reason {
+            self.nil_prevote_reasons
+                .insert(self.round_state.round, reason);
+        }
+
         if previous_step != self.round_state.step && self.round_state.step != Step::Unstarted {
             let pending_inputs = self.multiplex_step_change(input_round);
 
@@ -427,6 +802,7 @@ where
             .field("proposal", &self.proposal_keeper)
             .field("votes", &self.vote_keeper)
             .field("round_state", &self.round_state)
+            .field("has_proposer_selector", &self.proposer_selector.is_some())
             .finish()
     }
 }
@@ -1,6 +1,12 @@
 use derive_where::derive_where;
 
-use malachitebft_core_types::{Context, Round};
+use malachitebft_core_state_machine::state::Step;
+use malachitebft_core_types::{CertificateError, Context, Round};
+use malachitebft_core_votekeeper::keeper::VoteError;
+
+/// Alias for the type of errors that can be yielded by the `Driver`,
+/// for consumers which need to disambiguate it from their own `Error` type.
+pub type DriverError<Ctx> = Error<Ctx>;
 
 /// The type of errors that can be yielded by the `Driver`.
 #[derive_where(Clone, Debug, PartialEq, Eq)]
@@ -21,6 +27,24 @@ where
     #[error("Validator not found: {0}")]
     ValidatorNotFound(Ctx::Address),
 
+    /// Received a vote which was rejected by the vote keeper,
+    /// eg. because it was cast by a validator outside of the validator set
+    #[error("Invalid vote: {0}")]
+    InvalidVote(#[from] VoteError<Ctx>),
+
+    /// Received a proposal for the current round from a validator which is not its proposer
+    #[error("Received proposal from {actual} instead of the proposer {expected}")]
+    ProposerMismatch {
+        /// Proposer for the round
+        expected: Ctx::Address,
+        /// Validator which sent the proposal
+        actual: Ctx::Address,
+    },
+
+    /// The validator set for the given height is empty
+    #[error("Empty validator set for height {0}")]
+    EmptyValidatorSet(Ctx::Height),
+
     /// Received a proposal for another height
     #[error("Received proposal for height {proposal_height} different from consensus height {consensus_height}")]
     InvalidProposalHeight {
@@ -49,4 +73,85 @@ where
         /// Consensus height
         consensus_height: Ctx::Height,
     },
+
+    /// Asked to start a round lower than the current round of the current height
+    #[error("Cannot start round {received}, already at round {current}")]
+    PastRound {
+        /// Current round
+        current: Round,
+        /// Round to start
+        received: Round,
+    },
+
+    /// Received a certificate which does not hold against the validator set
+    #[error("Invalid certificate: {0}")]
+    InvalidCertificate(CertificateError<Ctx>),
+
+    /// Asked to propose a value outside of the propose step of the current round
+    #[error(
+        "Cannot propose a value for round {round} while at step {step:?} of round {current_round}"
+    )]
+    WrongStep {
+        /// Round of the value to propose
+        round: Round,
+        /// Current round
+        current_round: Round,
+        /// Current step
+        step: Step,
+    },
+
+    /// Received a message signed by us which conflicts with one we already signed
+    #[error("Self-equivocation detected at height {0} and round {1}")]
+    SelfEquivocation(Ctx::Height, Round),
+}
+
+/// The kind of an [`Error`], eg. to tell protocol violations by peers apart from other errors.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorKind {
+    /// The input is for a height other than the current one
+    WrongHeight,
+
+    /// The input is for a round older than the current one
+    PastRound,
+
+    /// The input is invalid for the current height and round, eg. a proposal from a validator
+    /// which is not the proposer, a message from an unknown validator or an invalid certificate
+    Invalid,
+
+    /// The input was provided by the local node at a time it cannot be processed,
+    /// or conflicts with what the local node has already done
+    Local,
+}
+
+impl<Ctx> Error<Ctx>
+where
+    Ctx: Context,
+{
+    /// The kind of this error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::InvalidProposalHeight { .. }
+            | Self::InvalidVoteHeight { .. }
+            | Self::InvalidCertificateHeight { .. } => ErrorKind::WrongHeight,
+
+            Self::PastRound { .. } => ErrorKind::PastRound,
+
+            Self::ProposerNotFound(_)
+            | Self::ValidatorNotFound(_)
+            | Self::InvalidVote(_)
+            | Self::ProposerMismatch { .. }
+            | Self::InvalidCertificate(_) => ErrorKind::Invalid,
+
+            Self::NoProposer(_, _)
+            | Self::EmptyValidatorSet(_)
+            | Self::WrongStep { .. }
+            | Self::SelfEquivocation(_, _) => ErrorKind::Local,
+        }
+    }
+
+    /// Whether the input which caused this error is a protocol violation,
+    /// ie. a message sent by a peer which it should not have sent.
+    pub fn is_protocol_violation(&self) -> bool {
+        self.kind() == ErrorKind::Invalid
+    }
 }
@@ -6,6 +6,18 @@ use derive_where::derive_where;
 
 /// Events that can be received by the [`Driver`](crate::Driver).
 #[derive_where(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Ctx::Height: serde::Serialize, Ctx::Address: serde::Serialize, \
+                     Ctx::Value: serde::Serialize, SignedProposal<Ctx>: serde::Serialize, \
+                     SignedVote<Ctx>: serde::Serialize, CommitCertificate<Ctx>: serde::Serialize",
+        deserialize = "Ctx::Height: serde::Deserialize<'de>, Ctx::Address: serde::Deserialize<'de>, \
+                       Ctx::Value: serde::Deserialize<'de>, SignedProposal<Ctx>: serde::Deserialize<'de>, \
+                       SignedVote<Ctx>: serde::Deserialize<'de>, CommitCertificate<Ctx>: serde::Deserialize<'de>",
+    ))
+)]
 pub enum Input<Ctx>
 where
     Ctx: Context,
@@ -19,10 +31,21 @@ where
     /// Receive a proposal, of the given validity
     Proposal(SignedProposal<Ctx>, Validity),
 
+    /// Receive the validity of a proposal which was previously received
+    /// with [`Validity::Unknown`].
+    ///
+    /// Until then, we do not prevote for that proposal. If the propose timeout
+    /// elapses first, we prevote nil and the late validity will not change our prevote.
+    ProposalValidated(SignedProposal<Ctx>, Validity),
+
     /// Receive a vote
     Vote(SignedVote<Ctx>),
 
-    /// Received a commit certificate
+    /// Receive a commit certificate, eg. from a peer when catching up.
+    ///
+    /// The certificate is verified against the validator set before being applied.
+    /// If the proposal for the certified value is known, we decide on it right away,
+    /// otherwise the decision happens as soon as that proposal is received.
     CommitCertificate(CommitCertificate<Ctx>),
 
     /// Receive a timeout
@@ -14,16 +14,25 @@
 
 extern crate alloc;
 
+mod batch;
 mod driver;
 mod error;
 mod input;
 mod mux;
 mod output;
-mod proposal_keeper;
+mod proposer;
+mod replay;
+
+pub mod proposal_keeper;
 
 pub use driver::Driver;
-pub use error::Error;
+pub use error::{DriverError, Error, ErrorKind};
 pub use input::Input;
 pub use output::Output;
+pub use proposal_keeper::RecordProposalError;
+pub use proposer::{ProposerSelection, ProposerSelectionHook, ProposerSelector};
+pub use replay::WalEntry;
 
+pub use malachitebft_core_state_machine::output::PrevoteReason;
+pub use malachitebft_core_state_machine::state::Step;
 pub use malachitebft_core_votekeeper::ThresholdParams;
@@ -58,25 +58,28 @@ where
     ///
     /// 1. Check that there is an ongoing round, otherwise return `None`
     ///
-    /// 2. If the proposal is invalid, the method follows these steps:
+    /// 2. If the validity of the proposal is unknown, return `None`, so that we neither prevote
+    ///    for it nor decide on it until its validity is known, see `Input::ProposalValidated`.
+    ///
+    /// 3. If the proposal is invalid, the method follows these steps:
     ///    a. If we are at propose step and the proposal's proof-of-lock (POL) round is `Nil`, return
     ///       `RoundInput::InvalidProposal`.
     ///    b. If we are at propose step and there is a polka for a prior-round proof-of-lock (POL),
     ///       return `RoundInput::InvalidProposalAndPolkaPrevious`.
     ///    c. For other steps or if there is no prior-round POL, return `None`.
     ///
-    /// 3. If a quorum of precommit votes is met for the proposal's value,
+    /// 4. If a quorum of precommit votes is met for the proposal's value,
     ///    return `RoundInput::ProposalAndPrecommitValue` including the proposal.
     ///
-    /// 4. If the proposal is for a different round than the current one, return `None`.
+    /// 5. If the proposal is for a different round than the current one, return `None`.
     ///
-    /// 5. If a polka is present for the current round and we are beyond the prevote step,
+    /// 6. If a polka is present for the current round and we are beyond the prevote step,
     ///    return `RoundInput::ProposalAndPolkaCurrent`, including the proposal.
     ///
-    /// 6. If we are at the propose step, and a polka exists for a the propopsal's POL round,
+    /// 7. If we are at the propose step, and a polka exists for a the propopsal's POL round,
     ///    return `RoundInput::ProposalAndPolkaPrevious`, including the proposal.
     ///
-    /// 7. If none of the above conditions are met, simply wrap the proposal in
+    /// 8. If none of the above conditions are met, simply wrap the proposal in
     ///    `RoundInput::Proposal` and return it.
     pub(crate) fn multiplex_proposal(
         &mut self,
@@ -91,6 +94,11 @@ where
             return None;
         }
 
+        // Wait until the validity of the proposal is known
+        if validity.is_unknown() {
+            return None;
+        }
+
         // Determine if there is a polka for a previous round
         let polka_previous = proposal.pol_round().is_defined()
             && proposal.pol_round() < self.round_state.round
@@ -178,6 +186,15 @@ where
         self.proposal_keeper
             .store_proposal(signed_proposal, validity);
 
+        // The validity of the value may already be known from an earlier proposal for it
+        let validity = match self
+            .proposal_keeper
+            .get_proposal_and_validity_for_round(proposal.round())
+        {
+            Some((stored, stored_validity)) if stored.message == proposal => *stored_validity,
+            _ => validity,
+        };
+
         self.multiplex_proposal(proposal, validity)
     }
 
@@ -1,9 +1,19 @@
 use derive_where::derive_where;
 
-use malachitebft_core_types::{Context, Round, Timeout};
+use malachitebft_core_types::{CommitCertificate, Context, Round, Timeout};
 
 /// Messages emitted by the [`Driver`](crate::Driver)
 #[derive_where(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Ctx::Height: serde::Serialize, Ctx::Proposal: serde::Serialize, \
+                     Ctx::Vote: serde::Serialize, CommitCertificate<Ctx>: serde::Serialize",
+        deserialize = "Ctx::Height: serde::Deserialize<'de>, Ctx::Proposal: serde::Deserialize<'de>, \
+                       Ctx::Vote: serde::Deserialize<'de>, CommitCertificate<Ctx>: serde::Deserialize<'de>",
+    ))
+)]
 pub enum Output<Ctx>
 where
     Ctx: Context,
@@ -15,10 +25,16 @@ where
     Propose(Ctx::Proposal),
 
     /// Broadcast a vote for a value
+    ///
+    /// The vote MUST NOT be signed nor broadcast before the state which led to it
+    /// has been durably persisted, so that a conflicting vote cannot be cast after a crash.
     Vote(Ctx::Vote),
 
-    /// Decide on a value
-    Decide(Round, Ctx::Proposal),
+    /// Decide on a value, at the given consensus round.
+    ///
+    /// The commit certificate holds the precommits for the decided value
+    /// which formed the +2/3 quorum, at the round of the proposal.
+    Decide(Round, Ctx::Proposal, CommitCertificate<Ctx>),
 
     /// Schedule a timeout
     ScheduleTimeout(Timeout),
@@ -7,7 +7,9 @@ use alloc::vec::Vec;
 use derive_where::derive_where;
 use thiserror::Error;
 
-use malachitebft_core_types::{Context, Proposal, Round, SignedProposal, Validity};
+use malachitebft_core_types::{
+    Context, Proposal, Round, SignedProposal, ValidatorSet, Validity, Value,
+};
 
 /// Errors can that be yielded when recording a proposal.
 #[derive_where(Debug)]
@@ -17,7 +19,11 @@ where
     Ctx: Context,
 {
     /// Attempted to record a conflicting proposal.
-    #[error("Conflicting proposal: existing: {existing}, conflicting: {conflicting}")]
+    #[error(
+        "Conflicting proposal at round {} from validator {}",
+        .conflicting.round(),
+        .conflicting.validator_address()
+    )]
     ConflictingProposal {
         /// The proposal already recorded for the same value.
         existing: SignedProposal<Ctx>,
@@ -26,7 +32,12 @@ where
     },
 
     /// Attempted to record a conflicting proposal from a different validator.
-    #[error("Invalid conflicting proposal: existing: {existing}, conflicting: {conflicting}")]
+    #[error(
+        "Invalid conflicting proposal at round {} from validator {}, existing proposal is from validator {}",
+        .conflicting.round(),
+        .conflicting.validator_address(),
+        .existing.validator_address()
+    )]
     InvalidConflictingProposal {
         /// The proposal already recorded for the same value.
         existing: SignedProposal<Ctx>,
@@ -35,13 +46,25 @@ where
     },
 }
 
+/// Maximum number of proposals kept for the value of a round.
+///
+/// Bounds the memory a proposer can make us use by re-proposing its value with many different POL rounds.
+const MAX_PROPOSALS_PER_ROUND: usize = 8;
+
 #[derive_where(Clone, Debug, PartialEq, Eq, Default)]
 struct PerRound<Ctx>
 where
     Ctx: Context,
 {
-    /// The proposal received in a given round (proposal.round) if any.
+    /// The latest proposal received in a given round (proposal.round) if any.
     proposal: Option<(SignedProposal<Ctx>, Validity)>,
+
+    /// The proposals received for the value of the round, in the order they were received,
+    /// at most one per POL round and at most [`MAX_PROPOSALS_PER_ROUND`] in total.
+    ///
+    /// The proposer may re-propose the same value with a different POL round,
+    /// eg. once it learns of a polka for that value in a previous round.
+    received: Vec<SignedProposal<Ctx>>,
 }
 
 impl<Ctx> PerRound<Ctx>
@@ -49,13 +72,17 @@ where
     Ctx: Context,
 {
     /// Add a proposal to the round, checking for conflicts.
+    ///
+    /// Two proposals conflict if they are for different values, regardless of their POL round.
+    /// A proposal for the same value replaces the one stored before it, but the first one
+    /// received is kept around so that it can be used as evidence in case of equivocation.
     pub fn add(
         &mut self,
         proposal: SignedProposal<Ctx>,
         validity: Validity,
     ) -> Result<(), RecordProposalError<Ctx>> {
-        if let Some((existing, _)) = self.get_proposal() {
-            if existing.value() != proposal.value() {
+        if let Some(existing) = self.received.first() {
+            if existing.value().id() != proposal.value().id() {
                 if existing.validator_address() != proposal.validator_address() {
                     // This is not a valid equivocating proposal, since the two proposers are different
                     // We should never reach this point, since the consensus algorithm should prevent this.
@@ -73,27 +100,48 @@ where
             }
         }
 
-        // Add the proposal
+        // Do not forget the validity of the value we already received
+        let validity = match &self.proposal {
+            Some((_, known)) if validity.is_unknown() => *known,
+            _ => validity,
+        };
+
+        let known_pol_round = self
+            .received
+            .iter()
+            .any(|received| received.pol_round() == proposal.pol_round());
+
+        if !known_pol_round && self.received.len() < MAX_PROPOSALS_PER_ROUND {
+            self.received.push(proposal.clone());
+        }
+
+        // Replace the proposal with the latest one
         self.proposal = Some((proposal, validity));
 
         Ok(())
     }
 
-    /// Return the proposal received from the given validator.
+    /// Return the latest proposal received for the round, along with the validity of its value.
     pub fn get_proposal(&self) -> Option<&(SignedProposal<Ctx>, Validity)> {
         self.proposal.as_ref()
     }
 }
 
 /// Keeps track of proposals.
-#[derive_where(Clone, Debug, Default)]
+#[derive_where(Clone, Debug)]
 pub struct ProposalKeeper<Ctx>
 where
     Ctx: Context,
 {
+    /// The validator set for this height.
+    validator_set: Ctx::ValidatorSet,
+
     /// The proposal for each round.
     per_round: BTreeMap<Round, PerRound<Ctx>>,
 
+    /// Proposals from validators which are no longer part of the validator set.
+    quarantined: Vec<(SignedProposal<Ctx>, Validity)>,
+
     /// Evidence of equivocation.
     evidence: EvidenceMap<Ctx>,
 }
@@ -102,9 +150,52 @@ impl<Ctx> ProposalKeeper<Ctx>
 where
     Ctx: Context,
 {
-    /// Create a new `ProposalKeeper` instance
-    pub fn new() -> Self {
-        Self::default()
+    /// Create a new `ProposalKeeper` instance for the given validator set.
+    pub fn new(validator_set: Ctx::ValidatorSet) -> Self {
+        Self {
+            validator_set,
+            per_round: BTreeMap::new(),
+            quarantined: Vec::new(),
+            evidence: EvidenceMap::new(),
+        }
+    }
+
+    /// Return the current validator set.
+    pub fn validator_set(&self) -> &Ctx::ValidatorSet {
+        &self.validator_set
+    }
+
+    /// Replace the validator set, eg. when it changes in the middle of a height.
+    ///
+    /// Proposals already stored are re-validated against the new validator set:
+    /// those whose proposer is not part of the new set anymore are moved to the
+    /// quarantined proposals, and will not be considered by the driver anymore.
+    ///
+    /// Evidence of equivocation recorded so far is retained as is,
+    /// since it remains valid regardless of the validator set change.
+    pub fn update_validator_set(&mut self, new_set: Ctx::ValidatorSet) {
+        for per_round in self.per_round.values_mut() {
+            let is_known = |(proposal, _): &(SignedProposal<Ctx>, Validity)| {
+                new_set
+                    .get_by_address(proposal.validator_address())
+                    .is_some()
+            };
+
+            if per_round.proposal.as_ref().is_some_and(|p| !is_known(p)) {
+                self.quarantined.extend(per_round.proposal.take());
+                per_round.received.clear();
+            }
+        }
+
+        self.per_round
+            .retain(|_, per_round| per_round.proposal.is_some());
+
+        self.validator_set = new_set;
+    }
+
+    /// Return the proposals from validators which are no longer part of the validator set.
+    pub fn quarantined(&self) -> &[(SignedProposal<Ctx>, Validity)] {
+        &self.quarantined
     }
 
     /// Return the proposal and validity for the round.
@@ -112,21 +203,64 @@ where
         &self,
         round: Round,
     ) -> Option<&(SignedProposal<Ctx>, Validity)> {
+        self.per_round.get(&round).and_then(PerRound::get_proposal)
+    }
+
+    /// Return all the proposals received for the value of the round, in the order they were received.
+    ///
+    /// There can be more than one when the proposer re-proposed its value with a different POL round.
+    /// Conflicting proposals, ie. for a different value, are not included and are recorded as evidence instead.
+    pub fn get_proposals_for_round(&self, round: Round) -> &[SignedProposal<Ctx>] {
         self.per_round
             .get(&round)
-            .and_then(|round_info| round_info.proposal.as_ref())
+            .map_or(&[], |per_round| per_round.received.as_slice())
     }
 
-    /// Return the evidence of equivocation.
+    /// Set the validity of a proposal which was stored with [`Validity::Unknown`].
+    ///
+    /// Since the validity is a property of the value, it applies to the latest proposal stored
+    /// for the round as long as it is for the same value, even if its POL round differs.
+    ///
+    /// Returns `false` if no such proposal is stored, or if its validity is already known.
+    pub fn resolve_validity(&mut self, proposal: &SignedProposal<Ctx>, validity: Validity) -> bool {
+        let Some((existing, existing_validity)) = self
+            .per_round
+            .get_mut(&proposal.round())
+            .and_then(|per_round| per_round.proposal.as_mut())
+        else {
+            return false;
+        };
+
+        if existing.value().id() != proposal.value().id() || !existing_validity.is_unknown() {
+            return false;
+        }
+
+        *existing_validity = validity;
+
+        if validity == Validity::Invalid {
+            self.evidence.add_invalid_proposal(existing.clone());
+        }
+
+        true
+    }
+
+    /// Return the evidence of misbehavior, ie. equivocating and invalid proposals.
     pub fn evidence(&self) -> &EvidenceMap<Ctx> {
         &self.evidence
     }
 
     /// Store a proposal, checking for conflicts and storing evidence of equivocation if necessary.
     ///
+    /// Proposals whose value is invalid are stored as well, tagged with their validity,
+    /// and are recorded as evidence of an invalid proposal.
+    ///
     /// # Precondition
     /// - The given proposal must have been proposed by the expected proposer at the proposal's height and round.
     pub fn store_proposal(&mut self, proposal: SignedProposal<Ctx>, validity: Validity) {
+        if validity == Validity::Invalid {
+            self.evidence.add_invalid_proposal(proposal.clone());
+        }
+
         let per_round = self.per_round.entry(proposal.round()).or_default();
 
         match per_round.add(proposal, validity) {
@@ -155,7 +289,7 @@ where
     }
 }
 
-/// Keeps track of evidence of equivocation.
+/// Keeps track of evidence of misbehavior by proposers, ie. equivocating and invalid proposals.
 #[derive_where(Clone, Debug, Default)]
 pub struct EvidenceMap<Ctx>
 where
@@ -163,6 +297,9 @@ where
 {
     #[allow(clippy::type_complexity)]
     map: BTreeMap<Ctx::Address, Vec<(SignedProposal<Ctx>, SignedProposal<Ctx>)>>,
+
+    /// Proposals whose value was deemed invalid, by proposer.
+    invalid_proposals: BTreeMap<Ctx::Address, Vec<SignedProposal<Ctx>>>,
 }
 
 impl<Ctx> EvidenceMap<Ctx>
@@ -174,9 +311,9 @@ where
         Self::default()
     }
 
-    /// Return whether or not there is any evidence of equivocation.
+    /// Return whether or not there is any evidence of misbehavior.
     pub fn is_empty(&self) -> bool {
-        self.map.is_empty()
+        self.map.is_empty() && self.invalid_proposals.is_empty()
     }
 
     /// Return the evidence of equivocation for a given address, if any.
@@ -187,6 +324,24 @@ where
         self.map.get(address)
     }
 
+    /// Return the proposals from the given validator whose value was deemed invalid, if any.
+    pub fn invalid_proposals(&self, address: &Ctx::Address) -> Option<&Vec<SignedProposal<Ctx>>> {
+        self.invalid_proposals.get(address)
+    }
+
+    /// Add evidence of an invalid proposal, ie. a properly signed proposal
+    /// from the proposer whose value was deemed invalid.
+    pub(crate) fn add_invalid_proposal(&mut self, proposal: SignedProposal<Ctx>) {
+        let proposals = self
+            .invalid_proposals
+            .entry(proposal.validator_address().clone())
+            .or_default();
+
+        if !proposals.contains(&proposal) {
+            proposals.push(proposal);
+        }
+    }
+
     /// Add evidence of equivocating proposals, ie. two proposals submitted by the same validator,
     /// but with different values but for the same height and round.
     ///
@@ -208,3 +363,200 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use malachitebft_test::utils::validators::make_validators;
+    use malachitebft_test::{
+        Height, Proposal as TestProposal, Signature, TestContext, ValidatorSet as TestValidatorSet,
+        Value,
+    };
+
+    fn signed_proposal(
+        round: Round,
+        value: Value,
+        address: malachitebft_test::Address,
+    ) -> SignedProposal<TestContext> {
+        signed_proposal_with_pol(round, value, Round::Nil, address)
+    }
+
+    fn signed_proposal_with_pol(
+        round: Round,
+        value: Value,
+        pol_round: Round,
+        address: malachitebft_test::Address,
+    ) -> SignedProposal<TestContext> {
+        SignedProposal::new(
+            TestProposal::new(Height::new(1), round, value, pol_round, address),
+            Signature::test(),
+        )
+    }
+
+    #[test]
+    fn update_validator_set_quarantines_unknown_proposers() {
+        let [(v1, _), (v2, _), (v3, _)] = make_validators([1, 1, 1]);
+
+        let old_set = TestValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+        let new_set = TestValidatorSet::new(vec![v2.clone(), v3.clone()]);
+
+        let mut keeper = ProposalKeeper::<TestContext>::new(old_set);
+
+        let from_v1 = signed_proposal(Round::new(0), Value::new(1), v1.address);
+        let from_v2 = signed_proposal(Round::new(1), Value::new(2), v2.address);
+
+        keeper.store_proposal(from_v1.clone(), Validity::Valid);
+        keeper.store_proposal(from_v2.clone(), Validity::Valid);
+
+        // Equivocation from v1 at round 0
+        let conflicting = signed_proposal(Round::new(0), Value::new(3), v1.address);
+        keeper.store_proposal(conflicting.clone(), Validity::Valid);
+
+        keeper.update_validator_set(new_set.clone());
+
+        assert_eq!(keeper.validator_set(), &new_set);
+        assert_eq!(keeper.quarantined(), &[(from_v1.clone(), Validity::Valid)]);
+
+        assert!(keeper
+            .get_proposal_and_validity_for_round(Round::new(0))
+            .is_none());
+
+        assert_eq!(
+            keeper.get_proposal_and_validity_for_round(Round::new(1)),
+            Some(&(from_v2, Validity::Valid))
+        );
+
+        // Evidence is retained
+        assert_eq!(
+            keeper.evidence().get(&v1.address),
+            Some(&vec![(from_v1, conflicting)])
+        );
+    }
+
+    #[test]
+    fn invalid_proposals_are_recorded_as_evidence() {
+        let [(v1, _), (v2, _)] = make_validators([1, 1]);
+
+        let validator_set = TestValidatorSet::new(vec![v1.clone(), v2.clone()]);
+        let mut keeper = ProposalKeeper::<TestContext>::new(validator_set);
+
+        let invalid = signed_proposal(Round::new(0), Value::new(1), v1.address);
+        keeper.store_proposal(invalid.clone(), Validity::Invalid);
+
+        // Validity only known once the value has been checked
+        let unknown = signed_proposal(Round::new(1), Value::new(2), v2.address);
+        keeper.store_proposal(unknown.clone(), Validity::Unknown);
+        assert_eq!(keeper.evidence().invalid_proposals(&v2.address), None);
+
+        assert!(keeper.resolve_validity(&unknown, Validity::Invalid));
+
+        assert_eq!(
+            keeper.evidence().invalid_proposals(&v1.address),
+            Some(&vec![invalid])
+        );
+        assert_eq!(
+            keeper.evidence().invalid_proposals(&v2.address),
+            Some(&vec![unknown])
+        );
+        assert_eq!(keeper.evidence().get(&v1.address), None);
+    }
+
+    #[test]
+    fn reproposal_with_different_pol_round_is_not_a_conflict() {
+        let [(v1, _), (v2, _)] = make_validators([1, 1]);
+
+        let validator_set = TestValidatorSet::new(vec![v1.clone(), v2.clone()]);
+        let mut keeper = ProposalKeeper::<TestContext>::new(validator_set);
+
+        let round = Round::new(1);
+        let first = signed_proposal_with_pol(round, Value::new(1), Round::Nil, v1.address);
+        let second = signed_proposal_with_pol(round, Value::new(1), Round::new(0), v1.address);
+
+        keeper.store_proposal(first.clone(), Validity::Valid);
+        keeper.store_proposal(second.clone(), Validity::Unknown);
+
+        // The latest proposal is stored, with the validity already known for its value
+        assert_eq!(
+            keeper.get_proposal_and_validity_for_round(round),
+            Some(&(second.clone(), Validity::Valid))
+        );
+
+        assert_eq!(
+            keeper.get_proposals_for_round(round),
+            &[first.clone(), second.clone()]
+        );
+        assert!(keeper.get_proposals_for_round(Round::new(0)).is_empty());
+        assert!(keeper.evidence().is_empty());
+
+        // A proposal for another value is a conflict with the first proposal received
+        let conflicting = signed_proposal(round, Value::new(2), v1.address);
+        keeper.store_proposal(conflicting.clone(), Validity::Valid);
+
+        assert_eq!(
+            keeper.evidence().get(&v1.address),
+            Some(&vec![(first.clone(), conflicting)])
+        );
+        assert_eq!(keeper.get_proposals_for_round(round), &[first, second]);
+    }
+
+    #[test]
+    fn reproposals_kept_for_a_round_are_bounded() {
+        let [(v1, _)] = make_validators([1]);
+
+        let validator_set = TestValidatorSet::new(vec![v1.clone()]);
+        let mut keeper = ProposalKeeper::<TestContext>::new(validator_set);
+
+        let round = Round::new(100);
+
+        // Re-proposing with the same POL round does not add a proposal
+        let first = signed_proposal_with_pol(round, Value::new(1), Round::Nil, v1.address);
+        keeper.store_proposal(first.clone(), Validity::Valid);
+        keeper.store_proposal(first.clone(), Validity::Valid);
+        assert_eq!(keeper.get_proposals_for_round(round), &[first]);
+
+        let mut last = None;
+
+        for pol_round in Round::range(Round::new(0), round) {
+            let proposal = signed_proposal_with_pol(round, Value::new(1), pol_round, v1.address);
+            keeper.store_proposal(proposal.clone(), Validity::Unknown);
+            last = Some(proposal);
+        }
+
+        assert_eq!(
+            keeper.get_proposals_for_round(round).len(),
+            MAX_PROPOSALS_PER_ROUND
+        );
+
+        // The latest proposal is still the one stored for the round
+        assert_eq!(
+            keeper.get_proposal_and_validity_for_round(round),
+            Some(&(last.unwrap(), Validity::Valid))
+        );
+        assert!(keeper.evidence().is_empty());
+    }
+
+    #[test]
+    fn validity_resolves_for_the_latest_proposal_of_the_value() {
+        let [(v1, _)] = make_validators([1]);
+
+        let validator_set = TestValidatorSet::new(vec![v1.clone()]);
+        let mut keeper = ProposalKeeper::<TestContext>::new(validator_set);
+
+        let round = Round::new(1);
+        let first = signed_proposal_with_pol(round, Value::new(1), Round::Nil, v1.address);
+        let second = signed_proposal_with_pol(round, Value::new(1), Round::new(0), v1.address);
+
+        keeper.store_proposal(first.clone(), Validity::Unknown);
+        keeper.store_proposal(second.clone(), Validity::Unknown);
+
+        // The value of the first proposal is validated after the re-proposal was received
+        assert!(keeper.resolve_validity(&first, Validity::Valid));
+        assert!(!keeper.resolve_validity(&second, Validity::Valid));
+
+        assert_eq!(
+            keeper.get_proposal_and_validity_for_round(round),
+            Some(&(second, Validity::Valid))
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! Pluggable proposer selection, which lets the application take its own state
+//! into account when selecting the proposer at a given height, eg. to skip jailed validators.
+//!
+//! When no [`ProposerSelector`] is set on the [`Driver`], the proposer is selected
+//! by [`Context::select_proposer`].
+
+use alloc::sync::Arc;
+
+use malachitebft_core_types::{Context, Round, Validator};
+
+use crate::Driver;
+
+/// The information available when selecting the proposer for a given height and round.
+///
+/// Further fields may be added in the future, hence this type can only be constructed
+/// outside of this crate with [`ProposerSelection::new`]. State specific to the application is expected to be held
+/// by the [`ProposerSelector`] itself, which can be swapped at every height.
+#[non_exhaustive]
+pub struct ProposerSelection<'a, Ctx>
+where
+    Ctx: Context,
+{
+    /// The height at which to select a proposer
+    pub height: Ctx::Height,
+
+    /// The round at which to select a proposer
+    pub round: Round,
+
+    /// The validator set at that height
+    pub validator_set: &'a Ctx::ValidatorSet,
+}
+
+impl<'a, Ctx> ProposerSelection<'a, Ctx>
+where
+    Ctx: Context,
+{
+    /// Create a new selection for the given height, round and validator set.
+    pub fn new(height: Ctx::Height, round: Round, validator_set: &'a Ctx::ValidatorSet) -> Self {
+        Self {
+            height,
+            round,
+            validator_set,
+        }
+    }
+}
+
+/// Selects the proposer for a given height and round.
+pub trait ProposerSelector<Ctx>
+where
+    Self: Send + Sync,
+    Ctx: Context,
+{
+    /// Select a proposer from the validator set of the given selection.
+    ///
+    /// # Important
+    /// This function must be deterministic!
+    /// For a given selection, all nodes must select the same proposer.
+    fn select_proposer<'a>(&self, selection: &ProposerSelection<'a, Ctx>) -> &'a Ctx::Validator;
+}
+
+/// A hook called with every proposer selected by a [`ProposerSelector`] set on the driver.
+///
+/// This is meant for tests, eg. to check that the drivers of all nodes
+/// select the same proposer for a given height and round.
+pub type ProposerSelectionHook<Ctx> =
+    Arc<dyn Fn(&ProposerSelection<'_, Ctx>, &<Ctx as Context>::Address) + Send + Sync>;
+
+impl<Ctx> Driver<Ctx>
+where
+    Ctx: Context,
+{
+    /// Set the proposer selector to use from now on, in place of [`Context::select_proposer`].
+    ///
+    /// This is meant to be called in between heights, before the driver
+    /// is started at the next height, so that all rounds of a height
+    /// use the same selector.
+    pub fn set_proposer_selector(&mut self, selector: Arc<dyn ProposerSelector<Ctx>>) {
+        debug_assert!(
+            self.round() == Round::Nil,
+            "proposer selector must only be changed in between heights"
+        );
+
+        self.proposer_selector = Some(selector);
+    }
+
+    /// Remove the proposer selector, falling back to [`Context::select_proposer`].
+    pub fn clear_proposer_selector(&mut self) {
+        self.proposer_selector = None;
+    }
+
+    /// Set a hook to be called with every proposer selected by the proposer selector.
+    pub fn set_proposer_selection_hook(&mut self, hook: ProposerSelectionHook<Ctx>) {
+        self.proposer_selection_hook = Some(hook);
+    }
+
+    /// Select the proposer for the given height and round in the current validator set,
+    /// using the proposer selector if one is set, or [`Context::select_proposer`] otherwise.
+    pub fn select_proposer(&self, height: Ctx::Height, round: Round) -> &Ctx::Validator {
+        let Some(selector) = &self.proposer_selector else {
+            return self
+                .ctx
+                .select_proposer(self.validator_set(), height, round);
+        };
+
+        let selection = ProposerSelection {
+            height,
+            round,
+            validator_set: self.validator_set(),
+        };
+
+        let proposer = selector.select_proposer(&selection);
+
+        if let Some(hook) = &self.proposer_selection_hook {
+            hook(&selection, proposer.address());
+        }
+
+        proposer
+    }
+}
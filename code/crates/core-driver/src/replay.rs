@@ -0,0 +1,118 @@
+//! Support for replaying the inputs accepted by the driver at the current height,
+//! eg. after a crash, from a write-ahead log.
+//!
+//! Once enabled with [`Driver::set_record_wal_entries`], every input which was
+//! successfully processed by the driver is recorded as a [`WalEntry`].
+//! Those entries are made of the same types as the driver [`Input`], and can therefore
+//! be persisted using the existing encoding for these types (eg. their Protobuf impls).
+//!
+//! Replaying the entries on a driver started at the same height reconstructs the exact
+//! same state, including the locked and valid values, as well as the set of votes that
+//! were already signed by this node.
+
+use alloc::vec::Vec;
+
+use malachitebft_core_types::{Context, Round, Vote, VoteType};
+
+use crate::{Driver, Error, Input, Output};
+
+/// An entry in the write-ahead log of the driver.
+pub type WalEntry<Ctx> = Input<Ctx>;
+
+impl<Ctx> Driver<Ctx>
+where
+    Ctx: Context,
+{
+    /// Return the entries to persist in the write-ahead log in order to be able
+    /// to reconstruct the current state of the driver with [`Driver::replay`].
+    ///
+    /// Always empty unless recording is enabled, see [`Driver::set_record_wal_entries`].
+    pub fn to_wal_entries(&self) -> Vec<WalEntry<Ctx>> {
+        self.wal_entries.clone().unwrap_or_default()
+    }
+
+    /// Replay the given write-ahead log entries, reconstructing the state
+    /// the driver was in when these entries were recorded.
+    ///
+    /// The outputs produced while replaying are discarded, as their side effects
+    /// are assumed to have already been performed before the crash.
+    /// The votes produced while replaying are however recorded as signed,
+    /// and the driver will therefore never emit another vote of the same type
+    /// for the same round at this height.
+    ///
+    /// # Precondition
+    /// - The driver must have just been created for, or moved to, the height of the entries.
+    pub fn replay(
+        &mut self,
+        entries: impl IntoIterator<Item = WalEntry<Ctx>>,
+    ) -> Result<(), Error<Ctx>> {
+        for entry in entries {
+            self.apply_replay(entry)?;
+        }
+
+        Ok(())
+    }
+
+    /// Process a single write-ahead log entry while replaying it,
+    /// suppressing the outputs which would have side effects on the network.
+    ///
+    /// The input goes through the same state transitions as with [`Driver::process`],
+    /// and is recorded in the write-ahead log again if recording is enabled. The following outputs are suppressed:
+    /// - [`Output::Propose`], as our proposal was already broadcast before the crash
+    /// - [`Output::Vote`], as our vote was already broadcast before the crash;
+    ///   the vote is nonetheless recorded as signed, see [`Driver::has_signed_vote`]
+    /// - [`Output::GetValue`], as the value we were asked to build leads to a proposal
+    ///
+    /// All other outputs are returned, in particular [`Output::Decide`],
+    /// so that a decision reached while replaying is not lost.
+    pub fn apply_replay(&mut self, entry: WalEntry<Ctx>) -> Result<Vec<Output<Ctx>>, Error<Ctx>> {
+        let mut outputs = self.process(entry)?;
+
+        outputs.retain(|output| {
+            !matches!(
+                output,
+                Output::Propose(_) | Output::Vote(_) | Output::GetValue(..)
+            )
+        });
+
+        Ok(outputs)
+    }
+
+    /// Return whether we have already signed a vote of the given type at the given round.
+    pub fn has_signed_vote(&self, round: Round, vote_type: VoteType) -> bool {
+        self.signed_votes.contains(&(round, vote_type))
+    }
+
+    /// Return the round and type of our own vote, if the input carries one.
+    pub(crate) fn own_vote(&self, input: &Input<Ctx>) -> Option<(Round, VoteType)> {
+        match input {
+            Input::Vote(vote) if vote.validator_address() == self.address() => {
+                Some((vote.round(), vote.vote_type()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Record our own vote as signed, if the input carried one,
+    /// and the input in the write-ahead log, if recording is enabled.
+    pub(crate) fn record_input(
+        &mut self,
+        signed_vote: Option<(Round, VoteType)>,
+        entry: Option<WalEntry<Ctx>>,
+    ) {
+        self.signed_votes.extend(signed_vote);
+
+        if let (Some(entries), Some(entry)) = (&mut self.wal_entries, entry) {
+            entries.push(entry);
+        }
+    }
+
+    /// Filter out the votes we have already signed at this height,
+    /// and record the remaining ones as signed.
+    pub(crate) fn filter_signed_votes(&mut self, outputs: &mut Vec<Output<Ctx>>) {
+        outputs.retain(|output| match output {
+            Output::Vote(vote) => self.signed_votes.insert((vote.round(), vote.vote_type())),
+            _ => true,
+        });
+    }
+}
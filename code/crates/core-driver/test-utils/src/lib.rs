@@ -1,9 +1,34 @@
 #![allow(clippy::needless_update)]
 
-use malachitebft_core_driver::{Input, Output};
+pub mod network;
+pub mod simulation;
+
+use std::collections::BTreeSet;
+
+use malachitebft_core_driver::{Driver, Input, Output};
 use malachitebft_core_state_machine::state::{RoundValue, State, Step};
-use malachitebft_core_types::{NilOrVal, Round, SignedProposal, SignedVote, Timeout, Validity};
-use malachitebft_test::{Address, Height, Proposal, Signature, TestContext, Value, Vote};
+use malachitebft_core_types::{
+    CommitCertificate, NilOrVal, Round, SignedProposal, SignedVote, Timeout, Validity,
+};
+use malachitebft_test::utils::validators::make_validators;
+use malachitebft_test::{
+    Address, Height, Proposal, Signature, TestContext, ValidatorSet, Value, Vote,
+};
+
+/// Setup the driver of the last of four validators with the same voting power, at height 1,
+/// returning it along with the addresses of the validators, in order.
+///
+/// The first validator is the proposer of round 0.
+pub fn setup_driver() -> (Driver<TestContext>, [Address; 4]) {
+    let [(v1, _sk1), (v2, _sk2), (v3, _sk3), (v4, sk4)] = make_validators([1, 1, 1, 1]);
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone(), v4.clone()]);
+
+    // We are v4, and v1 is the proposer
+    let ctx = TestContext::new(sk4);
+    let driver = Driver::new(ctx, Height::new(1), vs, v4.address, Default::default());
+
+    (driver, [v1.address, v2.address, v3.address, v4.address])
+}
 
 pub fn new_round_input(round: Round, proposer: Address) -> Input<TestContext> {
     Input::NewRound(Height::new(1), round, proposer)
@@ -34,6 +59,17 @@ pub fn proposal_input(
     Input::Proposal(SignedProposal::new(proposal, Signature::test()), validity)
 }
 
+pub fn proposal_validated_input(
+    round: Round,
+    value: Value,
+    locked_round: Round,
+    validity: Validity,
+    address: Address,
+) -> Input<TestContext> {
+    let proposal = Proposal::new(Height::new(1), round, value, locked_round, address);
+    Input::ProposalValidated(SignedProposal::new(proposal, Signature::test()), validity)
+}
+
 pub fn prevote_output(round: Round, value: Value, addr: &Address) -> Output<TestContext> {
     Output::Vote(Vote::new_prevote(
         Height::new(1),
@@ -71,6 +107,13 @@ pub fn prevote_nil_input(addr: &Address) -> Input<TestContext> {
     ))
 }
 
+pub fn prevote_nil_input_at(round: Round, addr: &Address) -> Input<TestContext> {
+    Input::Vote(SignedVote::new(
+        Vote::new_prevote(Height::new(1), round, NilOrVal::Nil, *addr),
+        Signature::test(),
+    ))
+}
+
 pub fn prevote_input_at(round: Round, value: Value, addr: &Address) -> Input<TestContext> {
     Input::Vote(SignedVote::new(
         Vote::new_prevote(Height::new(1), round, NilOrVal::Val(value.id()), *addr),
@@ -117,8 +160,37 @@ pub fn precommit_input_at(round: Round, value: Value, addr: &Address) -> Input<T
     ))
 }
 
-pub fn decide_output(round: Round, proposal: Proposal) -> Output<TestContext> {
-    Output::Decide(round, proposal)
+pub fn commit_certificate(
+    proposal: &Proposal,
+    signers: &[Address],
+) -> CommitCertificate<TestContext> {
+    // Order the precommits the same way the vote keeper does
+    let precommits: BTreeSet<_> = signers
+        .iter()
+        .map(|addr| {
+            SignedVote::new(
+                Vote::new_precommit(
+                    Height::new(1),
+                    proposal.round,
+                    NilOrVal::Val(proposal.value.id()),
+                    *addr,
+                ),
+                Signature::test(),
+            )
+        })
+        .collect();
+
+    CommitCertificate::new(
+        Height::new(1),
+        proposal.round,
+        proposal.value.id(),
+        precommits.into_iter().collect(),
+    )
+}
+
+pub fn decide_output(round: Round, proposal: Proposal, signers: &[Address]) -> Output<TestContext> {
+    let certificate = commit_certificate(&proposal, signers);
+    Output::Decide(round, proposal, certificate)
 }
 
 pub fn start_propose_timer_output(round: Round) -> Output<TestContext> {
@@ -0,0 +1,291 @@
+//! A deterministic network simulator, routing the proposals and votes emitted
+//! by the drivers of a set of validators with per-edge latency and random message loss.
+//!
+//! Time is virtual: a [`SimNetwork`] keeps a queue of pending deliveries and timeouts,
+//! ordered by the time at which they are due, and processes them one at a time.
+//! Message loss is drawn from an RNG seeded at construction, so that a run
+//! is fully determined by its seed and its configuration, and failures reproduce.
+//!
+//! Since nodes may miss messages altogether, every node periodically rebroadcasts
+//! the messages it sent in its current round, in the same way that consensus
+//! rebroadcasts its last vote when a step times out.
+//!
+//! Proposals are routed whole, since the driver is not concerned with proposal parts.
+//!
+//! ```rust,ignore
+//! let mut net = SimNetwork::new([1, 1, 1, 1], 42).with_drop_rate(0.2);
+//! net.run_until(Duration::from_secs(60));
+//!
+//! assert!(net.all_decided());
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use malachitebft_core_driver::{Input, Output};
+use malachitebft_core_types::{Round, Timeout, TimeoutKind};
+use malachitebft_test::utils::validators::make_validators;
+use malachitebft_test::{Height, TestContext, ValidatorSet, Value};
+
+use crate::simulation::{Message, Node};
+
+const DEFAULT_LATENCY: Duration = Duration::from_millis(100);
+const DEFAULT_REBROADCAST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Something due to happen at a given time in a [`SimNetwork`]
+#[derive(Clone, Debug)]
+enum Scheduled {
+    /// Deliver a message sent by a validator to another one
+    Deliver { to: usize, message: Box<Message> },
+
+    /// A timeout of a validator elapses
+    Timeout { node: usize, timeout: Timeout },
+
+    /// A validator rebroadcasts the messages it sent in its current round
+    Rebroadcast { node: usize },
+}
+
+/// Routes the messages of a set of drivers over a simulated network.
+pub struct SimNetwork {
+    nodes: Vec<Node>,
+    rng: StdRng,
+    now: Duration,
+    seq: u64,
+    queue: BTreeMap<(Duration, u64), Scheduled>,
+    drop_rate: f64,
+    latency: Duration,
+    edge_latencies: HashMap<(usize, usize), Duration>,
+    rebroadcast_interval: Duration,
+    started: bool,
+}
+
+impl SimNetwork {
+    /// Create a network of validators with the given voting powers, all running a driver,
+    /// whose randomness is derived from the given seed.
+    pub fn new<const N: usize>(voting_powers: [u64; N], seed: u64) -> Self {
+        let validators = make_validators(voting_powers);
+        let validator_set = ValidatorSet::new(validators.iter().map(|(v, _)| v.clone()));
+
+        let nodes = validators
+            .into_iter()
+            .enumerate()
+            .map(|(index, (_, private_key))| Node::new(index, private_key, validator_set.clone()))
+            .collect();
+
+        Self {
+            nodes,
+            rng: StdRng::seed_from_u64(seed),
+            now: Duration::ZERO,
+            seq: 0,
+            queue: BTreeMap::new(),
+            drop_rate: 0.0,
+            latency: DEFAULT_LATENCY,
+            edge_latencies: HashMap::new(),
+            rebroadcast_interval: DEFAULT_REBROADCAST_INTERVAL,
+            started: false,
+        }
+    }
+
+    /// Drop each message sent over any edge with the given probability.
+    pub fn with_drop_rate(mut self, drop_rate: f64) -> Self {
+        assert!(
+            (0.0..1.0).contains(&drop_rate),
+            "drop rate must be in [0, 1)"
+        );
+        self.drop_rate = drop_rate;
+        self
+    }
+
+    /// Deliver messages after the given latency, unless configured otherwise for an edge.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Deliver messages sent by validator `from` to validator `to` after the given latency.
+    pub fn with_edge_latency(mut self, from: usize, to: usize, latency: Duration) -> Self {
+        self.edge_latencies.insert((from, to), latency);
+        self
+    }
+
+    /// Rebroadcast the messages of the current round at the given interval.
+    pub fn with_rebroadcast_interval(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "rebroadcast interval must not be zero");
+        self.rebroadcast_interval = interval;
+        self
+    }
+
+    /// The current virtual time
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// The node of the validator at the given index
+    pub fn node(&self, index: usize) -> &Node {
+        &self.nodes[index]
+    }
+
+    /// All the nodes
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter()
+    }
+
+    /// Whether every node has decided
+    pub fn all_decided(&self) -> bool {
+        self.nodes.iter().all(|node| node.decision().is_some())
+    }
+
+    /// The value decided on by every node, if they all decided on the same value
+    pub fn decision(&self) -> Option<Value> {
+        let decision = self.nodes.first()?.decision()?;
+
+        self.nodes
+            .iter()
+            .all(|node| node.decided(decision))
+            .then_some(decision)
+    }
+
+    /// Start round 0 on every node if needed, then process the scheduled deliveries
+    /// and timeouts in order, until every node has decided or the given virtual time is reached.
+    ///
+    /// Returns whether every node has decided.
+    pub fn run_until(&mut self, deadline: Duration) -> bool {
+        if !self.started {
+            self.start();
+        }
+
+        while !self.all_decided() {
+            let Some(entry) = self.queue.first_entry() else {
+                break;
+            };
+
+            let (at, _) = *entry.key();
+            if at > deadline {
+                break;
+            }
+
+            let scheduled = entry.remove();
+            self.now = at;
+            self.handle(scheduled);
+        }
+
+        self.all_decided()
+    }
+
+    fn start(&mut self) {
+        self.started = true;
+
+        let round = Round::new(0);
+
+        for index in 0..self.nodes.len() {
+            let proposer = self.nodes[index]
+                .driver()
+                .select_proposer(Height::new(1), round)
+                .address;
+
+            self.process(index, Input::NewRound(Height::new(1), round, proposer));
+
+            let interval = self.rebroadcast_interval;
+            self.schedule(interval, Scheduled::Rebroadcast { node: index });
+        }
+    }
+
+    fn handle(&mut self, scheduled: Scheduled) {
+        match scheduled {
+            Scheduled::Deliver { to, message } => {
+                self.process(to, message.into_input());
+            }
+
+            Scheduled::Timeout { node, timeout } => {
+                self.process(node, Input::TimeoutElapsed(timeout));
+            }
+
+            Scheduled::Rebroadcast { node } => {
+                let round = self.nodes[node].driver().round();
+                let messages = self.nodes[node].messages_in(round);
+                self.broadcast(node, messages);
+
+                let interval = self.rebroadcast_interval;
+                self.schedule(interval, Scheduled::Rebroadcast { node });
+            }
+        }
+    }
+
+    /// Feed the given input to a node, then schedule the timeouts it asks for
+    /// and the delivery of the messages it sends.
+    ///
+    /// Nodes which have decided do not process any input anymore.
+    fn process(&mut self, index: usize, input: Input<TestContext>) {
+        let node = &mut self.nodes[index];
+
+        if node.decision().is_some() {
+            return;
+        }
+
+        let seen = node.outputs().len();
+        let messages = node.process(input);
+
+        let timeouts: Vec<_> = node.outputs()[seen..]
+            .iter()
+            .filter_map(|output| match output {
+                Output::ScheduleTimeout(timeout) => Some(*timeout),
+                _ => None,
+            })
+            .collect();
+
+        for timeout in timeouts {
+            let duration = timeout_duration(timeout);
+            self.schedule(
+                duration,
+                Scheduled::Timeout {
+                    node: index,
+                    timeout,
+                },
+            );
+        }
+
+        self.broadcast(index, messages);
+    }
+
+    /// Send the given messages to every other node, dropping each of them
+    /// with the configured probability.
+    fn broadcast(&mut self, from: usize, messages: Vec<Message>) {
+        for message in messages {
+            for to in (0..self.nodes.len()).filter(|&to| to != from) {
+                if self.rng.gen_bool(self.drop_rate) {
+                    continue;
+                }
+
+                let latency = self
+                    .edge_latencies
+                    .get(&(from, to))
+                    .copied()
+                    .unwrap_or(self.latency);
+
+                let message = Box::new(message.clone());
+                self.schedule(latency, Scheduled::Deliver { to, message });
+            }
+        }
+    }
+
+    fn schedule(&mut self, after: Duration, scheduled: Scheduled) {
+        // Entries due at the same time are processed in the order they were scheduled
+        self.seq += 1;
+        self.queue.insert((self.now + after, self.seq), scheduled);
+    }
+}
+
+/// The duration of a timeout, increasing with the round,
+/// with the same defaults as the consensus configuration.
+fn timeout_duration(timeout: Timeout) -> Duration {
+    let (base, delta) = match timeout.kind {
+        TimeoutKind::Propose => (Duration::from_secs(3), Duration::from_millis(500)),
+        _ => (Duration::from_secs(1), Duration::from_millis(500)),
+    };
+
+    let round = timeout.round.as_u32().unwrap_or(0);
+    base + delta * round
+}
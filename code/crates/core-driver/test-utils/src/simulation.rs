@@ -0,0 +1,472 @@
+//! A deterministic harness to run scripted scenarios against one or more drivers,
+//! without any actor or network.
+//!
+//! A [`Simulation`] runs one driver per validator (or only for some of them),
+//! delivers a schedule of [`Event`]s to every running driver, and cross-feeds
+//! the proposals and votes emitted by each driver to all the others,
+//! until no message is left in flight.
+//!
+//! Facts about the outcome of the scenario can then be asserted on each [`Node`],
+//! eg. [`Node::decided`], [`Node::locked_at`] or [`Node::prevoted_nil_in`].
+//!
+//! ```rust,ignore
+//! let mut sim = Simulation::new([1, 1, 1, 1]);
+//! sim.start();
+//!
+//! let value = sim.value_of(sim.proposer(Round::new(0)));
+//! assert!(sim.nodes().all(|node| node.decided(value)));
+//! ```
+
+use std::collections::VecDeque;
+
+use malachitebft_core_driver::{Driver, Input, Output};
+use malachitebft_core_state_machine::state::RoundValue;
+use malachitebft_core_types::{
+    Context, NilOrVal, Round, SignedProposal, SignedVote, Timeout, TimeoutKind, Validity,
+    Vote as _, VoteType,
+};
+use malachitebft_test::utils::validators::make_validators;
+use malachitebft_test::{
+    Address, Height, PrivateKey, Proposal, Signature, TestContext, Validator, ValidatorSet, Value,
+    Vote,
+};
+
+/// An event in the schedule of a [`Simulation`].
+///
+/// Validators are referred to by their index in the voting powers
+/// the simulation was created with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A proposal from the given validator is received
+    Proposal {
+        from: usize,
+        round: Round,
+        value: Value,
+        pol_round: Round,
+        validity: Validity,
+    },
+
+    /// A vote from the given validator is received, for a value or for nil if `value` is `None`
+    Vote {
+        from: usize,
+        round: Round,
+        vote_type: VoteType,
+        value: Option<Value>,
+    },
+
+    /// A timeout elapses
+    Timeout(TimeoutKind, Round),
+}
+
+/// A valid proposal for the given value, without POL round.
+pub fn proposal(from: usize, round: Round, value: Value) -> Event {
+    proposal_with_pol(from, round, value, Round::Nil)
+}
+
+/// A valid proposal for the given value, with the given POL round.
+pub fn proposal_with_pol(from: usize, round: Round, value: Value, pol_round: Round) -> Event {
+    Event::Proposal {
+        from,
+        round,
+        value,
+        pol_round,
+        validity: Validity::Valid,
+    }
+}
+
+/// An invalid proposal for the given value, without POL round.
+pub fn invalid_proposal(from: usize, round: Round, value: Value) -> Event {
+    Event::Proposal {
+        from,
+        round,
+        value,
+        pol_round: Round::Nil,
+        validity: Validity::Invalid,
+    }
+}
+
+/// A vote of the given type, for a value or for nil if `value` is `None`.
+pub fn vote(from: usize, round: Round, vote_type: VoteType, value: Option<Value>) -> Event {
+    Event::Vote {
+        from,
+        round,
+        vote_type,
+        value,
+    }
+}
+
+/// A prevote for the given value.
+pub fn prevote(from: usize, round: Round, value: Value) -> Event {
+    vote(from, round, VoteType::Prevote, Some(value))
+}
+
+/// A prevote for nil.
+pub fn prevote_nil(from: usize, round: Round) -> Event {
+    vote(from, round, VoteType::Prevote, None)
+}
+
+/// A precommit for the given value.
+pub fn precommit(from: usize, round: Round, value: Value) -> Event {
+    vote(from, round, VoteType::Precommit, Some(value))
+}
+
+/// A precommit for nil.
+pub fn precommit_nil(from: usize, round: Round) -> Event {
+    vote(from, round, VoteType::Precommit, None)
+}
+
+/// The given timeout elapses.
+pub fn timeout(kind: TimeoutKind, round: Round) -> Event {
+    Event::Timeout(kind, round)
+}
+
+/// A message broadcast by a driver to the other validators.
+#[derive(Clone, Debug)]
+pub(crate) enum Message {
+    Proposal(SignedProposal<TestContext>),
+    Vote(SignedVote<TestContext>),
+}
+
+impl Message {
+    pub(crate) fn into_input(self) -> Input<TestContext> {
+        match self {
+            Message::Proposal(proposal) => Input::Proposal(proposal, Validity::Valid),
+            Message::Vote(vote) => Input::Vote(vote),
+        }
+    }
+}
+
+/// A validator running a driver in a [`Simulation`].
+pub struct Node {
+    index: usize,
+    value: Value,
+    driver: Driver<TestContext>,
+    outputs: Vec<Output<TestContext>>,
+}
+
+impl Node {
+    pub(crate) fn new(index: usize, private_key: PrivateKey, validator_set: ValidatorSet) -> Self {
+        let address = Address::from_public_key(&private_key.public_key());
+        let ctx = TestContext::new(private_key);
+        let driver = Driver::new(
+            ctx,
+            Height::new(1),
+            validator_set,
+            address,
+            Default::default(),
+        );
+
+        Self {
+            index,
+            value: Value::new(index as u64 + 1),
+            driver,
+            outputs: Vec::new(),
+        }
+    }
+
+    /// The index of this validator in the simulation
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The value proposed by this validator when it is the proposer
+    pub fn value(&self) -> Value {
+        self.value
+    }
+
+    /// The driver of this validator
+    pub fn driver(&self) -> &Driver<TestContext> {
+        &self.driver
+    }
+
+    /// All the outputs of the driver so far, in order
+    pub fn outputs(&self) -> &[Output<TestContext>] {
+        &self.outputs
+    }
+
+    /// The value decided on, if any
+    pub fn decision(&self) -> Option<Value> {
+        self.outputs.iter().find_map(|output| match output {
+            Output::Decide(_, proposal, _) => Some(proposal.value),
+            _ => None,
+        })
+    }
+
+    /// Whether the given value was decided on
+    pub fn decided(&self, value: Value) -> bool {
+        self.decision() == Some(value)
+    }
+
+    /// The value this validator is currently locked on, if any
+    pub fn locked(&self) -> Option<&RoundValue<Value>> {
+        self.driver.round_state().locked.as_ref()
+    }
+
+    /// Whether this validator is currently locked on a value at the given round
+    pub fn locked_at(&self, round: Round) -> bool {
+        self.locked().is_some_and(|locked| locked.round == round)
+    }
+
+    /// Whether this validator voted for the given value, or for nil if `value` is `None`
+    pub fn voted(&self, round: Round, vote_type: VoteType, value: Option<Value>) -> bool {
+        let value = match value {
+            Some(value) => NilOrVal::Val(value.id()),
+            None => NilOrVal::Nil,
+        };
+
+        self.outputs.iter().any(|output| {
+            matches!(output, Output::Vote(vote)
+                if vote.round() == round && vote.vote_type() == vote_type && vote.value() == &value)
+        })
+    }
+
+    /// Whether this validator prevoted for the given value in the given round
+    pub fn prevoted_in(&self, round: Round, value: Value) -> bool {
+        self.voted(round, VoteType::Prevote, Some(value))
+    }
+
+    /// Whether this validator prevoted for nil in the given round
+    pub fn prevoted_nil_in(&self, round: Round) -> bool {
+        self.voted(round, VoteType::Prevote, None)
+    }
+
+    /// Whether this validator precommitted for the given value in the given round
+    pub fn precommitted_in(&self, round: Round, value: Value) -> bool {
+        self.voted(round, VoteType::Precommit, Some(value))
+    }
+
+    /// Whether this validator precommitted for nil in the given round
+    pub fn precommitted_nil_in(&self, round: Round) -> bool {
+        self.voted(round, VoteType::Precommit, None)
+    }
+
+    /// Process the given input, and the inputs resulting from it,
+    /// returning the messages to broadcast to the other validators.
+    ///
+    /// As in consensus, our own proposals and votes are fed back to the driver,
+    /// and we propose our value whenever the driver asks for one.
+    pub(crate) fn process(&mut self, input: Input<TestContext>) -> Vec<Message> {
+        let mut inputs = VecDeque::from([input]);
+        let mut messages = Vec::new();
+
+        while let Some(input) = inputs.pop_front() {
+            let outputs = self.driver.process(input.clone()).unwrap_or_else(|e| {
+                panic!("validator {} failed to process {input:?}: {e}", self.index)
+            });
+
+            for output in outputs {
+                match &output {
+                    Output::NewRound(height, round) => {
+                        let proposer = self.driver.select_proposer(*height, *round).address;
+                        inputs.push_back(Input::NewRound(*height, *round, proposer));
+                    }
+
+                    Output::GetValue(_, round, _) => {
+                        inputs.push_back(Input::ProposeValue(*round, self.value));
+                    }
+
+                    Output::Propose(proposal) => {
+                        let proposal = SignedProposal::new(proposal.clone(), Signature::test());
+                        inputs.push_back(Input::Proposal(proposal.clone(), Validity::Valid));
+                        messages.push(Message::Proposal(proposal));
+                    }
+
+                    Output::Vote(vote) => {
+                        let vote = SignedVote::new(vote.clone(), Signature::test());
+                        inputs.push_back(Input::Vote(vote.clone()));
+                        messages.push(Message::Vote(vote));
+                    }
+
+                    Output::Decide(..) | Output::ScheduleTimeout(_) => {}
+                }
+
+                self.outputs.push(output);
+            }
+        }
+
+        messages
+    }
+
+    /// The proposal and votes this validator broadcast in the given round
+    pub(crate) fn messages_in(&self, round: Round) -> Vec<Message> {
+        self.outputs
+            .iter()
+            .filter_map(|output| match output {
+                Output::Propose(proposal) if proposal.round == round => Some(Message::Proposal(
+                    SignedProposal::new(proposal.clone(), Signature::test()),
+                )),
+                Output::Vote(vote) if vote.round() == round => Some(Message::Vote(
+                    SignedVote::new(vote.clone(), Signature::test()),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Runs a schedule of events against the drivers of some or all validators,
+/// cross-feeding the messages they emit to each other.
+pub struct Simulation {
+    ctx: TestContext,
+    validators: Vec<Validator>,
+    validator_set: ValidatorSet,
+    nodes: Vec<Node>,
+}
+
+impl Simulation {
+    /// Create a simulation with validators of the given voting powers, all running a driver.
+    pub fn new<const N: usize>(voting_powers: [u64; N]) -> Self {
+        Self::with_nodes(voting_powers, 0..N)
+    }
+
+    /// Create a simulation with validators of the given voting powers,
+    /// in which only the validators at the given indices run a driver.
+    ///
+    /// The proposals and votes of the other validators are expected to be scheduled as events.
+    pub fn with_nodes<const N: usize>(
+        voting_powers: [u64; N],
+        running: impl IntoIterator<Item = usize>,
+    ) -> Self {
+        let validators = make_validators(voting_powers);
+        let validator_set = ValidatorSet::new(validators.iter().map(|(v, _)| v.clone()));
+
+        let mut running: Vec<_> = running.into_iter().collect();
+        running.sort_unstable();
+        running.dedup();
+
+        let nodes = running
+            .into_iter()
+            .map(|index| Node::new(index, validators[index].1.clone(), validator_set.clone()))
+            .collect();
+
+        Self {
+            ctx: TestContext::new(validators[0].1.clone()),
+            validators: validators.into_iter().map(|(v, _)| v).collect(),
+            validator_set,
+            nodes,
+        }
+    }
+
+    /// The address of the validator at the given index
+    pub fn address(&self, index: usize) -> Address {
+        self.validators[index].address
+    }
+
+    /// The index of the proposer for the given round
+    pub fn proposer(&self, round: Round) -> usize {
+        let proposer = self
+            .ctx
+            .select_proposer(&self.validator_set, Height::new(1), round)
+            .address;
+
+        self.index_of(&proposer)
+    }
+
+    /// The value proposed by the validator at the given index when it is the proposer
+    pub fn value_of(&self, index: usize) -> Value {
+        Value::new(index as u64 + 1)
+    }
+
+    /// The running node of the validator at the given index
+    ///
+    /// # Panics
+    /// If that validator does not run a driver in this simulation
+    pub fn node(&self, index: usize) -> &Node {
+        self.nodes
+            .iter()
+            .find(|node| node.index == index)
+            .unwrap_or_else(|| panic!("validator {index} does not run a driver"))
+    }
+
+    /// All the running nodes
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter()
+    }
+
+    /// Start round 0 on every running node, and run until no message is left in flight.
+    pub fn start(&mut self) -> &mut Self {
+        let proposer = self.address(self.proposer(Round::new(0)));
+        self.deliver(Input::NewRound(Height::new(1), Round::new(0), proposer));
+        self
+    }
+
+    /// Deliver the given events in order to every running node,
+    /// running until no message is left in flight after each event.
+    pub fn run(&mut self, events: impl IntoIterator<Item = Event>) -> &mut Self {
+        for event in events {
+            let input = self.input_for(event);
+            self.deliver(input);
+        }
+
+        self
+    }
+
+    fn index_of(&self, address: &Address) -> usize {
+        self.validators
+            .iter()
+            .position(|v| &v.address == address)
+            .expect("validator is in the validator set")
+    }
+
+    fn input_for(&self, event: Event) -> Input<TestContext> {
+        match event {
+            Event::Proposal {
+                from,
+                round,
+                value,
+                pol_round,
+                validity,
+            } => {
+                let proposal =
+                    Proposal::new(Height::new(1), round, value, pol_round, self.address(from));
+
+                Input::Proposal(SignedProposal::new(proposal, Signature::test()), validity)
+            }
+
+            Event::Vote {
+                from,
+                round,
+                vote_type,
+                value,
+            } => {
+                let value = match value {
+                    Some(value) => NilOrVal::Val(value.id()),
+                    None => NilOrVal::Nil,
+                };
+
+                let vote = match vote_type {
+                    VoteType::Prevote => {
+                        Vote::new_prevote(Height::new(1), round, value, self.address(from))
+                    }
+                    VoteType::Precommit => {
+                        Vote::new_precommit(Height::new(1), round, value, self.address(from))
+                    }
+                };
+
+                Input::Vote(SignedVote::new(vote, Signature::test()))
+            }
+
+            Event::Timeout(kind, round) => Input::TimeoutElapsed(Timeout::new(round, kind)),
+        }
+    }
+
+    /// Deliver the given input to every running node, then cross-feed the messages
+    /// they emit to all the other running nodes, in order, until none is left.
+    fn deliver(&mut self, input: Input<TestContext>) {
+        let mut in_flight = VecDeque::new();
+
+        for node in &mut self.nodes {
+            for message in node.process(input.clone()) {
+                in_flight.push_back((node.index, message));
+            }
+        }
+
+        while let Some((from, message)) = in_flight.pop_front() {
+            for node in self.nodes.iter_mut().filter(|node| node.index != from) {
+                for reply in node.process(message.clone().into_input()) {
+                    in_flight.push_back((node.index, reply));
+                }
+            }
+        }
+    }
+}
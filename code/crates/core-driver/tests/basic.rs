@@ -6,13 +6,18 @@ use malachitebft_core_state_machine::state::{RoundValue, State, Step};
 use malachitebft_core_types::{
     NilOrVal, Round, SignedProposal, SignedVote, Timeout, TimeoutKind, Validity,
 };
+use malachitebft_core_votekeeper::keeper::VoteError;
 use malachitebft_test::proposer_selector::{FixedProposer, ProposerSelector, RotateProposer};
 use malachitebft_test::utils::validators::make_validators;
 use malachitebft_test::{
     Address, Height, Proposal, Signature, TestContext, ValidatorSet, Value, ValueId, Vote,
 };
 
-use informalsystems_malachitebft_core_driver::{Driver, Error, Input, Output};
+use malachitebft_core_driver_test_utils::decide_output;
+
+use informalsystems_malachitebft_core_driver::{
+    Driver, Error, ErrorKind, Input, Output, RecordProposalError,
+};
 
 pub struct TestStep {
     desc: &'static str,
@@ -75,7 +80,7 @@ pub fn output_to_input(
             Validity::Valid,
         )),
         Output::Vote(v) => Some(Input::Vote(SignedVote::new(v, Signature::test()))),
-        Output::Decide(_, _) => None,
+        Output::Decide(_, _, _) => None,
         Output::ScheduleTimeout(_) => None,
         Output::GetValue(_, _, _) => None,
     }
@@ -258,7 +263,11 @@ fn driver_steps_proposer() {
                 NilOrVal::Val(value.id()),
                 v3.address,
             ))),
-            expected_outputs: vec![Output::Decide(Round::new(0), proposal.message)],
+            expected_outputs: vec![decide_output(
+                Round::new(0),
+                proposal.message.clone(),
+                &[my_addr, v2.address, v3.address],
+            )],
             expected_round: Round::new(0),
             new_state: State {
                 height: Height::new(1),
@@ -500,7 +509,11 @@ fn driver_steps_not_proposer_valid() {
                 NilOrVal::Val(value.id()),
                 v3.address,
             ))),
-            expected_outputs: vec![Output::Decide(Round::new(0), proposal.message)],
+            expected_outputs: vec![decide_output(
+                Round::new(0),
+                proposal.message.clone(),
+                &[v1.address, v2.address, v3.address],
+            )],
             expected_round: Round::new(0),
             new_state: State {
                 height: Height::new(1),
@@ -1014,9 +1027,297 @@ fn driver_steps_validator_not_found() {
         v2.address,
     )));
 
+    assert_eq!(
+        output,
+        Err(Error::InvalidVote(VoteError::UnknownValidator(v2.address)))
+    );
+}
+
+#[test]
+fn driver_steps_propose_value_wrong_step() {
+    let value = Value::new(9999);
+
+    let [(v1, sk1), (v2, _sk2), (v3, _sk3)] = make_validators([1, 2, 3]);
+    let (my_sk, my_addr) = (sk1, v1.address);
+
+    let height = Height::new(1);
+    let ctx = TestContext::new(my_sk.clone());
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+    let mut driver = Driver::new(ctx, height, vs, my_addr, Default::default());
+
+    driver
+        .process(Input::NewRound(Height::new(1), Round::new(0), my_addr))
+        .expect("execute succeeded");
+
+    // Value for another round
+    let output = driver.process(Input::ProposeValue(Round::new(1), value));
+    assert_eq!(
+        output,
+        Err(Error::WrongStep {
+            round: Round::new(1),
+            current_round: Round::new(0),
+            step: Step::Propose,
+        })
+    );
+
+    // Timeout propose, we move to prevote step
+    driver
+        .process(Input::TimeoutElapsed(Timeout::propose(Round::new(0))))
+        .expect("execute succeeded");
+
+    // Value for the current round, but too late
+    let output = driver.process(Input::ProposeValue(Round::new(0), value));
+    assert_eq!(
+        output,
+        Err(Error::WrongStep {
+            round: Round::new(0),
+            current_round: Round::new(0),
+            step: Step::Prevote,
+        })
+    );
+}
+
+#[test]
+fn driver_steps_self_equivocation() {
+    let value = Value::new(9999);
+
+    let [(v1, _sk1), (v2, _sk2), (v3, sk3)] = make_validators([1, 2, 3]);
+    let (my_sk, my_addr) = (sk3, v3.address);
+
+    let height = Height::new(1);
+    let ctx = TestContext::new(my_sk.clone());
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+    let mut driver = Driver::new(ctx, height, vs, my_addr, Default::default());
+
+    driver
+        .process(Input::NewRound(Height::new(1), Round::new(0), v1.address))
+        .expect("execute succeeded");
+
+    driver
+        .process(Input::Vote(new_signed_prevote(
+            Height::new(1),
+            Round::new(0),
+            NilOrVal::Nil,
+            my_addr,
+        )))
+        .expect("execute succeeded");
+
+    // A prevote for a value signed by us at the same round conflicts with our nil prevote
+    let output = driver.process(Input::Vote(new_signed_prevote(
+        Height::new(1),
+        Round::new(0),
+        NilOrVal::Val(value.id()),
+        my_addr,
+    )));
+
+    assert_eq!(
+        output,
+        Err(Error::SelfEquivocation(Height::new(1), Round::new(0)))
+    );
+}
+
+#[test]
+fn driver_steps_proposal_from_unknown_validator() {
+    let value = Value::new(9999);
+
+    let [(v1, _sk1), (v2, _sk2), (v3, sk3)] = make_validators([1, 2, 3]);
+    let (my_sk, my_addr) = (sk3, v3.address);
+
+    let height = Height::new(1);
+    let ctx = TestContext::new(my_sk.clone());
+
+    // We omit v2 from the validator set
+    let vs = ValidatorSet::new(vec![v1.clone(), v3.clone()]);
+
+    let mut driver = Driver::new(ctx, height, vs, my_addr, Default::default());
+
+    driver
+        .process(Input::NewRound(Height::new(1), Round::new(0), v1.address))
+        .expect("execute succeeded");
+
+    let proposal =
+        new_signed_proposal(Height::new(1), Round::new(0), value, Round::Nil, v2.address);
+
+    let output = driver.process(Input::Proposal(proposal, Validity::Valid));
     assert_eq!(output, Err(Error::ValidatorNotFound(v2.address)));
 }
 
+#[test]
+fn driver_steps_proposal_from_non_proposer() {
+    let value = Value::new(9999);
+
+    let [(v1, _sk1), (v2, _sk2), (v3, sk3)] = make_validators([1, 2, 3]);
+    let (my_sk, my_addr) = (sk3, v3.address);
+
+    let height = Height::new(1);
+    let ctx = TestContext::new(my_sk.clone());
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+    let mut driver = Driver::new(ctx, height, vs, my_addr, Default::default());
+
+    // Proposer is v1
+    driver
+        .process(Input::NewRound(Height::new(1), Round::new(0), v1.address))
+        .expect("execute succeeded");
+
+    let proposal =
+        new_signed_proposal(Height::new(1), Round::new(0), value, Round::Nil, v2.address);
+
+    let output = driver.process(Input::Proposal(proposal, Validity::Valid));
+    assert_eq!(
+        output,
+        Err(Error::ProposerMismatch {
+            expected: v1.address,
+            actual: v2.address,
+        })
+    );
+
+    // The proposal was not recorded, we are still waiting for the one of the proposer
+    assert_eq!(driver.step(), Step::Propose);
+    assert!(driver
+        .proposals()
+        .get_proposal_and_validity_for_round(Round::new(0))
+        .is_none());
+}
+
+#[test]
+fn driver_steps_past_round() {
+    let [(v1, _sk1), (v2, _sk2), (v3, sk3)] = make_validators([1, 2, 3]);
+    let (my_sk, my_addr) = (sk3, v3.address);
+
+    let height = Height::new(1);
+    let ctx = TestContext::new(my_sk.clone());
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+    let mut driver = Driver::new(ctx, height, vs, my_addr, Default::default());
+
+    driver
+        .process(Input::NewRound(Height::new(1), Round::new(1), v2.address))
+        .expect("execute succeeded");
+
+    let output = driver.process(Input::NewRound(Height::new(1), Round::new(0), v1.address));
+    assert_eq!(
+        output,
+        Err(Error::PastRound {
+            current: Round::new(1),
+            received: Round::new(0),
+        })
+    );
+    assert_eq!(driver.round(), Round::new(1));
+}
+
+#[test]
+fn driver_error_kinds() {
+    let value = Value::new(9999);
+
+    let [(v1, _sk1), (v2, _sk2), (v3, sk3)] = make_validators([1, 2, 3]);
+    let (my_sk, my_addr) = (sk3, v3.address);
+
+    let height = Height::new(1);
+    let ctx = TestContext::new(my_sk.clone());
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+    let mut driver = Driver::new(ctx, height, vs, my_addr, Default::default());
+
+    driver
+        .process(Input::NewRound(Height::new(1), Round::new(1), v1.address))
+        .expect("execute succeeded");
+
+    let prevote = new_signed_prevote(
+        Height::new(2),
+        Round::new(1),
+        NilOrVal::Val(value.id()),
+        v2.address,
+    );
+
+    let error = driver.process(Input::Vote(prevote)).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::WrongHeight);
+    assert!(!error.is_protocol_violation());
+
+    let error = driver
+        .process(Input::NewRound(Height::new(1), Round::new(0), v1.address))
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::PastRound);
+    assert!(!error.is_protocol_violation());
+
+    let proposal =
+        new_signed_proposal(Height::new(1), Round::new(1), value, Round::Nil, v2.address);
+
+    let error = driver
+        .process(Input::Proposal(proposal, Validity::Valid))
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Invalid);
+    assert!(error.is_protocol_violation());
+}
+
+#[test]
+fn driver_steps_duplicate_vote_is_not_an_error() {
+    let value = Value::new(9999);
+
+    let [(v1, _sk1), (v2, _sk2), (v3, sk3)] = make_validators([1, 2, 3]);
+    let (my_sk, my_addr) = (sk3, v3.address);
+
+    let height = Height::new(1);
+    let ctx = TestContext::new(my_sk.clone());
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+    let mut driver = Driver::new(ctx, height, vs, my_addr, Default::default());
+
+    driver
+        .process(Input::NewRound(Height::new(1), Round::new(0), v1.address))
+        .expect("execute succeeded");
+
+    let prevote = new_signed_prevote(
+        Height::new(1),
+        Round::new(0),
+        NilOrVal::Val(value.id()),
+        v2.address,
+    );
+
+    for _ in 0..2 {
+        let output = driver.process(Input::Vote(prevote.clone()));
+        assert_eq!(output, Ok(vec![]));
+    }
+}
+
+#[test]
+fn record_proposal_error_display() {
+    let [(v1, _sk1), (v2, _sk2)] = make_validators([1, 1]);
+
+    let existing = new_signed_proposal(
+        Height::new(1),
+        Round::new(2),
+        Value::new(1),
+        Round::Nil,
+        v1.address,
+    );
+
+    let conflicting = new_signed_proposal(
+        Height::new(1),
+        Round::new(2),
+        Value::new(2),
+        Round::Nil,
+        v2.address,
+    );
+
+    let error: Box<dyn core::error::Error> = Box::new(
+        RecordProposalError::<TestContext>::InvalidConflictingProposal {
+            existing,
+            conflicting,
+        },
+    );
+
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "Invalid conflicting proposal at round 2 from validator {}, existing proposal is from validator {}",
+            v2.address, v1.address
+        )
+    );
+}
+
 #[test]
 fn driver_steps_skip_round_skip_threshold() {
     let value = Value::new(9999);
@@ -1222,6 +1523,275 @@ fn driver_steps_skip_round_quorum_threshold() {
     run_steps(&mut driver, steps, sel.as_ref(), &vs);
 }
 
+#[test]
+fn driver_steps_skip_round_on_future_proposal() {
+    let value = Value::new(9999);
+
+    let sel = Arc::new(RotateProposer);
+
+    let validators = make_validators([1, 1, 1]);
+    let vs = ValidatorSet::new(validators.iter().map(|(v, _)| v.clone()));
+
+    let height = Height::new(1);
+
+    // With three validators, the proposer of round 3 is also the proposer of round 0
+    let proposer = sel.select_proposer(height, Round::new(0), &vs);
+    assert_eq!(proposer, sel.select_proposer(height, Round::new(3), &vs));
+
+    let mut others = validators.iter().filter(|(v, _)| v.address != proposer);
+    let (other, _) = others.next().unwrap();
+    let (me, my_sk) = others.next().unwrap();
+    let my_addr = me.address;
+
+    let ctx = TestContext::new(my_sk.clone());
+    let mut driver = Driver::new(ctx, height, vs.clone(), my_addr, Default::default());
+
+    let proposal = new_signed_proposal(height, Round::new(3), value, Round::Nil, proposer);
+
+    let steps = vec![
+        TestStep {
+            desc: "Start round 0, we are not the proposer",
+            input: Some(Input::NewRound(height, Round::new(0), proposer)),
+            expected_outputs: vec![Output::ScheduleTimeout(Timeout::propose(Round::new(0)))],
+            expected_round: Round::new(0),
+            new_state: State {
+                height,
+                round: Round::new(0),
+                step: Step::Propose,
+                ..Default::default()
+            },
+        },
+        TestStep {
+            desc: "Another validator prevotes in round 3, not enough to skip",
+            input: Some(Input::Vote(new_signed_prevote(
+                height,
+                Round::new(3),
+                NilOrVal::Val(value.id()),
+                other.address,
+            ))),
+            expected_outputs: vec![],
+            expected_round: Round::new(0),
+            new_state: State {
+                height,
+                round: Round::new(0),
+                step: Step::Propose,
+                ..Default::default()
+            },
+        },
+        TestStep {
+            desc: "Receive the proposal for round 3, we get +1/3 messages from round 3",
+            input: Some(Input::Proposal(proposal, Validity::Valid)),
+            expected_outputs: vec![Output::NewRound(height, Round::new(3))],
+            expected_round: Round::new(3),
+            new_state: State {
+                height,
+                round: Round::new(3),
+                step: Step::Unstarted,
+                ..Default::default()
+            },
+        },
+        TestStep {
+            desc: "Start round 3, prevote the proposal we already have",
+            input: None,
+            expected_outputs: vec![
+                Output::ScheduleTimeout(Timeout::propose(Round::new(3))),
+                Output::Vote(Vote::new_prevote(
+                    height,
+                    Round::new(3),
+                    NilOrVal::Val(value.id()),
+                    my_addr,
+                )),
+            ],
+            expected_round: Round::new(3),
+            new_state: State {
+                height,
+                round: Round::new(3),
+                step: Step::Prevote,
+                ..Default::default()
+            },
+        },
+    ];
+
+    run_steps(&mut driver, steps, sel.as_ref(), &vs);
+}
+
+/// Steps up to the prevote timeout elapsing while we have a polka for `value` but no proposal,
+/// for a driver with the proposal grace period enabled, where we are not the proposer.
+fn proposal_grace_setup(value: Value) -> (Driver<TestContext>, Vec<TestStep>, Address, Address) {
+    let [(v1, _sk1), (v2, _sk2), (v3, _sk3), (v4, sk4)] = make_validators([1, 1, 1, 1]);
+
+    // Proposer is v1, so we, v4, are not the proposer
+    let (my_sk, my_addr) = (sk4, v4.address);
+
+    let height = Height::new(1);
+    let ctx = TestContext::new(my_sk.clone());
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone(), v4.clone()]);
+
+    let mut driver = Driver::new(ctx, height, vs, my_addr, Default::default());
+    driver.set_proposal_grace(true);
+
+    let prevote_value = |addr| {
+        Input::Vote(new_signed_prevote(
+            height,
+            Round::new(0),
+            NilOrVal::Val(value.id()),
+            addr,
+        ))
+    };
+
+    let in_step = |step| State::<TestContext> {
+        height,
+        round: Round::new(0),
+        step,
+        ..Default::default()
+    };
+
+    let steps = vec![
+        TestStep {
+            desc: "Start round 0, we, v4, are not the proposer",
+            input: Some(Input::NewRound(height, Round::new(0), v1.address)),
+            expected_outputs: vec![Output::ScheduleTimeout(Timeout::propose(Round::new(0)))],
+            expected_round: Round::new(0),
+            new_state: in_step(Step::Propose),
+        },
+        TestStep {
+            desc: "v1 prevotes its proposal, which we have not received",
+            input: Some(prevote_value(v1.address)),
+            expected_outputs: vec![],
+            expected_round: Round::new(0),
+            new_state: in_step(Step::Propose),
+        },
+        TestStep {
+            desc: "v2 prevotes the proposal",
+            input: Some(prevote_value(v2.address)),
+            expected_outputs: vec![],
+            expected_round: Round::new(0),
+            new_state: in_step(Step::Propose),
+        },
+        TestStep {
+            desc: "v3 prevotes the proposal, we get +2/3 prevotes for its value",
+            input: Some(prevote_value(v3.address)),
+            expected_outputs: vec![],
+            expected_round: Round::new(0),
+            new_state: in_step(Step::Propose),
+        },
+        TestStep {
+            desc: "Receive a propose timeout, prevote nil and schedule the prevote timeout (v4)",
+            input: Some(Input::TimeoutElapsed(Timeout::propose(Round::new(0)))),
+            expected_outputs: vec![
+                Output::Vote(Vote::new_prevote(
+                    height,
+                    Round::new(0),
+                    NilOrVal::Nil,
+                    my_addr,
+                )),
+                Output::ScheduleTimeout(Timeout::prevote(Round::new(0))),
+            ],
+            expected_round: Round::new(0),
+            new_state: in_step(Step::Prevote),
+        },
+        TestStep {
+            desc:
+                "Receive a prevote timeout, wait for the late proposal instead of precommitting nil",
+            input: Some(Input::TimeoutElapsed(Timeout::prevote(Round::new(0)))),
+            expected_outputs: vec![Output::ScheduleTimeout(Timeout::proposal_grace(
+                Round::new(0),
+            ))],
+            expected_round: Round::new(0),
+            new_state: in_step(Step::Prevote),
+        },
+    ];
+
+    (driver, steps, v1.address, my_addr)
+}
+
+#[test]
+fn driver_steps_proposal_arrives_in_grace_period() {
+    let value = Value::new(9999);
+    let height = Height::new(1);
+
+    let (mut driver, mut steps, proposer, my_addr) = proposal_grace_setup(value);
+
+    let sel = Arc::new(FixedProposer::new(proposer));
+    let vs = driver.validator_set().clone();
+
+    let proposal = new_signed_proposal(height, Round::new(0), value, Round::Nil, proposer);
+
+    let locked: State<TestContext> = State {
+        height,
+        round: Round::new(0),
+        step: Step::Precommit,
+        locked: Some(RoundValue {
+            value,
+            round: Round::new(0),
+        }),
+        valid: Some(RoundValue {
+            value,
+            round: Round::new(0),
+        }),
+        ..Default::default()
+    };
+
+    steps.extend([
+        TestStep {
+            desc: "Receive the late proposal in the grace period, precommit its value (v4)",
+            input: Some(Input::Proposal(proposal, Validity::Valid)),
+            expected_outputs: vec![Output::Vote(Vote::new_precommit(
+                height,
+                Round::new(0),
+                NilOrVal::Val(value.id()),
+                my_addr,
+            ))],
+            expected_round: Round::new(0),
+            new_state: locked.clone(),
+        },
+        TestStep {
+            desc: "The grace period elapses, we do not precommit again",
+            input: Some(Input::TimeoutElapsed(Timeout::proposal_grace(Round::new(
+                0,
+            )))),
+            expected_outputs: vec![],
+            expected_round: Round::new(0),
+            new_state: locked,
+        },
+    ]);
+
+    run_steps(&mut driver, steps, sel.as_ref(), &vs);
+}
+
+#[test]
+fn driver_steps_proposal_does_not_arrive_in_grace_period() {
+    let value = Value::new(9999);
+    let height = Height::new(1);
+
+    let (mut driver, mut steps, proposer, my_addr) = proposal_grace_setup(value);
+
+    let sel = Arc::new(FixedProposer::new(proposer));
+    let vs = driver.validator_set().clone();
+
+    steps.push(TestStep {
+        desc: "The grace period elapses without the proposal, precommit nil (v4)",
+        input: Some(Input::TimeoutElapsed(Timeout::proposal_grace(Round::new(
+            0,
+        )))),
+        expected_outputs: vec![Output::Vote(Vote::new_precommit(
+            height,
+            Round::new(0),
+            NilOrVal::Nil,
+            my_addr,
+        ))],
+        expected_round: Round::new(0),
+        new_state: State {
+            height,
+            round: Round::new(0),
+            step: Step::Precommit,
+            ..Default::default()
+        },
+    });
+
+    run_steps(&mut driver, steps, sel.as_ref(), &vs);
+}
+
 fn run_steps(
     driver: &mut Driver<TestContext>,
     steps: Vec<TestStep>,
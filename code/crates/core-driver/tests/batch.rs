@@ -0,0 +1,119 @@
+use malachitebft_core_types::{NilOrVal, Round, SignedVote};
+
+use malachitebft_core_driver_test_utils::*;
+use malachitebft_test::{Address, Height, Signature, TestContext, Value, Vote};
+
+use informalsystems_malachitebft_core_driver::{Error, Input, Output};
+
+// We prevote nil after the propose timeout, while the others prevote a value we never received.
+// Both the polka for any value and the polka for that value then schedule the prevote timeout.
+fn polka_inputs(value: Value, [v1, v2, v3, me]: [Address; 4]) -> Vec<Input<TestContext>> {
+    vec![
+        new_round_input(Round::new(0), v1),
+        timeout_propose_input(Round::new(0)),
+        prevote_nil_input(&me),
+        prevote_input(value, &v1),
+        prevote_input(value, &v2),
+        prevote_input(value, &v3),
+    ]
+}
+
+#[test]
+fn driver_batch_coalesces_timeouts() {
+    let value = Value::new(9999);
+
+    let (mut driver, addrs) = setup_driver();
+    let me = addrs[3];
+
+    let outputs: Vec<Output<TestContext>> = polka_inputs(value, addrs)
+        .into_iter()
+        .flat_map(|input| driver.process(input).unwrap())
+        .collect();
+
+    assert_eq!(
+        outputs,
+        vec![
+            start_propose_timer_output(Round::new(0)),
+            prevote_nil_output(Round::new(0), &me),
+            start_prevote_timer_output(Round::new(0)),
+            start_prevote_timer_output(Round::new(0)),
+        ]
+    );
+
+    let (mut driver, _) = setup_driver();
+    let (outputs, errors) = driver.process_batch(polka_inputs(value, addrs));
+
+    assert!(errors.is_empty());
+
+    assert_eq!(
+        outputs,
+        vec![
+            start_propose_timer_output(Round::new(0)),
+            prevote_nil_output(Round::new(0), &me),
+            start_prevote_timer_output(Round::new(0)),
+        ]
+    );
+}
+
+#[test]
+fn driver_batch_drops_timeouts_of_skipped_rounds() {
+    let value = Value::new(9999);
+
+    let (mut driver, addrs) = setup_driver();
+    let [v1, _, v3, me] = addrs;
+
+    let mut inputs = polka_inputs(value, addrs);
+    inputs.extend([
+        prevote_input_at(Round::new(2), value, &v1),
+        prevote_input_at(Round::new(2), value, &v3),
+    ]);
+
+    let (outputs, errors) = driver.process_batch(inputs);
+
+    assert!(errors.is_empty());
+    assert_eq!(driver.round(), Round::new(2));
+    assert_eq!(
+        outputs,
+        vec![
+            prevote_nil_output(Round::new(0), &me),
+            new_round_output(Round::new(2)),
+        ]
+    );
+}
+
+#[test]
+fn driver_batch_skips_failing_inputs() {
+    let value = Value::new(9999);
+
+    let (mut driver, addrs) = setup_driver();
+    let [_, v2, _, me] = addrs;
+
+    // A vote for the next height fails in the middle of the batch
+    let future_vote = Input::Vote(SignedVote::new(
+        Vote::new_prevote(Height::new(2), Round::new(0), NilOrVal::Val(value.id()), v2),
+        Signature::test(),
+    ));
+
+    let mut inputs = polka_inputs(value, addrs);
+    inputs.insert(3, future_vote);
+
+    let (outputs, errors) = driver.process_batch(inputs);
+
+    // The outputs of the inputs before and after the failing one are all returned
+    assert_eq!(
+        outputs,
+        vec![
+            start_propose_timer_output(Round::new(0)),
+            prevote_nil_output(Round::new(0), &me),
+            start_prevote_timer_output(Round::new(0)),
+        ]
+    );
+
+    assert_eq!(
+        errors,
+        vec![Error::InvalidVoteHeight {
+            vote_height: Height::new(2),
+            consensus_height: Height::new(1),
+        }]
+    );
+}
@@ -0,0 +1,160 @@
+use malachitebft_core_types::{NilOrVal, Round, SigningProvider, Validity};
+use malachitebft_test::utils::byzantine::ByzantineValidator;
+use malachitebft_test::utils::validators::make_validators;
+use malachitebft_test::{Ed25519Provider, Height, TestContext, ValidatorSet, Value};
+
+use informalsystems_malachitebft_core_driver::{Driver, Input};
+
+// v1=1, v2=1, v3=1, we are v3, v1 is the proposer and equivocates
+#[test]
+fn equivocating_proposer_is_recorded_as_evidence() {
+    let [(v1, sk1), (v2, _sk2), (v3, sk3)] = make_validators([1, 1, 1]);
+
+    let height = Height::new(1);
+    let round = Round::new(0);
+
+    let ctx = TestContext::new(sk3.clone());
+    let vs = ValidatorSet::new(vec![v1.clone(), v2, v3.clone()]);
+    let mut driver = Driver::new(ctx, height, vs, v3.address, Default::default());
+
+    let byzantine = ByzantineValidator::new(v1.clone(), sk1);
+    let (a, b) =
+        byzantine.conflicting_proposals(height, round, (Value::new(9999), Value::new(8888)));
+
+    // Each proposal is properly signed by the byzantine validator
+    let provider = Ed25519Provider::new(sk3);
+    for proposal in [&a, &b] {
+        assert!(provider.verify_signed_proposal(
+            &proposal.message,
+            &proposal.signature,
+            &v1.public_key
+        ));
+    }
+
+    driver
+        .process(Input::NewRound(height, round, v1.address))
+        .expect("new round");
+
+    driver
+        .process(Input::Proposal(a.clone(), Validity::Valid))
+        .expect("first proposal");
+
+    driver
+        .process(Input::Proposal(b.clone(), Validity::Valid))
+        .expect("conflicting proposal");
+
+    assert_eq!(driver.evidence().get(&v1.address), Some(&vec![(a, b)]));
+}
+
+// v1=1, v2=1, v3=1, we are v3, v2 equivocates on its precommit
+#[test]
+fn equivocating_precommits_are_recorded_as_evidence() {
+    let [(v1, _sk1), (v2, sk2), (v3, sk3)] = make_validators([1, 1, 1]);
+
+    let height = Height::new(1);
+    let round = Round::new(0);
+
+    let ctx = TestContext::new(sk3);
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+    let mut driver = Driver::new(ctx, height, vs, v3.address, Default::default());
+
+    let byzantine = ByzantineValidator::new(v2.clone(), sk2);
+    let (a, b) = byzantine.conflicting_precommits(
+        height,
+        round,
+        (NilOrVal::Val(Value::new(9999).id()), NilOrVal::Nil),
+    );
+
+    driver
+        .process(Input::NewRound(height, round, v1.address))
+        .expect("new round");
+
+    driver
+        .process(Input::Vote(a.clone()))
+        .expect("first precommit");
+
+    driver
+        .process(Input::Vote(b.clone()))
+        .expect("conflicting precommit");
+
+    assert_eq!(
+        driver.votes().evidence().get(&v2.address),
+        Some(&vec![(a, b)])
+    );
+}
+
+// v1=1, v2=1, v3=1, we are v3, v1 is the proposer and sends an invalid proposal
+#[test]
+fn invalid_proposal_is_retrievable_with_its_validity() {
+    let [(v1, sk1), (v2, _sk2), (v3, sk3)] = make_validators([1, 1, 1]);
+
+    let height = Height::new(1);
+    let round = Round::new(0);
+
+    let ctx = TestContext::new(sk3);
+    let vs = ValidatorSet::new(vec![v1.clone(), v2, v3.clone()]);
+    let mut driver = Driver::new(ctx, height, vs, v3.address, Default::default());
+
+    let byzantine = ByzantineValidator::new(v1.clone(), sk1);
+    let (invalid, _) =
+        byzantine.conflicting_proposals(height, round, (Value::new(9999), Value::new(8888)));
+
+    driver
+        .process(Input::NewRound(height, round, v1.address))
+        .expect("new round");
+
+    driver
+        .process(Input::Proposal(invalid.clone(), Validity::Invalid))
+        .expect("invalid proposal");
+
+    assert_eq!(
+        driver
+            .proposals()
+            .get_proposal_and_validity_for_round(round),
+        Some(&(invalid.clone(), Validity::Invalid))
+    );
+
+    assert_eq!(
+        driver.evidence().invalid_proposals(&v1.address),
+        Some(&vec![invalid])
+    );
+    assert_eq!(driver.evidence().get(&v1.address), None);
+}
+
+// v1=1, v2=1, v3=1, we are v3, v1 is the proposer and sends an invalid then a valid proposal
+#[test]
+fn invalid_then_valid_proposal_is_recorded_as_evidence() {
+    let [(v1, sk1), (v2, _sk2), (v3, sk3)] = make_validators([1, 1, 1]);
+
+    let height = Height::new(1);
+    let round = Round::new(0);
+
+    let ctx = TestContext::new(sk3);
+    let vs = ValidatorSet::new(vec![v1.clone(), v2, v3.clone()]);
+    let mut driver = Driver::new(ctx, height, vs, v3.address, Default::default());
+
+    let byzantine = ByzantineValidator::new(v1.clone(), sk1);
+    let (invalid, valid) =
+        byzantine.conflicting_proposals(height, round, (Value::new(9999), Value::new(8888)));
+
+    driver
+        .process(Input::NewRound(height, round, v1.address))
+        .expect("new round");
+
+    driver
+        .process(Input::Proposal(invalid.clone(), Validity::Invalid))
+        .expect("invalid proposal");
+
+    driver
+        .process(Input::Proposal(valid.clone(), Validity::Valid))
+        .expect("valid proposal");
+
+    assert_eq!(
+        driver.evidence().get(&v1.address),
+        Some(&vec![(invalid.clone(), valid)])
+    );
+    assert_eq!(
+        driver.evidence().invalid_proposals(&v1.address),
+        Some(&vec![invalid])
+    );
+}
@@ -0,0 +1,362 @@
+use malachitebft_core_state_machine::state::Step;
+use malachitebft_core_types::{
+    CertificateError, CommitCertificate, CommitSignature, NilOrVal, Round, SignedVote,
+    SigningProvider, SigningProviderExt, Timeout, Validity,
+};
+
+use malachitebft_core_driver_test_utils::*;
+use malachitebft_test::utils::validators::make_validators;
+use malachitebft_test::{
+    Ed25519Provider, Height, PrivateKey, Proposal, Signature, TestContext, Validator, ValidatorSet,
+    Value, ValueId, Vote,
+};
+
+use informalsystems_malachitebft_core_driver::{Driver, Error, Input, Output};
+
+fn signed_precommit(
+    sk: &PrivateKey,
+    validator: &Validator,
+    value: Value,
+) -> SignedVote<TestContext> {
+    signed_precommit_for(sk, validator, NilOrVal::Val(value.id()))
+}
+
+fn signed_precommit_for(
+    sk: &PrivateKey,
+    validator: &Validator,
+    value: NilOrVal<ValueId>,
+) -> SignedVote<TestContext> {
+    Ed25519Provider::new(sk.clone()).sign_vote(Vote::new_precommit(
+        Height::new(1),
+        Round::new(0),
+        value,
+        validator.address,
+    ))
+}
+
+fn decision_certificate(outputs: Vec<Output<TestContext>>) -> CommitCertificate<TestContext> {
+    outputs
+        .into_iter()
+        .find_map(|output| match output {
+            Output::Decide(_, _, certificate) => Some(certificate),
+            _ => None,
+        })
+        .expect("decided")
+}
+
+// The certificate must carry more than 2/3 of the voting power,
+// and all its signatures must verify against the canonical sign bytes of the precommits.
+fn assert_valid_certificate(
+    driver: &Driver<TestContext>,
+    certificate: &CommitCertificate<TestContext>,
+) {
+    let [(_, sk)] = make_validators([1]);
+    let vs = driver.validator_set();
+
+    let signed_power: u64 = certificate
+        .aggregated_signature
+        .signatures
+        .iter()
+        .map(|sig| vs.get_by_address(&sig.address).unwrap().voting_power)
+        .sum();
+
+    assert!(3 * signed_power > 2 * vs.total_voting_power());
+
+    Ed25519Provider::new(sk)
+        .verify_certificate(certificate, vs, Default::default())
+        .expect("certificate is valid");
+}
+
+// The last validator is left out of the validator set
+fn setup() -> (Driver<TestContext>, [(Validator, PrivateKey); 4]) {
+    let validators = make_validators([1, 2, 3, 5]);
+    let [(v1, _), (v2, _), (v3, sk3), _] = validators.clone();
+
+    let ctx = TestContext::new(sk3);
+    let vs = ValidatorSet::new(vec![v1.clone(), v2, v3.clone()]);
+
+    let mut driver = Driver::new(ctx, Height::new(1), vs, v3.address, Default::default());
+
+    driver
+        .process(new_round_input(Round::new(0), v1.address))
+        .expect("execute succeeded");
+
+    (driver, validators)
+}
+
+fn assert_rejected(
+    driver: &mut Driver<TestContext>,
+    certificate: CommitCertificate<TestContext>,
+    expected: CertificateError<TestContext>,
+) {
+    let state_before = driver.round_state().clone();
+
+    let output = driver.process(Input::CommitCertificate(certificate.clone()));
+    assert_eq!(output, Err(Error::InvalidCertificate(expected)));
+
+    // State must be left untouched
+    assert_eq!(driver.round_state(), &state_before);
+    assert!(driver
+        .get_certificate(certificate.round, certificate.value_id)
+        .is_none());
+}
+
+#[test]
+fn driver_certificate_not_enough_voting_power() {
+    let value = Value::new(9999);
+    let (mut driver, [(v1, sk1), (v2, sk2), _, _]) = setup();
+
+    let commits = vec![
+        signed_precommit(&sk1, &v1, value),
+        signed_precommit(&sk2, &v2, value),
+    ];
+
+    let certificate = CommitCertificate::new(Height::new(1), Round::new(0), value.id(), commits);
+
+    assert_rejected(
+        &mut driver,
+        certificate,
+        CertificateError::NotEnoughVotingPower {
+            signed: 3,
+            total: 6,
+            expected: 4,
+        },
+    );
+}
+
+#[test]
+fn driver_certificate_duplicate_signers() {
+    let value = Value::new(9999);
+    let (mut driver, [_, (v2, sk2), _, _]) = setup();
+
+    // v2 signs three times, which would amount to 6 > 2/3 * 6 if counted more than once
+    let commit = signed_precommit(&sk2, &v2, value);
+    let commits = vec![commit.clone(), commit.clone(), commit.clone()];
+
+    let certificate = CommitCertificate::new(Height::new(1), Round::new(0), value.id(), commits);
+    let duplicate = certificate.aggregated_signature.signatures[1].clone();
+
+    assert_rejected(
+        &mut driver,
+        certificate,
+        CertificateError::DuplicateVote(duplicate),
+    );
+}
+
+#[test]
+fn driver_certificate_unknown_signer() {
+    let value = Value::new(9999);
+    let (mut driver, [_, (v2, sk2), (v3, sk3), (outsider, outsider_sk)]) = setup();
+
+    let commits = vec![
+        signed_precommit(&sk2, &v2, value),
+        signed_precommit(&sk3, &v3, value),
+        signed_precommit(&outsider_sk, &outsider, value),
+    ];
+
+    let certificate = CommitCertificate::new(Height::new(1), Round::new(0), value.id(), commits);
+    let unknown = certificate.aggregated_signature.signatures[2].clone();
+
+    assert_rejected(
+        &mut driver,
+        certificate,
+        CertificateError::UnknownValidator(unknown),
+    );
+}
+
+#[test]
+fn driver_certificate_invalid_signature() {
+    let value = Value::new(9999);
+    let (mut driver, [_, (v2, _), _, _]) = setup();
+
+    let commits = vec![SignedVote::new(
+        Vote::new_precommit(
+            Height::new(1),
+            Round::new(0),
+            NilOrVal::Val(value.id()),
+            v2.address,
+        ),
+        Signature::test(),
+    )];
+
+    let certificate = CommitCertificate::new(Height::new(1), Round::new(0), value.id(), commits);
+
+    assert_rejected(
+        &mut driver,
+        certificate,
+        CertificateError::InvalidSignature(CommitSignature::new(
+            v2.address,
+            Signature::test(),
+            None,
+        )),
+    );
+}
+
+#[test]
+fn driver_certificate_decides_when_proposal_is_known() {
+    let value = Value::new(9999);
+    let (mut driver, [(v1, sk1), (v2, sk2), (v3, sk3), _]) = setup();
+
+    driver
+        .process(proposal_input(
+            Round::new(0),
+            value,
+            Round::Nil,
+            Validity::Valid,
+            v1.address,
+        ))
+        .expect("execute succeeded");
+
+    let commits = vec![
+        signed_precommit(&sk1, &v1, value),
+        signed_precommit(&sk2, &v2, value),
+        signed_precommit(&sk3, &v3, value),
+    ];
+
+    let certificate = CommitCertificate::new(Height::new(1), Round::new(0), value.id(), commits);
+
+    let outputs = driver
+        .process(Input::CommitCertificate(certificate.clone()))
+        .expect("execute succeeded");
+
+    let proposal = Proposal::new(Height::new(1), Round::new(0), value, Round::Nil, v1.address);
+
+    assert_eq!(
+        outputs,
+        vec![Output::Decide(Round::new(0), proposal, certificate)]
+    );
+    assert_eq!(driver.step(), Step::Commit);
+}
+
+#[test]
+fn driver_certificate_decides_when_proposal_is_received() {
+    let value = Value::new(9999);
+    let (mut driver, [(v1, sk1), (v2, sk2), (v3, sk3), _]) = setup();
+
+    let commits = vec![
+        signed_precommit(&sk1, &v1, value),
+        signed_precommit(&sk2, &v2, value),
+        signed_precommit(&sk3, &v3, value),
+    ];
+
+    let certificate = CommitCertificate::new(Height::new(1), Round::new(0), value.id(), commits);
+
+    let outputs = driver
+        .process(Input::CommitCertificate(certificate.clone()))
+        .expect("execute succeeded");
+
+    assert!(outputs.is_empty());
+    assert_eq!(driver.step(), Step::Propose);
+
+    let outputs = driver
+        .process(proposal_input(
+            Round::new(0),
+            value,
+            Round::Nil,
+            Validity::Valid,
+            v1.address,
+        ))
+        .expect("execute succeeded");
+
+    let proposal = Proposal::new(Height::new(1), Round::new(0), value, Round::Nil, v1.address);
+
+    assert_eq!(
+        outputs,
+        vec![Output::Decide(Round::new(0), proposal, certificate)]
+    );
+    assert_eq!(driver.step(), Step::Commit);
+}
+
+#[test]
+fn driver_decision_certificate_only_includes_precommits_for_value() {
+    let value = Value::new(9999);
+    let (mut driver, [(v1, sk1), (v2, sk2), (v3, sk3), _]) = setup();
+
+    driver
+        .process(proposal_input(
+            Round::new(0),
+            value,
+            Round::Nil,
+            Validity::Valid,
+            v1.address,
+        ))
+        .expect("execute succeeded");
+
+    let nil = signed_precommit_for(&sk1, &v1, NilOrVal::Nil);
+    let precommit2 = signed_precommit(&sk2, &v2, value);
+    let precommit3 = signed_precommit(&sk3, &v3, value);
+
+    let mut outputs = vec![];
+
+    for precommit in [nil, precommit2.clone(), precommit3.clone()] {
+        outputs = driver
+            .process(Input::Vote(precommit))
+            .expect("execute succeeded");
+    }
+
+    let certificate = decision_certificate(outputs);
+
+    assert_eq!(certificate.round, Round::new(0));
+    assert_eq!(certificate.value_id, value.id());
+    assert_eq!(
+        certificate,
+        CommitCertificate::new(
+            Height::new(1),
+            Round::new(0),
+            value.id(),
+            vec![precommit2, precommit3]
+        )
+    );
+
+    assert_valid_certificate(&driver, &certificate);
+}
+
+// We get +2/3 precommits for a value in round 0 but not its proposal, and move to round 1.
+// When we then receive the proposal from round 0, we decide on it via L49
+// and the certificate must contain the precommits from round 0.
+#[test]
+fn driver_decision_certificate_from_earlier_round() {
+    let value = Value::new(9999);
+    let (mut driver, [(v1, _), (v2, sk2), (v3, sk3), _]) = setup();
+
+    for precommit in [
+        signed_precommit(&sk2, &v2, value),
+        signed_precommit(&sk3, &v3, value),
+    ] {
+        driver
+            .process(Input::Vote(precommit))
+            .expect("execute succeeded");
+    }
+
+    driver
+        .process(Input::TimeoutElapsed(Timeout::precommit(Round::new(0))))
+        .expect("execute succeeded");
+
+    driver
+        .process(new_round_input(Round::new(1), v2.address))
+        .expect("execute succeeded");
+
+    assert_eq!(driver.round(), Round::new(1));
+
+    let outputs = driver
+        .process(proposal_input(
+            Round::new(0),
+            value,
+            Round::Nil,
+            Validity::Valid,
+            v1.address,
+        ))
+        .expect("execute succeeded");
+
+    let proposal = Proposal::new(Height::new(1), Round::new(0), value, Round::Nil, v1.address);
+    assert!(
+        matches!(&outputs[..], [Output::Decide(round, p, _)] if *round == Round::new(1) && *p == proposal)
+    );
+
+    let certificate = decision_certificate(outputs);
+
+    assert_eq!(certificate.round, Round::new(0));
+    assert_eq!(certificate.aggregated_signature.signatures.len(), 2);
+
+    assert_valid_certificate(&driver, &certificate);
+}
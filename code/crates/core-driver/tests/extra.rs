@@ -5,7 +5,7 @@ use malachitebft_core_driver_test_utils::*;
 use malachitebft_test::utils::validators::make_validators;
 use malachitebft_test::{Height, Proposal, TestContext, ValidatorSet, Value};
 
-use informalsystems_malachitebft_core_driver::{Driver, Input, Output};
+use informalsystems_malachitebft_core_driver::{Driver, Error, Input, Output, PrevoteReason, Step};
 
 // The following tests are performed:
 // - L49 with commits from current rounds, no locked value, no valid value:
@@ -37,6 +37,15 @@ use informalsystems_malachitebft_core_driver::{Driver, Input, Output};
 //
 // - L34 with previously received polkaAny and entering prevote (due to received proposal)
 //      `driver_steps_polka_any_then_proposal_other()`
+//
+// - L22 deferred until the validity of the proposal is known, before timeoutPropose
+//      `driver_steps_proposal_validated_before_timeout_propose()`
+//
+// - L57 with a proposal whose validity becomes known after timeoutPropose
+//      `driver_steps_proposal_validated_after_timeout_propose()`
+//
+// - L28 in round 2 via a re-proposal of the value with a POL round, validated after it was received
+//      `driver_steps_reproposal_with_pol_round_validated_later()`
 
 struct TestStep {
     desc: &'static str,
@@ -104,7 +113,11 @@ fn driver_steps_decide_current_with_no_locked_no_valid() {
                 Validity::Valid,
                 v1.address,
             ),
-            expected_outputs: vec![decide_output(Round::new(0), proposal)],
+            expected_outputs: vec![decide_output(
+                Round::new(0),
+                proposal,
+                &[v1.address, v2.address],
+            )],
             expected_round: Round::new(0),
             new_state: decided_state(Round::new(0), value),
         },
@@ -215,13 +228,22 @@ fn driver_steps_decide_previous_with_no_locked_no_valid() {
                 Validity::Valid,
                 v1.address,
             ),
-            expected_outputs: vec![decide_output(Round::new(1), proposal)],
+            expected_outputs: vec![decide_output(
+                Round::new(1),
+                proposal,
+                &[v1.address, v2.address],
+            )],
             expected_round: Round::new(1),
             new_state: decided_state(Round::new(1), value),
         },
     ];
 
     run_steps(&mut driver, steps);
+
+    assert_eq!(
+        driver.nil_prevote_reason(Round::new(0)),
+        Some(PrevoteReason::TimeoutPropose)
+    );
 }
 
 // Arrive at L49 with commits from previous round, with locked and valid values
@@ -342,7 +364,11 @@ fn driver_steps_decide_previous_with_locked_and_valid() {
         TestStep {
             desc: "v2 precommits for round 0 and same proposal, we get +2/3 precommit, decide",
             input: precommit_input(Round::new(0), value, &v2.address),
-            expected_outputs: vec![decide_output(Round::new(1), proposal.clone())],
+            expected_outputs: vec![decide_output(
+                Round::new(1),
+                proposal.clone(),
+                &[v1.address, v2.address],
+            )],
             expected_round: Round::new(1),
             new_state: decided_state_with_proposal_and_locked_and_valid(
                 Round::new(1),
@@ -466,7 +492,7 @@ fn driver_steps_polka_previous_with_locked() {
                 value,
                 Round::new(0),
                 Validity::Valid,
-                v1.address,
+                v2.address,
             ),
             expected_outputs: vec![prevote_output(Round::new(1), value, &v2.address)],
             expected_round: Round::new(1),
@@ -570,7 +596,7 @@ fn driver_steps_polka_previous_invalid_proposal() {
                 value,
                 Round::new(0),
                 Validity::Invalid,
-                v1.address,
+                v2.address,
             ),
             expected_outputs: vec![prevote_nil_output(Round::new(1), &my_addr)],
             expected_round: Round::new(1),
@@ -681,7 +707,7 @@ fn driver_steps_polka_previous_new_proposal() {
                 other_value,
                 Round::Nil,
                 Validity::Valid,
-                v1.address,
+                v2.address,
             ),
             expected_outputs: vec![prevote_nil_output(Round::new(1), &my_addr)],
             expected_round: Round::new(1),
@@ -699,6 +725,13 @@ fn driver_steps_polka_previous_new_proposal() {
     ];
 
     run_steps(&mut driver, steps);
+
+    // We prevoted nil in round 1 because we are locked on the value of round 0
+    assert_eq!(driver.nil_prevote_reason(Round::new(0)), None);
+    assert_eq!(
+        driver.nil_prevote_reason(Round::new(1)),
+        Some(PrevoteReason::LockedOnDifferentValue(Round::new(0)))
+    );
 }
 
 // Arrive at L36 in round 0, with step precommit and then L28 in round 1 with no locked value.
@@ -787,7 +820,7 @@ fn driver_steps_polka_previous_with_no_locked() {
                 value,
                 Round::Nil,
                 Validity::Valid,
-                v3.address,
+                v1.address,
             ),
             expected_outputs: vec![],
             expected_round: Round::new(0),
@@ -1189,8 +1222,7 @@ fn driver_equivocate_proposal() {
 }
 
 #[test]
-#[should_panic(expected = "unreachable code: Conflicting proposals from different validators")]
-fn driver_conflicting_proposal_unreachable() {
+fn driver_conflicting_proposal_from_non_proposer() {
     let value1 = Value::new(9999);
     let value2 = Value::new(42);
 
@@ -1224,22 +1256,27 @@ fn driver_conflicting_proposal_unreachable() {
             expected_round: Round::new(0),
             new_state: prevote_state(Round::new(0)),
         },
-        TestStep {
-            desc: "Receive proposal 2 from v2",
-            input: proposal_input(
-                Round::new(0),
-                value2,
-                Round::Nil,
-                Validity::Valid,
-                v2.address,
-            ),
-            expected_outputs: vec![],
-            expected_round: Round::new(0),
-            new_state: prevote_state(Round::new(0)),
-        },
     ];
 
     run_steps(&mut driver, steps);
+
+    // A conflicting proposal can only come from a validator which is not the proposer
+    let result = driver.process(proposal_input(
+        Round::new(0),
+        value2,
+        Round::Nil,
+        Validity::Valid,
+        v2.address,
+    ));
+
+    assert_eq!(
+        result,
+        Err(Error::ProposerMismatch {
+            expected: v1.address,
+            actual: v2.address,
+        })
+    );
+    assert_eq!(driver.round_state(), &prevote_state(Round::new(0)));
 }
 
 #[test]
@@ -1464,7 +1501,7 @@ fn driver_step_change_mux_with_proposal_and_commit_quorum() {
             input: new_round_input(Round::new(1), v2.address),
             expected_outputs: vec![
                 start_propose_timer_output(Round::new(1)),
-                decide_output(Round::new(1), proposal),
+                decide_output(Round::new(1), proposal, &[v1.address, v2.address]),
             ],
             expected_round: Round::new(1),
             new_state: decided_state(Round::new(1), value),
@@ -1485,7 +1522,7 @@ fn proposal_mux_with_polka() {
     let ctx = TestContext::new(my_sk.clone());
     let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
 
-    let proposal = Proposal::new(Height::new(1), Round::new(1), value, Round::Nil, v1.address);
+    let proposal = Proposal::new(Height::new(1), Round::new(1), value, Round::Nil, v2.address);
 
     let mut driver = Driver::new(ctx, height, vs, my_addr, Default::default());
 
@@ -1525,7 +1562,7 @@ fn proposal_mux_with_polka() {
                 value,
                 Round::Nil,
                 Validity::Valid,
-                v1.address,
+                v2.address,
             ),
             expected_outputs: vec![
                 prevote_output(Round::new(1), value, &my_addr),
@@ -1553,7 +1590,7 @@ fn proposal_mux_with_commit_quorum() {
     let ctx = TestContext::new(my_sk.clone());
     let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
 
-    let proposal = Proposal::new(Height::new(1), Round::new(1), value, Round::Nil, v1.address);
+    let proposal = Proposal::new(Height::new(1), Round::new(1), value, Round::Nil, v2.address);
 
     let mut driver = Driver::new(ctx, height, vs, my_addr, Default::default());
 
@@ -1597,9 +1634,13 @@ fn proposal_mux_with_commit_quorum() {
                 value,
                 Round::Nil,
                 Validity::Valid,
-                v1.address,
+                v2.address,
             ),
-            expected_outputs: vec![decide_output(Round::new(1), proposal)],
+            expected_outputs: vec![decide_output(
+                Round::new(1),
+                proposal,
+                &[v1.address, v2.address],
+            )],
             expected_round: Round::new(1),
             new_state: decided_state(Round::new(1), value),
         },
@@ -1608,6 +1649,250 @@ fn proposal_mux_with_commit_quorum() {
     run_steps(&mut driver, steps);
 }
 
+// Arrive at L22 once the validity of a proposal received earlier becomes known
+//
+// Ev:             NewRound(0)           Proposal(unknown)         ProposalValidated(valid)
+// State: NewRound ------------> Propose -----------------> Propose ------------------------> Prevote
+// Msg:            propose_timer         None                      prevote(v)
+// Alg:            L21                                             L24
+//
+// v1=2, v2=3, v3=2, we are v3
+// L21 - v3 is not proposer starts propose timer (step propose)
+// v3 receives a proposal whose validity is not known yet, does not prevote (step propose)
+// L24 - v3 learns that the proposal is valid, prevotes value (step prevote)
+#[test]
+fn driver_steps_proposal_validated_before_timeout_propose() {
+    let value = Value::new(9999);
+
+    let [(v1, _sk1), (v2, _sk2), (v3, sk3)] = make_validators([2, 3, 2]);
+    let (my_sk, my_addr) = (sk3.clone(), v3.address);
+
+    let height = Height::new(1);
+    let ctx = TestContext::new(my_sk.clone());
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+    let mut driver = Driver::new(ctx, height, vs, my_addr, Default::default());
+
+    let steps = vec![
+        TestStep {
+            desc: "Start round 0, we, v3, are not the proposer, start timeout propose",
+            input: new_round_input(Round::new(0), v1.address),
+            expected_outputs: vec![start_propose_timer_output(Round::new(0))],
+            expected_round: Round::new(0),
+            new_state: propose_state(Round::new(0)),
+        },
+        TestStep {
+            desc: "Receive a proposal from v1 of unknown validity, do not prevote yet",
+            input: proposal_input(
+                Round::new(0),
+                value,
+                Round::Nil,
+                Validity::Unknown,
+                v1.address,
+            ),
+            expected_outputs: vec![],
+            expected_round: Round::new(0),
+            new_state: propose_state(Round::new(0)),
+        },
+        TestStep {
+            desc: "The proposal is valid - L22 send prevote",
+            input: proposal_validated_input(
+                Round::new(0),
+                value,
+                Round::Nil,
+                Validity::Valid,
+                v1.address,
+            ),
+            expected_outputs: vec![prevote_output(Round::new(0), value, &my_addr)],
+            expected_round: Round::new(0),
+            new_state: prevote_state(Round::new(0)),
+        },
+    ];
+
+    run_steps(&mut driver, steps);
+}
+
+// Arrive at L57 while the validity of the proposal is not known, and ignore it once known
+//
+// Ev:             NewRound(0)           Proposal(unknown)         Timeout(propose)         ProposalValidated(valid)
+// State: NewRound ------------> Propose -----------------> Propose ---------------> Prevote ------------------------> Prevote
+// Msg:            propose_timer         None                      prevote(nil)             None
+// Alg:            L21                                             L57
+//
+// v1=2, v2=3, v3=2, we are v3
+// L21 - v3 is not proposer starts propose timer (step propose)
+// v3 receives a proposal whose validity is not known yet, does not prevote (step propose)
+// L57 - v3 receives timeout propose, prevotes nil (step prevote)
+// v3 learns that the proposal is valid, but already prevoted (step prevote)
+#[test]
+fn driver_steps_proposal_validated_after_timeout_propose() {
+    let value = Value::new(9999);
+
+    let [(v1, _sk1), (v2, _sk2), (v3, sk3)] = make_validators([2, 3, 2]);
+    let (my_sk, my_addr) = (sk3.clone(), v3.address);
+
+    let height = Height::new(1);
+    let ctx = TestContext::new(my_sk.clone());
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+    let mut driver = Driver::new(ctx, height, vs, my_addr, Default::default());
+
+    let steps = vec![
+        TestStep {
+            desc: "Start round 0, we, v3, are not the proposer, start timeout propose",
+            input: new_round_input(Round::new(0), v1.address),
+            expected_outputs: vec![start_propose_timer_output(Round::new(0))],
+            expected_round: Round::new(0),
+            new_state: propose_state(Round::new(0)),
+        },
+        TestStep {
+            desc: "Receive a proposal from v1 of unknown validity, do not prevote yet",
+            input: proposal_input(
+                Round::new(0),
+                value,
+                Round::Nil,
+                Validity::Unknown,
+                v1.address,
+            ),
+            expected_outputs: vec![],
+            expected_round: Round::new(0),
+            new_state: propose_state(Round::new(0)),
+        },
+        TestStep {
+            desc: "Timeout propose, prevote nil",
+            input: timeout_propose_input(Round::new(0)),
+            expected_outputs: vec![prevote_nil_output(Round::new(0), &my_addr)],
+            expected_round: Round::new(0),
+            new_state: prevote_state(Round::new(0)),
+        },
+        TestStep {
+            desc: "The proposal is valid, but we already prevoted nil",
+            input: proposal_validated_input(
+                Round::new(0),
+                value,
+                Round::Nil,
+                Validity::Valid,
+                v1.address,
+            ),
+            expected_outputs: vec![],
+            expected_round: Round::new(0),
+            new_state: prevote_state(Round::new(0)),
+        },
+    ];
+
+    run_steps(&mut driver, steps);
+
+    assert_eq!(
+        driver
+            .proposals()
+            .get_proposal_and_validity_for_round(Round::new(0))
+            .map(|(_, validity)| *validity),
+        Some(Validity::Valid)
+    );
+}
+
+// L28 in round 2 with a value re-proposed with a POL round, after its first proposal without one.
+//
+// v1=2, v2=3, v3=2, we are v3
+//
+// Round 0: we lock on `value`.
+// Round 1: we time out and prevote nil, then see a polka for `other_value` without its proposal.
+// Round 2: v1 proposes `other_value` without a POL round, then re-proposes it with POL round 1.
+//   Once the value is validated, the latest proposal is considered:
+//   L28 - lockedRound (0) <= vr (1), so we prevote `other_value`,
+//   whereas the first proposal alone would have made us prevote nil (L22-L26).
+#[test]
+fn driver_steps_reproposal_with_pol_round_validated_later() {
+    let value = Value::new(9999);
+    let other_value = Value::new(8888);
+
+    let [(v1, _sk1), (v2, _sk2), (v3, sk3)] = make_validators([2, 3, 2]);
+    let (my_sk, my_addr) = (sk3, v3.address);
+
+    let height = Height::new(1);
+    let ctx = TestContext::new(my_sk.clone());
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+    let mut driver = Driver::new(ctx, height, vs, my_addr, Default::default());
+
+    let inputs = vec![
+        // Round 0, lock on `value`
+        new_round_input(Round::new(0), v1.address),
+        proposal_input(
+            Round::new(0),
+            value,
+            Round::Nil,
+            Validity::Valid,
+            v1.address,
+        ),
+        prevote_input(value, &v1.address),
+        prevote_input(value, &v2.address),
+        // Round 1, polka for `other_value` without its proposal
+        prevote_input_at(Round::new(1), other_value, &v2.address),
+        new_round_input(Round::new(1), v2.address),
+        timeout_propose_input(Round::new(1)),
+        prevote_input_at(Round::new(1), other_value, &v1.address),
+        timeout_prevote_input(Round::new(1)),
+        // Round 2
+        prevote_input_at(Round::new(2), other_value, &v2.address),
+        new_round_input(Round::new(2), v1.address),
+    ];
+
+    for input in inputs {
+        driver.process(input).expect("process succeeded");
+    }
+
+    assert_eq!(driver.round(), Round::new(2));
+    assert_eq!(driver.step(), Step::Propose);
+
+    let proposal = proposal_input(
+        Round::new(2),
+        other_value,
+        Round::Nil,
+        Validity::Unknown,
+        v1.address,
+    );
+
+    let reproposal = proposal_input(
+        Round::new(2),
+        other_value,
+        Round::new(1),
+        Validity::Unknown,
+        v1.address,
+    );
+
+    // Neither proposal is acted upon until its value is validated
+    assert_eq!(driver.process(proposal).unwrap(), vec![]);
+    assert_eq!(driver.process(reproposal).unwrap(), vec![]);
+
+    // The re-proposal is not a conflict, both proposals are kept
+    assert_eq!(
+        driver
+            .proposals()
+            .get_proposals_for_round(Round::new(2))
+            .len(),
+        2
+    );
+    assert!(driver.proposals().evidence().is_empty());
+
+    // The value of the first proposal is validated
+    let outputs = driver
+        .process(proposal_validated_input(
+            Round::new(2),
+            other_value,
+            Round::Nil,
+            Validity::Valid,
+            v1.address,
+        ))
+        .unwrap();
+
+    assert_eq!(
+        outputs,
+        vec![prevote_output(Round::new(2), other_value, &my_addr)]
+    );
+    assert_eq!(driver.nil_prevote_reason(Round::new(2)), None);
+}
+
 fn run_steps(driver: &mut Driver<TestContext>, steps: Vec<TestStep>) {
     for step in steps {
         println!("Step: {}", step.desc);
@@ -1619,3 +1904,39 @@ fn run_steps(driver: &mut Driver<TestContext>, steps: Vec<TestStep>) {
         assert_eq!(driver.round_state(), &step.new_state, "expected state");
     }
 }
+
+#[test]
+fn driver_exposes_round_and_step() {
+    let value = Value::new(9999);
+
+    let [(v1, _sk1), (v2, _sk2), (v3, sk3)] = make_validators([2, 3, 2]);
+    let (my_sk, my_addr) = (sk3.clone(), v3.address);
+
+    let ctx = TestContext::new(my_sk.clone());
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+    let mut driver = Driver::new(ctx, Height::new(1), vs, my_addr, Default::default());
+
+    assert_eq!(driver.round(), Round::Nil);
+    assert_eq!(driver.step(), Step::Unstarted);
+
+    driver
+        .process(new_round_input(Round::new(0), v1.address))
+        .expect("process succeeded");
+
+    assert_eq!(driver.round(), Round::new(0));
+    assert_eq!(driver.step(), Step::Propose);
+
+    driver
+        .process(proposal_input(
+            Round::new(0),
+            value,
+            Round::Nil,
+            Validity::Valid,
+            v1.address,
+        ))
+        .expect("process succeeded");
+
+    assert_eq!(driver.round(), Round::new(0));
+    assert_eq!(driver.step(), Step::Prevote);
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use malachitebft_core_driver_test_utils::network::SimNetwork;
+
+const DEADLINE: Duration = Duration::from_secs(120);
+
+#[test]
+fn network_decides_with_delays() {
+    let mut net = SimNetwork::new([1, 1, 1, 1], 42)
+        .with_latency(Duration::from_millis(250))
+        .with_edge_latency(0, 1, Duration::from_secs(2))
+        .with_edge_latency(2, 3, Duration::from_millis(1500));
+
+    assert!(net.run_until(DEADLINE));
+    assert!(net.decision().is_some());
+}
+
+#[test]
+fn network_decides_under_message_loss() {
+    for seed in 0..10 {
+        let mut net = SimNetwork::new([1, 1, 1, 1], seed)
+            .with_latency(Duration::from_millis(100))
+            .with_drop_rate(0.2);
+
+        assert!(net.run_until(DEADLINE), "no decision with seed {seed}");
+        assert!(net.decision().is_some(), "disagreement with seed {seed}");
+    }
+}
+
+#[test]
+fn network_runs_are_deterministic() {
+    let run = |seed| {
+        let mut net = SimNetwork::new([1, 2, 3, 1], seed)
+            .with_latency(Duration::from_millis(100))
+            .with_drop_rate(0.3);
+
+        net.run_until(DEADLINE);
+
+        let outputs: Vec<_> = net.nodes().map(|node| node.outputs().to_vec()).collect();
+        (net.now(), outputs)
+    };
+
+    assert_eq!(run(7), run(7));
+}
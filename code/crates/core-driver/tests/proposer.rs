@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use malachitebft_core_types::{Context, Round, ValidatorSet as _};
+
+use malachitebft_test::utils::validators::make_validators;
+use malachitebft_test::{Address, Height, TestContext, Validator, ValidatorSet};
+
+use informalsystems_malachitebft_core_driver::{Driver, ProposerSelection, ProposerSelector};
+
+/// Rotates through the validators which are not jailed
+struct SkipJailed {
+    jailed: Vec<Address>,
+}
+
+impl ProposerSelector<TestContext> for SkipJailed {
+    fn select_proposer<'a>(&self, selection: &ProposerSelection<'a, TestContext>) -> &'a Validator {
+        let eligible: Vec<_> = selection
+            .validator_set
+            .validators
+            .iter()
+            .filter(|v| !self.jailed.contains(&v.address))
+            .collect();
+
+        let index = (selection.height.as_u64() as usize - 1 + selection.round.as_i64() as usize)
+            % eligible.len();
+
+        eligible[index]
+    }
+}
+
+fn new_driver(vs: &ValidatorSet) -> Driver<TestContext> {
+    let [(v, sk)] = make_validators([1]);
+    let ctx = TestContext::new(sk);
+    Driver::new(
+        ctx,
+        Height::new(1),
+        vs.clone(),
+        v.address,
+        Default::default(),
+    )
+}
+
+#[test]
+fn driver_falls_back_to_context_proposer_selection() {
+    let [(v1, _), (v2, _), (v3, _)] = make_validators([1, 1, 1]);
+    let vs = ValidatorSet::new(vec![v1, v2, v3]);
+
+    let driver = new_driver(&vs);
+
+    for round in Round::range(Round::new(0), Round::new(6)) {
+        let [(_, sk)] = make_validators([1]);
+
+        assert_eq!(
+            driver.select_proposer(Height::new(1), round),
+            TestContext::new(sk).select_proposer(&vs, Height::new(1), round)
+        );
+    }
+}
+
+#[test]
+fn driver_uses_proposer_selector() {
+    let [(v1, _), (v2, _), (v3, _)] = make_validators([1, 1, 1]);
+    let vs = ValidatorSet::new(vec![v1, v2, v3]);
+
+    let jailed = vs.get_by_index(1).unwrap().address;
+
+    let mut driver = new_driver(&vs);
+    driver.set_proposer_selector(Arc::new(SkipJailed {
+        jailed: vec![jailed],
+    }));
+
+    for round in Round::range(Round::new(0), Round::new(6)) {
+        let proposer = driver.select_proposer(Height::new(1), round);
+        assert_ne!(proposer.address, jailed);
+    }
+
+    driver.clear_proposer_selector();
+
+    assert_eq!(
+        driver
+            .select_proposer(Height::new(1), Round::new(1))
+            .address,
+        jailed
+    );
+}
+
+#[test]
+fn proposer_selection_hook_detects_divergent_selections() {
+    let [(v1, _), (v2, _), (v3, _)] = make_validators([1, 1, 1]);
+    let vs = ValidatorSet::new(vec![v1, v2, v3]);
+
+    let selections = Arc::new(Mutex::new(BTreeMap::new()));
+    let divergent = Arc::new(Mutex::new(Vec::new()));
+
+    let drivers: Vec<_> = [vec![], vec![vs.get_by_index(0).unwrap().address]]
+        .into_iter()
+        .map(|jailed| {
+            let mut driver = new_driver(&vs);
+            driver.set_proposer_selector(Arc::new(SkipJailed { jailed }));
+
+            let selections = Arc::clone(&selections);
+            let divergent = Arc::clone(&divergent);
+
+            driver.set_proposer_selection_hook(Arc::new(
+                move |selection: &ProposerSelection<'_, TestContext>, proposer: &Address| {
+                    let key = (selection.height, selection.round);
+                    let mut selections = selections.lock().unwrap();
+                    let selected = selections.entry(key).or_insert(*proposer);
+
+                    if selected != proposer {
+                        divergent.lock().unwrap().push(key);
+                    }
+                },
+            ));
+
+            driver
+        })
+        .collect();
+
+    for driver in &drivers {
+        driver.select_proposer(Height::new(1), Round::new(0));
+    }
+
+    assert_eq!(
+        *divergent.lock().unwrap(),
+        vec![(Height::new(1), Round::new(0))]
+    );
+}
@@ -0,0 +1,251 @@
+use malachitebft_core_state_machine::state::State;
+use malachitebft_core_types::{NilOrVal, Round, Validity, Vote as _, VoteType};
+
+use malachitebft_core_driver_test_utils::*;
+use malachitebft_test::utils::validators::make_validators;
+use malachitebft_test::{Address, Height, TestContext, ValidatorSet, Value, Vote};
+
+use informalsystems_malachitebft_core_driver::{Driver, Input, Output};
+
+fn new_driver(vs: &ValidatorSet, my_addr: Address) -> Driver<TestContext> {
+    let [(_, sk)] = make_validators([1]);
+    let ctx = TestContext::new(sk);
+    let mut driver = Driver::new(ctx, Height::new(1), vs.clone(), my_addr, Default::default());
+    driver.set_record_wal_entries(true);
+    driver
+}
+
+fn votes(outputs: &[Output<TestContext>]) -> Vec<Vote> {
+    outputs
+        .iter()
+        .filter_map(|output| match output {
+            Output::Vote(vote) => Some(vote.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+// Run a full round where we, v2, prevote and precommit the proposal of v1 and decide on it.
+// Then, for each step of the round, simulate a crash by replaying the inputs accepted so far
+// on a fresh driver, and check that:
+// - the replayed driver ends up in the exact same state
+// - feeding it the rest of the inputs never yields a vote for a round and type we already signed
+#[test]
+fn driver_replay_at_each_step() {
+    let value = Value::new(9999);
+
+    let [(v1, _sk1), (v2, _sk2), (v3, _sk3), (v4, _sk4)] = make_validators([1, 1, 1, 1]);
+    let my_addr = v2.address;
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone(), v4.clone()]);
+
+    let inputs = vec![
+        new_round_input(Round::new(0), v1.address),
+        proposal_input(
+            Round::new(0),
+            value,
+            Round::Nil,
+            Validity::Valid,
+            v1.address,
+        ),
+        prevote_input(value, &my_addr),
+        prevote_input(value, &v1.address),
+        prevote_input(value, &v3.address),
+        precommit_input(Round::new(0), value, &my_addr),
+        precommit_input(Round::new(0), value, &v1.address),
+        precommit_input(Round::new(0), value, &v3.address),
+    ];
+
+    let mut driver = new_driver(&vs, my_addr);
+
+    let mut states: Vec<State<TestContext>> = vec![driver.round_state().clone()];
+    let mut signed: Vec<Vec<Vote>> = vec![vec![]];
+
+    for input in &inputs {
+        let outputs = driver.process(input.clone()).expect("process succeeded");
+
+        let mut all_votes = signed.last().unwrap().clone();
+        all_votes.extend(votes(&outputs));
+
+        states.push(driver.round_state().clone());
+        signed.push(all_votes);
+    }
+
+    assert_eq!(driver.to_wal_entries(), inputs);
+    assert_eq!(
+        signed.last().unwrap(),
+        &vec![
+            Vote::new_prevote(
+                Height::new(1),
+                Round::new(0),
+                NilOrVal::Val(value.id()),
+                my_addr
+            ),
+            Vote::new_precommit(
+                Height::new(1),
+                Round::new(0),
+                NilOrVal::Val(value.id()),
+                my_addr
+            ),
+        ]
+    );
+
+    for crash_at in 0..=inputs.len() {
+        let mut replayed = new_driver(&vs, my_addr);
+
+        replayed
+            .replay(inputs[..crash_at].iter().cloned())
+            .expect("replay succeeded");
+
+        assert_eq!(
+            replayed.round_state(),
+            &states[crash_at],
+            "state mismatch after crash at step {crash_at}"
+        );
+
+        for vote in &signed[crash_at] {
+            assert!(replayed.has_signed_vote(vote.round(), vote.vote_type()));
+        }
+
+        for input in &inputs[crash_at..] {
+            let outputs = replayed.process(input.clone()).expect("process succeeded");
+
+            for vote in votes(&outputs) {
+                assert!(
+                    !signed[crash_at]
+                        .iter()
+                        .any(|s| s.round() == vote.round() && s.vote_type() == vote.vote_type()),
+                    "vote {vote:?} emitted twice after crash at step {crash_at}"
+                );
+            }
+        }
+
+        assert_eq!(replayed.round_state(), states.last().unwrap());
+    }
+}
+
+// We crashed after signing a nil prevote but before the timeout which triggered it
+// was persisted. After replay, receiving the proposal must not make us prevote again.
+#[test]
+fn driver_replay_refuses_to_sign_twice() {
+    let value = Value::new(9999);
+
+    let [(v1, _sk1), (v2, _sk2), (v3, _sk3)] = make_validators([1, 1, 1]);
+    let my_addr = v2.address;
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+    let mut driver = new_driver(&vs, my_addr);
+
+    driver
+        .replay([
+            new_round_input(Round::new(0), v1.address),
+            prevote_nil_input(&my_addr),
+        ])
+        .expect("replay succeeded");
+
+    assert!(driver.has_signed_vote(Round::new(0), VoteType::Prevote));
+    assert!(!driver.has_signed_vote(Round::new(0), VoteType::Precommit));
+
+    let outputs = driver
+        .process(proposal_input(
+            Round::new(0),
+            value,
+            Round::Nil,
+            Validity::Valid,
+            v1.address,
+        ))
+        .expect("process succeeded");
+
+    assert!(votes(&outputs).is_empty());
+}
+
+#[test]
+fn driver_wal_entries_reset_on_new_height() {
+    let [(v1, _sk1), (v2, _sk2)] = make_validators([1, 1]);
+    let my_addr = v2.address;
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone()]);
+
+    let mut driver = new_driver(&vs, my_addr);
+
+    driver
+        .process(new_round_input(Round::new(0), v1.address))
+        .expect("process succeeded");
+    driver
+        .process(prevote_nil_input(&my_addr))
+        .expect("process succeeded");
+
+    assert_eq!(driver.to_wal_entries().len(), 2);
+    assert!(driver.has_signed_vote(Round::new(0), VoteType::Prevote));
+
+    driver
+        .move_to_next_height(vs)
+        .expect("move to height succeeded");
+
+    assert_eq!(driver.height(), Height::new(2));
+
+    assert!(driver.to_wal_entries().is_empty());
+    assert!(!driver.has_signed_vote(Round::new(0), VoteType::Prevote));
+}
+
+// Replaying a full round in which we proposed, prevoted and precommitted a value
+// must not yield any proposal or vote, but must still yield the decision.
+#[test]
+fn driver_apply_replay_suppresses_proposals_and_votes() {
+    let value = Value::new(9999);
+
+    let [(v1, _sk1), (v2, _sk2), (v3, _sk3)] = make_validators([1, 1, 1]);
+    let my_addr = v1.address;
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+    let inputs = vec![
+        new_round_input(Round::new(0), my_addr),
+        Input::ProposeValue(Round::new(0), value),
+        proposal_input(Round::new(0), value, Round::Nil, Validity::Valid, my_addr),
+        prevote_input(value, &my_addr),
+        prevote_input(value, &v2.address),
+        prevote_input(value, &v3.address),
+        precommit_input(Round::new(0), value, &my_addr),
+        precommit_input(Round::new(0), value, &v2.address),
+        precommit_input(Round::new(0), value, &v3.address),
+    ];
+
+    let mut driver = new_driver(&vs, my_addr);
+
+    let mut outputs = Vec::new();
+    for input in inputs {
+        outputs.extend(driver.apply_replay(input).expect("replay succeeded"));
+    }
+
+    assert!(!outputs.iter().any(|output| matches!(
+        output,
+        Output::Propose(_) | Output::Vote(_) | Output::GetValue(..)
+    )));
+
+    assert!(outputs
+        .iter()
+        .any(|output| matches!(output, Output::Decide(round, _, _) if *round == Round::new(0))));
+
+    assert!(driver.has_signed_vote(Round::new(0), VoteType::Prevote));
+    assert!(driver.has_signed_vote(Round::new(0), VoteType::Precommit));
+}
+
+#[test]
+fn driver_wal_entries_not_recorded_by_default() {
+    let [(v1, sk1), (v2, _sk2)] = make_validators([1, 1]);
+    let my_addr = v2.address;
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone()]);
+
+    let ctx = TestContext::new(sk1);
+    let mut driver = Driver::new(ctx, Height::new(1), vs, my_addr, Default::default());
+
+    driver
+        .process(new_round_input(Round::new(0), v1.address))
+        .expect("process succeeded");
+    driver
+        .process(prevote_nil_input(&my_addr))
+        .expect("process succeeded");
+
+    assert!(driver.to_wal_entries().is_empty());
+
+    // Our own votes are still tracked, so that we never sign another one
+    assert!(driver.has_signed_vote(Round::new(0), VoteType::Prevote));
+}
@@ -0,0 +1,249 @@
+#![cfg(feature = "serde")]
+#![allow(clippy::needless_update)]
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use malachitebft_core_state_machine::output::Output as RoundOutput;
+use malachitebft_core_state_machine::state::{RoundValue, State, Step};
+use malachitebft_core_state_machine::transition::Transition;
+use malachitebft_core_types::{NilOrVal, Round, Timeout, TimeoutKind, Validity};
+
+use malachitebft_core_driver_test_utils::*;
+use malachitebft_test::utils::validators::make_validators;
+use malachitebft_test::{Height, Proposal, TestContext, ValidatorSet, Value};
+
+use informalsystems_malachitebft_core_driver::{Driver, Input, Output};
+
+fn round_trip<T>(value: &T) -> T
+where
+    T: Serialize + DeserializeOwned,
+{
+    let json = serde_json::to_string(value).expect("serialization succeeded");
+    serde_json::from_str(&json).expect("deserialization succeeded")
+}
+
+#[test]
+fn serde_round() {
+    assert_eq!(serde_json::to_string(&Round::Nil).unwrap(), "-1");
+    assert_eq!(serde_json::to_string(&Round::new(0)).unwrap(), "0");
+    assert_eq!(serde_json::to_string(&Round::new(42)).unwrap(), "42");
+
+    for round in [
+        Round::Nil,
+        Round::new(0),
+        Round::new(1),
+        Round::new(u32::MAX),
+    ] {
+        assert_eq!(round_trip(&round), round);
+    }
+
+    assert!(serde_json::from_str::<Round>("-2").is_err());
+    assert!(serde_json::from_str::<Round>(&(u64::from(u32::MAX) + 1).to_string()).is_err());
+}
+
+#[test]
+fn serde_state() {
+    let value = Value::new(9999);
+
+    let state = State::<TestContext> {
+        height: Height::new(1),
+        round: Round::new(2),
+        step: Step::Precommit,
+        locked: Some(RoundValue::new(value, Round::new(1))),
+        valid: Some(RoundValue::new(value, Round::new(2))),
+        decision: None,
+        ..Default::default()
+    };
+
+    assert_eq!(round_trip(&state), state);
+    assert_eq!(
+        round_trip(&State::<TestContext>::default()),
+        State::default()
+    );
+}
+
+#[test]
+fn serde_transition() {
+    let value = Value::new(9999);
+    let [(v1, _)] = make_validators([1]);
+
+    let state = State::<TestContext> {
+        height: Height::new(1),
+        round: Round::new(2),
+        step: Step::Precommit,
+        locked: Some(RoundValue::new(value, Round::new(2))),
+        valid: Some(RoundValue::new(value, Round::new(2))),
+        decision: None,
+        ..Default::default()
+    };
+
+    let transitions = vec![
+        Transition::to(state.clone()).with_output(RoundOutput::precommit(
+            Height::new(1),
+            Round::new(2),
+            NilOrVal::Val(value.id()),
+            v1.address,
+        )),
+        Transition::to(state.clone()).with_output(RoundOutput::proposal(
+            Height::new(1),
+            Round::new(2),
+            value,
+            Round::new(1),
+            v1.address,
+        )),
+        Transition::to(state.clone()).with_output(RoundOutput::schedule_timeout(
+            Round::new(2),
+            TimeoutKind::Precommit,
+        )),
+        Transition::invalid(state),
+    ];
+
+    for transition in transitions {
+        assert_eq!(round_trip(&transition), transition);
+    }
+}
+
+#[test]
+fn serde_input() {
+    let value = Value::new(9999);
+    let [(v1, _), (v2, _)] = make_validators([1, 1]);
+
+    let inputs = vec![
+        new_round_input(Round::new(0), v1.address),
+        Input::ProposeValue(Round::new(0), value),
+        proposal_input(
+            Round::new(1),
+            value,
+            Round::new(0),
+            Validity::Valid,
+            v1.address,
+        ),
+        prevote_input(value, &v2.address),
+        prevote_nil_input(&v2.address),
+        precommit_input(Round::new(0), value, &v2.address),
+        timeout_propose_input(Round::new(3)),
+    ];
+
+    for input in inputs {
+        assert_eq!(round_trip(&input), input);
+    }
+}
+
+#[test]
+fn serde_output() {
+    let value = Value::new(9999);
+    let [(v1, _), (v2, _)] = make_validators([1, 1]);
+    let proposal = Proposal::new(Height::new(1), Round::new(0), value, Round::Nil, v1.address);
+
+    let outputs = vec![
+        new_round_output(Round::new(0)),
+        proposal_output(Round::new(0), value, Round::Nil, v1.address),
+        prevote_output(Round::new(0), value, &v2.address),
+        precommit_nil_output(Round::new(1), &v2.address),
+        decide_output(Round::new(1), proposal, &[v1.address, v2.address]),
+        start_prevote_timer_output(Round::new(0)),
+        Output::GetValue(
+            Height::new(1),
+            Round::new(0),
+            Timeout::propose(Round::new(0)),
+        ),
+    ];
+
+    for output in outputs {
+        assert_eq!(round_trip(&output), output);
+    }
+
+    // The certificate carried in the decision must also survive the round-trip
+    let Output::Decide(_, _, certificate) = decide_output(
+        Round::new(0),
+        Proposal::new(Height::new(1), Round::new(0), value, Round::Nil, v1.address),
+        &[v1.address],
+    ) else {
+        unreachable!()
+    };
+
+    assert_eq!(certificate.value_id, value.id());
+    assert_eq!(round_trip(&certificate), certificate);
+    assert_eq!(
+        round_trip(&NilOrVal::Val(value.id())),
+        NilOrVal::Val(value.id())
+    );
+}
+
+#[test]
+fn serde_round_tally_snapshot() {
+    let value = Value::new(9999);
+
+    let validators = make_validators([1, 1, 1, 1]);
+    let vs = ValidatorSet::new(validators.iter().map(|(v, _)| v.clone()));
+    let [a, b, c, d] = [0, 1, 2, 3].map(|i| vs.validators[i].address);
+
+    let ctx = TestContext::new(validators[0].1.clone());
+    let mut driver = Driver::new(ctx, Height::new(1), vs, d, Default::default());
+
+    // Mid-round, we have mixed prevotes for nil and for the value, and only some precommits
+    let inputs = vec![
+        new_round_input(Round::new(0), a),
+        prevote_input(value, &a),
+        prevote_input(value, &b),
+        prevote_nil_input(&c),
+        prevote_input(value, &d),
+        precommit_input(Round::new(0), value, &a),
+        precommit_nil_input(Round::new(0), &c),
+        prevote_nil_input_at(Round::new(1), &b),
+    ];
+
+    for input in inputs {
+        driver.process(input).unwrap();
+    }
+
+    let tallies = driver.tallies();
+
+    assert_eq!(
+        serde_json::to_value(&tallies).unwrap(),
+        serde_json::json!([
+            {
+                "round": 0,
+                "total_weight": 4,
+                "validators": [
+                    { "address": a, "voting_power": 1, "prevote": { "Val": 9999 }, "precommit": { "Val": 9999 } },
+                    { "address": b, "voting_power": 1, "prevote": { "Val": 9999 }, "precommit": null },
+                    { "address": c, "voting_power": 1, "prevote": "Nil", "precommit": "Nil" },
+                    { "address": d, "voting_power": 1, "prevote": { "Val": 9999 }, "precommit": null },
+                ],
+                "prevotes": [
+                    { "value": "Nil", "weight": 1 },
+                    { "value": { "Val": 9999 }, "weight": 3 },
+                ],
+                "precommits": [
+                    { "value": "Nil", "weight": 1 },
+                    { "value": { "Val": 9999 }, "weight": 1 },
+                ],
+                "thresholds": ["PolkaAny", { "PolkaValue": 9999 }],
+            },
+            {
+                "round": 1,
+                "total_weight": 4,
+                "validators": [
+                    { "address": a, "voting_power": 1, "prevote": null, "precommit": null },
+                    { "address": b, "voting_power": 1, "prevote": "Nil", "precommit": null },
+                    { "address": c, "voting_power": 1, "prevote": null, "precommit": null },
+                    { "address": d, "voting_power": 1, "prevote": null, "precommit": null },
+                ],
+                "prevotes": [{ "value": "Nil", "weight": 1 }],
+                "precommits": [],
+                "thresholds": [],
+            },
+        ])
+    );
+
+    assert_eq!(tallies[0].prevote_weight(), 4);
+    assert_eq!(tallies[0].precommit_weight(), 2);
+    assert_eq!(driver.round_tally(Round::new(1)).as_ref(), tallies.get(1));
+    assert_eq!(driver.round_tally(Round::new(2)), None);
+
+    for tally in tallies {
+        assert_eq!(round_trip(&tally), tally);
+    }
+}
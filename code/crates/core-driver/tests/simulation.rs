@@ -0,0 +1,111 @@
+use malachitebft_core_types::{Round, TimeoutKind};
+use malachitebft_test::Value;
+
+use malachitebft_core_driver_test_utils::simulation::*;
+
+// Same scenario as `driver_steps_not_proposer_valid`
+//
+// v1=1, v2=2, v3=3, we are v2, v1 is the proposer
+#[test]
+fn simulation_not_proposer_valid() {
+    let value = Value::new(9999);
+    let round = Round::new(0);
+
+    let mut sim = Simulation::with_nodes([1, 2, 3], [1]);
+    assert_eq!(sim.proposer(round), 0);
+
+    sim.start().run([
+        proposal(0, round, value),
+        prevote(0, round, value),
+        prevote(2, round, value),
+        precommit(0, round, value),
+        precommit(2, round, value),
+    ]);
+
+    let node = sim.node(1);
+    assert!(node.prevoted_in(round, value));
+    assert!(node.precommitted_in(round, value));
+    assert!(node.locked_at(round));
+    assert!(node.decided(value));
+}
+
+// Same scenario as `driver_steps_polka_previous_new_proposal`, followed by a polka for the new value
+// in round 1, upon which we change our lock to the new value.
+//
+// v1=2, v2=3, v3=2, we are v3
+#[test]
+fn simulation_lock_change() {
+    let value = Value::new(9999);
+    let other_value = Value::new(8888);
+    let (r0, r1) = (Round::new(0), Round::new(1));
+
+    let mut sim = Simulation::with_nodes([2, 3, 2], [2]);
+    assert_eq!(sim.proposer(r0), 0);
+    assert_eq!(sim.proposer(r1), 1);
+
+    // Lock on the value in round 0
+    sim.start().run([
+        proposal(0, r0, value),
+        prevote(0, r0, value),
+        prevote(1, r0, value),
+    ]);
+
+    assert!(sim.node(2).precommitted_in(r0, value));
+    assert!(sim.node(2).locked_at(r0));
+
+    // Skip to round 1, where we do not prevote for another value while locked
+    sim.run([prevote(1, r1, other_value), proposal(1, r1, other_value)]);
+
+    assert_eq!(sim.node(2).driver().round(), r1);
+    assert!(sim.node(2).prevoted_nil_in(r1));
+    assert!(sim.node(2).locked_at(r0));
+
+    // A polka for the other value in round 1 moves our lock to it
+    sim.run([prevote(0, r1, other_value)]);
+
+    let node = sim.node(2);
+    assert!(node.precommitted_in(r1, other_value));
+    assert!(node.locked_at(r1));
+    assert_eq!(node.locked().unwrap().value, other_value);
+}
+
+#[test]
+fn simulation_network_decides() {
+    let mut sim = Simulation::new([1, 1, 1, 1]);
+    sim.start();
+
+    let value = sim.value_of(sim.proposer(Round::new(0)));
+
+    assert_eq!(sim.nodes().count(), 4);
+    assert!(sim.nodes().all(|node| node.decided(value)));
+}
+
+#[test]
+fn simulation_network_decides_with_one_validator_offline() {
+    let mut sim = Simulation::with_nodes([1, 1, 1, 1], [0, 1, 2]);
+    sim.start();
+
+    let value = sim.value_of(sim.proposer(Round::new(0)));
+
+    assert!(sim.nodes().all(|node| node.decided(value)));
+}
+
+#[test]
+fn simulation_network_moves_on_when_proposer_is_offline() {
+    let (r0, r1) = (Round::new(0), Round::new(1));
+
+    let mut sim = Simulation::with_nodes([1, 1, 1, 1], [1, 2, 3]);
+    assert_eq!(sim.proposer(r0), 0);
+
+    sim.start().run([timeout(TimeoutKind::Propose, r0)]);
+
+    assert!(sim.nodes().all(|node| node.prevoted_nil_in(r0)));
+    assert!(sim.nodes().all(|node| node.precommitted_nil_in(r0)));
+    assert!(sim.nodes().all(|node| node.decision().is_none()));
+
+    sim.run([timeout(TimeoutKind::Precommit, r0)]);
+
+    let value = sim.value_of(sim.proposer(r1));
+
+    assert!(sim.nodes().all(|node| node.decided(value)));
+}
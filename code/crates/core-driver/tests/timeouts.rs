@@ -0,0 +1,128 @@
+use malachitebft_core_types::{Round, Timeout, Validity};
+
+use malachitebft_core_driver_test_utils::*;
+use malachitebft_test::{TestContext, Value};
+
+use informalsystems_malachitebft_core_driver::{Driver, Input, Output};
+
+fn process_all(driver: &mut Driver<TestContext>, inputs: Vec<Input<TestContext>>) {
+    for input in inputs {
+        driver.process(input).unwrap();
+    }
+}
+
+// Check that the propose, prevote and precommit timeouts of the given round are ignored
+// without changing the state of the driver.
+fn assert_stale_timeouts_ignored(driver: &mut Driver<TestContext>, round: Round) {
+    let (current_round, current_step) = (driver.round(), driver.step());
+
+    let timeouts = [
+        Timeout::propose(round),
+        Timeout::prevote(round),
+        Timeout::precommit(round),
+    ];
+
+    for timeout in timeouts {
+        assert!(driver.is_stale_timeout(&timeout), "{timeout:?} is stale");
+
+        let outputs = driver.process(Input::TimeoutElapsed(timeout)).unwrap();
+
+        assert_eq!(outputs, vec![], "{timeout:?} is ignored");
+        assert_eq!(driver.round(), current_round);
+        assert_eq!(driver.step(), current_step);
+    }
+}
+
+#[test]
+fn stale_timeouts_after_round_skip() {
+    let value = Value::new(9999);
+
+    let (mut driver, [v1, v2, v3, me]) = setup_driver();
+
+    process_all(
+        &mut driver,
+        vec![
+            new_round_input(Round::new(0), v1),
+            timeout_propose_input(Round::new(0)),
+            prevote_input(value, &v1),
+            prevote_input(value, &v2),
+            prevote_input(value, &v3),
+        ],
+    );
+
+    assert!(driver.step_is_prevote());
+
+    // Receive votes for round 2 from f+1 validators, skip to round 2
+    let outputs = driver
+        .process(prevote_input_at(Round::new(2), value, &v1))
+        .unwrap();
+    assert_eq!(outputs, vec![]);
+
+    let outputs = driver
+        .process(prevote_input_at(Round::new(2), value, &v3))
+        .unwrap();
+    assert_eq!(outputs, vec![new_round_output(Round::new(2))]);
+
+    let outputs = driver.process(new_round_input(Round::new(2), v1)).unwrap();
+    assert_eq!(outputs, vec![start_propose_timer_output(Round::new(2))]);
+
+    // The timers of round 0 elapse after the round skip
+    assert_stale_timeouts_ignored(&mut driver, Round::new(0));
+
+    // The prevote and precommit timeouts of the current round do not apply in the propose step
+    assert!(driver.is_stale_timeout(&Timeout::prevote(Round::new(2))));
+    assert!(!driver.is_stale_timeout(&Timeout::precommit(Round::new(2))));
+
+    // The propose timeout for the current round is still applied
+    let outputs = driver
+        .process(timeout_propose_input(Round::new(2)))
+        .unwrap();
+    assert_eq!(outputs, vec![prevote_nil_output(Round::new(2), &me)]);
+
+    // Once we prevoted, the propose timeout is stale
+    assert_stale_timeouts_ignored(&mut driver, Round::new(0));
+    assert!(driver.is_stale_timeout(&Timeout::propose(Round::new(2))));
+    assert_eq!(
+        driver
+            .process(timeout_propose_input(Round::new(2)))
+            .unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn stale_timeouts_after_decision() {
+    let value = Value::new(9999);
+
+    let (mut driver, [v1, v2, v3, _me]) = setup_driver();
+
+    process_all(
+        &mut driver,
+        vec![
+            new_round_input(Round::new(0), v1),
+            precommit_input(Round::new(0), value, &v1),
+            precommit_input(Round::new(0), value, &v2),
+            precommit_input(Round::new(0), value, &v3),
+        ],
+    );
+
+    let outputs = driver
+        .process(proposal_input(
+            Round::new(0),
+            value,
+            Round::Nil,
+            Validity::Valid,
+            v1,
+        ))
+        .unwrap();
+
+    assert!(outputs
+        .iter()
+        .any(|output| matches!(output, Output::Decide(round, _, _) if *round == Round::new(0))));
+    assert!(driver.step_is_commit());
+
+    // The timers of the decided round elapse after the decision
+    assert_stale_timeouts_ignored(&mut driver, Round::new(0));
+
+    assert!(driver.step_is_commit());
+}
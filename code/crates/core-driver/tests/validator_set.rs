@@ -0,0 +1,86 @@
+use malachitebft_core_types::{Round, Validity};
+
+use malachitebft_core_driver_test_utils::*;
+use malachitebft_test::utils::validators::make_validators;
+use malachitebft_test::{Height, TestContext, ValidatorSet, Value};
+
+use informalsystems_malachitebft_core_driver::{Driver, Error};
+
+#[test]
+fn driver_update_validator_set_in_the_middle_of_a_height() {
+    let value = Value::new(9999);
+
+    let [(v1, _sk1), (v2, _sk2), (v3, _sk3), (v4, sk4)] = make_validators([1, 1, 1, 1]);
+    let old_set = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone(), v4.clone()]);
+    let new_set = ValidatorSet::new(vec![v2.clone(), v3.clone(), v4.clone()]);
+
+    // We are v4, and v1 is the proposer of round 0
+    let ctx = TestContext::new(sk4);
+    let mut driver = Driver::new(ctx, Height::new(1), old_set, v4.address, Default::default());
+
+    let proposal = proposal_input(
+        Round::new(0),
+        value,
+        Round::Nil,
+        Validity::Valid,
+        v1.address,
+    );
+
+    for input in [
+        new_round_input(Round::new(0), v1.address),
+        proposal,
+        prevote_input(value, &v1.address),
+        prevote_input(value, &v2.address),
+    ] {
+        driver.process(input).unwrap();
+    }
+
+    // v1 is rotated out of the validator set
+    driver.update_validator_set(new_set.clone()).unwrap();
+
+    assert_eq!(driver.validator_set(), &new_set);
+    assert_eq!(driver.votes().validator_set(), &new_set);
+    assert_eq!(driver.proposals().validator_set(), &new_set);
+
+    // v1 was the proposer of the current round, which is selected again from the new validator set
+    let proposer = driver.get_proposer().unwrap().address;
+    assert_ne!(proposer, v1.address);
+
+    // The proposal of v1 is quarantined
+    assert_eq!(driver.proposals().quarantined().len(), 1);
+    assert!(driver
+        .proposals()
+        .get_proposal_and_validity_for_round(Round::new(0))
+        .is_none());
+
+    // The prevote of v1 is dropped, while the one of v2 is kept
+    let received = driver
+        .votes()
+        .per_round(Round::new(0))
+        .unwrap()
+        .received_votes();
+
+    assert_eq!(received.len(), 1);
+    assert!(received
+        .iter()
+        .all(|vote| vote.validator_address == v2.address));
+
+    // Proposers of the following rounds are selected from the new validator set
+    for round in Round::range(Round::new(1), Round::new(6)) {
+        let proposer = driver.select_proposer(Height::new(1), round);
+        assert_ne!(proposer.address, v1.address);
+    }
+
+    // Votes from v1 are now rejected
+    assert!(matches!(
+        driver.process(prevote_input(value, &v1.address)),
+        Err(Error::InvalidVote(_))
+    ));
+
+    // v2, v3 and ourselves make up a quorum of the new validator set
+    let outputs = driver.process(prevote_input(value, &v3.address)).unwrap();
+    assert!(outputs.is_empty());
+
+    let outputs = driver.process(prevote_input(value, &v4.address)).unwrap();
+    assert_eq!(outputs, vec![start_prevote_timer_output(Round::new(0))]);
+}
@@ -6,6 +6,16 @@ use malachitebft_core_types::{Context, NilOrVal, Round, Timeout, TimeoutKind, Va
 
 /// Output of the round state machine.
 #[derive_where(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Ctx::Height: serde::Serialize, Ctx::Proposal: serde::Serialize, \
+                     Ctx::Vote: serde::Serialize",
+        deserialize = "Ctx::Height: serde::Deserialize<'de>, Ctx::Proposal: serde::Deserialize<'de>, \
+                       Ctx::Vote: serde::Deserialize<'de>",
+    ))
+)]
 pub enum Output<Ctx>
 where
     Ctx: Context,
@@ -30,6 +40,21 @@ where
     Decision(Round, Ctx::Proposal),
 }
 
+/// Why the state machine prevoted nil.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PrevoteReason {
+    /// The proposal is valid but we are locked on a different value at the given round,
+    /// which is higher than the POL round of the proposal, if any.
+    LockedOnDifferentValue(Round),
+
+    /// The proposal is invalid.
+    InvalidProposal,
+
+    /// We did not receive a proposal before the propose timeout elapsed.
+    TimeoutPropose,
+}
+
 impl<Ctx: Context> Output<Ctx> {
     /// Build a `Proposal` output.
     pub fn proposal(
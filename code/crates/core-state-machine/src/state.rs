@@ -13,6 +13,7 @@ use malachitebft_core_types::{Context, Round};
 
 /// A value and its associated round
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RoundValue<Value> {
     /// The value
     pub value: Value,
@@ -29,6 +30,7 @@ impl<Value> RoundValue<Value> {
 
 /// The step of consensus in this round
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Step {
     /// The round has not started yet
     Unstarted,
@@ -49,6 +51,14 @@ pub enum Step {
 
 /// The state of the consensus state machine
 #[derive_where(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Ctx::Height: serde::Serialize, Ctx::Value: serde::Serialize",
+        deserialize = "Ctx::Height: serde::Deserialize<'de>, Ctx::Value: serde::Deserialize<'de>",
+    ))
+)]
 pub struct State<Ctx>
 where
     Ctx: Context,
@@ -74,6 +84,7 @@ where
     /// Buffer with traces of tendermint algorithm lines,
     #[cfg(feature = "debug")]
     #[derive_where(skip)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub traces: alloc::vec::Vec<Trace<Ctx>>,
 }
 
@@ -4,7 +4,7 @@ use malachitebft_core_types::{Context, NilOrVal, Proposal, Round, TimeoutKind, V
 
 use crate::debug_trace;
 use crate::input::Input;
-use crate::output::Output;
+use crate::output::{Output, PrevoteReason};
 use crate::state::{State, Step};
 use crate::transition::Transition;
 
@@ -122,7 +122,9 @@ where
         }
 
         // L22 with invalid proposal
-        (Step::Propose, Input::InvalidProposal) if this_round => prevote_nil(state, info.address),
+        (Step::Propose, Input::InvalidProposal) if this_round => {
+            prevote_nil(state, info.address, PrevoteReason::InvalidProposal)
+        }
 
         // L28 with valid proposal
         (Step::Propose, Input::ProposalAndPolkaPrevious(proposal))
@@ -139,7 +141,7 @@ where
             debug_trace!(state, Line::L28InvalidProposal);
             debug_trace!(state, Line::L32InvalidValue);
 
-            prevote_nil(state, info.address)
+            prevote_nil(state, info.address, PrevoteReason::InvalidProposal)
         }
 
         // L57
@@ -147,7 +149,7 @@ where
         (Step::Propose, Input::TimeoutPropose) if this_round && info.is_proposer() => {
             debug_trace!(state, Line::L59Proposer);
 
-            prevote_nil(state, info.address)
+            prevote_nil(state, info.address, PrevoteReason::TimeoutPropose)
         }
 
         // L57
@@ -155,7 +157,7 @@ where
         (Step::Propose, Input::TimeoutPropose) if this_round => {
             debug_trace!(state, Line::L59NonProposer);
 
-            prevote_nil(state, info.address)
+            prevote_nil(state, info.address, PrevoteReason::TimeoutPropose)
         }
 
         //
@@ -338,10 +340,11 @@ where
             debug_trace!(state, Line::L24ValidAndLockedValue);
             NilOrVal::Val(proposed)
         }
-        Some(_) => {
+        Some(locked) => {
             // locked on a different value
+            let reason = PrevoteReason::LockedOnDifferentValue(locked.round);
             debug_trace!(state, Line::L26ValidAndLockedValue);
-            NilOrVal::Nil
+            return prevote_nil(state, address, reason);
         }
         None => {
             // not locked, prevote the value
@@ -382,10 +385,11 @@ where
             debug_trace!(state, Line::L30ValidLockedValue);
             NilOrVal::Val(proposed)
         }
-        Some(_) => {
+        Some(locked) => {
             // we're locked on a different value in a higher round, prevote nil
+            let reason = PrevoteReason::LockedOnDifferentValue(locked.round);
             debug_trace!(state, Line::L32InvalidValue);
-            NilOrVal::Nil
+            return prevote_nil(state, address, reason);
         }
         None => {
             // not locked, prevote the value
@@ -398,16 +402,23 @@ where
     Transition::to(state.with_step(Step::Prevote)).with_output(output)
 }
 
-/// Received a complete proposal for an empty or invalid value, or timed out; prevote nil.
+/// Received a complete proposal for an empty or invalid value or for a value
+/// conflicting with our lock, or timed out; prevote nil for the given reason.
 ///
-/// Ref: L22/L25, L28/L31, L57
-pub fn prevote_nil<Ctx>(state: State<Ctx>, address: &Ctx::Address) -> Transition<Ctx>
+/// Ref: L22/L25, L22/L26, L28/L31, L28/L32, L57
+pub fn prevote_nil<Ctx>(
+    state: State<Ctx>,
+    address: &Ctx::Address,
+    reason: PrevoteReason,
+) -> Transition<Ctx>
 where
     Ctx: Context,
 {
     let output = Output::prevote(state.height, state.round, NilOrVal::Nil, address.clone());
 
-    Transition::to(state.with_step(Step::Prevote)).with_output(output)
+    Transition::to(state.with_step(Step::Prevote))
+        .with_output(output)
+        .with_nil_prevote_reason(reason)
 }
 
 // ---------------------------------------------------------------------
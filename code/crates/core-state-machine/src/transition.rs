@@ -1,12 +1,24 @@
 //! A transition taken by the state machine after processing an input.
 
+use derive_where::derive_where;
+
 use malachitebft_core_types::Context;
 
-use crate::output::Output;
+use crate::output::{Output, PrevoteReason};
 use crate::state::State;
 
 /// A transition taken by the state machine after processing an input.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive_where(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Ctx::Height: serde::Serialize, Ctx::Value: serde::Serialize, \
+                     Ctx::Proposal: serde::Serialize, Ctx::Vote: serde::Serialize",
+        deserialize = "Ctx::Height: serde::Deserialize<'de>, Ctx::Value: serde::Deserialize<'de>, \
+                       Ctx::Proposal: serde::Deserialize<'de>, Ctx::Vote: serde::Deserialize<'de>",
+    ))
+)]
 pub struct Transition<Ctx>
 where
     Ctx: Context,
@@ -15,6 +27,8 @@ where
     pub next_state: State<Ctx>,
     /// The output to emit.
     pub output: Option<Output<Ctx>>,
+    /// Why we prevoted nil, if the output is our prevote for nil.
+    pub nil_prevote_reason: Option<PrevoteReason>,
     /// Whether the transition is valid or not.
     pub valid: bool,
 }
@@ -28,6 +42,7 @@ where
         Self {
             next_state,
             output: None,
+            nil_prevote_reason: None,
             valid: true,
         }
     }
@@ -37,6 +52,7 @@ where
         Self {
             next_state,
             output: None,
+            nil_prevote_reason: None,
             valid: false,
         }
     }
@@ -46,4 +62,10 @@ where
         self.output = Some(output);
         self
     }
+
+    /// Set the reason why the output of the transition is a prevote for nil.
+    pub fn with_nil_prevote_reason(mut self, reason: PrevoteReason) -> Self {
+        self.nil_prevote_reason = Some(reason);
+        self
+    }
 }
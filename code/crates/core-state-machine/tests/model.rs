@@ -0,0 +1,318 @@
+//! Property-based tests checking that the round state machine upholds
+//! the core invariants of Tendermint, for arbitrary sequences of inputs.
+//!
+//! On failure, proptest shrinks the sequence of inputs down to a minimal
+//! counterexample, which is printed as a list of `r<round>: <input>` lines.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+
+use malachitebft_core_state_machine::input::Input;
+use malachitebft_core_state_machine::output::Output;
+use malachitebft_core_state_machine::state::{RoundValue, State, Step};
+use malachitebft_core_state_machine::state_machine::Info;
+use malachitebft_core_types::{NilOrVal, Round, Vote as _, VoteType};
+use malachitebft_test::{Address, Height, Proposal, TestContext, Value, ValueId};
+
+const ADDRESS: Address = Address::new([42; 20]);
+const OTHER: Address = Address::new([43; 20]);
+
+const MAX_ROUND: u32 = 4;
+const MAX_VALUE: u64 = 3;
+
+/// We are the proposer in even rounds, the other validator in odd rounds
+fn proposer(round: Round) -> &'static Address {
+    match round.as_u32() {
+        Some(r) if r % 2 == 0 => &ADDRESS,
+        _ => &OTHER,
+    }
+}
+
+fn proposal(round: u32, value: u64, pol_round: Round) -> Proposal {
+    Proposal::new(
+        Height::new(1),
+        Round::new(round),
+        Value::new(value),
+        pol_round,
+        *proposer(Round::new(round)),
+    )
+}
+
+/// A compact description of an input, which keeps counterexamples readable
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Action {
+    NewRound,
+    ProposeValue(u64),
+    Proposal(u64),
+    InvalidProposal,
+    ProposalAndPolkaPrevious(u64, u32),
+    InvalidProposalAndPolkaPrevious(u64, u32),
+    PolkaAny,
+    PolkaNil,
+    ProposalAndPolkaCurrent(u64),
+    PrecommitAny,
+    ProposalAndPrecommitValue(u64),
+    PrecommitValue(u64),
+    SkipRound,
+    TimeoutPropose,
+    TimeoutPrevote,
+    TimeoutPrecommit,
+}
+
+impl fmt::Debug for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NewRound => write!(f, "NewRound"),
+            Self::ProposeValue(v) => write!(f, "ProposeValue(v{v})"),
+            Self::Proposal(v) => write!(f, "Proposal(v{v})"),
+            Self::InvalidProposal => write!(f, "InvalidProposal"),
+            Self::ProposalAndPolkaPrevious(v, vr) => {
+                write!(f, "ProposalAndPolkaPrevious(v{v}, vr={vr})")
+            }
+            Self::InvalidProposalAndPolkaPrevious(v, vr) => {
+                write!(f, "InvalidProposalAndPolkaPrevious(v{v}, vr={vr})")
+            }
+            Self::PolkaAny => write!(f, "PolkaAny"),
+            Self::PolkaNil => write!(f, "PolkaNil"),
+            Self::ProposalAndPolkaCurrent(v) => write!(f, "ProposalAndPolkaCurrent(v{v})"),
+            Self::PrecommitAny => write!(f, "PrecommitAny"),
+            Self::ProposalAndPrecommitValue(v) => write!(f, "ProposalAndPrecommitValue(v{v})"),
+            Self::PrecommitValue(v) => write!(f, "PrecommitValue(v{v})"),
+            Self::SkipRound => write!(f, "SkipRound"),
+            Self::TimeoutPropose => write!(f, "TimeoutPropose"),
+            Self::TimeoutPrevote => write!(f, "TimeoutPrevote"),
+            Self::TimeoutPrecommit => write!(f, "TimeoutPrecommit"),
+        }
+    }
+}
+
+/// An input for the given round
+#[derive(Copy, Clone, PartialEq, Eq)]
+struct Event {
+    round: u32,
+    action: Action,
+}
+
+impl fmt::Debug for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "r{}: {:?}", self.round, self.action)
+    }
+}
+
+impl Event {
+    fn input_round(&self) -> Round {
+        Round::new(self.round)
+    }
+
+    fn to_input(self) -> Input<TestContext> {
+        let round = self.round;
+
+        match self.action {
+            Action::NewRound => Input::NewRound(Round::new(round)),
+            Action::ProposeValue(v) => Input::ProposeValue(Value::new(v)),
+            Action::Proposal(v) => Input::Proposal(proposal(round, v, Round::Nil)),
+            Action::InvalidProposal => Input::InvalidProposal,
+            Action::ProposalAndPolkaPrevious(v, vr) => {
+                Input::ProposalAndPolkaPrevious(proposal(round, v, Round::new(vr)))
+            }
+            Action::InvalidProposalAndPolkaPrevious(v, vr) => {
+                Input::InvalidProposalAndPolkaPrevious(proposal(round, v, Round::new(vr)))
+            }
+            Action::PolkaAny => Input::PolkaAny,
+            Action::PolkaNil => Input::PolkaNil,
+            Action::ProposalAndPolkaCurrent(v) => {
+                Input::ProposalAndPolkaCurrent(proposal(round, v, Round::Nil))
+            }
+            Action::PrecommitAny => Input::PrecommitAny,
+            Action::ProposalAndPrecommitValue(v) => {
+                Input::ProposalAndPrecommitValue(proposal(round, v, Round::Nil))
+            }
+            Action::PrecommitValue(v) => Input::PrecommitValue(ValueId::new(v)),
+            Action::SkipRound => Input::SkipRound(Round::new(round)),
+            Action::TimeoutPropose => Input::TimeoutPropose,
+            Action::TimeoutPrevote => Input::TimeoutPrevote,
+            Action::TimeoutPrecommit => Input::TimeoutPrecommit,
+        }
+    }
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    let value = || 0..MAX_VALUE;
+    let pol_round = || 0..MAX_ROUND;
+
+    prop_oneof![
+        Just(Action::NewRound),
+        value().prop_map(Action::ProposeValue),
+        value().prop_map(Action::Proposal),
+        Just(Action::InvalidProposal),
+        (value(), pol_round()).prop_map(|(v, vr)| Action::ProposalAndPolkaPrevious(v, vr)),
+        (value(), pol_round()).prop_map(|(v, vr)| Action::InvalidProposalAndPolkaPrevious(v, vr)),
+        Just(Action::PolkaAny),
+        Just(Action::PolkaNil),
+        value().prop_map(Action::ProposalAndPolkaCurrent),
+        Just(Action::PrecommitAny),
+        value().prop_map(Action::ProposalAndPrecommitValue),
+        value().prop_map(Action::PrecommitValue),
+        Just(Action::SkipRound),
+        Just(Action::TimeoutPropose),
+        Just(Action::TimeoutPrevote),
+        Just(Action::TimeoutPrecommit),
+    ]
+}
+
+fn event() -> impl Strategy<Value = Event> {
+    (0..MAX_ROUND, action()).prop_map(|(round, action)| Event { round, action })
+}
+
+/// Records the outputs emitted by the state machine, per round
+#[derive(Default)]
+struct Recorder {
+    outputs: BTreeMap<Round, Vec<Output<TestContext>>>,
+}
+
+impl Recorder {
+    fn record(&mut self, round: Round, output: Output<TestContext>) {
+        self.outputs.entry(round).or_default().push(output);
+    }
+
+    fn precommits(&self, round: Round) -> impl Iterator<Item = &NilOrVal<ValueId>> {
+        self.outputs
+            .get(&round)
+            .into_iter()
+            .flatten()
+            .filter_map(|output| match output {
+                Output::Vote(vote) if vote.vote_type() == VoteType::Precommit => Some(vote.value()),
+                _ => None,
+            })
+    }
+}
+
+/// Feeds inputs to the state machine the way the driver does,
+/// and checks the invariants on every transition.
+struct Harness {
+    state: State<TestContext>,
+    recorder: Recorder,
+}
+
+impl Harness {
+    fn new() -> Self {
+        Self {
+            state: State::new(Height::new(1), Round::Nil),
+            recorder: Recorder::default(),
+        }
+    }
+
+    /// Whether the driver could ever feed this input to the state machine
+    fn is_feasible(&self, event: &Event) -> bool {
+        match event.action {
+            // The driver only ever starts rounds ahead of the current one
+            Action::NewRound => self.state.round <= event.input_round(),
+
+            // The driver only asks for a value to propose when we are the proposer
+            Action::ProposeValue(_) => proposer(event.input_round()) == &ADDRESS,
+
+            _ => true,
+        }
+    }
+
+    fn apply(&mut self, index: usize, event: Event) -> Result<(), TestCaseError> {
+        if !self.is_feasible(&event) {
+            return Ok(());
+        }
+
+        let input = event.to_input();
+        let info = Info::new(event.input_round(), &ADDRESS, proposer(event.input_round()));
+
+        let prev = self.state.clone();
+        let transition = self.state.clone().apply(&info, input.clone());
+        let next = transition.next_state;
+
+        let ctx = || format!("at #{index} {event:?}, from {prev:?} to {next:?}");
+
+        // Once in the commit step, nothing happens anymore
+        if prev.step == Step::Commit {
+            prop_assert!(transition.output.is_none(), "output after commit {}", ctx());
+            prop_assert_eq!(&prev, &next, "state changed after commit {}", ctx());
+        }
+
+        // Rounds never go backwards, and steps never go backwards within a round
+        prop_assert!(prev.round <= next.round, "round went backwards {}", ctx());
+
+        if prev.round == next.round {
+            prop_assert!(prev.step <= next.step, "step went backwards {}", ctx());
+        } else {
+            prop_assert!(
+                matches!(
+                    input,
+                    Input::NewRound(_) | Input::SkipRound(_) | Input::TimeoutPrecommit
+                ),
+                "round changed without a round skip {}",
+                ctx()
+            );
+        }
+
+        // The lock is only ever set or moved by a polka at a higher round
+        if prev.locked != next.locked {
+            let polka = match &input {
+                Input::ProposalAndPolkaCurrent(proposal) => {
+                    Some(RoundValue::new(proposal.value, prev.round))
+                }
+                _ => None,
+            };
+
+            prop_assert!(
+                next.locked.is_some() && next.locked == polka,
+                "lock changed without a polka {}",
+                ctx()
+            );
+
+            if let Some(locked) = &prev.locked {
+                prop_assert!(
+                    locked.round < prev.round,
+                    "lock changed by a polka at a lower round {}",
+                    ctx()
+                );
+            }
+        }
+
+        if let Some(output) = transition.output {
+            // We never precommit two different values in the same round
+            if let Output::Vote(vote) = &output {
+                if vote.vote_type() == VoteType::Precommit {
+                    let round = vote.round();
+
+                    prop_assert!(
+                        self.recorder.precommits(round).all(|v| v == vote.value()),
+                        "conflicting precommits in round {round} {}",
+                        ctx()
+                    );
+                }
+            }
+
+            self.recorder.record(prev.round, output);
+        }
+
+        self.state = next;
+
+        Ok(())
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2048))]
+
+    #[test]
+    fn state_machine_upholds_invariants(events in prop::collection::vec(event(), 1..64)) {
+        let mut harness = Harness::new();
+
+        // Always start with the first round, otherwise most inputs are ignored
+        harness.apply(0, Event { round: 0, action: Action::NewRound })?;
+
+        for (index, event) in events.into_iter().enumerate() {
+            harness.apply(index + 1, event)?;
+        }
+    }
+}
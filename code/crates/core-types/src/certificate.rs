@@ -1,3 +1,4 @@
+use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 use derive_where::derive_where;
 use thiserror::Error;
@@ -9,6 +10,14 @@ use crate::{
 
 /// Represents a signature for a certificate, including the address and the signature itself.
 #[derive_where(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Ctx::Address: serde::Serialize, Signature<Ctx>: serde::Serialize",
+        deserialize = "Ctx::Address: serde::Deserialize<'de>, Signature<Ctx>: serde::Deserialize<'de>",
+    ))
+)]
 pub struct CommitSignature<Ctx: Context> {
     /// The address associated with the signature.
     pub address: Ctx::Address,
@@ -35,6 +44,14 @@ impl<Ctx: Context> CommitSignature<Ctx> {
 
 /// Aggregated signature.
 #[derive_where(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "CommitSignature<Ctx>: serde::Serialize",
+        deserialize = "CommitSignature<Ctx>: serde::Deserialize<'de>",
+    ))
+)]
 pub struct AggregatedSignature<Ctx: Context> {
     /// A collection of commit signatures.
     pub signatures: Vec<CommitSignature<Ctx>>,
@@ -49,6 +66,16 @@ impl<Ctx: Context> AggregatedSignature<Ctx> {
 
 /// Represents a certificate containing the message (height, round, value_id) and an aggregated signature.
 #[derive_where(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Ctx::Height: serde::Serialize, ValueId<Ctx>: serde::Serialize, \
+                     CommitSignature<Ctx>: serde::Serialize",
+        deserialize = "Ctx::Height: serde::Deserialize<'de>, ValueId<Ctx>: serde::Deserialize<'de>, \
+                       CommitSignature<Ctx>: serde::Deserialize<'de>",
+    ))
+)]
 pub struct CommitCertificate<Ctx: Context> {
     /// The height of the certificate.
     pub height: Ctx::Height,
@@ -97,7 +124,7 @@ impl<Ctx: Context> CommitCertificate<Ctx> {
 }
 
 /// Represents an error that can occur when verifying a certificate.
-#[derive_where(Clone, Debug)]
+#[derive_where(Clone, Debug, PartialEq, Eq)]
 #[derive(Error)]
 pub enum CertificateError<Ctx: Context> {
     /// One of the commit signature is invalid.
@@ -108,6 +135,129 @@ pub enum CertificateError<Ctx: Context> {
     #[error("A validator in the certificate is not in the validator set: {0:?}")]
     UnknownValidator(CommitSignature<Ctx>),
 
+    /// A validator signed the certificate more than once.
+    #[error("A validator signed the certificate more than once: {0:?}")]
+    DuplicateVote(CommitSignature<Ctx>),
+
+    /// Not enough voting power has signed the certificate.
+    #[error(
+        "Not enough voting power has signed the certificate: \
+         signed={signed}, total={total}, expected={expected}"
+    )]
+    NotEnoughVotingPower {
+        /// Signed voting power
+        signed: VotingPower,
+        /// Total voting power
+        total: VotingPower,
+        /// Expected voting power
+        expected: VotingPower,
+    },
+}
+
+/// Represents the signature of a prevote in a polka certificate.
+#[derive_where(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Ctx::Address: serde::Serialize, Signature<Ctx>: serde::Serialize",
+        deserialize = "Ctx::Address: serde::Deserialize<'de>, Signature<Ctx>: serde::Deserialize<'de>",
+    ))
+)]
+pub struct PolkaSignature<Ctx: Context> {
+    /// The address of the validator which signed the prevote.
+    pub address: Ctx::Address,
+    /// The signature of the prevote.
+    pub signature: Signature<Ctx>,
+}
+
+impl<Ctx: Context> PolkaSignature<Ctx> {
+    /// Create a new `PolkaSignature` from an address and a signature.
+    pub fn new(address: Ctx::Address, signature: Signature<Ctx>) -> Self {
+        Self { address, signature }
+    }
+}
+
+/// Represents a certificate that a polka, ie. prevotes from 2/3+ of the voting power,
+/// was reached for a value at a given height and round.
+///
+/// This allows eg. light clients to check that a value was prevoted by a quorum
+/// without having to receive the individual prevotes.
+#[derive_where(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Ctx::Height: serde::Serialize, ValueId<Ctx>: serde::Serialize, \
+                     PolkaSignature<Ctx>: serde::Serialize",
+        deserialize = "Ctx::Height: serde::Deserialize<'de>, ValueId<Ctx>: serde::Deserialize<'de>, \
+                       PolkaSignature<Ctx>: serde::Deserialize<'de>",
+    ))
+)]
+pub struct PolkaCertificate<Ctx: Context> {
+    /// The height of the certificate.
+    pub height: Ctx::Height,
+    /// The round at which the polka was reached.
+    pub round: Round,
+    /// The identifier for the value being certified.
+    pub value_id: ValueId<Ctx>,
+    /// The signatures of the prevotes for the value.
+    pub polka_signatures: Vec<PolkaSignature<Ctx>>,
+}
+
+impl<Ctx: Context> PolkaCertificate<Ctx> {
+    /// Creates a new `PolkaCertificate` from a vector of signed votes.
+    ///
+    /// Only the prevotes for the given value at the given height and round are kept,
+    /// and only the first prevote of each validator.
+    pub fn new(
+        height: Ctx::Height,
+        round: Round,
+        value_id: ValueId<Ctx>,
+        prevotes: Vec<SignedVote<Ctx>>,
+    ) -> Self {
+        let mut signers = BTreeSet::new();
+
+        let polka_signatures = prevotes
+            .into_iter()
+            .filter(|vote| {
+                matches!(vote.value(), NilOrVal::Val(id) if id == &value_id)
+                    && vote.vote_type() == VoteType::Prevote
+                    && vote.round() == round
+                    && vote.height() == height
+            })
+            .filter(|vote| signers.insert(vote.validator_address().clone()))
+            .map(|signed_vote| PolkaSignature {
+                address: signed_vote.validator_address().clone(),
+                signature: signed_vote.signature,
+            })
+            .collect();
+
+        Self {
+            height,
+            round,
+            value_id,
+            polka_signatures,
+        }
+    }
+}
+
+/// Represents an error that can occur when verifying a polka certificate.
+#[derive_where(Clone, Debug, PartialEq, Eq)]
+#[derive(Error)]
+pub enum PolkaCertificateError<Ctx: Context> {
+    /// One of the prevote signatures is invalid.
+    #[error("Invalid prevote signature: {0:?}")]
+    InvalidSignature(PolkaSignature<Ctx>),
+
+    /// A validator in the certificate is not in the validator set.
+    #[error("A validator in the certificate is not in the validator set: {0:?}")]
+    UnknownValidator(PolkaSignature<Ctx>),
+
+    /// A validator signed the certificate more than once.
+    #[error("A validator signed the certificate more than once: {0:?}")]
+    DuplicateVote(PolkaSignature<Ctx>),
+
     /// Not enough voting power has signed the certificate.
     #[error(
         "Not enough voting power has signed the certificate: \
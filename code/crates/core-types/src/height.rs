@@ -1,4 +1,5 @@
 use core::fmt::{Debug, Display};
+use core::str::FromStr;
 
 /// Defines the requirements for a height type.
 ///
@@ -7,15 +8,32 @@ use core::fmt::{Debug, Display};
 /// A height of 0 represents a chain which has not yet produced a block.
 pub trait Height
 where
-    Self:
-        Default + Copy + Clone + Debug + Display + PartialEq + Eq + PartialOrd + Ord + Send + Sync,
+    Self: Default
+        + Copy
+        + Clone
+        + Debug
+        + Display
+        + FromStr
+        + PartialEq
+        + Eq
+        + PartialOrd
+        + Ord
+        + Send
+        + Sync,
 {
+    /// The height of a chain which has not yet produced a block.
+    const ZERO: Self;
+
+    /// The height of the first block of the chain.
+    const INITIAL: Self;
+
     /// Increment the height by one.
     fn increment(&self) -> Self {
         self.increment_by(1)
     }
 
     /// Decrement the height by one.
+    /// Returns None if the height would be decremented below the initial height.
     fn decrement(&self) -> Option<Self> {
         self.decrement_by(1)
     }
@@ -24,7 +42,7 @@ where
     fn increment_by(&self, n: u64) -> Self;
 
     /// Decrement this height by the given amount.
-    /// Returns None if the height would be decremented below its minimum.
+    /// Returns None if the height would be decremented below the initial height.
     fn decrement_by(&self, n: u64) -> Option<Self>;
 
     /// Convert the height to a `u64`.
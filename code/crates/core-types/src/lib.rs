@@ -52,7 +52,10 @@ pub type SignedProposalPart<Ctx> = SignedMessage<Ctx, <Ctx as Context>::Proposal
 /// A signed vote extension
 pub type SignedExtension<Ctx> = SignedMessage<Ctx, Extension>;
 
-pub use certificate::{AggregatedSignature, CertificateError, CommitCertificate, CommitSignature};
+pub use certificate::{
+    AggregatedSignature, CertificateError, CommitCertificate, CommitSignature, PolkaCertificate,
+    PolkaCertificateError, PolkaSignature,
+};
 pub use context::Context;
 pub use height::Height;
 pub use proposal::{Proposal, Validity};
@@ -64,5 +67,5 @@ pub use threshold::{Threshold, ThresholdParam, ThresholdParams};
 pub use timeout::{Timeout, TimeoutKind};
 pub use validator_set::{Address, Validator, ValidatorSet, VotingPower};
 pub use value::{NilOrVal, Value, ValueOrigin};
-pub use vote::{Extension, Vote, VoteType};
+pub use vote::{Extension, ParseVoteTypeError, Vote, VoteType};
 pub use vote_set::VoteSet;
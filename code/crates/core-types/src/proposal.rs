@@ -29,11 +29,15 @@ where
 
 /// Whether or not a proposal is valid.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Validity {
     /// The proposal is valid.
     Valid,
     /// The proposal is invalid.
     Invalid,
+    /// The validity of the proposal cannot be determined yet,
+    /// eg. because it depends on state the application has not finished executing.
+    Unknown,
 }
 
 impl Validity {
@@ -42,6 +46,11 @@ impl Validity {
         self == Validity::Valid
     }
 
+    /// Returns `true` if the validity of the proposal is not known yet.
+    pub fn is_unknown(self) -> bool {
+        self == Validity::Unknown
+    }
+
     /// Returns `Valid` if given true, `Invalid` if given false.
     pub fn from_bool(valid: bool) -> Self {
         if valid {
@@ -5,6 +5,9 @@ use core::{cmp, fmt};
 /// Can be either:
 /// - `Round::Nil` (ie. `-1`)
 /// - `Round::Some(r)` where `r >= 0`
+///
+/// Rounds are ordered by their `i64` representation,
+/// so `Round::Nil` is less than every defined round.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Round {
     /// No round, ie. `-1`
@@ -42,6 +45,17 @@ impl Round {
         }
     }
 
+    /// Convert an `i64` to a round, checking that it is a valid round.
+    ///
+    /// `-1` is converted to `Round::Nil`, and `r` to `Round::Some(r)` if `0 <= r <= u32::MAX`.
+    /// Returns `None` for any other value.
+    pub fn try_from_i64(round: i64) -> Option<Self> {
+        match round {
+            -1 => Some(Round::Nil),
+            r => u32::try_from(r).ok().map(Round::new),
+        }
+    }
+
     /// Whether the round is defined, ie. `r >= 0`.
     pub fn is_defined(&self) -> bool {
         matches!(self, Round::Some(_))
@@ -62,6 +76,38 @@ impl Round {
             Round::Some(r) => Round::new(r + 1),
         }
     }
+
+    /// Subtract `n` from the round, stopping at the initial zero round.
+    ///
+    /// If the round is nil, then it is returned as is.
+    pub fn saturating_sub(&self, n: u32) -> Round {
+        match self {
+            Round::Nil => Round::Nil,
+            Round::Some(r) => Round::new(r.saturating_sub(n)),
+        }
+    }
+
+    /// Iterate over the rounds starting at the given one, in increasing order,
+    /// up to and including `Round::Some(u32::MAX)`.
+    ///
+    /// If the given round is nil, then the iteration starts with `Round::Nil`.
+    pub fn iter_from(round: Round) -> impl Iterator<Item = Round> {
+        core::iter::successors(Some(round), |round| match round {
+            Round::Nil => Some(Round::new(0)),
+            Round::Some(r) => r.checked_add(1).map(Round::new),
+        })
+    }
+
+    /// Iterate over the defined rounds from `start` (inclusive) to `end` (exclusive),
+    /// in increasing order.
+    ///
+    /// `Round::Nil` is never yielded, so a nil `start` is the same as starting at the zero round.
+    /// If `start` is not lower than `end`, then the iterator is empty.
+    pub fn range(start: Round, end: Round) -> impl Iterator<Item = Round> {
+        Round::iter_from(start)
+            .filter(Round::is_defined)
+            .take_while(move |round| *round < end)
+    }
 }
 
 impl From<u32> for Round {
@@ -91,6 +137,30 @@ impl From<i64> for Round {
     }
 }
 
+/// Rounds are serialized as their `i64` representation, ie. `-1` for `Round::Nil`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Round {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_i64(self.as_i64())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Round {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let r = i64::deserialize(deserializer)?;
+        Round::try_from_i64(r).ok_or_else(|| D::Error::custom(format_args!("invalid round: {r}")))
+    }
+}
+
 impl PartialOrd for Round {
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
         Some(self.cmp(other))
@@ -138,4 +208,102 @@ mod tests {
         assert!(Round::Some(1).is_defined());
         assert!(Round::Some(2).is_defined());
     }
+
+    #[test]
+    fn test_saturating_sub() {
+        assert_eq!(Round::Nil.saturating_sub(1), Round::Nil);
+        assert_eq!(Round::new(0).saturating_sub(0), Round::new(0));
+        assert_eq!(Round::new(5).saturating_sub(2), Round::new(3));
+        assert_eq!(Round::new(5).saturating_sub(5), Round::new(0));
+        assert_eq!(Round::new(5).saturating_sub(u32::MAX), Round::new(0));
+    }
+
+    #[test]
+    fn test_iter_from() {
+        let mut iter = Round::iter_from(Round::Nil);
+        assert_eq!(iter.next(), Some(Round::Nil));
+        assert_eq!(iter.next(), Some(Round::new(0)));
+        assert_eq!(iter.next(), Some(Round::new(1)));
+
+        let mut iter = Round::iter_from(Round::new(u32::MAX - 1));
+        assert_eq!(iter.next(), Some(Round::new(u32::MAX - 1)));
+        assert_eq!(iter.next(), Some(Round::new(u32::MAX)));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_range() {
+        let range = |start, end| Round::range(start, end).collect::<Vec<_>>();
+
+        assert_eq!(
+            range(Round::new(1), Round::new(4)),
+            [1, 2, 3].map(Round::new)
+        );
+        assert_eq!(range(Round::Nil, Round::new(2)), [0, 1].map(Round::new));
+
+        assert!(range(Round::new(2), Round::new(2)).is_empty());
+        assert!(range(Round::new(3), Round::new(1)).is_empty());
+        assert!(range(Round::new(0), Round::Nil).is_empty());
+        assert!(range(Round::Nil, Round::Nil).is_empty());
+
+        assert_eq!(
+            range(Round::new(u32::MAX - 1), Round::new(u32::MAX)),
+            [Round::new(u32::MAX - 1)]
+        );
+    }
+
+    #[test]
+    fn test_nil_is_less_than_defined_rounds() {
+        for r in [0, 1, u32::MAX] {
+            assert!(Round::Nil < Round::new(r));
+            assert_eq!(Round::Nil.min(Round::new(r)), Round::Nil);
+            assert_eq!(Round::Nil.max(Round::new(r)), Round::new(r));
+        }
+
+        assert_eq!(Round::new(1).min(Round::new(2)), Round::new(1));
+        assert_eq!(Round::new(1).max(Round::new(2)), Round::new(2));
+        assert_eq!(Round::Nil.max(Round::Nil), Round::Nil);
+    }
+
+    #[test]
+    fn test_try_from_i64() {
+        assert_eq!(Round::try_from_i64(-1), Some(Round::Nil));
+        assert_eq!(Round::try_from_i64(0), Some(Round::new(0)));
+        assert_eq!(
+            Round::try_from_i64(i64::from(u32::MAX)),
+            Some(Round::new(u32::MAX))
+        );
+
+        assert_eq!(Round::try_from_i64(-2), None);
+        assert_eq!(Round::try_from_i64(-7), None);
+        assert_eq!(Round::try_from_i64(i64::MIN), None);
+        assert_eq!(Round::try_from_i64(i64::from(u32::MAX) + 1), None);
+        assert_eq!(Round::try_from_i64(i64::MAX), None);
+    }
+
+    #[test]
+    fn test_try_from_i64_arbitrary() {
+        // Simple xorshift generator, to go through a wide range of values deterministically
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as i64
+        };
+
+        for _ in 0..10_000 {
+            // Bias half of the values towards the boundaries of the valid range
+            let value = match next() % 4 {
+                0 => next() % 16,
+                1 => i64::from(u32::MAX) - next() % 16,
+                _ => next(),
+            };
+
+            match Round::try_from_i64(value) {
+                Some(round) => assert_eq!(round.as_i64(), value),
+                None => assert!(value < -1 || value > i64::from(u32::MAX)),
+            }
+        }
+    }
 }
@@ -6,6 +6,14 @@ use crate::{Context, Signature};
 
 /// A signed message, ie. a message emitted by a validator and signed by its private key.
 #[derive_where(Clone, Debug, PartialEq, Eq, PartialOrd, Ord; Msg)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Msg: serde::Serialize, Signature<Ctx>: serde::Serialize",
+        deserialize = "Msg: serde::Deserialize<'de>, Signature<Ctx>: serde::Deserialize<'de>",
+    ))
+)]
 pub struct SignedMessage<Ctx, Msg>
 where
     Ctx: Context,
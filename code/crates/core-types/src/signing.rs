@@ -1,9 +1,10 @@
+use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 use core::fmt::{Debug, Display};
 
 use crate::{
-    CertificateError, CommitCertificate, CommitSignature, Context, PublicKey, Signature,
-    SignedMessage, ThresholdParams, VotingPower,
+    CertificateError, CommitCertificate, CommitSignature, Context, NilOrVal, PolkaCertificate,
+    PolkaCertificateError, PublicKey, Signature, SignedMessage, ThresholdParams, VotingPower,
 };
 
 /// A signing scheme that can be used to sign votes and verify such signatures.
@@ -110,6 +111,7 @@ where
     /// Verify the given certificate against the given validator set.
     ///
     /// - For each commit signature in the certificate:
+    ///   - Check that the validator is in the validator set and has not signed twice
     ///   - Reconstruct the signed precommit and verify its signature
     /// - Check that we have 2/3+ of voting power has signed the certificate
     ///
@@ -120,6 +122,21 @@ where
         validator_set: &Ctx::ValidatorSet,
         thresholds: ThresholdParams,
     ) -> Result<(), CertificateError<Ctx>>;
+
+    /// Verify the given polka certificate against the given validator set.
+    ///
+    /// - For each prevote signature in the certificate:
+    ///   - Check that the validator is in the validator set and has not signed twice
+    ///   - Reconstruct the signed prevote and verify its signature
+    /// - Check that we have 2/3+ of voting power has signed the certificate
+    ///
+    /// If any of those steps fail, return a [`PolkaCertificateError`].
+    fn verify_polka_certificate(
+        &self,
+        certificate: &PolkaCertificate<Ctx>,
+        validator_set: &Ctx::ValidatorSet,
+        thresholds: ThresholdParams,
+    ) -> Result<(), PolkaCertificateError<Ctx>>;
 }
 
 impl<Ctx, P> SigningProviderExt<Ctx> for P
@@ -130,6 +147,7 @@ where
     /// Verify the certificate against the given validator set.
     ///
     /// - For each commit signature in the certificate:
+    ///   - Check that the validator is in the validator set and has not signed twice
     ///   - Reconstruct the signed precommit and verify its signature
     /// - Check that we have 2/3+ of voting power has signed the certificate
     ///
@@ -144,6 +162,7 @@ where
 
         let total_voting_power = validator_set.total_voting_power();
         let mut signed_voting_power = 0;
+        let mut signers = BTreeSet::new();
 
         // For each commit signature, reconstruct the signed precommit and verify the signature
         for commit_sig in &certificate.aggregated_signature.signatures {
@@ -152,6 +171,12 @@ where
                 return Err(CertificateError::UnknownValidator(commit_sig.clone()));
             };
 
+            // Abort if the validator already signed the certificate,
+            // so that its voting power is not counted more than once
+            if !signers.insert(&commit_sig.address) {
+                return Err(CertificateError::DuplicateVote(commit_sig.clone()));
+            }
+
             let voting_power = self.verify_commit_signature(certificate, commit_sig, validator)?;
             signed_voting_power += voting_power;
         }
@@ -170,4 +195,66 @@ where
             })
         }
     }
+
+    /// Verify the polka certificate against the given validator set.
+    ///
+    /// - For each prevote signature in the certificate:
+    ///   - Check that the validator is in the validator set and has not signed twice
+    ///   - Reconstruct the signed prevote and verify its signature
+    /// - Check that we have 2/3+ of voting power has signed the certificate
+    ///
+    /// If any of those steps fail, return a [`PolkaCertificateError`].
+    fn verify_polka_certificate(
+        &self,
+        certificate: &PolkaCertificate<Ctx>,
+        validator_set: &Ctx::ValidatorSet,
+        thresholds: ThresholdParams,
+    ) -> Result<(), PolkaCertificateError<Ctx>> {
+        use crate::{Validator, ValidatorSet};
+
+        let total_voting_power = validator_set.total_voting_power();
+        let mut signed_voting_power = 0;
+        let mut signers = BTreeSet::new();
+
+        // For each prevote signature, reconstruct the signed prevote and verify the signature
+        for polka_sig in &certificate.polka_signatures {
+            // Abort if validator not in validator set
+            let Some(validator) = validator_set.get_by_address(&polka_sig.address) else {
+                return Err(PolkaCertificateError::UnknownValidator(polka_sig.clone()));
+            };
+
+            // Abort if the validator already signed the certificate,
+            // so that its voting power is not counted more than once
+            if !signers.insert(&polka_sig.address) {
+                return Err(PolkaCertificateError::DuplicateVote(polka_sig.clone()));
+            }
+
+            let prevote = Ctx::new_prevote(
+                certificate.height,
+                certificate.round,
+                NilOrVal::Val(certificate.value_id.clone()),
+                polka_sig.address.clone(),
+            );
+
+            if !self.verify_signed_vote(&prevote, &polka_sig.signature, validator.public_key()) {
+                return Err(PolkaCertificateError::InvalidSignature(polka_sig.clone()));
+            }
+
+            signed_voting_power += validator.voting_power();
+        }
+
+        // Check if we have 2/3+ voting power
+        if thresholds
+            .quorum
+            .is_met(signed_voting_power, total_voting_power)
+        {
+            Ok(())
+        } else {
+            Err(PolkaCertificateError::NotEnoughVotingPower {
+                signed: signed_voting_power,
+                total: total_voting_power,
+                expected: thresholds.quorum.min_expected(total_voting_power),
+            })
+        }
+    }
 }
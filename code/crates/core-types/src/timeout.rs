@@ -4,6 +4,7 @@ use crate::Round;
 
 /// The timeout type. There may be multiple timeouts running in a given step.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TimeoutKind {
     /// Timeout for the propose step.
     Propose,
@@ -22,10 +23,15 @@ pub enum TimeoutKind {
 
     /// Timeout for the commit step.
     Commit,
+
+    /// Grace period for receiving a late proposal once the prevote timeout has elapsed,
+    /// when we already have a polka for a value but not the proposal itself.
+    ProposalGrace,
 }
 
 /// A timeout for a round step.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Timeout {
     /// The timeout kind.
     pub kind: TimeoutKind,
@@ -68,6 +74,11 @@ impl Timeout {
     pub const fn commit(round: Round) -> Self {
         Self::new(round, TimeoutKind::Commit)
     }
+
+    /// Create a new grace period timeout for receiving a late proposal in the given round.
+    pub const fn proposal_grace(round: Round) -> Self {
+        Self::new(round, TimeoutKind::ProposalGrace)
+    }
 }
 
 impl fmt::Display for Timeout {
@@ -1,11 +1,15 @@
-use core::fmt::Debug;
+use alloc::string::{String, ToString};
+use core::fmt::{self, Debug};
+use core::str::FromStr;
 
 use bytes::Bytes;
+use thiserror::Error;
 
 use crate::{Context, NilOrVal, Round, SignedExtension, Value};
 
 /// A type of vote.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VoteType {
     /// Votes for values which validators observe are valid for a given round.
     Prevote,
@@ -14,9 +18,57 @@ pub enum VoteType {
     Precommit,
 }
 
+impl VoteType {
+    /// The string representation of this vote type, either `prevote` or `precommit`.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            VoteType::Prevote => "prevote",
+            VoteType::Precommit => "precommit",
+        }
+    }
+}
+
+impl fmt::Display for VoteType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<VoteType> for &'static str {
+    fn from(vote_type: VoteType) -> Self {
+        vote_type.as_str()
+    }
+}
+
+impl FromStr for VoteType {
+    type Err = ParseVoteTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "prevote" => Ok(VoteType::Prevote),
+            "precommit" => Ok(VoteType::Precommit),
+            other => Err(ParseVoteTypeError(other.to_string())),
+        }
+    }
+}
+
+impl TryFrom<&str> for VoteType {
+    type Error = ParseVoteTypeError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Error returned when parsing a string which is not a vote type.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("Unknown vote type: {0}, available: prevote, precommit")]
+pub struct ParseVoteTypeError(pub String);
+
 /// Vote extensions allows applications to extend the pre-commit vote with arbitrary data.
 /// This allows applications to force their validators to do more than just validate blocks within consensus.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Extension {
     /// This data is opaque to the consensus algorithm but can contain application-specific information.
     pub data: Bytes,
@@ -76,3 +128,31 @@ where
     /// Extend this vote with an extension, overriding any existing extension.
     fn extend(self, extension: SignedExtension<Ctx>) -> Self;
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+
+    use super::*;
+
+    #[test]
+    fn vote_type_roundtrip() {
+        for vote_type in [VoteType::Prevote, VoteType::Precommit] {
+            assert_eq!(format!("{vote_type}").parse(), Ok(vote_type));
+            assert_eq!(VoteType::try_from(<&str>::from(vote_type)), Ok(vote_type));
+        }
+
+        assert_eq!(VoteType::Prevote.to_string(), "prevote");
+        assert_eq!(VoteType::Precommit.to_string(), "precommit");
+    }
+
+    #[test]
+    fn unknown_vote_type() {
+        for s in ["", "Prevote", "pre-commit", "nil"] {
+            assert_eq!(
+                s.parse::<VoteType>(),
+                Err(ParseVoteTypeError(s.to_string()))
+            );
+        }
+    }
+}
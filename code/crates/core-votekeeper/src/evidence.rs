@@ -41,7 +41,10 @@ where
         debug_assert_eq!(existing.validator_address(), vote.validator_address());
 
         if let Some(evidence) = self.map.get_mut(vote.validator_address()) {
-            evidence.push((existing, vote));
+            // Do not record the same pair of conflicting votes twice
+            if !evidence.contains(&(existing.clone(), vote.clone())) {
+                evidence.push((existing, vote));
+            }
         } else {
             self.map
                 .insert(vote.validator_address().clone(), vec![(existing, vote)]);
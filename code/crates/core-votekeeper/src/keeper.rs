@@ -4,9 +4,11 @@ use derive_where::derive_where;
 use thiserror::Error;
 
 use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
 
 use malachitebft_core_types::{
-    Context, NilOrVal, Round, SignedVote, Validator, ValidatorSet, ValueId, Vote, VoteType,
+    Context, NilOrVal, PolkaCertificate, Round, SignedVote, Validator, ValidatorSet, ValueId, Vote,
+    VoteType,
 };
 
 use crate::evidence::EvidenceMap;
@@ -16,6 +18,7 @@ use crate::{Threshold, ThresholdParams, Weight};
 
 /// Messages emitted by the vote keeper
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Output<Value> {
     /// We have a quorum of prevotes for some value or nil
     PolkaAny,
@@ -32,7 +35,8 @@ pub enum Output<Value> {
     /// We have a quorum of precommits for a specific value
     PrecommitValue(Value),
 
-    /// We have f+1 honest votes for a value at a higher round
+    /// Validators with f+1 weight have voted in rounds higher than ours,
+    /// the smallest of which is the given round
     SkipRound(Round),
 }
 
@@ -71,6 +75,18 @@ where
     },
 }
 
+/// Errors that can be yielded when applying a vote.
+#[derive_where(Clone, Debug, PartialEq, Eq)]
+#[derive(Error)]
+pub enum VoteError<Ctx>
+where
+    Ctx: Context,
+{
+    /// The vote was cast by a validator which is not part of the validator set.
+    #[error("Vote from unknown validator: {0}")]
+    UnknownValidator(Ctx::Address),
+}
+
 impl<Ctx> PerRound<Ctx>
 where
     Ctx: Context,
@@ -94,6 +110,9 @@ where
                     conflicting: vote,
                 });
             }
+
+            // We already have this vote, do not store it twice
+            return Ok(());
         }
 
         // Add the vote to the round
@@ -158,6 +177,9 @@ where
 
     /// Evidence of equivocation.
     evidence: EvidenceMap<Ctx>,
+
+    /// The highest round each validator has voted in, along with its weight.
+    highest_rounds: BTreeMap<Ctx::Address, (Round, Weight)>,
 }
 
 impl<Ctx> VoteKeeper<Ctx>
@@ -172,6 +194,7 @@ where
             threshold_params,
             per_round: BTreeMap::new(),
             evidence: EvidenceMap::new(),
+            highest_rounds: BTreeMap::new(),
         }
     }
 
@@ -180,16 +203,66 @@ where
         &self.validator_set
     }
 
+    /// Replace the validator set, eg. when it changes in the middle of a height.
+    ///
+    /// The votes received so far are tallied again with the voting power of their validators
+    /// in the new set, and those cast by validators which are not part of it anymore are dropped.
+    /// Outputs already emitted are not emitted again, and evidence of equivocation is retained.
+    pub fn update_validator_set(&mut self, validator_set: Ctx::ValidatorSet) {
+        for per_round in self.per_round.values_mut() {
+            let received_votes = core::mem::take(&mut per_round.received_votes);
+
+            *per_round = PerRound {
+                emitted_outputs: core::mem::take(&mut per_round.emitted_outputs),
+                ..PerRound::new()
+            };
+
+            for vote in received_votes {
+                if let Some(validator) = validator_set.get_by_address(vote.validator_address()) {
+                    // Votes were received without conflict, so they are recorded again as is
+                    let _ = per_round.add(vote, validator.voting_power());
+                }
+            }
+        }
+
+        self.highest_rounds.retain(|address, (_, weight)| {
+            match validator_set.get_by_address(address) {
+                Some(validator) => {
+                    *weight = validator.voting_power();
+                    true
+                }
+                None => false,
+            }
+        });
+
+        self.validator_set = validator_set;
+    }
+
     /// Return the total weight (ie. voting power) of the network.
     pub fn total_weight(&self) -> Weight {
         self.validator_set.total_voting_power()
     }
 
+    /// Return the voting power of the given validator,
+    /// which is zero for validators which are not part of the validator set.
+    pub fn voting_power_of(&self, address: &Ctx::Address) -> Weight {
+        self.validator_set
+            .get_by_address(address)
+            .map_or(0, |validator| validator.voting_power())
+    }
+
     /// Return the votes for the given round.
     pub fn per_round(&self, round: Round) -> Option<&PerRound<Ctx>> {
         self.per_round.get(&round)
     }
 
+    /// Return the votes for all the rounds we have seen votes for so far, in increasing order.
+    pub(crate) fn per_rounds(&self) -> impl Iterator<Item = (Round, &PerRound<Ctx>)> {
+        self.per_round
+            .iter()
+            .map(|(round, per_round)| (*round, per_round))
+    }
+
     /// Return how many rounds we have seen votes for so far.
     pub fn rounds(&self) -> usize {
         self.per_round.len()
@@ -200,21 +273,51 @@ where
         &self.evidence
     }
 
-    /// Apply a vote with a given weight, potentially triggering an output.
+    /// Return the round to skip to, if validators with f+1 weight have voted in rounds
+    /// higher than the given one, counting each validator only once.
+    ///
+    /// The round to skip to is the smallest among the highest rounds these validators voted in,
+    /// so that at least one correct validator is known to be at that round or higher.
+    pub fn skip_round(&self, round: Round) -> Option<Round> {
+        let voted_higher = || {
+            self.highest_rounds
+                .values()
+                .filter(move |(highest, _)| *highest > round)
+        };
+
+        let weight = voted_higher().map(|(_, weight)| weight).sum();
+
+        if self
+            .threshold_params
+            .honest
+            .is_met(weight, self.total_weight())
+        {
+            voted_higher().map(|(highest, _)| *highest).min()
+        } else {
+            None
+        }
+    }
+
+    /// Apply a vote, potentially triggering an output.
+    ///
+    /// Votes cast by validators which are not part of the validator set are rejected
+    /// without being stored, so that they cannot be used to bloat the memory of the keeper.
     pub fn apply_vote(
         &mut self,
         vote: SignedVote<Ctx>,
         round: Round,
-    ) -> Option<Output<ValueId<Ctx>>> {
-        let total_weight = self.total_weight();
-        let per_round = self.per_round.entry(vote.round()).or_default();
-
+    ) -> Result<Option<Output<ValueId<Ctx>>>, VoteError<Ctx>> {
         let Some(validator) = self.validator_set.get_by_address(vote.validator_address()) else {
-            // Vote from unknown validator, let's discard it.
-            return None;
+            return Err(VoteError::UnknownValidator(
+                vote.validator_address().clone(),
+            ));
         };
 
-        match per_round.add(vote.clone(), validator.voting_power()) {
+        let weight = validator.voting_power();
+        let total_weight = self.total_weight();
+        let per_round = self.per_round.entry(vote.round()).or_default();
+
+        match per_round.add(vote.clone(), weight) {
             Ok(()) => (),
             Err(RecordVoteError::ConflictingVote {
                 existing,
@@ -222,25 +325,18 @@ where
             }) => {
                 // This is an equivocating vote
                 self.evidence.add(existing, conflicting);
-                return None;
+                return Ok(None);
             }
         }
 
-        if vote.round() > round {
-            let combined_weight = per_round.addresses_weights.sum();
-
-            let skip_round = self
-                .threshold_params
-                .honest
-                .is_met(combined_weight, total_weight);
-
-            if skip_round {
-                let output = Output::SkipRound(vote.round());
-                per_round.emitted_outputs.insert(output.clone());
-                return Some(output);
-            }
+        if let Some(output) =
+            self.record_round_activity(vote.validator_address(), weight, vote.round(), round)
+        {
+            return Ok(Some(output));
         }
 
+        let per_round = self.per_round.entry(vote.round()).or_default();
+
         let threshold = compute_threshold(
             vote.vote_type(),
             per_round,
@@ -255,12 +351,58 @@ where
             // Ensure we do not output the same message twice
             Some(output) if !per_round.emitted_outputs.contains(&output) => {
                 per_round.emitted_outputs.insert(output.clone());
-                Some(output)
+                Ok(Some(output))
             }
-            _ => None,
+            _ => Ok(None),
         }
     }
 
+    /// Apply a proposal for a round higher than the current one, potentially triggering
+    /// a [`Output::SkipRound`] output.
+    ///
+    /// The proposal must have been signed by the proposer of its round. Its proposer
+    /// then counts towards the f+1 threshold for skipping rounds, along with the validators
+    /// which voted in higher rounds, counting each validator only once.
+    pub fn apply_proposal_round(
+        &mut self,
+        proposer: &Ctx::Address,
+        proposal_round: Round,
+        round: Round,
+    ) -> Option<Output<ValueId<Ctx>>> {
+        let weight = self.validator_set.get_by_address(proposer)?.voting_power();
+        self.record_round_activity(proposer, weight, proposal_round, round)
+    }
+
+    /// Record that the given validator is at the given message round or higher,
+    /// and check whether we should skip to a round higher than the current one.
+    fn record_round_activity(
+        &mut self,
+        address: &Ctx::Address,
+        weight: Weight,
+        msg_round: Round,
+        round: Round,
+    ) -> Option<Output<ValueId<Ctx>>> {
+        self.highest_rounds
+            .entry(address.clone())
+            .and_modify(|(highest, _)| *highest = (*highest).max(msg_round))
+            .or_insert((msg_round, weight));
+
+        if msg_round <= round {
+            return None;
+        }
+
+        let skip_round = self.skip_round(round)?;
+        let output = Output::SkipRound(skip_round);
+
+        self.per_round
+            .entry(skip_round)
+            .or_default()
+            .emitted_outputs
+            .insert(output.clone());
+
+        Some(output)
+    }
+
     /// Check if a threshold is met, ie. if we have a quorum for that threshold.
     pub fn is_threshold_met(
         &self,
@@ -277,6 +419,48 @@ where
             )
         })
     }
+
+    /// Return a certificate for the polka for the given value at the given round,
+    /// if we have received prevotes for that value from 2/3+ of the voting power.
+    ///
+    /// The certificate holds all the prevotes for the value we have received at that round,
+    /// which may be more than the minimal set needed to reach the threshold.
+    /// Prevotes for nil or for other values are excluded, and since conflicting votes
+    /// are never recorded, the certificate holds at most one prevote per validator.
+    pub fn polka_certificate(
+        &self,
+        round: Round,
+        value_id: &ValueId<Ctx>,
+    ) -> Option<PolkaCertificate<Ctx>> {
+        if !self.is_threshold_met(
+            &round,
+            VoteType::Prevote,
+            Threshold::Value(value_id.clone()),
+        ) {
+            return None;
+        }
+
+        let per_round = self.per_round.get(&round)?;
+
+        let prevotes: Vec<_> = per_round
+            .received_votes
+            .iter()
+            .filter(|vote| {
+                vote.vote_type() == VoteType::Prevote
+                    && matches!(vote.value(), NilOrVal::Val(id) if id == value_id)
+            })
+            .cloned()
+            .collect();
+
+        let height = prevotes.first()?.height();
+
+        Some(PolkaCertificate::new(
+            height,
+            round,
+            value_id.clone(),
+            prevotes,
+        ))
+    }
 }
 
 /// Compute whether or not we have reached a threshold for the given value,
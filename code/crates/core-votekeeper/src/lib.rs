@@ -18,6 +18,7 @@ pub mod evidence;
 pub mod keeper;
 pub mod round_votes;
 pub mod round_weights;
+pub mod tally;
 pub mod value_weights;
 
 /// Represents the weight of a vote,
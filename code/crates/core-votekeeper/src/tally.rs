@@ -0,0 +1,173 @@
+//! Snapshots of the votes tallied for each round, eg. for exposing them over RPC.
+//!
+//! A tally only holds the addresses of the validators and the ids of the values they voted for,
+//! and never the full votes, so that it is cheap to build.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use derive_where::derive_where;
+
+use malachitebft_core_types::{
+    Context, NilOrVal, Round, Validator, ValidatorSet, ValueId, Vote, VoteType,
+};
+
+use crate::count::VoteCount;
+use crate::keeper::{Output, PerRound, VoteKeeper};
+use crate::Weight;
+
+/// The votes cast by a validator of the validator set in a round
+#[derive_where(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Ctx::Address: serde::Serialize, ValueId<Ctx>: serde::Serialize",
+        deserialize = "Ctx::Address: serde::Deserialize<'de>, ValueId<Ctx>: serde::Deserialize<'de>",
+    ))
+)]
+pub struct ValidatorTally<Ctx: Context> {
+    /// The address of the validator
+    pub address: Ctx::Address,
+
+    /// The voting power of the validator
+    pub voting_power: Weight,
+
+    /// The value the validator prevoted for, if we received its prevote
+    pub prevote: Option<NilOrVal<ValueId<Ctx>>>,
+
+    /// The value the validator precommitted for, if we received its precommit
+    pub precommit: Option<NilOrVal<ValueId<Ctx>>>,
+}
+
+/// The accumulated voting power of the votes of a given type for a value, or for nil
+#[derive_where(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "ValueId<Ctx>: serde::Serialize",
+        deserialize = "ValueId<Ctx>: serde::Deserialize<'de>",
+    ))
+)]
+pub struct ValueTally<Ctx: Context> {
+    /// The value, or nil
+    pub value: NilOrVal<ValueId<Ctx>>,
+
+    /// The accumulated voting power of the votes for that value
+    pub weight: Weight,
+}
+
+/// The votes tallied for a round
+#[derive_where(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "Ctx::Address: serde::Serialize, ValueId<Ctx>: serde::Serialize",
+        deserialize = "Ctx::Address: serde::Deserialize<'de>, ValueId<Ctx>: serde::Deserialize<'de>",
+    ))
+)]
+pub struct RoundTally<Ctx: Context> {
+    /// The round
+    pub round: Round,
+
+    /// The total voting power of the validator set
+    pub total_weight: Weight,
+
+    /// The votes of every validator, in the order of the validator set
+    pub validators: Vec<ValidatorTally<Ctx>>,
+
+    /// The accumulated voting power of the prevotes for each value, including nil
+    pub prevotes: Vec<ValueTally<Ctx>>,
+
+    /// The accumulated voting power of the precommits for each value, including nil
+    pub precommits: Vec<ValueTally<Ctx>>,
+
+    /// The thresholds which have been reached in this round
+    pub thresholds: Vec<Output<ValueId<Ctx>>>,
+}
+
+impl<Ctx: Context> RoundTally<Ctx> {
+    /// The accumulated voting power of all the prevotes
+    pub fn prevote_weight(&self) -> Weight {
+        self.prevotes.iter().map(|tally| tally.weight).sum()
+    }
+
+    /// The accumulated voting power of all the precommits
+    pub fn precommit_weight(&self) -> Weight {
+        self.precommits.iter().map(|tally| tally.weight).sum()
+    }
+
+    fn new(round: Round, per_round: &PerRound<Ctx>, validator_set: &Ctx::ValidatorSet) -> Self {
+        // The prevote and precommit of each validator which voted in this round
+        let mut votes: BTreeMap<&Ctx::Address, ValidatorVotes<'_, Ctx>> = BTreeMap::new();
+
+        for vote in per_round.received_votes() {
+            let entry = votes.entry(vote.validator_address()).or_default();
+
+            match vote.vote_type() {
+                VoteType::Prevote => entry.0 = Some(vote.value()),
+                VoteType::Precommit => entry.1 = Some(vote.value()),
+            }
+        }
+
+        let validators = (0..validator_set.count())
+            .filter_map(|index| validator_set.get_by_index(index))
+            .map(|validator| {
+                let (prevote, precommit) =
+                    votes.get(validator.address()).copied().unwrap_or_default();
+
+                ValidatorTally {
+                    address: validator.address().clone(),
+                    voting_power: validator.voting_power(),
+                    prevote: prevote.cloned(),
+                    precommit: precommit.cloned(),
+                }
+            })
+            .collect();
+
+        Self {
+            round,
+            total_weight: validator_set.total_voting_power(),
+            validators,
+            prevotes: value_tallies(per_round.votes().prevotes()),
+            precommits: value_tallies(per_round.votes().precommits()),
+            thresholds: per_round.emitted_outputs().iter().cloned().collect(),
+        }
+    }
+}
+
+type ValidatorVotes<'a, Ctx> = (
+    Option<&'a NilOrVal<ValueId<Ctx>>>,
+    Option<&'a NilOrVal<ValueId<Ctx>>>,
+);
+
+fn value_tallies<Ctx: Context>(count: &VoteCount<Ctx>) -> Vec<ValueTally<Ctx>> {
+    count
+        .values_weights
+        .iter()
+        .map(|(value, weight)| ValueTally {
+            value: value.clone(),
+            weight,
+        })
+        .collect()
+}
+
+impl<Ctx> VoteKeeper<Ctx>
+where
+    Ctx: Context,
+{
+    /// Return the votes tallied for the given round, if we have seen any vote for it.
+    pub fn round_tally(&self, round: Round) -> Option<RoundTally<Ctx>> {
+        self.per_round(round)
+            .map(|per_round| RoundTally::new(round, per_round, self.validator_set()))
+    }
+
+    /// Return the votes tallied for all the rounds we have seen votes for, in increasing order.
+    pub fn tallies(&self) -> Vec<RoundTally<Ctx>> {
+        self.per_rounds()
+            .map(|(round, per_round)| RoundTally::new(round, per_round, self.validator_set()))
+            .collect()
+    }
+}
@@ -38,6 +38,13 @@ impl<Value> ValuesWeights<Value> {
         self.value_weights.get(value).copied().unwrap_or(0)
     }
 
+    /// Return the values and their weights, in increasing order of the values.
+    pub fn iter(&self) -> impl Iterator<Item = (&Value, Weight)> {
+        self.value_weights
+            .iter()
+            .map(|(value, weight)| (value, *weight))
+    }
+
     /// Return the sum of the weights of all values.
     pub fn sum(&self) -> Weight {
         let mut weight: Weight = 0;
@@ -1,9 +1,12 @@
-use malachitebft_core_types::{NilOrVal, Round, SignedVote};
+use malachitebft_core_types::{
+    NilOrVal, PolkaCertificateError, Round, SignedVote, SigningProvider, SigningProviderExt,
+};
 
-use informalsystems_malachitebft_core_votekeeper::keeper::{Output, VoteKeeper};
+use informalsystems_malachitebft_core_votekeeper::keeper::{Output, VoteError, VoteKeeper};
 
 use malachitebft_test::{
-    Address, Height, PrivateKey, Signature, TestContext, Validator, ValidatorSet, ValueId, Vote,
+    Address, Ed25519Provider, Height, PrivateKey, Signature, TestContext, Validator, ValidatorSet,
+    ValueId, Vote,
 };
 
 fn setup<const N: usize>(vp: [u64; N]) -> ([Address; N], VoteKeeper<TestContext>) {
@@ -50,15 +53,15 @@ fn prevote_apply_nil() {
     let round = Round::new(0);
 
     let vote = new_signed_prevote(height, round, NilOrVal::Nil, addr1);
-    let msg = keeper.apply_vote(vote.clone(), round);
+    let msg = keeper.apply_vote(vote.clone(), round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_prevote(height, round, NilOrVal::Nil, addr2);
-    let msg = keeper.apply_vote(vote.clone(), round);
+    let msg = keeper.apply_vote(vote.clone(), round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_prevote(height, round, NilOrVal::Nil, addr3);
-    let msg = keeper.apply_vote(vote, round);
+    let msg = keeper.apply_vote(vote, round).unwrap();
     assert_eq!(msg, Some(Output::PolkaNil));
 }
 
@@ -70,15 +73,15 @@ fn precommit_apply_nil() {
     let round = Round::new(0);
 
     let vote = new_signed_precommit(height, round, NilOrVal::Nil, addr1);
-    let msg = keeper.apply_vote(vote.clone(), round);
+    let msg = keeper.apply_vote(vote.clone(), round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_precommit(height, Round::new(0), NilOrVal::Nil, addr2);
-    let msg = keeper.apply_vote(vote.clone(), round);
+    let msg = keeper.apply_vote(vote.clone(), round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_precommit(height, Round::new(0), NilOrVal::Nil, addr3);
-    let msg = keeper.apply_vote(vote, round);
+    let msg = keeper.apply_vote(vote, round).unwrap();
     assert_eq!(msg, Some(Output::PrecommitAny));
 }
 
@@ -92,19 +95,19 @@ fn prevote_apply_single_value() {
     let round = Round::new(0);
 
     let vote = new_signed_prevote(height, Round::new(0), val, addr1);
-    let msg = keeper.apply_vote(vote.clone(), round);
+    let msg = keeper.apply_vote(vote.clone(), round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_prevote(height, Round::new(0), val, addr2);
-    let msg = keeper.apply_vote(vote.clone(), round);
+    let msg = keeper.apply_vote(vote.clone(), round).unwrap();
     assert_eq!(msg, None);
 
     let vote_nil = new_signed_prevote(height, Round::new(0), NilOrVal::Nil, addr3);
-    let msg = keeper.apply_vote(vote_nil, round);
+    let msg = keeper.apply_vote(vote_nil, round).unwrap();
     assert_eq!(msg, Some(Output::PolkaAny));
 
     let vote = new_signed_prevote(height, Round::new(0), val, addr4);
-    let msg = keeper.apply_vote(vote, round);
+    let msg = keeper.apply_vote(vote, round).unwrap();
     assert_eq!(msg, Some(Output::PolkaValue(id)));
 }
 
@@ -118,19 +121,19 @@ fn precommit_apply_single_value() {
     let round = Round::new(0);
 
     let vote = new_signed_precommit(height, Round::new(0), val, addr1);
-    let msg = keeper.apply_vote(vote.clone(), round);
+    let msg = keeper.apply_vote(vote.clone(), round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_precommit(height, Round::new(0), val, addr2);
-    let msg = keeper.apply_vote(vote.clone(), round);
+    let msg = keeper.apply_vote(vote.clone(), round).unwrap();
     assert_eq!(msg, None);
 
     let vote_nil = new_signed_precommit(height, Round::new(0), NilOrVal::Nil, addr3);
-    let msg = keeper.apply_vote(vote_nil, round);
+    let msg = keeper.apply_vote(vote_nil, round).unwrap();
     assert_eq!(msg, Some(Output::PrecommitAny));
 
     let vote = new_signed_precommit(height, Round::new(0), val, addr4);
-    let msg = keeper.apply_vote(vote, round);
+    let msg = keeper.apply_vote(vote, round).unwrap();
     assert_eq!(msg, Some(Output::PrecommitValue(id)));
 }
 
@@ -145,15 +148,15 @@ fn skip_round_small_quorum_prevotes_two_vals() {
     let fut_round = Round::new(1);
 
     let vote = new_signed_prevote(height, cur_round, val, addr1);
-    let msg = keeper.apply_vote(vote.clone(), cur_round);
+    let msg = keeper.apply_vote(vote.clone(), cur_round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_prevote(height, fut_round, val, addr2);
-    let msg = keeper.apply_vote(vote.clone(), cur_round);
+    let msg = keeper.apply_vote(vote.clone(), cur_round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_prevote(height, fut_round, val, addr3);
-    let msg = keeper.apply_vote(vote, cur_round);
+    let msg = keeper.apply_vote(vote, cur_round).unwrap();
     assert_eq!(msg, Some(Output::SkipRound(Round::new(1))));
 }
 
@@ -168,15 +171,15 @@ fn skip_round_small_quorum_with_prevote_precommit_two_vals() {
     let fut_round = Round::new(1);
 
     let vote = new_signed_prevote(height, cur_round, val, addr1);
-    let msg = keeper.apply_vote(vote.clone(), cur_round);
+    let msg = keeper.apply_vote(vote.clone(), cur_round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_prevote(height, fut_round, val, addr2);
-    let msg = keeper.apply_vote(vote.clone(), cur_round);
+    let msg = keeper.apply_vote(vote.clone(), cur_round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_precommit(height, fut_round, val, addr3);
-    let msg = keeper.apply_vote(vote, cur_round);
+    let msg = keeper.apply_vote(vote, cur_round).unwrap();
     assert_eq!(msg, Some(Output::SkipRound(Round::new(1))));
 }
 
@@ -191,15 +194,15 @@ fn skip_round_full_quorum_with_prevote_precommit_two_vals() {
     let fut_round = Round::new(1);
 
     let vote = new_signed_prevote(height, cur_round, val, addr1);
-    let msg = keeper.apply_vote(vote.clone(), cur_round);
+    let msg = keeper.apply_vote(vote.clone(), cur_round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_prevote(height, fut_round, val, addr2);
-    let msg = keeper.apply_vote(vote.clone(), cur_round);
+    let msg = keeper.apply_vote(vote.clone(), cur_round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_precommit(height, fut_round, val, addr3);
-    let msg = keeper.apply_vote(vote, cur_round);
+    let msg = keeper.apply_vote(vote, cur_round).unwrap();
     assert_eq!(msg, Some(Output::SkipRound(Round::new(1))));
 }
 
@@ -214,15 +217,15 @@ fn no_skip_round_small_quorum_with_same_val() {
     let fut_round = Round::new(1);
 
     let vote = new_signed_prevote(height, cur_round, val, addr1);
-    let msg = keeper.apply_vote(vote.clone(), cur_round);
+    let msg = keeper.apply_vote(vote.clone(), cur_round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_prevote(height, fut_round, val, addr2);
-    let msg = keeper.apply_vote(vote.clone(), cur_round);
+    let msg = keeper.apply_vote(vote.clone(), cur_round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_precommit(height, fut_round, val, addr2);
-    let msg = keeper.apply_vote(vote, cur_round);
+    let msg = keeper.apply_vote(vote, cur_round).unwrap();
     assert_eq!(msg, None);
 }
 
@@ -237,16 +240,95 @@ fn no_skip_round_full_quorum_with_same_val() {
     let fut_round = Round::new(1);
 
     let vote = new_signed_prevote(height, cur_round, val, addr1);
-    let msg = keeper.apply_vote(vote.clone(), cur_round);
+    let msg = keeper.apply_vote(vote.clone(), cur_round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_prevote(height, fut_round, val, addr2);
-    let msg = keeper.apply_vote(vote.clone(), cur_round);
+    let msg = keeper.apply_vote(vote.clone(), cur_round).unwrap();
     assert_eq!(msg, None);
 
     let vote = new_signed_precommit(height, fut_round, val, addr2);
-    let msg = keeper.apply_vote(vote, cur_round);
+    let msg = keeper.apply_vote(vote, cur_round).unwrap();
+    assert_eq!(msg, None);
+}
+
+#[test]
+fn skip_round_votes_across_future_rounds() {
+    let ([addr1, addr2, ..], mut keeper) = setup([1, 1, 1, 1]);
+
+    let val = NilOrVal::Val(ValueId::new(1));
+    let height = Height::new(1);
+    let cur_round = Round::new(0);
+
+    let vote = new_signed_prevote(height, Round::new(3), val, addr1);
+    let msg = keeper.apply_vote(vote, cur_round).unwrap();
+    assert_eq!(msg, None);
+
+    let vote = new_signed_precommit(height, Round::new(7), val, addr2);
+    let msg = keeper.apply_vote(vote, cur_round).unwrap();
+    assert_eq!(msg, Some(Output::SkipRound(Round::new(3))));
+    assert_eq!(keeper.skip_round(Round::new(2)), Some(Round::new(3)));
+    assert_eq!(keeper.skip_round(Round::new(3)), None);
+}
+
+#[test]
+fn no_skip_round_byzantine_validator_in_far_future_rounds() {
+    let ([_, _, _, byzantine], mut keeper) = setup([1, 1, 1, 1]);
+
+    let val = NilOrVal::Val(ValueId::new(1));
+    let height = Height::new(1);
+    let cur_round = Round::new(0);
+
+    // A single validator voting in many far-future rounds must only be counted once
+    for round in [5, 10, 100, 1000] {
+        let vote = new_signed_prevote(height, Round::new(round), val, byzantine);
+        let msg = keeper.apply_vote(vote, cur_round).unwrap();
+        assert_eq!(msg, None);
+
+        let vote = new_signed_precommit(height, Round::new(round), val, byzantine);
+        let msg = keeper.apply_vote(vote, cur_round).unwrap();
+        assert_eq!(msg, None);
+    }
+
+    assert_eq!(keeper.skip_round(cur_round), None);
+}
+
+#[test]
+fn skip_round_byzantine_validator_cannot_pull_round_up() {
+    let ([addr1, _, _, byzantine], mut keeper) = setup([1, 1, 1, 1]);
+
+    let val = NilOrVal::Val(ValueId::new(1));
+    let height = Height::new(1);
+    let cur_round = Round::new(0);
+
+    let vote = new_signed_precommit(height, Round::new(1000), val, byzantine);
+    let msg = keeper.apply_vote(vote, cur_round).unwrap();
+    assert_eq!(msg, None);
+
+    // Only the correct validator is known to be at round 2, so we must not skip further
+    let vote = new_signed_prevote(height, Round::new(2), val, addr1);
+    let msg = keeper.apply_vote(vote, cur_round).unwrap();
+    assert_eq!(msg, Some(Output::SkipRound(Round::new(2))));
+}
+
+#[test]
+fn skip_round_proposal_and_votes_from_same_validator() {
+    let ([addr1, addr2, ..], mut keeper) = setup([1, 1, 1, 1]);
+
+    let val = NilOrVal::Val(ValueId::new(1));
+    let height = Height::new(1);
+    let cur_round = Round::new(0);
+
+    let vote = new_signed_prevote(height, Round::new(3), val, addr1);
+    let msg = keeper.apply_vote(vote, cur_round).unwrap();
     assert_eq!(msg, None);
+
+    // The proposer already voted in round 3, so it must only be counted once
+    let msg = keeper.apply_proposal_round(&addr1, Round::new(3), cur_round);
+    assert_eq!(msg, None);
+
+    let msg = keeper.apply_proposal_round(&addr2, Round::new(3), cur_round);
+    assert_eq!(msg, Some(Output::SkipRound(Round::new(3))));
 }
 
 #[test]
@@ -260,11 +342,11 @@ fn same_votes() {
     let val = NilOrVal::Val(id);
 
     let vote1 = new_signed_prevote(height, round, val, addr1);
-    let msg = keeper.apply_vote(vote1.clone(), round);
+    let msg = keeper.apply_vote(vote1.clone(), round).unwrap();
     assert_eq!(msg, None);
 
     let vote2 = new_signed_prevote(height, round, val, addr1);
-    let msg = keeper.apply_vote(vote2.clone(), round);
+    let msg = keeper.apply_vote(vote2.clone(), round).unwrap();
     assert_eq!(msg, None);
 
     assert!(keeper.evidence().is_empty());
@@ -282,26 +364,226 @@ fn equivocation() {
     let val1 = NilOrVal::Val(id1);
 
     let vote11 = new_signed_prevote(height, round, val1, addr1);
-    let msg = keeper.apply_vote(vote11.clone(), round);
+    let msg = keeper.apply_vote(vote11.clone(), round).unwrap();
     assert_eq!(msg, None);
 
     let vote12 = new_signed_prevote(height, round, NilOrVal::Nil, addr1);
-    let msg = keeper.apply_vote(vote12.clone(), round);
+    let msg = keeper.apply_vote(vote12.clone(), round).unwrap();
     assert_eq!(msg, None);
 
     assert!(!keeper.evidence().is_empty());
     assert_eq!(keeper.evidence().get(&addr1), Some(&vec![(vote11, vote12)]));
 
     let vote21 = new_signed_prevote(height, round, val1, addr2);
-    let msg = keeper.apply_vote(vote21.clone(), round);
+    let msg = keeper.apply_vote(vote21.clone(), round).unwrap();
     assert_eq!(msg, None);
 
     let id2 = ValueId::new(2);
     let val2 = NilOrVal::Val(id2);
 
     let vote22 = new_signed_prevote(height, round, val2, addr2);
-    let msg = keeper.apply_vote(vote22.clone(), round);
+    let msg = keeper.apply_vote(vote22.clone(), round).unwrap();
     assert_eq!(msg, None);
 
     assert_eq!(keeper.evidence().get(&addr2), Some(&vec![(vote21, vote22)]));
 }
+
+fn signed_prevote_by(
+    index: u8,
+    height: Height,
+    round: Round,
+    value: NilOrVal<ValueId>,
+    addr: Address,
+) -> SignedVote<TestContext> {
+    let provider = Ed25519Provider::new(PrivateKey::from([index; 32]));
+    provider.sign_vote(Vote::new_prevote(height, round, value, addr))
+}
+
+#[test]
+fn polka_certificate_only_includes_prevotes_for_value() {
+    let ([addr1, addr2, addr3, addr4, addr5], mut keeper) = setup([1, 1, 1, 1, 1]);
+
+    let height = Height::new(1);
+    let round = Round::new(0);
+    let id = ValueId::new(1);
+    let val = NilOrVal::Val(id);
+
+    for (i, addr) in [addr1, addr2, addr3].into_iter().enumerate() {
+        keeper
+            .apply_vote(signed_prevote_by(i as u8, height, round, val, addr), round)
+            .unwrap();
+    }
+
+    // No polka yet
+    assert_eq!(keeper.polka_certificate(round, &id), None);
+
+    keeper
+        .apply_vote(
+            signed_prevote_by(3, height, round, NilOrVal::Nil, addr4),
+            round,
+        )
+        .unwrap();
+    keeper
+        .apply_vote(signed_prevote_by(4, height, round, val, addr5), round)
+        .unwrap();
+
+    // Duplicate prevote
+    keeper
+        .apply_vote(signed_prevote_by(4, height, round, val, addr5), round)
+        .unwrap();
+
+    let certificate = keeper.polka_certificate(round, &id).unwrap();
+
+    assert_eq!(certificate.height, height);
+    assert_eq!(certificate.round, round);
+    assert_eq!(certificate.value_id, id);
+
+    let mut signers: Vec<_> = certificate
+        .polka_signatures
+        .iter()
+        .map(|sig| sig.address)
+        .collect();
+    signers.sort();
+
+    let mut expected = vec![addr1, addr2, addr3, addr5];
+    expected.sort();
+
+    assert_eq!(signers, expected);
+
+    assert_eq!(keeper.polka_certificate(round, &ValueId::new(2)), None);
+    assert_eq!(keeper.polka_certificate(Round::new(1), &id), None);
+}
+
+#[test]
+fn polka_certificate_verifies_against_validator_set() {
+    let ([addr1, addr2, addr3, _], mut keeper) = setup([1, 1, 1, 1]);
+
+    let height = Height::new(1);
+    let round = Round::new(0);
+    let id = ValueId::new(1);
+    let val = NilOrVal::Val(id);
+
+    for (i, addr) in [addr1, addr2, addr3].into_iter().enumerate() {
+        keeper
+            .apply_vote(signed_prevote_by(i as u8, height, round, val, addr), round)
+            .unwrap();
+    }
+
+    let provider = Ed25519Provider::new(PrivateKey::from([0; 32]));
+    let validator_set = keeper.validator_set();
+
+    let certificate = keeper.polka_certificate(round, &id).unwrap();
+    assert_eq!(
+        provider.verify_polka_certificate(&certificate, validator_set, Default::default()),
+        Ok(())
+    );
+
+    // Not enough voting power
+    let mut partial = certificate.clone();
+    partial.polka_signatures.truncate(2);
+    assert_eq!(
+        provider.verify_polka_certificate(&partial, validator_set, Default::default()),
+        Err(PolkaCertificateError::NotEnoughVotingPower {
+            signed: 2,
+            total: 4,
+            expected: 2,
+        })
+    );
+
+    // Duplicate signer
+    let mut duplicate = certificate.clone();
+    let sig = duplicate.polka_signatures[0].clone();
+    duplicate.polka_signatures.push(sig.clone());
+    assert_eq!(
+        provider.verify_polka_certificate(&duplicate, validator_set, Default::default()),
+        Err(PolkaCertificateError::DuplicateVote(sig))
+    );
+
+    // Signature over another value
+    let mut forged = certificate.clone();
+    forged.value_id = ValueId::new(2);
+    assert!(matches!(
+        provider.verify_polka_certificate(&forged, validator_set, Default::default()),
+        Err(PolkaCertificateError::InvalidSignature(_))
+    ));
+
+    // Signature from a validator outside of the validator set
+    let mut unknown = certificate;
+    unknown.polka_signatures[0].address =
+        Address::from_public_key(&PrivateKey::from([9; 32]).public_key());
+    let sig = unknown.polka_signatures[0].clone();
+    assert_eq!(
+        provider.verify_polka_certificate(&unknown, validator_set, Default::default()),
+        Err(PolkaCertificateError::UnknownValidator(sig))
+    );
+}
+
+#[test]
+fn votes_from_unknown_validators_are_not_stored() {
+    let ([addr1, addr2, addr3], mut keeper) = setup([1, 1, 1]);
+
+    let height = Height::new(1);
+    let round = Round::new(0);
+
+    for i in 0..10_000_u32 {
+        let mut bytes = [0xff; 20];
+        bytes[..4].copy_from_slice(&i.to_be_bytes());
+        let unknown = Address::new(bytes);
+
+        let vote_round = Round::new(i % 100);
+        let vote = if i % 2 == 0 {
+            new_signed_prevote(height, vote_round, NilOrVal::Nil, unknown)
+        } else {
+            new_signed_precommit(height, vote_round, NilOrVal::Nil, unknown)
+        };
+
+        assert_eq!(
+            keeper.apply_vote(vote, round),
+            Err(VoteError::UnknownValidator(unknown))
+        );
+        assert_eq!(keeper.voting_power_of(&unknown), 0);
+    }
+
+    // Nothing was stored for any round
+    assert_eq!(keeper.rounds(), 0);
+    assert!(keeper.evidence().is_empty());
+
+    // Known validators cannot grow the storage past one prevote and one precommit each
+    let val = NilOrVal::Val(ValueId::new(1));
+
+    for _ in 0..100 {
+        for (i, addr) in [addr1, addr2, addr3].into_iter().enumerate() {
+            keeper
+                .apply_vote(signed_prevote_by(i as u8, height, round, val, addr), round)
+                .unwrap();
+
+            // Same prevote, with another signature
+            keeper
+                .apply_vote(signed_prevote_by(9, height, round, val, addr), round)
+                .unwrap();
+
+            keeper
+                .apply_vote(new_signed_precommit(height, round, val, addr), round)
+                .unwrap();
+
+            // Equivocating precommit, recorded as evidence
+            keeper
+                .apply_vote(
+                    new_signed_precommit(height, round, NilOrVal::Nil, addr),
+                    round,
+                )
+                .unwrap();
+        }
+    }
+
+    assert_eq!(keeper.rounds(), 1);
+    assert_eq!(
+        keeper.per_round(round).unwrap().received_votes().len(),
+        2 * 3
+    );
+    assert_eq!(keeper.voting_power_of(&addr1), 1);
+
+    for addr in [addr1, addr2, addr3] {
+        assert_eq!(keeper.evidence().get(&addr).map(Vec::len), Some(1));
+    }
+}
@@ -89,7 +89,7 @@ fn request_response_config() -> request_response::Config {
 }
 
 impl Behaviour {
-    pub fn new(keypair: &Keypair, config: Config) -> Self {
+    pub fn new(keypair: &Keypair, config: &Config) -> Self {
         let kademlia = Toggle::from(
             (config.enabled && config.bootstrap_protocol == BootstrapProtocol::Kademlia).then(
                 || {
@@ -1,9 +1,18 @@
+use std::collections::HashSet;
 use std::time::Duration;
 
+use libp2p::{Multiaddr, PeerId};
+
 const DEFAULT_NUM_OUTBOUND_PEERS: usize = 20;
 const DEFAULT_NUM_INBOUND_PEERS: usize = 20;
+const DEFAULT_MAX_INBOUND_PEERS: usize = 100;
+
+const DEFAULT_RANDOM_SELECTION_FRACTION: f64 = 0.25;
 
 const DEFAULT_EPHEMERAL_CONNECTION_TIMEOUT: Duration = Duration::from_secs(15);
+const DEFAULT_PERSISTENT_PEER_UNREACHABLE_THRESHOLD: Duration = Duration::from_secs(60);
+
+const DEFAULT_DNS_SEEDS_RESOLUTION_INTERVAL: Duration = Duration::from_secs(300);
 
 const DEFAULT_DIAL_MAX_RETRIES: usize = 5;
 const DEFAULT_PEERS_REQUEST_MAX_RETRIES: usize = 5;
@@ -23,18 +32,53 @@ pub enum Selector {
     Random,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct Config {
     pub enabled: bool,
 
     pub bootstrap_protocol: BootstrapProtocol,
     pub selector: Selector,
+    /// Fraction of the outbound candidates selected at random rather than by score,
+    /// to avoid being eclipsed by the peers closest to us
+    pub random_selection_fraction: f64,
+    /// Seed for the random choices made when selecting outbound candidates,
+    /// so that the same discovered peers yield the same selection across runs.
+    /// A random seed is used if not set.
+    pub selection_seed: Option<u64>,
 
     pub num_outbound_peers: usize,
     pub num_inbound_peers: usize,
+    /// Maximum number of incoming connections, not counting connections
+    /// to outbound and persistent peers. When the limit is exceeded,
+    /// the least useful incoming connections are closed.
+    pub max_inbound_peers: usize,
 
     pub ephemeral_connection_timeout: Duration,
 
+    /// Peers to always stay connected to. They are dialed at startup, their connections
+    /// are never closed, and they are redialed indefinitely when disconnected.
+    /// They do not count towards the number of outbound peers.
+    pub persistent_peers: Vec<Multiaddr>,
+    /// Time after which a warning is emitted if a persistent peer is still unreachable
+    pub persistent_peer_unreachable_threshold: Duration,
+    /// Trusted peers (eg. seed or sentry nodes) which are always kept as outbound
+    /// connections when connected, and reconnected first when repairing outbound connections.
+    /// Unlike persistent peers, they count towards the number of outbound peers.
+    pub pinned_peers: HashSet<PeerId>,
+    /// Peers which are never selected as outbound connections, nor kept as inbound connections,
+    /// eg. known malicious nodes. This takes precedence over the pinned peers.
+    pub denied_peers: HashSet<PeerId>,
+
+    /// DNS names resolving to the addresses of bootstrap peers, as multiaddrs starting with
+    /// a `/dns`, `/dns4` or `/dns6` component, eg. `/dns/seed.example.com/tcp/27000`.
+    /// They are resolved at startup and every `dns_seeds_resolution_interval`,
+    /// and all the addresses they resolve to are dialed.
+    pub bootstrap_dns_seeds: Vec<String>,
+    pub dns_seeds_resolution_interval: Duration,
+    /// The DNS seeds are resolved again early when the number of connected peers
+    /// drops below this threshold, eg. after the peers were all redeployed.
+    pub dns_seeds_min_peers: usize,
+
     pub dial_max_retries: usize,
     pub request_max_retries: usize,
     pub connect_request_max_retries: usize,
@@ -51,12 +95,24 @@ impl Default for Config {
 
             bootstrap_protocol: BootstrapProtocol::default(),
             selector: Selector::default(),
+            random_selection_fraction: DEFAULT_RANDOM_SELECTION_FRACTION,
+            selection_seed: None,
 
             num_outbound_peers: DEFAULT_NUM_OUTBOUND_PEERS,
             num_inbound_peers: DEFAULT_NUM_INBOUND_PEERS,
+            max_inbound_peers: DEFAULT_MAX_INBOUND_PEERS,
 
             ephemeral_connection_timeout: DEFAULT_EPHEMERAL_CONNECTION_TIMEOUT,
 
+            persistent_peers: Vec::new(),
+            persistent_peer_unreachable_threshold: DEFAULT_PERSISTENT_PEER_UNREACHABLE_THRESHOLD,
+            pinned_peers: HashSet::new(),
+            denied_peers: HashSet::new(),
+
+            bootstrap_dns_seeds: Vec::new(),
+            dns_seeds_resolution_interval: DEFAULT_DNS_SEEDS_RESOLUTION_INTERVAL,
+            dns_seeds_min_peers: DEFAULT_NUM_OUTBOUND_PEERS,
+
             dial_max_retries: DEFAULT_DIAL_MAX_RETRIES,
             request_max_retries: DEFAULT_PEERS_REQUEST_MAX_RETRIES,
             connect_request_max_retries: DEFAULT_CONNECT_REQUEST_MAX_RETRIES,
@@ -80,6 +136,18 @@ impl Config {
         self.selector = selector;
     }
 
+    pub fn set_random_selection_fraction(&mut self, fraction: f64) {
+        if !(0.0..=1.0).contains(&fraction) {
+            panic!("Random selection fraction should be between 0 and 1");
+        }
+
+        self.random_selection_fraction = fraction;
+    }
+
+    pub fn set_selection_seed(&mut self, seed: u64) {
+        self.selection_seed = Some(seed);
+    }
+
     pub fn set_peers_bounds(&mut self, num_outbound_peers: usize, num_inbound_peers: usize) {
         if num_inbound_peers < num_outbound_peers {
             panic!("Number of inbound peers should be greater than or equal to number of outbound peers");
@@ -89,7 +157,39 @@ impl Config {
         self.num_inbound_peers = num_inbound_peers;
     }
 
+    pub fn set_max_inbound_peers(&mut self, max_inbound_peers: usize) {
+        self.max_inbound_peers = max_inbound_peers;
+    }
+
     pub fn set_ephemeral_connection_timeout(&mut self, timeout: Duration) {
         self.ephemeral_connection_timeout = timeout;
     }
+
+    pub fn set_persistent_peers(&mut self, persistent_peers: Vec<Multiaddr>) {
+        self.persistent_peers = persistent_peers;
+    }
+
+    pub fn set_persistent_peer_unreachable_threshold(&mut self, threshold: Duration) {
+        self.persistent_peer_unreachable_threshold = threshold;
+    }
+
+    pub fn set_pinned_peers(&mut self, pinned_peers: HashSet<PeerId>) {
+        self.pinned_peers = pinned_peers;
+    }
+
+    pub fn set_denied_peers(&mut self, denied_peers: HashSet<PeerId>) {
+        self.denied_peers = denied_peers;
+    }
+
+    pub fn set_bootstrap_dns_seeds(&mut self, seeds: Vec<String>) {
+        self.bootstrap_dns_seeds = seeds;
+    }
+
+    pub fn set_dns_seeds_resolution_interval(&mut self, interval: Duration) {
+        self.dns_seeds_resolution_interval = interval;
+    }
+
+    pub fn set_dns_seeds_min_peers(&mut self, min_peers: usize) {
+        self.dns_seeds_min_peers = min_peers;
+    }
 }
@@ -8,12 +8,13 @@ use libp2p::{request_response::OutboundRequestId, swarm::ConnectionId, Multiaddr
 use tokio::sync::mpsc;
 use tracing::error;
 
-use crate::{request::RequestData, ConnectionData};
+use crate::{dns::DnsResolution, request::RequestData, ConnectionData};
 
 const DEFAULT_DIAL_CONCURRENT_FACTOR: usize = 20;
 const DEFAULT_PEERS_REQUEST_CONCURRENT_FACTOR: usize = 20;
 const DEFAULT_CONNECT_REQUEST_CONCURRENT_FACTOR: usize = 100;
 const DEFAULT_CLOSE_CONCURRENT_FACTOR: usize = usize::MAX;
+const DEFAULT_DNS_RESOLUTION_CONCURRENT_FACTOR: usize = usize::MAX;
 
 #[derive(Debug)]
 pub struct Action<T, U, V> {
@@ -62,6 +63,11 @@ where
         });
     }
 
+    /// Sender to the queue, for tasks producing values on their own
+    pub(crate) fn sender(&self) -> mpsc::UnboundedSender<V> {
+        self.tx_queue.clone()
+    }
+
     pub(crate) fn queue_len(&self) -> usize {
         self.rx_queue.len()
     }
@@ -119,6 +125,7 @@ pub struct Controller {
     pub peers_request: Action<PeerId, OutboundRequestId, RequestData>,
     pub connect_request: Action<PeerId, OutboundRequestId, RequestData>,
     pub close: Action<(), (), (PeerId, ConnectionId)>,
+    pub dns_resolution: Action<(), (), DnsResolution>,
 }
 
 impl Controller {
@@ -128,6 +135,7 @@ impl Controller {
             peers_request: Action::new(DEFAULT_PEERS_REQUEST_CONCURRENT_FACTOR),
             connect_request: Action::new(DEFAULT_CONNECT_REQUEST_CONCURRENT_FACTOR),
             close: Action::new(DEFAULT_CLOSE_CONCURRENT_FACTOR),
+            dns_resolution: Action::new(DEFAULT_DNS_RESOLUTION_CONCURRENT_FACTOR),
         }
     }
 
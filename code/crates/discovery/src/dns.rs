@@ -0,0 +1,165 @@
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::time::Instant;
+
+use libp2p::multiaddr::Protocol;
+use libp2p::Multiaddr;
+
+use crate::util::Retry;
+
+pub type ResolveFuture = Pin<Box<dyn Future<Output = io::Result<Vec<IpAddr>>> + Send>>;
+
+/// Resolves the host name of a DNS seed to IP addresses
+pub trait DnsResolver: fmt::Debug + Send + Sync {
+    fn resolve(&self, host: &str) -> ResolveFuture;
+}
+
+/// Resolver using the system configuration, through Tokio
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TokioDnsResolver;
+
+impl DnsResolver for TokioDnsResolver {
+    fn resolve(&self, host: &str) -> ResolveFuture {
+        let host = host.to_string();
+
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((host.as_str(), 0)).await?;
+            Ok(addrs.map(|addr| addr.ip()).collect())
+        })
+    }
+}
+
+/// Which IP addresses of a DNS seed to keep
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum IpVersion {
+    Any,
+    V4,
+    V6,
+}
+
+/// A DNS seed, ie. a multiaddr starting with a `/dns`, `/dns4` or `/dns6` component,
+/// eg. `/dns/seed.example.com/tcp/27000`, which resolves to the addresses of one or more peers.
+#[derive(Debug)]
+pub(crate) struct DnsSeed {
+    pub(crate) seed: String,
+    pub(crate) host: String,
+    ip_version: IpVersion,
+    /// Protocols following the DNS component, eg. `/tcp/27000`
+    suffix: Multiaddr,
+    /// Addresses the seed resolved to the last time
+    pub(crate) resolved: Vec<Multiaddr>,
+    /// Time of the last successful resolution
+    pub(crate) last_resolved: Option<Instant>,
+    /// Backoff state for retrying failed resolutions
+    pub(crate) retry: Retry,
+    /// Incremented every time a resolution is scheduled, so that the results
+    /// of resolutions which were superseded by a newer one are ignored
+    pub(crate) generation: u64,
+}
+
+impl DnsSeed {
+    /// Replace the DNS component of the seed with each of the given IP addresses
+    pub(crate) fn to_multiaddrs(&self, ips: &[IpAddr]) -> Vec<Multiaddr> {
+        let mut multiaddrs: Vec<Multiaddr> = Vec::new();
+
+        for ip in ips {
+            let protocol = match (ip, self.ip_version) {
+                (IpAddr::V4(ip), IpVersion::Any | IpVersion::V4) => Protocol::Ip4(*ip),
+                (IpAddr::V6(ip), IpVersion::Any | IpVersion::V6) => Protocol::Ip6(*ip),
+                _ => continue,
+            };
+
+            let multiaddr = self
+                .suffix
+                .iter()
+                .fold(Multiaddr::empty().with(protocol), |addr, p| addr.with(p));
+
+            if !multiaddrs.contains(&multiaddr) {
+                multiaddrs.push(multiaddr);
+            }
+        }
+
+        multiaddrs
+    }
+}
+
+impl FromStr for DnsSeed {
+    type Err = String;
+
+    fn from_str(seed: &str) -> Result<Self, Self::Err> {
+        let multiaddr = Multiaddr::from_str(seed).map_err(|e| e.to_string())?;
+
+        let mut protocols = multiaddr.iter();
+
+        let (host, ip_version) = match protocols.next() {
+            Some(Protocol::Dns(host)) => (host.to_string(), IpVersion::Any),
+            Some(Protocol::Dns4(host)) => (host.to_string(), IpVersion::V4),
+            Some(Protocol::Dns6(host)) => (host.to_string(), IpVersion::V6),
+            _ => return Err("expected a /dns, /dns4 or /dns6 multiaddr".to_string()),
+        };
+
+        Ok(Self {
+            seed: seed.to_string(),
+            host,
+            ip_version,
+            suffix: protocols.collect(),
+            resolved: Vec::new(),
+            last_resolved: None,
+            retry: Retry::new(),
+            generation: 0,
+        })
+    }
+}
+
+/// Outcome of the resolution of a DNS seed
+#[derive(Debug)]
+pub struct DnsResolution {
+    pub(crate) seed: usize,
+    pub(crate) generation: u64,
+    pub(crate) result: io::Result<Vec<IpAddr>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    #[test]
+    fn seed_resolves_to_one_multiaddr_per_ip() {
+        let seed = DnsSeed::from_str("/dns/seed.example.com/udp/27000/quic-v1").unwrap();
+        assert_eq!(seed.host, "seed.example.com");
+
+        let ips = [
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            IpAddr::V6(Ipv6Addr::LOCALHOST),
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+        ];
+
+        assert_eq!(
+            seed.to_multiaddrs(&ips),
+            vec![
+                "/ip4/10.0.0.1/udp/27000/quic-v1"
+                    .parse::<Multiaddr>()
+                    .unwrap(),
+                "/ip6/::1/udp/27000/quic-v1".parse().unwrap(),
+            ]
+        );
+
+        let seed = DnsSeed::from_str("/dns4/seed.example.com/tcp/27000").unwrap();
+        assert_eq!(
+            seed.to_multiaddrs(&ips),
+            vec!["/ip4/10.0.0.1/tcp/27000".parse::<Multiaddr>().unwrap()]
+        );
+    }
+
+    #[test]
+    fn seed_must_be_a_dns_multiaddr() {
+        assert!(DnsSeed::from_str("/ip4/10.0.0.1/tcp/27000").is_err());
+        assert!(DnsSeed::from_str("seed.example.com:27000").is_err());
+    }
+}
@@ -12,12 +12,15 @@ where
     }
 
     fn should_close(&self, peer_id: PeerId, connection_id: ConnectionId) -> bool {
-        // Only close ephemeral connections (i.e not inbound/outbound connections)
-        self.outbound_connections
-            .get(&peer_id)
-            .map_or(true, |out_conn| {
-                out_conn.connection_id != Some(connection_id)
-            })
+        // Only close ephemeral connections (i.e not inbound/outbound/persistent/pinned connections)
+        !self.is_persistent_peer(&peer_id)
+            && !self.is_pinned_peer(&peer_id)
+            && self
+                .outbound_connections
+                .get(&peer_id)
+                .map_or(true, |out_conn| {
+                    out_conn.connection_id != Some(connection_id)
+                })
             && self.inbound_connections.get(&peer_id) != Some(&connection_id)
     }
 
@@ -38,6 +41,8 @@ where
         {
             if swarm.close_connection(connection_id) {
                 info!("Closing connection {connection_id} to peer {peer_id}");
+
+                self.metrics.increment_total_closed_connections();
             } else {
                 error!("Error closing connection {connection_id} to peer {peer_id}");
             }
@@ -52,12 +57,15 @@ where
         peer_id: PeerId,
         connection_id: ConnectionId,
     ) {
+        let mut disconnected = false;
+
         if let Some(connections) = self.active_connections.get_mut(&peer_id) {
             if connections.contains(&connection_id) {
                 warn!("Removing active connection {connection_id} to peer {peer_id}");
                 connections.retain(|id| id != &connection_id);
                 if connections.is_empty() {
                     self.active_connections.remove(&peer_id);
+                    disconnected = true;
                 }
             } else {
                 warn!("Non-established connection {connection_id} to peer {peer_id} closed");
@@ -67,6 +75,8 @@ where
         // In case the connection was closed before identifying the peer
         self.controller.dial.remove_in_progress(&connection_id);
 
+        self.handle_closed_incoming_connection(connection_id);
+
         if self
             .outbound_connections
             .get(&peer_id)
@@ -87,6 +97,11 @@ where
             self.inbound_connections.remove(&peer_id);
         }
 
+        if disconnected {
+            self.handle_closed_persistent_connection(peer_id);
+            self.check_dns_seeds_min_peers();
+        }
+
         self.update_connections_metrics();
     }
 }
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use libp2p::{
     request_response::{OutboundRequestId, ResponseChannel},
     PeerId, Swarm,
@@ -10,10 +12,53 @@ use crate::{
     Discovery, DiscoveryClient,
 };
 
+const CONNECT_REQUEST_BASE_BACKOFF: Duration = Duration::from_secs(1);
+const CONNECT_REQUEST_MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+/// Delay before a new connect request to a peer after the given number of consecutive failures,
+/// doubling with each failure up to `CONNECT_REQUEST_MAX_BACKOFF`.
+fn connect_request_backoff(failures: u32) -> Duration {
+    if failures == 0 {
+        return Duration::ZERO;
+    }
+
+    CONNECT_REQUEST_BASE_BACKOFF
+        .saturating_mul(2_u32.saturating_pow(failures - 1))
+        .min(CONNECT_REQUEST_MAX_BACKOFF)
+}
+
 impl<C> Discovery<C>
 where
     C: DiscoveryClient,
 {
+    /// Current backoff applied before sending a connect request to the given peer
+    pub fn connect_request_backoff(&self, peer_id: &PeerId) -> Duration {
+        connect_request_backoff(
+            self.connect_request_failures
+                .get(peer_id)
+                .copied()
+                .unwrap_or(0),
+        )
+    }
+
+    /// Enqueue a connect request to the given peer, delayed by the backoff
+    /// resulting from previous failed connect requests to that peer.
+    pub(crate) fn add_to_connect_request_queue(&mut self, peer_id: PeerId) {
+        let backoff = self.connect_request_backoff(&peer_id);
+
+        if !backoff.is_zero() {
+            debug!(
+                "Delaying connect request to peer {peer_id} by {}ms after previous failures",
+                backoff.as_millis()
+            );
+        }
+
+        self.controller.connect_request.add_to_queue(
+            RequestData::new(peer_id),
+            (!backoff.is_zero()).then_some(backoff),
+        );
+    }
+
     pub fn can_connect_request(&self) -> bool {
         self.controller.peers_request.can_perform()
     }
@@ -67,6 +112,10 @@ where
         if self.outbound_connections.contains_key(&peer) {
             info!("Peer {peer} is already an outbound connection");
 
+            accepted = true;
+        } else if self.is_persistent_peer(&peer) {
+            info!("Peer {peer} is a persistent peer");
+
             accepted = true;
         } else if self.inbound_connections.len() < self.config.num_inbound_peers {
             info!("Upgrading connection of peer {peer} to inbound connection");
@@ -119,6 +168,8 @@ where
         if accepted {
             info!("Successfully upgraded connection of peer {peer} to outbound connection");
 
+            self.connect_request_failures.remove(&peer);
+
             if let Some(out_conn) = self.outbound_connections.get_mut(&peer) {
                 out_conn.is_persistent = true;
             }
@@ -145,6 +196,8 @@ where
     fn handle_connect_rejection(&mut self, swarm: &mut Swarm<C>, peer: PeerId) {
         self.outbound_connections.remove(&peer);
 
+        *self.connect_request_failures.entry(peer).or_default() += 1;
+
         if self.is_enabled() {
             self.repair_outbound_connection(swarm);
         }
@@ -160,6 +213,8 @@ where
             .connect_request
             .remove_in_progress(&request_id)
         {
+            self.scorer.record_failure(request_data.peer_id());
+
             if request_data.retry.count() < self.config.connect_request_max_retries {
                 // Retry request after a delay
                 request_data.retry.inc_count();
@@ -182,3 +237,57 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use malachitebft_metrics::Registry;
+
+    use super::*;
+    use crate::test_utils::make_swarm;
+    use crate::Config;
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        assert_eq!(connect_request_backoff(0), Duration::ZERO);
+        assert_eq!(connect_request_backoff(1), Duration::from_secs(1));
+        assert_eq!(connect_request_backoff(2), Duration::from_secs(2));
+        assert_eq!(connect_request_backoff(5), Duration::from_secs(16));
+        assert_eq!(connect_request_backoff(9), Duration::from_secs(256));
+        assert_eq!(connect_request_backoff(10), CONNECT_REQUEST_MAX_BACKOFF);
+        assert_eq!(
+            connect_request_backoff(u32::MAX),
+            CONNECT_REQUEST_MAX_BACKOFF
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_connect_requests_are_backed_off() {
+        let config = Config::new(true);
+        let mut swarm = make_swarm(&config);
+        let mut discovery = Discovery::new(config, vec![], &mut Registry::default());
+
+        let peer_id = PeerId::random();
+
+        // No failures yet, the request is enqueued right away
+        discovery.add_to_connect_request_queue(peer_id);
+        assert_eq!(discovery.controller.connect_request.queue_len(), 1);
+        discovery.controller.connect_request.recv().await;
+
+        discovery.handle_connect_rejection(&mut swarm, peer_id);
+        discovery.handle_connect_rejection(&mut swarm, peer_id);
+        assert_eq!(
+            discovery.connect_request_backoff(&peer_id),
+            Duration::from_secs(2)
+        );
+
+        // The request is delayed
+        discovery.add_to_connect_request_queue(peer_id);
+        assert_eq!(discovery.controller.connect_request.queue_len(), 0);
+
+        // Other peers are not affected
+        assert_eq!(
+            discovery.connect_request_backoff(&PeerId::random()),
+            Duration::ZERO
+        );
+    }
+}
@@ -91,6 +91,10 @@ where
                 debug!(
                     "Accepted incoming connection from {peer_id} with connection {connection_id}"
                 );
+
+                if !self.handle_incoming_connection(swarm, peer_id, connection_id) {
+                    return;
+                }
             }
         }
 
@@ -119,6 +123,15 @@ where
 
     pub fn handle_failed_connection(&mut self, swarm: &mut Swarm<C>, connection_id: ConnectionId) {
         if let Some(mut connection_data) = self.controller.dial.remove_in_progress(&connection_id) {
+            if let Some(peer_id) = connection_data.peer_id() {
+                self.scorer.record_failure(peer_id);
+            }
+
+            // Persistent peers are retried indefinitely
+            if self.handle_failed_persistent_connection(&connection_data) {
+                return;
+            }
+
             if connection_data.retry.count() < self.config.dial_max_retries {
                 // Retry dialing after a delay
                 connection_data.retry.inc_count();
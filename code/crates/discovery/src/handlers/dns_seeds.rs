@@ -0,0 +1,343 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use libp2p::Swarm;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    connection::ConnectionData, dns::DnsResolution, util::Retry, Discovery, DiscoveryClient,
+};
+
+/// Minimum delay between two resolutions of a DNS seed triggered by a lack of connected peers,
+/// to avoid flooding the DNS server when peers keep disconnecting.
+const MIN_DNS_SEED_EARLY_RESOLUTION_DELAY: Duration = Duration::from_secs(10);
+
+impl<C> Discovery<C>
+where
+    C: DiscoveryClient,
+{
+    /// Resolve all the DNS seeds. Each seed is then resolved again periodically.
+    pub fn resolve_dns_seeds(&mut self) {
+        for index in 0..self.dns_seeds.len() {
+            self.schedule_dns_resolution(index, None);
+        }
+    }
+
+    /// Spawn the resolution of a DNS seed after the given delay, if any.
+    /// The result is sent to the `dns_resolution` queue of the controller.
+    ///
+    /// Any resolution of the seed scheduled before is superseded by this one.
+    fn schedule_dns_resolution(&mut self, index: usize, delay: Option<Duration>) {
+        let seed = &mut self.dns_seeds[index];
+        seed.generation += 1;
+
+        let generation = seed.generation;
+        let host = seed.host.clone();
+        let resolver = Arc::clone(&self.dns_resolver);
+        let tx_queue = self.controller.dns_resolution.sender();
+
+        tokio::spawn(async move {
+            if let Some(delay) = delay {
+                tokio::time::sleep(delay).await;
+            }
+
+            let result = resolver.resolve(&host).await;
+
+            tx_queue
+                .send(DnsResolution {
+                    seed: index,
+                    generation,
+                    result,
+                })
+                .unwrap_or_else(|e| {
+                    error!("Failed to send DNS resolution to queue: {:?}", e);
+                })
+        });
+    }
+
+    /// Dial the addresses a DNS seed resolved to, and schedule its next resolution.
+    pub fn handle_dns_resolution(&mut self, swarm: &Swarm<C>, resolution: DnsResolution) {
+        let index = resolution.seed;
+
+        let Some(seed) = self.dns_seeds.get_mut(index) else {
+            return;
+        };
+
+        if seed.generation != resolution.generation {
+            debug!("Ignoring superseded resolution of DNS seed {}", seed.seed);
+            return;
+        }
+
+        let interval = self.config.dns_seeds_resolution_interval;
+
+        let multiaddrs = match resolution.result {
+            Ok(ips) if !ips.is_empty() => seed.to_multiaddrs(&ips),
+            Ok(_) => Vec::new(),
+            Err(e) => {
+                warn!("Failed to resolve DNS seed {}: {e}", seed.seed);
+                Vec::new()
+            }
+        };
+
+        if multiaddrs.is_empty() {
+            // Never wait longer than the regular resolution interval
+            let delay = seed.retry.next_delay().min(interval);
+            seed.retry.inc_count();
+
+            warn!(
+                "No address for DNS seed {}, retrying in {}ms",
+                seed.seed,
+                delay.as_millis()
+            );
+
+            self.schedule_dns_resolution(index, Some(delay));
+            return;
+        }
+
+        info!(
+            "DNS seed {} resolved to {} addresses",
+            seed.seed,
+            multiaddrs.len()
+        );
+
+        let stale: Vec<_> = seed
+            .resolved
+            .iter()
+            .filter(|addr| !multiaddrs.contains(addr))
+            .cloned()
+            .collect();
+
+        seed.resolved = multiaddrs.clone();
+        seed.last_resolved = Some(Instant::now());
+        seed.retry = Retry::new();
+
+        // Forget the addresses the seed does not resolve to anymore, eg. after a redeployment
+        self.bootstrap_nodes
+            .retain(|(_, addr)| !stale.contains(addr));
+
+        for multiaddr in multiaddrs {
+            if !self
+                .bootstrap_nodes
+                .iter()
+                .any(|(_, addr)| *addr == multiaddr)
+            {
+                self.bootstrap_nodes.push((None, multiaddr.clone()));
+            }
+
+            self.add_to_dial_queue(swarm, ConnectionData::new(None, multiaddr));
+        }
+
+        self.schedule_dns_resolution(index, Some(interval));
+    }
+
+    /// Resolve the DNS seeds right away if we are connected to too few peers.
+    ///
+    /// Seeds which are failing to resolve are left alone, since they are already being retried.
+    pub(crate) fn check_dns_seeds_min_peers(&mut self) {
+        if !self.is_enabled() || self.active_connections.len() >= self.config.dns_seeds_min_peers {
+            return;
+        }
+
+        let indices: Vec<usize> = self
+            .dns_seeds
+            .iter()
+            .enumerate()
+            .filter(|(_, seed)| {
+                seed.last_resolved.is_some_and(|last_resolved| {
+                    last_resolved.elapsed() >= MIN_DNS_SEED_EARLY_RESOLUTION_DELAY
+                })
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        if indices.is_empty() {
+            return;
+        }
+
+        info!(
+            "Only {} connected peers, resolving {} DNS seeds",
+            self.active_connections.len(),
+            indices.len()
+        );
+
+        for index in indices {
+            self.schedule_dns_resolution(index, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::io;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::sync::Mutex;
+
+    use libp2p::swarm::ConnectionId;
+    use libp2p::{Multiaddr, PeerId};
+    use malachitebft_metrics::Registry;
+
+    use super::*;
+    use crate::test_utils::make_swarm;
+    use crate::{Behaviour, Config, DnsResolver, ResolveFuture};
+
+    const SEED: &str = "/dns/seed.example.com/tcp/27000";
+
+    /// Resolver returning the given results in order, and no address once they are exhausted
+    #[derive(Debug, Default)]
+    struct MockResolver {
+        results: Mutex<VecDeque<io::Result<Vec<IpAddr>>>>,
+    }
+
+    impl MockResolver {
+        fn new(results: Vec<io::Result<Vec<IpAddr>>>) -> Arc<Self> {
+            Arc::new(Self {
+                results: Mutex::new(results.into()),
+            })
+        }
+    }
+
+    impl DnsResolver for MockResolver {
+        fn resolve(&self, host: &str) -> ResolveFuture {
+            assert_eq!(host, "seed.example.com");
+
+            let result = self
+                .results
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or(Ok(vec![]));
+
+            Box::pin(async move { result })
+        }
+    }
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, last))
+    }
+
+    fn multiaddr(last: u8) -> Multiaddr {
+        format!("/ip4/10.0.0.{last}/tcp/27000").parse().unwrap()
+    }
+
+    fn setup(
+        interval: Duration,
+        min_peers: usize,
+        results: Vec<io::Result<Vec<IpAddr>>>,
+    ) -> (Discovery<Behaviour>, Swarm<Behaviour>) {
+        let mut config = Config::new(true);
+        config.set_bootstrap_dns_seeds(vec![SEED.to_string()]);
+        config.set_dns_seeds_resolution_interval(interval);
+        config.set_dns_seeds_min_peers(min_peers);
+
+        let swarm = make_swarm(&config);
+
+        let mut discovery = Discovery::new(config, vec![], &mut Registry::default());
+        discovery.set_dns_resolver(MockResolver::new(results));
+
+        (discovery, swarm)
+    }
+
+    async fn next_resolution(discovery: &mut Discovery<Behaviour>, swarm: &Swarm<Behaviour>) {
+        let resolution = discovery.controller.dns_resolution.recv().await.unwrap();
+        discovery.handle_dns_resolution(swarm, resolution);
+    }
+
+    async fn dialed(discovery: &mut Discovery<Behaviour>) -> Vec<Multiaddr> {
+        let mut dialed = Vec::new();
+
+        while discovery.controller.dial.queue_len() > 0 {
+            let connection_data = discovery.controller.dial.recv().await.unwrap();
+            dialed.push(connection_data.multiaddr());
+        }
+
+        dialed
+    }
+
+    fn bootstrap_addrs(discovery: &Discovery<Behaviour>) -> Vec<Multiaddr> {
+        discovery
+            .bootstrap_nodes
+            .iter()
+            .map(|(_, addr)| addr.clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn dns_seed_is_resolved_periodically_and_retried() {
+        let (mut discovery, swarm) = setup(
+            Duration::from_millis(10),
+            0,
+            vec![
+                Ok(vec![ip(1)]),
+                Err(io::Error::new(io::ErrorKind::Other, "lookup failed")),
+                Ok(vec![ip(2), ip(3)]),
+            ],
+        );
+
+        discovery.resolve_dns_seeds();
+
+        next_resolution(&mut discovery, &swarm).await;
+        assert_eq!(dialed(&mut discovery).await, vec![multiaddr(1)]);
+        assert_eq!(bootstrap_addrs(&discovery), vec![multiaddr(1)]);
+
+        // The failure is retried with backoff, and does not change the known addresses
+        next_resolution(&mut discovery, &swarm).await;
+        assert_eq!(dialed(&mut discovery).await, vec![]);
+        assert_eq!(discovery.dns_seeds[0].retry.count(), 1);
+        assert_eq!(bootstrap_addrs(&discovery), vec![multiaddr(1)]);
+
+        // The seed now resolves to new addresses, eg. after a redeployment
+        next_resolution(&mut discovery, &swarm).await;
+        assert_eq!(
+            dialed(&mut discovery).await,
+            vec![multiaddr(2), multiaddr(3)]
+        );
+        assert_eq!(discovery.dns_seeds[0].retry.count(), 0);
+        assert_eq!(
+            bootstrap_addrs(&discovery),
+            vec![multiaddr(2), multiaddr(3)]
+        );
+    }
+
+    #[tokio::test]
+    async fn dns_seed_is_resolved_again_when_peers_are_lost() {
+        let (mut discovery, mut swarm) = setup(
+            Duration::from_secs(3600),
+            1,
+            vec![Ok(vec![ip(1)]), Ok(vec![ip(2)])],
+        );
+
+        discovery.resolve_dns_seeds();
+
+        next_resolution(&mut discovery, &swarm).await;
+        assert_eq!(dialed(&mut discovery).await, vec![multiaddr(1)]);
+
+        let generation = discovery.dns_seeds[0].generation;
+
+        // The only peer we are connected to goes away
+        let peer_id = PeerId::random();
+        let connection_id = ConnectionId::new_unchecked(0);
+        discovery
+            .active_connections
+            .insert(peer_id, vec![connection_id]);
+
+        // Too soon after the last resolution
+        discovery.handle_closed_connection(&mut swarm, peer_id, connection_id);
+        assert_eq!(discovery.dns_seeds[0].generation, generation);
+
+        discovery.dns_seeds[0].last_resolved =
+            Instant::now().checked_sub(MIN_DNS_SEED_EARLY_RESOLUTION_DELAY);
+
+        discovery
+            .active_connections
+            .insert(peer_id, vec![connection_id]);
+        discovery.handle_closed_connection(&mut swarm, peer_id, connection_id);
+
+        // The periodic resolution is superseded by the early one
+        assert_eq!(discovery.dns_seeds[0].generation, generation + 1);
+
+        next_resolution(&mut discovery, &swarm).await;
+        assert_eq!(dialed(&mut discovery).await, vec![multiaddr(2)]);
+        assert_eq!(bootstrap_addrs(&discovery), vec![multiaddr(2)]);
+    }
+}
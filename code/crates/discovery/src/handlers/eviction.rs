@@ -0,0 +1,313 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use libp2p::{swarm::ConnectionId, PeerId, Swarm};
+use tracing::{error, warn};
+
+use crate::{Discovery, DiscoveryClient, IncomingConnection};
+
+/// A peer is considered recently active if a message was received from it within this window
+const RECENT_ACTIVITY_WINDOW: Duration = Duration::from_secs(60);
+
+/// How useful an incoming connection is. When the maximum number of incoming connections
+/// is exceeded, the connections with the lowest score are closed first.
+///
+/// Fields are compared in declaration order.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct ConnectionScore {
+    /// Whether the connection was upgraded to an inbound connection
+    is_inbound: bool,
+    /// Whether a message was recently received from the peer
+    is_recently_active: bool,
+    /// Whether the peer is part of the discovered peers
+    is_discovered: bool,
+    /// How long the connection has been established for
+    age: Duration,
+}
+
+impl fmt::Display for ConnectionScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "inbound: {}, recently active: {}, discovered: {}, age: {}ms",
+            self.is_inbound,
+            self.is_recently_active,
+            self.is_discovered,
+            self.age.as_millis()
+        )
+    }
+}
+
+impl<C> Discovery<C>
+where
+    C: DiscoveryClient,
+{
+    /// Outbound and persistent connections are never evicted,
+    /// and do not count towards the maximum number of incoming connections.
+    fn is_protected_connection(&self, peer_id: &PeerId, connection_id: ConnectionId) -> bool {
+        self.is_persistent_peer(peer_id)
+            || self
+                .outbound_connections
+                .get(peer_id)
+                .map_or(false, |out_conn| {
+                    out_conn.connection_id == Some(connection_id)
+                })
+    }
+
+    fn connection_score(
+        &self,
+        connection_id: ConnectionId,
+        connection: &IncomingConnection,
+        now: Instant,
+    ) -> ConnectionScore {
+        ConnectionScore {
+            is_inbound: self.inbound_connections.get(&connection.peer_id) == Some(&connection_id),
+            is_recently_active: connection.last_activity.map_or(false, |last_activity| {
+                now.duration_since(last_activity) < RECENT_ACTIVITY_WINDOW
+            }),
+            is_discovered: self.discovered_peers.contains_key(&connection.peer_id),
+            age: now.duration_since(connection.established_at),
+        }
+    }
+
+    /// Record a new incoming connection, and close the least useful incoming connections
+    /// if the maximum number of incoming connections is exceeded.
+    ///
+    /// Returns whether the new connection was kept.
+    pub(crate) fn handle_incoming_connection(
+        &mut self,
+        swarm: &mut Swarm<C>,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+    ) -> bool {
+        self.incoming_connections
+            .insert(connection_id, IncomingConnection::new(peer_id));
+
+        let now = Instant::now();
+
+        let mut candidates: Vec<_> = self
+            .incoming_connections
+            .iter()
+            .filter(|(id, conn)| !self.is_protected_connection(&conn.peer_id, **id))
+            .map(|(id, conn)| (self.connection_score(*id, conn, now), *id, conn.peer_id))
+            .collect();
+
+        let max = self.config.max_inbound_peers;
+
+        if candidates.len() <= max {
+            return true;
+        }
+
+        candidates.sort_by_key(|(score, _, _)| *score);
+
+        let excess = candidates.len() - max;
+        let mut accepted = true;
+
+        for (score, id, peer) in candidates.into_iter().take(excess) {
+            if id == connection_id {
+                warn!("Rejecting incoming connection {id} from peer {peer}: limit of {max} incoming connections reached and it has the lowest score ({score})");
+                accepted = false;
+            } else {
+                warn!("Evicting incoming connection {id} from peer {peer}: limit of {max} incoming connections reached and it has the lowest score ({score})");
+            }
+
+            self.incoming_connections.remove(&id);
+
+            if !swarm.close_connection(id) {
+                error!("Error closing connection {id} to peer {peer}");
+            }
+        }
+
+        accepted
+    }
+
+    pub(crate) fn handle_closed_incoming_connection(&mut self, connection_id: ConnectionId) {
+        self.incoming_connections.remove(&connection_id);
+    }
+
+    /// Record that a message was received from the given peer
+    pub fn record_peer_activity(&mut self, peer_id: &PeerId) {
+        let now = Instant::now();
+
+        for connection in self
+            .incoming_connections
+            .values_mut()
+            .filter(|conn| conn.peer_id == *peer_id)
+        {
+            connection.last_activity = Some(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::str::FromStr;
+
+    use libp2p::core::ConnectedPoint;
+    use libp2p::Multiaddr;
+    use malachitebft_metrics::Registry;
+
+    use super::*;
+    use crate::test_utils::{identify_info, make_swarm};
+    use crate::{Behaviour, Config, OutboundConnection, PersistentPeer};
+
+    const MAX: usize = 5;
+
+    fn setup() -> (Discovery<Behaviour>, Swarm<Behaviour>) {
+        let mut config = Config::new(true);
+        config.set_max_inbound_peers(MAX);
+
+        let swarm = make_swarm(&config);
+        let discovery = Discovery::new(config, vec![], &mut Registry::default());
+
+        (discovery, swarm)
+    }
+
+    fn listener() -> ConnectedPoint {
+        ConnectedPoint::Listener {
+            local_addr: Multiaddr::from_str("/ip4/127.0.0.1/tcp/27000").unwrap(),
+            send_back_addr: Multiaddr::from_str("/ip4/127.0.0.1/tcp/27001").unwrap(),
+        }
+    }
+
+    fn connect(
+        discovery: &mut Discovery<Behaviour>,
+        swarm: &mut Swarm<Behaviour>,
+        index: usize,
+    ) -> (PeerId, ConnectionId) {
+        let peer_id = PeerId::random();
+        let connection_id = ConnectionId::new_unchecked(index);
+
+        discovery.handle_connection(swarm, peer_id, connection_id, listener());
+
+        (peer_id, connection_id)
+    }
+
+    fn set_age(discovery: &mut Discovery<Behaviour>, connection_id: ConnectionId, secs: u64) {
+        discovery
+            .incoming_connections
+            .get_mut(&connection_id)
+            .unwrap()
+            .established_at = Instant::now() - Duration::from_secs(secs);
+    }
+
+    fn discover(discovery: &mut Discovery<Behaviour>, peer_id: PeerId) {
+        discovery
+            .discovered_peers
+            .insert(peer_id, identify_info(vec![]));
+    }
+
+    fn surviving(discovery: &Discovery<Behaviour>) -> BTreeSet<ConnectionId> {
+        discovery.incoming_connections.keys().copied().collect()
+    }
+
+    #[test]
+    fn lowest_scoring_incoming_connections_are_evicted() {
+        let (mut discovery, mut swarm) = setup();
+
+        // Fill up the incoming connections
+        let conns: Vec<_> = (0..MAX)
+            .map(|i| connect(&mut discovery, &mut swarm, i))
+            .collect();
+
+        for (i, secs) in [50, 30, 10, 20, 40].into_iter().enumerate() {
+            set_age(&mut discovery, conns[i].1, secs);
+        }
+
+        discover(&mut discovery, conns[1].0);
+        discover(&mut discovery, conns[3].0);
+        discovery.record_peer_activity(&conns[2].0);
+
+        assert_eq!(surviving(&discovery).len(), MAX);
+
+        // Two new connections from discovered peers evict the two oldest
+        // connections from peers which are neither discovered nor active
+        for i in MAX..MAX + 2 {
+            let peer_id = PeerId::random();
+            discover(&mut discovery, peer_id);
+
+            let connection_id = ConnectionId::new_unchecked(i);
+            discovery.handle_connection(&mut swarm, peer_id, connection_id, listener());
+            assert!(discovery.incoming_connections.contains_key(&connection_id));
+        }
+
+        let mut evicted = BTreeSet::from([conns[4].1, conns[0].1]);
+
+        // Three new connections from unknown peers are rejected
+        for i in MAX + 2..MAX + 5 {
+            let (_, connection_id) = connect(&mut discovery, &mut swarm, i);
+            evicted.insert(connection_id);
+        }
+
+        let surviving = surviving(&discovery);
+        assert_eq!(surviving.len(), MAX);
+        assert!(surviving.is_disjoint(&evicted));
+        assert_eq!(
+            surviving,
+            [
+                conns[1].1,
+                conns[2].1,
+                conns[3].1,
+                ConnectionId::new_unchecked(MAX),
+                ConnectionId::new_unchecked(MAX + 1)
+            ]
+            .into_iter()
+            .collect()
+        );
+    }
+
+    #[test]
+    fn outbound_and_persistent_connections_are_never_evicted() {
+        let (mut discovery, mut swarm) = setup();
+
+        let outbound_peer = PeerId::random();
+        let outbound_connection = ConnectionId::new_unchecked(100);
+        discovery.outbound_connections.insert(
+            outbound_peer,
+            OutboundConnection {
+                connection_id: Some(outbound_connection),
+                is_persistent: true,
+            },
+        );
+
+        let persistent_multiaddr = Multiaddr::from_str("/ip4/127.0.0.1/tcp/27002").unwrap();
+        let persistent_peer = PeerId::random();
+        let mut peer = PersistentPeer::new(persistent_multiaddr);
+        peer.peer_id = Some(persistent_peer);
+        discovery.persistent_peers.push(peer);
+        let persistent_connection = ConnectionId::new_unchecked(101);
+
+        for (peer_id, connection_id) in [
+            (outbound_peer, outbound_connection),
+            (persistent_peer, persistent_connection),
+        ] {
+            discovery.handle_connection(&mut swarm, peer_id, connection_id, listener());
+        }
+
+        // Fill up and exceed the limit with ephemeral connections
+        for i in 0..MAX + 5 {
+            connect(&mut discovery, &mut swarm, i);
+        }
+
+        let surviving = surviving(&discovery);
+        assert_eq!(surviving.len(), MAX + 2);
+        assert!(surviving.contains(&outbound_connection));
+        assert!(surviving.contains(&persistent_connection));
+    }
+
+    #[test]
+    fn connection_score_ordering() {
+        let score = |is_inbound, is_recently_active, is_discovered, secs| ConnectionScore {
+            is_inbound,
+            is_recently_active,
+            is_discovered,
+            age: Duration::from_secs(secs),
+        };
+
+        assert!(score(false, false, false, 100) < score(false, false, true, 0));
+        assert!(score(false, false, true, 100) < score(false, true, false, 0));
+        assert!(score(false, true, true, 100) < score(true, false, false, 0));
+        assert!(score(false, false, false, 1) < score(false, false, false, 2));
+    }
+}
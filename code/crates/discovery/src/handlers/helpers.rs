@@ -1,4 +1,4 @@
-use tracing::info;
+use tracing::{debug, info};
 
 use crate::{Discovery, DiscoveryClient};
 
@@ -17,23 +17,52 @@ where
         let num_active_connections = self.active_connections_len();
         let num_outbound_connections = self.outbound_connections.len();
         let num_inbound_connections = self.inbound_connections.len();
-        let num_ephemeral_connections = num_active_connections
-            .saturating_sub(num_outbound_connections + num_inbound_connections);
+        let num_persistent_connections: usize = self
+            .active_connections
+            .iter()
+            .filter(|(peer_id, _)| self.is_persistent_peer(peer_id))
+            .map(|(_, connection_ids)| connection_ids.len())
+            .sum();
+        let num_ephemeral_connections = num_active_connections.saturating_sub(
+            num_outbound_connections + num_inbound_connections + num_persistent_connections,
+        );
 
         info!(
-            "Active connections: {} (duplicates: {}), Outbound connections: {}, Inbound connections: {}, Ephemeral connections: {}",
+            "Active connections: {} (duplicates: {}), Outbound connections: {}, Inbound connections: {}, Persistent connections: {}, Ephemeral connections: {}",
             num_active_connections,
             self.active_connections_num_duplicates(),
             num_outbound_connections,
             num_inbound_connections,
+            num_persistent_connections,
             num_ephemeral_connections,
         );
 
+        self.metrics
+            .set_num_discovered_peers(self.discovered_peers.len());
         self.metrics.set_connections_status(
             num_active_connections,
             num_outbound_connections,
             num_inbound_connections,
+            num_persistent_connections,
             num_ephemeral_connections,
         );
     }
+
+    /// Record how many outbound connections we are missing to reach the target number of outbound peers
+    pub(crate) fn update_outbound_connections_deficit(&self) {
+        let deficit = self
+            .config
+            .num_outbound_peers
+            .saturating_sub(self.outbound_connections.len());
+
+        if deficit > 0 {
+            debug!(
+                "Missing {deficit} outbound connections (got {}, expected {})",
+                self.outbound_connections.len(),
+                self.config.num_outbound_peers
+            );
+        }
+
+        self.metrics.set_outbound_connections_deficit(deficit);
+    }
 }
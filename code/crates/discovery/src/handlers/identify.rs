@@ -2,7 +2,7 @@ use libp2p::{identify, swarm::ConnectionId, PeerId, Swarm};
 use tracing::{info, warn};
 
 use crate::config::BootstrapProtocol;
-use crate::{request::RequestData, Discovery, DiscoveryClient, OutboundConnection, State};
+use crate::{Discovery, DiscoveryClient, OutboundConnection, State};
 
 impl<C> Discovery<C>
 where
@@ -72,7 +72,19 @@ where
             self.active_connections.insert(peer_id, vec![connection_id]);
         }
 
-        if self.is_enabled() {
+        self.register_persistent_peer(peer_id, &info);
+
+        if self.is_persistent_peer(&peer_id) {
+            // Connections to persistent peers are kept regardless of the discovery process,
+            // and do not count towards the number of outbound connections.
+            info!("Connection {connection_id} from peer {peer_id} is persistent");
+
+            if self.is_enabled() && self.config.bootstrap_protocol == BootstrapProtocol::Kademlia {
+                swarm
+                    .behaviour_mut()
+                    .add_address(&peer_id, info.listen_addrs.first().unwrap().clone());
+            }
+        } else if self.is_enabled() {
             if self
                 .outbound_connections
                 .get(&peer_id)
@@ -104,9 +116,7 @@ where
                     },
                 );
 
-                self.controller
-                    .connect_request
-                    .add_to_queue(RequestData::new(peer_id), None);
+                self.add_to_connect_request_queue(peer_id);
 
                 if self.outbound_connections.len() >= self.config.num_outbound_peers {
                     info!("Minimum number of peers reached");
@@ -145,9 +155,7 @@ where
                     },
                 );
 
-                self.controller
-                    .connect_request
-                    .add_to_queue(RequestData::new(peer_id), None);
+                self.add_to_connect_request_queue(peer_id);
             } else {
                 info!("Connection {connection_id} from peer {peer_id} is ephemeral");
 
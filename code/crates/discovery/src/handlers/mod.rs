@@ -4,8 +4,12 @@ pub mod bootstrap;
 pub mod close;
 pub mod connect_request;
 pub mod dial;
+pub mod dns_seeds;
+pub mod eviction;
 pub mod extension;
 pub mod helpers;
 pub mod identify;
 pub mod peers_management;
 pub mod peers_request;
+pub mod persistent_peers;
+pub mod topology;
@@ -1,7 +1,7 @@
 use libp2p::{swarm::ConnectionId, PeerId, Swarm};
 use tracing::{debug, info, warn};
 
-use crate::{request::RequestData, Discovery, DiscoveryClient, OutboundConnection};
+use crate::{connection::ConnectionData, Discovery, DiscoveryClient, OutboundConnection};
 
 use super::selection::selector::Selection;
 
@@ -9,7 +9,73 @@ impl<C> Discovery<C>
 where
     C: DiscoveryClient,
 {
+    fn is_active_connection(&self, peer_id: &PeerId, connection_id: &ConnectionId) -> bool {
+        self.active_connections
+            .get(peer_id)
+            .map_or(false, |connection_ids| {
+                connection_ids.contains(connection_id)
+            })
+    }
+
+    pub(crate) fn is_pinned_peer(&self, peer_id: &PeerId) -> bool {
+        self.config.pinned_peers.contains(peer_id)
+    }
+
+    pub(crate) fn is_denied_peer(&self, peer_id: &PeerId) -> bool {
+        self.config.denied_peers.contains(peer_id)
+    }
+
+    /// Add the given peer to the outbound connections, using its active connection if any,
+    /// and request the peer to keep the connection persistent.
+    fn add_outbound_connection(&mut self, peer_id: PeerId) {
+        let connection_id = match self.active_connections.get(&peer_id) {
+            Some(connection_ids) => {
+                if connection_ids.len() > 1 {
+                    warn!("Peer {peer_id} has more than one connection");
+                }
+                connection_ids.first().cloned()
+            }
+            None => {
+                warn!("Peer {peer_id} has no active connection");
+                None
+            }
+        };
+
+        self.outbound_connections.insert(
+            peer_id,
+            OutboundConnection {
+                connection_id,
+                is_persistent: false,
+            },
+        );
+
+        self.add_to_connect_request_queue(peer_id);
+    }
+
+    /// Keep the pinned peers we are connected to as outbound connections
+    fn keep_pinned_peers(&mut self) {
+        let pinned_peers: Vec<PeerId> = self
+            .config
+            .pinned_peers
+            .iter()
+            .filter(|peer_id| {
+                self.active_connections.contains_key(peer_id)
+                    && !self.outbound_connections.contains_key(peer_id)
+                    && !self.is_denied_peer(peer_id)
+            })
+            .cloned()
+            .collect();
+
+        for peer_id in pinned_peers {
+            info!("Keeping pinned peer {peer_id} as outbound connection");
+
+            self.add_outbound_connection(peer_id);
+        }
+    }
+
     fn select_outbound_connections(&mut self, swarm: &mut Swarm<C>) {
+        self.keep_pinned_peers();
+
         let n = self
             .config
             .num_outbound_peers
@@ -19,6 +85,7 @@ where
             swarm,
             &self.discovered_peers,
             self.get_excluded_peers(),
+            self.scorer.as_ref(),
             n,
         ) {
             Selection::Exactly(peers) => {
@@ -36,31 +103,7 @@ where
         };
 
         for peer_id in peers {
-            if let Some(connection_ids) = self.active_connections.get(&peer_id) {
-                if connection_ids.len() > 1 {
-                    warn!("Peer {peer_id} has more than one connection");
-                }
-                self.outbound_connections.insert(
-                    peer_id,
-                    OutboundConnection {
-                        connection_id: connection_ids.first().cloned(),
-                        is_persistent: false,
-                    },
-                );
-            } else {
-                warn!("Peer {peer_id} has no active connection");
-                self.outbound_connections.insert(
-                    peer_id,
-                    OutboundConnection {
-                        connection_id: None,
-                        is_persistent: false,
-                    },
-                );
-            }
-
-            self.controller
-                .connect_request
-                .add_to_queue(RequestData::new(peer_id), None);
+            self.add_outbound_connection(peer_id);
         }
 
         // Safety check: make sure that the inbound connections are not part of the outbound connections
@@ -71,6 +114,8 @@ where
                     out_conn.connection_id != Some(*connection_id)
                 })
         });
+
+        self.update_connections_metrics();
     }
 
     pub(crate) fn adjust_connections(&mut self, swarm: &mut Swarm<C>) {
@@ -80,6 +125,10 @@ where
 
         info!("Adjusting connections");
 
+        // Do not keep any connection to the denied peers
+        self.inbound_connections
+            .retain(|peer_id, _| !self.config.denied_peers.contains(peer_id));
+
         self.select_outbound_connections(swarm);
 
         let connections_to_close: Vec<(PeerId, ConnectionId)> = self
@@ -104,6 +153,10 @@ where
                         out_conn.connection_id != Some(*connection_id)
                     })
             })
+            // Remove persistent and pinned connections
+            .filter(|(peer_id, _)| {
+                !self.is_persistent_peer(peer_id) && !self.is_pinned_peer(peer_id)
+            })
             .collect();
 
         info!(
@@ -123,22 +176,76 @@ where
                 Some(self.config.ephemeral_connection_timeout),
             );
         }
+
+        self.update_outbound_connections_deficit();
     }
 
     pub(crate) fn repair_outbound_connection(&mut self, swarm: &mut Swarm<C>) {
+        self.try_repair_outbound_connection(swarm);
+
+        self.update_outbound_connections_deficit();
+    }
+
+    fn try_repair_outbound_connection(&mut self, swarm: &mut Swarm<C>) {
         if !self.is_enabled() || self.outbound_connections.len() >= self.config.num_outbound_peers {
             return;
         }
 
         info!("Repairing an outbound connection");
 
+        // Reconnect to a pinned peer first if any is missing from the outbound connections
+        if let Some(peer_id) = self
+            .config
+            .pinned_peers
+            .iter()
+            .find(|peer_id| {
+                !self.outbound_connections.contains_key(peer_id)
+                    && !self.is_denied_peer(peer_id)
+                    && (self.active_connections.contains_key(peer_id)
+                        || self.discovered_peers.contains_key(peer_id))
+            })
+            .cloned()
+        {
+            info!("Repairing outbound connections with pinned peer {peer_id}");
+
+            if !self.active_connections.contains_key(&peer_id) {
+                if let Some(multiaddr) = self
+                    .discovered_peers
+                    .get(&peer_id)
+                    .and_then(|info| info.listen_addrs.first().cloned())
+                {
+                    self.controller
+                        .dial
+                        .add_to_queue(ConnectionData::new(Some(peer_id), multiaddr), None);
+                }
+            }
+
+            self.add_outbound_connection(peer_id);
+
+            self.update_connections_metrics();
+
+            return;
+        }
+
         // Upgrade any inbound connection to outbound if any is available
         if let Some((peer_id, connection_id)) = self
             .inbound_connections
             .iter()
             // Do not select inbound connections whose peer id is already in the outbound connections
             // with another connection id
-            .find(|(peer_id, _)| !self.outbound_connections.contains_key(peer_id))
+            .filter(|(peer_id, _)| !self.outbound_connections.contains_key(peer_id))
+            // Never upgrade a connection from a denied peer
+            .filter(|(peer_id, _)| !self.is_denied_peer(peer_id))
+            // Skip inbound connections which were closed in the meantime
+            .find(|(peer_id, connection_id)| {
+                let is_active = self.is_active_connection(peer_id, connection_id);
+
+                if !is_active {
+                    debug!("Inbound connection {connection_id} of peer {peer_id} is not active anymore, skipping it");
+                }
+
+                is_active
+            })
             .map(|(peer_id, connection_id)| (*peer_id, *connection_id))
         {
             info!("Upgrading connection {connection_id} of peer {peer_id} to outbound connection");
@@ -155,6 +262,7 @@ where
             // Consider the connect request as done
             self.controller.connect_request.register_done_on(peer_id);
 
+            self.metrics.increment_total_upgraded_connections();
             self.update_connections_metrics();
 
             return;
@@ -165,44 +273,250 @@ where
             swarm,
             &self.discovered_peers,
             self.get_excluded_peers(),
+            self.scorer.as_ref(),
             1,
         ) {
             Selection::Exactly(peers) => {
                 if let Some(peer_id) = peers.first() {
                     info!("Trying to connect to peer {peer_id} to repair outbound connections");
-                    if let Some(connection_ids) = self.active_connections.get(peer_id) {
-                        if connection_ids.len() > 1 {
-                            warn!("Peer {peer_id} has more than one connection");
-                        }
-                        self.outbound_connections.insert(
-                            *peer_id,
-                            OutboundConnection {
-                                connection_id: connection_ids.first().cloned(),
-                                is_persistent: false,
-                            },
-                        );
-                    } else {
-                        warn!("Peer {peer_id} has no active connection");
-                        self.outbound_connections.insert(
-                            *peer_id,
-                            OutboundConnection {
-                                connection_id: None,
-                                is_persistent: false,
-                            },
-                        );
-                    }
 
-                    self.controller
-                        .connect_request
-                        .add_to_queue(RequestData::new(*peer_id), None);
+                    self.add_outbound_connection(*peer_id);
                 }
             }
             _ => {
                 // If no candidate is available, then trigger the discovery extension
                 warn!("No available peers to repair outbound connections");
 
+                self.metrics.increment_total_extensions_without_candidates();
+
                 self.initiate_extension_with_target(swarm, 1);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use malachitebft_metrics::Registry;
+
+    use super::*;
+    use crate::config::Selector;
+    use crate::test_utils::{identify_info, make_swarm};
+    use crate::{Behaviour, Config};
+
+    fn setup(pinned_peer: PeerId) -> (Discovery<Behaviour>, Swarm<Behaviour>) {
+        let mut config = Config::new(true);
+        config.set_peers_bounds(2, 2);
+        config.set_pinned_peers(HashSet::from([pinned_peer]));
+
+        let swarm = make_swarm(&config);
+        let discovery = Discovery::new(config, vec![], &mut Registry::default());
+
+        (discovery, swarm)
+    }
+
+    #[tokio::test]
+    async fn pinned_peer_survives_adjust_cycle() {
+        let pinned_peer = PeerId::random();
+        let (mut discovery, mut swarm) = setup(pinned_peer);
+
+        let connection_id = ConnectionId::new_unchecked(0);
+        discovery
+            .active_connections
+            .insert(pinned_peer, vec![connection_id]);
+
+        discovery.adjust_connections(&mut swarm);
+
+        let out_conn = &discovery.outbound_connections[&pinned_peer];
+        assert_eq!(out_conn.connection_id, Some(connection_id));
+        assert_eq!(discovery.controller.connect_request.queue_len(), 1);
+
+        // Running another cycle keeps the pinned peer as is
+        discovery.adjust_connections(&mut swarm);
+
+        assert_eq!(discovery.outbound_connections.len(), 1);
+        assert!(discovery.outbound_connections.contains_key(&pinned_peer));
+        assert_eq!(discovery.controller.connect_request.queue_len(), 1);
+
+        // Only the pinned peer was available as outbound candidate
+        assert_eq!(discovery.metrics.get_outbound_connections_deficit(), 1);
+    }
+
+    #[tokio::test]
+    async fn repair_without_candidates_is_recorded() {
+        let (mut discovery, mut swarm) = setup(PeerId::random());
+
+        discovery.repair_outbound_connection(&mut swarm);
+
+        assert!(discovery.outbound_connections.is_empty());
+        assert_eq!(discovery.metrics.get_outbound_connections_deficit(), 2);
+        assert_eq!(
+            discovery.metrics.get_total_extensions_without_candidates(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn repair_prefers_pinned_peers() {
+        let pinned_peer = PeerId::random();
+        let (mut discovery, mut swarm) = setup(pinned_peer);
+
+        let inbound_peer = PeerId::random();
+        let inbound_connection = ConnectionId::new_unchecked(0);
+        discovery
+            .active_connections
+            .insert(inbound_peer, vec![inbound_connection]);
+        discovery
+            .inbound_connections
+            .insert(inbound_peer, inbound_connection);
+
+        let pinned_connection = ConnectionId::new_unchecked(1);
+        discovery
+            .active_connections
+            .insert(pinned_peer, vec![pinned_connection]);
+
+        discovery.repair_outbound_connection(&mut swarm);
+
+        assert_eq!(discovery.outbound_connections.len(), 1);
+        assert_eq!(
+            discovery.outbound_connections[&pinned_peer].connection_id,
+            Some(pinned_connection)
+        );
+        assert!(discovery.inbound_connections.contains_key(&inbound_peer));
+    }
+
+    #[tokio::test]
+    async fn repair_skips_closed_inbound_connections() {
+        let (mut discovery, mut swarm) = setup(PeerId::random());
+
+        // The connection of the first peer was closed after being selected as inbound,
+        // but before the closing was processed
+        let closed_peer = PeerId::random();
+        let closed_connection = ConnectionId::new_unchecked(0);
+        discovery
+            .inbound_connections
+            .insert(closed_peer, closed_connection);
+
+        let active_peer = PeerId::random();
+        let active_connection = ConnectionId::new_unchecked(1);
+        discovery
+            .active_connections
+            .insert(active_peer, vec![active_connection]);
+        discovery
+            .inbound_connections
+            .insert(active_peer, active_connection);
+
+        discovery.repair_outbound_connection(&mut swarm);
+
+        assert!(!discovery.outbound_connections.contains_key(&closed_peer));
+        assert_eq!(
+            discovery.outbound_connections[&active_peer].connection_id,
+            Some(active_connection)
+        );
+
+        // With only the closed connection left, the repair falls back to the selection
+        discovery.repair_outbound_connection(&mut swarm);
+
+        assert!(!discovery.outbound_connections.contains_key(&closed_peer));
+        assert_eq!(
+            discovery.metrics.get_total_extensions_without_candidates(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn connections_metrics_follow_connects_and_disconnects() {
+        let (mut discovery, mut swarm) = setup(PeerId::random());
+
+        let peer_id = PeerId::random();
+        let connection_id = ConnectionId::new_unchecked(0);
+        let multiaddr: libp2p::Multiaddr = "/ip4/127.0.0.1/tcp/27000".parse().unwrap();
+
+        // With the initial discovery done and missing outbound peers, the connection is outbound
+        discovery.handle_new_peer(
+            &mut swarm,
+            connection_id,
+            peer_id,
+            identify_info(vec![multiaddr]),
+        );
+
+        assert_eq!(discovery.metrics.get_num_discovered_peers(), 1);
+        assert_eq!(discovery.metrics.get_num_active_connections(), 1);
+        assert_eq!(discovery.metrics.get_num_outbound_connections(), 1);
+        assert_eq!(discovery.metrics.get_num_inbound_connections(), 0);
+
+        discovery.handle_closed_connection(&mut swarm, peer_id, connection_id);
+
+        // The peer stays known, but the connection is gone
+        assert_eq!(discovery.metrics.get_num_discovered_peers(), 1);
+        assert_eq!(discovery.metrics.get_num_active_connections(), 0);
+        assert_eq!(discovery.metrics.get_num_outbound_connections(), 0);
+    }
+
+    #[tokio::test]
+    async fn upgraded_inbound_connection_is_recorded() {
+        let (mut discovery, mut swarm) = setup(PeerId::random());
+
+        let peer_id = PeerId::random();
+        let connection_id = ConnectionId::new_unchecked(0);
+        discovery
+            .active_connections
+            .insert(peer_id, vec![connection_id]);
+        discovery.inbound_connections.insert(peer_id, connection_id);
+
+        discovery.repair_outbound_connection(&mut swarm);
+
+        assert_eq!(discovery.metrics.get_total_upgraded_connections(), 1);
+        assert_eq!(discovery.metrics.get_num_outbound_connections(), 1);
+        assert_eq!(discovery.metrics.get_num_inbound_connections(), 0);
+    }
+
+    #[tokio::test]
+    async fn denied_peer_is_never_selected() {
+        let denied_peer = PeerId::random();
+
+        let mut config = Config::new(true);
+        config.set_selector(Selector::Random);
+        config.set_peers_bounds(2, 2);
+        config.set_pinned_peers(HashSet::from([denied_peer]));
+        config.set_denied_peers(HashSet::from([denied_peer]));
+
+        let mut swarm = make_swarm(&config);
+        let mut discovery = Discovery::new(config, vec![], &mut Registry::default());
+
+        // The denied peer is the only candidate, and is connected to us as an inbound peer
+        let connection_id = ConnectionId::new_unchecked(0);
+        discovery.discovered_peers.insert(
+            denied_peer,
+            identify_info(vec!["/ip4/127.0.0.1/tcp/27000".parse().unwrap()]),
+        );
+        discovery
+            .active_connections
+            .insert(denied_peer, vec![connection_id]);
+        discovery
+            .inbound_connections
+            .insert(denied_peer, connection_id);
+
+        assert_eq!(discovery.get_excluded_peers(), vec![denied_peer]);
+
+        discovery.repair_outbound_connection(&mut swarm);
+
+        assert!(discovery.outbound_connections.is_empty());
+        assert_eq!(
+            discovery.metrics.get_total_extensions_without_candidates(),
+            1
+        );
+
+        discovery.adjust_connections(&mut swarm);
+
+        assert!(discovery.outbound_connections.is_empty());
+        assert!(discovery.inbound_connections.is_empty());
+
+        // Reconnecting does not make the peer eligible again
+        discovery.repair_outbound_connection(&mut swarm);
+
+        assert!(discovery.outbound_connections.is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+use std::time::{Duration, Instant};
+
+use libp2p::{identify, PeerId, Swarm};
+use tracing::{info, warn};
+
+use crate::{connection::ConnectionData, util::Retry, Discovery, DiscoveryClient};
+
+/// Persistent peers are redialed indefinitely, so the backoff between two redials is capped.
+const MAX_PERSISTENT_PEER_REDIAL_DELAY: Duration = Duration::from_secs(60);
+
+impl<C> Discovery<C>
+where
+    C: DiscoveryClient,
+{
+    pub(crate) fn is_persistent_peer(&self, peer_id: &PeerId) -> bool {
+        self.persistent_peers
+            .iter()
+            .any(|peer| peer.peer_id.as_ref() == Some(peer_id))
+    }
+
+    pub fn dial_persistent_peers(&mut self, swarm: &Swarm<C>) {
+        let multiaddrs: Vec<_> = self
+            .persistent_peers
+            .iter()
+            .map(|peer| peer.multiaddr.clone())
+            .collect();
+
+        for multiaddr in multiaddrs {
+            self.add_to_dial_queue(swarm, ConnectionData::new(None, multiaddr));
+        }
+    }
+
+    /// Record the peer id of a persistent peer once it is identified, and reset its backoff.
+    pub(crate) fn register_persistent_peer(&mut self, peer_id: PeerId, info: &identify::Info) {
+        let Some(peer) = self.persistent_peers.iter_mut().find(|peer| {
+            peer.peer_id == Some(peer_id) || info.listen_addrs.contains(&peer.multiaddr)
+        }) else {
+            return;
+        };
+
+        if let Some(unreachable_since) = peer.unreachable_since {
+            info!(
+                "Persistent peer {peer_id} is reachable again after {}ms",
+                unreachable_since.elapsed().as_millis()
+            );
+        }
+
+        peer.peer_id = Some(peer_id);
+        peer.retry = Retry::new();
+        peer.unreachable_since = None;
+        peer.reported_unreachable = false;
+    }
+
+    /// Queue a redial of the persistent peer, if any, whose last connection was closed.
+    pub(crate) fn handle_closed_persistent_connection(&mut self, peer_id: PeerId) {
+        if let Some(index) = self
+            .persistent_peers
+            .iter()
+            .position(|peer| peer.peer_id == Some(peer_id))
+        {
+            warn!("Lost connection to persistent peer {peer_id}, redialing");
+
+            self.redial_persistent_peer(index);
+        }
+    }
+
+    /// Queue a redial if the failed dial was to a persistent peer.
+    ///
+    /// Returns whether the dial was to a persistent peer.
+    pub(crate) fn handle_failed_persistent_connection(
+        &mut self,
+        connection_data: &ConnectionData,
+    ) -> bool {
+        let Some(index) = self.persistent_peers.iter().position(|peer| {
+            peer.multiaddr == connection_data.multiaddr()
+                || (peer.peer_id.is_some() && peer.peer_id == connection_data.peer_id())
+        }) else {
+            return false;
+        };
+
+        self.redial_persistent_peer(index);
+
+        true
+    }
+
+    fn redial_persistent_peer(&mut self, index: usize) {
+        let threshold = self.config.persistent_peer_unreachable_threshold;
+        let peer = &mut self.persistent_peers[index];
+
+        let now = Instant::now();
+        let unreachable_since = *peer.unreachable_since.get_or_insert(now);
+        let unreachable_for = now.duration_since(unreachable_since);
+
+        if !peer.reported_unreachable && unreachable_for >= threshold {
+            warn!(
+                "Persistent peer at {} has been unreachable for {}ms, will keep retrying",
+                peer.multiaddr,
+                unreachable_for.as_millis()
+            );
+
+            peer.reported_unreachable = true;
+        }
+
+        // The first redial happens right away, subsequent ones are backed off
+        let delay = (peer.retry.count() > 0).then(|| {
+            peer.retry
+                .next_delay()
+                .min(MAX_PERSISTENT_PEER_REDIAL_DELAY)
+        });
+
+        peer.retry.inc_count();
+
+        let mut connection_data = ConnectionData::new(peer.peer_id, peer.multiaddr.clone());
+        connection_data.retry = peer.retry.clone();
+
+        self.controller.dial.add_to_queue(connection_data, delay);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use libp2p::swarm::ConnectionId;
+    use libp2p::Multiaddr;
+    use malachitebft_metrics::Registry;
+
+    use super::*;
+    use crate::test_utils::{identify_info, make_swarm};
+    use crate::{Behaviour, Config};
+
+    fn setup(threshold: Duration) -> (Discovery<Behaviour>, Swarm<Behaviour>, Multiaddr) {
+        let multiaddr = Multiaddr::from_str("/ip4/127.0.0.1/tcp/27000").unwrap();
+
+        let mut config = Config::new(true);
+        config.set_persistent_peers(vec![multiaddr.clone()]);
+        config.set_persistent_peer_unreachable_threshold(threshold);
+
+        let swarm = make_swarm(&config);
+
+        let discovery = Discovery::new(config, vec![], &mut Registry::default());
+
+        (discovery, swarm, multiaddr)
+    }
+
+    #[tokio::test]
+    async fn persistent_peer_is_redialed_on_disconnect() {
+        let (mut discovery, mut swarm, multiaddr) = setup(Duration::from_secs(60));
+
+        let peer_id = PeerId::random();
+        let connection_id = ConnectionId::new_unchecked(0);
+
+        // The peer was dialed and identified
+        discovery.persistent_peers[0].peer_id = Some(peer_id);
+        discovery
+            .active_connections
+            .insert(peer_id, vec![connection_id]);
+
+        assert!(discovery.is_persistent_peer(&peer_id));
+        assert_eq!(discovery.controller.dial.queue_len(), 0);
+
+        discovery.handle_closed_connection(&mut swarm, peer_id, connection_id);
+
+        assert_eq!(discovery.controller.dial.queue_len(), 1);
+
+        let connection_data = discovery.controller.dial.recv().await.unwrap();
+        assert_eq!(connection_data.peer_id(), Some(peer_id));
+        assert_eq!(connection_data.multiaddr(), multiaddr);
+
+        let peer = &discovery.persistent_peers[0];
+        assert!(peer.unreachable_since.is_some());
+        assert!(!peer.reported_unreachable);
+        assert_eq!(peer.retry.count(), 1);
+    }
+
+    #[tokio::test]
+    async fn persistent_peer_connections_are_not_closed() {
+        let (mut discovery, mut swarm, _) = setup(Duration::from_secs(60));
+
+        let peer_id = PeerId::random();
+        let connection_id = ConnectionId::new_unchecked(0);
+
+        discovery.persistent_peers[0].peer_id = Some(peer_id);
+        discovery
+            .active_connections
+            .insert(peer_id, vec![connection_id]);
+
+        discovery.adjust_connections(&mut swarm);
+
+        // The persistent peer is neither selected as outbound nor scheduled for closing
+        assert!(discovery.outbound_connections.is_empty());
+        assert_eq!(discovery.controller.close.queue_len(), 0);
+    }
+
+    #[tokio::test]
+    async fn unreachable_persistent_peer_is_reported_and_retried() {
+        let (mut discovery, mut swarm, multiaddr) = setup(Duration::ZERO);
+
+        let connection_data = ConnectionData::new(None, multiaddr.clone());
+        let connection_id = ConnectionId::new_unchecked(0);
+
+        // Fail more dials than allowed for regular peers
+        for _ in 0..=discovery.config.dial_max_retries {
+            discovery
+                .controller
+                .dial
+                .register_in_progress(connection_id, connection_data.clone());
+
+            discovery.handle_failed_connection(&mut swarm, connection_id);
+        }
+
+        let peer = &discovery.persistent_peers[0];
+        assert!(peer.reported_unreachable);
+        assert_eq!(peer.retry.count(), discovery.config.dial_max_retries + 1);
+
+        // A successful identification resets the backoff
+        let peer_id = PeerId::random();
+        discovery.register_persistent_peer(peer_id, &identify_info(vec![multiaddr]));
+
+        let peer = &discovery.persistent_peers[0];
+        assert_eq!(peer.peer_id, Some(peer_id));
+        assert!(peer.unreachable_since.is_none());
+        assert!(!peer.reported_unreachable);
+        assert_eq!(peer.retry.count(), 0);
+    }
+}
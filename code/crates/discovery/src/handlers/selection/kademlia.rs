@@ -1,19 +1,28 @@
 use std::collections::HashMap;
 
 use libp2p::{identify, PeerId, Swarm};
-use rand::seq::SliceRandom;
+use rand::rngs::StdRng;
 use tracing::{debug, warn};
 
 use crate::DiscoveryClient;
 
+use super::scorer::{select_by_score, PeerScorer};
 use super::selector::{Selection, Selector};
 
 #[derive(Debug)]
-pub struct KademliaSelector {}
+pub struct KademliaSelector {
+    /// Fraction of the candidates completing the kbuckets ones picked at random rather than by score
+    random_fraction: f64,
+    /// Source of randomness for the selection, seeded for reproducible selections
+    rng: StdRng,
+}
 
 impl KademliaSelector {
-    pub fn new() -> Self {
-        KademliaSelector {}
+    pub fn new(random_fraction: f64, rng: StdRng) -> Self {
+        KademliaSelector {
+            random_fraction,
+            rng,
+        }
     }
 
     fn kbuckets(&self, swarm: &mut Swarm<impl DiscoveryClient>) -> Vec<(u32, Vec<PeerId>)> {
@@ -41,6 +50,7 @@ where
         swarm: &mut Swarm<C>,
         discovered: &HashMap<PeerId, identify::Info>,
         excluded: Vec<PeerId>,
+        scorer: &dyn PeerScorer,
         n: usize,
     ) -> Selection<PeerId> {
         if n == 0 {
@@ -79,12 +89,17 @@ where
                 candidates.extend(peers.iter());
             }
         } else {
-            // Select candidates in round-robin fashion based on kbucket index in reverse order
+            // Select candidates in round-robin fashion based on kbucket index in reverse order,
+            // picking the highest-scoring peer of each kbucket
             for (_, peers) in kbuckets_candidates.iter().rev().cycle() {
                 if candidates.len() >= n {
                     break;
                 }
-                if let Some(peer_id) = peers.iter().find(|peer_id| !candidates.contains(peer_id)) {
+                if let Some(peer_id) = peers
+                    .iter()
+                    .filter(|peer_id| !candidates.contains(peer_id))
+                    .max_by(|a, b| scorer.score(a).total_cmp(&scorer.score(b)))
+                {
                     candidates.push(*peer_id);
                 }
             }
@@ -94,7 +109,6 @@ where
 
         debug!("Not enough peers in kbuckets, completing with random discovered peers");
 
-        let mut rng = rand::thread_rng();
         let remaining = n - candidates.len();
 
         if discovered.len() < remaining {
@@ -106,15 +120,20 @@ where
             return Selection::Only(candidates);
         }
 
-        candidates.extend(
-            discovered
-                .keys()
-                .filter(|peer_id| !candidates.contains(peer_id))
-                .filter(|peer_id| !excluded.contains(peer_id))
-                .cloned()
-                .collect::<Vec<PeerId>>()
-                .choose_multiple(&mut rng, remaining),
-        );
+        let discovered_candidates: Vec<PeerId> = discovered
+            .keys()
+            .filter(|peer_id| !candidates.contains(peer_id))
+            .filter(|peer_id| !excluded.contains(peer_id))
+            .cloned()
+            .collect();
+
+        candidates.extend(select_by_score(
+            discovered_candidates,
+            remaining,
+            scorer,
+            self.random_fraction,
+            &mut self.rng,
+        ));
 
         Selection::Exactly(candidates)
     }
@@ -1,3 +1,4 @@
 pub mod kademlia;
 pub mod random;
+pub mod scorer;
 pub mod selector;
@@ -1,18 +1,27 @@
 use std::collections::HashMap;
 
 use libp2p::{identify, PeerId, Swarm};
-use rand::seq::SliceRandom;
+use rand::rngs::StdRng;
 
 use crate::DiscoveryClient;
 
+use super::scorer::{select_by_score, PeerScorer};
 use super::selector::{Selection, Selector};
 
 #[derive(Debug)]
-pub struct RandomSelector {}
+pub struct RandomSelector {
+    /// Fraction of the candidates picked at random rather than by score
+    random_fraction: f64,
+    /// Source of randomness for the selection, seeded for reproducible selections
+    rng: StdRng,
+}
 
 impl RandomSelector {
-    pub fn new() -> Self {
-        RandomSelector {}
+    pub fn new(random_fraction: f64, rng: StdRng) -> Self {
+        RandomSelector {
+            random_fraction,
+            rng,
+        }
     }
 }
 
@@ -25,22 +34,26 @@ where
         _swarm: &mut Swarm<C>,
         discovered: &HashMap<PeerId, identify::Info>,
         excluded: Vec<PeerId>,
+        scorer: &dyn PeerScorer,
         n: usize,
     ) -> Selection<PeerId> {
         if n == 0 {
             return Selection::None;
         }
 
-        let mut discovered_candidates: Vec<PeerId> = discovered
+        let discovered_candidates: Vec<PeerId> = discovered
             .keys()
             .filter(|peer_id| !excluded.contains(peer_id))
             .cloned()
             .collect();
 
-        let mut rng = rand::thread_rng();
-        discovered_candidates.shuffle(&mut rng);
-
-        let candidates: Vec<PeerId> = discovered_candidates.into_iter().take(n).collect();
+        let candidates = select_by_score(
+            discovered_candidates,
+            n,
+            scorer,
+            self.random_fraction,
+            &mut self.rng,
+        );
 
         match candidates.len() {
             0 => Selection::None,
@@ -49,3 +62,118 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::test_utils::{identify_info, make_swarm};
+    use crate::{Behaviour, Config, DefaultPeerScorer};
+
+    /// Peers sorted by increasing round-trip time
+    fn setup(
+        num_peers: u64,
+    ) -> (
+        Swarm<Behaviour>,
+        Vec<PeerId>,
+        HashMap<PeerId, identify::Info>,
+        DefaultPeerScorer,
+    ) {
+        let mut scorer = DefaultPeerScorer::default();
+        let peers: Vec<PeerId> = (0..num_peers).map(|_| PeerId::random()).collect();
+
+        for (i, peer_id) in (1..).zip(&peers) {
+            scorer.record_rtt(*peer_id, Duration::from_millis(i * 20));
+        }
+
+        let discovered = peers
+            .iter()
+            .map(|peer_id| (*peer_id, identify_info(vec![])))
+            .collect();
+
+        (make_swarm(&Config::default()), peers, discovered, scorer)
+    }
+
+    #[test]
+    fn lowest_rtt_peers_are_selected_first() {
+        let (mut swarm, peers, discovered, scorer) = setup(10);
+        let mut selector = RandomSelector::new(0.0, StdRng::from_entropy());
+
+        let Selection::Exactly(selected) = selector.try_select_n_outbound_candidates(
+            &mut swarm,
+            &discovered,
+            vec![peers[0]],
+            &scorer,
+            3,
+        ) else {
+            panic!("expected exactly 3 candidates");
+        };
+
+        assert_eq!(selected, peers[1..4]);
+    }
+
+    #[test]
+    fn fraction_of_candidates_is_random() {
+        let (mut swarm, peers, discovered, scorer) = setup(10);
+        let mut selector = RandomSelector::new(0.5, StdRng::from_entropy());
+
+        let Selection::Exactly(selected) =
+            selector.try_select_n_outbound_candidates(&mut swarm, &discovered, vec![], &scorer, 4)
+        else {
+            panic!("expected exactly 4 candidates");
+        };
+
+        // The two best peers are always selected, the other two are picked among the rest
+        assert_eq!(selected[..2], peers[..2]);
+        assert!(selected[2..]
+            .iter()
+            .all(|peer_id| peers[2..].contains(peer_id)));
+        assert_ne!(selected[2], selected[3]);
+    }
+
+    #[test]
+    fn all_candidates_are_selected_if_not_enough() {
+        let (mut swarm, peers, discovered, scorer) = setup(3);
+        let mut selector = RandomSelector::new(0.0, StdRng::from_entropy());
+
+        let Selection::Only(selected) =
+            selector.try_select_n_outbound_candidates(&mut swarm, &discovered, vec![], &scorer, 5)
+        else {
+            panic!("expected only 3 candidates");
+        };
+
+        assert_eq!(selected.len(), 3);
+        assert!(peers.iter().all(|peer_id| selected.contains(peer_id)));
+    }
+
+    #[test]
+    fn same_seed_yields_same_selection() {
+        let (mut swarm, peers, _, scorer) = setup(20);
+
+        let select = |swarm: &mut Swarm<Behaviour>, seed| {
+            // Build the discovered peers anew, so that they are iterated in a different order
+            let discovered: HashMap<_, _> = peers
+                .iter()
+                .map(|peer_id| (*peer_id, identify_info(vec![])))
+                .collect();
+
+            let mut selector = RandomSelector::new(0.5, StdRng::seed_from_u64(seed));
+
+            let Selection::Exactly(selected) =
+                selector.try_select_n_outbound_candidates(swarm, &discovered, vec![], &scorer, 8)
+            else {
+                panic!("expected exactly 8 candidates");
+            };
+
+            selected
+        };
+
+        let selected = select(&mut swarm, 42);
+
+        assert_eq!(selected, select(&mut swarm, 42));
+        assert_ne!(selected, select(&mut swarm, 43));
+    }
+}
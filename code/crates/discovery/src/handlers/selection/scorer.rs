@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+
+use libp2p::PeerId;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Round-trip time at which the latency factor of a peer score is one half
+const REFERENCE_RTT: Duration = Duration::from_millis(100);
+
+/// Weight of a new round-trip time measurement in the moving average
+const RTT_SMOOTHING_FACTOR: f64 = 0.2;
+
+/// Time after which half of the failures recorded for a peer are forgotten
+const DEFAULT_FAILURES_HALF_LIFE: Duration = Duration::from_secs(10 * 60);
+
+/// Scores peers to decide which ones to select as outbound candidates first.
+pub trait PeerScorer: Debug + Send {
+    /// Record a round-trip time measurement to the given peer
+    fn record_rtt(&mut self, peer_id: PeerId, rtt: Duration);
+
+    /// Record a failure to connect or talk to the given peer
+    fn record_failure(&mut self, peer_id: PeerId);
+
+    /// Score of the given peer, the higher the better
+    fn score(&self, peer_id: &PeerId) -> f64;
+}
+
+#[derive(Debug)]
+struct PeerStats {
+    /// Exponential moving average of the round-trip time
+    rtt: Option<Duration>,
+    /// Number of failures, decayed as of `failures_updated_at`
+    failures: f64,
+    failures_updated_at: Instant,
+}
+
+/// Scores peers based on their round-trip time and on the number of recent failures.
+///
+/// The score of a peer is in `[0, 1]` and is the product of:
+/// - a latency factor `REFERENCE_RTT / (REFERENCE_RTT + rtt)`, which is one half for peers
+///   with an unknown round-trip time,
+/// - a failure factor `2^-failures`, where the number of failures decays exponentially
+///   over time so that peers which were flaky in the past can recover.
+#[derive(Debug)]
+pub struct DefaultPeerScorer {
+    failures_half_life: Duration,
+    stats: HashMap<PeerId, PeerStats>,
+}
+
+impl Default for DefaultPeerScorer {
+    fn default() -> Self {
+        Self::new(DEFAULT_FAILURES_HALF_LIFE)
+    }
+}
+
+impl DefaultPeerScorer {
+    pub fn new(failures_half_life: Duration) -> Self {
+        Self {
+            failures_half_life,
+            stats: HashMap::new(),
+        }
+    }
+
+    fn stats_mut(&mut self, peer_id: PeerId, now: Instant) -> &mut PeerStats {
+        self.stats.entry(peer_id).or_insert_with(|| PeerStats {
+            rtt: None,
+            failures: 0.0,
+            failures_updated_at: now,
+        })
+    }
+
+    fn decayed_failures(&self, stats: &PeerStats, now: Instant) -> f64 {
+        if self.failures_half_life.is_zero() {
+            return 0.0;
+        }
+
+        let elapsed = now.saturating_duration_since(stats.failures_updated_at);
+        let half_lives = elapsed.as_secs_f64() / self.failures_half_life.as_secs_f64();
+
+        stats.failures * 0.5_f64.powf(half_lives)
+    }
+
+    pub fn record_failure_at(&mut self, peer_id: PeerId, now: Instant) {
+        let failures = self
+            .stats
+            .get(&peer_id)
+            .map_or(0.0, |stats| self.decayed_failures(stats, now));
+
+        let stats = self.stats_mut(peer_id, now);
+        stats.failures = failures + 1.0;
+        stats.failures_updated_at = now;
+    }
+
+    pub fn score_at(&self, peer_id: &PeerId, now: Instant) -> f64 {
+        let Some(stats) = self.stats.get(peer_id) else {
+            return latency_factor(None);
+        };
+
+        latency_factor(stats.rtt) * 0.5_f64.powf(self.decayed_failures(stats, now))
+    }
+}
+
+impl PeerScorer for DefaultPeerScorer {
+    fn record_rtt(&mut self, peer_id: PeerId, rtt: Duration) {
+        let stats = self.stats_mut(peer_id, Instant::now());
+
+        stats.rtt = Some(match stats.rtt {
+            Some(avg) => {
+                avg.mul_f64(1.0 - RTT_SMOOTHING_FACTOR) + rtt.mul_f64(RTT_SMOOTHING_FACTOR)
+            }
+            None => rtt,
+        });
+    }
+
+    fn record_failure(&mut self, peer_id: PeerId) {
+        self.record_failure_at(peer_id, Instant::now());
+    }
+
+    fn score(&self, peer_id: &PeerId) -> f64 {
+        self.score_at(peer_id, Instant::now())
+    }
+}
+
+fn latency_factor(rtt: Option<Duration>) -> f64 {
+    let reference = REFERENCE_RTT.as_secs_f64();
+    let rtt = rtt.map_or(reference, |rtt| rtt.as_secs_f64());
+
+    reference / (reference + rtt)
+}
+
+/// Select `n` peers among the given candidates, highest-scoring first.
+///
+/// A fraction of the selected peers is picked at random among the remaining candidates,
+/// so that we do not end up connected only to the peers closest to us.
+pub(crate) fn select_by_score(
+    mut candidates: Vec<PeerId>,
+    n: usize,
+    scorer: &dyn PeerScorer,
+    random_fraction: f64,
+    rng: &mut impl Rng,
+) -> Vec<PeerId> {
+    // Sort first so that the selection only depends on the generator,
+    // and not on the order in which the candidates were discovered
+    candidates.sort();
+
+    // Break ties between peers with the same score at random
+    candidates.shuffle(rng);
+
+    if candidates.len() <= n {
+        return candidates;
+    }
+
+    let num_random = (n as f64 * random_fraction.clamp(0.0, 1.0)).floor() as usize;
+    let num_best = n - num_random;
+
+    let mut scored: Vec<(f64, PeerId)> = candidates
+        .into_iter()
+        .map(|peer_id| (scorer.score(&peer_id), peer_id))
+        .collect();
+
+    scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+    let rest: Vec<PeerId> = scored
+        .split_off(num_best)
+        .into_iter()
+        .map(|(_, p)| p)
+        .collect();
+
+    scored
+        .into_iter()
+        .map(|(_, peer_id)| peer_id)
+        .chain(rest.choose_multiple(rng, num_random).cloned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn latency_factor_math() {
+        assert!(approx_eq(latency_factor(None), 0.5));
+        assert!(approx_eq(latency_factor(Some(REFERENCE_RTT)), 0.5));
+        assert!(approx_eq(latency_factor(Some(Duration::ZERO)), 1.0));
+        assert!(approx_eq(latency_factor(Some(REFERENCE_RTT * 3)), 0.25));
+    }
+
+    #[test]
+    fn rtt_is_smoothed() {
+        let mut scorer = DefaultPeerScorer::default();
+        let peer_id = PeerId::random();
+
+        scorer.record_rtt(peer_id, Duration::from_millis(100));
+        scorer.record_rtt(peer_id, Duration::from_millis(200));
+
+        assert_eq!(scorer.stats[&peer_id].rtt, Some(Duration::from_millis(120)));
+    }
+
+    #[test]
+    fn lower_rtt_scores_higher() {
+        let mut scorer = DefaultPeerScorer::default();
+        let (near, far, unknown) = (PeerId::random(), PeerId::random(), PeerId::random());
+
+        scorer.record_rtt(near, Duration::from_millis(10));
+        scorer.record_rtt(far, Duration::from_millis(150));
+
+        assert!(scorer.score(&near) > scorer.score(&unknown));
+        assert!(scorer.score(&unknown) > scorer.score(&far));
+    }
+
+    #[test]
+    fn failures_decay_over_time() {
+        let half_life = Duration::from_secs(600);
+        let mut scorer = DefaultPeerScorer::new(half_life);
+        let peer_id = PeerId::random();
+        let now = Instant::now();
+
+        scorer.record_rtt(peer_id, REFERENCE_RTT);
+        assert!(approx_eq(scorer.score_at(&peer_id, now), 0.5));
+
+        // Each failure halves the score
+        scorer.record_failure_at(peer_id, now);
+        scorer.record_failure_at(peer_id, now);
+        assert!(approx_eq(scorer.score_at(&peer_id, now), 0.125));
+
+        // After one half-life, one of the two failures is forgotten
+        assert!(approx_eq(scorer.score_at(&peer_id, now + half_life), 0.25));
+
+        // Failures recorded later add up to the decayed count
+        scorer.record_failure_at(peer_id, now + half_life);
+        assert!(approx_eq(scorer.score_at(&peer_id, now + half_life), 0.125));
+
+        // An hour later, the peer has almost fully recovered
+        let later = now + half_life + Duration::from_secs(3600);
+        assert!(scorer.score_at(&peer_id, later) > 0.48);
+    }
+
+    #[test]
+    fn zero_half_life_forgets_failures() {
+        let mut scorer = DefaultPeerScorer::new(Duration::ZERO);
+        let peer_id = PeerId::random();
+
+        scorer.record_failure(peer_id);
+
+        assert!(approx_eq(scorer.score(&peer_id), 0.5));
+    }
+}
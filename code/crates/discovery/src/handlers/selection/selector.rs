@@ -1,6 +1,8 @@
 use std::{collections::HashMap, fmt::Debug};
 
 use libp2p::{identify, PeerId, Swarm};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use tracing::info;
 
 use crate::config;
@@ -8,6 +10,7 @@ use crate::{Discovery, DiscoveryClient};
 
 use super::kademlia::KademliaSelector;
 use super::random::RandomSelector;
+use super::scorer::PeerScorer;
 
 impl<C> Discovery<C>
 where
@@ -16,7 +19,14 @@ where
     pub(crate) fn get_selector(
         bootstrap_protocol: config::BootstrapProtocol,
         selector: config::Selector,
+        random_selection_fraction: f64,
+        selection_seed: Option<u64>,
     ) -> Box<dyn Selector<C>> {
+        let rng = match selection_seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
         match selector {
             config::Selector::Kademlia => {
                 if bootstrap_protocol != config::BootstrapProtocol::Kademlia {
@@ -26,23 +36,25 @@ where
                 }
 
                 info!("Using Kademlia selector");
-                Box::new(KademliaSelector::new())
+                Box::new(KademliaSelector::new(random_selection_fraction, rng))
             }
 
             config::Selector::Random => {
                 info!("Using Random selector");
-                Box::new(RandomSelector::new())
+                Box::new(RandomSelector::new(random_selection_fraction, rng))
             }
         }
     }
 
     /// Excluded peers are those that are already outbound connections or have already
-    /// been requested to be so.
+    /// been requested to be so, as well as the denied peers.
     pub(crate) fn get_excluded_peers(&self) -> Vec<PeerId> {
         self.discovered_peers
             .keys()
             .filter(|peer_id| {
                 self.outbound_connections.contains_key(peer_id)
+                    || self.is_persistent_peer(peer_id)
+                    || self.is_denied_peer(peer_id)
                     || self.controller.connect_request.is_done_on(peer_id)
             })
             .cloned()
@@ -61,12 +73,14 @@ where
     C: DiscoveryClient,
 {
     /// Try to select `n` valid outbound candidates. It might return less than `n`
-    ///  candidates if there are not enough valid peers.
+    ///  candidates if there are not enough valid peers. Higher-scoring peers are
+    ///  selected first.
     fn try_select_n_outbound_candidates(
         &mut self,
         swarm: &mut Swarm<C>,
         discovered: &HashMap<PeerId, identify::Info>,
         excluded: Vec<PeerId>,
+        scorer: &dyn PeerScorer,
         n: usize,
     ) -> Selection<PeerId>;
 }
@@ -0,0 +1,154 @@
+use libp2p::{swarm::ConnectionId, PeerId};
+
+use crate::{Discovery, DiscoveryClient};
+
+/// An outbound peer, as part of a [`Topology`] snapshot
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutboundPeer {
+    pub peer_id: PeerId,
+    /// Connection used for this peer, if already established
+    pub connection_id: Option<ConnectionId>,
+    /// Whether the peer accepted to keep the connection persistent
+    pub is_persistent: bool,
+}
+
+/// A snapshot of the peers managed by discovery.
+///
+/// All lists are sorted by peer id.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Topology {
+    pub outbound: Vec<OutboundPeer>,
+    pub inbound: Vec<(PeerId, ConnectionId)>,
+    /// Configured persistent peers which have been identified
+    pub persistent: Vec<PeerId>,
+    /// Discovered peers without any active connection
+    pub unconnected: Vec<PeerId>,
+    /// Peers with a connect request in progress
+    pub pending_connect_requests: Vec<PeerId>,
+    /// Number of connect requests waiting to be sent
+    pub queued_connect_requests: usize,
+}
+
+impl<C> Discovery<C>
+where
+    C: DiscoveryClient,
+{
+    /// Return a consistent snapshot of the current topology
+    pub fn topology(&self) -> Topology {
+        let mut outbound: Vec<OutboundPeer> = self
+            .outbound_connections
+            .iter()
+            .map(|(peer_id, out_conn)| OutboundPeer {
+                peer_id: *peer_id,
+                connection_id: out_conn.connection_id,
+                is_persistent: out_conn.is_persistent,
+            })
+            .collect();
+        outbound.sort_by_key(|peer| peer.peer_id);
+
+        let mut inbound: Vec<(PeerId, ConnectionId)> = self
+            .inbound_connections
+            .iter()
+            .map(|(peer_id, connection_id)| (*peer_id, *connection_id))
+            .collect();
+        inbound.sort();
+
+        let mut persistent: Vec<PeerId> = self
+            .persistent_peers
+            .iter()
+            .filter_map(|peer| peer.peer_id)
+            .collect();
+        persistent.sort();
+
+        let mut unconnected: Vec<PeerId> = self
+            .discovered_peers
+            .keys()
+            .filter(|peer_id| !self.active_connections.contains_key(peer_id))
+            .cloned()
+            .collect();
+        unconnected.sort();
+
+        let mut pending_connect_requests: Vec<PeerId> = self
+            .controller
+            .connect_request
+            .get_in_progress_iter()
+            .map(|(_, request_data)| request_data.peer_id())
+            .collect();
+        pending_connect_requests.sort();
+
+        Topology {
+            outbound,
+            inbound,
+            persistent,
+            unconnected,
+            pending_connect_requests,
+            queued_connect_requests: self.controller.connect_request.queue_len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use malachitebft_metrics::Registry;
+
+    use super::*;
+    use crate::test_utils::identify_info;
+    use crate::{Behaviour, Config, OutboundConnection};
+
+    #[tokio::test]
+    async fn topology_snapshot() {
+        let mut discovery: Discovery<Behaviour> =
+            Discovery::new(Config::new(true), vec![], &mut Registry::default());
+
+        let mut peers: Vec<PeerId> = (0..4).map(|_| PeerId::random()).collect();
+        peers.sort();
+        let [outbound, inbound, unconnected, requested] = peers[..] else {
+            unreachable!()
+        };
+
+        let outbound_connection = ConnectionId::new_unchecked(0);
+        let inbound_connection = ConnectionId::new_unchecked(1);
+
+        discovery
+            .active_connections
+            .insert(outbound, vec![outbound_connection]);
+        discovery
+            .active_connections
+            .insert(inbound, vec![inbound_connection]);
+
+        for peer_id in [outbound, inbound, unconnected] {
+            discovery
+                .discovered_peers
+                .insert(peer_id, identify_info(vec![]));
+        }
+
+        discovery.outbound_connections.insert(
+            outbound,
+            OutboundConnection {
+                connection_id: Some(outbound_connection),
+                is_persistent: true,
+            },
+        );
+        discovery
+            .inbound_connections
+            .insert(inbound, inbound_connection);
+
+        discovery.add_to_connect_request_queue(requested);
+
+        assert_eq!(
+            discovery.topology(),
+            Topology {
+                outbound: vec![OutboundPeer {
+                    peer_id: outbound,
+                    connection_id: Some(outbound_connection),
+                    is_persistent: true,
+                }],
+                inbound: vec![(inbound, inbound_connection)],
+                persistent: vec![],
+                unconnected: vec![unconnected],
+                pending_connect_requests: vec![],
+                queued_connect_requests: 1,
+            }
+        );
+    }
+}
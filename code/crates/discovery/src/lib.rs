@@ -1,4 +1,6 @@
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use tracing::{debug, error, info, warn};
 
@@ -7,6 +9,7 @@ use malachitebft_metrics::Registry;
 use libp2p::{identify, kad, request_response, swarm::ConnectionId, Multiaddr, PeerId, Swarm};
 
 mod util;
+use util::Retry;
 
 mod behaviour;
 pub use behaviour::*;
@@ -20,14 +23,23 @@ pub use config::Config;
 mod controller;
 use controller::Controller;
 
+mod dns;
+use dns::DnsSeed;
+pub use dns::{DnsResolution, DnsResolver, ResolveFuture, TokioDnsResolver};
+
 mod handlers;
+pub use handlers::selection::scorer::{DefaultPeerScorer, PeerScorer};
 use handlers::selection::selector::Selector;
+pub use handlers::topology::{OutboundPeer, Topology};
 
 mod metrics;
 use metrics::Metrics;
 
 mod request;
 
+#[cfg(test)]
+mod test_utils;
+
 #[derive(Debug, PartialEq)]
 enum State {
     Bootstrapping,
@@ -49,6 +61,51 @@ struct OutboundConnection {
     is_persistent: bool,
 }
 
+/// A connection dialed by a remote peer
+#[derive(Debug)]
+struct IncomingConnection {
+    peer_id: PeerId,
+    established_at: Instant,
+    /// Last time a message was received from the peer
+    last_activity: Option<Instant>,
+}
+
+impl IncomingConnection {
+    fn new(peer_id: PeerId) -> Self {
+        Self {
+            peer_id,
+            established_at: Instant::now(),
+            last_activity: None,
+        }
+    }
+}
+
+/// A peer configured by the operator to always stay connected to.
+#[derive(Debug)]
+struct PersistentPeer {
+    /// Peer id, only known once the peer has been identified
+    peer_id: Option<PeerId>,
+    multiaddr: Multiaddr,
+    /// Backoff state for redialing the peer while it is unreachable
+    retry: Retry,
+    /// Time at which the peer became unreachable, if it is not connected
+    unreachable_since: Option<Instant>,
+    /// Whether a warning was emitted for the current unreachability period
+    reported_unreachable: bool,
+}
+
+impl PersistentPeer {
+    fn new(multiaddr: Multiaddr) -> Self {
+        Self {
+            peer_id: None,
+            multiaddr,
+            retry: Retry::new(),
+            unreachable_since: None,
+            reported_unreachable: false,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Discovery<C>
 where
@@ -58,12 +115,19 @@ where
     state: State,
 
     selector: Box<dyn Selector<C>>,
+    scorer: Box<dyn PeerScorer>,
 
     bootstrap_nodes: Vec<(Option<PeerId>, Multiaddr)>,
+    dns_seeds: Vec<DnsSeed>,
+    dns_resolver: Arc<dyn DnsResolver>,
+    persistent_peers: Vec<PersistentPeer>,
     discovered_peers: HashMap<PeerId, identify::Info>,
     active_connections: HashMap<PeerId, Vec<ConnectionId>>,
     outbound_connections: HashMap<PeerId, OutboundConnection>,
     inbound_connections: HashMap<PeerId, ConnectionId>,
+    /// Number of consecutive failed connect requests per peer, used to back off
+    connect_request_failures: HashMap<PeerId, u32>,
+    incoming_connections: HashMap<ConnectionId, IncomingConnection>,
 
     pub controller: Controller,
     metrics: Metrics,
@@ -83,7 +147,21 @@ where
             }
         );
 
-        let state = if config.enabled && bootstrap_nodes.is_empty() {
+        let dns_seeds: Vec<DnsSeed> = config
+            .bootstrap_dns_seeds
+            .iter()
+            .filter_map(|seed| match seed.parse() {
+                Ok(dns_seed) => Some(dns_seed),
+                Err(e) => {
+                    error!("Invalid DNS seed {seed}: {e}");
+                    None
+                }
+            })
+            .collect();
+
+        let no_bootstrap = bootstrap_nodes.is_empty() && dns_seeds.is_empty();
+
+        let state = if config.enabled && no_bootstrap {
             warn!("No bootstrap nodes provided");
             info!("Discovery found 0 peers in 0ms");
             State::Idle
@@ -105,24 +183,45 @@ where
             State::Idle
         };
 
+        let selector = Discovery::get_selector(
+            config.bootstrap_protocol,
+            config.selector,
+            config.random_selection_fraction,
+            config.selection_seed,
+        );
+        let metrics = Metrics::new(registry, !config.enabled || no_bootstrap);
+
+        let persistent_peers = config
+            .persistent_peers
+            .iter()
+            .cloned()
+            .map(PersistentPeer::new)
+            .collect();
+
         Self {
             config,
             state,
 
-            selector: Discovery::get_selector(config.bootstrap_protocol, config.selector),
+            selector,
+            scorer: Box::new(DefaultPeerScorer::default()),
 
             bootstrap_nodes: bootstrap_nodes
                 .clone()
                 .into_iter()
                 .map(|addr| (None, addr))
                 .collect(),
+            dns_seeds,
+            dns_resolver: Arc::new(TokioDnsResolver),
+            persistent_peers,
             discovered_peers: HashMap::new(),
             active_connections: HashMap::new(),
             outbound_connections: HashMap::new(),
             inbound_connections: HashMap::new(),
+            connect_request_failures: HashMap::new(),
+            incoming_connections: HashMap::new(),
 
             controller: Controller::new(),
-            metrics: Metrics::new(registry, !config.enabled || bootstrap_nodes.is_empty()),
+            metrics,
         }
     }
 
@@ -130,6 +229,26 @@ where
         self.config.enabled
     }
 
+    /// Replace the scorer used to rank outbound candidates
+    pub fn set_peer_scorer(&mut self, scorer: Box<dyn PeerScorer>) {
+        self.scorer = scorer;
+    }
+
+    /// Replace the resolver used to resolve the DNS seeds
+    pub fn set_dns_resolver(&mut self, resolver: Arc<dyn DnsResolver>) {
+        self.dns_resolver = resolver;
+    }
+
+    /// Record a round-trip time measurement to the given peer, eg. from a ping
+    pub fn record_peer_rtt(&mut self, peer_id: PeerId, rtt: Duration) {
+        self.scorer.record_rtt(peer_id, rtt);
+    }
+
+    /// Record a failure to reach the given peer, eg. a failed ping
+    pub fn record_peer_failure(&mut self, peer_id: PeerId) {
+        self.scorer.record_failure(peer_id);
+    }
+
     fn active_connections_len(&self) -> usize {
         self.active_connections.values().map(Vec::len).sum()
     }
@@ -16,6 +16,8 @@ pub(crate) struct Metrics {
 
     /// Total number of discovered peers
     total_discovered: Counter,
+    /// Number of peers currently known to discovery
+    num_discovered_peers: Gauge,
 
     /// Number of active connections
     num_active_connections: Gauge,
@@ -25,6 +27,10 @@ pub(crate) struct Metrics {
     num_inbound_connections: Gauge,
     /// Number of ephemeral connections
     num_ephemeral_connections: Gauge,
+    /// Number of connections to persistent peers
+    num_persistent_connections: Gauge,
+    /// Number of outbound connections missing to reach the target number of outbound peers
+    outbound_connections_deficit: Gauge,
 
     /// Total number of dial attempts
     total_dials: Counter,
@@ -40,6 +46,12 @@ pub(crate) struct Metrics {
     total_failed_connect_requests: Counter,
     /// Total number of rejected connect request attempts
     total_rejected_connect_requests: Counter,
+    /// Total number of discovery extensions triggered because no outbound candidates were available
+    total_extensions_without_candidates: Counter,
+    /// Total number of inbound connections upgraded to outbound connections
+    total_upgraded_connections: Counter,
+    /// Total number of ephemeral connections closed when adjusting connections
+    total_closed_connections: Counter,
 }
 
 impl Metrics {
@@ -52,11 +64,14 @@ impl Metrics {
             initial_discovery_finished: if set_finished { Some(now) } else { None },
 
             total_discovered: Counter::default(),
+            num_discovered_peers: Gauge::default(),
 
             num_active_connections: Gauge::default(),
             num_outbound_connections: Gauge::default(),
             num_inbound_connections: Gauge::default(),
             num_ephemeral_connections: Gauge::default(),
+            num_persistent_connections: Gauge::default(),
+            outbound_connections_deficit: Gauge::default(),
 
             total_dials: Counter::default(),
             total_failed_dials: Counter::default(),
@@ -65,6 +80,9 @@ impl Metrics {
             total_connect_requests: Counter::default(),
             total_failed_connect_requests: Counter::default(),
             total_rejected_connect_requests: Counter::default(),
+            total_extensions_without_candidates: Counter::default(),
+            total_upgraded_connections: Counter::default(),
+            total_closed_connections: Counter::default(),
         };
 
         registry.register(
@@ -73,6 +91,12 @@ impl Metrics {
             this.total_discovered.clone(),
         );
 
+        registry.register(
+            "num_discovered_peers",
+            "Number of peers currently known to discovery",
+            this.num_discovered_peers.clone(),
+        );
+
         registry.register(
             "num_active_connections",
             "Number of active connections",
@@ -97,6 +121,18 @@ impl Metrics {
             this.num_ephemeral_connections.clone(),
         );
 
+        registry.register(
+            "num_persistent_connections",
+            "Number of connections to persistent peers",
+            this.num_persistent_connections.clone(),
+        );
+
+        registry.register(
+            "outbound_connections_deficit",
+            "Number of outbound connections missing to reach the target number of outbound peers",
+            this.outbound_connections_deficit.clone(),
+        );
+
         registry.register(
             "total_dials",
             "Total number of dial attempts",
@@ -139,6 +175,24 @@ impl Metrics {
             this.total_rejected_connect_requests.clone(),
         );
 
+        registry.register(
+            "total_extensions_without_candidates",
+            "Total number of discovery extensions triggered because no outbound candidates were available",
+            this.total_extensions_without_candidates.clone(),
+        );
+
+        registry.register(
+            "total_upgraded_connections",
+            "Total number of inbound connections upgraded to outbound connections",
+            this.total_upgraded_connections.clone(),
+        );
+
+        registry.register(
+            "total_closed_connections",
+            "Total number of ephemeral connections closed when adjusting connections",
+            this.total_closed_connections.clone(),
+        );
+
         this
     }
 
@@ -172,19 +226,29 @@ impl Metrics {
         self.total_discovered.inc();
     }
 
+    pub(crate) fn set_num_discovered_peers(&self, num_discovered: usize) {
+        self.num_discovered_peers.set(num_discovered as i64);
+    }
+
     pub(crate) fn set_connections_status(
         &self,
         num_active: usize,
         num_outbound: usize,
         num_inbound: usize,
+        num_persistent: usize,
         num_ephemeral: usize,
     ) {
         self.num_active_connections.set(num_active as i64);
         self.num_outbound_connections.set(num_outbound as i64);
         self.num_inbound_connections.set(num_inbound as i64);
+        self.num_persistent_connections.set(num_persistent as i64);
         self.num_ephemeral_connections.set(num_ephemeral as i64);
     }
 
+    pub(crate) fn set_outbound_connections_deficit(&self, deficit: usize) {
+        self.outbound_connections_deficit.set(deficit as i64);
+    }
+
     pub(crate) fn increment_total_dials(&self) {
         self.total_dials.inc();
     }
@@ -215,7 +279,54 @@ impl Metrics {
         self.total_rejected_connect_requests.inc();
     }
 
+    pub(crate) fn increment_total_extensions_without_candidates(&self) {
+        self.total_extensions_without_candidates.inc();
+    }
+
+    pub(crate) fn increment_total_upgraded_connections(&self) {
+        self.total_upgraded_connections.inc();
+    }
+
+    pub(crate) fn increment_total_closed_connections(&self) {
+        self.total_closed_connections.inc();
+    }
+
     pub(crate) fn _get_total_rejected_connect_requests(&self) -> u64 {
         self.total_rejected_connect_requests.get()
     }
+
+    #[cfg(test)]
+    pub(crate) fn get_outbound_connections_deficit(&self) -> i64 {
+        self.outbound_connections_deficit.get()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn get_total_extensions_without_candidates(&self) -> u64 {
+        self.total_extensions_without_candidates.get()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn get_num_discovered_peers(&self) -> i64 {
+        self.num_discovered_peers.get()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn get_num_active_connections(&self) -> i64 {
+        self.num_active_connections.get()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn get_num_outbound_connections(&self) -> i64 {
+        self.num_outbound_connections.get()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn get_num_inbound_connections(&self) -> i64 {
+        self.num_inbound_connections.get()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn get_total_upgraded_connections(&self) -> u64 {
+        self.total_upgraded_connections.get()
+    }
 }
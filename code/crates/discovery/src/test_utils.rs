@@ -0,0 +1,59 @@
+use libp2p::kad::{Addresses, KBucketKey, KBucketRef, RoutingUpdate};
+use libp2p::request_response::{OutboundRequestId, ResponseChannel};
+use libp2p::{identify, noise, tcp, yamux, Multiaddr, PeerId, Swarm, SwarmBuilder};
+
+use crate::{Behaviour, Config, DiscoveryClient, Request, Response};
+
+impl DiscoveryClient for Behaviour {
+    fn add_address(&mut self, peer: &PeerId, address: Multiaddr) -> RoutingUpdate {
+        self.kademlia
+            .as_mut()
+            .expect("Kademlia behaviour should be available")
+            .add_address(peer, address)
+    }
+
+    fn kbuckets(&mut self) -> impl Iterator<Item = KBucketRef<'_, KBucketKey<PeerId>, Addresses>> {
+        self.kademlia
+            .as_mut()
+            .expect("Kademlia behaviour should be available")
+            .kbuckets()
+    }
+
+    fn send_request(&mut self, peer_id: &PeerId, req: Request) -> OutboundRequestId {
+        self.request_response.send_request(peer_id, req)
+    }
+
+    fn send_response(
+        &mut self,
+        ch: ResponseChannel<Response>,
+        rs: Response,
+    ) -> Result<(), Response> {
+        self.request_response.send_response(ch, rs)
+    }
+}
+
+/// Build a swarm which is never polled, for handlers which require one
+pub(crate) fn make_swarm(config: &Config) -> Swarm<Behaviour> {
+    SwarmBuilder::with_new_identity()
+        .with_tokio()
+        .with_tcp(
+            tcp::Config::default(),
+            noise::Config::new,
+            yamux::Config::default,
+        )
+        .unwrap()
+        .with_behaviour(|keypair| Behaviour::new(keypair, config))
+        .unwrap()
+        .build()
+}
+
+pub(crate) fn identify_info(listen_addrs: Vec<Multiaddr>) -> identify::Info {
+    identify::Info {
+        public_key: libp2p::identity::Keypair::generate_ed25519().public(),
+        protocol_version: String::new(),
+        agent_version: String::new(),
+        listen_addrs,
+        protocols: vec![],
+        observed_addr: Multiaddr::empty(),
+    }
+}
@@ -20,7 +20,7 @@ impl Iterator for FibonacciBackoff {
     type Item = Duration;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let new_next = self.current + self.next;
+        let new_next = self.current.saturating_add(self.next);
         self.current = self.next;
         self.next = new_next;
 
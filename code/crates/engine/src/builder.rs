@@ -0,0 +1,471 @@
+//! Assembling a full node from its components.
+//!
+//! The [`NodeBuilder`] spawns the actors making up a node and wires them together,
+//! using sensible defaults for everything which is not explicitly provided.
+//! Each component can also be provided as an already spawned actor instead,
+//! eg. to swap in a mock mempool or value builder in tests.
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use ractor::{ActorCell, SpawnErr};
+use tokio::task::{JoinError, JoinHandle};
+use tracing::warn;
+
+use malachitebft_config::TimeoutConfig;
+use malachitebft_core_consensus::{ThresholdParams, ValuePayload};
+use malachitebft_core_types::Context;
+use malachitebft_metrics::{Metrics, SharedRegistry};
+use malachitebft_network::{
+    Config as GossipConfig, DiscoveryConfig, GossipSubConfig, Keypair, PubSubProtocol,
+    TransportProtocol,
+};
+use malachitebft_sync as sync;
+
+use crate::consensus::{Consensus, ConsensusParams, ConsensusRef};
+use crate::host::HostRef;
+use crate::network::{Network, NetworkCodec, NetworkRef};
+use crate::node::{Msg as NodeMsg, Node, NodeRef};
+use crate::signer::Signer;
+use crate::supervisor::{Component, RestartFn, RestartPolicy};
+use crate::sync::{Params as SyncParams, Sync, SyncRef};
+use crate::util::events::TxEvent;
+use crate::wal::{Wal, WalCodec, WalRef};
+
+/// How long a graceful shutdown may take by default, before the node is killed
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Extra time given to the node actor to terminate once it has stopped the other actors
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// An error which occurred while building a node
+#[derive(Debug, thiserror::Error)]
+pub enum BuildError {
+    /// A required component was not provided
+    #[error("Missing {0}, which is required to build the node")]
+    Missing(&'static str),
+
+    /// The directory of the write-ahead log could not be created
+    #[error("Failed to create the WAL directory: {0}")]
+    WalDir(#[from] std::io::Error),
+
+    /// An actor could not be spawned
+    #[error("Failed to spawn the {0} actor: {1}")]
+    Spawn(&'static str, SpawnErr),
+}
+
+/// The actors making up a running node, as returned by [`NodeBuilder::spawn`]
+pub struct NodeHandles<Ctx: Context> {
+    /// The node actor, which supervises all the other actors
+    pub node: NodeRef,
+
+    /// The consensus actor
+    pub consensus: ConsensusRef<Ctx>,
+
+    /// The consensus gossip layer
+    pub network: NetworkRef<Ctx>,
+
+    /// The host, which builds the values to propose
+    pub host: HostRef<Ctx>,
+
+    /// The write-ahead log
+    pub wal: WalRef<Ctx>,
+
+    /// The value sync actor, if enabled
+    pub sync: Option<SyncRef<Ctx>>,
+
+    /// The handle of the task running the node actor
+    pub handle: JoinHandle<()>,
+
+    /// How long a graceful shutdown may take, before the node is killed
+    pub shutdown_timeout: Duration,
+}
+
+impl<Ctx: Context> NodeHandles<Ctx> {
+    /// Stop the node along with the actors it supervises, and wait for it to terminate.
+    ///
+    /// A graceful shutdown first lets consensus finish the current height or round
+    /// and flushes the WAL to disk, see [`NodeMsg::Shutdown`], and kills the node
+    /// if it did not terminate within `shutdown_timeout`.
+    /// Otherwise, the node is stopped right away.
+    pub async fn shutdown(mut self, graceful: bool) -> Result<(), JoinError> {
+        if !graceful {
+            self.node.stop(Some("Node is shutting down".to_string()));
+            return self.handle.await;
+        }
+
+        let deadline = Instant::now() + self.shutdown_timeout;
+
+        if let Err(e) = self.node.cast(NodeMsg::Shutdown(deadline)) {
+            warn!("Failed to shut down the node gracefully: {e}");
+            return self.handle.await;
+        }
+
+        let timeout = self.shutdown_timeout + SHUTDOWN_GRACE_PERIOD;
+
+        match tokio::time::timeout(timeout, &mut self.handle).await {
+            Ok(result) => result,
+            Err(_) => {
+                warn!("Node did not shut down in time, killing it");
+                self.node.kill();
+                self.handle.await
+            }
+        }
+    }
+}
+
+/// Builds a full node out of its components, see the [module documentation](self).
+///
+/// The context, the address and the network key of the validator, the initial validator set
+/// and the host are required, along with a codec unless both the network and the WAL are provided.
+pub struct NodeBuilder<Ctx: Context, Codec> {
+    ctx: Option<Ctx>,
+    address: Option<Ctx::Address>,
+    keypair: Option<Keypair>,
+    initial_validator_set: Option<Ctx::ValidatorSet>,
+    start_height: Option<Ctx::Height>,
+    codec: Option<Codec>,
+    host: Option<HostRef<Ctx>>,
+    mempool: Option<ActorCell>,
+    signer: Option<Signer<Ctx>>,
+    timeouts: TimeoutConfig,
+    value_payload: ValuePayload,
+    threshold_params: ThresholdParams,
+    gossip: Option<GossipConfig>,
+    network: Option<NetworkRef<Ctx>>,
+    wal_dir: PathBuf,
+    wal: Option<WalRef<Ctx>>,
+    sync: Option<SyncParams>,
+    registry: Option<SharedRegistry>,
+    metrics: Option<Metrics>,
+    tx_event: TxEvent<Ctx>,
+    restartable: Vec<(Component, RestartFn)>,
+    restart_policy: RestartPolicy,
+    shutdown_timeout: Duration,
+    span: tracing::Span,
+}
+
+impl<Ctx: Context, Codec> Default for NodeBuilder<Ctx, Codec> {
+    fn default() -> Self {
+        Self {
+            ctx: None,
+            address: None,
+            keypair: None,
+            initial_validator_set: None,
+            start_height: None,
+            codec: None,
+            host: None,
+            mempool: None,
+            signer: None,
+            timeouts: TimeoutConfig::default(),
+            value_payload: ValuePayload::PartsOnly,
+            threshold_params: ThresholdParams::default(),
+            gossip: None,
+            network: None,
+            wal_dir: PathBuf::from("wal"),
+            wal: None,
+            sync: None,
+            registry: None,
+            metrics: None,
+            tx_event: TxEvent::new(),
+            restartable: Vec::new(),
+            restart_policy: RestartPolicy::default(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            span: tracing::Span::current(),
+        }
+    }
+}
+
+impl<Ctx: Context, Codec> NodeBuilder<Ctx, Codec> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given context, which also signs the messages of the node
+    /// unless another signer is provided with [`NodeBuilder::with_signer`]
+    pub fn with_context(mut self, ctx: Ctx) -> Self {
+        self.ctx = Some(ctx);
+        self
+    }
+
+    /// Use the given address for this validator
+    pub fn with_address(mut self, address: Ctx::Address) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Use the given key as the identity of this validator on the network
+    pub fn with_validator_key(mut self, keypair: Keypair) -> Self {
+        self.keypair = Some(keypair);
+        self
+    }
+
+    /// Start consensus with the given validator set
+    pub fn with_validator_set(mut self, validator_set: Ctx::ValidatorSet) -> Self {
+        self.initial_validator_set = Some(validator_set);
+        self
+    }
+
+    /// Start consensus at the given height, instead of the default one
+    pub fn with_start_height(mut self, height: Ctx::Height) -> Self {
+        self.start_height = Some(height);
+        self
+    }
+
+    /// Use the given codec for the messages sent over the network and stored in the WAL
+    pub fn with_codec(mut self, codec: Codec) -> Self {
+        self.codec = Some(codec);
+        self
+    }
+
+    /// Use the given host for building the values to propose and assembling the ones received
+    pub fn with_value_builder(mut self, host: HostRef<Ctx>) -> Self {
+        self.host = Some(host);
+        self
+    }
+
+    /// Supervise the given mempool actor
+    pub fn with_mempool(mut self, mempool: ActorCell) -> Self {
+        self.mempool = Some(mempool);
+        self
+    }
+
+    /// Sign the messages of the node with the given signer, instead of the context
+    pub fn with_signer(mut self, signer: Signer<Ctx>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Use the given consensus timeouts, instead of the default ones
+    pub fn with_timeouts(mut self, timeouts: TimeoutConfig) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Deliver the proposed values with the given messages, instead of proposal parts only
+    pub fn with_value_payload(mut self, value_payload: ValuePayload) -> Self {
+        self.value_payload = value_payload;
+        self
+    }
+
+    /// Use the given quorum and honest thresholds, instead of the default ones
+    pub fn with_threshold_params(mut self, threshold_params: ThresholdParams) -> Self {
+        self.threshold_params = threshold_params;
+        self
+    }
+
+    /// Spawn the consensus gossip layer with the given configuration,
+    /// instead of listening on a random local port without any peer
+    pub fn with_gossip(mut self, config: GossipConfig) -> Self {
+        self.gossip = Some(config);
+        self
+    }
+
+    /// Use the given consensus gossip layer, instead of spawning one
+    pub fn with_network(mut self, network: NetworkRef<Ctx>) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Store the write-ahead log in the given directory, instead of `./wal`
+    pub fn with_wal_dir(mut self, wal_dir: impl Into<PathBuf>) -> Self {
+        self.wal_dir = wal_dir.into();
+        self
+    }
+
+    /// Use the given write-ahead log, instead of spawning one
+    pub fn with_wal(mut self, wal: WalRef<Ctx>) -> Self {
+        self.wal = Some(wal);
+        self
+    }
+
+    /// Enable value sync with the given parameters, it is disabled by default
+    pub fn with_sync(mut self, params: SyncParams) -> Self {
+        self.sync = Some(params);
+        self
+    }
+
+    /// Register the metrics of the node in the given registry, instead of the global one
+    pub fn with_metrics(mut self, registry: SharedRegistry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Record the consensus metrics in the given metrics, eg. to share them with the host,
+    /// instead of registering new ones
+    pub fn with_consensus_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Emit the events of the node on the given channel
+    pub fn with_tx_event(mut self, tx_event: TxEvent<Ctx>) -> Self {
+        self.tx_event = tx_event;
+        self
+    }
+
+    /// Restart the given component with the given function when it fails, see [`Node::with_restart`]
+    pub fn with_restart(mut self, component: Component, restart: RestartFn) -> Self {
+        self.restartable.push((component, restart));
+        self
+    }
+
+    /// Use the given policy for restarting the components which fail
+    pub fn with_restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.restart_policy = policy;
+        self
+    }
+
+    /// Give up on a graceful shutdown of the node after the given duration, instead of 10 seconds
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Record the traces of the node in the given span, instead of the current one
+    pub fn with_span(mut self, span: tracing::Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Spawn the components which were not provided, then the node actor supervising all of them.
+    pub async fn spawn(self) -> Result<NodeHandles<Ctx>, BuildError>
+    where
+        Codec: NetworkCodec<Ctx> + WalCodec<Ctx> + Clone,
+    {
+        let ctx = self.ctx.ok_or(BuildError::Missing("context"))?;
+        let address = self.address.ok_or(BuildError::Missing("address"))?;
+        let host = self.host.ok_or(BuildError::Missing("value builder"))?;
+        let initial_validator_set = self
+            .initial_validator_set
+            .ok_or(BuildError::Missing("validator set"))?;
+
+        let start_height = self.start_height.unwrap_or_default();
+        let registry = self
+            .registry
+            .unwrap_or_else(|| SharedRegistry::global().clone());
+        let metrics = self.metrics.unwrap_or_else(|| Metrics::register(&registry));
+
+        let network = match self.network {
+            Some(network) => network,
+            None => {
+                let keypair = self.keypair.ok_or(BuildError::Missing("validator key"))?;
+                let codec = self.codec.clone().ok_or(BuildError::Missing("codec"))?;
+                let config = self.gossip.unwrap_or_else(default_gossip_config);
+
+                Network::spawn(keypair, config, registry.clone(), codec, self.span.clone())
+                    .await
+                    .map_err(|e| BuildError::Spawn("network", e))?
+            }
+        };
+
+        let wal = match self.wal {
+            Some(wal) => wal,
+            None => {
+                let codec = self.codec.ok_or(BuildError::Missing("codec"))?;
+
+                std::fs::create_dir_all(&self.wal_dir)?;
+                let wal_file = self.wal_dir.join("consensus.wal");
+
+                Wal::spawn(&ctx, codec, wal_file, registry.clone(), self.span.clone())
+                    .await
+                    .map_err(|e| BuildError::Spawn("WAL", e))?
+            }
+        };
+
+        let sync = match self.sync {
+            Some(params) => {
+                let metrics = sync::Metrics::register(&registry);
+
+                let sync = Sync::spawn(
+                    ctx.clone(),
+                    network.clone(),
+                    host.clone(),
+                    params,
+                    metrics,
+                    self.span.clone(),
+                )
+                .await
+                .map_err(|e| BuildError::Spawn("sync", e))?;
+
+                Some(sync)
+            }
+            None => None,
+        };
+
+        let params = ConsensusParams {
+            initial_height: start_height,
+            initial_validator_set,
+            address,
+            threshold_params: self.threshold_params,
+            value_payload: self.value_payload,
+            proposal_grace: self.timeouts.proposal_grace_period.is_some(),
+        };
+
+        let signer = self.signer.unwrap_or_else(|| Signer::local(ctx.clone()));
+
+        let consensus = Consensus::spawn(
+            ctx.clone(),
+            params,
+            self.timeouts,
+            signer,
+            network.clone(),
+            host.clone(),
+            wal.clone(),
+            sync.clone(),
+            metrics,
+            self.tx_event,
+            self.span.clone(),
+        )
+        .await
+        .map_err(|e| BuildError::Spawn("consensus", e))?;
+
+        let mut node = Node::new(
+            ctx,
+            network.clone(),
+            consensus.clone(),
+            wal.clone(),
+            sync.clone(),
+            self.mempool,
+            host.clone(),
+            start_height,
+            self.span,
+        )
+        .with_restart_policy(self.restart_policy);
+
+        for (component, restart) in self.restartable {
+            node = node.with_restart(component, restart);
+        }
+
+        let (node, handle) = node
+            .spawn()
+            .await
+            .map_err(|e| BuildError::Spawn("node", e))?;
+
+        Ok(NodeHandles {
+            node,
+            consensus,
+            network,
+            host,
+            wal,
+            sync,
+            handle,
+            shutdown_timeout: self.shutdown_timeout,
+        })
+    }
+}
+
+/// Listen on a random local TCP port, without any peer to connect to
+fn default_gossip_config() -> GossipConfig {
+    GossipConfig {
+        listen_addr: "/ip4/127.0.0.1/tcp/0".parse().expect("valid multiaddr"),
+        persistent_peers: Vec::new(),
+        discovery: DiscoveryConfig::default(),
+        idle_connection_timeout: Duration::from_secs(15 * 60),
+        transport: TransportProtocol::Tcp,
+        gossipsub: GossipSubConfig::default(),
+        pubsub_protocol: PubSubProtocol::default(),
+        rpc_max_size: 10 * 1024 * 1024,
+        pubsub_max_size: 4 * 1024 * 1024,
+    }
+}
@@ -1,35 +1,44 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::time::Duration;
 
 use async_trait::async_trait;
 use eyre::eyre;
 use ractor::{Actor, ActorProcessingErr, ActorRef, RpcReplyPort};
 use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
 use malachitebft_codec as codec;
 use malachitebft_config::TimeoutConfig;
 use malachitebft_core_consensus::{
-    Effect, PeerId, Resumable, Resume, SignedConsensusMsg, ValueToPropose,
+    DriverError, Effect, PeerId, Resumable, Resume, SignedConsensusMsg, ValueToPropose,
 };
 use malachitebft_core_types::{
     Context, Round, SignedExtension, SigningProvider, SigningProviderExt, Timeout, TimeoutKind,
-    ValidatorSet, ValueOrigin,
+    ValidatorSet, Value, ValueOrigin, Vote,
 };
 use malachitebft_metrics::Metrics;
 use malachitebft_sync::{
-    self as sync, InboundRequestId, Response, ValueResponse, VoteSetRequest, VoteSetResponse,
+    self as sync, DecidedValue, InboundRequestId, OutboundRequestId, Response, ValueResponse,
+    VoteSetRequest, VoteSetResponse,
 };
 
-use crate::host::{HostMsg, HostRef, LocallyProposedValue, ProposedValue};
+use crate::host::{self, HostMsg, HostRef, LocallyProposedValue, ProposedValue};
 use crate::network::{NetworkEvent, NetworkMsg, NetworkRef, Status};
+use crate::signer::{Signer, SignerError};
+use crate::supervisor::Component;
 use crate::sync::Msg as SyncMsg;
 use crate::sync::SyncRef;
 use crate::util::events::{Event, TxEvent};
+use crate::util::prepared_value::PreparedValue;
 use crate::util::streaming::StreamMessage;
 use crate::util::timers::{TimeoutElapsed, TimerScheduler};
+use crate::util::value_builds::ValueBuilds;
 use crate::wal::{Msg as WalMsg, WalEntry, WalRef};
 
+mod events;
+pub use events::{ConsensusEvent, ConsensusEvents, ConsensusSubscription};
+
 pub use malachitebft_core_consensus::Error as ConsensusError;
 pub use malachitebft_core_consensus::Params as ConsensusParams;
 pub use malachitebft_core_consensus::State as ConsensusState;
@@ -67,12 +76,14 @@ where
     ctx: Ctx,
     params: ConsensusParams<Ctx>,
     timeout_config: TimeoutConfig,
+    signer: Signer<Ctx>,
     network: NetworkRef<Ctx>,
     host: HostRef<Ctx>,
     wal: WalRef<Ctx>,
     sync: Option<SyncRef<Ctx>>,
     metrics: Metrics,
     tx_event: TxEvent<Ctx>,
+    events: ConsensusEvents<Ctx>,
     span: tracing::Span,
 }
 
@@ -88,14 +99,53 @@ pub enum Msg<Ctx: Context> {
     /// A timeout has elapsed
     TimeoutElapsed(TimeoutElapsed<Timeout>),
 
+    /// The host has built a value, which is proposed once the host is ready to propose it,
+    /// or when the given deadline has passed, whichever comes first
+    BuiltValue(LocallyProposedValue<Ctx>, Instant),
+
+    /// The application's mempool has changed materially since it built the value
+    /// kept for being proposed in a later round of the given height, which must not be proposed anymore.
+    ///
+    /// See [`LocallyProposedValue::reusable`].
+    InvalidatePreparedValue(Ctx::Height),
+
     /// The proposal builder has built a value and can be used in a new proposal consensus message
     ProposeValue(Ctx::Height, Round, Ctx::Value, Option<SignedExtension<Ctx>>),
 
     /// Received and assembled the full value proposed by a validator
     ReceivedProposedValue(ProposedValue<Ctx>, ValueOrigin),
 
+    /// The host has determined the validity of a value it previously sent
+    /// with [`Validity::Unknown`](malachitebft_core_types::Validity::Unknown)
+    /// in [`Msg::ReceivedProposedValue`].
+    ValueValidated(ProposedValue<Ctx>),
+
+    /// The proposal builder has made progress building a value for the given height and round.
+    ///
+    /// This message is purely informational and is not required to be sent by the host.
+    BuildProgress {
+        height: Ctx::Height,
+        round: Round,
+        /// Sequence number of the last part that was built
+        sequence: u64,
+        /// Total size in bytes of the parts built so far
+        bytes: usize,
+    },
+
     /// Get the status of the consensus state machine
     GetStatus(RpcReplyPort<Status<Ctx>>),
+
+    /// Subscribe to the lifecycle events emitted by consensus
+    Subscribe(RpcReplyPort<ConsensusSubscription<Ctx>>),
+
+    /// A component consensus depends on has been restarted by the node after a failure
+    DependencyRestarted(Component),
+
+    /// Stop starting new heights, and reply once the current height is decided
+    /// or the next timeout elapses, whichever comes first.
+    ///
+    /// Sent by the node before shutting down gracefully.
+    Drain(RpcReplyPort<()>),
 }
 
 impl<Ctx: Context> From<NetworkEvent<Ctx>> for Msg<Ctx> {
@@ -127,27 +177,9 @@ impl Timeouts {
         self.config = config;
     }
 
-    fn duration_for(&self, step: TimeoutKind) -> Duration {
-        match step {
-            TimeoutKind::Propose => self.config.timeout_propose,
-            TimeoutKind::Prevote => self.config.timeout_prevote,
-            TimeoutKind::Precommit => self.config.timeout_precommit,
-            TimeoutKind::Commit => self.config.timeout_commit,
-            TimeoutKind::PrevoteTimeLimit => self.config.timeout_step,
-            TimeoutKind::PrecommitTimeLimit => self.config.timeout_step,
-        }
-    }
-
-    fn increase_timeout(&mut self, step: TimeoutKind) {
-        let c = &mut self.config;
-        match step {
-            TimeoutKind::Propose => c.timeout_propose += c.timeout_propose_delta,
-            TimeoutKind::Prevote => c.timeout_prevote += c.timeout_prevote_delta,
-            TimeoutKind::Precommit => c.timeout_precommit += c.timeout_precommit_delta,
-            TimeoutKind::Commit => (),
-            TimeoutKind::PrevoteTimeLimit => (),
-            TimeoutKind::PrecommitTimeLimit => (),
-        };
+    /// The duration of the given timeout, which grows linearly with its round.
+    fn duration_for(&self, timeout: &Timeout) -> Duration {
+        self.config.duration_for_round(timeout.kind, timeout.round)
     }
 }
 
@@ -156,6 +188,7 @@ enum Phase {
     Unstarted,
     Running,
     Recovering,
+    Draining,
 }
 
 pub struct State<Ctx: Context> {
@@ -165,14 +198,27 @@ pub struct State<Ctx: Context> {
     /// Timeouts configuration
     timeouts: Timeouts,
 
+    /// Values being built by the host for us to propose
+    value_builds: ValueBuilds<Ctx::Height>,
+
+    /// Value built too late for its round, to propose in a later round of the current height
+    prepared_value: PreparedValue<Ctx>,
+
     /// The state of the consensus state machine
     consensus: ConsensusState<Ctx>,
 
     /// The set of peers we are connected to.
     connected_peers: BTreeSet<PeerId>,
 
+    /// Values received from sync for heights above the current one,
+    /// processed once consensus reaches their height.
+    synced_values: BTreeMap<Ctx::Height, (OutboundRequestId, PeerId, DecidedValue<Ctx>)>,
+
     /// The current phase
     phase: Phase,
+
+    /// Where to notify the node once we are done draining
+    drained: Option<RpcReplyPort<()>>,
 }
 
 impl<Ctx> State<Ctx>
@@ -193,6 +239,7 @@ where
         ctx: Ctx,
         params: ConsensusParams<Ctx>,
         timeout_config: TimeoutConfig,
+        signer: Signer<Ctx>,
         network: NetworkRef<Ctx>,
         host: HostRef<Ctx>,
         wal: WalRef<Ctx>,
@@ -205,12 +252,14 @@ where
             ctx,
             params,
             timeout_config,
+            signer,
             network,
             host,
             wal,
             sync,
             metrics,
             tx_event,
+            events: ConsensusEvents::default(),
             span,
         };
 
@@ -236,6 +285,8 @@ where
                     height,
                     &mut state.timers,
                     &mut state.timeouts,
+                    &mut state.value_builds,
+                    &mut state.prepared_value,
                     state.phase,
                     effect
                 ).await
@@ -250,9 +301,17 @@ where
         msg: Msg<Ctx>,
     ) -> Result<(), ActorProcessingErr> {
         match msg {
+            Msg::StartHeight(height, _) if state.phase == Phase::Draining => {
+                info!(%height, "Shutting down, not starting a new height");
+                Ok(())
+            }
+
             Msg::StartHeight(height, validator_set) => {
                 state.phase = Phase::Running;
 
+                // A value built for a previous height cannot be proposed anymore
+                state.prepared_value.clear();
+
                 let result = self
                     .process_input(
                         &myself,
@@ -278,6 +337,25 @@ where
                     error!(%height, "Error when checking and replaying WAL: {e}");
                 }
 
+                // Values synced ahead of time for lower heights are not needed anymore
+                state.synced_values = state.synced_values.split_off(&height);
+
+                if let Some((request_id, peer, value)) = state.synced_values.remove(&height) {
+                    debug!(%height, %request_id, "Processing value synced ahead of time");
+
+                    self.process_synced_value(&myself, state, request_id, peer, value)
+                        .await?;
+                }
+
+                // If we have buffered inputs for a higher height than the one we just started,
+                // our peers have moved on and we will not get enough votes to decide at this height.
+                // Ask the sync actor to catch up instead of waiting forever.
+                if let Some(peers_height) = state.consensus.buffered_height() {
+                    if peers_height > height {
+                        self.catch_up(height, peers_height);
+                    }
+                }
+
                 Ok(())
             }
 
@@ -290,6 +368,13 @@ where
                     extension,
                 };
 
+                self.events.send(|| ConsensusEvent::ProposalReceived {
+                    height,
+                    round,
+                    proposer: state.consensus.address().clone(),
+                    value_id: value.id(),
+                });
+
                 let result = self
                     .process_input(
                         &myself,
@@ -352,45 +437,25 @@ where
                             return Ok(());
                         };
 
-                        self.host.call_and_forward(
-                            |reply_to| HostMsg::ProcessSyncedValue {
-                                height: value.certificate.height,
-                                round: value.certificate.round,
-                                validator_address: state.consensus.address().clone(),
-                                value_bytes: value.value_bytes.clone(),
-                                reply_to,
-                            },
-                            &myself,
-                            |proposed| {
-                                Msg::<Ctx>::ReceivedProposedValue(proposed, ValueOrigin::Sync)
-                            },
-                            None,
-                        )?;
+                        let value_height = value.certificate.height;
 
-                        if let Err(e) = self
-                            .process_input(
-                                &myself,
-                                state,
-                                ConsensusInput::CommitCertificate(value.certificate),
-                            )
-                            .await
-                        {
-                            error!(%height, %request_id, "Error when processing received synced block: {e}");
-
-                            let Some(sync) = self.sync.as_ref() else {
-                                warn!("Received sync response but sync actor is not available");
-                                return Ok(());
-                            };
-
-                            if let ConsensusError::InvalidCertificate(certificate, e) = e {
-                                sync.cast(SyncMsg::InvalidCertificate(peer, certificate, e))
-                                    .map_err(|e| {
-                                        eyre!(
-                                            "Error when notifying sync of invalid certificate: {e}"
-                                        )
-                                    })?;
-                            }
+                        if value_height < state.height() {
+                            debug!(%value_height, %request_id, "Received synced value for lower height, dropping");
+                            return Ok(());
                         }
+
+                        // Values are requested in batches and may arrive out of order,
+                        // keep the ones for higher heights until consensus gets there.
+                        if value_height > state.height() {
+                            debug!(%value_height, %request_id, "Received synced value for higher height, keeping for later");
+                            state
+                                .synced_values
+                                .insert(value_height, (request_id, peer, value));
+                            return Ok(());
+                        }
+
+                        self.process_synced_value(&myself, state, request_id, peer, value)
+                            .await?;
                     }
 
                     NetworkEvent::Request(
@@ -445,11 +510,19 @@ where
                     }
 
                     NetworkEvent::Vote(from, vote) => {
+                        self.events.send(|| ConsensusEvent::VoteReceived {
+                            height: vote.height(),
+                            round: vote.round(),
+                            vote_type: vote.vote_type(),
+                            voter: vote.validator_address().clone(),
+                            value_id: vote.value().clone(),
+                        });
+
                         if let Err(e) = self
                             .process_input(&myself, state, ConsensusInput::Vote(vote))
                             .await
                         {
-                            error!(%from, "Error when processing vote: {e}");
+                            log_process_error(&e, "vote", Some(from));
                         }
                     }
 
@@ -463,7 +536,7 @@ where
                             .process_input(&myself, state, ConsensusInput::Proposal(proposal))
                             .await
                         {
-                            error!(%from, "Error when processing proposal: {e}");
+                            log_process_error(&e, "proposal", Some(from));
                         }
                     }
 
@@ -501,8 +574,6 @@ where
                     return Ok(());
                 };
 
-                state.timeouts.increase_timeout(timeout.kind);
-
                 if matches!(
                     timeout.kind,
                     TimeoutKind::Prevote
@@ -515,6 +586,12 @@ where
                     state.consensus.print_state();
                 }
 
+                self.events.send(|| ConsensusEvent::TimeoutElapsed {
+                    height: state.height(),
+                    round: timeout.round,
+                    kind: timeout.kind,
+                });
+
                 let result = self
                     .process_input(&myself, state, ConsensusInput::TimeoutElapsed(timeout))
                     .await;
@@ -523,6 +600,11 @@ where
                     error!("Error when processing TimeoutElapsed message: {e:?}");
                 }
 
+                if state.phase == Phase::Draining {
+                    info!(round = %timeout.round, step = ?timeout.kind, "Timeout elapsed while draining");
+                    finish_draining(state);
+                }
+
                 Ok(())
             }
 
@@ -530,17 +612,94 @@ where
                 self.tx_event
                     .send(|| Event::ReceivedProposedValue(value.clone(), origin));
 
+                self.events.send(|| ConsensusEvent::ProposalReceived {
+                    height: value.height,
+                    round: value.round,
+                    proposer: value.proposer.clone(),
+                    value_id: value.value.id(),
+                });
+
                 let result = self
                     .process_input(&myself, state, ConsensusInput::ProposedValue(value, origin))
                     .await;
 
                 if let Err(e) = result {
-                    error!("Error when processing ReceivedProposedValue message: {e}");
+                    log_process_error(&e, "ReceivedProposedValue message", None);
+                }
+
+                Ok(())
+            }
+
+            Msg::ValueValidated(value) => {
+                let result = self
+                    .process_input(&myself, state, ConsensusInput::ValueValidated(value))
+                    .await;
+
+                if let Err(e) = result {
+                    log_process_error(&e, "ValueValidated message", None);
                 }
 
                 Ok(())
             }
 
+            Msg::BuiltValue(proposed, deadline) => {
+                state.value_builds.finish(proposed.height, proposed.round);
+
+                // Keep a reusable value which missed its round for when we propose again
+                let Some(proposed) = state.prepared_value.keep_if_late(
+                    proposed,
+                    state.height(),
+                    state.consensus.round(),
+                    state.consensus.driver.step_is_propose(),
+                ) else {
+                    return Ok(());
+                };
+
+                let host = self.host.clone();
+                let myself = myself.clone();
+                let timeout = deadline.saturating_duration_since(Instant::now());
+
+                // Ask the host for its decision without blocking consensus
+                tokio::spawn(async move {
+                    let Some(proposed) = host::ready_to_propose(&host, proposed, timeout).await
+                    else {
+                        return;
+                    };
+
+                    let msg = Msg::ProposeValue(
+                        proposed.height,
+                        proposed.round,
+                        proposed.value,
+                        proposed.extension,
+                    );
+
+                    if let Err(e) = myself.cast(msg) {
+                        error!("Failed to forward the value to propose: {e}");
+                    }
+                });
+
+                Ok(())
+            }
+
+            Msg::InvalidatePreparedValue(height) => {
+                state.prepared_value.invalidate(height);
+                Ok(())
+            }
+
+            Msg::BuildProgress {
+                height,
+                round,
+                sequence,
+                bytes,
+            } => {
+                debug!(%height, %round, %sequence, %bytes, "Value build in progress");
+
+                self.tx_event
+                    .send(|| Event::BuildProgress(height, round, sequence, bytes));
+
+                Ok(())
+            }
+
             Msg::GetStatus(reply_to) => {
                 let history_min_height = self.get_history_min_height().await?;
                 let status = Status::new(state.consensus.height(), history_min_height);
@@ -551,6 +710,110 @@ where
 
                 Ok(())
             }
+
+            Msg::Subscribe(reply_to) => {
+                if let Err(e) = reply_to.send(self.events.subscribe()) {
+                    error!("Error when replying to Subscribe message: {e}");
+                }
+
+                Ok(())
+            }
+
+            Msg::DependencyRestarted(component) => {
+                // Consensus does not hold a reference to any restartable component,
+                // so there is nothing to subscribe to again.
+                warn!(%component, "Dependency has been restarted");
+
+                // The transactions of a value built before the mempool was restarted may be gone
+                if component == Component::Mempool {
+                    state.prepared_value.invalidate(state.height());
+                }
+
+                self.tx_event.send(|| Event::DependencyRestarted(component));
+
+                Ok(())
+            }
+
+            Msg::Drain(reply_to) => {
+                let ready =
+                    state.phase != Phase::Running || state.consensus.driver.step_is_commit();
+
+                info!("Draining, waiting for the current height to be decided or for the next timeout");
+
+                state.phase = Phase::Draining;
+                state.drained = Some(reply_to);
+
+                // Nothing is in flight if we never started a height or already decided the current one
+                if ready {
+                    finish_draining(state);
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    async fn process_synced_value(
+        &self,
+        myself: &ActorRef<Msg<Ctx>>,
+        state: &mut State<Ctx>,
+        request_id: OutboundRequestId,
+        peer: PeerId,
+        value: DecidedValue<Ctx>,
+    ) -> Result<(), ActorProcessingErr> {
+        let height = value.certificate.height;
+
+        self.host.call_and_forward(
+            |reply_to| HostMsg::ProcessSyncedValue {
+                height,
+                round: value.certificate.round,
+                validator_address: state.consensus.address().clone(),
+                value_bytes: value.value_bytes.clone(),
+                reply_to,
+            },
+            myself,
+            |proposed| Msg::<Ctx>::ReceivedProposedValue(proposed, ValueOrigin::Sync),
+            None,
+        )?;
+
+        if let Err(e) = self
+            .process_input(
+                myself,
+                state,
+                ConsensusInput::CommitCertificate(value.certificate),
+            )
+            .await
+        {
+            error!(%height, %request_id, "Error when processing received synced block: {e}");
+
+            let Some(sync) = self.sync.as_ref() else {
+                warn!("Received sync response but sync actor is not available");
+                return Ok(());
+            };
+
+            if let ConsensusError::InvalidCertificate(certificate, e) = e {
+                sync.cast(SyncMsg::InvalidCertificate(peer, certificate, e))
+                    .map_err(|e| eyre!("Error when notifying sync of invalid certificate: {e}"))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn catch_up(&self, height: Ctx::Height, peers_height: Ctx::Height) {
+        warn!(%height, %peers_height, "Started a height lower than the one of our peers, catching up");
+
+        self.tx_event
+            .send(|| Event::CatchingUp(height, peers_height));
+
+        let Some(sync) = &self.sync else {
+            warn!(%height, "Sync is disabled, cannot catch up with peers");
+            return;
+        };
+
+        // Our peers have decided this height already, ask one of them for the decided value
+        if let Err(e) = sync.cast(SyncMsg::CatchUp(height)) {
+            error!(%height, "Error when asking sync to catch up: {e}");
         }
     }
 
@@ -563,9 +826,6 @@ where
         // Make sure the associated timer is cancelled
         state.timers.cancel(&timeout);
 
-        // Increase the timeout for the next round
-        state.timeouts.increase_timeout(timeout.kind);
-
         // Print debug information if the timeout is for a prevote or precommit
         if matches!(timeout.kind, TimeoutKind::Prevote | TimeoutKind::Precommit) {
             warn!(step = ?timeout.kind, "Timeout elapsed");
@@ -671,25 +931,23 @@ where
         height: Ctx::Height,
         round: Round,
         timeout: Duration,
+        cancel: CancellationToken,
     ) -> Result<(), ActorProcessingErr> {
+        // The host must be ready to propose the value before the propose timeout elapses
+        let deadline = Instant::now() + timeout;
+
         // Call `GetValue` on the Host actor, and forward the reply
-        // to the current actor, wrapping it in `Msg::ProposeValue`.
+        // to the current actor, wrapping it in `Msg::BuiltValue`.
         self.host.call_and_forward(
             |reply_to| HostMsg::GetValue {
                 height,
                 round,
                 timeout,
+                cancel,
                 reply_to,
             },
             myself,
-            |proposed: LocallyProposedValue<Ctx>| {
-                Msg::<Ctx>::ProposeValue(
-                    proposed.height,
-                    proposed.round,
-                    proposed.value,
-                    proposed.extension,
-                )
-            },
+            move |proposed: LocallyProposedValue<Ctx>| Msg::<Ctx>::BuiltValue(proposed, deadline),
             None,
         )?;
 
@@ -743,34 +1001,36 @@ where
         Ok(())
     }
 
-    async fn wal_flush(&self, phase: Phase) -> Result<(), ActorProcessingErr> {
+    /// Sync the WAL to disk, returning whether it succeeded.
+    async fn wal_flush(&self, phase: Phase) -> Result<bool, ActorProcessingErr> {
         if phase == Phase::Recovering {
-            return Ok(());
+            return Ok(true);
         }
 
         let result = ractor::call!(self.wal, WalMsg::Flush);
 
         match result {
-            Ok(Ok(())) => {
-                // Success
-            }
+            Ok(Ok(())) => Ok(true),
             Ok(Err(e)) => {
                 error!("Failed to flush WAL to disk: {e}");
+                Ok(false)
             }
             Err(e) => {
                 error!("Failed to send Flush command to WAL: {e}");
+                Ok(false)
             }
         }
-
-        Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn handle_effect(
         &self,
         myself: &ActorRef<Msg<Ctx>>,
         height: Ctx::Height,
         timers: &mut Timers,
         timeouts: &mut Timeouts,
+        value_builds: &mut ValueBuilds<Ctx::Height>,
+        prepared_value: &mut PreparedValue<Ctx>,
         phase: Phase,
         effect: Effect<Ctx>,
     ) -> Result<Resume<Ctx>, ActorProcessingErr> {
@@ -791,7 +1051,7 @@ where
             }
 
             Effect::ScheduleTimeout(timeout, r) => {
-                let duration = timeouts.duration_for(timeout.kind);
+                let duration = timeouts.duration_for(&timeout);
                 timers.start_timer(timeout, duration);
 
                 Ok(r.resume_with(()))
@@ -800,6 +1060,12 @@ where
             Effect::StartRound(height, round, proposer, r) => {
                 self.wal_flush(phase).await?;
 
+                self.events.send(|| ConsensusEvent::RoundStarted {
+                    height,
+                    round,
+                    proposer: proposer.clone(),
+                });
+
                 self.host.cast(HostMsg::StartedRound {
                     height,
                     round,
@@ -814,25 +1080,61 @@ where
             Effect::SignProposal(proposal, r) => {
                 let start = Instant::now();
 
-                let signed_proposal = self.ctx.signing_provider().sign_proposal(proposal);
+                // Better to miss a proposal than to stall the actor waiting on the signer
+                match self.signer.sign_proposal(proposal).await {
+                    Ok(signed_proposal) => {
+                        self.metrics
+                            .signature_signing_time
+                            .observe(start.elapsed().as_secs_f64());
 
-                self.metrics
-                    .signature_signing_time
-                    .observe(start.elapsed().as_secs_f64());
+                        Ok(r.resume_with(Some(signed_proposal)))
+                    }
+                    Err(e) => {
+                        error!("Failed to sign proposal, not emitting it: {e}");
+                        self.metrics.signer_failures.inc();
+
+                        Ok(r.resume_with(None))
+                    }
+                }
+            }
+
+            Effect::PersistBeforeVote(height, round, vote_type, r) => {
+                // Sync the WAL to disk before signing our vote, so that the inputs which led
+                // to that vote are replayed after a crash, and we cast the very same vote again.
+                let persisted = self.wal_flush(phase).await?;
 
-                Ok(r.resume_with(signed_proposal))
+                if !persisted {
+                    error!(%height, %round, ?vote_type, "Failed to persist WAL, not voting");
+                }
+
+                Ok(r.resume_with(persisted))
             }
 
             Effect::SignVote(vote, r) => {
                 let start = Instant::now();
 
-                let signed_vote = self.ctx.signing_provider().sign_vote(vote);
+                // Better to miss a vote than to stall the actor waiting on the signer
+                match self.signer.sign_vote(vote).await {
+                    Ok(signed_vote) => {
+                        self.metrics
+                            .signature_signing_time
+                            .observe(start.elapsed().as_secs_f64());
 
-                self.metrics
-                    .signature_signing_time
-                    .observe(start.elapsed().as_secs_f64());
+                        Ok(r.resume_with(Some(signed_vote)))
+                    }
+                    Err(e @ SignerError::DoubleSignAttempt(_)) => {
+                        error!("Prevented a double sign, dropping our vote: {e}");
+                        self.metrics.double_sign_attempts.inc();
 
-                Ok(r.resume_with(signed_vote))
+                        Ok(r.resume_with(None))
+                    }
+                    Err(e) => {
+                        error!("Failed to sign vote, not emitting it: {e}");
+                        self.metrics.signer_failures.inc();
+
+                        Ok(r.resume_with(None))
+                    }
+                }
             }
 
             Effect::VerifySignature(msg, pk, r) => {
@@ -886,9 +1188,37 @@ where
             }
 
             Effect::GetValue(height, round, timeout, r) => {
-                let timeout_duration = timeouts.duration_for(timeout.kind);
+                // Propose the value built too late for an earlier round right away, if any,
+                // after asking the host to stream it again for this round
+                if let Some(prepared) = prepared_value.take(height, round) {
+                    info!(%height, %round, "Proposing value built for an earlier round");
+
+                    self.host
+                        .cast(HostMsg::RestreamValue {
+                            height,
+                            round,
+                            valid_round: Round::Nil,
+                            address: self.params.address.clone(),
+                            value_id: prepared.value.id(),
+                        })
+                        .map_err(|e| eyre!("Error when asking host to restream value: {e:?}"))?;
+
+                    myself
+                        .cast(Msg::ProposeValue(
+                            prepared.height,
+                            prepared.round,
+                            prepared.value,
+                            prepared.extension,
+                        ))
+                        .map_err(|e| eyre!("Error when proposing prepared value: {e:?}"))?;
+
+                    return Ok(r.resume_with(()));
+                }
+
+                let timeout_duration = timeouts.duration_for(&timeout);
+                let cancel = value_builds.start(height, round);
 
-                self.get_value(myself, height, round, timeout_duration)
+                self.get_value(myself, height, round, timeout_duration, cancel)
                     .map_err(|e| eyre!("Error when asking for value to be built: {e:?}"))?;
 
                 Ok(r.resume_with(()))
@@ -923,6 +1253,12 @@ where
 
                 self.tx_event.send(|| Event::Decided(certificate.clone()));
 
+                self.events.send(|| ConsensusEvent::Decided {
+                    height: certificate.height,
+                    round: certificate.round,
+                    value_id: certificate.value_id.clone(),
+                });
+
                 let height = certificate.height;
 
                 self.host
@@ -1019,9 +1355,13 @@ where
         Ok(State {
             timers: Timers::new(Box::new(myself)),
             timeouts: Timeouts::new(self.timeout_config),
+            value_builds: ValueBuilds::default(),
+            prepared_value: PreparedValue::default(),
             consensus: ConsensusState::new(self.ctx.clone(), self.params.clone()),
             connected_peers: BTreeSet::new(),
+            synced_values: BTreeMap::new(),
             phase: Phase::Unstarted,
+            drained: None,
         })
     }
 
@@ -1052,6 +1392,18 @@ where
             error!("Error when handling message: {e:?}");
         }
 
+        if state.phase == Phase::Draining && state.consensus.driver.step_is_commit() {
+            finish_draining(state);
+        }
+
+        // Stop building values for rounds we are not proposing at anymore,
+        // eg. because we skipped the round or timed out waiting for the value
+        state.value_builds.cancel_stale(
+            state.height(),
+            state.consensus.round(),
+            state.consensus.driver.step_is_propose(),
+        );
+
         Ok(())
     }
 
@@ -1063,7 +1415,52 @@ where
         info!("Stopping...");
 
         state.timers.cancel_all();
+        state.value_builds.cancel_all();
 
         Ok(())
     }
 }
+
+/// Notify the node that we are done draining, if it is still waiting for it
+fn finish_draining<Ctx: Context>(state: &mut State<Ctx>) {
+    if let Some(reply_to) = state.drained.take() {
+        info!("Done draining");
+
+        if let Err(e) = reply_to.send(()) {
+            error!("Error when replying to Drain message: {e}");
+        }
+    }
+}
+
+/// Log an error raised while processing an input received from a peer or from the host.
+///
+/// Errors caused by the input itself, eg. a vote from a validator which is not part of
+/// the validator set, are to be expected from faulty or lagging peers and are logged as warnings.
+/// Errors which point at an inconsistency in our own state are logged as errors.
+fn log_process_error<Ctx: Context>(e: &ConsensusError<Ctx>, input: &str, from: Option<PeerId>) {
+    let ConsensusError::DriverProcess(driver_error) = e else {
+        error!(?from, "Error when processing {input}: {e}");
+        return;
+    };
+
+    match driver_error {
+        DriverError::ValidatorNotFound(_)
+        | DriverError::InvalidVote(_)
+        | DriverError::ProposerMismatch { .. }
+        | DriverError::InvalidProposalHeight { .. }
+        | DriverError::InvalidVoteHeight { .. }
+        | DriverError::InvalidCertificateHeight { .. }
+        | DriverError::InvalidCertificate(_) => {
+            warn!(?from, "Invalid {input}: {driver_error}");
+        }
+
+        DriverError::NoProposer(_, _)
+        | DriverError::ProposerNotFound(_)
+        | DriverError::EmptyValidatorSet(_)
+        | DriverError::WrongStep { .. }
+        | DriverError::PastRound { .. }
+        | DriverError::SelfEquivocation(_, _) => {
+            error!(?from, "Error when processing {input}: {driver_error}");
+        }
+    }
+}
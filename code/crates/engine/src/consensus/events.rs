@@ -0,0 +1,178 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use derive_where::derive_where;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+use malachitebft_core_types::{Context, NilOrVal, Round, TimeoutKind, ValueId, VoteType};
+
+/// Default number of events buffered for each subscriber before the oldest ones are dropped
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// Lifecycle events emitted by the consensus actor, eg. for pushing them to RPC clients.
+#[derive_where(Clone, Debug, PartialEq, Eq)]
+pub enum ConsensusEvent<Ctx: Context> {
+    /// A new round has started
+    RoundStarted {
+        height: Ctx::Height,
+        round: Round,
+        proposer: Ctx::Address,
+    },
+
+    /// A full proposed value was received, either from the network or from our own host
+    ProposalReceived {
+        height: Ctx::Height,
+        round: Round,
+        proposer: Ctx::Address,
+        value_id: ValueId<Ctx>,
+    },
+
+    /// A vote was received from the network
+    VoteReceived {
+        height: Ctx::Height,
+        round: Round,
+        vote_type: VoteType,
+        voter: Ctx::Address,
+        value_id: NilOrVal<ValueId<Ctx>>,
+    },
+
+    /// A value was decided
+    Decided {
+        height: Ctx::Height,
+        round: Round,
+        value_id: ValueId<Ctx>,
+    },
+
+    /// A timeout has elapsed
+    TimeoutElapsed {
+        height: Ctx::Height,
+        round: Round,
+        kind: TimeoutKind,
+    },
+}
+
+impl<Ctx: Context> ConsensusEvent<Ctx> {
+    /// The height at which this event happened
+    pub fn height(&self) -> Ctx::Height {
+        match self {
+            Self::RoundStarted { height, .. }
+            | Self::ProposalReceived { height, .. }
+            | Self::VoteReceived { height, .. }
+            | Self::Decided { height, .. }
+            | Self::TimeoutElapsed { height, .. } => *height,
+        }
+    }
+}
+
+/// Sending side of the consensus events, owned by the consensus actor.
+///
+/// Events are sent over a bounded broadcast channel, so that slow subscribers never
+/// block consensus. When a subscriber falls behind, its oldest events are dropped
+/// and counted in [`ConsensusEvents::dropped`].
+pub struct ConsensusEvents<Ctx: Context> {
+    tx: broadcast::Sender<ConsensusEvent<Ctx>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl<Ctx: Context> ConsensusEvents<Ctx> {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+
+        Self {
+            tx,
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn subscribe(&self) -> ConsensusSubscription<Ctx> {
+        ConsensusSubscription {
+            rx: self.tx.subscribe(),
+            dropped: 0,
+            total_dropped: Arc::clone(&self.dropped),
+        }
+    }
+
+    /// Send an event to all subscribers, if there are any
+    pub fn send(&self, event: impl FnOnce() -> ConsensusEvent<Ctx>) {
+        if self.tx.receiver_count() > 0 {
+            let _ = self.tx.send(event());
+        }
+    }
+
+    /// Total number of events dropped across all subscribers
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl<Ctx: Context> Default for ConsensusEvents<Ctx> {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+/// Receiving side of the consensus events, obtained with [`ConsensusMsg::Subscribe`](super::Msg::Subscribe).
+pub struct ConsensusSubscription<Ctx: Context> {
+    rx: broadcast::Receiver<ConsensusEvent<Ctx>>,
+    dropped: u64,
+    total_dropped: Arc<AtomicU64>,
+}
+
+impl<Ctx: Context> ConsensusSubscription<Ctx> {
+    /// Receive the next event, skipping over the events dropped because we fell behind.
+    ///
+    /// Returns `None` once the consensus actor has stopped.
+    pub async fn recv(&mut self) -> Option<ConsensusEvent<Ctx>> {
+        loop {
+            match self.rx.recv().await {
+                Ok(event) => return Some(event),
+                Err(RecvError::Lagged(count)) => {
+                    warn!(%count, "Consensus event subscriber is lagging, dropped oldest events");
+
+                    self.dropped += count;
+                    self.total_dropped.fetch_add(count, Ordering::Relaxed);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Number of events dropped for this subscriber
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use malachitebft_test::{Height, TestContext};
+
+    use super::*;
+
+    fn timeout(round: u32) -> ConsensusEvent<TestContext> {
+        ConsensusEvent::TimeoutElapsed {
+            height: Height::new(1),
+            round: Round::new(round),
+            kind: TimeoutKind::Propose,
+        }
+    }
+
+    #[tokio::test]
+    async fn slow_subscriber_drops_oldest_events() {
+        let events = ConsensusEvents::new(2);
+        let mut slow = events.subscribe();
+
+        for round in 0..5 {
+            events.send(|| timeout(round));
+        }
+
+        assert_eq!(slow.recv().await, Some(timeout(3)));
+        assert_eq!(slow.recv().await, Some(timeout(4)));
+        assert_eq!(slow.dropped(), 3);
+        assert_eq!(events.dropped(), 3);
+
+        drop(events);
+        assert_eq!(slow.recv().await, None);
+    }
+}
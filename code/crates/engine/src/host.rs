@@ -2,7 +2,9 @@ use bytes::Bytes;
 use std::time::Duration;
 
 use derive_where::derive_where;
+use ractor::rpc::CallResult;
 use ractor::{ActorRef, RpcReplyPort};
+use tracing::{info, warn};
 
 use malachitebft_core_consensus::PeerId;
 use malachitebft_core_types::{CommitCertificate, Context, Round, SignedExtension, ValueId};
@@ -11,6 +13,9 @@ use malachitebft_sync::DecidedValue;
 use crate::consensus::ConsensusRef;
 use crate::util::streaming::StreamMessage;
 
+/// Token for cancelling the build of a value, see [`HostMsg::GetValue`].
+pub use tokio_util::sync::CancellationToken;
+
 /// A value to propose that has just been received.
 pub use malachitebft_core_consensus::ProposedValue;
 
@@ -24,6 +29,9 @@ pub struct LocallyProposedValue<Ctx: Context> {
     pub round: Round,
     pub value: Ctx::Value,
     pub extension: Option<SignedExtension<Ctx>>,
+    /// Whether the value can be proposed again in a later round of the same height,
+    /// if it was built too late to be proposed in the round it was built for.
+    pub reusable: bool,
 }
 
 impl<Ctx: Context> LocallyProposedValue<Ctx> {
@@ -38,10 +46,41 @@ impl<Ctx: Context> LocallyProposedValue<Ctx> {
             round,
             value,
             extension,
+            reusable: false,
+        }
+    }
+
+    /// Mark the value as reusable, so that if it is built too late for its round,
+    /// consensus proposes it in the next round of the same height where we are the proposer
+    /// instead of asking the host to build a new one.
+    ///
+    /// The host MUST then be able to restream the value for that round,
+    /// see [`HostMsg::RestreamValue`].
+    pub fn reusable(self) -> Self {
+        Self {
+            reusable: true,
+            ..self
         }
     }
 }
 
+/// The decision of the application on a value it has just built,
+/// in reply to [`HostMsg::ReadyToPropose`].
+#[derive_where(Clone, Debug, PartialEq, Eq)]
+pub enum ProposeDecision<Ctx: Context> {
+    /// Propose the value as built
+    Accept,
+
+    /// Propose the given value instead of the one which was built.
+    ///
+    /// The application MUST have streamed the proposal parts for that value
+    /// before replying, as it would have done for a value built in reply to [`HostMsg::GetValue`].
+    Replace(Ctx::Value),
+
+    /// Do not propose any value, and let the propose timeout elapse
+    Reject,
+}
+
 /// A reference to the host actor.
 pub type HostRef<Ctx> = ActorRef<HostMsg<Ctx>>;
 
@@ -59,13 +98,29 @@ pub enum HostMsg<Ctx: Context> {
     },
 
     /// Request to build a local block/value from Driver
+    ///
+    /// The token is cancelled once consensus has left the propose step of that round,
+    /// eg. because it skipped the round, in which case the value will not be proposed
+    /// and the host should stop building it.
     GetValue {
         height: Ctx::Height,
         round: Round,
         timeout: Duration,
+        cancel: CancellationToken,
         reply_to: RpcReplyPort<LocallyProposedValue<Ctx>>,
     },
 
+    /// The value built in reply to `GetValue` is about to be proposed,
+    /// giving the application a last chance to replace or reject it.
+    ///
+    /// If the application does not reply in time, the value is proposed as built.
+    ReadyToPropose {
+        height: Ctx::Height,
+        round: Round,
+        value: Ctx::Value,
+        reply_to: RpcReplyPort<ProposeDecision<Ctx>>,
+    },
+
     /// Request to restream an existing block/value from Driver
     RestreamValue {
         height: Ctx::Height,
@@ -112,3 +167,152 @@ pub enum HostMsg<Ctx: Context> {
         reply_to: RpcReplyPort<ProposedValue<Ctx>>,
     },
 }
+
+/// Ask the host whether to propose the given value, which it has just built,
+/// and return the value to propose, if any.
+///
+/// If the host does not reply within the given timeout, or drops the reply port,
+/// the value is proposed as built, so that the host cannot prevent us from proposing.
+pub async fn ready_to_propose<Ctx: Context>(
+    host: &HostRef<Ctx>,
+    proposed: LocallyProposedValue<Ctx>,
+    timeout: Duration,
+) -> Option<LocallyProposedValue<Ctx>> {
+    let (height, round) = (proposed.height, proposed.round);
+
+    let result = host
+        .call(
+            |reply_to| HostMsg::ReadyToPropose {
+                height,
+                round,
+                value: proposed.value.clone(),
+                reply_to,
+            },
+            Some(timeout),
+        )
+        .await;
+
+    match result {
+        Ok(CallResult::Success(ProposeDecision::Accept)) => Some(proposed),
+
+        Ok(CallResult::Success(ProposeDecision::Replace(value))) => {
+            info!(%height, %round, "Host replaced the value to propose");
+            Some(LocallyProposedValue { value, ..proposed })
+        }
+
+        Ok(CallResult::Success(ProposeDecision::Reject)) => {
+            info!(%height, %round, "Host rejected the value to propose, not proposing any value");
+            None
+        }
+
+        Ok(CallResult::Timeout) => {
+            warn!(%height, %round, "Host did not reply in time, proposing the value as built");
+            Some(proposed)
+        }
+
+        Ok(CallResult::SenderError) => {
+            warn!(%height, %round, "Host dropped the reply, proposing the value as built");
+            Some(proposed)
+        }
+
+        Err(e) => {
+            warn!(%height, %round, "Failed to reach the host, proposing the value as built: {e}");
+            Some(proposed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use async_trait::async_trait;
+    use ractor::{Actor, ActorProcessingErr};
+
+    use malachitebft_test::{Height, TestContext, Value};
+
+    /// A host which replies to `ReadyToPropose` with the given decision, or never replies if `None`
+    struct MockHost(Option<ProposeDecision<TestContext>>);
+
+    #[async_trait]
+    impl Actor for MockHost {
+        type Msg = HostMsg<TestContext>;
+        type State = Vec<RpcReplyPort<ProposeDecision<TestContext>>>;
+        type Arguments = ();
+
+        async fn pre_start(
+            &self,
+            _myself: ActorRef<Self::Msg>,
+            _args: Self::Arguments,
+        ) -> Result<Self::State, ActorProcessingErr> {
+            Ok(Vec::new())
+        }
+
+        async fn handle(
+            &self,
+            _myself: ActorRef<Self::Msg>,
+            msg: Self::Msg,
+            pending: &mut Self::State,
+        ) -> Result<(), ActorProcessingErr> {
+            if let HostMsg::ReadyToPropose { reply_to, .. } = msg {
+                match &self.0 {
+                    Some(decision) => reply_to.send(decision.clone())?,
+                    // Keep the reply port alive without ever replying
+                    None => pending.push(reply_to),
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    fn proposed() -> LocallyProposedValue<TestContext> {
+        LocallyProposedValue::new(Height::new(1), Round::new(0), Value::new(1), None)
+    }
+
+    async fn run(
+        decision: Option<ProposeDecision<TestContext>>,
+    ) -> Option<LocallyProposedValue<TestContext>> {
+        let (host, handle) = Actor::spawn(None, MockHost(decision), ()).await.unwrap();
+
+        let result = ready_to_propose(&host, proposed(), Duration::from_millis(100)).await;
+
+        host.stop(None);
+        handle.await.unwrap();
+
+        result
+    }
+
+    #[tokio::test]
+    async fn ready_to_propose_accept() {
+        let result = run(Some(ProposeDecision::Accept)).await;
+        assert_eq!(result, Some(proposed()));
+    }
+
+    #[tokio::test]
+    async fn ready_to_propose_replace() {
+        let result = run(Some(ProposeDecision::Replace(Value::new(2)))).await;
+
+        assert_eq!(
+            result,
+            Some(LocallyProposedValue::new(
+                Height::new(1),
+                Round::new(0),
+                Value::new(2),
+                None
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn ready_to_propose_reject() {
+        let result = run(Some(ProposeDecision::Reject)).await;
+        assert_eq!(result, None);
+    }
+
+    #[tokio::test]
+    async fn ready_to_propose_timeout() {
+        let result = run(None).await;
+        assert_eq!(result, Some(proposed()));
+    }
+}
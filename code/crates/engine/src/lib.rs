@@ -1,7 +1,10 @@
+pub mod builder;
 pub mod consensus;
 pub mod host;
 pub mod network;
 pub mod node;
+pub mod signer;
+pub mod supervisor;
 pub mod sync;
 pub mod util;
 pub mod wal;
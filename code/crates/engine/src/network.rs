@@ -15,7 +15,6 @@ use malachitebft_sync::{
     self as sync, InboundRequestId, OutboundRequestId, RawMessage, Request, Response,
 };
 
-use malachitebft_codec as codec;
 use malachitebft_core_consensus::SignedConsensusMsg;
 use malachitebft_core_types::{Context, SignedProposal, SignedVote};
 use malachitebft_metrics::SharedRegistry;
@@ -29,6 +28,26 @@ use crate::util::streaming::StreamMessage;
 pub type NetworkRef<Ctx> = ActorRef<Msg<Ctx>>;
 pub type NetworkMsg<Ctx> = Msg<Ctx>;
 
+/// Codec for all the messages sent over the network, ie. consensus and sync messages.
+///
+/// This trait is automatically implemented for any type that implements
+/// both [`ConsensusCodec`] and [`SyncCodec`].
+pub trait NetworkCodec<Ctx>
+where
+    Ctx: Context,
+    Self: ConsensusCodec<Ctx>,
+    Self: SyncCodec<Ctx>,
+{
+}
+
+impl<Ctx, Codec> NetworkCodec<Ctx> for Codec
+where
+    Ctx: Context,
+    Codec: ConsensusCodec<Ctx>,
+    Codec: SyncCodec<Ctx>,
+{
+}
+
 pub struct Network<Ctx, Codec> {
     codec: Codec,
     span: tracing::Span,
@@ -48,8 +67,7 @@ impl<Ctx, Codec> Network<Ctx, Codec> {
 impl<Ctx, Codec> Network<Ctx, Codec>
 where
     Ctx: Context,
-    Codec: ConsensusCodec<Ctx>,
-    Codec: SyncCodec<Ctx>,
+    Codec: NetworkCodec<Ctx>,
 {
     pub async fn spawn(
         keypair: Keypair,
@@ -101,9 +119,29 @@ pub enum State<Ctx: Context> {
         ctrl_handle: CtrlHandle,
         recv_task: JoinHandle<()>,
         inbound_requests: HashMap<InboundRequestId, request_response::InboundRequestId>,
+        max_sizes: MaxSizes,
     },
 }
 
+/// Maximum size of the raw messages received from peers, checked before decoding them
+#[derive(Copy, Clone, Debug)]
+pub struct MaxSizes {
+    pub pubsub: usize,
+    pub rpc: usize,
+}
+
+impl MaxSizes {
+    fn check(limit: usize, size: usize) -> Result<(), String> {
+        if size > limit {
+            return Err(format!(
+                "message of {size} bytes exceeds the maximum size of {limit} bytes"
+            ));
+        }
+
+        Ok(())
+    }
+}
+
 #[derive_where(Clone, Debug, PartialEq, Eq)]
 pub struct Status<Ctx: Context> {
     pub height: Ctx::Height,
@@ -150,13 +188,7 @@ pub enum Msg<Ctx: Context> {
 impl<Ctx, Codec> Actor for Network<Ctx, Codec>
 where
     Ctx: Context,
-    Codec: Send + Sync + 'static,
-    Codec: codec::Codec<Ctx::ProposalPart>,
-    Codec: codec::Codec<SignedConsensusMsg<Ctx>>,
-    Codec: codec::Codec<StreamMessage<Ctx::ProposalPart>>,
-    Codec: codec::Codec<sync::Status<Ctx>>,
-    Codec: codec::Codec<sync::Request<Ctx>>,
-    Codec: codec::Codec<sync::Response<Ctx>>,
+    Codec: NetworkCodec<Ctx> + Send + Sync + 'static,
 {
     type Msg = Msg<Ctx>;
     type State = State<Ctx>;
@@ -167,6 +199,11 @@ where
         myself: ActorRef<Msg<Ctx>>,
         args: Args,
     ) -> Result<Self::State, ActorProcessingErr> {
+        let max_sizes = MaxSizes {
+            pubsub: args.config.pubsub_max_size,
+            rpc: args.config.rpc_max_size,
+        };
+
         let handle = malachitebft_network::spawn(args.keypair, args.config, args.metrics).await?;
 
         let (mut recv_handle, ctrl_handle) = handle.split();
@@ -186,6 +223,7 @@ where
             ctrl_handle,
             recv_task,
             inbound_requests: HashMap::new(),
+            max_sizes,
         })
     }
 
@@ -209,6 +247,7 @@ where
             output_port,
             ctrl_handle,
             inbound_requests,
+            max_sizes,
             ..
         } = state
         else {
@@ -296,6 +335,11 @@ where
             }
 
             Msg::NewEvent(Event::Message(Channel::Consensus, from, data)) => {
+                if let Err(e) = MaxSizes::check(max_sizes.pubsub, data.len()) {
+                    error!(%from, "Dropping gossip message: {e}");
+                    return Ok(());
+                }
+
                 let msg = match self.codec.decode(data) {
                     Ok(msg) => msg,
                     Err(e) => {
@@ -315,6 +359,11 @@ where
             }
 
             Msg::NewEvent(Event::Message(Channel::ProposalParts, from, data)) => {
+                if let Err(e) = MaxSizes::check(max_sizes.pubsub, data.len()) {
+                    error!(%from, "Dropping stream message: {e}");
+                    return Ok(());
+                }
+
                 let msg: StreamMessage<Ctx::ProposalPart> = match self.codec.decode(data) {
                     Ok(stream_msg) => stream_msg,
                     Err(e) => {
@@ -334,6 +383,11 @@ where
             }
 
             Msg::NewEvent(Event::Message(Channel::Sync, from, data)) => {
+                if let Err(e) = MaxSizes::check(max_sizes.pubsub, data.len()) {
+                    error!(%from, "Dropping status message: {e}");
+                    return Ok(());
+                }
+
                 let status: sync::Status<Ctx> = match self.codec.decode(data) {
                     Ok(status) => status,
                     Err(e) => {
@@ -361,6 +415,11 @@ where
                     peer,
                     body,
                 } => {
+                    if let Err(e) = MaxSizes::check(max_sizes.rpc, body.len()) {
+                        error!(%peer, "Dropping sync request: {e}");
+                        return Ok(());
+                    }
+
                     let request: sync::Request<Ctx> = match self.codec.decode(body) {
                         Ok(request) => request,
                         Err(e) => {
@@ -383,6 +442,11 @@ where
                     peer,
                     body,
                 } => {
+                    if let Err(e) = MaxSizes::check(max_sizes.rpc, body.len()) {
+                        error!(%peer, "Dropping sync response: {e}");
+                        return Ok(());
+                    }
+
                     let response: sync::Response<Ctx> = match self.codec.decode(body) {
                         Ok(response) => response,
                         Err(e) => {
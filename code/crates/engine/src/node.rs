@@ -1,17 +1,32 @@
+use std::time::Instant;
+
 use async_trait::async_trait;
+use ractor::rpc::CallResult;
 use ractor::{Actor, ActorCell, ActorProcessingErr, ActorRef, SupervisionEvent};
 use tokio::task::JoinHandle;
 use tracing::{error, info, warn};
 
 use malachitebft_core_types::Context;
 
-use crate::consensus::ConsensusRef;
+use crate::consensus::{ConsensusMsg, ConsensusRef};
 use crate::host::HostRef;
 use crate::network::NetworkRef;
+use crate::supervisor::{Component, RestartFn, RestartPolicy, Restarts};
 use crate::sync::SyncRef;
-use crate::wal::WalRef;
+use crate::wal::{Msg as WalMsg, WalRef};
+
+pub type NodeRef = ActorRef<Msg>;
 
-pub type NodeRef = ActorRef<()>;
+pub enum Msg {
+    /// Shut down the node gracefully, giving up on waiting for the in-flight round
+    /// and for the actors to stop once the given deadline has passed.
+    ///
+    /// Consensus is first drained, ie. it stops starting new heights and waits until the current
+    /// height is decided or the next timeout elapses. The WAL is then flushed to disk,
+    /// and the actors are stopped in dependency order: consensus, sync, the host, the mempool,
+    /// the gossip layer and finally the WAL.
+    Shutdown(Instant),
+}
 
 #[allow(dead_code)]
 pub struct Node<Ctx: Context> {
@@ -20,12 +35,29 @@ pub struct Node<Ctx: Context> {
     consensus: ConsensusRef<Ctx>,
     wal: WalRef<Ctx>,
     sync: Option<SyncRef<Ctx>>,
-    mempool: ActorCell,
+    mempool: Option<ActorCell>,
     host: HostRef<Ctx>,
     start_height: Ctx::Height,
+    restart_policy: RestartPolicy,
+    restartable: Vec<(Component, RestartFn)>,
     span: tracing::Span,
 }
 
+/// A component restarted by the node actor when it fails
+struct Supervised {
+    component: Component,
+    cell: ActorCell,
+    restarts: Restarts,
+}
+
+/// The components supervised by the node actor
+pub struct State {
+    supervised: Vec<Supervised>,
+
+    /// Whether the node is shutting down, in which case the components are not restarted
+    shutting_down: bool,
+}
+
 impl<Ctx> Node<Ctx>
 where
     Ctx: Context,
@@ -37,7 +69,7 @@ where
         consensus: ConsensusRef<Ctx>,
         wal: WalRef<Ctx>,
         sync: Option<SyncRef<Ctx>>,
-        mempool: ActorCell,
+        mempool: Option<ActorCell>,
         host: HostRef<Ctx>,
         start_height: Ctx::Height,
         span: tracing::Span,
@@ -51,60 +83,229 @@ where
             mempool,
             host,
             start_height,
+            restart_policy: RestartPolicy::default(),
+            restartable: Vec::new(),
             span,
         }
     }
 
-    pub async fn spawn(self) -> Result<(ActorRef<()>, JoinHandle<()>), ractor::SpawnErr> {
+    /// Restart the given component with the given function when it fails,
+    /// instead of leaving the node running without it.
+    pub fn with_restart(mut self, component: Component, restart: RestartFn) -> Self {
+        self.restartable.push((component, restart));
+        self
+    }
+
+    /// Use the given policy for restarting the components which fail
+    pub fn with_restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.restart_policy = policy;
+        self
+    }
+
+    /// The actor currently running the given component, if any
+    fn actor_of(&self, component: Component) -> Option<ActorCell> {
+        match component {
+            Component::Mempool => self.mempool.clone(),
+        }
+    }
+
+    /// Restart the given component after a backoff delay, until it is successfully respawned,
+    /// and notify consensus of the restart.
+    ///
+    /// Returns `false` if the component failed too many times, in which case the node must be shut down.
+    async fn restart(
+        &self,
+        myself: &NodeRef,
+        supervised: &mut Supervised,
+    ) -> Result<bool, ActorProcessingErr> {
+        let component = supervised.component;
+
+        let Some((_, restart)) = self.restartable.iter().find(|(c, _)| *c == component) else {
+            return Ok(false);
+        };
+
+        loop {
+            let Some(backoff) = supervised
+                .restarts
+                .next(&self.restart_policy, Instant::now())
+            else {
+                return Ok(false);
+            };
+
+            warn!(%component, ?backoff, "Restarting component");
+            tokio::time::sleep(backoff).await;
+
+            match restart().await {
+                Ok(cell) => {
+                    cell.link(myself.get_cell());
+                    supervised.cell = cell;
+                    break;
+                }
+                Err(e) => {
+                    error!(%component, "Failed to restart component: {e}");
+                }
+            }
+        }
+
+        info!(%component, "Component has been restarted");
+
+        self.consensus
+            .cast(ConsensusMsg::DependencyRestarted(component))?;
+
+        Ok(true)
+    }
+
+    /// Drain consensus, flush the WAL and stop the actors in dependency order,
+    /// see [`Msg::Shutdown`].
+    async fn shutdown(&self, state: &State, deadline: Instant) {
+        info!("Shutting down gracefully");
+
+        let timeout = deadline.saturating_duration_since(Instant::now());
+
+        match self
+            .consensus
+            .call(ConsensusMsg::Drain, Some(timeout))
+            .await
+        {
+            Ok(CallResult::Success(())) => info!("Consensus has been drained"),
+            Ok(CallResult::Timeout) => warn!("Consensus was not drained in time"),
+            Ok(CallResult::SenderError) | Err(_) => warn!("Failed to drain consensus"),
+        }
+
+        // Stop consensus first, so that nothing is appended to the WAL after it is flushed
+        stop_actor("consensus", self.consensus.get_cell(), deadline).await;
+
+        let timeout = deadline.saturating_duration_since(Instant::now());
+
+        match self.wal.call(WalMsg::Flush, Some(timeout)).await {
+            Ok(CallResult::Success(Ok(()))) => info!("Flushed WAL to disk"),
+            Ok(CallResult::Success(Err(e))) => error!("Failed to flush WAL to disk: {e}"),
+            Ok(CallResult::Timeout) => warn!("WAL was not flushed in time"),
+            Ok(CallResult::SenderError) | Err(_) => warn!("Failed to flush WAL"),
+        }
+
+        if let Some(sync) = &self.sync {
+            stop_actor("sync", sync.get_cell(), deadline).await;
+        }
+
+        stop_actor("host", self.host.get_cell(), deadline).await;
+
+        // The mempool may have been restarted since the node was spawned
+        let mempool = state
+            .supervised
+            .iter()
+            .find(|supervised| supervised.component == Component::Mempool)
+            .map(|supervised| supervised.cell.clone())
+            .or_else(|| self.mempool.clone());
+
+        if let Some(mempool) = mempool {
+            stop_actor("mempool", mempool, deadline).await;
+        }
+
+        // Stopping the gossip layer closes the connections to our peers
+        stop_actor("network", self.network.get_cell(), deadline).await;
+        stop_actor("WAL", self.wal.get_cell(), deadline).await;
+
+        info!("Node has shut down");
+    }
+
+    pub async fn spawn(self) -> Result<(NodeRef, JoinHandle<()>), ractor::SpawnErr> {
         Actor::spawn(None, self, ()).await
     }
 }
 
+/// Stop the given actor and wait until it has stopped, killing it if it is still running at the deadline
+async fn stop_actor(name: &str, actor: ActorCell, deadline: Instant) {
+    let timeout = deadline.saturating_duration_since(Instant::now());
+    let reason = Some("Node is shutting down".to_string());
+
+    if let Err(e) = actor.stop_and_wait(reason, Some(timeout)).await {
+        warn!("The {name} actor did not stop in time, killing it: {e}");
+        actor.kill();
+    }
+}
+
 #[async_trait]
 impl<Ctx> Actor for Node<Ctx>
 where
     Ctx: Context,
 {
-    type Msg = ();
-    type State = ();
+    type Msg = Msg;
+    type State = State;
     type Arguments = ();
 
     async fn pre_start(
         &self,
         myself: ActorRef<Self::Msg>,
         _args: (),
-    ) -> Result<(), ActorProcessingErr> {
+    ) -> Result<State, ActorProcessingErr> {
         // Set ourselves as the supervisor of the other actors
         self.network.link(myself.get_cell());
         self.consensus.link(myself.get_cell());
-        self.mempool.link(myself.get_cell());
         self.host.link(myself.get_cell());
         self.wal.link(myself.get_cell());
 
+        if let Some(actor) = &self.mempool {
+            actor.link(myself.get_cell());
+        }
+
         if let Some(actor) = &self.sync {
             actor.link(myself.get_cell());
         }
 
-        Ok(())
+        let supervised = self
+            .restartable
+            .iter()
+            .filter_map(|(component, _)| {
+                Some(Supervised {
+                    component: *component,
+                    cell: self.actor_of(*component)?,
+                    restarts: Restarts::default(),
+                })
+            })
+            .collect();
+
+        Ok(State {
+            supervised,
+            shutting_down: false,
+        })
     }
 
     #[tracing::instrument(name = "node", parent = &self.span, skip_all)]
     async fn handle(
         &self,
-        _myself: ActorRef<Self::Msg>,
-        _msg: Self::Msg,
-        _state: &mut (),
+        myself: ActorRef<Self::Msg>,
+        msg: Self::Msg,
+        state: &mut State,
     ) -> Result<(), ActorProcessingErr> {
+        match msg {
+            Msg::Shutdown(deadline) => {
+                state.shutting_down = true;
+
+                self.shutdown(state, deadline).await;
+                myself.stop(Some("Node has shut down".to_string()));
+            }
+        }
+
         Ok(())
     }
 
     #[tracing::instrument(name = "node", parent = &self.span, skip_all)]
     async fn handle_supervisor_evt(
         &self,
-        _myself: ActorRef<Self::Msg>,
+        myself: ActorRef<Self::Msg>,
         evt: SupervisionEvent,
-        _state: &mut (),
+        state: &mut State,
     ) -> Result<(), ActorProcessingErr> {
+        let failed = match &evt {
+            SupervisionEvent::ActorTerminated(cell, _, _)
+            | SupervisionEvent::ActorFailed(cell, _) => state
+                .supervised
+                .iter_mut()
+                .find(|supervised| supervised.cell.get_id() == cell.get_id()),
+            _ => None,
+        };
+
         match evt {
             SupervisionEvent::ActorStarted(cell) => {
                 info!(actor = %cell.get_id(), "Actor has started");
@@ -122,6 +323,18 @@ where
             SupervisionEvent::ProcessGroupChanged(_) => (),
         }
 
+        if state.shutting_down {
+            return Ok(());
+        }
+
+        if let Some(supervised) = failed {
+            if !self.restart(&myself, supervised).await? {
+                let component = supervised.component;
+                error!(%component, "Component keeps failing, shutting down the node");
+                myself.stop(Some(format!("The {component} keeps failing")));
+            }
+        }
+
         Ok(())
     }
 }
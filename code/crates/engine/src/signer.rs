@@ -0,0 +1,278 @@
+//! Signing of the votes and proposals emitted by this node.
+//!
+//! The consensus actor does not sign its messages with an in-memory private key directly,
+//! but asks a [`SignerProvider`] to do so. This allows the consensus key to be held in an HSM
+//! or by a remote signer process, see [`LocalSigner`] and [`RemoteSigner`].
+//!
+//! A [`SigningGuard`] can be put in front of any signer to prevent double signing.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use derive_where::derive_where;
+
+use malachitebft_core_types::{Context, SignedProposal, SignedProposalPart, SignedVote};
+
+mod guard;
+mod local;
+mod remote;
+
+pub use guard::{FileGuardStore, GuardStore, InMemoryGuardStore, SigningGuard};
+pub use local::LocalSigner;
+pub use remote::{read_frame, write_frame, MAX_FRAME_SIZE};
+pub use remote::{MessageKind, RemoteSigner, SignRequest, SignResponse, SignerCodec};
+
+/// How long the consensus actor waits for a signature by default.
+pub const DEFAULT_SIGNER_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Errors that can occur when asking a [`SignerProvider`] for a signature.
+#[derive(Debug, thiserror::Error)]
+pub enum SignerError {
+    /// The signer did not produce a signature in time
+    #[error("Signer did not respond within {0:?}")]
+    Timeout(Duration),
+
+    /// Failed to communicate with the signer
+    #[error("Failed to communicate with the signer: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Failed to encode the message to sign
+    #[error("Failed to encode the message to sign: {0}")]
+    Encode(String),
+
+    /// Failed to decode the response of the signer
+    #[error("Failed to decode the signer response: {0}")]
+    Decode(String),
+
+    /// The signer refused to sign the message, eg. because it would be a double sign
+    #[error("Signer refused to sign the message: {0}")]
+    Rejected(String),
+
+    /// The message conflicts with a message signed before, see [`SigningGuard`]
+    #[error("Double sign attempt: {0}")]
+    DoubleSignAttempt(String),
+}
+
+/// A provider of signatures for the votes, proposals and proposal parts emitted by this node.
+///
+/// Unlike [`SigningProvider`](malachitebft_core_types::SigningProvider),
+/// signing is asynchronous and can fail, eg. when the key is held by a remote signer.
+#[async_trait]
+pub trait SignerProvider<Ctx>
+where
+    Self: Send + Sync + 'static,
+    Ctx: Context,
+{
+    /// Sign the given vote with our private key.
+    async fn sign_vote(&self, vote: Ctx::Vote) -> Result<SignedVote<Ctx>, SignerError>;
+
+    /// Sign the given proposal with our private key.
+    async fn sign_proposal(
+        &self,
+        proposal: Ctx::Proposal,
+    ) -> Result<SignedProposal<Ctx>, SignerError>;
+
+    /// Sign the given proposal part with our private key.
+    async fn sign_block_part(
+        &self,
+        part: Ctx::ProposalPart,
+    ) -> Result<SignedProposalPart<Ctx>, SignerError>;
+}
+
+/// A [`SignerProvider`] along with how long to wait for each of its signatures.
+///
+/// A signer which does not respond in time fails with [`SignerError::Timeout`],
+/// so that a stalled signer makes us miss a vote rather than stall the consensus actor.
+#[derive_where(Clone)]
+pub struct Signer<Ctx>
+where
+    Ctx: Context,
+{
+    provider: Arc<dyn SignerProvider<Ctx>>,
+    timeout: Duration,
+}
+
+impl<Ctx> Signer<Ctx>
+where
+    Ctx: Context,
+{
+    pub fn new(provider: impl SignerProvider<Ctx>, timeout: Duration) -> Self {
+        Self {
+            provider: Arc::new(provider),
+            timeout,
+        }
+    }
+
+    /// Sign with the signing provider of the given context, ie. with an in-memory private key.
+    pub fn local(ctx: Ctx) -> Self {
+        Self::new(LocalSigner::new(ctx), DEFAULT_SIGNER_TIMEOUT)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub async fn sign_vote(&self, vote: Ctx::Vote) -> Result<SignedVote<Ctx>, SignerError> {
+        self.with_timeout(self.provider.sign_vote(vote)).await
+    }
+
+    pub async fn sign_proposal(
+        &self,
+        proposal: Ctx::Proposal,
+    ) -> Result<SignedProposal<Ctx>, SignerError> {
+        self.with_timeout(self.provider.sign_proposal(proposal))
+            .await
+    }
+
+    pub async fn sign_block_part(
+        &self,
+        part: Ctx::ProposalPart,
+    ) -> Result<SignedProposalPart<Ctx>, SignerError> {
+        self.with_timeout(self.provider.sign_block_part(part)).await
+    }
+
+    async fn with_timeout<T>(
+        &self,
+        signing: impl std::future::Future<Output = Result<T, SignerError>>,
+    ) -> Result<T, SignerError> {
+        tokio::time::timeout(self.timeout, signing)
+            .await
+            .map_err(|_| SignerError::Timeout(self.timeout))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use malachitebft_core_types::{NilOrVal, Round, SigningProvider};
+    use malachitebft_test::utils::validators::make_validators;
+    use malachitebft_test::{
+        Address, Ed25519Provider, Height, Proposal, ProposalInit, ProposalPart, TestContext, Value,
+        Vote,
+    };
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    /// A signer which takes the given amount of time to sign
+    struct DelayedSigner {
+        delay: Duration,
+        local: LocalSigner<TestContext>,
+    }
+
+    #[async_trait]
+    impl SignerProvider<TestContext> for DelayedSigner {
+        async fn sign_vote(&self, vote: Vote) -> Result<SignedVote<TestContext>, SignerError> {
+            tokio::time::sleep(self.delay).await;
+            self.local.sign_vote(vote).await
+        }
+
+        async fn sign_proposal(
+            &self,
+            proposal: Proposal,
+        ) -> Result<SignedProposal<TestContext>, SignerError> {
+            tokio::time::sleep(self.delay).await;
+            self.local.sign_proposal(proposal).await
+        }
+
+        async fn sign_block_part(
+            &self,
+            part: ProposalPart,
+        ) -> Result<SignedProposalPart<TestContext>, SignerError> {
+            tokio::time::sleep(self.delay).await;
+            self.local.sign_block_part(part).await
+        }
+    }
+
+    /// A signer which refuses to sign anything
+    struct FailingSigner;
+
+    #[async_trait]
+    impl SignerProvider<TestContext> for FailingSigner {
+        async fn sign_vote(&self, _vote: Vote) -> Result<SignedVote<TestContext>, SignerError> {
+            Err(SignerError::Rejected("vote".to_string()))
+        }
+
+        async fn sign_proposal(
+            &self,
+            _proposal: Proposal,
+        ) -> Result<SignedProposal<TestContext>, SignerError> {
+            Err(SignerError::Rejected("proposal".to_string()))
+        }
+
+        async fn sign_block_part(
+            &self,
+            _part: ProposalPart,
+        ) -> Result<SignedProposalPart<TestContext>, SignerError> {
+            Err(SignerError::Rejected("block part".to_string()))
+        }
+    }
+
+    fn setup() -> (TestContext, Ed25519Provider, Address) {
+        let [(validator, private_key)] = make_validators([1]);
+        let ctx = TestContext::new(private_key.clone());
+        (ctx, Ed25519Provider::new(private_key), validator.address)
+    }
+
+    fn prevote(address: Address) -> Vote {
+        Vote::new_prevote(Height::new(1), Round::new(0), NilOrVal::Nil, address)
+    }
+
+    fn delayed(ctx: TestContext, delay: Duration) -> Signer<TestContext> {
+        let local = LocalSigner::new(ctx);
+        Signer::new(DelayedSigner { delay, local }, TIMEOUT)
+    }
+
+    #[tokio::test]
+    async fn local_signer_signs_like_the_signing_provider() {
+        let (ctx, provider, address) = setup();
+        let signer = Signer::local(ctx);
+
+        let vote = prevote(address);
+        let signed_vote = signer.sign_vote(vote.clone()).await.unwrap();
+        assert_eq!(signed_vote, provider.sign_vote(vote));
+
+        let proposal = Proposal::new(
+            Height::new(1),
+            Round::new(0),
+            Value::new(42),
+            Round::Nil,
+            address,
+        );
+        let signed_proposal = signer.sign_proposal(proposal.clone()).await.unwrap();
+        assert_eq!(signed_proposal, provider.sign_proposal(proposal));
+
+        let part = ProposalPart::Init(ProposalInit::new(Height::new(1), Round::new(0), address));
+        let signed_part = signer.sign_block_part(part.clone()).await.unwrap();
+        assert_eq!(signed_part, provider.sign_proposal_part(part));
+    }
+
+    #[tokio::test]
+    async fn slow_signer_within_timeout() {
+        let (ctx, provider, address) = setup();
+        let signer = delayed(ctx, TIMEOUT / 10);
+
+        let vote = prevote(address);
+        let signed_vote = signer.sign_vote(vote.clone()).await.unwrap();
+        assert_eq!(signed_vote, provider.sign_vote(vote));
+    }
+
+    #[tokio::test]
+    async fn slow_signer_times_out() {
+        let (ctx, _, address) = setup();
+        let signer = delayed(ctx, TIMEOUT * 10);
+
+        let result = signer.sign_vote(prevote(address)).await;
+        assert!(matches!(result, Err(SignerError::Timeout(TIMEOUT))));
+    }
+
+    #[tokio::test]
+    async fn failing_signer() {
+        let (_, _, address) = setup();
+        let signer = Signer::new(FailingSigner, TIMEOUT);
+
+        let result = signer.sign_vote(prevote(address)).await;
+        assert!(matches!(result, Err(SignerError::Rejected(_))));
+    }
+}
@@ -0,0 +1,476 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::sync::Mutex as StdMutex;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use malachitebft_codec as codec;
+use malachitebft_core_types::{Context, SignedProposal, SignedProposalPart, SignedVote, Vote as _};
+
+use super::{SignerError, SignerProvider};
+
+/// Storage for the votes signed by a [`SigningGuard`] at the latest height it signed a vote for.
+pub trait GuardStore<Ctx>
+where
+    Self: Send + Sync + 'static,
+    Ctx: Context,
+{
+    /// Load the votes persisted with [`GuardStore::save`], if any.
+    fn load(&self) -> io::Result<Vec<Ctx::Vote>>;
+
+    /// Persist the given votes, replacing the ones previously persisted.
+    ///
+    /// The votes must be durably persisted once this method returns.
+    fn save(&self, votes: &[Ctx::Vote]) -> io::Result<()>;
+}
+
+/// Keeps the signed votes in memory, ie. they are lost on restart.
+pub struct InMemoryGuardStore<Ctx: Context> {
+    votes: StdMutex<Vec<Ctx::Vote>>,
+}
+
+impl<Ctx: Context> InMemoryGuardStore<Ctx> {
+    pub fn new() -> Self {
+        Self {
+            votes: StdMutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<Ctx: Context> Default for InMemoryGuardStore<Ctx> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Ctx: Context> GuardStore<Ctx> for InMemoryGuardStore<Ctx> {
+    fn load(&self) -> io::Result<Vec<Ctx::Vote>> {
+        Ok(self.votes.lock().unwrap().clone())
+    }
+
+    fn save(&self, votes: &[Ctx::Vote]) -> io::Result<()> {
+        *self.votes.lock().unwrap() = votes.to_vec();
+        Ok(())
+    }
+}
+
+/// Persists the signed votes to a file, encoded with the given codec.
+///
+/// The file holds the encoded votes, each prefixed with its length as a big-endian `u32`.
+/// It is replaced atomically, by writing to a temporary file which is then renamed.
+pub struct FileGuardStore<Codec> {
+    path: PathBuf,
+    codec: Codec,
+}
+
+impl<Codec> FileGuardStore<Codec> {
+    pub fn new(path: impl Into<PathBuf>, codec: Codec) -> Self {
+        Self {
+            path: path.into(),
+            codec,
+        }
+    }
+}
+
+impl<Ctx, Codec> GuardStore<Ctx> for FileGuardStore<Codec>
+where
+    Ctx: Context,
+    Codec: codec::Codec<Ctx::Vote>,
+{
+    fn load(&self) -> io::Result<Vec<Ctx::Vote>> {
+        let mut bytes = Vec::new();
+
+        match fs::File::open(&self.path) {
+            Ok(mut file) => file.read_to_end(&mut bytes)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut votes = Vec::new();
+        let mut rest = bytes.as_slice();
+
+        while !rest.is_empty() {
+            let (len, tail) = rest.split_first_chunk::<4>().ok_or_else(truncated)?;
+            let len = u32::from_be_bytes(*len) as usize;
+
+            if tail.len() < len {
+                return Err(truncated());
+            }
+
+            let (vote, tail) = tail.split_at(len);
+            let vote = self
+                .codec
+                .decode(Bytes::copy_from_slice(vote))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+            votes.push(vote);
+            rest = tail;
+        }
+
+        Ok(votes)
+    }
+
+    fn save(&self, votes: &[Ctx::Vote]) -> io::Result<()> {
+        let mut bytes = Vec::new();
+
+        for vote in votes {
+            let vote = self
+                .codec
+                .encode(vote)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+            bytes.extend_from_slice(&(vote.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(&vote);
+        }
+
+        let tmp_path = self.path.with_extension("tmp");
+
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+
+        fs::rename(&tmp_path, &self.path)?;
+
+        // Make sure the rename itself is persisted
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::File::open(dir)?.sync_all()?;
+        }
+
+        Ok(())
+    }
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated signed votes file")
+}
+
+/// Sits in front of a [`SignerProvider`] and refuses to sign a vote which conflicts
+/// with a vote signed before, even across restarts, to prevent double signing.
+///
+/// A vote conflicts with a vote signed before if:
+/// - both are for the same height, round and vote type, but for different values, or
+/// - it is for a lower height than the latest height we signed a vote for.
+///
+/// Signing the exact same vote again is allowed, eg. when replaying the WAL after a restart.
+///
+/// The vote is persisted before it is signed, so that a restart in between
+/// signing and broadcasting a vote cannot lead us to sign a conflicting vote.
+///
+/// Proposals and proposal parts are passed through to the signer, as this node also signs
+/// the proposals it infers from values proposed by other validators, which are never broadcast.
+pub struct SigningGuard<Ctx, Signer, Store>
+where
+    Ctx: Context,
+{
+    signer: Signer,
+    store: Store,
+
+    /// The votes signed at the latest height we signed a vote for
+    votes: Mutex<Vec<Ctx::Vote>>,
+}
+
+impl<Ctx, Signer, Store> SigningGuard<Ctx, Signer, Store>
+where
+    Ctx: Context,
+    Signer: SignerProvider<Ctx>,
+    Store: GuardStore<Ctx>,
+{
+    /// Guard the given signer, with the votes previously persisted in the given store.
+    pub fn new(signer: Signer, store: Store) -> io::Result<Self> {
+        let votes = store.load()?;
+
+        Ok(Self {
+            signer,
+            store,
+            votes: Mutex::new(votes),
+        })
+    }
+
+    /// Check that the given vote does not conflict with the votes signed before, and persist it.
+    fn guard(&self, votes: &mut Vec<Ctx::Vote>, vote: &Ctx::Vote) -> Result<(), SignerError> {
+        let latest_height = votes.first().map(|v| v.height());
+
+        if let Some(latest_height) = latest_height.filter(|&h| vote.height() < h) {
+            return Err(SignerError::DoubleSignAttempt(format!(
+                "{:?} vote at height {} is below the latest signed height {latest_height}",
+                vote.vote_type(),
+                vote.height(),
+            )));
+        }
+
+        // The votes signed at lower heights cannot conflict with this vote anymore
+        let new_height = latest_height.is_none_or(|h| vote.height() > h);
+
+        let signed = if new_height {
+            None
+        } else {
+            votes
+                .iter()
+                .find(|v| v.round() == vote.round() && v.vote_type() == vote.vote_type())
+        };
+
+        match signed {
+            // Idempotent re-sign, nothing new to persist
+            Some(signed) if signed.value() == vote.value() => Ok(()),
+
+            Some(_) => Err(SignerError::DoubleSignAttempt(format!(
+                "{:?} vote at height {} and round {} conflicts with an already signed vote",
+                vote.vote_type(),
+                vote.height(),
+                vote.round(),
+            ))),
+
+            None => {
+                let mut next = if new_height {
+                    Vec::new()
+                } else {
+                    votes.clone()
+                };
+
+                next.push(vote.clone());
+                self.store.save(&next)?;
+                *votes = next;
+
+                Ok(())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<Ctx, Signer, Store> SignerProvider<Ctx> for SigningGuard<Ctx, Signer, Store>
+where
+    Ctx: Context,
+    Signer: SignerProvider<Ctx>,
+    Store: GuardStore<Ctx>,
+{
+    async fn sign_vote(&self, vote: Ctx::Vote) -> Result<SignedVote<Ctx>, SignerError> {
+        // Hold the lock while signing, so that concurrent requests are checked one at a time
+        let mut votes = self.votes.lock().await;
+
+        if let Err(e) = self.guard(&mut votes, &vote) {
+            warn!("Refusing to sign vote: {e}");
+            return Err(e);
+        }
+
+        self.signer.sign_vote(vote).await
+    }
+
+    async fn sign_proposal(
+        &self,
+        proposal: Ctx::Proposal,
+    ) -> Result<SignedProposal<Ctx>, SignerError> {
+        self.signer.sign_proposal(proposal).await
+    }
+
+    async fn sign_block_part(
+        &self,
+        part: Ctx::ProposalPart,
+    ) -> Result<SignedProposalPart<Ctx>, SignerError> {
+        self.signer.sign_block_part(part).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::Path;
+
+    use malachitebft_core_types::{NilOrVal, Round, SigningProvider};
+    use malachitebft_test::codec::proto::ProtobufCodec;
+    use malachitebft_test::utils::validators::make_validators;
+    use malachitebft_test::{Address, Ed25519Provider, Height, TestContext, ValueId, Vote};
+
+    use crate::signer::LocalSigner;
+
+    type Guard<Store> = SigningGuard<TestContext, LocalSigner<TestContext>, Store>;
+
+    struct Setup {
+        ctx: TestContext,
+        provider: Ed25519Provider,
+        address: Address,
+    }
+
+    fn setup() -> Setup {
+        let [(validator, private_key)] = make_validators([1]);
+
+        Setup {
+            ctx: TestContext::new(private_key.clone()),
+            provider: Ed25519Provider::new(private_key),
+            address: validator.address,
+        }
+    }
+
+    impl Setup {
+        fn guard<Store: GuardStore<TestContext>>(&self, store: Store) -> Guard<Store> {
+            SigningGuard::new(LocalSigner::new(self.ctx.clone()), store).unwrap()
+        }
+
+        fn file_guard(&self, path: &Path) -> Guard<FileGuardStore<ProtobufCodec>> {
+            self.guard(FileGuardStore::new(path, ProtobufCodec))
+        }
+
+        fn prevote(&self, height: u64, round: u32, value: u64) -> Vote {
+            Vote::new_prevote(
+                Height::new(height),
+                Round::new(round),
+                NilOrVal::Val(ValueId::new(value)),
+                self.address,
+            )
+        }
+
+        fn precommit(&self, height: u64, round: u32, value: u64) -> Vote {
+            Vote::new_precommit(
+                Height::new(height),
+                Round::new(round),
+                NilOrVal::Val(ValueId::new(value)),
+                self.address,
+            )
+        }
+    }
+
+    fn is_double_sign<T>(result: Result<T, SignerError>) -> bool {
+        matches!(result, Err(SignerError::DoubleSignAttempt(_)))
+    }
+
+    #[tokio::test]
+    async fn conflicting_votes_are_refused() {
+        let setup = setup();
+        let guard = setup.guard(InMemoryGuardStore::new());
+
+        guard.sign_vote(setup.prevote(1, 0, 42)).await.unwrap();
+        assert!(is_double_sign(
+            guard.sign_vote(setup.prevote(1, 0, 43)).await
+        ));
+
+        let nil_prevote =
+            Vote::new_prevote(Height::new(1), Round::new(0), NilOrVal::Nil, setup.address);
+        assert!(is_double_sign(guard.sign_vote(nil_prevote).await));
+
+        // Other vote types and rounds do not conflict
+        guard.sign_vote(setup.precommit(1, 0, 43)).await.unwrap();
+        guard.sign_vote(setup.prevote(1, 1, 43)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn same_vote_can_be_signed_again() {
+        let setup = setup();
+        let guard = setup.guard(InMemoryGuardStore::new());
+
+        let vote = setup.prevote(1, 0, 42);
+        let first = guard.sign_vote(vote.clone()).await.unwrap();
+        guard.sign_vote(setup.prevote(1, 1, 42)).await.unwrap();
+        let second = guard.sign_vote(vote.clone()).await.unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(first, setup.provider.sign_vote(vote));
+    }
+
+    #[tokio::test]
+    async fn votes_below_latest_height_are_refused() {
+        let setup = setup();
+        let guard = setup.guard(InMemoryGuardStore::new());
+
+        guard.sign_vote(setup.prevote(1, 0, 42)).await.unwrap();
+        guard.sign_vote(setup.prevote(2, 0, 43)).await.unwrap();
+
+        assert!(is_double_sign(
+            guard.sign_vote(setup.prevote(1, 0, 42)).await
+        ));
+        assert!(is_double_sign(
+            guard.sign_vote(setup.prevote(1, 1, 42)).await
+        ));
+    }
+
+    #[tokio::test]
+    async fn no_conflicting_signature_after_restart() {
+        let setup = setup();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signed_votes");
+
+        let vote = setup.prevote(1, 0, 42);
+
+        // Sign a vote and crash before broadcasting it
+        {
+            let guard = setup.file_guard(&path);
+            guard.sign_vote(setup.precommit(1, 0, 41)).await.unwrap();
+            guard.sign_vote(vote.clone()).await.unwrap();
+        }
+
+        // After restarting, a conflicting vote cannot be signed
+        let guard = setup.file_guard(&path);
+        assert!(is_double_sign(
+            guard.sign_vote(setup.prevote(1, 0, 43)).await
+        ));
+        assert!(is_double_sign(
+            guard.sign_vote(setup.precommit(1, 0, 43)).await
+        ));
+
+        // But the vote signed before the restart can be signed again, eg. to broadcast it
+        let signed_vote = guard.sign_vote(vote.clone()).await.unwrap();
+        assert_eq!(signed_vote, setup.provider.sign_vote(vote));
+    }
+
+    #[tokio::test]
+    async fn vote_is_persisted_before_signing() {
+        struct FailingSigner;
+
+        #[async_trait]
+        impl SignerProvider<TestContext> for FailingSigner {
+            async fn sign_vote(&self, _: Vote) -> Result<SignedVote<TestContext>, SignerError> {
+                Err(SignerError::Rejected("crashed".to_string()))
+            }
+
+            async fn sign_proposal(
+                &self,
+                _: malachitebft_test::Proposal,
+            ) -> Result<SignedProposal<TestContext>, SignerError> {
+                Err(SignerError::Rejected("crashed".to_string()))
+            }
+
+            async fn sign_block_part(
+                &self,
+                _: malachitebft_test::ProposalPart,
+            ) -> Result<SignedProposalPart<TestContext>, SignerError> {
+                Err(SignerError::Rejected("crashed".to_string()))
+            }
+        }
+
+        let setup = setup();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signed_votes");
+
+        // The signer may have produced a signature before failing, so the vote counts as signed
+        let guard =
+            SigningGuard::new(FailingSigner, FileGuardStore::new(&path, ProtobufCodec)).unwrap();
+        assert!(guard.sign_vote(setup.prevote(1, 0, 42)).await.is_err());
+        drop(guard);
+
+        let guard = setup.file_guard(&path);
+        assert!(is_double_sign(
+            guard.sign_vote(setup.prevote(1, 0, 43)).await
+        ));
+        guard.sign_vote(setup.prevote(1, 0, 42)).await.unwrap();
+    }
+
+    #[test]
+    fn truncated_file_is_an_error() {
+        let setup = setup();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signed_votes");
+
+        let store = FileGuardStore::new(&path, ProtobufCodec);
+        let votes = [setup.prevote(1, 0, 42), setup.precommit(1, 0, 42)];
+        GuardStore::<TestContext>::save(&store, &votes).unwrap();
+        assert_eq!(GuardStore::<TestContext>::load(&store).unwrap(), votes);
+
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(GuardStore::<TestContext>::load(&store).is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use async_trait::async_trait;
+
+use malachitebft_core_types::{
+    Context, SignedProposal, SignedProposalPart, SignedVote, SigningProvider,
+};
+
+use super::{SignerError, SignerProvider};
+
+/// Signs with the [`SigningProvider`] of the context, ie. with a private key held in memory.
+///
+/// Signing never fails, nor waits on anything else.
+pub struct LocalSigner<Ctx> {
+    ctx: Ctx,
+}
+
+impl<Ctx> LocalSigner<Ctx>
+where
+    Ctx: Context,
+{
+    pub fn new(ctx: Ctx) -> Self {
+        Self { ctx }
+    }
+}
+
+#[async_trait]
+impl<Ctx> SignerProvider<Ctx> for LocalSigner<Ctx>
+where
+    Ctx: Context,
+{
+    async fn sign_vote(&self, vote: Ctx::Vote) -> Result<SignedVote<Ctx>, SignerError> {
+        Ok(self.ctx.signing_provider().sign_vote(vote))
+    }
+
+    async fn sign_proposal(
+        &self,
+        proposal: Ctx::Proposal,
+    ) -> Result<SignedProposal<Ctx>, SignerError> {
+        Ok(self.ctx.signing_provider().sign_proposal(proposal))
+    }
+
+    async fn sign_block_part(
+        &self,
+        part: Ctx::ProposalPart,
+    ) -> Result<SignedProposalPart<Ctx>, SignerError> {
+        Ok(self.ctx.signing_provider().sign_proposal_part(part))
+    }
+}
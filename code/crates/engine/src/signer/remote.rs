@@ -0,0 +1,353 @@
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use prost::Message;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+use malachitebft_codec as codec;
+use malachitebft_core_types::{
+    Context, SignedMessage, SignedProposal, SignedProposalPart, SignedVote, SigningScheme,
+};
+
+use super::{SignerError, SignerProvider};
+
+/// Maximum size of a frame exchanged with a remote signer, in bytes.
+pub const MAX_FRAME_SIZE: usize = 1024 * 1024;
+
+/// Codec used to send the messages to sign to a remote signer.
+///
+/// This trait is automatically implemented for any type that implements:
+/// - [`codec::Codec<Ctx::Vote>`]
+/// - [`codec::Codec<Ctx::Proposal>`]
+/// - [`codec::Codec<Ctx::ProposalPart>`]
+pub trait SignerCodec<Ctx>
+where
+    Ctx: Context,
+    Self: codec::Codec<Ctx::Vote>,
+    Self: codec::Codec<Ctx::Proposal>,
+    Self: codec::Codec<Ctx::ProposalPart>,
+{
+}
+
+impl<Ctx, Codec> SignerCodec<Ctx> for Codec
+where
+    Ctx: Context,
+    Self: codec::Codec<Ctx::Vote>,
+    Self: codec::Codec<Ctx::Proposal>,
+    Self: codec::Codec<Ctx::ProposalPart>,
+{
+}
+
+/// The kind of message to sign.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum MessageKind {
+    Vote = 0,
+    Proposal = 1,
+    ProposalPart = 2,
+}
+
+/// A request to sign a message, sent to the remote signer.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SignRequest {
+    /// The kind of message to sign
+    #[prost(enumeration = "MessageKind", tag = "1")]
+    pub kind: i32,
+
+    /// The message to sign, encoded with the codec of the application
+    #[prost(bytes = "bytes", tag = "2")]
+    pub message: Bytes,
+}
+
+/// The response of the remote signer to a [`SignRequest`].
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SignResponse {
+    /// The signature of the message, encoded with the signing scheme of the context
+    #[prost(bytes = "bytes", tag = "1")]
+    pub signature: Bytes,
+
+    /// Why the signer refused to sign the message, empty if it did sign it
+    #[prost(string, tag = "2")]
+    pub error: String,
+}
+
+/// Asks a remote signer process listening on a unix socket to sign our messages.
+///
+/// For each message, a new connection is opened, over which a single [`SignRequest`]
+/// is sent and a single [`SignResponse`] is received, both framed with [`write_frame`].
+///
+/// The remote signer is responsible for preventing double signing.
+pub struct RemoteSigner<Ctx, Codec> {
+    socket_path: PathBuf,
+    codec: Codec,
+    _marker: PhantomData<Ctx>,
+}
+
+impl<Ctx, Codec> RemoteSigner<Ctx, Codec>
+where
+    Ctx: Context,
+    Codec: SignerCodec<Ctx>,
+{
+    pub fn new(socket_path: impl Into<PathBuf>, codec: Codec) -> Self {
+        Self {
+            socket_path: socket_path.into(),
+            codec,
+            _marker: PhantomData,
+        }
+    }
+
+    async fn sign<Msg>(
+        &self,
+        kind: MessageKind,
+        msg: Msg,
+    ) -> Result<SignedMessage<Ctx, Msg>, SignerError>
+    where
+        Codec: codec::Codec<Msg>,
+    {
+        let message = self
+            .codec
+            .encode(&msg)
+            .map_err(|e| SignerError::Encode(e.to_string()))?;
+
+        let request = SignRequest {
+            kind: kind.into(),
+            message,
+        };
+
+        let mut stream = UnixStream::connect(&self.socket_path).await?;
+        write_frame(&mut stream, &request.encode_to_vec()).await?;
+        let frame = read_frame(&mut stream).await?;
+
+        let response = SignResponse::decode(frame.as_slice())
+            .map_err(|e| SignerError::Decode(e.to_string()))?;
+
+        if !response.error.is_empty() {
+            return Err(SignerError::Rejected(response.error));
+        }
+
+        let signature =
+            <Ctx::SigningScheme as SigningScheme>::decode_signature(&response.signature)
+                .map_err(|e| SignerError::Decode(e.to_string()))?;
+
+        Ok(SignedMessage::new(msg, signature))
+    }
+}
+
+#[async_trait]
+impl<Ctx, Codec> SignerProvider<Ctx> for RemoteSigner<Ctx, Codec>
+where
+    Ctx: Context,
+    Codec: SignerCodec<Ctx>,
+{
+    async fn sign_vote(&self, vote: Ctx::Vote) -> Result<SignedVote<Ctx>, SignerError> {
+        self.sign(MessageKind::Vote, vote).await
+    }
+
+    async fn sign_proposal(
+        &self,
+        proposal: Ctx::Proposal,
+    ) -> Result<SignedProposal<Ctx>, SignerError> {
+        self.sign(MessageKind::Proposal, proposal).await
+    }
+
+    async fn sign_block_part(
+        &self,
+        part: Ctx::ProposalPart,
+    ) -> Result<SignedProposalPart<Ctx>, SignerError> {
+        self.sign(MessageKind::ProposalPart, part).await
+    }
+}
+
+/// Write a frame, ie. the length of the payload as a big-endian `u32` followed by the payload.
+pub async fn write_frame<W>(writer: &mut W, payload: &[u8]) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    if payload.len() > MAX_FRAME_SIZE {
+        return Err(frame_too_large(payload.len()));
+    }
+
+    writer.write_u32(payload.len() as u32).await?;
+    writer.write_all(payload).await?;
+    writer.flush().await
+}
+
+/// Read a frame written with [`write_frame`], returning its payload.
+pub async fn read_frame<R>(reader: &mut R) -> std::io::Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin,
+{
+    let len = reader.read_u32().await? as usize;
+
+    if len > MAX_FRAME_SIZE {
+        return Err(frame_too_large(len));
+    }
+
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload).await?;
+    Ok(payload)
+}
+
+fn frame_too_large(len: usize) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Frame of {len} bytes exceeds the maximum of {MAX_FRAME_SIZE} bytes"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::Path;
+
+    use malachitebft_codec::Codec as _;
+    use malachitebft_core_types::{NilOrVal, Round, SigningProvider};
+    use malachitebft_test::codec::proto::ProtobufCodec;
+    use malachitebft_test::utils::validators::make_validators;
+    use malachitebft_test::{
+        Ed25519Provider, Height, ProposalInit, ProposalPart, TestContext, Vote,
+    };
+    use tokio::net::UnixListener;
+
+    /// Serve a single request with a signer that signs votes and proposal parts only
+    fn serve_one(path: &Path, provider: Ed25519Provider) -> tokio::task::JoinHandle<()> {
+        let listener = UnixListener::bind(path).unwrap();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            let frame = read_frame(&mut stream).await.unwrap();
+            let request = SignRequest::decode(frame.as_slice()).unwrap();
+
+            let response = match request.kind() {
+                MessageKind::Vote => {
+                    let vote: Vote = ProtobufCodec.decode(request.message).unwrap();
+                    let signed_vote = provider.sign_vote(vote);
+                    SignResponse {
+                        signature: Bytes::copy_from_slice(&signed_vote.signature.to_bytes()),
+                        error: String::new(),
+                    }
+                }
+                MessageKind::ProposalPart => {
+                    let part: ProposalPart = ProtobufCodec.decode(request.message).unwrap();
+                    let signed_part = provider.sign_proposal_part(part);
+                    SignResponse {
+                        signature: Bytes::copy_from_slice(&signed_part.signature.to_bytes()),
+                        error: String::new(),
+                    }
+                }
+                kind => SignResponse {
+                    signature: Bytes::new(),
+                    error: format!("Refusing to sign a {kind:?}"),
+                },
+            };
+
+            write_frame(&mut stream, &response.encode_to_vec())
+                .await
+                .unwrap();
+        })
+    }
+
+    #[tokio::test]
+    async fn remote_signer_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signer.sock");
+
+        let [(validator, private_key)] = make_validators([1]);
+        let provider = Ed25519Provider::new(private_key.clone());
+        let server = serve_one(&path, Ed25519Provider::new(private_key));
+
+        let signer = RemoteSigner::<TestContext, _>::new(&path, ProtobufCodec);
+
+        let vote = Vote::new_prevote(
+            Height::new(1),
+            Round::new(0),
+            NilOrVal::Nil,
+            validator.address,
+        );
+
+        let signed_vote = signer.sign_vote(vote.clone()).await.unwrap();
+        assert_eq!(signed_vote, provider.sign_vote(vote));
+
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn remote_signer_signs_proposal_parts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signer.sock");
+
+        let [(validator, private_key)] = make_validators([1]);
+        let provider = Ed25519Provider::new(private_key.clone());
+        let server = serve_one(&path, Ed25519Provider::new(private_key));
+
+        let signer = RemoteSigner::<TestContext, _>::new(&path, ProtobufCodec);
+
+        let part = ProposalPart::Init(ProposalInit::new(
+            Height::new(1),
+            Round::new(0),
+            validator.address,
+        ));
+
+        let signed_part = signer.sign_block_part(part.clone()).await.unwrap();
+        assert_eq!(signed_part, provider.sign_proposal_part(part));
+
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn remote_signer_rejects() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signer.sock");
+
+        let [(validator, private_key)] = make_validators([1]);
+        let server = serve_one(&path, Ed25519Provider::new(private_key));
+
+        let signer = RemoteSigner::<TestContext, _>::new(&path, ProtobufCodec);
+
+        let proposal = malachitebft_test::Proposal::new(
+            Height::new(1),
+            Round::new(0),
+            malachitebft_test::Value::new(42),
+            Round::Nil,
+            validator.address,
+        );
+
+        let result = signer.sign_proposal(proposal).await;
+        assert!(matches!(result, Err(SignerError::Rejected(_))));
+
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn remote_signer_unreachable() {
+        let dir = tempfile::tempdir().unwrap();
+        let signer =
+            RemoteSigner::<TestContext, _>::new(dir.path().join("none.sock"), ProtobufCodec);
+
+        let [(validator, _)] = make_validators([1]);
+        let vote = Vote::new_prevote(
+            Height::new(1),
+            Round::new(0),
+            NilOrVal::Nil,
+            validator.address,
+        );
+
+        let result = signer.sign_vote(vote).await;
+        assert!(matches!(result, Err(SignerError::Io(_))));
+    }
+
+    #[tokio::test]
+    async fn oversized_frame() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+
+        client.write_u32(MAX_FRAME_SIZE as u32 + 1).await.unwrap();
+
+        let result = read_frame(&mut server).await;
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+}
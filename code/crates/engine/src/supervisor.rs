@@ -0,0 +1,149 @@
+//! Restart strategy for the actors supervised by the node actor.
+//!
+//! When a restartable component fails, the node actor waits for a backoff delay which doubles
+//! with each recent failure, respawns the component and notifies consensus of the restart.
+//! If the component keeps failing, the node is shut down instead.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use ractor::{ActorCell, SpawnErr};
+
+/// A component of the node which can be restarted after a failure
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Component {
+    /// The mempool, along with its gossip layer
+    Mempool,
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Component::Mempool => write!(f, "mempool"),
+        }
+    }
+}
+
+pub type RestartFuture = Pin<Box<dyn Future<Output = Result<ActorCell, SpawnErr>> + Send>>;
+
+/// Respawns a component after it failed and returns the new actor,
+/// which is then supervised by the node actor in place of the failed one.
+///
+/// The function is responsible for handing over the new actor to the other actors using it,
+/// and for restoring the state of the component, if any.
+pub type RestartFn = Box<dyn Fn() -> RestartFuture + Send + Sync>;
+
+/// How failed components are restarted
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// Maximum number of restarts of a component within `window`,
+    /// after which the node is shut down
+    pub max_restarts: usize,
+
+    /// Period over which the restarts of a component are counted
+    pub window: Duration,
+
+    /// Delay before the first restart of a component
+    pub initial_backoff: Duration,
+
+    /// Maximum delay before restarting a component
+    pub max_backoff: Duration,
+}
+
+impl RestartPolicy {
+    /// Delay before the restart following the given number of recent restarts
+    pub fn backoff(&self, restarts: usize) -> Duration {
+        let factor = 1_u32.checked_shl(restarts as u32).unwrap_or(u32::MAX);
+
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            window: Duration::from_secs(60),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+/// The recent restarts of a component
+#[derive(Clone, Debug, Default)]
+pub struct Restarts {
+    times: VecDeque<Instant>,
+}
+
+impl Restarts {
+    /// Record a restart of the component at the given time and return the delay to wait
+    /// before restarting it, or `None` if it failed too many times and must not be restarted.
+    pub fn next(&mut self, policy: &RestartPolicy, now: Instant) -> Option<Duration> {
+        while let Some(time) = self.times.front() {
+            if now.saturating_duration_since(*time) <= policy.window {
+                break;
+            }
+
+            self.times.pop_front();
+        }
+
+        if self.times.len() >= policy.max_restarts {
+            return None;
+        }
+
+        let backoff = policy.backoff(self.times.len());
+        self.times.push_back(now);
+
+        Some(backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_until_max() {
+        let policy = RestartPolicy {
+            max_restarts: 100,
+            window: Duration::from_secs(60),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+
+        let backoffs: Vec<_> = (0..6).map(|n| policy.backoff(n)).collect();
+
+        assert_eq!(
+            backoffs,
+            [100, 200, 400, 800, 1000, 1000].map(Duration::from_millis)
+        );
+
+        assert_eq!(policy.backoff(usize::MAX), policy.max_backoff);
+    }
+
+    #[test]
+    fn crash_loop_is_escalated() {
+        let policy = RestartPolicy {
+            max_restarts: 3,
+            ..Default::default()
+        };
+
+        let mut restarts = Restarts::default();
+        let start = Instant::now();
+
+        assert_eq!(restarts.next(&policy, start), Some(policy.backoff(0)));
+        assert_eq!(restarts.next(&policy, start), Some(policy.backoff(1)));
+        assert_eq!(restarts.next(&policy, start), Some(policy.backoff(2)));
+        assert_eq!(restarts.next(&policy, start), None);
+
+        // Restarts older than the window are forgotten
+        let later = start + policy.window + Duration::from_secs(1);
+        assert_eq!(restarts.next(&policy, later), Some(policy.backoff(0)));
+    }
+}
@@ -97,6 +97,9 @@ pub enum Msg<Ctx: Context> {
     /// Consensus needs vote set from peers
     RequestVoteSet(Ctx::Height, Round),
 
+    /// Consensus is behind its peers and needs the decided value at the given height
+    CatchUp(Ctx::Height),
+
     /// Consensus has sent a vote set response to a peer
     SentVoteSetResponse(InboundRequestId, Ctx::Height, Round),
 }
@@ -117,6 +120,7 @@ impl<Ctx: Context> From<TimeoutElapsed<Timeout>> for Msg<Ctx> {
 pub struct Params {
     pub status_update_interval: Duration,
     pub request_timeout: Duration,
+    pub batch_size: usize,
 }
 
 impl Default for Params {
@@ -124,6 +128,7 @@ impl Default for Params {
         Self {
             status_update_interval: Duration::from_secs(5),
             request_timeout: Duration::from_secs(10),
+            batch_size: 5,
         }
     }
 }
@@ -326,6 +331,11 @@ where
                     .await?;
             }
 
+            Msg::CatchUp(height) => {
+                self.process_input(&myself, state, sync::Input::CatchUp(height))
+                    .await?;
+            }
+
             Msg::SentVoteSetResponse(request_id, height, round) => {
                 self.process_input(
                     &myself,
@@ -499,7 +509,7 @@ where
         let rng = Box::new(rand::rngs::StdRng::from_entropy());
 
         Ok(State {
-            sync: sync::State::new(rng),
+            sync: sync::State::new(rng, self.params.batch_size),
             timers: Timers::new(Box::new(myself.clone())),
             inflight: HashMap::new(),
             ticker,
@@ -6,6 +6,8 @@ use tokio::sync::broadcast;
 use malachitebft_core_consensus::{ProposedValue, SignedConsensusMsg, ValueToPropose};
 use malachitebft_core_types::{CommitCertificate, Context, Round, Timeout, ValueOrigin};
 
+use crate::supervisor::Component;
+
 pub type RxEvent<Ctx> = broadcast::Receiver<Event<Ctx>>;
 
 pub struct TxEvent<Ctx: Context> {
@@ -38,10 +40,12 @@ impl<Ctx: Context> Default for TxEvent<Ctx> {
 #[derive_where(Clone, Debug)]
 pub enum Event<Ctx: Context> {
     StartedHeight(Ctx::Height),
+    CatchingUp(Ctx::Height, Ctx::Height),
     StartedRound(Ctx::Height, Round),
     Published(SignedConsensusMsg<Ctx>),
     ProposedValue(ValueToPropose<Ctx>),
     ReceivedProposedValue(ProposedValue<Ctx>, ValueOrigin),
+    BuildProgress(Ctx::Height, Round, u64, usize),
     Decided(CommitCertificate<Ctx>),
     RequestedVoteSet(Ctx::Height, Round),
     SentVoteSetResponse(Ctx::Height, Round, usize),
@@ -49,12 +53,19 @@ pub enum Event<Ctx: Context> {
     WalReplayConsensus(SignedConsensusMsg<Ctx>),
     WalReplayTimeout(Timeout),
     WalReplayDone(Ctx::Height),
+    DependencyRestarted(Component),
 }
 
 impl<Ctx: Context> fmt::Display for Event<Ctx> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Event::StartedHeight(height) => write!(f, "StartedHeight(height: {height})"),
+            Event::CatchingUp(height, peers_height) => {
+                write!(
+                    f,
+                    "CatchingUp(height: {height}, peers_height: {peers_height})"
+                )
+            }
             Event::StartedRound(height, round) => {
                 write!(f, "StartedRound(height: {height}, round: {round})")
             }
@@ -66,6 +77,12 @@ impl<Ctx: Context> fmt::Display for Event<Ctx> {
                     "ReceivedProposedValue(value: {value:?}, origin: {origin:?})"
                 )
             }
+            Event::BuildProgress(height, round, sequence, bytes) => {
+                write!(
+                    f,
+                    "BuildProgress(height: {height}, round: {round}, sequence: {sequence}, bytes: {bytes})"
+                )
+            }
             Event::Decided(cert) => write!(f, "Decided(value: {})", cert.value_id),
             Event::RequestedVoteSet(height, round) => {
                 write!(f, "RequestedVoteSet(height: {height}, round: {round})")
@@ -82,6 +99,9 @@ impl<Ctx: Context> fmt::Display for Event<Ctx> {
             Event::WalReplayConsensus(msg) => write!(f, "WalReplayConsensus(msg: {msg:?})"),
             Event::WalReplayTimeout(timeout) => write!(f, "WalReplayTimeout(timeout: {timeout:?})"),
             Event::WalReplayDone(height) => write!(f, "WalReplayDone(height: {height})"),
+            Event::DependencyRestarted(component) => {
+                write!(f, "DependencyRestarted(component: {component})")
+            }
         }
     }
 }
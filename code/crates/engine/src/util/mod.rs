@@ -1,4 +1,6 @@
 pub mod events;
+pub mod prepared_value;
 pub mod streaming;
 pub mod ticker;
 pub mod timers;
+pub mod value_builds;
@@ -0,0 +1,145 @@
+use derive_where::derive_where;
+use tracing::{debug, info};
+
+use malachitebft_core_types::{Context, Round};
+
+use crate::host::LocallyProposedValue;
+
+/// A value which the host built too late to be proposed in the round it was built for,
+/// kept to be proposed in the next round of the same height where we are the proposer.
+///
+/// Only values marked as [reusable](LocallyProposedValue::reusable) by the host are kept.
+#[derive_where(Debug, Default)]
+pub struct PreparedValue<Ctx: Context> {
+    value: Option<LocallyProposedValue<Ctx>>,
+}
+
+impl<Ctx: Context> PreparedValue<Ctx> {
+    /// Keep the given value if it is reusable and was built too late for its round,
+    /// ie. consensus is at a later round of the same height, or has left the propose step.
+    ///
+    /// Returns the value back if it was not kept.
+    pub fn keep_if_late(
+        &mut self,
+        proposed: LocallyProposedValue<Ctx>,
+        height: Ctx::Height,
+        round: Round,
+        in_propose_step: bool,
+    ) -> Option<LocallyProposedValue<Ctx>> {
+        let late = proposed.height == height
+            && (proposed.round < round || (proposed.round == round && !in_propose_step));
+
+        if !late || !proposed.reusable {
+            return Some(proposed);
+        }
+
+        info!(
+            height = %proposed.height, round = %proposed.round,
+            "Value was built too late for its round, keeping it for a later round"
+        );
+
+        self.value = Some(proposed);
+
+        None
+    }
+
+    /// Take the value kept for the given height, if any, to propose it at the given round.
+    pub fn take(&mut self, height: Ctx::Height, round: Round) -> Option<LocallyProposedValue<Ctx>> {
+        let value = self.value.take()?;
+
+        if value.height != height {
+            return None;
+        }
+
+        Some(LocallyProposedValue { round, ..value })
+    }
+
+    /// Drop the value kept for the given height, if any,
+    /// eg. because the mempool of the application has changed materially.
+    pub fn invalidate(&mut self, height: Ctx::Height) {
+        if self
+            .value
+            .as_ref()
+            .is_some_and(|value| value.height == height)
+        {
+            debug!(%height, "Invalidating the value kept for a later round");
+            self.value = None;
+        }
+    }
+
+    /// Drop the value kept, if any, eg. when moving to the next height
+    pub fn clear(&mut self) {
+        self.value = None;
+    }
+
+    /// Whether no value is kept
+    pub fn is_empty(&self) -> bool {
+        self.value.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use malachitebft_test::{Height, TestContext, Value};
+
+    fn proposed(round: u32) -> LocallyProposedValue<TestContext> {
+        LocallyProposedValue::new(Height::new(1), Round::new(round), Value::new(1), None)
+    }
+
+    #[test]
+    fn late_value_is_proposed_in_a_later_round() {
+        let mut prepared = PreparedValue::<TestContext>::default();
+
+        // Built for round 0, but the propose timeout elapsed and we are now at round 1
+        let late =
+            prepared.keep_if_late(proposed(0).reusable(), Height::new(1), Round::new(1), true);
+        assert_eq!(late, None);
+
+        // We are the proposer again at round 2, the value is proposed right away
+        let value = prepared.take(Height::new(1), Round::new(2)).unwrap();
+        assert_eq!(value.height, Height::new(1));
+        assert_eq!(value.round, Round::new(2));
+        assert_eq!(value.value, Value::new(1));
+
+        assert!(prepared.is_empty());
+    }
+
+    #[test]
+    fn value_in_time_or_not_reusable_is_not_kept() {
+        let mut prepared = PreparedValue::<TestContext>::default();
+
+        let value = proposed(0).reusable();
+        let result = prepared.keep_if_late(value.clone(), Height::new(1), Round::new(0), true);
+        assert_eq!(result, Some(value));
+
+        let value = proposed(0);
+        let result = prepared.keep_if_late(value.clone(), Height::new(1), Round::new(0), false);
+        assert_eq!(result, Some(value));
+
+        assert!(prepared.is_empty());
+    }
+
+    #[test]
+    fn value_is_not_proposed_at_another_height() {
+        let mut prepared = PreparedValue::<TestContext>::default();
+
+        prepared.keep_if_late(proposed(0).reusable(), Height::new(1), Round::new(0), false);
+        assert!(prepared.take(Height::new(2), Round::new(0)).is_none());
+        assert!(prepared.is_empty());
+    }
+
+    #[test]
+    fn invalidated_value_is_not_proposed() {
+        let mut prepared = PreparedValue::<TestContext>::default();
+
+        prepared.keep_if_late(proposed(0).reusable(), Height::new(1), Round::new(1), true);
+
+        prepared.invalidate(Height::new(2));
+        assert!(!prepared.is_empty());
+
+        prepared.invalidate(Height::new(1));
+        assert!(prepared.take(Height::new(1), Round::new(2)).is_none());
+    }
+}
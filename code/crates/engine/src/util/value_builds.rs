@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use malachitebft_core_types::Round;
+
+/// The values being built by the host when we are the proposer, by height and round.
+///
+/// Each build is given a cancellation token, which is cancelled once consensus
+/// has left the propose step of the round the value was being built for,
+/// so that the host stops wasting time building a value which will never be proposed.
+#[derive(Debug)]
+pub struct ValueBuilds<Height> {
+    builds: BTreeMap<(Height, Round), CancellationToken>,
+}
+
+impl<Height> Default for ValueBuilds<Height> {
+    fn default() -> Self {
+        Self {
+            builds: BTreeMap::new(),
+        }
+    }
+}
+
+impl<Height> ValueBuilds<Height>
+where
+    Height: Copy + Ord + std::fmt::Display,
+{
+    /// Start tracking the build of a value for the given height and round,
+    /// cancelling any previous build for the same height and round.
+    pub fn start(&mut self, height: Height, round: Round) -> CancellationToken {
+        let token = CancellationToken::new();
+
+        if let Some(previous) = self.builds.insert((height, round), token.clone()) {
+            previous.cancel();
+        }
+
+        token
+    }
+
+    /// Stop tracking the build of a value once the value was built, without cancelling it.
+    pub fn finish(&mut self, height: Height, round: Round) {
+        self.builds.remove(&(height, round));
+    }
+
+    /// Cancel the builds for any height and round other than the given ones,
+    /// as well as the build for the given height and round if we are not in the propose step anymore.
+    pub fn cancel_stale(&mut self, height: Height, round: Round, in_propose_step: bool) {
+        self.builds.retain(|&(build_height, build_round), token| {
+            if build_height == height && build_round == round && in_propose_step {
+                return true;
+            }
+
+            debug!(height = %build_height, round = %build_round, "Cancelling value build for a stale round");
+            token.cancel();
+
+            false
+        });
+    }
+
+    /// Cancel all the builds, eg. when shutting down
+    pub fn cancel_all(&mut self) {
+        for (_, token) in std::mem::take(&mut self.builds) {
+            token.cancel();
+        }
+    }
+
+    /// Number of builds in flight
+    pub fn len(&self) -> usize {
+        self.builds.len()
+    }
+
+    /// Whether no build is in flight
+    pub fn is_empty(&self) -> bool {
+        self.builds.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_is_cancelled_when_the_round_is_skipped() {
+        let mut builds = ValueBuilds::<u64>::default();
+
+        let token = builds.start(1, Round::new(0));
+
+        // Still proposing at the same round
+        builds.cancel_stale(1, Round::new(0), true);
+        assert!(!token.is_cancelled());
+
+        // Skipped to the next round
+        builds.cancel_stale(1, Round::new(1), true);
+        assert!(token.is_cancelled());
+        assert!(builds.is_empty());
+    }
+
+    #[test]
+    fn build_is_cancelled_when_leaving_the_propose_step() {
+        let mut builds = ValueBuilds::<u64>::default();
+
+        let token = builds.start(1, Round::new(0));
+
+        // eg. after a polka for another value
+        builds.cancel_stale(1, Round::new(0), false);
+        assert!(token.is_cancelled());
+        assert!(builds.is_empty());
+    }
+
+    #[test]
+    fn finished_build_is_not_cancelled() {
+        let mut builds = ValueBuilds::<u64>::default();
+
+        let token = builds.start(1, Round::new(0));
+        builds.finish(1, Round::new(0));
+
+        builds.cancel_stale(2, Round::new(0), true);
+        assert!(!token.is_cancelled());
+    }
+
+    #[test]
+    fn rebuild_cancels_the_previous_build() {
+        let mut builds = ValueBuilds::<u64>::default();
+
+        let first = builds.start(1, Round::new(0));
+        let second = builds.start(1, Round::new(0));
+
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+        assert_eq!(builds.len(), 1);
+
+        builds.cancel_all();
+        assert!(second.is_cancelled());
+    }
+}
@@ -132,6 +132,7 @@ fn encode_timeout(timeout: &Timeout, mut buf: impl Write) -> io::Result<()> {
         TimeoutKind::Prevote => 2,
         TimeoutKind::Precommit => 3,
         TimeoutKind::Commit => 4,
+        TimeoutKind::ProposalGrace => 5,
 
         // We do not store these two timeouts in the WAL
         TimeoutKind::PrevoteTimeLimit | TimeoutKind::PrecommitTimeLimit => 0,
@@ -153,6 +154,7 @@ fn decode_timeout(mut buf: impl Read) -> io::Result<Timeout> {
         2 => TimeoutKind::Prevote,
         3 => TimeoutKind::Precommit,
         4 => TimeoutKind::Commit,
+        5 => TimeoutKind::ProposalGrace,
         _ => {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -161,7 +163,8 @@ fn decode_timeout(mut buf: impl Read) -> io::Result<Timeout> {
         }
     };
 
-    let round = Round::from(buf.read_i64::<BE>()?);
+    let round = Round::try_from_i64(buf.read_i64::<BE>()?)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid round"))?;
 
     Ok(Timeout::new(round, step))
 }
@@ -72,6 +72,21 @@ pub struct Inner {
     /// Size of each block in bytes
     pub block_size_bytes: Histogram,
 
+    /// Number of transactions reaped from the mempool while building a block
+    pub reaped_txes: Counter,
+
+    /// Number of reaped transactions which were included in a block being built
+    pub included_txes: Counter,
+
+    /// Number of reaped transactions which were left out of a block because they did not fit in it
+    pub excluded_txes: Counter,
+
+    /// Number of parts of each block built by this node
+    pub block_parts: Histogram,
+
+    /// Time taken to build a block, in seconds
+    pub block_build_time: Histogram,
+
     /// The consensus round in which the node was when it finalized a block
     pub consensus_round: Histogram,
 
@@ -96,6 +111,15 @@ pub struct Inner {
     /// Time taken to verify a signature
     pub signature_verification_time: Histogram,
 
+    /// Number of duplicate votes and proposals dropped before verifying their signature
+    pub duplicate_messages: Counter,
+
+    /// Number of votes and proposals which were not emitted because the signer failed or timed out
+    pub signer_failures: Counter,
+
+    /// Number of votes which were not emitted because they conflicted with a vote signed before
+    pub double_sign_attempts: Counter,
+
     /// Internal state for measuring time taken for consensus
     instant_consensus_started: Arc<AtomicInstant>,
 
@@ -118,6 +142,11 @@ impl Metrics {
             }),
             block_tx_count: Histogram::new(linear_buckets(0.0, 32.0, 128)),
             block_size_bytes: Histogram::new(linear_buckets(0.0, 64.0 * 1024.0, 128)),
+            reaped_txes: Counter::default(),
+            included_txes: Counter::default(),
+            excluded_txes: Counter::default(),
+            block_parts: Histogram::new(exponential_buckets(1.0, 2.0, 16)),
+            block_build_time: Histogram::new(linear_buckets(0.0, 0.1, 20)),
             consensus_round: Histogram::new(linear_buckets(0.0, 1.0, 20)),
             proposal_round: Histogram::new(linear_buckets(0.0, 1.0, 20)),
             step_timeouts: Counter::default(),
@@ -126,6 +155,9 @@ impl Metrics {
             round: Gauge::default(),
             signature_signing_time: Histogram::new(exponential_buckets(0.001, 2.0, 10)),
             signature_verification_time: Histogram::new(exponential_buckets(0.001, 2.0, 10)),
+            duplicate_messages: Counter::default(),
+            signer_failures: Counter::default(),
+            double_sign_attempts: Counter::default(),
             instant_consensus_started: Arc::new(AtomicInstant::empty()),
             instant_block_started: Arc::new(AtomicInstant::empty()),
             instant_step_started: Arc::new(Mutex::new((Step::Unstarted, Instant::now()))),
@@ -178,6 +210,36 @@ impl Metrics {
                 metrics.block_size_bytes.clone(),
             );
 
+            registry.register(
+                "reaped_txes",
+                "Number of transactions reaped from the mempool while building a block",
+                metrics.reaped_txes.clone(),
+            );
+
+            registry.register(
+                "included_txes",
+                "Number of reaped transactions which were included in a block being built",
+                metrics.included_txes.clone(),
+            );
+
+            registry.register(
+                "excluded_txes",
+                "Number of reaped transactions which were left out of a block because they did not fit in it",
+                metrics.excluded_txes.clone(),
+            );
+
+            registry.register(
+                "block_parts",
+                "Number of parts of each block built by this node",
+                metrics.block_parts.clone(),
+            );
+
+            registry.register(
+                "block_build_time",
+                "Time taken to build a block, in seconds",
+                metrics.block_build_time.clone(),
+            );
+
             registry.register(
                 "consensus_round",
                 "The consensus round in which the node was when it finalized a block",
@@ -225,6 +287,24 @@ impl Metrics {
                 "Time taken to verify a signature, in seconds",
                 metrics.signature_verification_time.clone(),
             );
+
+            registry.register(
+                "duplicate_messages",
+                "Number of duplicate votes and proposals dropped before verifying their signature",
+                metrics.duplicate_messages.clone(),
+            );
+
+            registry.register(
+                "signer_failures",
+                "Number of votes and proposals which were not emitted because the signer failed or timed out",
+                metrics.signer_failures.clone(),
+            );
+
+            registry.register(
+                "double_sign_attempts",
+                "Number of votes which were not emitted because they conflicted with a vote signed before",
+                metrics.double_sign_attempts.clone(),
+            );
         });
 
         metrics
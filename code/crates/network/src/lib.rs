@@ -196,7 +196,11 @@ pub async fn spawn(
     let (tx_ctrl, rx_ctrl) = mpsc::channel(32);
 
     let discovery = registry.with_prefix(DISCOVERY_METRICS_PREFIX, |reg| {
-        discovery::Discovery::new(config.discovery, config.persistent_peers.clone(), reg)
+        discovery::Discovery::new(
+            config.discovery.clone(),
+            config.persistent_peers.clone(),
+            reg,
+        )
     });
 
     let state = State::new(discovery);
@@ -223,6 +227,8 @@ async fn run(
     }
 
     state.discovery.dial_bootstrap_nodes(&swarm);
+    state.discovery.dial_persistent_peers(&swarm);
+    state.discovery.resolve_dns_seeds();
 
     if let Err(e) = pubsub::subscribe(&mut swarm, config.pubsub_protocol, Channel::consensus()) {
         error!("Error subscribing to consensus channels: {e}");
@@ -260,6 +266,11 @@ async fn run(
                 ControlFlow::Continue(())
             }
 
+            Some(resolution) = state.discovery.controller.dns_resolution.recv() => {
+                state.discovery.handle_dns_resolution(&swarm, resolution);
+                ControlFlow::Continue(())
+            }
+
             Some(ctrl) = rx_ctrl.recv() => {
                 handle_ctrl_msg(&mut swarm, &mut state, &config, ctrl).await
             }
@@ -455,9 +466,13 @@ async fn handle_swarm_event(
             match &event.result {
                 Ok(rtt) => {
                     trace!("Received pong from {} in {rtt:?}", event.peer);
+
+                    state.discovery.record_peer_rtt(event.peer, *rtt);
                 }
                 Err(e) => {
                     trace!("Received pong from {} with error: {e}", event.peer);
+
+                    state.discovery.record_peer_failure(event.peer);
                 }
             }
 
@@ -493,7 +508,7 @@ async fn handle_gossipsub_event(
     event: gossipsub::Event,
     _metrics: &Metrics,
     _swarm: &mut swarm::Swarm<Behaviour>,
-    _state: &mut State,
+    state: &mut State,
     tx_event: &mpsc::Sender<Event>,
 ) -> ControlFlow<()> {
     match event {
@@ -516,10 +531,12 @@ async fn handle_gossipsub_event(
         }
 
         gossipsub::Event::Message {
+            propagation_source,
             message_id,
             message,
-            ..
         } => {
+            state.discovery.record_peer_activity(&propagation_source);
+
             let Some(peer_id) = message.source else {
                 return ControlFlow::Continue(());
             };
@@ -561,7 +578,7 @@ async fn handle_broadcast_event(
     event: broadcast::Event,
     _metrics: &Metrics,
     _swarm: &mut swarm::Swarm<Behaviour>,
-    _state: &mut State,
+    state: &mut State,
     tx_event: &mpsc::Sender<Event>,
 ) -> ControlFlow<()> {
     match event {
@@ -584,6 +601,8 @@ async fn handle_broadcast_event(
         }
 
         broadcast::Event::Received(peer_id, topic, message) => {
+            state.discovery.record_peer_activity(&peer_id);
+
             let Some(channel) = Channel::from_broadcast_topic(&topic) else {
                 trace!("Received message from {peer_id} on different channel: {topic:?}");
                 return ControlFlow::Continue(());
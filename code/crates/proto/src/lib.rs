@@ -6,6 +6,15 @@ use thiserror::Error;
 
 use prost::{DecodeError, EncodeError, Message, Name};
 
+mod limits;
+pub use limits::DecodeLimits;
+
+mod nil_or_val;
+pub use nil_or_val::{decode_nil_or_val, encode_nil_or_val};
+
+mod stream;
+pub use stream::{decode_stream, encode_message, encode_stream, DecodedStream};
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Failed to decode Protobuf message")]
@@ -26,6 +35,19 @@ pub enum Error {
         field: &'static str,
     },
 
+    #[error("Invalid signature length: expected {expected} bytes, got {got}")]
+    InvalidSignatureLength { expected: usize, got: usize },
+
+    #[error("Unsupported message version: {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("Decode limit exceeded for `{field}`: got {got}, limit is {limit}")]
+    LimitExceeded {
+        field: &'static str,
+        limit: usize,
+        got: usize,
+    },
+
     #[error("Unknown message type: `{type_url}`")]
     UnknownMessageType { type_url: String },
 
@@ -64,12 +86,33 @@ pub trait Protobuf: Sized {
 
     fn to_proto(&self) -> Result<Self::Proto, Error>;
 
+    /// Check that the fields of the given message are within the given limits,
+    /// before converting it with [`Protobuf::from_proto`].
+    ///
+    /// Types with fields of unbounded size, eg. byte arrays or repeated fields,
+    /// should override this method.
+    fn check_limits(proto: &Self::Proto, limits: &DecodeLimits) -> Result<(), Error> {
+        let _ = (proto, limits);
+        Ok(())
+    }
+
     fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
         let proto = Self::Proto::decode(bytes)?;
         let result = Self::from_proto(proto)?;
         Ok(result)
     }
 
+    /// Decode a message received from a peer, failing with [`Error::LimitExceeded`]
+    /// if it exceeds the given limits.
+    fn from_bytes_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self, Error> {
+        limits.check_message_size(bytes.len())?;
+
+        let proto = Self::Proto::decode(bytes)?;
+        Self::check_limits(&proto, limits)?;
+
+        Self::from_proto(proto)
+    }
+
     fn to_bytes(&self) -> Result<Bytes, Error> {
         let proto = self.to_proto()?;
         Ok(Bytes::from(proto.encode_to_vec()))
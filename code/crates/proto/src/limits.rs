@@ -0,0 +1,76 @@
+//! Limits on the size of the messages received from peers,
+//! enforced when decoding them to avoid large allocations on crafted payloads.
+
+use crate::Error;
+
+/// Limits enforced when decoding messages received from peers.
+///
+/// The size of the raw message is checked before parsing it, which bounds the memory
+/// allocated by the Protobuf decoder. The size of individual fields is then checked
+/// by [`Protobuf::check_limits`](crate::Protobuf::check_limits) before converting
+/// the Protobuf message into its domain type.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum size of an encoded message, in bytes
+    pub max_message_size: usize,
+
+    /// Maximum size of a signature, in bytes
+    pub max_signature_bytes: usize,
+
+    /// Maximum number of transactions in a batch
+    pub max_transactions_per_batch: usize,
+
+    /// Maximum size of a transaction, in bytes
+    pub max_transaction_size: usize,
+
+    /// Maximum number of parts in a proposal
+    pub max_parts_per_proposal: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_message_size: 10 * 1024 * 1024,
+            max_signature_bytes: 64,
+            max_transactions_per_batch: 10_000,
+            max_transaction_size: 1024 * 1024,
+            max_parts_per_proposal: 10_000,
+        }
+    }
+}
+
+impl DecodeLimits {
+    /// Fail with [`Error::LimitExceeded`] if `got` is larger than `limit`.
+    pub fn check(field: &'static str, got: usize, limit: usize) -> Result<(), Error> {
+        if got > limit {
+            return Err(Error::LimitExceeded { field, limit, got });
+        }
+
+        Ok(())
+    }
+
+    pub fn check_message_size(&self, size: usize) -> Result<(), Error> {
+        Self::check("message", size, self.max_message_size)
+    }
+
+    pub fn check_signature_bytes(&self, size: usize) -> Result<(), Error> {
+        Self::check("signature", size, self.max_signature_bytes)
+    }
+
+    pub fn check_transactions_per_batch(&self, count: usize) -> Result<(), Error> {
+        Self::check("transactions", count, self.max_transactions_per_batch)
+    }
+
+    pub fn check_transaction_size(&self, size: usize) -> Result<(), Error> {
+        Self::check("transaction", size, self.max_transaction_size)
+    }
+
+    /// Parts are numbered from 0 in a proposal, so the sequence number of
+    /// a part must be lower than the maximum number of parts.
+    pub fn check_part_sequence(&self, sequence: u64) -> Result<(), Error> {
+        let parts = usize::try_from(sequence)
+            .unwrap_or(usize::MAX)
+            .saturating_add(1);
+        Self::check("parts", parts, self.max_parts_per_proposal)
+    }
+}
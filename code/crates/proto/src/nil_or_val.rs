@@ -0,0 +1,86 @@
+//! Encoding of [`NilOrVal`] as an optional message field.
+//!
+//! `Nil` is encoded as an absent field, and `Val` as a present one, even if the value
+//! is encoded as an empty message because all its fields have their default value.
+//! Since the presence of message fields is tracked on the wire, such a value
+//! is never confused with `Nil` when decoding it.
+
+use malachitebft_core_types::NilOrVal;
+
+use crate::{Error, Protobuf};
+
+/// Encode the given value, or `None` if it is `Nil`
+pub fn encode_nil_or_val<T: Protobuf>(value: &NilOrVal<T>) -> Result<Option<T::Proto>, Error> {
+    match value {
+        NilOrVal::Nil => Ok(None),
+        NilOrVal::Val(value) => value.to_proto().map(Some),
+    }
+}
+
+/// Decode the given value, or `Nil` if the field is absent
+pub fn decode_nil_or_val<T: Protobuf>(proto: Option<T::Proto>) -> Result<NilOrVal<T>, Error> {
+    match proto {
+        None => Ok(NilOrVal::Nil),
+        Some(proto) => T::from_proto(proto).map(NilOrVal::Val),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+    use prost_types::Duration;
+
+    use super::*;
+
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    struct Secs(i64);
+
+    impl Protobuf for Secs {
+        type Proto = Duration;
+
+        fn from_proto(proto: Self::Proto) -> Result<Self, Error> {
+            Ok(Self(proto.seconds))
+        }
+
+        fn to_proto(&self) -> Result<Self::Proto, Error> {
+            Ok(Duration {
+                seconds: self.0,
+                nanos: 0,
+            })
+        }
+    }
+
+    /// A message with an optional field, such as the value of a vote
+    #[derive(Clone, PartialEq, Message)]
+    struct Vote {
+        #[prost(message, optional, tag = "1")]
+        value: Option<Duration>,
+    }
+
+    fn roundtrip(value: NilOrVal<Secs>) -> NilOrVal<Secs> {
+        let vote = Vote {
+            value: encode_nil_or_val(&value).unwrap(),
+        };
+
+        let decoded = Vote::decode(vote.encode_to_vec().as_slice()).unwrap();
+        decode_nil_or_val(decoded.value).unwrap()
+    }
+
+    #[test]
+    fn roundtrip_nil() {
+        assert_eq!(roundtrip(NilOrVal::Nil), NilOrVal::Nil);
+    }
+
+    #[test]
+    fn roundtrip_val() {
+        assert_eq!(roundtrip(NilOrVal::Val(Secs(42))), NilOrVal::Val(Secs(42)));
+    }
+
+    #[test]
+    fn zero_val_is_not_nil() {
+        // The value is encoded as an empty message
+        assert_eq!(Secs(0).to_proto().unwrap().encoded_len(), 0);
+
+        assert_eq!(roundtrip(NilOrVal::Val(Secs(0))), NilOrVal::Val(Secs(0)));
+    }
+}
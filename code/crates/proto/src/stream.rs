@@ -0,0 +1,186 @@
+//! Encoding and decoding of streams of length-delimited Protobuf messages,
+//! eg. for persisting consensus messages to a write-ahead log.
+
+use prost::bytes::{Buf, BufMut, Bytes, BytesMut};
+use prost::encoding::{decode_varint, encoded_len_varint};
+use prost::Message;
+
+use crate::{Error, Protobuf};
+
+/// Maximum length of a varint-encoded length prefix.
+const MAX_VARINT_LEN: usize = 10;
+
+/// Encode the given messages, each prefixed with its varint-encoded length.
+pub fn encode_stream<'a, T, I>(messages: I) -> Result<Bytes, Error>
+where
+    T: Protobuf + 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    let mut buf = BytesMut::new();
+
+    for message in messages {
+        encode_message(message, &mut buf)?;
+    }
+
+    Ok(buf.freeze())
+}
+
+/// Encode a single message prefixed with its varint-encoded length,
+/// and append it to the given buffer.
+pub fn encode_message<T, B>(message: &T, buf: &mut B) -> Result<(), Error>
+where
+    T: Protobuf,
+    B: BufMut,
+{
+    let proto = message.to_proto()?;
+    proto.encode_length_delimited(buf)?;
+    Ok(())
+}
+
+/// The messages decoded from a stream of length-delimited messages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedStream<T> {
+    /// The messages which were fully decoded
+    pub messages: Vec<T>,
+
+    /// Number of bytes at the end of the stream which do not form a complete message,
+    /// eg. because the last write to the stream was interrupted.
+    pub truncated_bytes: usize,
+}
+
+impl<T> DecodedStream<T> {
+    /// Whether the stream ended with an incomplete message.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated_bytes > 0
+    }
+}
+
+/// Decode a stream of length-delimited messages, as produced by [`encode_stream`].
+///
+/// An incomplete message at the end of the stream is not an error,
+/// instead its length is reported in [`DecodedStream::truncated_bytes`].
+/// A complete message which cannot be decoded is an error.
+pub fn decode_stream<T>(bytes: &[u8]) -> Result<DecodedStream<T>, Error>
+where
+    T: Protobuf,
+{
+    let mut buf = bytes;
+    let mut messages = Vec::new();
+
+    while buf.has_remaining() {
+        let Some(len) = peek_length(buf)? else {
+            break;
+        };
+
+        let prefix_len = encoded_len_varint(len as u64);
+        if buf.remaining() - prefix_len < len {
+            break;
+        }
+
+        buf.advance(prefix_len);
+
+        let proto = T::Proto::decode(&buf[..len])?;
+        messages.push(T::from_proto(proto)?);
+
+        buf.advance(len);
+    }
+
+    Ok(DecodedStream {
+        messages,
+        truncated_bytes: buf.remaining(),
+    })
+}
+
+/// Read the length prefix at the start of the buffer without consuming it,
+/// returning `None` if the buffer ends before the end of the prefix.
+fn peek_length(buf: &[u8]) -> Result<Option<usize>, Error> {
+    let mut prefix = buf;
+
+    match decode_varint(&mut prefix) {
+        Ok(len) => usize::try_from(len)
+            .map(Some)
+            .map_err(|_| Error::Other(format!("Message length {len} is too large"))),
+
+        // A prefix cut short by the end of the buffer
+        Err(_) if buf.len() < MAX_VARINT_LEN => Ok(None),
+
+        Err(e) => Err(Error::Decode(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::Duration;
+
+    use super::*;
+
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    struct Millis(i64);
+
+    impl Protobuf for Millis {
+        type Proto = Duration;
+
+        fn from_proto(proto: Self::Proto) -> Result<Self, Error> {
+            Ok(Self(
+                proto.seconds * 1000 + i64::from(proto.nanos) / 1_000_000,
+            ))
+        }
+
+        fn to_proto(&self) -> Result<Self::Proto, Error> {
+            Ok(Duration {
+                seconds: self.0 / 1000,
+                nanos: (self.0 % 1000) as i32 * 1_000_000,
+            })
+        }
+    }
+
+    fn messages() -> Vec<Millis> {
+        vec![Millis(0), Millis(1), Millis(1500), Millis(123_456_789)]
+    }
+
+    #[test]
+    fn roundtrip() {
+        let messages = messages();
+        let bytes = encode_stream(&messages).unwrap();
+
+        let decoded = decode_stream::<Millis>(&bytes).unwrap();
+        assert_eq!(decoded.messages, messages);
+        assert!(!decoded.is_truncated());
+    }
+
+    #[test]
+    fn empty_stream() {
+        let bytes = encode_stream::<Millis, _>([]).unwrap();
+        assert!(bytes.is_empty());
+
+        let decoded = decode_stream::<Millis>(&bytes).unwrap();
+        assert!(decoded.messages.is_empty());
+        assert!(!decoded.is_truncated());
+    }
+
+    #[test]
+    fn truncated_tail() {
+        let messages = messages();
+        let bytes = encode_stream(&messages).unwrap();
+
+        let last_len = encode_stream(&messages[messages.len() - 1..])
+            .unwrap()
+            .len();
+
+        // Cut the last message at every possible position, including within its length prefix
+        for cut in 1..last_len {
+            let truncated = &bytes[..bytes.len() - cut];
+
+            let decoded = decode_stream::<Millis>(truncated).unwrap();
+            assert_eq!(decoded.messages, messages[..messages.len() - 1]);
+            assert_eq!(decoded.truncated_bytes, last_len - cut);
+        }
+    }
+
+    #[test]
+    fn corrupted_message() {
+        // A complete frame of one byte, which is not a valid message
+        let bytes = [1, 0xff];
+        assert!(decode_stream::<Millis>(&bytes).is_err());
+    }
+}
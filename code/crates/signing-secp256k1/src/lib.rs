@@ -0,0 +1,334 @@
+// no_std compatibility
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use malachitebft_core_types::SigningScheme;
+use sha2::{Digest, Sha256};
+use signature::{Keypair, Signer, Verifier};
+
+#[cfg(feature = "rand")]
+use rand::{CryptoRng, RngCore};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "serde")]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod serializers;
+
+/// Length of an encoded signature, ie. the `r` and `s` scalars in big-endian order.
+pub const SIGNATURE_LENGTH: usize = 64;
+
+/// Length of an encoded public key, ie. the compressed SEC1 encoding of the curve point.
+pub const PUBLIC_KEY_LENGTH: usize = 33;
+
+/// Length of an address derived from a public key.
+pub const ADDRESS_LENGTH: usize = 20;
+
+/// ECDSA over the secp256k1 curve, with SHA-256 as the message digest.
+///
+/// Signatures are encoded in their fixed-size form, without any tag identifying the scheme,
+/// so all the validators of a chain are expected to use the same signing scheme.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Secp256k1;
+
+impl Secp256k1 {
+    #[cfg(feature = "rand")]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    pub fn generate_keypair<R>(rng: R) -> PrivateKey
+    where
+        R: RngCore + CryptoRng,
+    {
+        PrivateKey::generate(rng)
+    }
+}
+
+impl SigningScheme for Secp256k1 {
+    type DecodingError = signature::Error;
+
+    type Signature = Signature;
+    type PublicKey = PublicKey;
+    type PrivateKey = PrivateKey;
+
+    fn encode_signature(signature: &Signature) -> Vec<u8> {
+        signature.to_bytes().to_vec()
+    }
+
+    fn decode_signature(bytes: &[u8]) -> Result<Self::Signature, Self::DecodingError> {
+        Signature::try_from(bytes)
+    }
+}
+
+/// A signature, always in its normalized "low S" form.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Signature(
+    #[cfg_attr(feature = "serde", serde(with = "self::serializers::signature"))]
+    k256::ecdsa::Signature,
+);
+
+impl Signature {
+    pub fn inner(&self) -> &k256::ecdsa::Signature {
+        &self.0
+    }
+
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_LENGTH] {
+        let mut bytes = [0; SIGNATURE_LENGTH];
+        bytes.copy_from_slice(&self.0.to_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: [u8; SIGNATURE_LENGTH]) -> Result<Self, signature::Error> {
+        Self::try_from(bytes.as_slice())
+    }
+}
+
+impl TryFrom<&[u8]> for Signature {
+    type Error = signature::Error;
+
+    /// Decode a signature, rejecting signatures which are not in their "low S" form
+    /// to prevent malleability.
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let signature = k256::ecdsa::Signature::from_slice(bytes)?;
+
+        if signature.normalize_s().is_some() {
+            return Err(signature::Error::new());
+        }
+
+        Ok(Self(signature))
+    }
+}
+
+impl PartialOrd for Signature {
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Signature {
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct PrivateKey(
+    #[cfg_attr(feature = "serde", serde(with = "self::serializers::signing_key"))]
+    k256::ecdsa::SigningKey,
+);
+
+impl PrivateKey {
+    #[cfg(feature = "rand")]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    pub fn generate<R>(mut rng: R) -> Self
+    where
+        R: RngCore + CryptoRng,
+    {
+        let signing_key = k256::ecdsa::SigningKey::random(&mut rng);
+
+        Self(signing_key)
+    }
+
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::new(*self.0.verifying_key())
+    }
+
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        Signature(self.0.sign(msg))
+    }
+
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    pub fn inner(&self) -> &k256::ecdsa::SigningKey {
+        &self.0
+    }
+}
+
+impl TryFrom<[u8; 32]> for PrivateKey {
+    type Error = signature::Error;
+
+    /// Fails if the given bytes are not a valid non-zero scalar.
+    fn try_from(bytes: [u8; 32]) -> Result<Self, Self::Error> {
+        k256::ecdsa::SigningKey::from_slice(&bytes).map(Self)
+    }
+}
+
+impl Signer<Signature> for PrivateKey {
+    fn try_sign(&self, msg: &[u8]) -> Result<Signature, signature::Error> {
+        self.0.try_sign(msg).map(Signature)
+    }
+}
+
+impl Keypair for PrivateKey {
+    type VerifyingKey = PublicKey;
+
+    fn verifying_key(&self) -> Self::VerifyingKey {
+        self.public_key()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct PublicKey(
+    #[cfg_attr(feature = "serde", serde(with = "self::serializers::verifying_key"))]
+    k256::ecdsa::VerifyingKey,
+);
+
+impl PublicKey {
+    pub fn new(key: impl Into<k256::ecdsa::VerifyingKey>) -> Self {
+        Self(key.into())
+    }
+
+    /// Encode the public key in its compressed SEC1 form.
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_LENGTH] {
+        let mut bytes = [0; PUBLIC_KEY_LENGTH];
+        bytes.copy_from_slice(self.0.to_encoded_point(true).as_bytes());
+        bytes
+    }
+
+    /// Decode a public key from its SEC1 encoding, either compressed or not.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, signature::Error> {
+        k256::ecdsa::VerifyingKey::from_sec1_bytes(bytes).map(Self)
+    }
+
+    /// Derive the address of this public key,
+    /// ie. the last 20 bytes of the SHA-256 hash of the compressed public key.
+    pub fn to_address_bytes(&self) -> [u8; ADDRESS_LENGTH] {
+        let hash = Sha256::digest(self.to_bytes());
+
+        let mut address = [0; ADDRESS_LENGTH];
+        address.copy_from_slice(&hash[hash.len() - ADDRESS_LENGTH..]);
+        address
+    }
+
+    pub fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), signature::Error> {
+        self.0.verify(msg, signature.inner())
+    }
+
+    pub fn inner(&self) -> &k256::ecdsa::VerifyingKey {
+        &self.0
+    }
+}
+
+impl Verifier<Signature> for PublicKey {
+    fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), signature::Error> {
+        PublicKey::verify(self, msg, signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    const MSG: &[u8] = b"hello malachite";
+
+    fn keys() -> (PrivateKey, PrivateKey) {
+        let mut rng = StdRng::seed_from_u64(0x42);
+        let key_a = k256::ecdsa::SigningKey::random(&mut rng);
+        let key_b = k256::ecdsa::SigningKey::random(&mut rng);
+        (PrivateKey(key_a), PrivateKey(key_b))
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let (key, _) = keys();
+
+        let signature = key.sign(MSG);
+        assert!(key.public_key().verify(MSG, &signature).is_ok());
+        assert!(key
+            .public_key()
+            .verify(b"other message", &signature)
+            .is_err());
+    }
+
+    #[test]
+    fn cross_verification_fails() {
+        let (key_a, key_b) = keys();
+
+        let signature_a = key_a.sign(MSG);
+        let signature_b = key_b.sign(MSG);
+
+        assert_ne!(signature_a, signature_b);
+        assert!(key_b.public_key().verify(MSG, &signature_a).is_err());
+        assert!(key_a.public_key().verify(MSG, &signature_b).is_err());
+    }
+
+    #[test]
+    fn signature_roundtrip() {
+        let (key, _) = keys();
+        let signature = key.sign(MSG);
+
+        let bytes = Secp256k1::encode_signature(&signature);
+        assert_eq!(bytes.len(), SIGNATURE_LENGTH);
+
+        let decoded = Secp256k1::decode_signature(&bytes).unwrap();
+        assert_eq!(decoded, signature);
+        assert!(key.public_key().verify(MSG, &decoded).is_ok());
+
+        assert!(Secp256k1::decode_signature(&bytes[1..]).is_err());
+        assert!(Secp256k1::decode_signature(&[0; SIGNATURE_LENGTH]).is_err());
+    }
+
+    #[test]
+    fn reject_high_s_signatures() {
+        let (key, _) = keys();
+        let signature = key.sign(MSG);
+
+        // Negating `s` yields another valid signature over the same message, which must be rejected
+        let (r, s) = signature.inner().split_scalars();
+        let high_s = k256::ecdsa::Signature::from_scalars(r, -s).unwrap();
+
+        assert!(Secp256k1::decode_signature(&high_s.to_bytes()).is_err());
+    }
+
+    #[test]
+    fn public_key_roundtrip() {
+        let (key, _) = keys();
+        let public_key = key.public_key();
+
+        let bytes = public_key.to_bytes();
+        assert!(bytes[0] == 0x02 || bytes[0] == 0x03);
+        assert_eq!(PublicKey::from_bytes(&bytes).unwrap(), public_key);
+
+        let uncompressed = public_key.inner().to_encoded_point(false);
+        assert_eq!(
+            PublicKey::from_bytes(uncompressed.as_bytes()).unwrap(),
+            public_key
+        );
+    }
+
+    #[test]
+    fn private_key_from_bytes() {
+        let (key, _) = keys();
+        let bytes: [u8; 32] = key.inner().to_bytes().into();
+
+        let decoded = PrivateKey::try_from(bytes).unwrap();
+        assert_eq!(decoded.public_key(), key.public_key());
+
+        assert!(PrivateKey::try_from([0; 32]).is_err());
+    }
+
+    #[test]
+    fn address_from_public_key() {
+        let (key_a, key_b) = keys();
+
+        let hash = Sha256::digest(key_a.public_key().to_bytes());
+        let address = key_a.public_key().to_address_bytes();
+
+        assert_eq!(address.as_slice(), &hash[12..]);
+        assert_ne!(address, key_b.public_key().to_address_bytes());
+    }
+}
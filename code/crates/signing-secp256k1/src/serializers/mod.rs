@@ -0,0 +1,4 @@
+pub mod base64string;
+pub mod signature;
+pub mod signing_key;
+pub mod verifying_key;
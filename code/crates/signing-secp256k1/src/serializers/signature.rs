@@ -0,0 +1,19 @@
+//! Serialize/deserialize Secp256k1 signatures as base64-encoded strings
+
+use k256::ecdsa::Signature;
+use serde::Serializer;
+
+pub fn serialize<S>(s: &Signature, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    super::base64string::serialize(&s.to_bytes(), ser)
+}
+
+pub fn deserialize<'de, D>(de: D) -> Result<Signature, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let bytes = super::base64string::deserialize(de)?;
+    Signature::from_slice(&bytes).map_err(serde::de::Error::custom)
+}
@@ -0,0 +1,34 @@
+//! Serde Secp256k1 SigningKey CometBFT serializer/deserializer.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use k256::ecdsa::SigningKey;
+use serde::{Deserialize, Serialize, Serializer};
+
+#[derive(Serialize, Deserialize)]
+struct PrivKey {
+    #[serde(rename = "type")]
+    key_type: String,
+    #[serde(with = "super::base64string")]
+    value: Vec<u8>,
+}
+
+pub fn serialize<S>(s: &SigningKey, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    PrivKey {
+        key_type: "tendermint/PrivKeySecp256k1".to_string(),
+        value: s.to_bytes().to_vec(),
+    }
+    .serialize(ser)
+}
+
+pub fn deserialize<'de, D>(de: D) -> Result<SigningKey, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let pk = PrivKey::deserialize(de)?;
+    SigningKey::from_slice(pk.value.as_slice()).map_err(serde::de::Error::custom)
+}
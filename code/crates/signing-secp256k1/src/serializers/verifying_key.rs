@@ -0,0 +1,34 @@
+//! Serde Secp256k1 VerifyingKey CometBFT serializer/deserializer.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use k256::ecdsa::VerifyingKey;
+use serde::{Deserialize, Serialize, Serializer};
+
+#[derive(Serialize, Deserialize)]
+struct PubKey {
+    #[serde(rename = "type")]
+    key_type: String,
+    #[serde(with = "super::base64string")]
+    value: Vec<u8>,
+}
+
+pub fn serialize<S>(s: &VerifyingKey, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    PubKey {
+        key_type: "tendermint/PubKeySecp256k1".to_string(),
+        value: s.to_encoded_point(true).as_bytes().to_vec(),
+    }
+    .serialize(ser)
+}
+
+pub fn deserialize<'de, D>(de: D) -> Result<VerifyingKey, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let pk = PubKey::deserialize(de)?;
+    VerifyingKey::from_sec1_bytes(pk.value.as_slice()).map_err(serde::de::Error::custom)
+}
@@ -7,13 +7,13 @@ use itertools::Itertools;
 use ractor::{async_trait, Actor, ActorProcessingErr, RpcReplyPort, SpawnErr};
 use rand::rngs::StdRng;
 use rand::SeedableRng;
-use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, trace, warn};
 
 use malachitebft_core_consensus::PeerId;
 use malachitebft_core_types::{CommitCertificate, Round, Validity, ValueOrigin};
 use malachitebft_engine::consensus::{ConsensusMsg, ConsensusRef};
-use malachitebft_engine::host::{LocallyProposedValue, ProposedValue};
+use malachitebft_engine::host::{LocallyProposedValue, ProposeDecision, ProposedValue};
 use malachitebft_engine::network::{NetworkMsg, NetworkRef};
 use malachitebft_engine::util::streaming::{StreamContent, StreamMessage};
 use malachitebft_metrics::Metrics;
@@ -22,12 +22,13 @@ use malachitebft_sync::DecidedValue;
 use crate::host::proposal::compute_proposal_signature;
 use crate::host::state::HostState;
 use crate::host::{Host as _, StarknetHost};
-use crate::mempool::{MempoolMsg, MempoolRef};
+use crate::mempool::{MempoolMsg, SharedMempoolRef};
 use crate::proto::Protobuf;
+use crate::streaming::metrics::Metrics as StreamingMetrics;
 use crate::types::*;
 
 pub struct Host {
-    mempool: MempoolRef,
+    mempool: SharedMempoolRef,
     network: NetworkRef<MockContext>,
     metrics: Metrics,
     span: tracing::Span,
@@ -40,9 +41,10 @@ impl Host {
     pub async fn spawn(
         home_dir: PathBuf,
         host: StarknetHost,
-        mempool: MempoolRef,
+        mempool: SharedMempoolRef,
         network: NetworkRef<MockContext>,
         metrics: Metrics,
+        streaming_metrics: StreamingMetrics,
         span: tracing::Span,
     ) -> Result<HostRef, SpawnErr> {
         let db_dir = home_dir.join("db");
@@ -52,7 +54,12 @@ impl Host {
         let (actor_ref, _) = Actor::spawn(
             None,
             Self::new(mempool, network, metrics, span),
-            HostState::new(host, db_path, &mut StdRng::from_entropy()),
+            HostState::new(
+                host,
+                db_path,
+                streaming_metrics,
+                &mut StdRng::from_entropy(),
+            ),
         )
         .await?;
 
@@ -60,7 +67,7 @@ impl Host {
     }
 
     pub fn new(
-        mempool: MempoolRef,
+        mempool: SharedMempoolRef,
         network: NetworkRef<MockContext>,
         metrics: Metrics,
         span: tracing::Span,
@@ -82,11 +89,10 @@ impl Actor for Host {
 
     async fn pre_start(
         &self,
-        myself: HostRef,
+        _myself: HostRef,
         initial_state: Self::State,
     ) -> Result<Self::State, ActorProcessingErr> {
-        self.mempool.link(myself.get_cell());
-
+        // NOTE: The mempool is supervised by the node actor, which restarts it when it fails
         Ok(initial_state)
     }
 
@@ -132,8 +138,26 @@ impl Host {
                 height,
                 round,
                 timeout,
+                cancel,
                 reply_to,
-            } => on_get_value(state, &self.network, height, round, timeout, reply_to).await,
+            } => {
+                on_get_value(
+                    state,
+                    &self.network,
+                    height,
+                    round,
+                    timeout,
+                    cancel,
+                    reply_to,
+                )
+                .await
+            }
+
+            HostMsg::ReadyToPropose { reply_to, .. } => {
+                // We have nothing to add to the value we just built
+                reply_to.send(ProposeDecision::Accept)?;
+                Ok(())
+            }
 
             HostMsg::RestreamValue {
                 height,
@@ -271,6 +295,7 @@ async fn on_get_value(
     height: Height,
     round: Round,
     timeout: Duration,
+    cancel: CancellationToken,
     reply_to: RpcReplyPort<LocallyProposedValue<MockContext>>,
 ) -> Result<(), ActorProcessingErr> {
     if let Some(value) = find_previously_built_value(state, height, round).await? {
@@ -286,35 +311,67 @@ async fn on_get_value(
         return Ok(());
     }
 
-    let deadline = Instant::now() + timeout;
+    let deadline = state.host.now() + timeout;
+
+    // If consensus keeps the values built too late for their round, to propose them in a later round,
+    // finish building the value even once consensus has moved on, but stop gossiping it
+    let reuse = state.host.params.reuse_late_values;
+    let build_cancel = if reuse {
+        CancellationToken::new()
+    } else {
+        cancel.clone()
+    };
 
     debug!(%height, %round, "Building new proposal...");
 
-    let (mut rx_part, rx_hash) = state.host.build_new_proposal(height, round, deadline).await;
+    let (mut rx_part, rx_hash) = state
+        .host
+        .build_new_proposal(height, round, deadline, build_cancel)
+        .await;
 
     let stream_id = state.next_stream_id();
 
     let mut sequence = 0;
+    let mut bytes = 0;
 
     while let Some(part) = rx_part.recv().await {
-        state.host.part_store.store(height, round, part.clone());
+        // Do not gossip the parts which were already built when consensus moved on
+        if cancel.is_cancelled() && !reuse {
+            debug!(%height, %round, "Proposal build was cancelled, not proposing any value");
+            return Ok(());
+        }
 
-        if state.host.params.value_payload.include_parts() {
+        state
+            .host
+            .part_store
+            .store(height, round, sequence, part.clone());
+
+        bytes += part.size_bytes();
+
+        if state.host.params.value_payload.include_parts() && !cancel.is_cancelled() {
             debug!(%stream_id, %sequence, "Broadcasting proposal part");
 
             let msg = StreamMessage::new(stream_id, sequence, StreamContent::Data(part.clone()));
             network.cast(NetworkMsg::PublishProposalPart(msg))?;
         }
 
+        if state.host.params.report_build_progress {
+            report_build_progress(state, height, round, sequence, bytes);
+        }
+
         sequence += 1;
     }
 
-    if state.host.params.value_payload.include_parts() {
+    let Ok(block_hash) = rx_hash.await else {
+        debug!(%height, %round, "Proposal build was cancelled, not proposing any value");
+        return Ok(());
+    };
+
+    if state.host.params.value_payload.include_parts() && !cancel.is_cancelled() {
         let msg = StreamMessage::new(stream_id, sequence, StreamContent::Fin(true));
         network.cast(NetworkMsg::PublishProposalPart(msg))?;
     }
 
-    let block_hash = rx_hash.await?;
     debug!(%block_hash, "Assembled block");
 
     state
@@ -334,16 +391,42 @@ async fn on_get_value(
         error!(%e, %height, %round, "Failed to store the proposed value");
     }
 
-    reply_to.send(LocallyProposedValue::new(
-        value.height,
-        value.round,
-        value.value,
-        value.extension,
-    ))?;
+    let proposed =
+        LocallyProposedValue::new(value.height, value.round, value.value, value.extension);
+
+    // The value is restreamed for the round it is eventually proposed at, if any
+    if reuse {
+        reply_to.send(proposed.reusable())?;
+    } else {
+        reply_to.send(proposed)?;
+    }
 
     Ok(())
 }
 
+fn report_build_progress(
+    state: &HostState,
+    height: Height,
+    round: Round,
+    sequence: u64,
+    bytes: usize,
+) {
+    let Some(consensus) = &state.consensus else {
+        return;
+    };
+
+    let msg = ConsensusMsg::BuildProgress {
+        height,
+        round,
+        sequence,
+        bytes,
+    };
+
+    if let Err(e) = consensus.cast(msg) {
+        warn!(%height, %round, "Failed to report build progress to consensus: {e}");
+    }
+}
+
 /// If we have already built a block for this height and round, return it to consensus
 /// This may happen when we are restarting after a crash and replaying the WAL.
 async fn find_previously_built_value(
@@ -401,7 +484,10 @@ async fn on_restream_value(
             PartType::Transactions | PartType::BlockProof => part,
         };
 
-        state.host.part_store.store(height, round, new_part.clone());
+        state
+            .host
+            .part_store
+            .store(height, round, sequence, new_part.clone());
 
         if state.host.params.value_payload.include_parts() {
             debug!(%stream_id, %sequence, "Broadcasting proposal part");
@@ -409,9 +495,9 @@ async fn on_restream_value(
             let msg = StreamMessage::new(stream_id, sequence, StreamContent::Data(new_part));
 
             network.cast(NetworkMsg::PublishProposalPart(msg))?;
-
-            sequence += 1;
         }
+
+        sequence += 1;
     }
 
     Ok(())
@@ -424,7 +510,7 @@ fn on_process_synced_value(
     proposer: Address,
     reply_to: RpcReplyPort<ProposedValue<MockContext>>,
 ) -> Result<(), ActorProcessingErr> {
-    let maybe_block = Block::from_bytes(value_bytes.as_ref());
+    let maybe_block = SyncedBlock::from_bytes(value_bytes.as_ref());
     if let Ok(block) = maybe_block {
         let proposed_value = ProposedValue {
             height,
@@ -484,8 +570,6 @@ async fn on_received_proposal_part(
     reply_to: RpcReplyPort<ProposedValue<MockContext>>,
 ) -> Result<(), ActorProcessingErr> {
     // TODO - use state.host.receive_proposal() and move some of the logic below there
-    let sequence = part.sequence;
-
     let Some(parts) = state.part_streams_map.insert(from, part) else {
         return Ok(());
     };
@@ -496,14 +580,13 @@ async fn on_received_proposal_part(
             round = %state.round,
             part.height = %parts.height,
             part.round = %parts.round,
-            part.sequence = %sequence,
             "Received outdated proposal part, ignoring"
         );
 
         return Ok(());
     }
 
-    for part in parts.parts {
+    for (sequence, part) in parts.parts {
         debug!(
             part.sequence = %sequence,
             part.height = %parts.height,
@@ -513,7 +596,7 @@ async fn on_received_proposal_part(
         );
 
         if let Some(value) = state
-            .build_value_from_part(parts.height, parts.round, part)
+            .build_value_from_part(parts.height, parts.round, sequence, part)
             .await
         {
             debug!(
@@ -539,7 +622,7 @@ async fn on_received_proposal_part(
 async fn on_decided(
     state: &mut HostState,
     consensus: &ConsensusRef<MockContext>,
-    mempool: &MempoolRef,
+    mempool: &SharedMempoolRef,
     certificate: CommitCertificate<MockContext>,
     metrics: &Metrics,
 ) -> Result<(), ActorProcessingErr> {
@@ -597,17 +680,40 @@ async fn on_decided(
     // Store the block
     prune_block_store(state).await;
 
-    // Notify the mempool to remove corresponding txs
-    mempool.cast(MempoolMsg::Update { tx_hashes })?;
+    // Notify the mempool to remove corresponding txs,
+    // which must not prevent us from starting the next height if the mempool is being restarted
+    if let Err(e) = mempool.get().cast(MempoolMsg::Update {
+        height: height.as_u64(),
+        committed_txes: tx_hashes,
+    }) {
+        error!(%height, "Failed to update the mempool: {e}");
+    }
 
     // Notify Starknet Host of the decision
     state.host.decision(certificate).await;
 
     // Start the next height
-    consensus.cast(ConsensusMsg::StartHeight(
-        state.height.increment(),
-        state.host.validator_set.clone(),
-    ))?;
+    let start_height =
+        ConsensusMsg::StartHeight(state.height.increment(), state.host.validator_set.clone());
+
+    let delay = state.host.params.start_height_delay;
+
+    if delay.is_zero() {
+        consensus.cast(start_height)?;
+    } else {
+        // Simulate an application which takes some time to execute the block
+        // before starting the next height, without blocking the host actor meanwhile.
+        debug!(?delay, "Delaying the start of the next height");
+
+        let consensus = consensus.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+
+            if let Err(e) = consensus.cast(start_height) {
+                error!("Failed to start the next height: {e}");
+            }
+        });
+    }
 
     Ok(())
 }
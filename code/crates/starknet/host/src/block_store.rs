@@ -16,14 +16,14 @@ use malachitebft_proto::Protobuf;
 use crate::codec::{self, ProtobufCodec};
 use crate::proto::{self as proto, Error as ProtoError};
 use crate::types::MockContext;
-use crate::types::{Block, BlockHash, Height, Transaction, Transactions};
+use crate::types::{BlockHash, Height, SyncedBlock, Transaction, Transactions};
 
 mod keys;
 use keys::{HeightKey, UndecidedValueKey};
 
 #[derive(Clone, Debug)]
 pub struct DecidedBlock {
-    pub block: Block,
+    pub block: SyncedBlock,
     pub certificate: CommitCertificate<MockContext>,
 }
 
@@ -86,7 +86,7 @@ impl Db {
         let block = {
             let table = tx.open_table(DECIDED_BLOCKS_TABLE)?;
             let value = table.get(&height)?;
-            value.and_then(|value| Block::from_bytes(&value.value()).ok())
+            value.and_then(|value| SyncedBlock::from_bytes(&value.value()).ok())
         };
         let certificate = {
             let table = tx.open_table(CERTIFICATES_TABLE)?;
@@ -127,7 +127,7 @@ impl Db {
         let tx = self.db.begin_read()?;
         let mut values = Vec::new();
 
-        let from = (height, round, BlockHash::new([0; 32]));
+        let from = (height, round, BlockHash::ZERO);
         let to = (height, round, BlockHash::new([255; 32]));
 
         let table = tx.open_table(UNDECIDED_VALUES_TABLE)?;
@@ -135,7 +135,7 @@ impl Db {
 
         for key in keys {
             if let Ok(Some(value)) = table.get(&key) {
-                let Ok(value) = ProtobufCodec.decode(Bytes::from(value.value())) else {
+                let Ok(value) = ProtobufCodec::default().decode(Bytes::from(value.value())) else {
                     error!(hash = %key.2, "Failed to decode ProposedValue");
                     continue;
                 };
@@ -149,7 +149,7 @@ impl Db {
 
     fn insert_undecided_value(&self, value: ProposedValue<MockContext>) -> Result<(), StoreError> {
         let key = (value.height, value.round, value.value);
-        let value = ProtobufCodec.encode(&value)?;
+        let value = ProtobufCodec::default().encode(&value)?;
         let tx = self.db.begin_write()?;
         {
             let mut table = tx.open_table(UNDECIDED_VALUES_TABLE)?;
@@ -195,7 +195,7 @@ impl Db {
             let mut undecided = tx.open_table(UNDECIDED_VALUES_TABLE)?;
             let keys = self.undecided_values_range(
                 &undecided,
-                ..(retain_height, Round::Nil, BlockHash::new([0; 32])),
+                ..(retain_height, Round::Nil, BlockHash::ZERO),
             )?;
             for key in keys {
                 undecided.remove(key)?;
@@ -273,7 +273,7 @@ impl BlockStore {
         txes: &[Transaction],
     ) -> Result<(), StoreError> {
         let decided_block = DecidedBlock {
-            block: Block {
+            block: SyncedBlock {
                 height: certificate.height,
                 block_hash: certificate.value_id,
                 transactions: Transactions::new(txes.to_vec()),
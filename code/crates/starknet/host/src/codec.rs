@@ -15,7 +15,7 @@ use malachitebft_core_consensus::{PeerId, ProposedValue, SignedConsensusMsg};
 use malachitebft_starknet_p2p_proto::ConsensusMessage;
 
 use crate::proto::consensus_message::Messages;
-use crate::proto::{self as proto, Error as ProtoError, Protobuf};
+use crate::proto::{self as proto, DecodeLimits, Error as ProtoError, Protobuf};
 use crate::types::{self as p2p, Address, BlockHash, Height, MockContext, ProposalPart, Vote};
 
 trait MessageExt {
@@ -31,13 +31,39 @@ where
     }
 }
 
-pub struct ProtobufCodec;
+/// Protobuf codec for the Starknet types.
+///
+/// Every message is checked against the configured [`DecodeLimits`] while it is decoded,
+/// so that a peer cannot get us to allocate unbounded amounts of memory.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ProtobufCodec {
+    limits: DecodeLimits,
+}
+
+impl ProtobufCodec {
+    pub fn new(limits: DecodeLimits) -> Self {
+        Self { limits }
+    }
+
+    pub fn limits(&self) -> &DecodeLimits {
+        &self.limits
+    }
+
+    /// Check the size of the given bytes before decoding them as a Protobuf message
+    fn decode_proto<M>(&self, bytes: Bytes) -> Result<M, ProtoError>
+    where
+        M: Message + Default,
+    {
+        self.limits.check_message_size(bytes.len())?;
+        Ok(M::decode(bytes)?)
+    }
+}
 
 impl Codec<Address> for ProtobufCodec {
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<Address, Self::Error> {
-        Protobuf::from_bytes(&bytes)
+        Protobuf::from_bytes_with_limits(&bytes, &self.limits)
     }
 
     fn encode(&self, address: &Address) -> Result<Bytes, Self::Error> {
@@ -49,7 +75,7 @@ impl Codec<BlockHash> for ProtobufCodec {
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<BlockHash, Self::Error> {
-        Protobuf::from_bytes(&bytes)
+        Protobuf::from_bytes_with_limits(&bytes, &self.limits)
     }
 
     fn encode(&self, block_hash: &BlockHash) -> Result<Bytes, Self::Error> {
@@ -61,7 +87,7 @@ impl Codec<ProposalPart> for ProtobufCodec {
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<ProposalPart, Self::Error> {
-        Protobuf::from_bytes(bytes.as_ref())
+        Protobuf::from_bytes_with_limits(&bytes, &self.limits)
     }
 
     fn encode(&self, msg: &ProposalPart) -> Result<Bytes, Self::Error> {
@@ -92,7 +118,7 @@ impl Codec<SignedExtension<MockContext>> for ProtobufCodec {
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<SignedExtension<MockContext>, Self::Error> {
-        decode_extension(proto::Extension::decode(bytes)?)
+        decode_extension(self.decode_proto::<proto::Extension>(bytes)?)
     }
 
     fn encode(&self, msg: &SignedExtension<MockContext>) -> Result<Bytes, Self::Error> {
@@ -113,7 +139,7 @@ pub fn decode_proposed_value(
         value: BlockHash::from_bytes(&proto.value)?,
         valid_round: Round::from(proto.valid_round),
         proposer: Address::from_proto(proposer)?,
-        validity: Validity::from_bool(proto.validity),
+        validity: decode_validity(proto.validity()),
         extension: proto.extension.map(decode_extension).transpose()?,
     })
 }
@@ -128,21 +154,34 @@ pub fn encode_proposed_value(
         valid_round: msg.valid_round.as_u32(),
         value: msg.value.to_bytes()?,
         proposer: Some(msg.proposer.to_proto()?),
-        validity: match msg.validity {
-            Validity::Valid => true,
-            Validity::Invalid => false,
-        },
+        validity: encode_validity(msg.validity).into(),
         extension: msg.extension.as_ref().map(encode_extension).transpose()?,
     };
 
     Ok(proto)
 }
 
+fn decode_validity(proto: proto::sync::proposed_value::Validity) -> Validity {
+    match proto {
+        proto::sync::proposed_value::Validity::Valid => Validity::Valid,
+        proto::sync::proposed_value::Validity::Invalid => Validity::Invalid,
+        proto::sync::proposed_value::Validity::Unknown => Validity::Unknown,
+    }
+}
+
+fn encode_validity(validity: Validity) -> proto::sync::proposed_value::Validity {
+    match validity {
+        Validity::Valid => proto::sync::proposed_value::Validity::Valid,
+        Validity::Invalid => proto::sync::proposed_value::Validity::Invalid,
+        Validity::Unknown => proto::sync::proposed_value::Validity::Unknown,
+    }
+}
+
 impl Codec<ProposedValue<MockContext>> for ProtobufCodec {
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<ProposedValue<MockContext>, Self::Error> {
-        decode_proposed_value(proto::sync::ProposedValue::decode(bytes)?)
+        decode_proposed_value(self.decode_proto::<proto::sync::ProposedValue>(bytes)?)
     }
 
     fn encode(&self, msg: &ProposedValue<MockContext>) -> Result<Bytes, Self::Error> {
@@ -164,7 +203,7 @@ impl Codec<PeerId> for ProtobufCodec {
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<PeerId, Self::Error> {
-        decode_peer_id(proto::PeerId::decode(bytes)?)
+        decode_peer_id(self.decode_proto::<proto::PeerId>(bytes)?)
     }
 
     fn encode(&self, peer_id: &PeerId) -> Result<Bytes, Self::Error> {
@@ -202,7 +241,7 @@ impl Codec<sync::Status<MockContext>> for ProtobufCodec {
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<sync::Status<MockContext>, Self::Error> {
-        decode_sync_status(proto::sync::Status::decode(bytes)?)
+        decode_sync_status(self.decode_proto::<proto::sync::Status>(bytes)?)
     }
 
     fn encode(&self, status: &sync::Status<MockContext>) -> Result<Bytes, Self::Error> {
@@ -267,7 +306,7 @@ impl Codec<sync::Request<MockContext>> for ProtobufCodec {
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<sync::Request<MockContext>, Self::Error> {
-        decode_sync_request(proto::sync::SyncRequest::decode(bytes)?)
+        decode_sync_request(self.decode_proto::<proto::sync::SyncRequest>(bytes)?)
     }
 
     fn encode(&self, request: &sync::Request<MockContext>) -> Result<Bytes, Self::Error> {
@@ -345,7 +384,7 @@ impl Codec<sync::Response<MockContext>> for ProtobufCodec {
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<sync::Response<MockContext>, Self::Error> {
-        decode_sync_response(proto::sync::SyncResponse::decode(bytes)?)
+        decode_sync_response(self.decode_proto::<proto::sync::SyncResponse>(bytes)?)
     }
 
     fn encode(&self, response: &sync::Response<MockContext>) -> Result<Bytes, Self::Error> {
@@ -396,7 +435,13 @@ impl Codec<SignedConsensusMsg<MockContext>> for ProtobufCodec {
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<SignedConsensusMsg<MockContext>, Self::Error> {
-        decode_consensus_message(proto::ConsensusMessage::decode(bytes)?)
+        let proto = self.decode_proto::<proto::ConsensusMessage>(bytes)?;
+
+        if let Some(signature) = &proto.signature {
+            <p2p::Signature as Protobuf>::check_limits(signature, &self.limits)?;
+        }
+
+        decode_consensus_message(proto)
     }
 
     fn encode(&self, msg: &SignedConsensusMsg<MockContext>) -> Result<Bytes, Self::Error> {
@@ -411,14 +456,14 @@ where
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<StreamMessage<T>, Self::Error> {
-        let p2p_msg = p2p::StreamMessage::from_bytes(&bytes)?;
+        let p2p_msg = p2p::StreamMessage::from_bytes_with_limits(&bytes, &self.limits)?;
 
         Ok(StreamMessage {
             stream_id: p2p_msg.id,
             sequence: p2p_msg.sequence,
             content: match p2p_msg.content {
                 p2p::StreamContent::Data(data) => {
-                    StreamContent::Data(T::from_bytes(data.as_ref())?)
+                    StreamContent::Data(T::from_bytes_with_limits(&data, &self.limits)?)
                 }
                 p2p::StreamContent::Fin(fin) => StreamContent::Fin(fin),
             },
@@ -498,7 +543,7 @@ impl Codec<AggregatedSignature<MockContext>> for ProtobufCodec {
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<AggregatedSignature<MockContext>, Self::Error> {
-        decode_aggregated_signature(proto::sync::AggregatedSignature::decode(bytes)?)
+        decode_aggregated_signature(self.decode_proto::<proto::sync::AggregatedSignature>(bytes)?)
     }
 
     fn encode(&self, msg: &AggregatedSignature<MockContext>) -> Result<Bytes, Self::Error> {
@@ -553,9 +598,7 @@ impl Codec<CommitCertificate<MockContext>> for ProtobufCodec {
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<CommitCertificate<MockContext>, Self::Error> {
-        decode_certificate(
-            proto::sync::CommitCertificate::decode(bytes).map_err(ProtoError::Decode)?,
-        )
+        decode_certificate(self.decode_proto::<proto::sync::CommitCertificate>(bytes)?)
     }
 
     fn encode(&self, msg: &CommitCertificate<MockContext>) -> Result<Bytes, Self::Error> {
@@ -591,7 +634,7 @@ impl Codec<sync::DecidedValue<MockContext>> for ProtobufCodec {
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<sync::DecidedValue<MockContext>, Self::Error> {
-        let proto = proto::sync::SyncedValue::decode(bytes).map_err(ProtoError::Decode)?;
+        let proto = self.decode_proto::<proto::sync::SyncedValue>(bytes)?;
         decode_synced_value(proto)
     }
 
@@ -642,3 +685,150 @@ pub(crate) fn decode_vote(msg: ConsensusMessage) -> Option<SignedVote<MockContex
     let vote = Vote::from_proto(vote).ok()?;
     Some(SignedVote::new(vote, signature))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use malachitebft_core_types::NilOrVal;
+
+    use crate::types::{Felt, Hash, PrivateKey, Transaction, Transactions};
+
+    fn limits() -> DecodeLimits {
+        DecodeLimits {
+            max_message_size: 4096,
+            max_signature_bytes: 64,
+            max_transactions_per_batch: 4,
+            max_transaction_size: 128,
+            max_parts_per_proposal: 8,
+        }
+    }
+
+    fn assert_limit_exceeded<T: core::fmt::Debug>(result: Result<T, ProtoError>, name: &str) {
+        match result {
+            Err(ProtoError::LimitExceeded { field, .. }) => assert_eq!(field, name),
+            other => panic!("expected `{name}` limit to be exceeded, got {other:?}"),
+        }
+    }
+
+    fn signed_vote() -> SignedVote<MockContext> {
+        let private_key = PrivateKey::generate(rand::thread_rng());
+        let vote = Vote::new_prevote(
+            Height::new(1, 1),
+            Round::new(0),
+            NilOrVal::Val(Hash::new([1; 32])),
+            Address::new([2; 32]),
+        );
+
+        SignedVote::new(vote, private_key.sign(&Felt::ONE))
+    }
+
+    fn transactions(count: usize, size: usize) -> ProposalPart {
+        let txes = (0..count)
+            .map(|i| Transaction::new(vec![i as u8; size]))
+            .collect();
+
+        ProposalPart::Transactions(Transactions::new(txes))
+    }
+
+    #[test]
+    fn decode_within_limits() {
+        let codec = ProtobufCodec::new(limits());
+
+        let vote = SignedConsensusMsg::Vote(signed_vote());
+        let bytes = codec.encode(&vote).unwrap();
+        let decoded: SignedConsensusMsg<MockContext> = codec.decode(bytes).unwrap();
+        assert_eq!(decoded, vote);
+
+        let part = transactions(4, 128);
+        let bytes = codec.encode(&part).unwrap();
+        assert_eq!(Codec::<ProposalPart>::decode(&codec, bytes).unwrap(), part);
+    }
+
+    #[test]
+    fn reject_oversized_message() {
+        let codec = ProtobufCodec::new(DecodeLimits {
+            max_message_size: 64,
+            ..limits()
+        });
+
+        let bytes = ProtobufCodec::default()
+            .encode(&transactions(1, 128))
+            .unwrap();
+
+        assert_limit_exceeded(
+            Codec::<ProposalPart>::decode(&codec, bytes.clone()),
+            "message",
+        );
+        assert_limit_exceeded(
+            Codec::<SignedConsensusMsg<MockContext>>::decode(&codec, bytes),
+            "message",
+        );
+    }
+
+    #[test]
+    fn reject_oversized_vote_signature() {
+        let codec = ProtobufCodec::new(limits());
+
+        let mut proto = encode_consensus_message(&SignedConsensusMsg::Vote(signed_vote())).unwrap();
+        let felt = proto::Felt252 {
+            elements: Bytes::from(vec![0xff; 1024]),
+        };
+        proto.signature.as_mut().unwrap().r = Some(felt);
+
+        let result: Result<SignedConsensusMsg<MockContext>, _> =
+            codec.decode(proto.encode_to_bytes());
+
+        assert_limit_exceeded(result, "signature");
+    }
+
+    #[test]
+    fn reject_too_many_transactions() {
+        let codec = ProtobufCodec::new(limits());
+        let bytes = codec.encode(&transactions(5, 1)).unwrap();
+
+        assert_limit_exceeded(Codec::<ProposalPart>::decode(&codec, bytes), "transactions");
+    }
+
+    #[test]
+    fn reject_oversized_transaction() {
+        let codec = ProtobufCodec::new(limits());
+        let bytes = codec.encode(&transactions(1, 129)).unwrap();
+
+        assert_limit_exceeded(Codec::<ProposalPart>::decode(&codec, bytes), "transaction");
+    }
+
+    #[test]
+    fn reject_proposal_part_beyond_max_parts() {
+        let codec = ProtobufCodec::new(limits());
+
+        let msg = |sequence| StreamMessage {
+            stream_id: 1,
+            sequence,
+            content: StreamContent::Data(transactions(1, 1)),
+        };
+
+        let bytes = codec.encode(&msg(7)).unwrap();
+        let result: Result<StreamMessage<ProposalPart>, _> = codec.decode(bytes);
+        assert!(result.is_ok());
+
+        let bytes = codec.encode(&msg(8)).unwrap();
+        let result: Result<StreamMessage<ProposalPart>, _> = codec.decode(bytes);
+        assert_limit_exceeded(result, "parts");
+    }
+
+    #[test]
+    fn reject_oversized_transaction_in_stream() {
+        let codec = ProtobufCodec::new(limits());
+
+        let msg = StreamMessage {
+            stream_id: 1,
+            sequence: 0,
+            content: StreamContent::Data(transactions(1, 256)),
+        };
+
+        let bytes = codec.encode(&msg).unwrap();
+        let result: Result<StreamMessage<ProposalPart>, _> = codec.decode(bytes);
+        assert_limit_exceeded(result, "transaction");
+    }
+}
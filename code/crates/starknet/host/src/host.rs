@@ -3,6 +3,7 @@ use std::collections::BTreeSet;
 use async_trait::async_trait;
 use tokio::sync::{mpsc, oneshot};
 use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 
 use malachitebft_core_types::{CommitCertificate, Round};
 
@@ -11,8 +12,10 @@ use crate::types::MockContext;
 pub mod proposal;
 pub mod starknet;
 pub mod state;
+pub mod tx_source;
 
-pub use starknet::{StarknetHost, StarknetParams};
+pub use starknet::{system_clock, Clock, StarknetHost, StarknetParams};
+pub use tx_source::{MempoolTxSource, TxSource, VecTxSource};
 
 #[async_trait]
 pub trait Host {
@@ -30,6 +33,8 @@ pub trait Host {
     /// Params:
     /// - deadline - When the Context must stop adding new TXs to the block.
     /// - height   - The height of the block being proposed.
+    /// - cancel   - Cancelled by consensus once it has left the propose step of the round,
+    ///              in which case the build must stop as soon as possible.
     ///
     /// Return
     /// - content    - A channel for sending the content of the proposal.
@@ -42,11 +47,30 @@ pub trait Host {
         height: Self::Height,
         round: Round,
         deadline: Instant,
+        cancel: CancellationToken,
     ) -> (
         mpsc::Receiver<Self::ProposalPart>,
         oneshot::Receiver<Self::BlockHash>,
     );
 
+    /// Cancel the in-flight build of a proposal, if any.
+    ///
+    /// This cancels the token given by consensus to [`Host::build_new_proposal`] for that
+    /// height and round, as consensus itself does once it has left the propose step of the round,
+    /// eg. so that the application can stop a build which it knows will not be proposed.
+    ///
+    /// Params:
+    /// - height - The height of the block being proposed.
+    /// - round  - The round for which the block is being proposed.
+    fn cancel(&self, height: Self::Height, round: Round);
+
+    /// The maximum number of parts that are buffered for a single proposal.
+    ///
+    /// Parts received past this limit are dropped and the proposal is not built,
+    /// so that a peer cannot make us buffer an unbounded amount of parts
+    /// before the metadata (ie. `Fin`) of the proposal arrives.
+    fn max_parts_per_value(&self) -> usize;
+
     /// Receive a proposal from a peer.
     ///
     /// Context must support receiving multiple valid proposals on the same (height, round). This
@@ -10,12 +10,14 @@ use rand::{RngCore, SeedableRng};
 use sha3::Digest;
 use tokio::sync::{mpsc, oneshot};
 use tokio::time::Instant;
-use tracing::{error, trace};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, trace};
 
 use malachitebft_core_types::Round;
+use malachitebft_metrics::Metrics;
 
-use crate::host::starknet::StarknetParams;
-use crate::mempool::{MempoolMsg, MempoolRef};
+use crate::host::starknet::{Clock, StarknetParams};
+use crate::host::TxSource;
 use crate::types::*;
 
 pub async fn build_proposal_task(
@@ -25,7 +27,10 @@ pub async fn build_proposal_task(
     private_key: PrivateKey,
     params: StarknetParams,
     deadline: Instant,
-    mempool: MempoolRef,
+    tx_source: Arc<dyn TxSource>,
+    clock: Clock,
+    metrics: Metrics,
+    cancel: CancellationToken,
     tx_part: mpsc::Sender<ProposalPart>,
     tx_block_hash: oneshot::Sender<BlockHash>,
 ) {
@@ -36,7 +41,10 @@ pub async fn build_proposal_task(
         private_key,
         params,
         deadline,
-        mempool,
+        tx_source,
+        clock,
+        metrics,
+        cancel,
         tx_part,
         tx_block_hash,
     )
@@ -53,11 +61,14 @@ async fn run_build_proposal_task(
     private_key: PrivateKey,
     params: StarknetParams,
     deadline: Instant,
-    mempool: MempoolRef,
+    tx_source: Arc<dyn TxSource>,
+    clock: Clock,
+    metrics: Metrics,
+    cancel: CancellationToken,
     tx_part: mpsc::Sender<ProposalPart>,
     tx_block_hash: oneshot::Sender<BlockHash>,
 ) -> Result<(), Box<dyn core::error::Error>> {
-    let start = Instant::now();
+    let start = clock();
     let build_duration = (deadline - start).mul_f32(params.time_allowance_factor);
 
     let mut sequence = 0;
@@ -82,21 +93,40 @@ async fn run_build_proposal_task(
     };
 
     loop {
+        if cancel.is_cancelled() {
+            debug!(%height, %round, "Proposal build was cancelled");
+            return Ok(());
+        }
+
         trace!(%height, %round, %sequence, "Building local value");
 
-        let reaped_txes = mempool
-            .call(
-                |reply| MempoolMsg::Reap {
-                    height: height.as_u64(),
-                    num_txes: params.txs_per_part,
-                    reply,
-                },
-                Some(build_duration),
-            )
-            .await?
-            .success_or(eyre!("Failed to reap transactions from the mempool"))?;
+        let next_batch = tokio::time::timeout(
+            build_duration,
+            tx_source.next_batch(height, params.txs_per_part),
+        );
 
-        trace!("Reaped {} transactions from the mempool", reaped_txes.len());
+        // Do not wait for transactions until the deadline if the build is cancelled in the meantime
+        let result = tokio::select! {
+            result = next_batch => result,
+            _ = cancel.cancelled() => {
+                debug!(%height, %round, "Proposal build was cancelled");
+                return Ok(());
+            }
+        };
+
+        let reaped_txes = match result {
+            Ok(reaped_txes) => reaped_txes,
+            Err(_) if params.create_empty_blocks => {
+                debug!(%height, %round, "Timed out while waiting for transactions, finishing the block");
+                break;
+            }
+            Err(_) => return Err(eyre!("Timed out while waiting for transactions").into()),
+        };
+
+        trace!(
+            "Reaped {} transactions from the tx source",
+            reaped_txes.len()
+        );
 
         if reaped_txes.is_empty() {
             break;
@@ -104,12 +134,15 @@ async fn run_build_proposal_task(
 
         let max_block_size = params.max_block_size.as_u64() as usize;
 
+        metrics.reaped_txes.inc_by(reaped_txes.len() as u64);
+
         let mut txes = Vec::new();
         let mut tx_count = 0;
 
         for tx in reaped_txes {
             if block_size + tx.size_bytes() > max_block_size {
                 max_block_size_reached = true;
+                metrics.excluded_txes.inc();
                 continue;
             }
 
@@ -119,20 +152,35 @@ async fn run_build_proposal_task(
             txes.push(tx);
         }
 
+        // Once the block is full, no other transaction fits in it,
+        // so finish the block right away instead of reaping more transactions.
+        if block_size >= max_block_size {
+            max_block_size_reached = true;
+        }
+
         block_tx_count += tx_count;
+        metrics.included_txes.inc_by(tx_count as u64);
 
         let exec_time = params.exec_time_per_tx * tx_count as u32;
-        tokio::time::sleep(exec_time).await;
+
+        // Dropping the senders without sending the block hash signals the cancellation
+        tokio::select! {
+            _ = tokio::time::sleep(exec_time) => {}
+            _ = cancel.cancelled() => {
+                debug!(%height, %round, "Proposal build was cancelled");
+                return Ok(());
+            }
+        }
 
         trace!(
             %sequence,
             "Created a tx batch with {tx_count} tx-es of size {} in {:?}",
             ByteSize::b(block_size as u64),
-            start.elapsed()
+            clock() - start
         );
 
         // Transactions
-        {
+        if !txes.is_empty() {
             let part = ProposalPart::Transactions(Transactions::new(txes));
 
             block_hasher.update(part.to_sign_bytes());
@@ -143,16 +191,32 @@ async fn run_build_proposal_task(
         if max_block_size_reached {
             trace!("Max block size reached, stopping tx generation");
             break;
-        } else if start.elapsed() > build_duration {
+        } else if clock() - start > build_duration {
             trace!("Time allowance exceeded, stopping tx generation");
             break;
         }
     }
 
-    // BlockProof
-    {
+    if block_tx_count == 0 {
+        if !params.create_empty_blocks {
+            return Err(eyre!("No transactions available, not proposing an empty block").into());
+        }
+
+        debug!(%height, %round, "No transactions available, proposing an empty block");
+
+        // An empty block always consists of the same parts, and therefore has the same hash
+        for part in empty_block_parts() {
+            block_hasher.update(part.to_sign_bytes());
+            tx_part.send(part).await?;
+            sequence += 1;
+        }
+    } else {
+        // BlockProof
         // TODO: Compute actual "proof"
-        let mut rng = StdRng::from_entropy();
+        let mut rng = match params.seed {
+            Some(seed) => StdRng::seed_from_u64(block_seed(seed, height, round)),
+            None => StdRng::from_entropy(),
+        };
         let mut proof = vec![0; 32];
         rng.fill_bytes(&mut proof);
 
@@ -177,11 +241,15 @@ async fn run_build_proposal_task(
     // Close the channel to signal no more parts to come
     drop(tx_part);
 
+    let build_time = clock() - start;
+    metrics.block_build_time.observe(build_time.as_secs_f64());
+    metrics.block_parts.observe(sequence as f64);
+
     let block_size = ByteSize::b(block_size as u64);
 
     trace!(
         tx_count = %block_tx_count, size = %block_size, hash = %block_hash, parts = %sequence,
-        "Built block in {:?}", start.elapsed()
+        "Built block in {build_time:?}"
     );
 
     tx_block_hash
@@ -191,6 +259,31 @@ async fn run_build_proposal_task(
     Ok(())
 }
 
+/// The parts of an empty block, in between the `Init` and `Fin` parts:
+/// a batch without any transaction, followed by an empty proof.
+fn empty_block_parts() -> [ProposalPart; 2] {
+    [
+        ProposalPart::Transactions(Transactions::new(vec![])),
+        ProposalPart::BlockProof(BlockProof::new(vec![])),
+    ]
+}
+
+/// The hash of an empty block, regardless of its height and round.
+pub fn empty_block_hash() -> BlockHash {
+    let mut block_hasher = sha3::Keccak256::new();
+    for part in empty_block_parts() {
+        block_hasher.update(part.to_sign_bytes());
+    }
+    BlockHash::new(block_hasher.finalize().into())
+}
+
+/// Derive the seed for building the block at the given height and round.
+fn block_seed(seed: u64, height: Height, round: Round) -> u64 {
+    seed ^ height.block_number.rotate_left(32)
+        ^ height.fork_id.rotate_left(48)
+        ^ round.as_i64() as u64
+}
+
 pub async fn repropose_task(
     block_hash: Hash,
     tx_part: mpsc::Sender<ProposalPart>,
@@ -238,5 +331,336 @@ pub fn compute_proposal_signature(
     private_key: &PrivateKey,
 ) -> Signature {
     let hash = compute_proposal_hash(init, block_hash);
-    private_key.sign(&hash.as_felt())
+    private_key.sign(&hash.as_felt_reduced())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use malachitebft_config::VoteExtensionsConfig;
+    use malachitebft_core_consensus::ValuePayload;
+    use malachitebft_engine::util::value_builds::ValueBuilds;
+
+    use super::*;
+    use crate::host::starknet::system_clock;
+    use crate::host::{Host, StarknetHost, VecTxSource};
+
+    const TX_SIZE: usize = 100;
+
+    fn params(max_block_size: ByteSize, txs_per_part: usize) -> StarknetParams {
+        StarknetParams {
+            max_block_size,
+            value_payload: ValuePayload::PartsOnly,
+            tx_size: ByteSize::b(TX_SIZE as u64),
+            txs_per_part,
+            time_allowance_factor: 0.5,
+            exec_time_per_tx: Duration::ZERO,
+            max_retain_blocks: 10,
+            vote_extensions: VoteExtensionsConfig::default(),
+            seed: Some(0),
+            max_parts_per_value: 10_000,
+            report_build_progress: false,
+            start_height_delay: Duration::ZERO,
+            create_empty_blocks: true,
+            reuse_late_values: false,
+        }
+    }
+
+    async fn build(
+        params: StarknetParams,
+        tx_source: Arc<VecTxSource>,
+        metrics: Metrics,
+    ) -> Vec<ProposalPart> {
+        let (parts, _) = build_with_clock(params, tx_source, system_clock(), metrics).await;
+        parts
+    }
+
+    async fn build_with_clock(
+        params: StarknetParams,
+        tx_source: Arc<VecTxSource>,
+        clock: Clock,
+        metrics: Metrics,
+    ) -> (Vec<ProposalPart>, BlockHash) {
+        let private_key = PrivateKey::generate(StdRng::seed_from_u64(0));
+        let proposer = Address::from_public_key(private_key.public_key());
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        let (tx_part, mut rx_part) = mpsc::channel(16);
+        let (tx_block_hash, rx_block_hash) = oneshot::channel();
+
+        build_proposal_task(
+            Height::new(1, 1),
+            Round::new(0),
+            proposer,
+            private_key,
+            params,
+            deadline,
+            tx_source,
+            clock,
+            metrics,
+            CancellationToken::new(),
+            tx_part,
+            tx_block_hash,
+        )
+        .await;
+
+        let block_hash = rx_block_hash.await.expect("block was built");
+
+        let mut parts = Vec::new();
+        while let Some(part) = rx_part.recv().await {
+            parts.push(part);
+        }
+        (parts, block_hash)
+    }
+
+    #[tokio::test]
+    async fn same_seed_and_clock_build_identical_blocks() {
+        let txes: Vec<_> = (0..10)
+            .map(|i| Transaction::new(vec![i as u8; TX_SIZE]))
+            .collect();
+
+        // A clock which never moves, so that the build never runs out of time
+        let now = Instant::now();
+        let clock: Clock = Arc::new(move || now);
+
+        let params = params(ByteSize::kib(100), 3);
+
+        let (parts1, hash1) = build_with_clock(
+            params,
+            Arc::new(VecTxSource::new(txes.clone())),
+            clock.clone(),
+            Metrics::new(),
+        )
+        .await;
+
+        let (parts2, hash2) = build_with_clock(
+            params,
+            Arc::new(VecTxSource::new(txes.clone())),
+            clock.clone(),
+            Metrics::new(),
+        )
+        .await;
+
+        assert_eq!(parts1, parts2);
+        assert_eq!(hash1, hash2);
+
+        // The block proof is derived from the seed, so another seed builds another block
+        let (parts3, hash3) = build_with_clock(
+            StarknetParams {
+                seed: Some(1),
+                ..params
+            },
+            Arc::new(VecTxSource::new(txes)),
+            clock,
+            Metrics::new(),
+        )
+        .await;
+
+        assert_ne!(parts1, parts3);
+        assert_ne!(hash1, hash3);
+    }
+
+    #[tokio::test]
+    async fn stops_reaping_once_the_block_is_full() {
+        let txes = (0..100).map(|i| Transaction::new(vec![i as u8; TX_SIZE]));
+        let tx_source = Arc::new(VecTxSource::new(txes.collect()));
+
+        // Room for exactly 4 transactions, reaped 2 at a time
+        let params = params(ByteSize::b(4 * TX_SIZE as u64), 2);
+
+        let start = Instant::now();
+        let parts = build(params, tx_source.clone(), Metrics::new()).await;
+
+        let tx_counts: Vec<_> = parts
+            .iter()
+            .filter_map(|part| part.as_transactions())
+            .map(|txes| txes.len())
+            .collect();
+
+        assert_eq!(tx_counts, vec![2, 2]);
+        assert!(parts.first().unwrap().as_init().is_some());
+        assert!(parts.last().unwrap().as_fin().is_some());
+
+        // No more transactions than the ones in the block were reaped from the source,
+        // and the block was finished well before the deadline
+        assert_eq!(tx_source.len(), 96);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn counts_the_transactions_which_do_not_fit() {
+        let txes = (0..100).map(|i| Transaction::new(vec![i as u8; TX_SIZE]));
+        let tx_source = Arc::new(VecTxSource::new(txes.collect()));
+
+        // Room for 3.5 transactions, reaped 2 at a time
+        let params = params(ByteSize::b(7 * TX_SIZE as u64 / 2), 2);
+
+        let metrics = Metrics::new();
+        let parts = build(params, tx_source.clone(), metrics.clone()).await;
+
+        let tx_counts: Vec<_> = parts
+            .iter()
+            .filter_map(|part| part.as_transactions())
+            .map(|txes| txes.len())
+            .collect();
+
+        assert_eq!(tx_counts, vec![2, 1]);
+
+        // The fourth transaction exceeds the remaining budget and is left out of the block
+        assert_eq!(metrics.reaped_txes.get(), 4);
+        assert_eq!(metrics.included_txes.get(), 3);
+        assert_eq!(metrics.excluded_txes.get(), 1);
+    }
+
+    /// A source which never hands out any transaction
+    struct PendingTxSource;
+
+    #[async_trait::async_trait]
+    impl TxSource for PendingTxSource {
+        async fn next_batch(&self, _height: Height, _max: usize) -> Vec<Transaction> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelled_build_returns_before_its_deadline() {
+        let params = params(ByteSize::kib(100), 1);
+
+        let private_key = PrivateKey::generate(StdRng::seed_from_u64(0));
+        let proposer = Address::from_public_key(private_key.public_key());
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        let cancel = CancellationToken::new();
+
+        let (tx_part, mut rx_part) = mpsc::channel(16);
+        let (tx_block_hash, rx_block_hash) = oneshot::channel();
+
+        let start = Instant::now();
+
+        let task = tokio::spawn(build_proposal_task(
+            Height::new(1, 1),
+            Round::new(0),
+            proposer,
+            private_key,
+            params,
+            deadline,
+            Arc::new(PendingTxSource),
+            system_clock(),
+            Metrics::new(),
+            cancel.clone(),
+            tx_part,
+            tx_block_hash,
+        ));
+
+        // The build is waiting for transactions
+        assert!(rx_part.recv().await.unwrap().as_init().is_some());
+
+        cancel.cancel();
+
+        tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .expect("build returned early")
+            .unwrap();
+
+        assert!(rx_part.recv().await.is_none());
+        assert!(rx_block_hash.await.is_err());
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn stops_building_once_consensus_skips_the_round() {
+        let txes = (0..100).map(|i| Transaction::new(vec![i as u8; TX_SIZE]));
+        let tx_source = Arc::new(VecTxSource::new(txes.collect()));
+
+        // One transaction per part, so that the build takes many iterations
+        let params = params(ByteSize::kib(100), 1);
+
+        let private_key = PrivateKey::generate(StdRng::seed_from_u64(0));
+        let proposer = Address::from_public_key(private_key.public_key());
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        let height = Height::new(1, 1);
+
+        // Consensus starts building a value for round 0
+        let mut builds = ValueBuilds::default();
+        let cancel = builds.start(height, Round::new(0));
+
+        let (tx_part, mut rx_part) = mpsc::channel(1);
+        let (tx_block_hash, rx_block_hash) = oneshot::channel();
+
+        let task = tokio::spawn(build_proposal_task(
+            height,
+            Round::new(0),
+            proposer,
+            private_key,
+            params,
+            deadline,
+            tx_source.clone(),
+            system_clock(),
+            Metrics::new(),
+            cancel.child_token(),
+            tx_part,
+            tx_block_hash,
+        ));
+
+        assert!(rx_part.recv().await.unwrap().as_init().is_some());
+        assert!(rx_part.recv().await.unwrap().as_transactions().is_some());
+
+        // Consensus skips to round 1 in the middle of the build
+        builds.cancel_stale(height, Round::new(1), true);
+        assert!(cancel.is_cancelled());
+
+        // At most the parts already handed over before the cancellation are received
+        let mut parts = Vec::new();
+        while let Some(part) = rx_part.recv().await {
+            parts.push(part);
+        }
+
+        task.await.unwrap();
+
+        assert!(parts.len() <= 2);
+        assert!(parts.iter().all(|part| part.as_fin().is_none()));
+
+        // No value is built, hence none is proposed
+        assert!(rx_block_hash.await.is_err());
+        assert!(tx_source.len() >= 100 - 4);
+    }
+
+    #[tokio::test]
+    async fn host_cancels_the_build_through_the_token_of_consensus() {
+        let private_key = PrivateKey::generate(StdRng::seed_from_u64(0));
+        let address = Address::from_public_key(private_key.public_key());
+
+        let host = StarknetHost::new(
+            params(ByteSize::kib(100), 1),
+            Arc::new(PendingTxSource),
+            address,
+            private_key,
+            ValidatorSet::new(Vec::new()),
+        );
+
+        let height = Height::new(1, 1);
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        let mut builds = ValueBuilds::default();
+        let cancel = builds.start(height, Round::new(0));
+
+        let (mut rx_part, rx_block_hash) = host
+            .build_new_proposal(height, Round::new(0), deadline, cancel.clone())
+            .await;
+
+        // The build is waiting for transactions
+        assert!(rx_part.recv().await.unwrap().as_init().is_some());
+
+        // Cancelling the build of another round leaves this one alone
+        host.cancel(height, Round::new(1));
+        assert!(!cancel.is_cancelled());
+
+        host.cancel(height, Round::new(0));
+        assert!(cancel.is_cancelled());
+
+        assert!(rx_part.recv().await.is_none());
+        assert!(rx_block_hash.await.is_err());
+    }
 }
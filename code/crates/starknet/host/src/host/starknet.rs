@@ -1,23 +1,35 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use async_trait::async_trait;
 use bytesize::ByteSize;
 use tokio::sync::{mpsc, oneshot};
 use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, Instrument};
 
 use malachitebft_config::VoteExtensionsConfig;
 use malachitebft_core_consensus::ValuePayload;
 use malachitebft_core_types::{CommitCertificate, Extension, Round, SignedExtension, SignedVote};
+use malachitebft_metrics::Metrics;
 
-use crate::host::Host;
-use crate::mempool::MempoolRef;
+use crate::host::{Host, TxSource};
 use crate::part_store::PartStore;
 use crate::types::*;
 
 use super::proposal::{build_proposal_task, repropose_task};
 
+/// A source of time for building proposals.
+///
+/// Can be replaced with a deterministic clock to make block building reproducible.
+pub type Clock = Arc<dyn Fn() -> Instant + Send + Sync>;
+
+/// A clock which reads the current time from the system.
+pub fn system_clock() -> Clock {
+    Arc::new(Instant::now)
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct StarknetParams {
     pub max_block_size: ByteSize,
@@ -28,35 +40,60 @@ pub struct StarknetParams {
     pub exec_time_per_tx: Duration,
     pub max_retain_blocks: usize,
     pub vote_extensions: VoteExtensionsConfig,
+    pub seed: Option<u64>,
+    pub max_parts_per_value: usize,
+    pub report_build_progress: bool,
+    pub start_height_delay: Duration,
+    pub create_empty_blocks: bool,
+    pub reuse_late_values: bool,
 }
 
 pub struct StarknetHost {
     pub params: StarknetParams,
-    pub mempool: MempoolRef,
+    pub tx_source: Arc<dyn TxSource>,
     pub address: Address,
     pub private_key: PrivateKey,
     pub validator_set: ValidatorSet,
     pub part_store: PartStore<MockContext>,
+    pub clock: Clock,
+    pub metrics: Metrics,
+    builds: Mutex<BTreeMap<(Height, Round), CancellationToken>>,
 }
 
 impl StarknetHost {
     pub fn new(
         params: StarknetParams,
-        mempool: MempoolRef,
+        tx_source: Arc<dyn TxSource>,
         address: Address,
         private_key: PrivateKey,
         validator_set: ValidatorSet,
     ) -> Self {
         Self {
             params,
-            mempool,
+            tx_source,
             address,
             private_key,
             validator_set,
-            part_store: Default::default(),
+            part_store: PartStore::with_max_parts_per_value(params.max_parts_per_value),
+            clock: system_clock(),
+            metrics: Metrics::new(),
+            builds: Default::default(),
         }
     }
 
+    pub fn with_clock(self, clock: Clock) -> Self {
+        Self { clock, ..self }
+    }
+
+    pub fn with_metrics(self, metrics: Metrics) -> Self {
+        Self { metrics, ..self }
+    }
+
+    /// Return the current time, as given by the clock of this host.
+    pub fn now(&self) -> Instant {
+        (self.clock)()
+    }
+
     pub fn generate_vote_extension(
         &self,
         _height: Height,
@@ -76,8 +113,11 @@ impl StarknetHost {
         rand::thread_rng().fill_bytes(&mut bytes);
 
         let hash = Hash::new(sha3::Keccak256::digest(&bytes).into());
+
+        let hash = hash.as_felt_reduced();
+
         let extension = Extension::from(bytes);
-        let signature = self.private_key.sign(&hash.as_felt());
+        let signature = self.private_key.sign(&hash);
 
         Some(SignedExtension::new(extension, signature))
     }
@@ -100,6 +140,7 @@ impl Host for StarknetHost {
         height: Self::Height,
         round: Round,
         deadline: Instant,
+        cancel: CancellationToken,
     ) -> (
         mpsc::Receiver<Self::ProposalPart>,
         oneshot::Receiver<Self::BlockHash>,
@@ -107,6 +148,14 @@ impl Host for StarknetHost {
         let (tx_part, rx_content) = mpsc::channel(self.params.txs_per_part);
         let (tx_block_hash, rx_block_hash) = oneshot::channel();
 
+        // Keep the token of consensus around so that the build can be cancelled through the host,
+        // forgetting about the builds which are already cancelled or for a previous height
+        {
+            let mut builds = self.builds.lock().unwrap();
+            builds.retain(|(h, _), token| *h >= height && !token.is_cancelled());
+            builds.insert((height, round), cancel.clone());
+        }
+
         tokio::spawn(
             build_proposal_task(
                 height,
@@ -115,7 +164,10 @@ impl Host for StarknetHost {
                 self.private_key,
                 self.params,
                 deadline,
-                self.mempool.clone(),
+                self.tx_source.clone(),
+                self.clock.clone(),
+                self.metrics.clone(),
+                cancel,
                 tx_part,
                 tx_block_hash,
             )
@@ -125,6 +177,17 @@ impl Host for StarknetHost {
         (rx_content, rx_block_hash)
     }
 
+    fn cancel(&self, height: Self::Height, round: Round) {
+        if let Some(token) = self.builds.lock().unwrap().remove(&(height, round)) {
+            debug!(%height, %round, "Cancelling proposal build");
+            token.cancel();
+        }
+    }
+
+    fn max_parts_per_value(&self) -> usize {
+        self.params.max_parts_per_value
+    }
+
     /// Receive a proposal from a peer.
     ///
     /// Context must support receiving multiple valid proposals on the same (height, round). This
@@ -180,7 +243,7 @@ impl Host for StarknetHost {
 
     /// Sign a message hash
     async fn sign(&self, message: Self::MessageHash) -> Self::Signature {
-        self.private_key.sign(&message.as_felt())
+        self.private_key.sign(&message.as_felt_reduced())
     }
 
     /// Validates the signature field of a message. If None returns false.
@@ -190,7 +253,7 @@ impl Host for StarknetHost {
         signature: &Self::Signature,
         public_key: &Self::PublicKey,
     ) -> bool {
-        public_key.verify(&hash.as_felt(), signature)
+        public_key.verify(&hash.as_felt_reduced(), signature)
     }
 
     /// Update the Context about which decision has been made. It is responsible for pinging any
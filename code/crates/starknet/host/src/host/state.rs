@@ -3,17 +3,18 @@ use std::sync::Arc;
 
 use rand::RngCore;
 use sha3::Digest;
-use tracing::{debug, error, trace};
+use tracing::{debug, error, trace, warn};
 
 use malachitebft_core_types::{Round, SignedExtension, Validity};
 use malachitebft_engine::consensus::ConsensusRef;
 use malachitebft_engine::host::ProposedValue;
-use malachitebft_engine::util::streaming::StreamId;
+use malachitebft_engine::util::streaming::{Sequence, StreamId};
 
 use crate::block_store::BlockStore;
 use crate::host::proposal::compute_proposal_hash;
 use crate::host::{Host, StarknetHost};
-use crate::streaming::PartStreamsMap;
+use crate::streaming::metrics::Metrics as StreamingMetrics;
+use crate::streaming::{PartStreamsMap, ReceiveWindow};
 use crate::types::*;
 
 pub struct HostState {
@@ -28,7 +29,12 @@ pub struct HostState {
 }
 
 impl HostState {
-    pub fn new<R>(host: StarknetHost, db_path: impl AsRef<Path>, rng: &mut R) -> Self
+    pub fn new<R>(
+        host: StarknetHost,
+        db_path: impl AsRef<Path>,
+        streaming_metrics: StreamingMetrics,
+        rng: &mut R,
+    ) -> Self
     where
         R: RngCore,
     {
@@ -39,7 +45,7 @@ impl HostState {
             host,
             consensus: None,
             block_store: BlockStore::new(db_path).unwrap(),
-            part_streams_map: PartStreamsMap::default(),
+            part_streams_map: PartStreamsMap::new(ReceiveWindow::unbounded(), streaming_metrics),
             next_stream_id: rng.next_u64(),
         }
     }
@@ -92,6 +98,15 @@ impl HostState {
             return None;
         }
 
+        if parts.len() > self.host.max_parts_per_value() {
+            error!(
+                parts.len = %parts.len(),
+                max = %self.host.max_parts_per_value(),
+                "Too many proposal parts, refusing to build the proposal"
+            );
+            return None;
+        }
+
         let Some(init) = parts.iter().find_map(|part| part.as_init()) else {
             error!("No Init part found in the proposal parts");
             return None;
@@ -156,22 +171,35 @@ impl HostState {
             return None;
         };
 
-        let valid = public_key.verify(&proposal_hash.as_felt(), signature);
+        let valid = public_key.verify(&proposal_hash.as_felt_reduced(), signature);
+
         Some(Validity::from_bool(valid))
     }
 
     #[tracing::instrument(skip_all, fields(
         part.height = %height,
         part.round = %round,
+        part.sequence = %sequence,
         part.message = ?part.part_type(),
     ))]
     pub async fn build_value_from_part(
         &mut self,
         height: Height,
         round: Round,
+        sequence: Sequence,
         part: ProposalPart,
     ) -> Option<ProposedValue<MockContext>> {
-        self.host.part_store.store(height, round, part.clone());
+        if !self
+            .host
+            .part_store
+            .store(height, round, sequence, part.clone())
+        {
+            warn!(
+                max = %self.host.max_parts_per_value(),
+                "Duplicate part or too many parts buffered for this proposal, dropping part"
+            );
+            return None;
+        }
 
         if let ProposalPart::Transactions(_txes) = &part {
             debug!("Simulating tx execution and proof verification");
@@ -192,12 +220,11 @@ impl HostState {
             "Blocks for which we have parts"
         );
 
-        // TODO: Do more validations, e.g. there is no higher tx proposal part,
-        //       check that we have received the proof, etc.
-        let Some(_fin) = parts.iter().find_map(|part| part.as_fin()) else {
-            debug!("Final proposal part has not been received yet");
+        // TODO: Do more validations, e.g. check that we have received the proof, etc.
+        if !self.host.part_store.is_complete(height, round) {
+            debug!("Not all proposal parts have been received yet");
             return None;
-        };
+        }
 
         let block_size: usize = parts.iter().map(|p| p.size_bytes()).sum();
         let tx_count: usize = parts.iter().map(|p| p.tx_count()).sum();
@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use ractor::rpc::CallResult;
+use tracing::error;
+
+use crate::mempool::{MempoolMsg, SharedMempoolRef};
+use crate::types::*;
+
+/// A source of transactions to include in the blocks we propose.
+#[async_trait]
+pub trait TxSource: Send + Sync {
+    /// Return the next batch of at most `max` transactions to include in the block at the given height.
+    ///
+    /// An empty batch signals that no more transactions are available for this block.
+    async fn next_batch(&self, height: Height, max: usize) -> Vec<Transaction>;
+}
+
+/// A source of transactions backed by the mempool actor.
+pub struct MempoolTxSource {
+    mempool: SharedMempoolRef,
+}
+
+impl MempoolTxSource {
+    pub fn new(mempool: SharedMempoolRef) -> Self {
+        Self { mempool }
+    }
+}
+
+#[async_trait]
+impl TxSource for MempoolTxSource {
+    async fn next_batch(&self, height: Height, max: usize) -> Vec<Transaction> {
+        let result = self
+            .mempool
+            .get()
+            .call(
+                |reply| MempoolMsg::Reap {
+                    height: height.as_u64(),
+                    num_txes: max,
+                    reply,
+                },
+                None,
+            )
+            .await;
+
+        match result {
+            Ok(CallResult::Success(txes)) => txes,
+            Ok(_) => {
+                error!(%height, "Failed to reap transactions from the mempool");
+                Vec::new()
+            }
+            Err(e) => {
+                error!(%height, "Failed to reap transactions from the mempool: {e}");
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// An in-memory source of transactions, which hands out the given transactions in order
+/// regardless of the height of the block being built.
+#[derive(Default)]
+pub struct VecTxSource {
+    txes: Mutex<VecDeque<Transaction>>,
+}
+
+impl VecTxSource {
+    pub fn new(txes: Vec<Transaction>) -> Self {
+        Self {
+            txes: Mutex::new(txes.into()),
+        }
+    }
+
+    /// Append the given transactions to the ones yet to be handed out.
+    pub fn extend(&self, txes: impl IntoIterator<Item = Transaction>) {
+        self.txes.lock().unwrap().extend(txes);
+    }
+
+    /// The number of transactions yet to be handed out.
+    pub fn len(&self) -> usize {
+        self.txes.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl TxSource for VecTxSource {
+    async fn next_batch(&self, _height: Height, max: usize) -> Vec<Transaction> {
+        let mut txes = self.txes.lock().unwrap();
+        let count = max.min(txes.len());
+        txes.drain(..count).collect()
+    }
+}
@@ -1,9 +1,11 @@
-use std::collections::BTreeMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use ractor::{Actor, ActorProcessingErr, ActorRef, RpcReplyPort};
-use rand::RngCore;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
 use tracing::{debug, info, trace};
 
 use malachitebft_config::{MempoolConfig, TestConfig};
@@ -16,13 +18,76 @@ use crate::types::{Hash, Transaction, Transactions};
 pub mod network;
 use network::{MempoolNetworkMsg, MempoolNetworkRef};
 
+pub mod metrics;
+use metrics::Metrics;
+
 pub type MempoolMsg = Msg;
 pub type MempoolRef = ActorRef<Msg>;
 
+/// Re-validates a transaction left in the mempool after a decision,
+/// returns whether the transaction is still valid.
+pub type RecheckFn = Arc<dyn Fn(&Transaction) -> bool + Send + Sync>;
+
+/// Returns the transactions to load into the mempool when it starts,
+/// eg. the ones persisted by a previous instance of the mempool which failed.
+pub type HandoffFn = Arc<dyn Fn() -> Vec<Transaction> + Send + Sync>;
+
+/// A copy of the transactions pending in the mempool, which outlives the mempool actor,
+/// so that they can be handed over to a new mempool when the previous one failed.
+#[derive(Clone, Default)]
+pub struct SharedPendingTxs(Arc<RwLock<BTreeMap<Hash, Transaction>>>);
+
+impl SharedPendingTxs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The transactions pending in the mempool
+    pub fn get(&self) -> Vec<Transaction> {
+        self.0.read().unwrap().values().cloned().collect()
+    }
+
+    fn insert(&self, hash: Hash, tx: &Transaction) {
+        self.0.write().unwrap().insert(hash, tx.clone());
+    }
+
+    fn remove(&self, hash: &Hash) {
+        self.0.write().unwrap().remove(hash);
+    }
+
+    fn clear(&self) {
+        self.0.write().unwrap().clear();
+    }
+}
+
+/// A reference to the mempool actor which stays valid when the actor is restarted
+#[derive(Clone)]
+pub struct SharedMempoolRef(Arc<RwLock<MempoolRef>>);
+
+impl SharedMempoolRef {
+    pub fn new(mempool: MempoolRef) -> Self {
+        Self(Arc::new(RwLock::new(mempool)))
+    }
+
+    /// The mempool actor currently running
+    pub fn get(&self) -> MempoolRef {
+        self.0.read().unwrap().clone()
+    }
+
+    /// Replace the mempool actor with the given one, eg. after it was restarted
+    pub fn set(&self, mempool: MempoolRef) {
+        *self.0.write().unwrap() = mempool;
+    }
+}
+
 pub struct Mempool {
     network: MempoolNetworkRef,
     config: MempoolConfig,   // todo - pick only what's needed
     test_config: TestConfig, // todo - pick only the mempool related
+    recheck: Option<RecheckFn>,
+    handoff: Option<HandoffFn>,
+    shared: Option<SharedPendingTxs>,
+    metrics: Metrics,
     span: tracing::Span,
 }
 
@@ -34,8 +99,11 @@ pub enum Msg {
         num_txes: usize,
         reply: RpcReplyPort<Vec<Transaction>>,
     },
+    /// A block was decided at the given height,
+    /// the committed transactions must be removed from the mempool.
     Update {
-        tx_hashes: Vec<Hash>,
+        height: u64,
+        committed_txes: Vec<Hash>,
     },
 }
 
@@ -45,25 +113,220 @@ impl From<Arc<NetworkEvent>> for Msg {
     }
 }
 
+/// A transaction pending in the mempool
+pub struct PendingTx {
+    pub tx: Transaction,
+    /// When the transaction was added to the mempool
+    pub received_at: Instant,
+}
+
 #[allow(dead_code)]
 pub struct State {
-    pub transactions: BTreeMap<Hash, Transaction>,
+    pub transactions: BTreeMap<Hash, PendingTx>,
+    /// Pending transactions ordered by the time they were added to the mempool
+    by_age: BTreeSet<(Instant, Hash)>,
+    /// Total size of the pending transactions, in bytes
+    total_bytes: usize,
+    /// Transactions already handed out for the block at the given height
+    reaped: (u64, BTreeSet<Hash>),
+    rng: Option<(u64, StdRng)>,
+    /// Copy of the pending transactions kept outside of the mempool actor
+    shared: Option<SharedPendingTxs>,
 }
 
 impl State {
     pub fn new() -> Self {
         Self {
             transactions: BTreeMap::new(),
+            by_age: BTreeSet::new(),
+            total_bytes: 0,
+            reaped: (0, BTreeSet::new()),
+            rng: None,
+            shared: None,
         }
     }
 
-    pub fn add_tx(&mut self, tx: &Transaction) {
-        self.transactions.entry(tx.hash()).or_insert(tx.clone());
+    /// Keep a copy of the pending transactions in the given [`SharedPendingTxs`],
+    /// replacing the transactions it held.
+    pub fn with_shared(shared: SharedPendingTxs) -> Self {
+        shared.clear();
+
+        Self {
+            shared: Some(shared),
+            ..Self::new()
+        }
     }
 
-    pub fn remove_tx(&mut self, hash: &Hash) {
-        self.transactions.remove_entry(hash);
+    /// Return a generator seeded for the given height,
+    /// so that the transactions reaped at a height are the same across runs.
+    fn seeded_rng(&mut self, seed: u64, height: u64) -> &mut StdRng {
+        if !matches!(&self.rng, Some((h, _)) if *h == height) {
+            self.rng = Some((height, StdRng::seed_from_u64(seed ^ height)));
+        }
+
+        let (_, rng) = self.rng.as_mut().unwrap();
+        rng
     }
+
+    /// Total size of the pending transactions, in bytes
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    pub fn add_tx(&mut self, tx: &Transaction, now: Instant) {
+        let hash = tx.hash();
+
+        if self.transactions.contains_key(&hash) {
+            return;
+        }
+
+        self.by_age.insert((now, hash));
+        self.total_bytes += tx.size_bytes();
+
+        if let Some(shared) = &self.shared {
+            shared.insert(hash, tx);
+        }
+
+        self.transactions.insert(
+            hash,
+            PendingTx {
+                tx: tx.clone(),
+                received_at: now,
+            },
+        );
+    }
+
+    pub fn remove_tx(&mut self, hash: &Hash) -> Option<Transaction> {
+        let pending = self.transactions.remove(hash)?;
+
+        self.by_age.remove(&(pending.received_at, *hash));
+        self.total_bytes -= pending.tx.size_bytes();
+
+        if let Some(shared) = &self.shared {
+            shared.remove(hash);
+        }
+
+        Some(pending.tx)
+    }
+
+    /// Remove the oldest transaction, if any
+    fn remove_oldest_tx(&mut self) -> Option<Transaction> {
+        let (_, hash) = self.by_age.first().copied()?;
+        self.remove_tx(&hash)
+    }
+
+    /// Evict the oldest transactions until the pending transactions fit in `max_bytes`.
+    pub fn evict_to_fit(&mut self, max_bytes: usize) -> Removed {
+        let mut removed = Removed::default();
+
+        while self.total_bytes > max_bytes {
+            let Some(tx) = self.remove_oldest_tx() else {
+                break;
+            };
+
+            removed.add(&tx);
+        }
+
+        removed
+    }
+
+    /// Expire the transactions which were added to the mempool more than `ttl` ago.
+    pub fn expire(&mut self, ttl: Duration, now: Instant) -> Removed {
+        let mut removed = Removed::default();
+
+        while let Some(&(received_at, hash)) = self.by_age.first() {
+            if now.saturating_duration_since(received_at) <= ttl {
+                break;
+            }
+
+            if let Some(tx) = self.remove_tx(&hash) {
+                removed.add(&tx);
+            }
+        }
+
+        removed
+    }
+
+    /// Return at most `num_txes` of the transactions pending in the mempool
+    /// which were not handed out yet for the block at the given height.
+    ///
+    /// Transactions are kept in the mempool until they are committed,
+    /// so the ones which did not make it into a block are proposed again at the next height.
+    pub fn reap(&mut self, height: u64, num_txes: usize) -> Vec<Transaction> {
+        let (reaped_height, reaped) = &mut self.reaped;
+
+        if *reaped_height != height {
+            *reaped_height = height;
+            reaped.clear();
+        }
+
+        let txes: Vec<Transaction> = self
+            .transactions
+            .iter()
+            .filter(|(hash, _)| !reaped.contains(*hash))
+            .take(num_txes)
+            .map(|(_, pending)| pending.tx.clone())
+            .collect();
+
+        reaped.extend(txes.iter().map(|tx| tx.hash()));
+
+        txes
+    }
+
+    /// Remove the committed transactions from the mempool, as well as the remaining
+    /// transactions which are not valid anymore according to the given `recheck` function.
+    ///
+    /// Committed transactions which are not in the mempool, eg. because we only
+    /// received the block from a peer, are ignored.
+    pub fn update(&mut self, committed_txes: &[Hash], recheck: Option<&RecheckFn>) -> UpdateStats {
+        let mut evicted = committed_txes
+            .iter()
+            .filter(|hash| self.remove_tx(hash).is_some())
+            .count();
+
+        if let Some(recheck) = recheck {
+            let invalid: Vec<Hash> = self
+                .transactions
+                .iter()
+                .filter(|(_, pending)| !recheck(&pending.tx))
+                .map(|(hash, _)| *hash)
+                .collect();
+
+            for hash in &invalid {
+                self.remove_tx(hash);
+            }
+
+            evicted += invalid.len();
+        }
+
+        UpdateStats {
+            evicted,
+            retained: self.transactions.len(),
+        }
+    }
+}
+
+/// Transactions removed from the mempool because it was full or they expired
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Removed {
+    pub count: usize,
+    pub bytes: usize,
+}
+
+impl Removed {
+    fn add(&mut self, tx: &Transaction) {
+        self.count += 1;
+        self.bytes += tx.size_bytes();
+    }
+}
+
+/// Outcome of updating the mempool after a decision
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UpdateStats {
+    /// Number of transactions removed from the mempool
+    pub evicted: usize,
+    /// Number of transactions left in the mempool
+    pub retained: usize,
 }
 
 impl Default for State {
@@ -77,28 +340,100 @@ impl Mempool {
         mempool_network: MempoolNetworkRef,
         mempool_config: MempoolConfig,
         test_config: TestConfig,
+        metrics: Metrics,
         span: tracing::Span,
     ) -> Self {
         Self {
             network: mempool_network,
             config: mempool_config,
             test_config,
+            recheck: None,
+            handoff: None,
+            shared: None,
+            metrics,
             span,
         }
     }
 
+    /// Re-validate the transactions left in the mempool after each decision with the given function.
+    pub fn with_recheck(mut self, recheck: RecheckFn) -> Self {
+        self.recheck = Some(recheck);
+        self
+    }
+
+    /// Load the transactions returned by the given function when the mempool starts,
+    /// instead of starting with an empty mempool.
+    pub fn with_handoff(mut self, handoff: HandoffFn) -> Self {
+        self.handoff = Some(handoff);
+        self
+    }
+
+    /// Keep a copy of the pending transactions in the given [`SharedPendingTxs`],
+    /// eg. to hand them over to a new mempool if this one fails.
+    pub fn with_shared(mut self, shared: SharedPendingTxs) -> Self {
+        self.shared = Some(shared);
+        self
+    }
+
     pub async fn spawn(
         mempool_network: MempoolNetworkRef,
         mempool_config: MempoolConfig,
         test_config: TestConfig,
+        metrics: Metrics,
         span: tracing::Span,
     ) -> Result<MempoolRef, ractor::SpawnErr> {
-        let node = Self::new(mempool_network, mempool_config, test_config, span);
+        let node = Self::new(mempool_network, mempool_config, test_config, metrics, span);
+        node.start().await
+    }
 
-        let (actor_ref, _) = Actor::spawn(None, node, ()).await?;
+    /// Spawn the mempool actor, once configured
+    pub async fn start(self) -> Result<MempoolRef, ractor::SpawnErr> {
+        let (actor_ref, _) = Actor::spawn(None, self, ()).await?;
         Ok(actor_ref)
     }
 
+    fn evict_to_fit(&self, state: &mut State) {
+        let max_bytes = self.config.max_mempool_bytes.as_u64() as usize;
+
+        if max_bytes == 0 {
+            return;
+        }
+
+        let removed = state.evict_to_fit(max_bytes);
+
+        if removed.count > 0 {
+            debug!(
+                count = removed.count,
+                bytes = removed.bytes,
+                total_bytes = state.total_bytes(),
+                "Mempool is full, evicted oldest transactions"
+            );
+
+            self.metrics.record_size_evicted(removed);
+        }
+    }
+
+    fn expire(&self, state: &mut State) {
+        let ttl = self.config.tx_ttl;
+
+        if ttl.is_zero() {
+            return;
+        }
+
+        let removed = state.expire(ttl, Instant::now());
+
+        if removed.count > 0 {
+            debug!(
+                count = removed.count,
+                bytes = removed.bytes,
+                ttl = ?ttl,
+                "Expired transactions"
+            );
+
+            self.metrics.record_expired(removed);
+        }
+    }
+
     pub async fn handle_network_event(
         &self,
         event: &NetworkEvent,
@@ -169,7 +504,27 @@ impl Actor for Mempool {
         self.network
             .cast(MempoolNetworkMsg::Subscribe(Box::new(myself.clone())))?;
 
-        Ok(State::new())
+        // Get the transactions to hand over before the shared copy is replaced by our own
+        let handed_over = self.handoff.as_ref().map(|handoff| handoff());
+
+        let mut state = match &self.shared {
+            Some(shared) => State::with_shared(shared.clone()),
+            None => State::new(),
+        };
+
+        if let Some(txes) = handed_over {
+            let now = Instant::now();
+
+            info!(count = txes.len(), "Loading handed over transactions");
+
+            for tx in txes.iter().take(self.config.max_tx_count) {
+                state.add_tx(tx, now);
+            }
+
+            self.evict_to_fit(&mut state);
+        }
+
+        Ok(state)
     }
 
     #[tracing::instrument("host.mempool", parent = &self.span, skip_all)]
@@ -186,31 +541,62 @@ impl Actor for Mempool {
 
             Msg::Input(tx) => {
                 if state.transactions.len() < self.config.max_tx_count {
-                    state.add_tx(&tx);
+                    state.add_tx(&tx, Instant::now());
+                    self.evict_to_fit(state);
                 } else {
                     trace!("Mempool is full, dropping transaction");
                 }
             }
 
             Msg::Reap {
-                reply, num_txes, ..
+                reply,
+                num_txes,
+                height,
             } => {
-                let txes = generate_and_broadcast_txes(
-                    num_txes,
+                // Never propose stale transactions
+                self.expire(state);
+
+                let mut thread_rng = rand::thread_rng();
+
+                // Pending transactions are only proposed when the blocks are not seeded,
+                // as the ones received from peers would make blocks differ across runs.
+                let mut txes = match self.test_config.seed {
+                    Some(_) => Vec::new(),
+                    None => state.reap(height, num_txes),
+                };
+
+                let rng: &mut dyn RngCore = match self.test_config.seed {
+                    Some(seed) => state.seeded_rng(seed, height),
+                    None => &mut thread_rng,
+                };
+
+                txes.extend(generate_and_broadcast_txes(
+                    num_txes - txes.len(),
                     self.test_config.tx_size.as_u64() as usize,
                     &self.config,
-                    state,
+                    rng,
                     &self.network,
-                )?;
+                )?);
 
                 reply.send(txes)?;
             }
 
-            Msg::Update { .. } => {
-                // FIXME: Remove only the given txes
-                // tx_hashes.iter().for_each(|hash| state.remove_tx(hash));
-
-                state.transactions.clear();
+            Msg::Update {
+                height,
+                committed_txes,
+            } => {
+                let stats = state.update(&committed_txes, self.recheck.as_ref());
+
+                debug!(
+                    %height,
+                    committed = committed_txes.len(),
+                    evicted = stats.evicted,
+                    retained = stats.retained,
+                    "Updated mempool after decision"
+                );
+
+                self.metrics.evicted_txes.inc_by(stats.evicted as u64);
+                self.metrics.retained_txes.inc_by(stats.retained as u64);
             }
         }
 
@@ -232,7 +618,7 @@ fn generate_and_broadcast_txes(
     count: usize,
     size: usize,
     config: &MempoolConfig,
-    _state: &mut State,
+    rng: &mut dyn RngCore,
     mempool_network: &MempoolNetworkRef,
 ) -> Result<Vec<Transaction>, ActorProcessingErr> {
     debug!(%count, %size, "Generating transactions");
@@ -242,7 +628,6 @@ fn generate_and_broadcast_txes(
 
     let mut transactions = Vec::with_capacity(count);
     let mut tx_batch = Transactions::default();
-    let mut rng = rand::thread_rng();
 
     for _ in 0..count {
         // Generate transaction
@@ -276,3 +661,165 @@ fn generate_and_broadcast_txes(
 
     Ok(transactions)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(byte: u8) -> Transaction {
+        Transaction::new(vec![byte; 32])
+    }
+
+    #[test]
+    fn committed_txes_are_not_reaped_at_next_height() {
+        let mut state = State::new();
+        let (tx1, tx2, tx3) = (tx(1), tx(2), tx(3));
+
+        for tx in [&tx1, &tx2, &tx3] {
+            state.add_tx(tx, Instant::now());
+        }
+
+        // Propose two transactions at height H
+        let proposed = state.reap(1, 2);
+        assert_eq!(proposed.len(), 2);
+
+        // Transactions are handed out only once per height
+        let rest = state.reap(1, 2);
+        assert_eq!(rest.len(), 1);
+        assert!(!proposed.contains(&rest[0]));
+
+        // The block at height H is decided, along with a transaction we never saw
+        let mut committed: Vec<_> = proposed.iter().map(|tx| tx.hash()).collect();
+        committed.push(tx(4).hash());
+
+        let stats = state.update(&committed, None);
+        assert_eq!(
+            stats,
+            UpdateStats {
+                evicted: 2,
+                retained: 1
+            }
+        );
+
+        // Only the transaction which was not committed is proposed at height H + 1
+        let next = state.reap(2, 10);
+        assert_eq!(next.len(), 1);
+        assert!(proposed.iter().all(|tx| !next.contains(tx)));
+    }
+
+    #[test]
+    fn invalid_txes_are_evicted_on_recheck() {
+        let mut state = State::new();
+        let (tx1, tx2, tx3) = (tx(1), tx(2), tx(3));
+
+        for tx in [&tx1, &tx2, &tx3] {
+            state.add_tx(tx, Instant::now());
+        }
+
+        let invalid = tx2.hash();
+        let recheck: RecheckFn = Arc::new(move |tx| tx.hash() != invalid);
+
+        let stats = state.update(&[tx1.hash()], Some(&recheck));
+        assert_eq!(
+            stats,
+            UpdateStats {
+                evicted: 2,
+                retained: 1
+            }
+        );
+        assert_eq!(state.reap(2, 10), vec![tx3]);
+    }
+
+    #[test]
+    fn oldest_txes_are_evicted_when_full() {
+        let mut state = State::new();
+        let start = Instant::now();
+        let txes: Vec<_> = (0..10).map(tx).collect();
+
+        let max_bytes = 4 * txes[0].size_bytes();
+
+        for (secs, tx) in (0..).zip(&txes) {
+            state.add_tx(tx, start + Duration::from_secs(secs));
+            state.evict_to_fit(max_bytes);
+
+            assert!(state.total_bytes() <= max_bytes);
+        }
+
+        // Only the four most recent transactions are left
+        let left = state.reap(1, 10);
+        assert_eq!(left.len(), 4);
+        assert!(txes[6..].iter().all(|tx| left.contains(tx)));
+
+        let removed = state.evict_to_fit(2 * txes[0].size_bytes());
+        assert_eq!(
+            removed,
+            Removed {
+                count: 2,
+                bytes: 2 * txes[0].size_bytes()
+            }
+        );
+        assert_eq!(state.total_bytes(), 2 * txes[0].size_bytes());
+    }
+
+    #[test]
+    fn expired_txes_are_not_reaped() {
+        let mut state = State::new();
+        let start = Instant::now();
+        let ttl = Duration::from_secs(30);
+        let (old, recent) = (tx(1), tx(2));
+
+        state.add_tx(&old, start);
+        state.add_tx(&recent, start + Duration::from_secs(20));
+
+        let removed = state.expire(ttl, start + Duration::from_secs(40));
+        assert_eq!(removed.count, 1);
+        assert_eq!(removed.bytes, old.size_bytes());
+
+        assert_eq!(state.total_bytes(), recent.size_bytes());
+        assert_eq!(state.reap(1, 10), vec![recent]);
+    }
+
+    #[test]
+    fn seeded_rng_is_the_same_across_runs() {
+        fn bytes(state: &mut State, seed: u64, height: u64) -> [u8; 32] {
+            let mut bytes = [0; 32];
+            state.seeded_rng(seed, height).fill_bytes(&mut bytes);
+            bytes
+        }
+
+        let (mut run1, mut run2) = (State::new(), State::new());
+
+        assert_eq!(bytes(&mut run1, 42, 1), bytes(&mut run2, 42, 1));
+        assert_eq!(bytes(&mut run1, 42, 2), bytes(&mut run2, 42, 2));
+        assert_ne!(
+            bytes(&mut State::new(), 42, 1),
+            bytes(&mut State::new(), 42, 2)
+        );
+    }
+
+    #[test]
+    fn pending_txes_outlive_the_mempool_state() {
+        let shared = SharedPendingTxs::new();
+        let (tx1, tx2, tx3) = (tx(1), tx(2), tx(3));
+
+        {
+            let mut state = State::with_shared(shared.clone());
+
+            for tx in [&tx1, &tx2, &tx3] {
+                state.add_tx(tx, Instant::now());
+            }
+
+            state.update(&[tx2.hash()], None);
+        }
+
+        let mut expected = vec![tx1, tx3];
+        expected.sort_by_key(|tx| tx.hash());
+
+        assert_eq!(shared.get(), expected);
+
+        // A new mempool state starts from its own transactions
+        let state = State::with_shared(shared.clone());
+        assert!(state.transactions.is_empty());
+        assert!(shared.get().is_empty());
+    }
+}
@@ -0,0 +1,86 @@
+use malachitebft_metrics::prometheus::metrics::counter::Counter;
+use malachitebft_metrics::SharedRegistry;
+
+use super::Removed;
+
+#[derive(Clone, Debug, Default)]
+pub struct Metrics {
+    /// Number of transactions removed from the mempool after a decision,
+    /// either because they were committed or because they are not valid anymore
+    pub evicted_txes: Counter,
+
+    /// Number of transactions kept in the mempool after a decision
+    pub retained_txes: Counter,
+
+    /// Number of transactions evicted because the mempool exceeded its maximum size
+    pub size_evicted_txes: Counter,
+
+    /// Size of the transactions evicted because the mempool exceeded its maximum size, in bytes
+    pub size_evicted_bytes: Counter,
+
+    /// Number of transactions expired because they were in the mempool for longer than their TTL
+    pub expired_txes: Counter,
+
+    /// Size of the transactions expired because they were in the mempool for longer than their TTL, in bytes
+    pub expired_bytes: Counter,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(registry: &SharedRegistry) -> Self {
+        let metrics = Self::new();
+
+        registry.with_prefix("malachitebft_starknet_mempool", |registry| {
+            registry.register(
+                "evicted_txes",
+                "Number of transactions removed from the mempool after a decision",
+                metrics.evicted_txes.clone(),
+            );
+
+            registry.register(
+                "retained_txes",
+                "Number of transactions kept in the mempool after a decision",
+                metrics.retained_txes.clone(),
+            );
+
+            registry.register(
+                "size_evicted_txes",
+                "Number of transactions evicted because the mempool exceeded its maximum size",
+                metrics.size_evicted_txes.clone(),
+            );
+
+            registry.register(
+                "size_evicted_bytes",
+                "Size of the transactions evicted because the mempool exceeded its maximum size, in bytes",
+                metrics.size_evicted_bytes.clone(),
+            );
+
+            registry.register(
+                "expired_txes",
+                "Number of transactions expired because they were in the mempool for longer than their TTL",
+                metrics.expired_txes.clone(),
+            );
+
+            registry.register(
+                "expired_bytes",
+                "Size of the transactions expired because they were in the mempool for longer than their TTL, in bytes",
+                metrics.expired_bytes.clone(),
+            );
+        });
+
+        metrics
+    }
+
+    pub fn record_size_evicted(&self, removed: Removed) {
+        self.size_evicted_txes.inc_by(removed.count as u64);
+        self.size_evicted_bytes.inc_by(removed.bytes as u64);
+    }
+
+    pub fn record_expired(&self, removed: Removed) {
+        self.expired_txes.inc_by(removed.count as u64);
+        self.expired_bytes.inc_by(removed.bytes as u64);
+    }
+}
@@ -1,4 +1,5 @@
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 use libp2p_identity::ecdsa;
 use ractor::async_trait;
@@ -10,6 +11,7 @@ use malachitebft_app::types::Keypair;
 use malachitebft_app::Node;
 use malachitebft_config::Config;
 use malachitebft_core_types::VotingPower;
+use malachitebft_engine::node::Msg as NodeMsg;
 use malachitebft_engine::util::events::TxEvent;
 
 use crate::spawn::spawn_node_actor;
@@ -125,30 +127,37 @@ impl Node for StarknetNode {
 
         let start_height = self.start_height.map(|height| Height::new(height, 1));
 
-        let (actor, handle) = spawn_node_actor(
+        let (node, _mempool) = spawn_node_actor(
             self.config.clone(),
             self.home_dir.clone(),
             genesis.validator_set,
             private_key,
             start_height,
             TxEvent::new(),
+            None,
             span.clone(),
         )
         .await;
 
         tokio::spawn({
-            let actor = actor.clone();
+            let actor = node.node.clone();
+            let timeout = node.shutdown_timeout;
             {
                 async move {
                     tokio::signal::ctrl_c().await.unwrap();
                     info!("Shutting down...");
-                    actor.stop(None);
+
+                    // Let consensus finish the current round and flush the WAL before stopping
+                    let deadline = Instant::now() + timeout;
+                    if actor.cast(NodeMsg::Shutdown(deadline)).is_err() {
+                        actor.stop(None);
+                    }
                 }
             }
             .instrument(span.clone())
         });
 
-        handle.await?;
+        node.handle.await?;
 
         Ok(())
     }
@@ -1,31 +1,34 @@
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
 use libp2p_identity::ecdsa;
-use malachitebft_engine::util::events::TxEvent;
-use malachitebft_engine::wal::{Wal, WalRef};
-use tokio::task::JoinHandle;
+use tokio::sync::Mutex;
+use tracing::warn;
 
 use malachitebft_config::{
-    self as config, Config as NodeConfig, MempoolConfig, SyncConfig, TestConfig, TransportProtocol,
+    self as config, Config as NodeConfig, MempoolConfig, TestConfig, TransportProtocol,
 };
 use malachitebft_core_consensus::ValuePayload;
-use malachitebft_engine::consensus::{Consensus, ConsensusParams, ConsensusRef};
+use malachitebft_engine::builder::{NodeBuilder, NodeHandles};
 use malachitebft_engine::host::HostRef;
 use malachitebft_engine::network::{Network, NetworkRef};
-use malachitebft_engine::node::{Node, NodeRef};
-use malachitebft_engine::sync::{Params as SyncParams, Sync, SyncRef};
+use malachitebft_engine::supervisor::{Component, RestartFn};
+use malachitebft_engine::sync::Params as SyncParams;
+use malachitebft_engine::util::events::TxEvent;
 use malachitebft_metrics::Metrics;
 use malachitebft_metrics::SharedRegistry;
 use malachitebft_network::Keypair;
-use malachitebft_sync as sync;
 use malachitebft_test_mempool::Config as MempoolNetworkConfig;
 
 use crate::actor::Host;
 use crate::codec::ProtobufCodec;
-use crate::host::{StarknetHost, StarknetParams};
+use crate::host::{Clock, MempoolTxSource, StarknetHost, StarknetParams};
+use crate::mempool::metrics::Metrics as MempoolMetrics;
 use crate::mempool::network::{MempoolNetwork, MempoolNetworkRef};
-use crate::mempool::{Mempool, MempoolRef};
+use crate::mempool::{Mempool, MempoolRef, SharedMempoolRef, SharedPendingTxs};
+use crate::proto::DecodeLimits;
+use crate::streaming::metrics::Metrics as StreamingMetrics;
 use crate::types::MockContext;
 use crate::types::{Address, Height, PrivateKey, ValidatorSet};
 
@@ -36,8 +39,9 @@ pub async fn spawn_node_actor(
     private_key: PrivateKey,
     start_height: Option<Height>,
     tx_event: TxEvent<MockContext>,
+    clock: Option<Clock>,
     span: tracing::Span,
-) -> (NodeRef, JoinHandle<()>) {
+) -> (NodeHandles<MockContext>, SharedMempoolRef) {
     let ctx = MockContext::new(private_key);
 
     let start_height = start_height.unwrap_or(Height::new(1, 1));
@@ -47,14 +51,40 @@ pub async fn spawn_node_actor(
     let address = Address::from_public_key(private_key.public_key());
 
     // Spawn mempool and its gossip layer
-    let mempool_network = spawn_mempool_network_actor(&cfg, &private_key, &registry, &span).await;
-    let mempool =
-        spawn_mempool_actor(mempool_network.clone(), &cfg.mempool, &cfg.test, &span).await;
+    let mempool_metrics = MempoolMetrics::register(&registry);
+    let mempool_network = spawn_mempool_network_actor(&cfg, &private_key, &registry, &span)
+        .await
+        .unwrap();
+    let pending_txes = SharedPendingTxs::new();
+    let mempool = spawn_mempool_actor(
+        mempool_network.clone(),
+        &cfg.mempool,
+        &cfg.test,
+        mempool_metrics.clone(),
+        pending_txes.clone(),
+        &span,
+    )
+    .await
+    .unwrap();
+
+    let mempool = SharedMempoolRef::new(mempool);
+
+    let restart_mempool = make_mempool_restart(
+        &cfg,
+        &private_key,
+        &registry,
+        mempool_metrics,
+        mempool_network,
+        mempool.clone(),
+        pending_txes,
+        &span,
+    );
 
     // Spawn consensus gossip
     let network = spawn_network_actor(&cfg, &private_key, &registry, &span).await;
 
     // Spawn the host actor
+    let streaming_metrics = StreamingMetrics::register(&registry);
     let host = spawn_host_actor(
         &home_dir,
         &cfg,
@@ -64,141 +94,102 @@ pub async fn spawn_node_actor(
         mempool.clone(),
         network.clone(),
         metrics.clone(),
+        streaming_metrics,
+        clock,
         &span,
     )
     .await;
 
-    let sync = spawn_sync_actor(
-        ctx.clone(),
-        network.clone(),
-        host.clone(),
-        &cfg.sync,
-        &registry,
-        &span,
-    )
-    .await;
-
-    let wal = spawn_wal_actor(&ctx, ProtobufCodec, &home_dir, &registry, &span).await;
-
-    // Spawn consensus
-    let consensus = spawn_consensus_actor(
-        start_height,
-        initial_validator_set,
-        address,
-        ctx.clone(),
-        cfg,
-        network.clone(),
-        host.clone(),
-        wal.clone(),
-        sync.clone(),
-        metrics,
-        tx_event,
-        &span,
-    )
-    .await;
-
-    // Spawn the node actor
-    let node = Node::new(
-        ctx,
-        network,
-        consensus,
-        wal,
-        sync,
-        mempool.get_cell(),
-        host,
-        start_height,
-        span,
-    );
-
-    let (actor_ref, handle) = node.spawn().await.unwrap();
-
-    (actor_ref, handle)
-}
-
-async fn spawn_wal_actor(
-    ctx: &MockContext,
-    codec: ProtobufCodec,
-    home_dir: &Path,
-    registry: &SharedRegistry,
-    span: &tracing::Span,
-) -> WalRef<MockContext> {
-    let wal_dir = home_dir.join("wal");
-    std::fs::create_dir_all(&wal_dir).unwrap();
-    let wal_file = wal_dir.join("consensus.wal");
-
-    Wal::spawn(ctx, codec, wal_file, registry.clone(), span.clone())
-        .await
-        .unwrap()
-}
+    let value_payload = match cfg.consensus.value_payload {
+        config::ValuePayload::PartsOnly => ValuePayload::PartsOnly,
+        config::ValuePayload::ProposalOnly => ValuePayload::ProposalOnly,
+        config::ValuePayload::ProposalAndParts => ValuePayload::ProposalAndParts,
+    };
 
-async fn spawn_sync_actor(
-    ctx: MockContext,
-    network: NetworkRef<MockContext>,
-    host: HostRef<MockContext>,
-    config: &SyncConfig,
-    registry: &SharedRegistry,
-    span: &tracing::Span,
-) -> Option<SyncRef<MockContext>> {
-    if !config.enabled {
-        return None;
+    // Spawn the WAL, sync and consensus, along with the node actor supervising them
+    let mut builder = NodeBuilder::new()
+        .with_context(ctx)
+        .with_address(address)
+        .with_validator_set(initial_validator_set)
+        .with_start_height(start_height)
+        .with_codec(ProtobufCodec::default())
+        .with_network(network)
+        .with_value_builder(host)
+        .with_mempool(mempool.get().get_cell())
+        .with_restart(Component::Mempool, restart_mempool)
+        .with_timeouts(cfg.consensus.timeouts)
+        .with_value_payload(value_payload)
+        .with_wal_dir(home_dir.join("wal"))
+        .with_metrics(registry)
+        .with_consensus_metrics(metrics)
+        .with_tx_event(tx_event)
+        .with_span(span);
+
+    if cfg.sync.enabled {
+        builder = builder.with_sync(SyncParams {
+            status_update_interval: cfg.sync.status_update_interval,
+            request_timeout: cfg.sync.request_timeout,
+            batch_size: cfg.sync.batch_size,
+        });
     }
 
-    let params = SyncParams {
-        status_update_interval: config.status_update_interval,
-        request_timeout: config.request_timeout,
-    };
+    let handles = builder.spawn().await.unwrap();
 
-    let metrics = sync::Metrics::register(registry);
-    let actor_ref = Sync::spawn(ctx, network, host, params, metrics, span.clone())
-        .await
-        .unwrap();
-
-    Some(actor_ref)
+    (handles, mempool)
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn spawn_consensus_actor(
-    initial_height: Height,
-    initial_validator_set: ValidatorSet,
-    address: Address,
-    ctx: MockContext,
-    cfg: NodeConfig,
-    network: NetworkRef<MockContext>,
-    host: HostRef<MockContext>,
-    wal: WalRef<MockContext>,
-    sync: Option<SyncRef<MockContext>>,
-    metrics: Metrics,
-    tx_event: TxEvent<MockContext>,
+/// Restart the mempool along with its gossip layer when it fails,
+/// and hand over the new mempool to the actors using it.
+///
+/// The transactions pending in the failed mempool are loaded into the new one.
+fn make_mempool_restart(
+    cfg: &NodeConfig,
+    private_key: &PrivateKey,
+    registry: &SharedRegistry,
+    metrics: MempoolMetrics,
+    mempool_network: MempoolNetworkRef,
+    mempool: SharedMempoolRef,
+    pending_txes: SharedPendingTxs,
     span: &tracing::Span,
-) -> ConsensusRef<MockContext> {
-    let value_payload = match cfg.consensus.value_payload {
-        malachitebft_config::ValuePayload::PartsOnly => ValuePayload::PartsOnly,
-        malachitebft_config::ValuePayload::ProposalOnly => ValuePayload::ProposalOnly,
-        malachitebft_config::ValuePayload::ProposalAndParts => ValuePayload::ProposalAndParts,
-    };
-
-    let consensus_params = ConsensusParams {
-        initial_height,
-        initial_validator_set,
-        address,
-        threshold_params: Default::default(),
-        value_payload,
-    };
-
-    Consensus::spawn(
-        ctx,
-        consensus_params,
-        cfg.consensus.timeouts,
-        network,
-        host,
-        wal,
-        sync,
-        metrics,
-        tx_event,
-        span.clone(),
-    )
-    .await
-    .unwrap()
+) -> RestartFn {
+    let cfg = cfg.clone();
+    let private_key = *private_key;
+    let registry = registry.clone();
+    let span = span.clone();
+    let mempool_network = Arc::new(Mutex::new(mempool_network));
+
+    Box::new(move || {
+        let (cfg, registry, metrics, span) =
+            (cfg.clone(), registry.clone(), metrics.clone(), span.clone());
+        let (mempool_network, mempool) = (Arc::clone(&mempool_network), mempool.clone());
+        let pending_txes = pending_txes.clone();
+
+        Box::pin(async move {
+            let mut mempool_network = mempool_network.lock().await;
+
+            // Release the address the gossip layer of the failed mempool is listening on
+            if let Err(e) = mempool_network.stop_and_wait(None, None).await {
+                warn!("Failed to stop the mempool gossip layer: {e}");
+            }
+
+            *mempool_network =
+                spawn_mempool_network_actor(&cfg, &private_key, &registry, &span).await?;
+
+            let new_mempool = spawn_mempool_actor(
+                mempool_network.clone(),
+                &cfg.mempool,
+                &cfg.test,
+                metrics,
+                pending_txes,
+                &span,
+            )
+            .await?;
+
+            mempool.set(new_mempool.clone());
+
+            Ok(new_mempool.get_cell())
+        })
+    })
 }
 
 async fn spawn_network_actor(
@@ -229,6 +220,7 @@ async fn spawn_network_actor(
             num_outbound_peers: cfg.consensus.p2p.discovery.num_outbound_peers,
             num_inbound_peers: cfg.consensus.p2p.discovery.num_inbound_peers,
             ephemeral_connection_timeout: cfg.consensus.p2p.discovery.ephemeral_connection_timeout,
+            persistent_peers: cfg.consensus.p2p.discovery.persistent_peers.clone(),
             ..Default::default()
         },
         idle_connection_timeout: Duration::from_secs(15 * 60),
@@ -254,7 +246,14 @@ async fn spawn_network_actor(
     };
 
     let keypair = make_keypair(private_key);
-    let codec = ProtobufCodec;
+
+    // Messages larger than what the network layer accepts are rejected before being decoded
+    let codec = ProtobufCodec::new(DecodeLimits {
+        max_message_size: config_gossip
+            .pubsub_max_size
+            .max(config_gossip.rpc_max_size),
+        ..DecodeLimits::default()
+    });
 
     Network::spawn(
         keypair,
@@ -278,16 +277,26 @@ async fn spawn_mempool_actor(
     mempool_network: MempoolNetworkRef,
     mempool_config: &MempoolConfig,
     test_config: &TestConfig,
+    metrics: MempoolMetrics,
+    pending_txes: SharedPendingTxs,
     span: &tracing::Span,
-) -> MempoolRef {
-    Mempool::spawn(
+) -> Result<MempoolRef, ractor::SpawnErr> {
+    let handoff = {
+        let pending_txes = pending_txes.clone();
+        Arc::new(move || pending_txes.get())
+    };
+
+    Mempool::new(
         mempool_network,
         mempool_config.clone(),
         *test_config,
+        metrics,
         span.clone(),
     )
+    .with_handoff(handoff)
+    .with_shared(pending_txes)
+    .start()
     .await
-    .unwrap()
 }
 
 async fn spawn_mempool_network_actor(
@@ -295,7 +304,7 @@ async fn spawn_mempool_network_actor(
     private_key: &PrivateKey,
     registry: &SharedRegistry,
     span: &tracing::Span,
-) -> MempoolNetworkRef {
+) -> Result<MempoolNetworkRef, ractor::SpawnErr> {
     let keypair = make_keypair(private_key);
 
     let config = MempoolNetworkConfig {
@@ -308,9 +317,7 @@ async fn spawn_mempool_network_actor(
         },
     };
 
-    MempoolNetwork::spawn(keypair, config, registry.clone(), span.clone())
-        .await
-        .unwrap()
+    MempoolNetwork::spawn(keypair, config, registry.clone(), span.clone()).await
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -320,9 +327,11 @@ async fn spawn_host_actor(
     address: &Address,
     private_key: &PrivateKey,
     initial_validator_set: &ValidatorSet,
-    mempool: MempoolRef,
+    mempool: SharedMempoolRef,
     network: NetworkRef<MockContext>,
     metrics: Metrics,
+    streaming_metrics: StreamingMetrics,
+    clock: Option<Clock>,
     span: &tracing::Span,
 ) -> HostRef<MockContext> {
     let value_payload = match cfg.consensus.value_payload {
@@ -340,15 +349,26 @@ async fn spawn_host_actor(
         exec_time_per_tx: cfg.test.exec_time_per_tx,
         max_retain_blocks: cfg.test.max_retain_blocks,
         vote_extensions: cfg.test.vote_extensions,
+        seed: cfg.test.seed,
+        max_parts_per_value: cfg.test.max_parts_per_value,
+        report_build_progress: cfg.test.report_build_progress,
+        start_height_delay: cfg.test.start_height_delay,
+        create_empty_blocks: cfg.test.create_empty_blocks,
+        reuse_late_values: cfg.test.reuse_late_values,
     };
 
-    let mock_host = StarknetHost::new(
+    let mut mock_host = StarknetHost::new(
         mock_params,
-        mempool.clone(),
+        Arc::new(MempoolTxSource::new(mempool.clone())),
         *address,
         *private_key,
         initial_validator_set.clone(),
-    );
+    )
+    .with_metrics(metrics.clone());
+
+    if let Some(clock) = clock {
+        mock_host = mock_host.with_clock(clock);
+    }
 
     Host::spawn(
         home_dir.to_owned(),
@@ -356,6 +376,7 @@ async fn spawn_host_actor(
         mempool,
         network,
         metrics,
+        streaming_metrics,
         span.clone(),
     )
     .await
@@ -2,6 +2,7 @@ use std::cmp::Ordering;
 use std::collections::{BTreeMap, BinaryHeap, HashSet};
 
 use derive_where::derive_where;
+use tracing::warn;
 
 use malachitebft_core_consensus::PeerId;
 use malachitebft_core_types::Round;
@@ -9,6 +10,9 @@ use malachitebft_engine::util::streaming::{Sequence, StreamId, StreamMessage};
 
 use crate::types::{Address, Height, ProposalInit, ProposalPart};
 
+pub mod metrics;
+use metrics::Metrics;
+
 struct MinSeq<T>(StreamMessage<T>);
 
 impl<T> PartialEq for MinSeq<T> {
@@ -51,6 +55,18 @@ impl<T> MinHeap<T> {
     fn peek(&self) -> Option<&StreamMessage<T>> {
         self.0.peek().map(|msg| &msg.0)
     }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Number of messages with a sequence greater than or equal to the given one
+    fn count_from(&self, sequence: Sequence) -> usize {
+        self.0
+            .iter()
+            .filter(|msg| msg.0.sequence >= sequence)
+            .count()
+    }
 }
 
 #[derive_where(Default)]
@@ -69,16 +85,16 @@ impl<T> StreamState<T> {
         self.fin_received && self.emitted_messages == self.total_messages
     }
 
-    fn emit(&mut self, msg: StreamMessage<T>, to_emit: &mut Vec<T>) {
+    fn emit(&mut self, msg: StreamMessage<T>, to_emit: &mut Vec<(Sequence, T)>) {
         if let Some(data) = msg.content.into_data() {
-            to_emit.push(data);
+            to_emit.push((msg.sequence, data));
         }
 
         self.next_sequence = msg.sequence + 1;
         self.emitted_messages += 1;
     }
 
-    fn emit_eligible_messages(&mut self, to_emit: &mut Vec<T>) {
+    fn emit_eligible_messages(&mut self, to_emit: &mut Vec<(Sequence, T)>) {
         while let Some(msg) = self.buffer.peek() {
             if msg.sequence == self.next_sequence {
                 let msg = self.buffer.pop().expect("peeked element should exist");
@@ -95,17 +111,63 @@ pub struct ProposalParts {
     pub height: Height,
     pub round: Round,
     pub proposer: Address,
-    pub parts: Vec<ProposalPart>,
+    /// The parts along with their sequence number, in increasing order of sequence
+    pub parts: Vec<(Sequence, ProposalPart)>,
+}
+
+/// Bounds the number of parts buffered for a stream, ie. for the proposal of a given height and round.
+///
+/// Parts whose sequence is within `size` of the lowest missing sequence of the stream are accepted,
+/// while at most `max_buffered` parts past that window are buffered. The parts past both are dropped.
+///
+/// Proposers do not retransmit the parts which were dropped, so that a proposal whose parts
+/// were dropped is never assembled. Nodes therefore always use an [unbounded](Self::unbounded) window.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ReceiveWindow {
+    /// Number of parts, starting at the lowest missing one, which are always accepted
+    pub size: usize,
+
+    /// Maximum number of parts buffered past the window
+    pub max_buffered: usize,
+}
+
+impl ReceiveWindow {
+    pub fn new(size: usize, max_buffered: usize) -> Self {
+        Self { size, max_buffered }
+    }
+
+    /// A window which accepts all parts
+    pub fn unbounded() -> Self {
+        Self::new(usize::MAX, usize::MAX)
+    }
+
+    fn accepts<T>(&self, state: &StreamState<T>, sequence: Sequence) -> bool {
+        let window_end = state.next_sequence.saturating_add(self.size as u64);
+
+        sequence < window_end || state.buffer.count_from(window_end) < self.max_buffered
+    }
+}
+
+impl Default for ReceiveWindow {
+    fn default() -> Self {
+        Self::unbounded()
+    }
 }
 
 #[derive(Default)]
 pub struct PartStreamsMap {
     streams: BTreeMap<(PeerId, StreamId), StreamState<ProposalPart>>,
+    window: ReceiveWindow,
+    metrics: Metrics,
 }
 
 impl PartStreamsMap {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(window: ReceiveWindow, metrics: Metrics) -> Self {
+        Self {
+            streams: BTreeMap::new(),
+            window,
+            metrics,
+        }
     }
 
     pub fn insert(
@@ -121,12 +183,38 @@ impl PartStreamsMap {
             return None;
         }
 
+        if !self.window.accepts(state, msg.sequence) {
+            // Forget about this part, so that it is accepted if it is ever sent again.
+            state.seen_sequences.remove(&msg.sequence);
+            self.metrics.dropped_parts.inc();
+
+            warn!(
+                %peer_id,
+                %stream_id,
+                sequence = %msg.sequence,
+                next_sequence = %state.next_sequence,
+                "Proposal part is too far ahead of the receive window, dropping it"
+            );
+
+            return None;
+        }
+
+        if msg.sequence != state.next_sequence {
+            self.metrics.reordered_parts.inc();
+        }
+
+        let buffered = state.buffer.len();
+
         let result = if msg.is_first() {
             Self::insert_first(state, msg)
         } else {
             Self::insert_other(state, msg)
         };
 
+        self.metrics
+            .buffered_parts
+            .inc_by(state.buffer.len() as i64 - buffered as i64);
+
         if state.has_emitted_all_messages() {
             self.streams.remove(&(peer_id, stream_id));
         }
@@ -182,3 +270,134 @@ impl PartStreamsMap {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use malachitebft_engine::util::streaming::StreamContent;
+    use malachitebft_network::{Keypair, PeerIdExt};
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    use crate::types::{Felt, PrivateKey, ProposalFin, Transaction, Transactions};
+
+    const STREAM_ID: StreamId = 42;
+
+    fn peer_id() -> PeerId {
+        PeerId::from_libp2p(&Keypair::generate_ecdsa().public().to_peer_id())
+    }
+
+    // A proposal made of an init part, `count` transaction parts, a fin part,
+    // and the final stream message, in order.
+    fn proposal(count: u64) -> Vec<StreamMessage<ProposalPart>> {
+        let private_key = PrivateKey::generate(StdRng::seed_from_u64(0));
+        let proposer = Address::from_public_key(private_key.public_key());
+
+        let init = ProposalPart::Init(ProposalInit {
+            height: Height::new(1, 1),
+            proposal_round: Round::new(0),
+            valid_round: Round::Nil,
+            proposer,
+        });
+
+        let txes = (1..=count).map(|i| {
+            let tx = Transaction::new(i.to_be_bytes().to_vec());
+            ProposalPart::Transactions(Transactions::new(vec![tx]))
+        });
+
+        let fin = ProposalPart::Fin(ProposalFin {
+            signature: private_key.sign(&Felt::ONE),
+        });
+
+        let mut msgs: Vec<_> = std::iter::once(init)
+            .chain(txes)
+            .chain(std::iter::once(fin))
+            .enumerate()
+            .map(|(i, part)| StreamMessage::new(STREAM_ID, i as u64, StreamContent::Data(part)))
+            .collect();
+
+        let fin_sequence = msgs.len() as u64;
+        msgs.push(StreamMessage::new(
+            STREAM_ID,
+            fin_sequence,
+            StreamContent::Fin(true),
+        ));
+
+        msgs
+    }
+
+    #[test]
+    fn shuffled_parts_are_assembled_within_the_window() {
+        const PARTS: u64 = 500;
+
+        let window = ReceiveWindow::new(8, 16);
+        let metrics = Metrics::new();
+        let mut map = PartStreamsMap::new(window, metrics.clone());
+
+        let peer_id = peer_id();
+        let expected = proposal(PARTS);
+
+        let mut msgs = expected.clone();
+        msgs.shuffle(&mut StdRng::seed_from_u64(0xcafe));
+
+        let mut received = Vec::new();
+        let mut max_buffered = 0;
+
+        // Parts dropped by the receiver are sent again after the others,
+        // as a sender which retransmits the parts it was not acknowledged for would
+        while !msgs.is_empty() {
+            for msg in std::mem::take(&mut msgs) {
+                let dropped = metrics.dropped_parts.get();
+
+                if let Some(parts) = map.insert(peer_id, msg.clone()) {
+                    received.extend(parts.parts);
+                }
+
+                if metrics.dropped_parts.get() > dropped {
+                    msgs.push(msg);
+                }
+
+                max_buffered = max_buffered.max(metrics.buffered_parts.get());
+            }
+        }
+
+        let expected: Vec<_> = expected
+            .into_iter()
+            .filter_map(|msg| Some((msg.sequence, msg.content.into_data()?)))
+            .collect();
+
+        assert_eq!(received, expected);
+
+        // The stream is done, and at most the window and the parts past it were buffered at once
+        assert!(map.streams.is_empty());
+        assert_eq!(metrics.buffered_parts.get(), 0);
+        assert!(max_buffered <= (window.size + window.max_buffered) as i64);
+
+        assert!(metrics.dropped_parts.get() > 0);
+        assert!(metrics.reordered_parts.get() > 0);
+    }
+
+    #[test]
+    fn unbounded_window_never_drops_parts() {
+        let metrics = Metrics::new();
+        let mut map = PartStreamsMap::new(ReceiveWindow::unbounded(), metrics.clone());
+
+        let peer_id = peer_id();
+
+        let mut msgs = proposal(100);
+        msgs.reverse();
+
+        let received: usize = msgs
+            .into_iter()
+            .filter_map(|msg| map.insert(peer_id, msg))
+            .map(|parts| parts.parts.len())
+            .sum();
+
+        // Init, transactions and fin
+        assert_eq!(received, 102);
+        assert_eq!(metrics.dropped_parts.get(), 0);
+        assert_eq!(metrics.buffered_parts.get(), 0);
+    }
+}
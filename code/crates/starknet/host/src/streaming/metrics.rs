@@ -0,0 +1,47 @@
+use malachitebft_metrics::prometheus::metrics::counter::Counter;
+use malachitebft_metrics::prometheus::metrics::gauge::Gauge;
+use malachitebft_metrics::SharedRegistry;
+
+#[derive(Clone, Debug, Default)]
+pub struct Metrics {
+    /// Number of proposal parts currently buffered, waiting for the parts before them
+    pub buffered_parts: Gauge,
+
+    /// Number of proposal parts dropped because they were too far ahead of the receive window
+    pub dropped_parts: Counter,
+
+    /// Number of proposal parts received out of order
+    pub reordered_parts: Counter,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(registry: &SharedRegistry) -> Self {
+        let metrics = Self::new();
+
+        registry.with_prefix("malachitebft_starknet_streaming", |registry| {
+            registry.register(
+                "buffered_parts",
+                "Number of proposal parts currently buffered, waiting for the parts before them",
+                metrics.buffered_parts.clone(),
+            );
+
+            registry.register(
+                "dropped_parts",
+                "Number of proposal parts dropped because they were too far ahead of the receive window",
+                metrics.dropped_parts.clone(),
+            );
+
+            registry.register(
+                "reordered_parts",
+                "Number of proposal parts received out of order",
+                metrics.reordered_parts.clone(),
+            );
+        });
+
+        metrics
+    }
+}
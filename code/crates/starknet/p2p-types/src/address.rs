@@ -21,6 +21,11 @@ impl Address {
     pub fn from_public_key(public_key: PublicKey) -> Self {
         Self(public_key)
     }
+
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    pub fn as_bytes(&self) -> [u8; 32] {
+        self.0.as_bytes()
+    }
 }
 
 impl fmt::Display for Address {
@@ -1,17 +1,20 @@
-use crate::{BlockHash, Height, Transactions};
+use sha3::Digest;
+
+use crate::{Address, BlockHash, Hash, Height, Transaction, Transactions};
 
 use malachitebft_proto::{Error as ProtoError, Protobuf};
 use malachitebft_starknet_p2p_proto as proto;
 
+/// A decided block, as exchanged by the sync protocol
 #[derive(Clone, Debug)]
-pub struct Block {
+pub struct SyncedBlock {
     pub height: Height,
     pub transactions: Transactions,
     pub block_hash: BlockHash,
 }
 
-impl Protobuf for Block {
-    type Proto = proto::sync::Block;
+impl Protobuf for SyncedBlock {
+    type Proto = proto::sync::SyncedBlock;
 
     fn from_proto(proto: Self::Proto) -> Result<Self, ProtoError> {
         let transactions = proto
@@ -38,3 +41,265 @@ impl Protobuf for Block {
         })
     }
 }
+
+/// The header of a block
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockHeader {
+    /// Hash of the previous block, [`Hash::ZERO`] for the genesis block
+    pub parent_hash: BlockHash,
+    pub height: Height,
+    /// Time at which the block was proposed, in seconds since the Unix epoch
+    pub timestamp: u64,
+    pub proposer: Address,
+    /// Root of the state after executing the block
+    pub state_root: Hash,
+}
+
+impl Protobuf for BlockHeader {
+    type Proto = proto::sync::BlockHeader;
+
+    fn from_proto(proto: Self::Proto) -> Result<Self, ProtoError> {
+        let parent_hash = proto
+            .parent_hash
+            .ok_or_else(|| ProtoError::missing_field::<Self::Proto>("parent_hash"))?;
+
+        let proposer = proto
+            .proposer
+            .ok_or_else(|| ProtoError::missing_field::<Self::Proto>("proposer"))?;
+
+        let state_root = proto
+            .state_root
+            .ok_or_else(|| ProtoError::missing_field::<Self::Proto>("state_root"))?;
+
+        Ok(Self {
+            parent_hash: BlockHash::from_proto(parent_hash)?,
+            height: Height::new(proto.block_number, proto.fork_id),
+            timestamp: proto.timestamp,
+            proposer: Address::from_proto(proposer)?,
+            state_root: Hash::from_proto(state_root)?,
+        })
+    }
+
+    fn to_proto(&self) -> Result<Self::Proto, ProtoError> {
+        Ok(Self::Proto {
+            parent_hash: Some(self.parent_hash.to_proto()?),
+            fork_id: self.height.fork_id,
+            block_number: self.height.block_number,
+            timestamp: self.timestamp,
+            proposer: Some(self.proposer.to_proto()?),
+            state_root: Some(self.state_root.to_proto()?),
+        })
+    }
+}
+
+/// The body of a block, made of the transaction batches streamed in the proposal
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockBody {
+    pub batches: Vec<Transactions>,
+}
+
+impl BlockBody {
+    pub fn new(batches: Vec<Transactions>) -> Self {
+        Self { batches }
+    }
+
+    /// Total number of transactions in the body
+    pub fn tx_count(&self) -> usize {
+        self.batches.iter().map(Transactions::len).sum()
+    }
+
+    /// Hash of the body, see [`Block::hash`]
+    pub fn hash(&self) -> Hash {
+        let mut hasher = sha3::Keccak256::new();
+
+        hasher.update((self.batches.len() as u64).to_be_bytes());
+
+        for batch in &self.batches {
+            hasher.update((batch.len() as u64).to_be_bytes());
+
+            for tx in batch.as_slice() {
+                hasher.update(Transaction::compute_hash(tx.as_bytes()).as_bytes());
+            }
+        }
+
+        Hash::new(hasher.finalize().into())
+    }
+}
+
+impl Protobuf for BlockBody {
+    type Proto = proto::sync::BlockBody;
+
+    fn from_proto(proto: Self::Proto) -> Result<Self, ProtoError> {
+        Ok(Self::new(
+            proto
+                .batches
+                .into_iter()
+                .map(Transactions::from_proto)
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+
+    fn to_proto(&self) -> Result<Self::Proto, ProtoError> {
+        Ok(Self::Proto {
+            batches: self
+                .batches
+                .iter()
+                .map(Transactions::to_proto)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// A full block, made of a header and a body
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub header: BlockHeader,
+    pub body: BlockBody,
+}
+
+impl Block {
+    pub fn new(header: BlockHeader, body: BlockBody) -> Self {
+        Self { header, body }
+    }
+
+    pub fn height(&self) -> Height {
+        self.header.height
+    }
+
+    /// Hash of the block, committing to both its header and its body.
+    ///
+    /// The hash is the Keccak-256 digest of the concatenation of:
+    /// 1. the fork id, as 8 big-endian bytes
+    /// 2. the block number, as 8 big-endian bytes
+    /// 3. the timestamp, as 8 big-endian bytes
+    /// 4. the parent hash (32 bytes)
+    /// 5. the proposer address (32 bytes)
+    /// 6. the state root (32 bytes)
+    /// 7. the hash of the body (32 bytes)
+    ///
+    /// The hash of the body is the Keccak-256 digest of the number of batches,
+    /// followed by, for each batch, the number of transactions in the batch and
+    /// the Keccak-256 digest of the data of each of these transactions,
+    /// where all numbers are encoded as 8 big-endian bytes.
+    pub fn hash(&self) -> BlockHash {
+        let header = &self.header;
+
+        let mut hasher = sha3::Keccak256::new();
+
+        hasher.update(header.height.fork_id.to_be_bytes());
+        hasher.update(header.height.block_number.to_be_bytes());
+        hasher.update(header.timestamp.to_be_bytes());
+        hasher.update(header.parent_hash.as_bytes());
+        hasher.update(header.proposer.as_bytes());
+        hasher.update(header.state_root.as_bytes());
+        hasher.update(self.body.hash().as_bytes());
+
+        BlockHash::new(hasher.finalize().into())
+    }
+}
+
+impl Protobuf for Block {
+    type Proto = proto::sync::Block;
+
+    fn from_proto(proto: Self::Proto) -> Result<Self, ProtoError> {
+        let header = proto
+            .header
+            .ok_or_else(|| ProtoError::missing_field::<Self::Proto>("header"))?;
+
+        let body = proto
+            .body
+            .ok_or_else(|| ProtoError::missing_field::<Self::Proto>("body"))?;
+
+        Ok(Self {
+            header: BlockHeader::from_proto(header)?,
+            body: BlockBody::from_proto(body)?,
+        })
+    }
+
+    fn to_proto(&self) -> Result<Self::Proto, ProtoError> {
+        Ok(Self::Proto {
+            header: Some(self.header.to_proto()?),
+            body: Some(self.body.to_proto()?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block() -> Block {
+        let mut proposer = [0; 32];
+        proposer[31] = 0x2a;
+
+        let header = BlockHeader {
+            parent_hash: Hash::new([1; 32]),
+            height: Height::new(7, 1),
+            timestamp: 1_700_000_000,
+            proposer: Address::new(proposer),
+            state_root: Hash::new([3; 32]),
+        };
+
+        let body = BlockBody::new(vec![
+            Transactions::new(vec![
+                Transaction::new(b"hello".to_vec()),
+                Transaction::new(b"world".to_vec()),
+            ]),
+            Transactions::new(vec![]),
+        ]);
+
+        Block::new(header, body)
+    }
+
+    #[test]
+    fn block_proto_round_trip() {
+        let block = block();
+
+        let bytes = block.to_bytes().unwrap();
+        let decoded = Block::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded, block);
+        assert_eq!(decoded.hash(), block.hash());
+    }
+
+    #[test]
+    fn block_proto_requires_header_and_body() {
+        let mut proto = block().to_proto().unwrap();
+        proto.header = None;
+        assert!(Block::from_proto(proto).is_err());
+
+        let mut proto = block().to_proto().unwrap();
+        proto.body = None;
+        assert!(Block::from_proto(proto).is_err());
+    }
+
+    #[test]
+    fn block_hash_golden_vector() {
+        let expected: Hash = "0x469a46def5260035c4bf08103c2333c95ec1d7a54d4e511300a2104098936f67"
+            .parse()
+            .unwrap();
+
+        assert_eq!(block().hash(), expected);
+    }
+
+    #[test]
+    fn block_hash_commits_to_header_and_body() {
+        let block = block();
+
+        let mut other = block.clone();
+        other.header.timestamp += 1;
+        assert_ne!(other.hash(), block.hash());
+
+        let mut other = block.clone();
+        other.header.state_root = Hash::ZERO;
+        assert_ne!(other.hash(), block.hash());
+
+        // Moving a transaction to another batch changes the hash
+        let mut other = block.clone();
+        other.body = BlockBody::new(vec![
+            Transactions::new(vec![Transaction::new(b"hello".to_vec())]),
+            Transactions::new(vec![Transaction::new(b"world".to_vec())]),
+        ]);
+        assert_ne!(other.hash(), block.hash());
+    }
+}
@@ -11,8 +11,13 @@ pub trait FeltExt: Sized {
 
 impl FeltExt for Felt {
     fn from_proto(proto: Felt252) -> Result<Self, Error> {
-        let mut felt = [0; 32];
-        felt.copy_from_slice(&proto.elements);
+        let felt = <[u8; 32]>::try_from(proto.elements.as_ref()).map_err(|_| {
+            Error::Other(format!(
+                "Invalid felt length: expected 32, got {}",
+                proto.elements.len()
+            ))
+        })?;
+
         Ok(Self::from_bytes_be(&felt))
     }
 
@@ -25,16 +25,49 @@ impl malachitebft_core_types::Value for BlockHash {
 pub struct Hash(Hash256);
 
 impl Hash {
+    /// The all-zero hash, eg. the parent hash of the genesis block
+    pub const ZERO: Self = Self::new([0; 32]);
+
     pub const fn new(bytes: [u8; 32]) -> Self {
         Self(Hash256::from_bytes(bytes))
     }
 
+    /// Whether this is the all-zero hash
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
     pub fn as_bytes(&self) -> &[u8; 32] {
         self.0.as_bytes()
     }
 
+    /// Convert the hash to a field element.
+    ///
+    /// Fails if the hash, read as a big-endian integer, does not fit in the Starknet field,
+    /// which can happen for any hash computed over arbitrary bytes.
+    pub fn try_as_felt(&self) -> Result<Felt, HashError> {
+        self.0
+            .try_into()
+            .map_err(|_| HashError::ExceedsFieldModulus(*self))
+    }
+
+    /// Convert the hash to a field element, reducing it modulo the Starknet field.
+    ///
+    /// The hash is read as a big-endian integer and mapped to its remainder modulo
+    /// the field prime `p = 2^251 + 17 * 2^192 + 1`. Hashes below `p` map to the same
+    /// field element as with [`Hash::try_as_felt`], while hashes `h >= p` map to `h - k * p`.
+    ///
+    /// This never fails, but is not injective: distinct hashes may map to the same
+    /// field element, eg. `h` and `h + p`. It is therefore only suitable for digests
+    /// which are themselves outputs of a collision-resistant hash function, such as
+    /// the messages we sign.
+    pub fn as_felt_reduced(&self) -> Felt {
+        Felt::from_bytes_be(self.as_bytes())
+    }
+
+    #[deprecated(note = "panics if the hash does not fit in a felt, use `try_as_felt` instead")]
     pub fn as_felt(&self) -> Felt {
-        self.0.try_into().unwrap()
+        self.try_as_felt().unwrap()
     }
 
     #[allow(clippy::len_without_is_empty)]
@@ -43,6 +76,56 @@ impl Hash {
     }
 }
 
+impl Default for Hash {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+/// Error returned when converting a [`Hash`] from bytes or into a [`Felt`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HashError {
+    /// The hash is not smaller than the modulus of the Starknet field
+    ExceedsFieldModulus(Hash),
+
+    /// The bytes of the hash are not exactly 32 bytes long
+    InvalidLength { got: usize },
+}
+
+impl fmt::Display for HashError {
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ExceedsFieldModulus(hash) => {
+                write!(f, "Hash {hash} exceeds the modulus of the Starknet field")
+            }
+            Self::InvalidLength { got } => {
+                write!(f, "Invalid hash length: got {got}, expected 32")
+            }
+        }
+    }
+}
+
+impl core::error::Error for HashError {}
+
+impl From<[u8; 32]> for Hash {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for Hash {
+    type Error = HashError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| HashError::InvalidLength { got: bytes.len() })?;
+
+        Ok(Self::new(bytes))
+    }
+}
+
 impl PartialOrd for Hash {
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
@@ -62,9 +145,7 @@ impl proto::Protobuf for Hash {
 
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn from_proto(proto: Self::Proto) -> Result<Self, proto::Error> {
-        Ok(Self::new(proto.elements.as_ref().try_into().map_err(
-            |_| proto::Error::Other("Invalid hash length".to_string()),
-        )?))
+        Self::try_from(proto.elements.as_ref()).map_err(|e| proto::Error::Other(e.to_string()))
     }
 
     #[cfg_attr(coverage_nightly, coverage(off))]
@@ -98,3 +179,127 @@ impl str::FromStr for Hash {
         Ok(Self(hash))
     }
 }
+
+/// Serialize a [`Hash`] as a `0x`-prefixed lowercase hex string, eg. for config files and logs.
+///
+/// Use with `#[serde(with = "malachitebft_starknet_p2p_types::hash::hex")]`.
+pub mod hex {
+    use core::fmt::Write;
+
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use super::Hash;
+
+    pub fn serialize<S>(hash: &Hash, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut hex = String::with_capacity(2 + 2 * hash.len());
+        hex.push_str("0x");
+
+        for byte in hash.as_bytes() {
+            write!(hex, "{byte:02x}").expect("writing to a string cannot fail");
+        }
+
+        serializer.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Hash, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let hex = String::deserialize(deserializer)?;
+
+        match hex.strip_prefix("0x") {
+            Some(digits) if digits.len() == 64 => hex.parse().map_err(de::Error::custom),
+            _ => Err(de::Error::custom(format!(
+                "expected a 0x-prefixed string of 64 hex digits, got `{hex}`"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The modulus of the Starknet field, `2^251 + 17 * 2^192 + 1`, in big-endian
+    const MODULUS: [u8; 32] = {
+        let mut bytes = [0; 32];
+        bytes[0] = 0x08;
+        bytes[7] = 0x11;
+        bytes[31] = 0x01;
+        bytes
+    };
+
+    fn modulus_plus(delta: i8) -> Hash {
+        let mut bytes = MODULUS;
+        bytes[31] = bytes[31].wrapping_add_signed(delta);
+        Hash::new(bytes)
+    }
+
+    #[test]
+    fn try_as_felt_accepts_hashes_below_modulus() {
+        assert_eq!(Hash::ZERO.try_as_felt(), Ok(Felt::ZERO));
+        assert_eq!(modulus_plus(-1).try_as_felt(), Ok(Felt::MAX));
+    }
+
+    #[test]
+    fn try_as_felt_rejects_hashes_not_below_modulus() {
+        for hash in [modulus_plus(0), modulus_plus(1), Hash::new([0xff; 32])] {
+            assert_eq!(
+                hash.try_as_felt(),
+                Err(HashError::ExceedsFieldModulus(hash))
+            );
+        }
+    }
+
+    #[test]
+    fn as_felt_reduced_reduces_modulo_the_field() {
+        assert_eq!(modulus_plus(-1).as_felt_reduced(), Felt::MAX);
+        assert_eq!(modulus_plus(0).as_felt_reduced(), Felt::ZERO);
+        assert_eq!(modulus_plus(1).as_felt_reduced(), Felt::ONE);
+
+        // (2^256 - 1) mod p
+        let expected = Felt::from_hex_unchecked(
+            "0x7fffffffffffdf0ffffffffffffffffffffffffffffffffffffffffffffffe0",
+        );
+        assert_eq!(Hash::new([0xff; 32]).as_felt_reduced(), expected);
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct WithHash {
+        #[serde(with = "hex")]
+        hash: Hash,
+    }
+
+    #[test]
+    fn hex_serde_round_trip() {
+        let mut bytes = [0; 32];
+        bytes[0] = 0x01;
+        bytes[31] = 0xab;
+
+        let value = WithHash {
+            hash: Hash::new(bytes),
+        };
+
+        let json = serde_json::to_string(&value).unwrap();
+        let expected = format!(r#"{{"hash":"0x01{}ab"}}"#, "00".repeat(30));
+        assert_eq!(json, expected);
+
+        let decoded: WithHash = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn hex_serde_rejects_malformed_strings() {
+        let unprefixed = format!(r#"{{"hash":"{}"}}"#, "00".repeat(32));
+        assert!(serde_json::from_str::<WithHash>(&unprefixed).is_err());
+
+        let too_short = r#"{"hash":"0x01ab"}"#;
+        assert!(serde_json::from_str::<WithHash>(too_short).is_err());
+
+        let not_hex = format!(r#"{{"hash":"0x{}"}}"#, "zz".repeat(32));
+        assert!(serde_json::from_str::<WithHash>(&not_hex).is_err());
+    }
+}
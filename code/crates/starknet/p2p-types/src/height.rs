@@ -1,4 +1,6 @@
 use core::fmt;
+use core::num::ParseIntError;
+use core::str::FromStr;
 
 /// A blockchain height
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -31,10 +33,7 @@ impl Height {
     }
 
     pub fn decrement(&self) -> Option<Self> {
-        self.block_number.checked_sub(1).map(|block_number| Self {
-            block_number,
-            fork_id: self.fork_id,
-        })
+        malachitebft_core_types::Height::decrement(self)
     }
 }
 
@@ -44,7 +43,19 @@ impl fmt::Display for Height {
     }
 }
 
+/// Parse a block number, on the default fork.
+impl FromStr for Height {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(|block_number| Self::new(block_number, 1))
+    }
+}
+
 impl malachitebft_core_types::Height for Height {
+    const ZERO: Self = Self::new(0, 1);
+    const INITIAL: Self = Self::new(1, 1);
+
     fn increment_by(&self, n: u64) -> Self {
         Self {
             block_number: self.block_number + n,
@@ -53,10 +64,13 @@ impl malachitebft_core_types::Height for Height {
     }
 
     fn decrement_by(&self, n: u64) -> Option<Self> {
-        Some(Self {
-            block_number: self.block_number.saturating_sub(n),
-            fork_id: self.fork_id,
-        })
+        self.block_number
+            .checked_sub(n)
+            .filter(|block_number| *block_number >= Self::INITIAL.block_number)
+            .map(|block_number| Self {
+                block_number,
+                fork_id: self.fork_id,
+            })
     }
 
     fn as_u64(&self) -> u64 {
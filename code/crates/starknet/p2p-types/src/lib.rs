@@ -29,13 +29,13 @@ mod proposal_part;
 pub use proposal_part::{PartType, ProposalFin, ProposalInit, ProposalPart};
 
 mod block;
-pub use block::Block;
+pub use block::{Block, BlockBody, BlockHeader, SyncedBlock};
 
 mod block_proof;
 pub use block_proof::BlockProof;
 
-mod hash;
-pub use hash::{BlockHash, Hash, MessageHash};
+pub mod hash;
+pub use hash::{BlockHash, Hash, HashError, MessageHash};
 
 mod streaming;
 pub use streaming::{StreamContent, StreamMessage};
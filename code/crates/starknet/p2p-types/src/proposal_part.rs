@@ -95,6 +95,21 @@ impl ProposalPart {
 impl proto::Protobuf for ProposalPart {
     type Proto = p2p_proto::ProposalPart;
 
+    fn check_limits(proto: &Self::Proto, limits: &proto::DecodeLimits) -> Result<(), proto::Error> {
+        use p2p_proto::proposal_part::Messages;
+
+        match &proto.messages {
+            Some(Messages::Transactions(txes)) => {
+                <Transactions as proto::Protobuf>::check_limits(txes, limits)
+            }
+            Some(Messages::Fin(p2p_proto::ProposalFin {
+                signature: Some(signature),
+                ..
+            })) => <Signature as proto::Protobuf>::check_limits(signature, limits),
+            _ => Ok(()),
+        }
+    }
+
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn from_proto(proto: Self::Proto) -> Result<Self, proto::Error> {
         use p2p_proto::proposal_part::Messages;
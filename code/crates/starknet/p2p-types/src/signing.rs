@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use starknet_core::crypto::{ecdsa_sign, ecdsa_verify};
 use starknet_crypto::{get_public_key, Felt};
 
-use malachitebft_proto::{Error as ProtoError, Protobuf};
+use malachitebft_proto::{DecodeLimits, Error as ProtoError, Protobuf};
 use malachitebft_starknet_p2p_proto as proto;
 
 mod provider;
@@ -114,6 +114,16 @@ impl Ord for Signature {
 impl Protobuf for Signature {
     type Proto = proto::ConsensusSignature;
 
+    fn check_limits(proto: &Self::Proto, limits: &DecodeLimits) -> Result<(), ProtoError> {
+        let size = [&proto.r, &proto.s]
+            .into_iter()
+            .flatten()
+            .map(|felt| felt.elements.len())
+            .sum();
+
+        limits.check_signature_bytes(size)
+    }
+
     fn from_proto(proto: Self::Proto) -> Result<Self, ProtoError> {
         let r = proto
             .r
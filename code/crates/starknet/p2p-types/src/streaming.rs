@@ -1,5 +1,5 @@
 use bytes::Bytes;
-use malachitebft_proto::Protobuf;
+use malachitebft_proto::{DecodeLimits, Protobuf};
 use malachitebft_starknet_p2p_proto as p2p_proto;
 
 pub struct StreamMessage {
@@ -25,6 +25,13 @@ pub enum StreamContent {
 impl Protobuf for StreamMessage {
     type Proto = p2p_proto::Stream;
 
+    fn check_limits(
+        proto: &Self::Proto,
+        limits: &DecodeLimits,
+    ) -> Result<(), malachitebft_proto::Error> {
+        limits.check_part_sequence(proto.sequence_number)
+    }
+
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn from_proto(proto: Self::Proto) -> Result<Self, malachitebft_proto::Error> {
         let content = match proto
@@ -153,6 +153,20 @@ impl Transactions {
 impl proto::Protobuf for Transactions {
     type Proto = p2p_proto::Transactions;
 
+    fn check_limits(proto: &Self::Proto, limits: &proto::DecodeLimits) -> Result<(), proto::Error> {
+        use malachitebft_starknet_p2p_proto::transaction::Txn;
+
+        limits.check_transactions_per_batch(proto.transactions.len())?;
+
+        for tx in &proto.transactions {
+            if let Some(Txn::Dummy(dummy)) = &tx.txn {
+                limits.check_transaction_size(dummy.bytes.len())?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn from_proto(proto: Self::Proto) -> Result<Self, proto::Error> {
         Ok(Self::new(
             proto
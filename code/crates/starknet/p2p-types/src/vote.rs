@@ -97,10 +97,7 @@ impl proto::Protobuf for Vote {
             vote_type,
             height: Height::new(proto.block_number, proto.fork_id),
             round: Round::new(proto.round),
-            block_hash: match proto.block_hash {
-                Some(block_hash) => NilOrVal::Val(BlockHash::from_proto(block_hash)?),
-                None => NilOrVal::Nil,
-            },
+            block_hash: proto::decode_nil_or_val(proto.block_hash)?,
             voter: Address::from_proto(
                 proto
                     .voter
@@ -117,10 +114,7 @@ impl proto::Protobuf for Vote {
             block_number: self.height.block_number,
             fork_id: self.height.fork_id,
             round: self.round.as_u32().expect("round should not be nil"),
-            block_hash: match &self.block_hash {
-                NilOrVal::Nil => None,
-                NilOrVal::Val(v) => Some(v.to_proto()?),
-            },
+            block_hash: proto::encode_nil_or_val(&self.block_hash)?,
             voter: Some(self.voter.to_proto()?),
             extension: self
                 .extension
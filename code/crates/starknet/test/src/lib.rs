@@ -1,7 +1,8 @@
 use core::fmt;
 use std::fs::{create_dir_all, remove_dir_all};
+use std::io;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
@@ -10,7 +11,7 @@ use eyre::bail;
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 use tokio::task::JoinSet;
-use tokio::time::{sleep, Duration};
+use tokio::time::{sleep, Duration, Instant};
 use tracing::{debug, error, error_span, info, Instrument, Span};
 
 use malachitebft_config::{
@@ -19,7 +20,14 @@ use malachitebft_config::{
 };
 use malachitebft_core_consensus::{SignedConsensusMsg, ValueToPropose};
 use malachitebft_core_types::{SignedVote, VotingPower};
+use malachitebft_engine::builder::NodeHandles;
+use malachitebft_engine::consensus::{
+    ConsensusEvent, ConsensusMsg, ConsensusRef, ConsensusSubscription,
+};
 use malachitebft_engine::util::events::{Event, RxEvent, TxEvent};
+use malachitebft_engine::wal::WalEntry;
+use malachitebft_starknet_host::codec::ProtobufCodec;
+use malachitebft_starknet_host::host::Clock;
 use malachitebft_starknet_host::spawn::spawn_node_actor;
 use malachitebft_starknet_host::types::MockContext;
 use malachitebft_starknet_host::types::{Height, PrivateKey, Validator, ValidatorSet};
@@ -67,6 +75,21 @@ pub struct TestParams {
     pub value_payload: ValuePayload,
     pub max_retain_blocks: usize,
     pub timeout_step: Duration,
+    /// Seed for generating the transactions and proofs of the blocks,
+    /// so that the same blocks are built for a given height and round across runs.
+    pub seed: Option<u64>,
+    /// Clock used for the deadlines when building blocks, defaults to the system clock.
+    pub clock: Option<Clock>,
+    /// Maximum number of parts buffered for a single proposal before it is discarded.
+    pub max_parts_per_value: usize,
+    /// Whether the proposer reports its progress to consensus while building a value.
+    pub report_build_progress: bool,
+    /// How long the application waits after a decision before starting the next height.
+    pub start_height_delay: Duration,
+    /// Whether the proposer builds an empty block when no transactions are available.
+    pub create_empty_blocks: bool,
+    /// Whether the proposer keeps a value built too late for its round to propose it in a later round.
+    pub reuse_late_values: bool,
 }
 
 impl Default for TestParams {
@@ -81,6 +104,13 @@ impl Default for TestParams {
             value_payload: ValuePayload::default(),
             max_retain_blocks: 50,
             timeout_step: Duration::from_secs(30),
+            seed: None,
+            clock: None,
+            max_parts_per_value: 10_000,
+            report_build_progress: false,
+            start_height_delay: Duration::ZERO,
+            create_empty_blocks: true,
+            reuse_late_values: false,
         }
     }
 }
@@ -97,16 +127,25 @@ impl TestParams {
         config.test.vote_extensions.size = self.vote_extensions.unwrap_or_default();
         config.test.max_retain_blocks = self.max_retain_blocks;
         config.consensus.timeouts.timeout_step = self.timeout_step;
+        config.test.seed = self.seed;
+        config.test.max_parts_per_value = self.max_parts_per_value;
+        config.test.report_build_progress = self.report_build_progress;
+        config.test.start_height_delay = self.start_height_delay;
+        config.test.create_empty_blocks = self.create_empty_blocks;
+        config.test.reuse_late_values = self.reuse_late_values;
     }
 }
 
 pub enum Step<S> {
     Crash(Duration),
+    CrashMempool(Duration),
     ResetDb,
     Restart(Duration),
     WaitUntil(u64),
     OnEvent(EventHandler<S>),
+    OnConsensusEvent(ConsensusEventHandler<S>),
     Expect(Expected),
+    ShutDown(Duration),
     Success,
     Fail(String),
 }
@@ -120,6 +159,12 @@ pub enum HandlerResult {
 pub type EventHandler<S> =
     Box<dyn Fn(Event<MockContext>, &mut S) -> Result<HandlerResult, eyre::Report> + Send + Sync>;
 
+pub type ConsensusEventHandler<S> = Box<
+    dyn Fn(ConsensusEvent<MockContext>, &mut S) -> Result<HandlerResult, eyre::Report>
+        + Send
+        + Sync,
+>;
+
 pub type NodeId = usize;
 
 pub struct TestNode<State = ()> {
@@ -184,6 +229,12 @@ impl<State> TestNode<State> {
         self
     }
 
+    /// Kill the mempool actor after the given duration, while the node keeps running
+    pub fn crash_mempool_after(&mut self, duration: Duration) -> &mut Self {
+        self.steps.push(Step::CrashMempool(duration));
+        self
+    }
+
     pub fn reset_db(&mut self) -> &mut Self {
         self.steps.push(Step::ResetDb);
         self
@@ -210,6 +261,17 @@ impl<State> TestNode<State> {
         self
     }
 
+    pub fn on_consensus_event<F>(&mut self, on_event: F) -> &mut Self
+    where
+        F: Fn(ConsensusEvent<MockContext>, &mut State) -> Result<HandlerResult, eyre::Report>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.steps.push(Step::OnConsensusEvent(Box::new(on_event)));
+        self
+    }
+
     pub fn expect_wal_replay(&mut self, at_height: u64) -> &mut Self {
         self.on_event(move |event, _| {
             let Event::WalReplayBegin(height, count) = event else {
@@ -279,6 +341,13 @@ impl<State> TestNode<State> {
         self
     }
 
+    /// Shut down the node gracefully, and check that it stopped within the given timeout
+    /// with its signed votes persisted in the WAL
+    pub fn shut_down_gracefully(&mut self, timeout: Duration) -> &mut Self {
+        self.steps.push(Step::ShutDown(timeout));
+        self
+    }
+
     pub fn success(&mut self) -> &mut Self {
         self.steps.push(Step::Success);
         self
@@ -375,11 +444,16 @@ where
     }
 
     pub async fn run_with_custom_config(self, timeout: Duration, params: TestParams) {
+        let clock = params.clock.clone();
         let configs = self.generate_custom_configs(params);
-        self.run_with_config(configs, timeout).await
+        self.run_nodes(configs, timeout, clock).await
     }
 
     pub async fn run_with_config(self, configs: Vec<Config>, timeout: Duration) {
+        self.run_nodes(configs, timeout, None).await
+    }
+
+    async fn run_nodes(self, configs: Vec<Config>, timeout: Duration, clock: Option<Clock>) {
         let _span = error_span!("test", id = %self.id).entered();
 
         let mut set = JoinSet::new();
@@ -391,6 +465,7 @@ where
             .zip(self.private_keys.into_iter())
         {
             let validator_set = self.validator_set.clone();
+            let clock = clock.clone();
 
             let home_dir = tempfile::TempDir::with_prefix(format!(
                 "informalsystems-malachitebft-starknet-test-{}",
@@ -402,7 +477,8 @@ where
             set.spawn(
                 async move {
                     let id = node.id;
-                    let result = run_node(node, home_dir, config, validator_set, private_key).await;
+                    let result =
+                        run_node(node, home_dir, config, validator_set, private_key, clock).await;
                     (id, result)
                 }
                 .in_current_span(),
@@ -459,6 +535,7 @@ async fn run_node<S>(
     config: Config,
     validator_set: ValidatorSet,
     private_key: PrivateKey,
+    clock: Option<Clock>,
 ) -> TestResult {
     sleep(node.start_delay).await;
 
@@ -468,17 +545,20 @@ async fn run_node<S>(
     let mut rx_event = tx_event.subscribe();
     let rx_event_bg = tx_event.subscribe();
 
-    let (mut actor_ref, mut handle) = spawn_node_actor(
+    let (mut handles, mut mempool) = spawn_node_actor(
         config.clone(),
         home_dir.clone(),
         validator_set.clone(),
         private_key,
         Some(node.start_height),
         tx_event,
+        clock.clone(),
         Span::current(),
     )
     .await;
 
+    let mut consensus_events = subscribe(&handles.consensus).await;
+
     let decisions = Arc::new(AtomicUsize::new(0));
     let current_height = Arc::new(AtomicUsize::new(0));
 
@@ -532,10 +612,23 @@ async fn run_node<S>(
                 info!("Node will crash at height {height}");
                 sleep(after).await;
 
-                actor_ref.kill_and_wait(None).await.expect("Node must stop");
+                handles
+                    .node
+                    .kill_and_wait(None)
+                    .await
+                    .expect("Node must stop");
 
                 bg.abort();
-                handle.abort();
+                handles.handle.abort();
+            }
+
+            Step::CrashMempool(after) => {
+                let height = current_height.load(Ordering::SeqCst);
+
+                info!("Mempool will crash at height {height}");
+                sleep(after).await;
+
+                mempool.get().kill();
             }
 
             Step::ResetDb => {
@@ -556,13 +649,14 @@ async fn run_node<S>(
                 let new_rx_event_bg = tx_event.subscribe();
 
                 info!("Spawning node");
-                let (new_actor_ref, new_handle) = spawn_node_actor(
+                let (new_handles, new_mempool) = spawn_node_actor(
                     config.clone(),
                     home_dir.clone(),
                     validator_set.clone(),
                     private_key,
                     Some(node.start_height),
                     tx_event,
+                    clock.clone(),
                     tracing::Span::current(),
                 )
                 .await;
@@ -571,8 +665,9 @@ async fn run_node<S>(
 
                 bg = spawn_bg(new_rx_event_bg);
 
-                actor_ref = new_actor_ref;
-                handle = new_handle;
+                consensus_events = subscribe(&new_handles.consensus).await;
+                handles = new_handles;
+                mempool = new_mempool;
                 rx_event = new_rx_event;
             }
 
@@ -586,9 +681,27 @@ async fn run_node<S>(
                             break 'inner;
                         }
                         Err(e) => {
-                            actor_ref.stop(Some("Test failed".to_string()));
-                            handle.abort();
                             bg.abort();
+                            shutdown(handles).await;
+
+                            return TestResult::Failure(e.to_string());
+                        }
+                    }
+                }
+            }
+
+            Step::OnConsensusEvent(on_event) => {
+                'inner: while let Some(event) = consensus_events.recv().await {
+                    match on_event(event, &mut node.state) {
+                        Ok(HandlerResult::WaitForNextEvent) => {
+                            continue 'inner;
+                        }
+                        Ok(HandlerResult::ContinueTest) => {
+                            break 'inner;
+                        }
+                        Err(e) => {
+                            bg.abort();
+                            shutdown(handles).await;
 
                             return TestResult::Failure(e.to_string());
                         }
@@ -599,9 +712,8 @@ async fn run_node<S>(
             Step::Expect(expected) => {
                 let actual = decisions.load(Ordering::SeqCst);
 
-                actor_ref.stop(Some("Test is over".to_string()));
-                handle.abort();
                 bg.abort();
+                shutdown(handles).await;
 
                 if expected.check(actual) {
                     return TestResult::Success(format!(
@@ -614,14 +726,42 @@ async fn run_node<S>(
                 }
             }
 
+            Step::ShutDown(timeout) => {
+                info!("Shutting down gracefully within {timeout:?}");
+
+                bg.abort();
+                handles.shutdown_timeout = timeout;
+
+                let start = Instant::now();
+
+                if let Err(e) = handles.shutdown(true).await {
+                    return TestResult::Failure(format!("Node failed to shut down: {e}"));
+                }
+
+                let elapsed = start.elapsed();
+
+                if elapsed > timeout {
+                    return TestResult::Failure(format!(
+                        "Node took {elapsed:?} to shut down, expected at most {timeout:?}"
+                    ));
+                }
+
+                return match wal_votes(&home_dir) {
+                    Ok(0) => TestResult::Failure("No vote was found in the WAL".to_string()),
+                    Ok(votes) => TestResult::Success(format!(
+                        "Shut down in {elapsed:?} with {votes} votes in the WAL"
+                    )),
+                    Err(e) => TestResult::Failure(format!("Failed to read the WAL: {e}")),
+                };
+            }
+
             Step::Success => {
                 break;
             }
 
             Step::Fail(reason) => {
-                actor_ref.stop(Some("Test failed".to_string()));
-                handle.abort();
                 bg.abort();
+                shutdown(handles).await;
 
                 return TestResult::Failure(reason);
             }
@@ -631,6 +771,41 @@ async fn run_node<S>(
     return TestResult::Success("OK".to_string());
 }
 
+async fn shutdown(handles: NodeHandles<MockContext>) {
+    match handles.shutdown(false).await {
+        // The node has already been killed by a previous step
+        Err(e) if e.is_cancelled() => (),
+        Err(e) => error!("Failed to shut down node: {e}"),
+        Ok(()) => (),
+    }
+}
+
+/// The number of votes stored in the WAL of the node with the given home directory
+fn wal_votes(home_dir: &Path) -> io::Result<usize> {
+    let mut log = malachitebft_wal::Log::open(home_dir.join("wal").join("consensus.wal"))?;
+
+    if log.is_empty() {
+        return Ok(0);
+    }
+
+    let codec = ProtobufCodec::default();
+    let mut votes = 0;
+
+    for entry in log.iter()? {
+        let entry = WalEntry::<MockContext>::decode(&codec, io::Cursor::new(entry?))?;
+
+        if let WalEntry::ConsensusMsg(SignedConsensusMsg::Vote(_)) = entry {
+            votes += 1;
+        }
+    }
+
+    Ok(votes)
+}
+
+async fn subscribe(consensus: &ConsensusRef<MockContext>) -> ConsensusSubscription<MockContext> {
+    ractor::call!(consensus, ConsensusMsg::Subscribe).expect("Consensus must accept subscriptions")
+}
+
 pub fn init_logging(test_module: &str) {
     use tracing_subscriber::util::SubscriberInitExt;
     use tracing_subscriber::{EnvFilter, FmtSubscriber};
@@ -719,11 +894,13 @@ pub fn make_node_config<S>(test: &Test<S>, i: usize) -> NodeConfig {
             },
             max_tx_count: 10000,
             gossip_batch_size: 100,
+            ..Default::default()
         },
         sync: SyncConfig {
             enabled: true,
             status_update_interval: Duration::from_secs(2),
             request_timeout: Duration::from_secs(5),
+            batch_size: 5,
         },
         metrics: MetricsConfig {
             enabled: false,
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use eyre::bail;
+use tracing::info;
+
+use malachitebft_core_types::Round;
+use malachitebft_engine::util::events::Event;
+use malachitebft_starknet_host::types::Height;
+
+use informalsystems_malachitebft_starknet_test::{
+    init_logging, HandlerResult, TestBuilder, TestParams,
+};
+
+/// Progress of the value build reported last, if any
+#[derive(Default)]
+struct State {
+    last: Option<(Height, Round, u64, usize)>,
+}
+
+#[tokio::test]
+pub async fn progress_is_reported_after_each_part() {
+    init_logging(module_path!());
+
+    let mut test = TestBuilder::<State>::new();
+
+    for _ in 0..3 {
+        test.add_node()
+            .start()
+            // Every node is the proposer of one of the first three heights
+            .on_event(|event, state| match event {
+                Event::BuildProgress(height, round, sequence, bytes) => {
+                    let expected = match state.last {
+                        Some((h, r, s, _)) if (h, r) == (height, round) => s + 1,
+                        _ => 0,
+                    };
+
+                    if sequence != expected {
+                        bail!("Expected progress for part {expected}, got part {sequence}");
+                    }
+
+                    if let Some((_, _, _, last_bytes)) = state.last.filter(|_| sequence > 0) {
+                        if bytes < last_bytes {
+                            bail!("Build progress went from {last_bytes} to {bytes} bytes");
+                        }
+                    }
+
+                    state.last = Some((height, round, sequence, bytes));
+                    Ok(HandlerResult::WaitForNextEvent)
+                }
+
+                // The value is handed to consensus once all its parts were built and reported
+                Event::ProposedValue(value) => {
+                    let Some((height, round, sequence, bytes)) = state.last else {
+                        bail!("Value proposed without any build progress");
+                    };
+
+                    if (height, round) != (value.height, value.round) {
+                        bail!("Last progress was reported for another build");
+                    }
+
+                    // Init, transactions, proof and Fin
+                    if sequence < 3 {
+                        bail!(
+                            "Expected progress for at least 4 parts, got {}",
+                            sequence + 1
+                        );
+                    }
+
+                    info!(%height, %round, parts = sequence + 1, %bytes, "Value built");
+                    Ok(HandlerResult::ContinueTest)
+                }
+
+                _ => Ok(HandlerResult::WaitForNextEvent),
+            })
+            .success();
+    }
+
+    test.build()
+        .run_with_custom_config(
+            Duration::from_secs(30),
+            TestParams {
+                report_build_progress: true,
+                ..Default::default()
+            },
+        )
+        .await
+}
+
+#[tokio::test]
+pub async fn progress_is_not_reported_unless_enabled() {
+    init_logging(module_path!());
+
+    const HEIGHT: u64 = 3;
+
+    let mut test = TestBuilder::<()>::new();
+
+    for _ in 0..3 {
+        test.add_node()
+            .start()
+            .on_event(|event, _| match event {
+                Event::BuildProgress(height, round, sequence, _) => {
+                    bail!(
+                        "Unexpected progress for part {sequence} at height {height}, round {round}"
+                    )
+                }
+
+                Event::Decided(certificate) if certificate.height.as_u64() == HEIGHT => {
+                    Ok(HandlerResult::ContinueTest)
+                }
+
+                _ => Ok(HandlerResult::WaitForNextEvent),
+            })
+            .success();
+    }
+
+    test.build()
+        .run_with_custom_config(Duration::from_secs(30), TestParams::default())
+        .await
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use eyre::bail;
+
+use informalsystems_malachitebft_starknet_test::{init_logging, HandlerResult, TestBuilder};
+use malachitebft_core_types::{NilOrVal, VoteType};
+use malachitebft_engine::consensus::ConsensusEvent;
+use malachitebft_starknet_host::types::{Height, MockContext};
+
+type Events = Vec<ConsensusEvent<MockContext>>;
+
+/// Check the events received for a single height, up to and including its decision
+fn check_decided_height(events: &Events) -> eyre::Result<()> {
+    let Some(ConsensusEvent::RoundStarted { round, .. }) = events.first() else {
+        bail!("Expected the height to start with a new round, got {events:?}");
+    };
+
+    if round.as_i64() != 0 {
+        bail!("Expected the first round to be 0, got {round}");
+    }
+
+    let Some(ConsensusEvent::Decided {
+        round: decided_round,
+        value_id,
+        ..
+    }) = events.last()
+    else {
+        bail!("Expected the height to end with a decision, got {events:?}");
+    };
+
+    let has_proposal = events.iter().any(|event| {
+        matches!(
+            event,
+            ConsensusEvent::ProposalReceived { round, value_id: id, .. }
+                if round == decided_round && id == value_id
+        )
+    });
+
+    if !has_proposal {
+        bail!("Expected to receive the proposal for the decided value, got {events:?}");
+    }
+
+    // Votes may arrive before the full proposed value has been assembled
+    let has_precommit = events.iter().any(|event| {
+        matches!(
+            event,
+            ConsensusEvent::VoteReceived {
+                round,
+                vote_type: VoteType::Precommit,
+                value_id: NilOrVal::Val(id),
+                ..
+            } if round == decided_round && id == value_id
+        )
+    });
+
+    if !has_precommit {
+        bail!("Expected to receive precommits for the decided value, got {events:?}");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn consensus_events_for_decided_height() {
+    init_logging(module_path!());
+
+    const HEIGHT: u64 = 2;
+
+    let mut test = TestBuilder::<Events>::new();
+
+    test.add_node()
+        .start()
+        .on_consensus_event(|event, events| {
+            if event.height() != Height::new(HEIGHT, 1) {
+                return Ok(HandlerResult::WaitForNextEvent);
+            }
+
+            let decided = matches!(event, ConsensusEvent::Decided { .. });
+            events.push(event);
+
+            if !decided {
+                return Ok(HandlerResult::WaitForNextEvent);
+            }
+
+            check_decided_height(events)?;
+
+            Ok(HandlerResult::ContinueTest)
+        })
+        .success();
+
+    test.add_node().start().wait_until(HEIGHT + 1).success();
+    test.add_node().start().wait_until(HEIGHT + 1).success();
+
+    test.build().run(Duration::from_secs(30)).await
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use eyre::bail;
+use tracing::info;
+
+use malachitebft_engine::util::events::Event;
+use malachitebft_starknet_host::host::proposal::empty_block_hash;
+
+use informalsystems_malachitebft_starknet_test::{
+    init_logging, HandlerResult, TestBuilder, TestParams,
+};
+
+#[tokio::test]
+pub async fn heights_advance_without_transactions() {
+    init_logging(module_path!());
+
+    const HEIGHT: u64 = 5;
+
+    let mut test = TestBuilder::<()>::new();
+
+    test.add_node()
+        .start()
+        // The first decided block must be empty
+        .on_event(|event, _| {
+            let Event::Decided(certificate) = event else {
+                return Ok(HandlerResult::WaitForNextEvent);
+            };
+
+            if certificate.value_id != empty_block_hash() {
+                bail!(
+                    "Expected an empty block at height {}, got {}",
+                    certificate.height,
+                    certificate.value_id
+                );
+            }
+
+            info!("Decided empty block at height {}", certificate.height);
+            Ok(HandlerResult::ContinueTest)
+        })
+        .wait_until(HEIGHT)
+        .success();
+
+    test.add_node().start().wait_until(HEIGHT).success();
+    test.add_node().start().wait_until(HEIGHT).success();
+    test.add_node().start().wait_until(HEIGHT).success();
+
+    test.build()
+        .run_with_custom_config(
+            Duration::from_secs(30),
+            TestParams {
+                // Do not reap nor generate any transaction, so that every block is empty
+                txs_per_part: 0,
+                create_empty_blocks: true,
+                ..Default::default()
+            },
+        )
+        .await
+}
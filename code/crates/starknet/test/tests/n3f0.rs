@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-use informalsystems_malachitebft_starknet_test::{init_logging, TestBuilder};
+use informalsystems_malachitebft_starknet_test::{init_logging, TestBuilder, TestParams};
 
 #[tokio::test]
 pub async fn all_correct_nodes() {
@@ -16,3 +16,26 @@ pub async fn all_correct_nodes() {
 
     test.build().run(Duration::from_secs(30)).await
 }
+
+#[tokio::test]
+pub async fn delayed_start_height() {
+    init_logging(module_path!());
+
+    const HEIGHT: u64 = 3;
+
+    let mut test = TestBuilder::<()>::new();
+
+    test.add_node().start().wait_until(HEIGHT).success();
+    test.add_node().start().wait_until(HEIGHT).success();
+    test.add_node().start().wait_until(HEIGHT).success();
+
+    test.build()
+        .run_with_custom_config(
+            Duration::from_secs(30),
+            TestParams {
+                start_height_delay: Duration::from_millis(200),
+                ..Default::default()
+            },
+        )
+        .await
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use eyre::bail;
+use tokio::time::Instant;
+use tracing::info;
+
+use malachitebft_core_types::Round;
+use malachitebft_engine::util::events::Event;
+
+use informalsystems_malachitebft_starknet_test::{
+    init_logging, HandlerResult, TestBuilder, TestParams,
+};
+
+#[derive(Default)]
+struct State {
+    round_started: Option<(Round, Instant)>,
+}
+
+// With two validators, the proposer of round 0 at height 1 is the proposer of round 2 as well.
+//
+// Every value takes 2s to build, longer than the propose timeout of rounds 0 (1s) and 1 (1.5s),
+// so that no value is proposed in those rounds. The value built for round 0 is ready before
+// round 2 starts though, and must be proposed right away in that round instead of building a new one,
+// which would take longer than the propose timeout of round 2 (2s) to reach the other validator.
+#[tokio::test]
+pub async fn late_value_is_proposed_in_a_later_round() {
+    init_logging(module_path!());
+
+    let mut test = TestBuilder::<State>::new();
+
+    for _ in 0..2 {
+        test.add_node()
+            .start()
+            .on_event(|event, state| match event {
+                Event::StartedRound(height, round) if height.as_u64() == 1 => {
+                    state.round_started = Some((round, Instant::now()));
+                    Ok(HandlerResult::WaitForNextEvent)
+                }
+
+                Event::ProposedValue(value) if value.height.as_u64() == 1 => {
+                    if value.round != Round::new(2) {
+                        bail!("Unexpected value proposed at round {}", value.round);
+                    }
+
+                    let Some((Round::Some(2), started)) = state.round_started else {
+                        bail!("Value proposed before round 2 started");
+                    };
+
+                    let elapsed = started.elapsed();
+                    info!("Proposed value {:?} after {elapsed:?}", value.value);
+
+                    if elapsed > Duration::from_millis(500) {
+                        bail!("Value was not proposed right away, took {elapsed:?}");
+                    }
+
+                    Ok(HandlerResult::WaitForNextEvent)
+                }
+
+                Event::Decided(certificate) if certificate.height.as_u64() == 1 => {
+                    if certificate.round != Round::new(2) {
+                        bail!("Expected a decision at round 2, got {}", certificate.round);
+                    }
+
+                    Ok(HandlerResult::ContinueTest)
+                }
+
+                _ => Ok(HandlerResult::WaitForNextEvent),
+            })
+            .success();
+    }
+
+    let test = test.build();
+
+    let mut configs = test.generate_custom_configs(TestParams {
+        txs_per_part: 10,
+        reuse_late_values: true,
+        ..Default::default()
+    });
+
+    for config in &mut configs {
+        config.consensus.timeouts.timeout_propose = Duration::from_secs(1);
+        config.consensus.timeouts.timeout_propose_delta = Duration::from_millis(500);
+        config.test.exec_time_per_tx = Duration::from_millis(200);
+    }
+
+    test.run_with_config(configs, Duration::from_secs(30)).await
+}
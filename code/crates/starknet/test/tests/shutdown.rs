@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use tracing::info;
+
+use informalsystems_malachitebft_starknet_test::{init_logging, HandlerResult, TestBuilder};
+
+#[tokio::test]
+pub async fn graceful_shutdown_in_the_middle_of_a_round() {
+    init_logging(module_path!());
+
+    const SHUTDOWN_HEIGHT: u64 = 3;
+    const HEIGHT: u64 = 6;
+
+    let mut test = TestBuilder::<()>::new();
+
+    test.add_node()
+        .with_voting_power(10)
+        .start()
+        .wait_until(HEIGHT)
+        .success();
+    test.add_node()
+        .with_voting_power(10)
+        .start()
+        .wait_until(HEIGHT)
+        .success();
+
+    test.add_node()
+        .with_voting_power(1)
+        .start()
+        .wait_until(SHUTDOWN_HEIGHT)
+        // Shut down right after voting, while the round is still in progress
+        .on_vote(|_vote, _| {
+            info!("Node voted, shutting down");
+            Ok(HandlerResult::ContinueTest)
+        })
+        // Check that the node stops in time, with its votes in the WAL
+        .shut_down_gracefully(Duration::from_secs(5));
+
+    test.build().run(Duration::from_secs(60)).await
+}
@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use tracing::info;
+
+use malachitebft_engine::supervisor::Component;
+use malachitebft_engine::util::events::Event;
+
+use informalsystems_malachitebft_starknet_test::{init_logging, HandlerResult, TestBuilder};
+
+#[tokio::test]
+pub async fn mempool_is_restarted_after_crash() {
+    init_logging(module_path!());
+
+    const CRASH_HEIGHT: u64 = 2;
+    const HEIGHT: u64 = 6;
+
+    let mut test = TestBuilder::<()>::new();
+
+    test.add_node()
+        .start()
+        .wait_until(CRASH_HEIGHT)
+        // Kill the mempool in the middle of the height
+        .crash_mempool_after(Duration::from_millis(100))
+        // Wait until the node restarts it
+        .on_event(|event, _| match event {
+            Event::DependencyRestarted(Component::Mempool) => {
+                info!("Mempool has been restarted");
+                Ok(HandlerResult::ContinueTest)
+            }
+            _ => Ok(HandlerResult::WaitForNextEvent),
+        })
+        // Check that the node still builds a value at a later height
+        .on_proposed_value(|value, _| {
+            if value.height.as_u64() > CRASH_HEIGHT {
+                info!("Proposed value at height {}", value.height);
+                Ok(HandlerResult::ContinueTest)
+            } else {
+                Ok(HandlerResult::WaitForNextEvent)
+            }
+        })
+        .wait_until(HEIGHT)
+        .success();
+
+    test.add_node().start().wait_until(HEIGHT).success();
+    test.add_node().start().wait_until(HEIGHT).success();
+
+    test.build().run(Duration::from_secs(60)).await
+}
@@ -1,7 +1,13 @@
 use std::time::Duration;
 
-use informalsystems_malachitebft_starknet_test::{init_logging, TestBuilder, TestParams};
+use eyre::bail;
+use tracing::info;
+
+use informalsystems_malachitebft_starknet_test::{
+    init_logging, HandlerResult, TestBuilder, TestParams,
+};
 use malachitebft_config::ValuePayload;
+use malachitebft_engine::util::events::Event;
 
 pub async fn crash_restart_from_start(params: TestParams) {
     init_logging(module_path!());
@@ -200,3 +206,94 @@ pub async fn start_late() {
         )
         .await
 }
+
+#[tokio::test]
+pub async fn catch_up_when_starting_a_height_behind() {
+    init_logging(module_path!());
+
+    const HEIGHT: u64 = 5;
+
+    let mut test = TestBuilder::<()>::new();
+
+    test.add_node()
+        .with_voting_power(10)
+        .start()
+        .wait_until(HEIGHT * 2)
+        .success();
+
+    test.add_node()
+        .with_voting_power(10)
+        .start()
+        .wait_until(HEIGHT * 2)
+        .success();
+
+    test.add_node()
+        .with_voting_power(10)
+        .start()
+        .wait_until(HEIGHT * 2)
+        .success();
+
+    // Start the 4th node late, so that it starts a height while holding
+    // the votes of its peers for a higher one, and has to catch up via sync.
+    test.add_node()
+        .with_voting_power(5)
+        .start_after(1, Duration::from_secs(5))
+        .on_event(|event, _| match event {
+            Event::CatchingUp(height, peers_height) => {
+                info!("Catching up from height {height} to height {peers_height}");
+
+                if peers_height <= height {
+                    bail!("Catching up from height {height} to lower height {peers_height}");
+                }
+
+                Ok(HandlerResult::ContinueTest)
+            }
+            _ => Ok(HandlerResult::WaitForNextEvent),
+        })
+        // Instead of getting stuck at the height it is catching up from
+        .wait_until(HEIGHT)
+        .success();
+
+    test.build()
+        .run_with_custom_config(
+            Duration::from_secs(30),
+            TestParams {
+                enable_sync: true,
+                ..Default::default()
+            },
+        )
+        .await
+}
+
+#[tokio::test]
+pub async fn catch_up_from_far_behind() {
+    const HEIGHT: u64 = 20;
+
+    let mut test = TestBuilder::<()>::new();
+
+    for _ in 0..3 {
+        test.add_node()
+            .with_voting_power(10)
+            .start()
+            .wait_until(HEIGHT + 5)
+            .success();
+    }
+
+    // Start the 4th node once the others are well ahead of it,
+    // it has to sync all the heights it missed in batches to catch up.
+    test.add_node()
+        .with_voting_power(5)
+        .start_after(1, Duration::from_secs(20))
+        .wait_until(HEIGHT + 5)
+        .success();
+
+    test.build()
+        .run_with_custom_config(
+            Duration::from_secs(90),
+            TestParams {
+                enable_sync: true,
+                ..Default::default()
+            },
+        )
+        .await
+}
@@ -82,6 +82,10 @@ pub enum Input<Ctx: Context> {
     /// Consensus needs a vote set for the height and round for recovery.
     GetVoteSet(Ctx::Height, Round),
 
+    /// Consensus is at the given height while its peers have already decided it,
+    /// and needs the decided value for that height to catch up with them.
+    CatchUp(Ctx::Height),
+
     /// A VoteSet request has been received from a peer
     VoteSetRequest(InboundRequestId, PeerId, VoteSetRequest<Ctx>),
 
@@ -128,6 +132,7 @@ where
         Input::GetVoteSet(height, round) => {
             on_get_vote_set(co, state, metrics, height, round).await
         }
+        Input::CatchUp(height) => on_catch_up(co, state, metrics, height).await,
         Input::VoteSetRequest(request_id, peer_id, request) => {
             on_vote_set_request(co, state, metrics, request_id, peer_id, request).await
         }
@@ -190,7 +195,7 @@ where
 
         // We are lagging behind one of our peer at least,
         // request sync from any peer already at or above that peer's height.
-        request_value(co, state, metrics).await?;
+        request_values(co, state, metrics).await?;
     }
 
     Ok(())
@@ -219,8 +224,8 @@ where
 
 #[tracing::instrument(skip_all)]
 pub async fn on_value_response<Ctx>(
-    _co: Co<Ctx>,
-    _state: &mut State<Ctx>,
+    co: Co<Ctx>,
+    state: &mut State<Ctx>,
     metrics: &Metrics,
     request_id: OutboundRequestId,
     peer: PeerId,
@@ -233,6 +238,13 @@ where
 
     metrics.decided_value_response_received(response.height.as_u64());
 
+    if response.value.is_none() && response.height > state.tip_height {
+        warn!(height = %response.height, %peer, "Received empty response, requesting value from another peer");
+
+        state.remove_pending_decided_value_request(response.height);
+        request_value_from_other_peer(&co, state, metrics, response.height, peer).await?;
+    }
+
     Ok(())
 }
 
@@ -251,11 +263,40 @@ where
 
     // Check if there is any peer already at or above the height we just started,
     // and request sync from that peer in order to catch up.
-    request_value(co, state, metrics).await?;
+    request_values(co, state, metrics).await?;
 
     Ok(())
 }
 
+pub async fn on_catch_up<Ctx>(
+    co: Co<Ctx>,
+    state: &mut State<Ctx>,
+    metrics: &Metrics,
+    height: Ctx::Height,
+) -> Result<(), Error<Ctx>>
+where
+    Ctx: Context,
+{
+    if state.has_pending_decided_value_request(&height) {
+        debug!(%height, "Already have a pending value request for this height");
+        return Ok(());
+    }
+
+    // The status of our peers may not have reached us yet, since they decided this height,
+    // in which case any of them can serve the value.
+    let Some(peer) = state
+        .random_peer_with_value(height)
+        .or_else(|| state.random_peer_with_history(height))
+    else {
+        warn!(%height, "No peer to request the value from in order to catch up");
+        return Ok(());
+    };
+
+    info!(%height, %peer, "Catching up with our peers, requesting value");
+
+    request_value_from_peer(&co, state, metrics, height, peer).await
+}
+
 pub async fn on_update_height<Ctx>(
     _co: Co<Ctx>,
     state: &mut State<Ctx>,
@@ -269,7 +310,7 @@ where
         debug!(%height, "Update height");
 
         state.tip_height = height;
-        state.remove_pending_decided_value_request(height);
+        state.remove_pending_decided_value_requests_up_to(height);
     }
 
     Ok(())
@@ -315,7 +356,7 @@ where
 }
 
 pub async fn on_sync_request_timed_out<Ctx>(
-    _co: Co<Ctx>,
+    co: Co<Ctx>,
     state: &mut State<Ctx>,
     metrics: &Metrics,
     peer_id: PeerId,
@@ -330,6 +371,10 @@ where
             warn!(%peer_id, %height, "Value request timed out");
             state.remove_pending_decided_value_request(height);
             metrics.decided_value_request_timed_out(height.as_u64());
+
+            if height > state.tip_height {
+                request_value_from_other_peer(&co, state, metrics, height, peer_id).await?;
+            }
         }
         Request::VoteSetRequest(vote_set_request) => {
             let height = vote_set_request.height;
@@ -343,10 +388,11 @@ where
     Ok(())
 }
 
-/// If there are no pending requests for the sync height,
-/// and there is peer at a higher height than our sync height,
-/// then sync from that peer.
-async fn request_value<Ctx>(
+/// Request the values for a batch of heights starting at the sync height.
+///
+/// For each height in the batch without a pending request, the value is requested
+/// from a random peer which has it. Stops at the first height that no peer has.
+async fn request_values<Ctx>(
     co: Co<Ctx>,
     state: &mut State<Ctx>,
     metrics: &Metrics,
@@ -354,22 +400,44 @@ async fn request_value<Ctx>(
 where
     Ctx: Context,
 {
-    let sync_height = state.sync_height;
-
-    if state.has_pending_decided_value_request(&sync_height) {
-        debug!(sync.height = %sync_height, "Already have a pending value request for this height");
-        return Ok(());
-    }
+    let mut height = state.sync_height;
+
+    for _ in 0..state.batch_size {
+        if state.has_pending_decided_value_request(&height) {
+            debug!(%height, "Already have a pending value request for this height");
+        } else if let Some(peer) = state.random_peer_with_value(height) {
+            request_value_from_peer(&co, state, metrics, height, peer).await?;
+        } else {
+            break;
+        }
 
-    if let Some(peer) = state.random_peer_with_value(sync_height) {
-        request_value_from_peer(co, state, metrics, sync_height, peer).await?;
+        height = height.increment();
     }
 
     Ok(())
 }
 
+/// Request the value for the given height from a peer other than the given one, if any.
+async fn request_value_from_other_peer<Ctx>(
+    co: &Co<Ctx>,
+    state: &mut State<Ctx>,
+    metrics: &Metrics,
+    height: Ctx::Height,
+    except: PeerId,
+) -> Result<(), Error<Ctx>>
+where
+    Ctx: Context,
+{
+    let Some(peer) = state.random_peer_with_value_except(height, except) else {
+        warn!(%height, "No other peer to request value from");
+        return Ok(());
+    };
+
+    request_value_from_peer(co, state, metrics, height, peer).await
+}
+
 async fn request_value_from_peer<Ctx>(
-    co: Co<Ctx>,
+    co: &Co<Ctx>,
     state: &mut State<Ctx>,
     metrics: &Metrics,
     height: Ctx::Height,
@@ -408,12 +476,7 @@ where
     info!("Requesting sync from another peer");
     state.remove_pending_decided_value_request(certificate.height);
 
-    let Some(peer) = state.random_peer_with_value_except(certificate.height, from) else {
-        error!("No other peer to request sync from");
-        return Ok(());
-    };
-
-    request_value_from_peer(co, state, metrics, certificate.height, peer).await
+    request_value_from_other_peer(&co, state, metrics, certificate.height, from).await
 }
 
 pub async fn on_get_vote_set<Ctx>(
@@ -20,6 +20,10 @@ where
     /// Height currently syncing.
     pub sync_height: Ctx::Height,
 
+    /// Maximum number of heights, starting at the sync height, for which
+    /// decided values are requested at the same time.
+    pub batch_size: usize,
+
     /// Decided value requests for these heights have been sent out to peers.
     pub pending_decided_value_requests: BTreeMap<Ctx::Height, PeerId>,
 
@@ -35,11 +39,12 @@ impl<Ctx> State<Ctx>
 where
     Ctx: Context,
 {
-    pub fn new(rng: Box<dyn rand::RngCore + Send>) -> Self {
+    pub fn new(rng: Box<dyn rand::RngCore + Send>, batch_size: usize) -> Self {
         Self {
             rng,
             tip_height: Ctx::Height::default(),
             sync_height: Ctx::Height::default(),
+            batch_size: batch_size.max(1),
             pending_decided_value_requests: BTreeMap::new(),
             pending_vote_set_requests: BTreeMap::new(),
             peers: BTreeMap::new(),
@@ -62,15 +67,16 @@ where
         self.random_peer_with_value(tip_height)
     }
 
-    /// Select at random a peer that that we know is at or above the given height.
+    /// Select at random a peer that that we know has the value for the given height,
+    /// ie. which is at or above that height and has not pruned it from its history.
     pub fn random_peer_with_value(&mut self, height: Ctx::Height) -> Option<PeerId> {
         self.peers
             .iter()
-            .filter_map(move |(&peer, status)| (status.height >= height).then_some(peer))
+            .filter_map(move |(&peer, status)| has_value(status, height).then_some(peer))
             .choose_stable(&mut self.rng)
     }
 
-    /// Select at random a peer that that we know is at or above the given height,
+    /// Select at random a peer that that we know has the value for the given height,
     /// except the given one.
     pub fn random_peer_with_value_except(
         &mut self,
@@ -79,11 +85,22 @@ where
     ) -> Option<PeerId> {
         self.peers
             .iter()
-            .filter_map(move |(&peer, status)| (status.height >= height).then_some(peer))
+            .filter_map(move |(&peer, status)| has_value(status, height).then_some(peer))
             .filter(|&peer| peer != except)
             .choose_stable(&mut self.rng)
     }
 
+    /// Select at random a peer that has not pruned the value for the given height from its history,
+    /// whether or not it has told us that it has reached that height yet.
+    pub fn random_peer_with_history(&mut self, height: Ctx::Height) -> Option<PeerId> {
+        self.peers
+            .iter()
+            .filter_map(move |(&peer, status)| {
+                (status.history_min_height <= height).then_some(peer)
+            })
+            .choose_stable(&mut self.rng)
+    }
+
     pub fn store_pending_decided_value_request(&mut self, height: Ctx::Height, peer: PeerId) {
         self.pending_decided_value_requests.insert(height, peer);
     }
@@ -92,9 +109,16 @@ where
         self.pending_decided_value_requests.remove(&height);
     }
 
+    /// Remove the pending requests for the given height and all heights below it.
+    pub fn remove_pending_decided_value_requests_up_to(&mut self, height: Ctx::Height) {
+        self.pending_decided_value_requests
+            .retain(|&h, _| h > height);
+    }
+
     pub fn has_pending_decided_value_request(&self, height: &Ctx::Height) -> bool {
         self.pending_decided_value_requests.contains_key(height)
     }
+
     pub fn store_pending_vote_set_request(
         &mut self,
         height: Ctx::Height,
@@ -113,3 +137,7 @@ where
             .contains_key(&(height, round))
     }
 }
+
+fn has_value<Ctx: Context>(status: &Status<Ctx>, height: Ctx::Height) -> bool {
+    status.history_min_height <= height && height <= status.height
+}
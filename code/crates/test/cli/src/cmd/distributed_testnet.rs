@@ -269,6 +269,7 @@ fn generate_distributed_config(
                     ephemeral_connection_timeout: Duration::from_millis(
                         ephemeral_connection_timeout_ms,
                     ),
+                    persistent_peers: vec![],
                 },
                 transport,
                 ..Default::default()
@@ -286,17 +287,20 @@ fn generate_distributed_config(
                     num_outbound_peers: 0,
                     num_inbound_peers: 0,
                     ephemeral_connection_timeout: Duration::from_secs(0),
+                    persistent_peers: vec![],
                 },
                 transport,
                 ..Default::default()
             },
             max_tx_count: 10000,
             gossip_batch_size: 0,
+            ..Default::default()
         },
         sync: SyncConfig {
             enabled: false,
             status_update_interval: Duration::from_secs(0),
             request_timeout: Duration::from_secs(0),
+            batch_size: 0,
         },
         metrics: MetricsConfig {
             enabled: true,
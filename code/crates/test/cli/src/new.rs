@@ -120,6 +120,7 @@ pub fn generate_config(
                     ephemeral_connection_timeout: Duration::from_millis(
                         ephemeral_connection_timeout_ms,
                     ),
+                    persistent_peers: vec![],
                 },
                 transport,
                 ..Default::default()
@@ -142,12 +143,14 @@ pub fn generate_config(
                     ephemeral_connection_timeout: Duration::from_millis(
                         ephemeral_connection_timeout_ms,
                     ),
+                    persistent_peers: vec![],
                 },
                 transport,
                 ..Default::default()
             },
             max_tx_count: 10000,
             gossip_batch_size: 0,
+            ..Default::default()
         },
         sync: Default::default(),
         metrics: MetricsConfig {
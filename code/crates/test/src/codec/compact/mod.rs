@@ -0,0 +1,900 @@
+//! A compact, fixed-layout binary encoding of the messages sent over the network.
+//!
+//! Unlike protobuf, fields carry neither tags nor varint lengths:
+//! every message is laid out as the concatenation of its fields, in declaration order.
+//!
+//! - Heights, values, value ids, stream ids and sequences are 8 bytes, big-endian.
+//! - Rounds are 4 bytes, big-endian, with `u32::MAX` standing for [`Round::Nil`].
+//! - Addresses are 20 bytes and signatures 64 bytes, as-is.
+//! - Enums and options are prefixed with a 1-byte tag.
+//! - Variable-size fields (extensions, value bytes, peer ids) and lists are prefixed
+//!   with their length as 4 bytes, big-endian.
+//!
+//! Decoding rejects messages larger than the maximum size of the codec,
+//! as well as messages followed by trailing bytes.
+
+use std::collections::BTreeSet;
+
+use bytes::{BufMut, Bytes};
+
+use malachitebft_app::streaming::{StreamContent, StreamMessage};
+use malachitebft_codec::Codec;
+use malachitebft_core_consensus::SignedConsensusMsg;
+use malachitebft_core_types::{
+    AggregatedSignature, CommitCertificate, CommitSignature, Extension, NilOrVal, PolkaCertificate,
+    PolkaSignature, Round, SignedExtension, SignedProposal, SignedVote, VoteSet, VoteType,
+};
+use malachitebft_signing_ed25519::Signature;
+use malachitebft_sync::{self as sync, PeerId};
+
+use crate::{
+    Address, Height, Proposal, ProposalData, ProposalFin, ProposalInit, ProposalPart, TestContext,
+    Value, ValueId, Vote,
+};
+
+/// Default maximum size of a message, matching the default maximum size of RPC messages.
+pub const DEFAULT_MAX_SIZE: usize = 10 * 1024 * 1024;
+
+/// Encoding of [`Round::Nil`]
+const NIL_ROUND: u32 = u32::MAX;
+
+const SIGNATURE_LEN: usize = 64;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CompactError {
+    #[error("Message too large: {size} bytes, maximum is {max} bytes")]
+    TooLarge { size: usize, max: usize },
+
+    #[error("Unexpected end of message while decoding {0}")]
+    UnexpectedEnd(&'static str),
+
+    #[error("Message followed by {0} trailing bytes")]
+    TrailingBytes(usize),
+
+    #[error("Invalid tag {tag} for {field}")]
+    InvalidTag { field: &'static str, tag: u8 },
+
+    #[error("Round {0} cannot be encoded")]
+    InvalidRound(u32),
+
+    #[error("Invalid peer id")]
+    InvalidPeerId,
+
+    #[error("Duplicate signature from validator {0} in certificate")]
+    DuplicateSignature(Address),
+
+    #[error("Field {0} is too large to be encoded")]
+    FieldTooLarge(&'static str),
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct CompactCodec {
+    max_size: usize,
+}
+
+impl CompactCodec {
+    /// Create a codec which rejects messages larger than `max_size` bytes during decode.
+    pub const fn new(max_size: usize) -> Self {
+        Self { max_size }
+    }
+
+    pub const fn max_size(&self) -> usize {
+        self.max_size
+    }
+}
+
+impl Default for CompactCodec {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_SIZE)
+    }
+}
+
+macro_rules! impl_codec {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Codec<$ty> for CompactCodec {
+                type Error = CompactError;
+
+                fn decode(&self, bytes: Bytes) -> Result<$ty, Self::Error> {
+                    if bytes.len() > self.max_size {
+                        return Err(CompactError::TooLarge {
+                            size: bytes.len(),
+                            max: self.max_size,
+                        });
+                    }
+
+                    let mut reader = Reader::new(bytes);
+                    let msg = Decode::decode(&mut reader)?;
+                    reader.finish()?;
+
+                    Ok(msg)
+                }
+
+                fn encode(&self, msg: &$ty) -> Result<Bytes, Self::Error> {
+                    let mut buf = Vec::new();
+                    Encode::encode(msg, &mut buf)?;
+                    Ok(Bytes::from(buf))
+                }
+            }
+        )*
+    };
+}
+
+impl_codec!(
+    Value,
+    ProposalPart,
+    Vote,
+    Proposal,
+    SignedConsensusMsg<TestContext>,
+    StreamMessage<ProposalPart>,
+    CommitCertificate<TestContext>,
+    PolkaCertificate<TestContext>,
+    sync::Status<TestContext>,
+    sync::Request<TestContext>,
+    sync::Response<TestContext>,
+);
+
+/// Reads the fields of a message, failing if the message ends too early
+struct Reader {
+    bytes: Bytes,
+}
+
+impl Reader {
+    fn new(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    fn take(&mut self, len: usize, field: &'static str) -> Result<Bytes, CompactError> {
+        if self.bytes.len() < len {
+            return Err(CompactError::UnexpectedEnd(field));
+        }
+
+        Ok(self.bytes.split_to(len))
+    }
+
+    fn array<const N: usize>(&mut self, field: &'static str) -> Result<[u8; N], CompactError> {
+        let bytes = self.take(N, field)?;
+        Ok(<[u8; N]>::try_from(bytes.as_ref()).expect("took exactly N bytes"))
+    }
+
+    fn u8(&mut self, field: &'static str) -> Result<u8, CompactError> {
+        self.array::<1>(field).map(|[b]| b)
+    }
+
+    fn u32(&mut self, field: &'static str) -> Result<u32, CompactError> {
+        self.array(field).map(u32::from_be_bytes)
+    }
+
+    fn u64(&mut self, field: &'static str) -> Result<u64, CompactError> {
+        self.array(field).map(u64::from_be_bytes)
+    }
+
+    /// Read a length-prefixed field
+    fn bytes(&mut self, field: &'static str) -> Result<Bytes, CompactError> {
+        let len = self.u32(field)? as usize;
+        self.take(len, field)
+    }
+
+    /// Read a length-prefixed list
+    fn list<T: Decode>(&mut self, field: &'static str) -> Result<Vec<T>, CompactError> {
+        let len = self.u32(field)?;
+
+        // Do not trust the length to pre-allocate, as it has not been checked yet
+        let mut items = Vec::new();
+        for _ in 0..len {
+            items.push(T::decode(self)?);
+        }
+
+        Ok(items)
+    }
+
+    fn finish(self) -> Result<(), CompactError> {
+        if !self.bytes.is_empty() {
+            return Err(CompactError::TrailingBytes(self.bytes.len()));
+        }
+
+        Ok(())
+    }
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8], field: &'static str) -> Result<(), CompactError> {
+    let len = u32::try_from(bytes.len()).map_err(|_| CompactError::FieldTooLarge(field))?;
+    buf.put_u32(len);
+    buf.put_slice(bytes);
+    Ok(())
+}
+
+fn put_list<T: Encode>(
+    buf: &mut Vec<u8>,
+    items: &[T],
+    field: &'static str,
+) -> Result<(), CompactError> {
+    let len = u32::try_from(items.len()).map_err(|_| CompactError::FieldTooLarge(field))?;
+    buf.put_u32(len);
+    items.iter().try_for_each(|item| item.encode(buf))
+}
+
+trait Encode {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError>;
+}
+
+trait Decode: Sized {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError>;
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        match self {
+            None => {
+                buf.put_u8(0);
+                Ok(())
+            }
+            Some(value) => {
+                buf.put_u8(1);
+                value.encode(buf)
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        match reader.u8("option")? {
+            0 => Ok(None),
+            1 => T::decode(reader).map(Some),
+            tag => Err(CompactError::InvalidTag {
+                field: "option",
+                tag,
+            }),
+        }
+    }
+}
+
+impl Encode for Height {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        buf.put_u64(self.as_u64());
+        Ok(())
+    }
+}
+
+impl Decode for Height {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        reader.u64("height").map(Height::new)
+    }
+}
+
+impl Encode for Round {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        match self.as_u32() {
+            None => buf.put_u32(NIL_ROUND),
+            Some(NIL_ROUND) => return Err(CompactError::InvalidRound(NIL_ROUND)),
+            Some(round) => buf.put_u32(round),
+        }
+
+        Ok(())
+    }
+}
+
+impl Decode for Round {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        match reader.u32("round")? {
+            NIL_ROUND => Ok(Round::Nil),
+            round => Ok(Round::new(round)),
+        }
+    }
+}
+
+impl Encode for Value {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        buf.put_u64(self.as_u64());
+        Ok(())
+    }
+}
+
+impl Decode for Value {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        reader.u64("value").map(Value::new)
+    }
+}
+
+impl Encode for ValueId {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        buf.put_u64(self.as_u64());
+        Ok(())
+    }
+}
+
+impl Decode for ValueId {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        reader.u64("value_id").map(ValueId::new)
+    }
+}
+
+impl Encode for Address {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        buf.put_slice(&self.into_inner());
+        Ok(())
+    }
+}
+
+impl Decode for Address {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        reader.array("address").map(Address::new)
+    }
+}
+
+impl Encode for Signature {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        buf.put_slice(&self.to_bytes());
+        Ok(())
+    }
+}
+
+impl Decode for Signature {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        reader
+            .array::<SIGNATURE_LEN>("signature")
+            .map(Signature::from_bytes)
+    }
+}
+
+impl Encode for SignedExtension<TestContext> {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        put_bytes(buf, &self.message.data, "extension")?;
+        self.signature.encode(buf)
+    }
+}
+
+impl Decode for SignedExtension<TestContext> {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        let extension = Extension::from(reader.bytes("extension")?);
+        let signature = Signature::decode(reader)?;
+        Ok(SignedExtension::new(extension, signature))
+    }
+}
+
+impl Encode for Vote {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        buf.put_u8(match self.typ {
+            VoteType::Prevote => 0,
+            VoteType::Precommit => 1,
+        });
+
+        self.height.encode(buf)?;
+        self.round.encode(buf)?;
+
+        match &self.value {
+            NilOrVal::Nil => buf.put_u8(0),
+            NilOrVal::Val(value_id) => {
+                buf.put_u8(1);
+                value_id.encode(buf)?;
+            }
+        }
+
+        self.validator_address.encode(buf)?;
+        self.extension.encode(buf)
+    }
+}
+
+impl Decode for Vote {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        let typ = match reader.u8("vote_type")? {
+            0 => VoteType::Prevote,
+            1 => VoteType::Precommit,
+            tag => {
+                return Err(CompactError::InvalidTag {
+                    field: "vote_type",
+                    tag,
+                })
+            }
+        };
+
+        let height = Height::decode(reader)?;
+        let round = Round::decode(reader)?;
+
+        let value = match reader.u8("vote_value")? {
+            0 => NilOrVal::Nil,
+            1 => NilOrVal::Val(ValueId::decode(reader)?),
+            tag => {
+                return Err(CompactError::InvalidTag {
+                    field: "vote_value",
+                    tag,
+                })
+            }
+        };
+
+        Ok(Vote {
+            typ,
+            height,
+            round,
+            value,
+            validator_address: Address::decode(reader)?,
+            extension: Option::decode(reader)?,
+        })
+    }
+}
+
+impl Encode for Proposal {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        self.height.encode(buf)?;
+        self.round.encode(buf)?;
+        self.value.encode(buf)?;
+        self.pol_round.encode(buf)?;
+        self.validator_address.encode(buf)
+    }
+}
+
+impl Decode for Proposal {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        Ok(Proposal::new(
+            Height::decode(reader)?,
+            Round::decode(reader)?,
+            Value::decode(reader)?,
+            Round::decode(reader)?,
+            Address::decode(reader)?,
+        ))
+    }
+}
+
+impl Encode for SignedConsensusMsg<TestContext> {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        match self {
+            SignedConsensusMsg::Proposal(proposal) => {
+                buf.put_u8(0);
+                proposal.message.encode(buf)?;
+                proposal.signature.encode(buf)
+            }
+            SignedConsensusMsg::Vote(vote) => {
+                buf.put_u8(1);
+                vote.message.encode(buf)?;
+                vote.signature.encode(buf)
+            }
+        }
+    }
+}
+
+impl Decode for SignedConsensusMsg<TestContext> {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        match reader.u8("signed_message")? {
+            0 => {
+                let proposal = Proposal::decode(reader)?;
+                let signature = Signature::decode(reader)?;
+                Ok(SignedConsensusMsg::Proposal(SignedProposal::new(
+                    proposal, signature,
+                )))
+            }
+            1 => {
+                let vote = Vote::decode(reader)?;
+                let signature = Signature::decode(reader)?;
+                Ok(SignedConsensusMsg::Vote(SignedVote::new(vote, signature)))
+            }
+            tag => Err(CompactError::InvalidTag {
+                field: "signed_message",
+                tag,
+            }),
+        }
+    }
+}
+
+impl Encode for ProposalPart {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        match self {
+            ProposalPart::Init(init) => {
+                buf.put_u8(0);
+                init.height.encode(buf)?;
+                init.round.encode(buf)?;
+                init.proposer.encode(buf)
+            }
+            ProposalPart::Data(data) => {
+                buf.put_u8(1);
+                buf.put_u64(data.factor);
+                Ok(())
+            }
+            ProposalPart::Fin(fin) => {
+                buf.put_u8(2);
+                fin.signature.encode(buf)
+            }
+        }
+    }
+}
+
+impl Decode for ProposalPart {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        match reader.u8("proposal_part")? {
+            0 => Ok(ProposalPart::Init(ProposalInit::new(
+                Height::decode(reader)?,
+                Round::decode(reader)?,
+                Address::decode(reader)?,
+            ))),
+            1 => Ok(ProposalPart::Data(ProposalData::new(reader.u64("factor")?))),
+            2 => Ok(ProposalPart::Fin(ProposalFin::new(Signature::decode(
+                reader,
+            )?))),
+            tag => Err(CompactError::InvalidTag {
+                field: "proposal_part",
+                tag,
+            }),
+        }
+    }
+}
+
+impl Encode for StreamMessage<ProposalPart> {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        buf.put_u64(self.stream_id);
+        buf.put_u64(self.sequence);
+
+        match &self.content {
+            StreamContent::Data(part) => {
+                buf.put_u8(0);
+                part.encode(buf)
+            }
+            StreamContent::Fin(fin) => {
+                buf.put_u8(1);
+                buf.put_u8(u8::from(*fin));
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Decode for StreamMessage<ProposalPart> {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        let stream_id = reader.u64("stream_id")?;
+        let sequence = reader.u64("sequence")?;
+
+        let content = match reader.u8("stream_content")? {
+            0 => StreamContent::Data(ProposalPart::decode(reader)?),
+            1 => match reader.u8("fin")? {
+                0 => StreamContent::Fin(false),
+                1 => StreamContent::Fin(true),
+                tag => return Err(CompactError::InvalidTag { field: "fin", tag }),
+            },
+            tag => {
+                return Err(CompactError::InvalidTag {
+                    field: "stream_content",
+                    tag,
+                })
+            }
+        };
+
+        Ok(StreamMessage::new(stream_id, sequence, content))
+    }
+}
+
+impl Encode for CommitSignature<TestContext> {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        self.address.encode(buf)?;
+        self.signature.encode(buf)?;
+        self.extension.encode(buf)
+    }
+}
+
+impl Decode for CommitSignature<TestContext> {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        Ok(CommitSignature::new(
+            Address::decode(reader)?,
+            Signature::decode(reader)?,
+            Option::decode(reader)?,
+        ))
+    }
+}
+
+impl Encode for CommitCertificate<TestContext> {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        self.height.encode(buf)?;
+        self.round.encode(buf)?;
+        self.value_id.encode(buf)?;
+        put_list(buf, &self.aggregated_signature.signatures, "signatures")
+    }
+}
+
+/// Decode a commit certificate,
+/// rejecting certificates with more than one signature from the same validator.
+impl Decode for CommitCertificate<TestContext> {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        let height = Height::decode(reader)?;
+        let round = Round::decode(reader)?;
+        let value_id = ValueId::decode(reader)?;
+        let signatures: Vec<CommitSignature<TestContext>> = reader.list("signatures")?;
+
+        check_unique_signers(signatures.iter().map(|s| s.address))?;
+
+        Ok(CommitCertificate {
+            height,
+            round,
+            value_id,
+            aggregated_signature: AggregatedSignature::new(signatures),
+        })
+    }
+}
+
+impl Encode for PolkaSignature<TestContext> {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        self.address.encode(buf)?;
+        self.signature.encode(buf)
+    }
+}
+
+impl Decode for PolkaSignature<TestContext> {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        Ok(PolkaSignature::new(
+            Address::decode(reader)?,
+            Signature::decode(reader)?,
+        ))
+    }
+}
+
+impl Encode for PolkaCertificate<TestContext> {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        self.height.encode(buf)?;
+        self.round.encode(buf)?;
+        self.value_id.encode(buf)?;
+        put_list(buf, &self.polka_signatures, "polka_signatures")
+    }
+}
+
+/// Decode a polka certificate,
+/// rejecting certificates with more than one signature from the same validator.
+impl Decode for PolkaCertificate<TestContext> {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        let height = Height::decode(reader)?;
+        let round = Round::decode(reader)?;
+        let value_id = ValueId::decode(reader)?;
+        let polka_signatures: Vec<PolkaSignature<TestContext>> = reader.list("polka_signatures")?;
+
+        check_unique_signers(polka_signatures.iter().map(|s| s.address))?;
+
+        Ok(PolkaCertificate {
+            height,
+            round,
+            value_id,
+            polka_signatures,
+        })
+    }
+}
+
+fn check_unique_signers(addresses: impl Iterator<Item = Address>) -> Result<(), CompactError> {
+    let mut signers = BTreeSet::new();
+
+    for address in addresses {
+        if !signers.insert(address) {
+            return Err(CompactError::DuplicateSignature(address));
+        }
+    }
+
+    Ok(())
+}
+
+impl Encode for SignedVote<TestContext> {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        self.message.encode(buf)?;
+        self.signature.encode(buf)
+    }
+}
+
+impl Decode for SignedVote<TestContext> {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        Ok(SignedVote::new(
+            Vote::decode(reader)?,
+            Signature::decode(reader)?,
+        ))
+    }
+}
+
+impl Encode for sync::DecidedValue<TestContext> {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        put_bytes(buf, &self.value_bytes, "value_bytes")?;
+        self.certificate.encode(buf)
+    }
+}
+
+impl Decode for sync::DecidedValue<TestContext> {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        Ok(sync::DecidedValue::new(
+            reader.bytes("value_bytes")?,
+            CommitCertificate::decode(reader)?,
+        ))
+    }
+}
+
+impl Encode for sync::Status<TestContext> {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        put_bytes(buf, &self.peer_id.to_bytes(), "peer_id")?;
+        self.height.encode(buf)?;
+        self.history_min_height.encode(buf)
+    }
+}
+
+impl Decode for sync::Status<TestContext> {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        let peer_id = PeerId::from_bytes(&reader.bytes("peer_id")?)
+            .map_err(|_| CompactError::InvalidPeerId)?;
+
+        Ok(sync::Status {
+            peer_id,
+            height: Height::decode(reader)?,
+            history_min_height: Height::decode(reader)?,
+        })
+    }
+}
+
+impl Encode for sync::Request<TestContext> {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        match self {
+            sync::Request::ValueRequest(req) => {
+                buf.put_u8(0);
+                req.height.encode(buf)
+            }
+            sync::Request::VoteSetRequest(req) => {
+                buf.put_u8(1);
+                req.height.encode(buf)?;
+                req.round.encode(buf)
+            }
+        }
+    }
+}
+
+impl Decode for sync::Request<TestContext> {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        match reader.u8("sync_request")? {
+            0 => Ok(sync::Request::ValueRequest(sync::ValueRequest::new(
+                Height::decode(reader)?,
+            ))),
+            1 => Ok(sync::Request::VoteSetRequest(sync::VoteSetRequest::new(
+                Height::decode(reader)?,
+                Round::decode(reader)?,
+            ))),
+            tag => Err(CompactError::InvalidTag {
+                field: "sync_request",
+                tag,
+            }),
+        }
+    }
+}
+
+impl Encode for sync::Response<TestContext> {
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), CompactError> {
+        match self {
+            sync::Response::ValueResponse(res) => {
+                buf.put_u8(0);
+                res.height.encode(buf)?;
+                res.value.encode(buf)
+            }
+            sync::Response::VoteSetResponse(res) => {
+                buf.put_u8(1);
+                res.height.encode(buf)?;
+                res.round.encode(buf)?;
+                put_list(buf, &res.vote_set.votes, "votes")
+            }
+        }
+    }
+}
+
+impl Decode for sync::Response<TestContext> {
+    fn decode(reader: &mut Reader) -> Result<Self, CompactError> {
+        match reader.u8("sync_response")? {
+            0 => Ok(sync::Response::ValueResponse(sync::ValueResponse::new(
+                Height::decode(reader)?,
+                Option::decode(reader)?,
+            ))),
+            1 => Ok(sync::Response::VoteSetResponse(sync::VoteSetResponse::new(
+                Height::decode(reader)?,
+                Round::decode(reader)?,
+                VoteSet::new(reader.list("votes")?),
+            ))),
+            tag => Err(CompactError::InvalidTag {
+                field: "sync_response",
+                tag,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(i: u8) -> Address {
+        Address::new([i; Address::LENGTH])
+    }
+
+    fn vote(round: Round) -> SignedConsensusMsg<TestContext> {
+        let vote = Vote::new_precommit(
+            Height::new(1),
+            round,
+            NilOrVal::Val(ValueId::new(42)),
+            address(1),
+        );
+
+        SignedConsensusMsg::Vote(SignedVote::new(vote, Signature::test()))
+    }
+
+    #[test]
+    fn vote_extension_roundtrip() {
+        let vote = Vote {
+            extension: Some(SignedExtension::new(
+                Extension::from(vec![1, 2, 3]),
+                Signature::test(),
+            )),
+            ..Vote::new_precommit(Height::new(1), Round::new(0), NilOrVal::Nil, address(1))
+        };
+
+        let bytes = CompactCodec::default().encode(&vote).unwrap();
+        let decoded: Vote = CompactCodec::default().decode(bytes).unwrap();
+        assert_eq!(decoded, vote);
+    }
+
+    #[test]
+    fn nil_round_roundtrip() {
+        let proposal = Proposal::new(
+            Height::new(1),
+            Round::new(0),
+            Value::new(42),
+            Round::Nil,
+            address(1),
+        );
+
+        let bytes = CompactCodec::default().encode(&proposal).unwrap();
+        let decoded: Proposal = CompactCodec::default().decode(bytes).unwrap();
+        assert_eq!(decoded, proposal);
+
+        let encoded = CompactCodec::default().encode(&vote(Round::new(NIL_ROUND)));
+        assert!(matches!(
+            encoded,
+            Err(CompactError::InvalidRound(NIL_ROUND))
+        ));
+    }
+
+    #[test]
+    fn reject_truncated_messages() {
+        let bytes = CompactCodec::default()
+            .encode(&vote(Round::new(0)))
+            .unwrap();
+
+        for len in [0, 1, bytes.len() - 1] {
+            let decoded: Result<SignedConsensusMsg<TestContext>, _> =
+                CompactCodec::default().decode(bytes.slice(..len));
+            assert!(matches!(decoded, Err(CompactError::UnexpectedEnd(_))));
+        }
+    }
+
+    #[test]
+    fn reject_invalid_tags() {
+        let mut bytes = CompactCodec::default()
+            .encode(&vote(Round::new(0)))
+            .unwrap()
+            .to_vec();
+        bytes[0] = 2;
+
+        let decoded: Result<SignedConsensusMsg<TestContext>, _> =
+            CompactCodec::default().decode(Bytes::from(bytes));
+
+        assert!(matches!(
+            decoded,
+            Err(CompactError::InvalidTag {
+                field: "signed_message",
+                tag: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn certificate_with_duplicate_validator_is_rejected() {
+        let certificate = CommitCertificate {
+            height: Height::new(1),
+            round: Round::new(2),
+            value_id: ValueId::new(42),
+            aggregated_signature: AggregatedSignature::new(
+                [1, 2, 1]
+                    .map(|i| CommitSignature::new(address(i), Signature::test(), None))
+                    .to_vec(),
+            ),
+        };
+
+        let bytes = CompactCodec::default().encode(&certificate).unwrap();
+        let decoded: Result<CommitCertificate<TestContext>, _> =
+            CompactCodec::default().decode(bytes);
+
+        assert!(matches!(decoded, Err(CompactError::DuplicateSignature(a)) if a == address(1)));
+    }
+}
@@ -1,2 +1,4 @@
+pub mod compact;
 // pub mod json;
 pub mod proto;
+pub mod wire;
@@ -0,0 +1,203 @@
+//! Self-describing envelope for the consensus messages of the test context,
+//! used to store messages of different kinds together, eg. in a log on disk,
+//! and to decode them without knowing their kind ahead of time.
+//!
+//! An envelope is laid out as follows:
+//! - version (1 byte): the version of the envelope, see [`ENVELOPE_VERSION`]
+//! - kind (1 byte): the kind of message, see [`MessageKind`]
+//! - body: the Protobuf encoding of the message
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use malachitebft_codec::Codec;
+use malachitebft_core_consensus::SignedConsensusMsg;
+use malachitebft_core_types::{SignedProposal, SignedVote};
+use malachitebft_proto::{Error as ProtoError, Protobuf};
+
+use super::ProtobufCodec;
+use crate::{ProposalPart, TestContext};
+
+/// Version of the envelope produced by [`ProtobufCodec::encode_envelope`]
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// A consensus message of any kind, as stored in an envelope
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConsensusMessage {
+    Vote(SignedVote<TestContext>),
+    Proposal(SignedProposal<TestContext>),
+    BlockPart(ProposalPart),
+}
+
+impl ConsensusMessage {
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            Self::Vote(_) => MessageKind::Vote,
+            Self::Proposal(_) => MessageKind::Proposal,
+            Self::BlockPart(_) => MessageKind::BlockPart,
+        }
+    }
+}
+
+/// Discriminant of the kind of message stored in an envelope
+///
+/// NOTE: The discriminants are part of the storage format and must never be reused.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MessageKind {
+    Vote = 1,
+    Proposal = 2,
+    BlockPart = 3,
+}
+
+impl TryFrom<u8> for MessageKind {
+    type Error = ProtoError;
+
+    fn try_from(kind: u8) -> Result<Self, Self::Error> {
+        match kind {
+            1 => Ok(Self::Vote),
+            2 => Ok(Self::Proposal),
+            3 => Ok(Self::BlockPart),
+            _ => Err(ProtoError::UnknownMessageType {
+                type_url: format!("envelope kind {kind}"),
+            }),
+        }
+    }
+}
+
+impl ProtobufCodec {
+    /// Encode the given message into a self-describing envelope
+    pub fn encode_envelope(&self, msg: &ConsensusMessage) -> Result<Bytes, ProtoError> {
+        let body = match msg {
+            ConsensusMessage::Vote(vote) => self.encode(&SignedConsensusMsg::Vote(vote.clone()))?,
+            ConsensusMessage::Proposal(proposal) => {
+                self.encode(&SignedConsensusMsg::Proposal(proposal.clone()))?
+            }
+            ConsensusMessage::BlockPart(part) => Protobuf::to_bytes(part)?,
+        };
+
+        let mut buf = BytesMut::with_capacity(2 + body.len());
+        buf.put_u8(ENVELOPE_VERSION);
+        buf.put_u8(msg.kind() as u8);
+        buf.put_slice(&body);
+
+        Ok(buf.freeze())
+    }
+
+    /// Decode a message from an envelope, whatever its kind
+    pub fn decode_envelope(&self, bytes: Bytes) -> Result<ConsensusMessage, ProtoError> {
+        let [version, kind, ..] = bytes[..] else {
+            return Err(ProtoError::Other(format!(
+                "envelope too short: expected at least 2 bytes, got {}",
+                bytes.len()
+            )));
+        };
+
+        if version != ENVELOPE_VERSION {
+            return Err(ProtoError::UnsupportedVersion(version.into()));
+        }
+
+        let kind = MessageKind::try_from(kind)?;
+        let body = bytes.slice(2..);
+
+        let msg = match kind {
+            MessageKind::BlockPart => ConsensusMessage::BlockPart(Protobuf::from_bytes(&body)?),
+            MessageKind::Vote | MessageKind::Proposal => match self.decode(body)? {
+                SignedConsensusMsg::Vote(vote) => ConsensusMessage::Vote(vote),
+                SignedConsensusMsg::Proposal(proposal) => ConsensusMessage::Proposal(proposal),
+            },
+        };
+
+        if msg.kind() != kind {
+            return Err(ProtoError::Other(format!(
+                "envelope of kind {kind:?} contains a message of kind {:?}",
+                msg.kind()
+            )));
+        }
+
+        Ok(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use malachitebft_core_types::{NilOrVal, Round};
+    use malachitebft_signing_ed25519::Signature;
+
+    use super::*;
+    use crate::{Address, Height, Proposal, ProposalData, ProposalInit, Value, ValueId, Vote};
+
+    fn address(i: u8) -> Address {
+        Address::new([i; Address::LENGTH])
+    }
+
+    fn messages() -> Vec<ConsensusMessage> {
+        let vote = Vote::new_precommit(
+            Height::new(1),
+            Round::new(0),
+            NilOrVal::Val(ValueId::new(42)),
+            address(1),
+        );
+
+        let proposal = Proposal::new(
+            Height::new(1),
+            Round::new(0),
+            Value::new(42),
+            Round::Nil,
+            address(2),
+        );
+
+        vec![
+            ConsensusMessage::BlockPart(ProposalPart::Init(ProposalInit::new(
+                Height::new(1),
+                Round::new(0),
+                address(2),
+            ))),
+            ConsensusMessage::Proposal(SignedProposal::new(proposal, Signature::test())),
+            ConsensusMessage::BlockPart(ProposalPart::Data(ProposalData::new(42))),
+            ConsensusMessage::Vote(SignedVote::new(vote, Signature::test())),
+        ]
+    }
+
+    #[test]
+    fn envelope_roundtrip() {
+        let codec = ProtobufCodec;
+
+        for msg in messages() {
+            let bytes = codec.encode_envelope(&msg).unwrap();
+            assert_eq!(bytes[0], ENVELOPE_VERSION);
+            assert_eq!(bytes[1], msg.kind() as u8);
+            assert_eq!(codec.decode_envelope(bytes).unwrap(), msg);
+        }
+    }
+
+    #[test]
+    fn reject_malformed_envelopes() {
+        let codec = ProtobufCodec;
+
+        let vote = messages().pop().unwrap();
+        let bytes = codec.encode_envelope(&vote).unwrap();
+
+        let with_header = |version: u8, kind: u8| {
+            let mut bytes = bytes.to_vec();
+            bytes[0] = version;
+            bytes[1] = kind;
+            Bytes::from(bytes)
+        };
+
+        assert!(codec.decode_envelope(Bytes::from_static(&[1])).is_err());
+
+        assert!(matches!(
+            codec.decode_envelope(with_header(2, MessageKind::Vote as u8)),
+            Err(ProtoError::UnsupportedVersion(2))
+        ));
+
+        assert!(matches!(
+            codec.decode_envelope(with_header(ENVELOPE_VERSION, 42)),
+            Err(ProtoError::UnknownMessageType { .. })
+        ));
+
+        assert!(codec
+            .decode_envelope(with_header(ENVELOPE_VERSION, MessageKind::Proposal as u8))
+            .is_err());
+    }
+}
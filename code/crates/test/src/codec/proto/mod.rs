@@ -1,3 +1,5 @@
+use std::collections::BTreeSet;
+
 use bytes::Bytes;
 use prost::Message;
 
@@ -5,16 +7,26 @@ use malachitebft_app::streaming::{StreamContent, StreamMessage};
 use malachitebft_codec::Codec;
 use malachitebft_core_consensus::SignedConsensusMsg;
 use malachitebft_core_types::{
-    AggregatedSignature, CommitCertificate, CommitSignature, Extension, Round, SignedExtension,
-    SignedProposal, SignedVote, VoteSet,
+    AggregatedSignature, CommitCertificate, CommitSignature, Extension, PolkaCertificate,
+    PolkaSignature, Round, SignedExtension, SignedProposal, SignedVote, VoteSet,
 };
 use malachitebft_proto::{Error as ProtoError, Protobuf};
 use malachitebft_signing_ed25519::Signature;
 use malachitebft_sync::{self as sync, PeerId};
 
 use crate::proto;
+use crate::vote::encode_round;
 use crate::{Address, Height, Proposal, ProposalPart, TestContext, Value, ValueId, Vote};
 
+mod envelope;
+pub use envelope::{ConsensusMessage, MessageKind, ENVELOPE_VERSION};
+
+/// Version of the format of the signed messages produced by this node.
+///
+/// Messages with a version of 0 were produced by nodes predating versioning,
+/// and share the format of version 1.
+pub const SIGNED_MESSAGE_VERSION: u32 = 1;
+
 #[derive(Copy, Clone, Debug)]
 pub struct ProtobufCodec;
 
@@ -42,12 +54,38 @@ impl Codec<ProposalPart> for ProtobufCodec {
     }
 }
 
+impl Codec<Vote> for ProtobufCodec {
+    type Error = ProtoError;
+
+    fn decode(&self, bytes: Bytes) -> Result<Vote, Self::Error> {
+        Protobuf::from_bytes(&bytes)
+    }
+
+    fn encode(&self, msg: &Vote) -> Result<Bytes, Self::Error> {
+        Protobuf::to_bytes(msg)
+    }
+}
+
+impl Codec<Proposal> for ProtobufCodec {
+    type Error = ProtoError;
+
+    fn decode(&self, bytes: Bytes) -> Result<Proposal, Self::Error> {
+        Protobuf::from_bytes(&bytes)
+    }
+
+    fn encode(&self, msg: &Proposal) -> Result<Bytes, Self::Error> {
+        Protobuf::to_bytes(msg)
+    }
+}
+
 impl Codec<SignedConsensusMsg<TestContext>> for ProtobufCodec {
     type Error = ProtoError;
 
     fn decode(&self, bytes: Bytes) -> Result<SignedConsensusMsg<TestContext>, Self::Error> {
         let proto = proto::SignedMessage::decode(bytes.as_ref())?;
 
+        check_version(proto.version)?;
+
         let signature = proto
             .signature
             .ok_or_else(|| ProtoError::missing_field::<proto::SignedMessage>("signature"))
@@ -74,12 +112,7 @@ impl Codec<SignedConsensusMsg<TestContext>> for ProtobufCodec {
     fn encode(&self, msg: &SignedConsensusMsg<TestContext>) -> Result<Bytes, Self::Error> {
         match msg {
             SignedConsensusMsg::Vote(vote) => {
-                let proto = proto::SignedMessage {
-                    message: Some(proto::signed_message::Message::Vote(
-                        vote.message.to_proto()?,
-                    )),
-                    signature: Some(encode_signature(&vote.signature)),
-                };
+                let proto = encode_vote(vote)?;
                 Ok(Bytes::from(proto.encode_to_vec()))
             }
             SignedConsensusMsg::Proposal(proposal) => {
@@ -88,6 +121,7 @@ impl Codec<SignedConsensusMsg<TestContext>> for ProtobufCodec {
                         proposal.message.to_proto()?,
                     )),
                     signature: Some(encode_signature(&proposal.signature)),
+                    version: SIGNED_MESSAGE_VERSION,
                 };
                 Ok(Bytes::from(proto.encode_to_vec()))
             }
@@ -135,6 +169,30 @@ impl Codec<StreamMessage<ProposalPart>> for ProtobufCodec {
     }
 }
 
+impl Codec<CommitCertificate<TestContext>> for ProtobufCodec {
+    type Error = ProtoError;
+
+    fn decode(&self, bytes: Bytes) -> Result<CommitCertificate<TestContext>, Self::Error> {
+        decode_certificate(proto::CommitCertificate::decode(bytes.as_ref())?)
+    }
+
+    fn encode(&self, msg: &CommitCertificate<TestContext>) -> Result<Bytes, Self::Error> {
+        Ok(Bytes::from(encode_certificate(msg)?.encode_to_vec()))
+    }
+}
+
+impl Codec<PolkaCertificate<TestContext>> for ProtobufCodec {
+    type Error = ProtoError;
+
+    fn decode(&self, bytes: Bytes) -> Result<PolkaCertificate<TestContext>, Self::Error> {
+        decode_polka_certificate(proto::PolkaCertificate::decode(bytes.as_ref())?)
+    }
+
+    fn encode(&self, msg: &PolkaCertificate<TestContext>) -> Result<Bytes, Self::Error> {
+        Ok(Bytes::from(encode_polka_certificate(msg)?.encode_to_vec()))
+    }
+}
+
 impl Codec<sync::Status<TestContext>> for ProtobufCodec {
     type Error = ProtoError;
 
@@ -147,8 +205,8 @@ impl Codec<sync::Status<TestContext>> for ProtobufCodec {
 
         Ok(sync::Status {
             peer_id: PeerId::from_bytes(proto_peer_id.id.as_ref()).unwrap(),
-            height: Height::new(proto.height),
-            history_min_height: Height::new(proto.earliest_height),
+            height: Height::from_proto(proto.height)?,
+            history_min_height: Height::from_proto(proto.earliest_height)?,
         })
     }
 
@@ -157,8 +215,8 @@ impl Codec<sync::Status<TestContext>> for ProtobufCodec {
             peer_id: Some(proto::PeerId {
                 id: Bytes::from(msg.peer_id.to_bytes()),
             }),
-            height: msg.height.as_u64(),
-            earliest_height: msg.history_min_height.as_u64(),
+            height: msg.height.to_proto()?,
+            earliest_height: msg.history_min_height.to_proto()?,
         };
 
         Ok(Bytes::from(proto.encode_to_vec()))
@@ -176,10 +234,10 @@ impl Codec<sync::Request<TestContext>> for ProtobufCodec {
 
         match request {
             proto::sync_request::Request::ValueRequest(req) => Ok(sync::Request::ValueRequest(
-                sync::ValueRequest::new(Height::new(req.height)),
+                sync::ValueRequest::new(Height::from_proto(req.height)?),
             )),
             proto::sync_request::Request::VoteSetRequest(req) => Ok(sync::Request::VoteSetRequest(
-                sync::VoteSetRequest::new(Height::new(req.height), Round::new(req.round)),
+                sync::VoteSetRequest::new(Height::from_proto(req.height)?, Round::new(req.round)),
             )),
         }
     }
@@ -189,14 +247,14 @@ impl Codec<sync::Request<TestContext>> for ProtobufCodec {
             sync::Request::ValueRequest(req) => proto::SyncRequest {
                 request: Some(proto::sync_request::Request::ValueRequest(
                     proto::ValueRequest {
-                        height: req.height.as_u64(),
+                        height: req.height.to_proto()?,
                     },
                 )),
             },
             sync::Request::VoteSetRequest(req) => proto::SyncRequest {
                 request: Some(proto::sync_request::Request::VoteSetRequest(
                     proto::VoteSetRequest {
-                        height: req.height.as_u64(),
+                        height: req.height.to_proto()?,
                         round: req.round.as_u32().unwrap(),
                     },
                 )),
@@ -229,12 +287,12 @@ fn decode_sync_response(
     let response = match response {
         proto::sync_response::Response::ValueResponse(value_response) => {
             sync::Response::ValueResponse(sync::ValueResponse::new(
-                Height::new(value_response.height),
+                Height::from_proto(value_response.height)?,
                 value_response.value.map(decode_synced_value).transpose()?,
             ))
         }
         proto::sync_response::Response::VoteSetResponse(vote_set_response) => {
-            let height = Height::new(vote_set_response.height);
+            let height = Height::from_proto(vote_set_response.height)?;
             let round = Round::new(vote_set_response.round);
             let vote_set = vote_set_response
                 .vote_set
@@ -257,7 +315,7 @@ fn encode_sync_response(
         sync::Response::ValueResponse(value_response) => proto::SyncResponse {
             response: Some(proto::sync_response::Response::ValueResponse(
                 proto::ValueResponse {
-                    height: value_response.height.as_u64(),
+                    height: value_response.height.to_proto()?,
                     value: value_response
                         .value
                         .as_ref()
@@ -269,7 +327,7 @@ fn encode_sync_response(
         sync::Response::VoteSetResponse(vote_set_response) => proto::SyncResponse {
             response: Some(proto::sync_response::Response::VoteSetResponse(
                 proto::VoteSetResponse {
-                    height: vote_set_response.height.as_u64(),
+                    height: vote_set_response.height.to_proto()?,
                     round: vote_set_response
                         .round
                         .as_u32()
@@ -321,7 +379,7 @@ fn decode_certificate(
         .and_then(decode_aggregated_signature)?;
 
     let certificate = CommitCertificate {
-        height: Height::new(certificate.height),
+        height: Height::from_proto(certificate.height)?,
         round: Round::new(certificate.round),
         value_id,
         aggregated_signature,
@@ -334,8 +392,8 @@ fn encode_certificate(
     certificate: &CommitCertificate<TestContext>,
 ) -> Result<proto::CommitCertificate, ProtoError> {
     Ok(proto::CommitCertificate {
-        height: certificate.height.as_u64(),
-        round: certificate.round.as_u32().expect("round should not be nil"),
+        height: certificate.height.to_proto()?,
+        round: encode_round(certificate.round)?,
         value_id: Some(certificate.value_id.to_proto()?),
         aggregated_signature: Some(encode_aggregate_signature(
             &certificate.aggregated_signature,
@@ -343,9 +401,13 @@ fn encode_certificate(
     })
 }
 
+/// Decode the commit signatures of a certificate,
+/// rejecting certificates with more than one signature from the same validator.
 fn decode_aggregated_signature(
     signature: proto::AggregatedSignature,
 ) -> Result<AggregatedSignature<TestContext>, ProtoError> {
+    let mut signers = BTreeSet::new();
+
     let signatures = signature
         .signatures
         .into_iter()
@@ -362,6 +424,12 @@ fn decode_aggregated_signature(
                 })
                 .and_then(Address::from_proto)?;
 
+            if !signers.insert(address) {
+                return Err(ProtoError::Other(format!(
+                    "duplicate signature from validator {address} in commit certificate"
+                )));
+            }
+
             let extension = s.extension.map(decode_extension).transpose()?;
 
             Ok(CommitSignature {
@@ -393,7 +461,77 @@ fn encode_aggregate_signature(
     Ok(proto::AggregatedSignature { signatures })
 }
 
-fn decode_extension(ext: proto::Extension) -> Result<SignedExtension<TestContext>, ProtoError> {
+/// Decode a polka certificate,
+/// rejecting certificates with more than one signature from the same validator.
+fn decode_polka_certificate(
+    certificate: proto::PolkaCertificate,
+) -> Result<PolkaCertificate<TestContext>, ProtoError> {
+    let value_id = certificate
+        .value_id
+        .ok_or_else(|| ProtoError::missing_field::<proto::PolkaCertificate>("value_id"))
+        .and_then(ValueId::from_proto)?;
+
+    let mut signers = BTreeSet::new();
+
+    let polka_signatures = certificate
+        .signatures
+        .into_iter()
+        .map(|s| {
+            let signature = s
+                .signature
+                .ok_or_else(|| ProtoError::missing_field::<proto::PolkaSignature>("signature"))
+                .and_then(decode_signature)?;
+
+            let address = s
+                .validator_address
+                .ok_or_else(|| {
+                    ProtoError::missing_field::<proto::PolkaSignature>("validator_address")
+                })
+                .and_then(Address::from_proto)?;
+
+            if !signers.insert(address) {
+                return Err(ProtoError::Other(format!(
+                    "duplicate signature from validator {address} in polka certificate"
+                )));
+            }
+
+            Ok(PolkaSignature { address, signature })
+        })
+        .collect::<Result<Vec<_>, ProtoError>>()?;
+
+    Ok(PolkaCertificate {
+        height: Height::from_proto(certificate.height)?,
+        round: Round::new(certificate.round),
+        value_id,
+        polka_signatures,
+    })
+}
+
+fn encode_polka_certificate(
+    certificate: &PolkaCertificate<TestContext>,
+) -> Result<proto::PolkaCertificate, ProtoError> {
+    let signatures = certificate
+        .polka_signatures
+        .iter()
+        .map(|s| {
+            Ok(proto::PolkaSignature {
+                validator_address: Some(s.address.to_proto()?),
+                signature: Some(encode_signature(&s.signature)),
+            })
+        })
+        .collect::<Result<_, ProtoError>>()?;
+
+    Ok(proto::PolkaCertificate {
+        height: certificate.height.to_proto()?,
+        round: encode_round(certificate.round)?,
+        value_id: Some(certificate.value_id.to_proto()?),
+        signatures,
+    })
+}
+
+pub(crate) fn decode_extension(
+    ext: proto::Extension,
+) -> Result<SignedExtension<TestContext>, ProtoError> {
     let extension = Extension::from(ext.data);
     let signature = ext
         .signature
@@ -403,7 +541,9 @@ fn decode_extension(ext: proto::Extension) -> Result<SignedExtension<TestContext
     Ok(SignedExtension::new(extension, signature))
 }
 
-fn encode_extension(ext: &SignedExtension<TestContext>) -> Result<proto::Extension, ProtoError> {
+pub(crate) fn encode_extension(
+    ext: &SignedExtension<TestContext>,
+) -> Result<proto::Extension, ProtoError> {
     Ok(proto::Extension {
         data: ext.message.data.clone(),
         signature: Some(encode_signature(&ext.signature)),
@@ -426,6 +566,7 @@ fn encode_vote(vote: &SignedVote<TestContext>) -> Result<proto::SignedMessage, P
             vote.message.to_proto()?,
         )),
         signature: Some(encode_signature(&vote.signature)),
+        version: SIGNED_MESSAGE_VERSION,
     })
 }
 
@@ -440,6 +581,8 @@ fn decode_vote_set(vote_set: proto::VoteSet) -> Result<VoteSet<TestContext>, Pro
 }
 
 fn decode_vote(msg: proto::SignedMessage) -> Option<SignedVote<TestContext>> {
+    check_version(msg.version).ok()?;
+
     let signature = msg.signature?;
     let vote = match msg.message {
         Some(proto::signed_message::Message::Vote(v)) => Some(v),
@@ -451,6 +594,14 @@ fn decode_vote(msg: proto::SignedMessage) -> Option<SignedVote<TestContext>> {
     Some(SignedVote::new(vote, signature))
 }
 
+fn check_version(version: u32) -> Result<(), ProtoError> {
+    if version > SIGNED_MESSAGE_VERSION {
+        return Err(ProtoError::UnsupportedVersion(version));
+    }
+
+    Ok(())
+}
+
 pub(crate) fn encode_signature(signature: &Signature) -> proto::Signature {
     proto::Signature {
         bytes: Bytes::copy_from_slice(signature.to_bytes().as_ref()),
@@ -458,7 +609,148 @@ pub(crate) fn encode_signature(signature: &Signature) -> proto::Signature {
 }
 
 pub(crate) fn decode_signature(signature: proto::Signature) -> Result<Signature, ProtoError> {
-    let bytes = <[u8; 64]>::try_from(signature.bytes.as_ref())
-        .map_err(|_| ProtoError::Other("Invalid signature length".to_string()))?;
+    const SIGNATURE_LEN: usize = 64;
+
+    let bytes = <[u8; SIGNATURE_LEN]>::try_from(signature.bytes.as_ref()).map_err(|_| {
+        ProtoError::InvalidSignatureLength {
+            expected: SIGNATURE_LEN,
+            got: signature.bytes.len(),
+        }
+    })?;
+
     Ok(Signature::from_bytes(bytes))
 }
+
+#[cfg(test)]
+mod tests {
+    use malachitebft_core_types::NilOrVal;
+
+    use super::*;
+
+    fn encoded_vote_with_version(version: u32) -> Bytes {
+        let vote = Vote::new_prevote(
+            Height::new(1),
+            Round::new(0),
+            NilOrVal::Nil,
+            Address::new([1; Address::LENGTH]),
+        );
+        let msg = SignedConsensusMsg::Vote(SignedVote::new(vote, Signature::test()));
+
+        let bytes = ProtobufCodec.encode(&msg).unwrap();
+        let mut proto = proto::SignedMessage::decode(bytes.as_ref()).unwrap();
+        assert_eq!(proto.version, SIGNED_MESSAGE_VERSION);
+
+        proto.version = version;
+        Bytes::from(proto.encode_to_vec())
+    }
+
+    #[test]
+    fn decode_supported_versions() {
+        for version in [0, SIGNED_MESSAGE_VERSION] {
+            let bytes = encoded_vote_with_version(version);
+            let decoded: Result<SignedConsensusMsg<TestContext>, _> = ProtobufCodec.decode(bytes);
+            assert!(decoded.is_ok(), "version {version} should be supported");
+        }
+    }
+
+    #[test]
+    fn reject_unknown_version() {
+        let version = SIGNED_MESSAGE_VERSION + 1;
+        let bytes = encoded_vote_with_version(version);
+
+        let decoded: Result<SignedConsensusMsg<TestContext>, _> = ProtobufCodec.decode(bytes);
+        assert!(matches!(decoded, Err(ProtoError::UnsupportedVersion(v)) if v == version));
+    }
+
+    fn certificate(signers: &[u8]) -> CommitCertificate<TestContext> {
+        let signatures = signers
+            .iter()
+            .map(|&i| {
+                CommitSignature::new(Address::new([i; Address::LENGTH]), Signature::test(), None)
+            })
+            .collect();
+
+        CommitCertificate {
+            height: Height::new(1),
+            round: Round::new(2),
+            value_id: ValueId::new(42),
+            aggregated_signature: AggregatedSignature::new(signatures),
+        }
+    }
+
+    #[test]
+    fn certificate_roundtrip() {
+        let cases: [&[u8]; 3] = [&[], &[1], &[1, 2, 3]];
+
+        for signers in cases {
+            let certificate = certificate(signers);
+
+            let bytes = ProtobufCodec.encode(&certificate).unwrap();
+            let decoded: CommitCertificate<TestContext> = ProtobufCodec.decode(bytes).unwrap();
+            assert_eq!(decoded, certificate);
+        }
+    }
+
+    #[test]
+    fn certificate_with_duplicate_validator_is_rejected() {
+        let bytes = ProtobufCodec.encode(&certificate(&[1, 2, 1])).unwrap();
+
+        let decoded: Result<CommitCertificate<TestContext>, _> = ProtobufCodec.decode(bytes);
+        assert!(matches!(decoded, Err(ProtoError::Other(e)) if e.contains("duplicate signature")));
+    }
+
+    fn polka_certificate(signers: &[u8]) -> PolkaCertificate<TestContext> {
+        let polka_signatures = signers
+            .iter()
+            .map(|&i| PolkaSignature::new(Address::new([i; Address::LENGTH]), Signature::test()))
+            .collect();
+
+        PolkaCertificate {
+            height: Height::new(1),
+            round: Round::new(2),
+            value_id: ValueId::new(42),
+            polka_signatures,
+        }
+    }
+
+    #[test]
+    fn polka_certificate_roundtrip() {
+        let cases: [&[u8]; 3] = [&[], &[1], &[1, 2, 3]];
+
+        for signers in cases {
+            let certificate = polka_certificate(signers);
+
+            let bytes = ProtobufCodec.encode(&certificate).unwrap();
+            let decoded: PolkaCertificate<TestContext> = ProtobufCodec.decode(bytes).unwrap();
+            assert_eq!(decoded, certificate);
+        }
+    }
+
+    #[test]
+    fn polka_certificate_with_duplicate_validator_is_rejected() {
+        let bytes = ProtobufCodec
+            .encode(&polka_certificate(&[1, 2, 1]))
+            .unwrap();
+
+        let decoded: Result<PolkaCertificate<TestContext>, _> = ProtobufCodec.decode(bytes);
+        assert!(matches!(decoded, Err(ProtoError::Other(e)) if e.contains("duplicate signature")));
+    }
+
+    #[test]
+    fn decode_signature_checks_length() {
+        let signature = Signature::test();
+        let decoded = decode_signature(encode_signature(&signature)).unwrap();
+        assert_eq!(decoded, signature);
+
+        for len in [0, 63, 65] {
+            let malformed = proto::Signature {
+                bytes: Bytes::from(vec![0; len]),
+            };
+
+            assert!(matches!(
+                decode_signature(malformed),
+                Err(ProtoError::InvalidSignatureLength { expected: 64, got }) if got == len
+            ));
+        }
+    }
+}
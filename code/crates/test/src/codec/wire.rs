@@ -0,0 +1,490 @@
+//! Codec for the messages sent over the network,
+//! using the encoding chosen in the configuration of the node.
+
+use bytes::{BufMut, Bytes, BytesMut};
+use prost::encoding::{decode_varint, encode_varint, encoded_len_varint};
+
+use malachitebft_app::streaming::StreamMessage;
+use malachitebft_codec::Codec;
+use malachitebft_config::{MessageEncoding, P2pConfig};
+use malachitebft_core_consensus::SignedConsensusMsg;
+use malachitebft_core_types::{CommitCertificate, PolkaCertificate};
+use malachitebft_proto::Error as ProtoError;
+use malachitebft_sync as sync;
+
+use crate::codec::compact::{CompactCodec, CompactError};
+use crate::codec::proto::ProtobufCodec;
+use crate::{Proposal, ProposalPart, TestContext, Value, Vote};
+
+#[derive(Debug, thiserror::Error)]
+pub enum WireError {
+    #[error("Message too large: {size} bytes, maximum is {max} bytes")]
+    TooLarge { size: usize, max: usize },
+
+    #[error("Message followed by {0} trailing bytes")]
+    TrailingBytes(usize),
+
+    #[error("Message truncated: expected {expected} bytes, got {got} bytes")]
+    Truncated { expected: usize, got: usize },
+
+    #[error(transparent)]
+    Protobuf(#[from] ProtoError),
+
+    #[error(transparent)]
+    Compact(#[from] CompactError),
+}
+
+/// Encodes and decodes messages with either [`ProtobufCodec`] or [`CompactCodec`].
+///
+/// Protobuf messages are prefixed with their length, as Protobuf alone skips unknown fields
+/// and would accept any bytes following a message.
+///
+/// Whatever the encoding, messages larger than the maximum size
+/// or followed by trailing bytes are rejected during decode.
+#[derive(Copy, Clone, Debug)]
+pub struct WireCodec {
+    encoding: MessageEncoding,
+    max_size: usize,
+}
+
+impl WireCodec {
+    pub const fn new(encoding: MessageEncoding, max_size: usize) -> Self {
+        Self { encoding, max_size }
+    }
+
+    /// Use the encoding of the given P2P configuration, and reject messages
+    /// larger than the maximum size of both pub-sub and RPC messages.
+    pub fn from_config(config: &P2pConfig) -> Self {
+        let max_size = config.pubsub_max_size.max(config.rpc_max_size);
+        Self::new(config.encoding, max_size.as_u64() as usize)
+    }
+
+    pub const fn encoding(&self) -> MessageEncoding {
+        self.encoding
+    }
+
+    pub const fn max_size(&self) -> usize {
+        self.max_size
+    }
+}
+
+/// Prefix the given Protobuf message with its length, encoded as a varint
+fn length_delimited(body: Bytes) -> Bytes {
+    let mut buf = BytesMut::with_capacity(encoded_len_varint(body.len() as u64) + body.len());
+    encode_varint(body.len() as u64, &mut buf);
+    buf.put(body);
+    buf.freeze()
+}
+
+/// Split the Protobuf message off the front of the given bytes, according to its length prefix,
+/// leaving any trailing bytes in place.
+fn split_length_delimited(bytes: &mut Bytes) -> Result<Bytes, WireError> {
+    let len = decode_varint(bytes).map_err(ProtoError::from)? as usize;
+
+    if len > bytes.len() {
+        return Err(WireError::Truncated {
+            expected: len,
+            got: bytes.len(),
+        });
+    }
+
+    Ok(bytes.split_to(len))
+}
+
+macro_rules! impl_codec {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Codec<$ty> for WireCodec {
+                type Error = WireError;
+
+                fn decode(&self, bytes: Bytes) -> Result<$ty, Self::Error> {
+                    if bytes.len() > self.max_size {
+                        return Err(WireError::TooLarge {
+                            size: bytes.len(),
+                            max: self.max_size,
+                        });
+                    }
+
+                    match self.encoding {
+                        MessageEncoding::Protobuf => {
+                            let mut bytes = bytes;
+                            let body = split_length_delimited(&mut bytes)?;
+                            let msg = ProtobufCodec.decode(body)?;
+
+                            if !bytes.is_empty() {
+                                return Err(WireError::TrailingBytes(bytes.len()));
+                            }
+
+                            Ok(msg)
+                        }
+                        MessageEncoding::Compact => Ok(CompactCodec::new(self.max_size).decode(bytes)?),
+                    }
+                }
+
+                fn encode(&self, msg: &$ty) -> Result<Bytes, Self::Error> {
+                    match self.encoding {
+                        MessageEncoding::Protobuf => {
+                            Ok(length_delimited(ProtobufCodec.encode(msg)?))
+                        }
+                        MessageEncoding::Compact => Ok(CompactCodec::new(self.max_size).encode(msg)?),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_codec!(
+    Value,
+    ProposalPart,
+    Vote,
+    Proposal,
+    SignedConsensusMsg<TestContext>,
+    StreamMessage<ProposalPart>,
+    CommitCertificate<TestContext>,
+    PolkaCertificate<TestContext>,
+    sync::Status<TestContext>,
+    sync::Request<TestContext>,
+    sync::Response<TestContext>,
+);
+
+#[cfg(test)]
+mod tests {
+    use core::fmt::Debug;
+
+    use malachitebft_app::streaming::StreamContent;
+    use malachitebft_core_types::{
+        AggregatedSignature, CommitSignature, Extension, NilOrVal, PolkaSignature, Round,
+        SignedExtension, SignedProposal, SignedVote, VoteSet,
+    };
+    use malachitebft_signing_ed25519::Signature;
+    use malachitebft_sync::PeerId;
+
+    use super::*;
+    use crate::{Address, Height, ProposalData, ProposalFin, ProposalInit, ValueId};
+
+    const MAX_SIZE: usize = 1024 * 1024;
+
+    const ENCODINGS: [MessageEncoding; 2] = [MessageEncoding::Protobuf, MessageEncoding::Compact];
+
+    fn address(i: u8) -> Address {
+        Address::new([i; Address::LENGTH])
+    }
+
+    fn extension() -> SignedExtension<TestContext> {
+        SignedExtension::new(Extension::from(vec![1, 2, 3]), Signature::test())
+    }
+
+    fn prevote() -> Vote {
+        Vote::new_prevote(
+            Height::new(1),
+            Round::new(0),
+            NilOrVal::Val(ValueId::new(42)),
+            address(1),
+        )
+    }
+
+    fn precommit() -> Vote {
+        Vote::new_precommit(Height::new(1), Round::new(0), NilOrVal::Nil, address(2))
+    }
+
+    fn precommit_with_extension() -> Vote {
+        Vote::new_precommit_with_extension(
+            Height::new(1),
+            Round::new(0),
+            NilOrVal::Val(ValueId::new(42)),
+            address(2),
+            extension(),
+        )
+    }
+
+    fn proposal() -> Proposal {
+        Proposal::new(
+            Height::new(1),
+            Round::new(1),
+            Value::new(42),
+            Round::new(0),
+            address(1),
+        )
+    }
+
+    fn proposal_parts() -> Vec<ProposalPart> {
+        vec![
+            ProposalPart::Init(ProposalInit::new(Height::new(1), Round::new(0), address(1))),
+            ProposalPart::Data(ProposalData::new(42)),
+            ProposalPart::Fin(ProposalFin::new(Signature::test())),
+        ]
+    }
+
+    fn signed_vote(vote: Vote) -> SignedVote<TestContext> {
+        SignedVote::new(vote, Signature::test())
+    }
+
+    fn certificate() -> CommitCertificate<TestContext> {
+        CommitCertificate {
+            height: Height::new(1),
+            round: Round::new(2),
+            value_id: ValueId::new(42),
+            aggregated_signature: AggregatedSignature::new(vec![
+                CommitSignature::new(address(1), Signature::test(), None),
+                CommitSignature::new(address(2), Signature::test(), Some(extension())),
+            ]),
+        }
+    }
+
+    fn polka_certificate() -> PolkaCertificate<TestContext> {
+        PolkaCertificate {
+            height: Height::new(1),
+            round: Round::new(2),
+            value_id: ValueId::new(42),
+            polka_signatures: vec![
+                PolkaSignature::new(address(1), Signature::test()),
+                PolkaSignature::new(address(2), Signature::test()),
+            ],
+        }
+    }
+
+    fn roundtrip<T>(codec: &WireCodec, msgs: impl IntoIterator<Item = T>)
+    where
+        T: Debug + PartialEq,
+        WireCodec: Codec<T>,
+        <WireCodec as Codec<T>>::Error: Debug,
+    {
+        for msg in msgs {
+            let bytes = codec.encode(&msg).unwrap();
+            let decoded: T = codec.decode(bytes).unwrap();
+            assert_eq!(decoded, msg, "{:?}", codec.encoding());
+        }
+    }
+
+    #[test]
+    fn roundtrip_all_messages() {
+        for encoding in ENCODINGS {
+            let codec = WireCodec::new(encoding, MAX_SIZE);
+
+            roundtrip(&codec, [Value::new(0), Value::new(u64::MAX)]);
+            roundtrip(&codec, [prevote(), precommit(), precommit_with_extension()]);
+            roundtrip(&codec, [proposal()]);
+            roundtrip(&codec, proposal_parts());
+
+            roundtrip(
+                &codec,
+                [
+                    SignedConsensusMsg::Vote(signed_vote(prevote())),
+                    SignedConsensusMsg::Vote(signed_vote(precommit())),
+                    SignedConsensusMsg::Vote(signed_vote(precommit_with_extension())),
+                    SignedConsensusMsg::Proposal(SignedProposal::new(
+                        proposal(),
+                        Signature::test(),
+                    )),
+                ],
+            );
+
+            roundtrip(
+                &codec,
+                proposal_parts()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, part)| StreamMessage::new(1, i as u64, StreamContent::Data(part)))
+                    .chain([StreamMessage::new(1, 3, StreamContent::Fin(true))]),
+            );
+
+            roundtrip(&codec, [certificate()]);
+            roundtrip(&codec, [polka_certificate()]);
+
+            roundtrip(
+                &codec,
+                [sync::Status {
+                    peer_id: PeerId::random(),
+                    height: Height::new(10),
+                    history_min_height: Height::new(1),
+                }],
+            );
+
+            roundtrip(
+                &codec,
+                [
+                    sync::Request::ValueRequest(sync::ValueRequest::new(Height::new(1))),
+                    sync::Request::VoteSetRequest(sync::VoteSetRequest::new(
+                        Height::new(1),
+                        Round::new(2),
+                    )),
+                ],
+            );
+
+            roundtrip(
+                &codec,
+                [
+                    sync::Response::ValueResponse(sync::ValueResponse::new(Height::new(1), None)),
+                    sync::Response::ValueResponse(sync::ValueResponse::new(
+                        Height::new(1),
+                        Some(sync::DecidedValue::new(
+                            Bytes::from_static(b"value"),
+                            certificate(),
+                        )),
+                    )),
+                    sync::Response::VoteSetResponse(sync::VoteSetResponse::new(
+                        Height::new(1),
+                        Round::new(0),
+                        VoteSet::new(vec![signed_vote(prevote()), signed_vote(precommit())]),
+                    )),
+                ],
+            );
+        }
+    }
+
+    #[test]
+    fn reject_trailing_bytes() {
+        let msg = SignedConsensusMsg::Vote(signed_vote(prevote()));
+
+        for encoding in ENCODINGS {
+            let codec = WireCodec::new(encoding, MAX_SIZE);
+
+            // An unknown protobuf field (number 15, varint), which protobuf alone would skip
+            let mut bytes = codec.encode(&msg).unwrap().to_vec();
+            bytes.extend_from_slice(&[0x78, 0x01]);
+
+            let decoded: Result<SignedConsensusMsg<TestContext>, _> =
+                codec.decode(Bytes::from(bytes));
+
+            match encoding {
+                MessageEncoding::Protobuf => {
+                    assert!(matches!(decoded, Err(WireError::TrailingBytes(2))))
+                }
+                MessageEncoding::Compact => assert!(matches!(
+                    decoded,
+                    Err(WireError::Compact(CompactError::TrailingBytes(2)))
+                )),
+            }
+        }
+    }
+
+    #[test]
+    fn reject_truncated_protobuf_messages() {
+        let msg = SignedConsensusMsg::Vote(signed_vote(prevote()));
+        let codec = WireCodec::new(MessageEncoding::Protobuf, MAX_SIZE);
+
+        let bytes = codec.encode(&msg).unwrap();
+        let truncated = bytes.slice(..bytes.len() - 1);
+
+        let decoded: Result<SignedConsensusMsg<TestContext>, _> = codec.decode(truncated);
+        assert!(
+            matches!(decoded, Err(WireError::Truncated { expected, got }) if expected == got + 1)
+        );
+    }
+
+    #[test]
+    fn reject_messages_larger_than_max_size() {
+        let msg = SignedConsensusMsg::Vote(signed_vote(precommit()));
+
+        for encoding in ENCODINGS {
+            let bytes = WireCodec::new(encoding, MAX_SIZE).encode(&msg).unwrap();
+
+            let codec = WireCodec::new(encoding, bytes.len() - 1);
+            let decoded: Result<SignedConsensusMsg<TestContext>, _> = codec.decode(bytes.clone());
+            assert!(
+                matches!(decoded, Err(WireError::TooLarge { size, max }) if size == bytes.len() && max == bytes.len() - 1)
+            );
+
+            let codec = WireCodec::new(encoding, bytes.len());
+            let decoded: Result<SignedConsensusMsg<TestContext>, _> = codec.decode(bytes);
+            assert!(decoded.is_ok());
+        }
+    }
+
+    #[test]
+    fn compact_encoding_is_smaller() {
+        let protobuf = WireCodec::new(MessageEncoding::Protobuf, MAX_SIZE);
+        let compact = WireCodec::new(MessageEncoding::Compact, MAX_SIZE);
+
+        fn sizes<T>(protobuf: &WireCodec, compact: &WireCodec, msg: T) -> (usize, usize)
+        where
+            WireCodec: Codec<T>,
+            <WireCodec as Codec<T>>::Error: Debug,
+        {
+            (
+                protobuf.encode(&msg).unwrap().len(),
+                compact.encode(&msg).unwrap().len(),
+            )
+        }
+
+        let height = Height::new(1_000_000);
+        let round = Round::new(0);
+        let value_id = NilOrVal::Val(ValueId::new(42));
+
+        let vote = |vote: Vote| SignedConsensusMsg::Vote(signed_vote(vote));
+
+        // Protobuf omits fields with a default value, so a prevote for nil in round 0 has about
+        // the same size in both encodings. Otherwise, the compact encoding saves the tags and
+        // lengths of the nested messages (value ids, addresses, signatures, etc.)
+        //
+        // Compact votes are laid out as: message tag (1) + vote type (1) + height (8)
+        // + round (4) + value (1 or 9) + address (20) + extension (1) + signature (64)
+        let cases = [
+            (
+                "prevote for nil",
+                sizes(
+                    &protobuf,
+                    &compact,
+                    vote(Vote::new_prevote(height, round, NilOrVal::Nil, address(1))),
+                ),
+                100,
+            ),
+            (
+                "precommit for a value",
+                sizes(
+                    &protobuf,
+                    &compact,
+                    vote(Vote::new_precommit(height, round, value_id, address(1))),
+                ),
+                108,
+            ),
+            (
+                "proposal",
+                sizes(
+                    &protobuf,
+                    &compact,
+                    SignedConsensusMsg::Proposal(SignedProposal::new(
+                        Proposal::new(height, round, Value::new(42), Round::Nil, address(1)),
+                        Signature::test(),
+                    )),
+                ),
+                109,
+            ),
+            (
+                "commit certificate",
+                sizes(&protobuf, &compact, certificate()),
+                20 + 4 + (20 + 64 + 1) + (20 + 64 + 1 + 4 + 3 + 64),
+            ),
+            (
+                "vote set response",
+                sizes(
+                    &protobuf,
+                    &compact,
+                    sync::Response::VoteSetResponse(sync::VoteSetResponse::new(
+                        height,
+                        round,
+                        VoteSet::new(
+                            (1..=4)
+                                .map(|i| {
+                                    signed_vote(Vote::new_precommit(
+                                        height,
+                                        round,
+                                        value_id,
+                                        address(i),
+                                    ))
+                                })
+                                .collect(),
+                        ),
+                    )),
+                ),
+                1 + 8 + 4 + 4 + 4 * 107,
+            ),
+        ];
+
+        for (name, (protobuf_size, compact_size), expected) in cases {
+            assert_eq!(compact_size, expected, "{name}");
+            assert!(compact_size <= protobuf_size, "{name}");
+        }
+    }
+}
@@ -1,4 +1,7 @@
 use core::fmt;
+use core::num::ParseIntError;
+use core::str::FromStr;
+
 use malachitebft_proto::{Error as ProtoError, Protobuf};
 use serde::{Deserialize, Serialize};
 
@@ -16,11 +19,37 @@ impl Height {
     }
 
     pub fn increment(&self) -> Self {
-        Self(self.0 + 1)
+        malachitebft_core_types::Height::increment(self)
     }
 
     pub fn decrement(&self) -> Option<Self> {
-        self.0.checked_sub(1).map(Self)
+        malachitebft_core_types::Height::decrement(self)
+    }
+
+    /// Increment the height by one, or return `None` on overflow.
+    pub const fn checked_increment(&self) -> Option<Self> {
+        match self.0.checked_add(1) {
+            Some(height) => Some(Self(height)),
+            None => None,
+        }
+    }
+
+    /// Increment the height by one, staying at `u64::MAX` on overflow.
+    pub const fn saturating_increment(&self) -> Self {
+        Self(self.0.saturating_add(1))
+    }
+
+    /// Decrement the height by one, or return `None` if that would go below the initial height.
+    ///
+    /// Same as [`Height::decrement`], named for symmetry with [`Height::checked_increment`].
+    pub fn checked_decrement(&self) -> Option<Self> {
+        self.decrement()
+    }
+
+    /// Decrement the height by one, staying at the current height
+    /// if that would go below the initial height.
+    pub fn saturating_decrement(&self) -> Self {
+        self.decrement().unwrap_or(*self)
     }
 }
 
@@ -42,13 +71,27 @@ impl fmt::Debug for Height {
     }
 }
 
+impl FromStr for Height {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
 impl malachitebft_core_types::Height for Height {
+    const ZERO: Self = Self(0);
+    const INITIAL: Self = Self(1);
+
     fn increment_by(&self, n: u64) -> Self {
         Self(self.0 + n)
     }
 
     fn decrement_by(&self, n: u64) -> Option<Self> {
-        Some(Self(self.0.saturating_sub(n)))
+        self.0
+            .checked_sub(n)
+            .map(Self)
+            .filter(|height| *height >= Self::INITIAL)
     }
 
     fn as_u64(&self) -> u64 {
@@ -67,3 +110,71 @@ impl Protobuf for Height {
         Ok(self.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use malachitebft_core_types::Height as _;
+
+    use super::*;
+
+    #[test]
+    fn increment() {
+        assert_eq!(Height::ZERO.increment(), Height::INITIAL);
+        assert_eq!(Height::INITIAL.increment_by(9), Height::new(10));
+    }
+
+    #[test]
+    fn decrement_stops_at_initial_height() {
+        assert_eq!(Height::new(2).decrement(), Some(Height::INITIAL));
+        assert_eq!(Height::INITIAL.decrement(), None);
+        assert_eq!(Height::ZERO.decrement(), None);
+        assert_eq!(Height::new(10).decrement_by(9), Some(Height::INITIAL));
+        assert_eq!(Height::new(10).decrement_by(10), None);
+        assert_eq!(Height::new(10).decrement_by(u64::MAX), None);
+    }
+
+    #[test]
+    fn checked_and_saturating_increment() {
+        let max = Height::new(u64::MAX);
+
+        assert_eq!(Height::ZERO.checked_increment(), Some(Height::INITIAL));
+        assert_eq!(Height::new(u64::MAX - 1).checked_increment(), Some(max));
+        assert_eq!(max.checked_increment(), None);
+
+        assert_eq!(Height::ZERO.saturating_increment(), Height::INITIAL);
+        assert_eq!(Height::new(u64::MAX - 1).saturating_increment(), max);
+        assert_eq!(max.saturating_increment(), max);
+    }
+
+    #[test]
+    fn checked_and_saturating_decrement() {
+        let max = Height::new(u64::MAX);
+
+        assert_eq!(max.checked_decrement(), Some(Height::new(u64::MAX - 1)));
+        assert_eq!(Height::new(2).checked_decrement(), Some(Height::INITIAL));
+        assert_eq!(Height::INITIAL.checked_decrement(), None);
+        assert_eq!(Height::ZERO.checked_decrement(), None);
+
+        assert_eq!(max.saturating_decrement(), Height::new(u64::MAX - 1));
+        assert_eq!(Height::new(2).saturating_decrement(), Height::INITIAL);
+        assert_eq!(Height::INITIAL.saturating_decrement(), Height::INITIAL);
+        assert_eq!(Height::ZERO.saturating_decrement(), Height::ZERO);
+    }
+
+    #[test]
+    fn display_and_from_str() {
+        let height = Height::new(42);
+        assert_eq!(height.to_string(), "42");
+        assert_eq!("42".parse::<Height>(), Ok(height));
+        assert!("-1".parse::<Height>().is_err());
+        assert!("forty-two".parse::<Height>().is_err());
+    }
+
+    #[test]
+    fn proto_roundtrip() {
+        for height in [Height::ZERO, Height::INITIAL, Height::new(u64::MAX)] {
+            let proto = height.to_proto().unwrap();
+            assert_eq!(Height::from_proto(proto).unwrap(), height);
+        }
+    }
+}
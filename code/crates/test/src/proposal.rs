@@ -1,11 +1,13 @@
 use bytes::Bytes;
 use malachitebft_core_types::Round;
 use malachitebft_proto::{Error as ProtoError, Protobuf};
+use serde::{Deserialize, Serialize};
 
+use crate::vote::encode_round;
 use crate::{Address, Height, TestContext, Value};
 
 /// A proposal for a value in a round
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Proposal {
     pub height: Height,
     pub round: Round,
@@ -69,7 +71,7 @@ impl Protobuf for Proposal {
     fn to_proto(&self) -> Result<Self::Proto, ProtoError> {
         Ok(Self::Proto {
             height: self.height.to_proto()?,
-            round: self.round.as_u32().expect("round should not be nil"),
+            round: encode_round(self.round)?,
             value: Some(self.value.to_proto()?),
             pol_round: self.pol_round.as_u32(),
             validator_address: Some(self.validator_address.to_proto()?),
@@ -95,3 +97,45 @@ impl Protobuf for Proposal {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proposal(round: Round, pol_round: Round) -> Proposal {
+        Proposal::new(
+            Height::new(1),
+            round,
+            Value::new(42),
+            pol_round,
+            Address::new([1; 20]),
+        )
+    }
+
+    #[test]
+    fn round_proto_roundtrip() {
+        let rounds = [
+            (Round::new(0), Round::Nil),
+            (Round::new(1), Round::new(0)),
+            (Round::new(u32::MAX), Round::new(u32::MAX - 1)),
+        ];
+
+        for (round, pol_round) in rounds {
+            let proto = proposal(round, pol_round).to_proto().unwrap();
+            assert_eq!(proto.round, round.as_u32().unwrap());
+            assert_eq!(proto.pol_round, pol_round.as_u32());
+            assert_eq!(
+                Proposal::from_proto(proto).unwrap(),
+                proposal(round, pol_round)
+            );
+        }
+    }
+
+    #[test]
+    fn nil_round_is_not_encoded() {
+        assert!(matches!(
+            proposal(Round::Nil, Round::Nil).to_proto(),
+            Err(ProtoError::Other(e)) if e == "invalid round"
+        ));
+    }
+}
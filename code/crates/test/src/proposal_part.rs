@@ -60,6 +60,13 @@ impl ProposalPart {
         }
     }
 
+    pub fn as_fin(&self) -> Option<&ProposalFin> {
+        match self {
+            Self::Fin(fin) => Some(fin),
+            _ => None,
+        }
+    }
+
     pub fn to_sign_bytes(&self) -> Bytes {
         proto::Protobuf::to_bytes(self).unwrap() // FIXME: unwrap
     }
@@ -118,7 +125,7 @@ impl Protobuf for ProposalPart {
 
         match part {
             Part::Init(init) => Ok(Self::Init(ProposalInit {
-                height: Height::new(init.height),
+                height: Height::from_proto(init.height)?,
                 round: Round::new(init.round),
                 proposer: init
                     .proposer
@@ -143,7 +150,7 @@ impl Protobuf for ProposalPart {
         match self {
             Self::Init(init) => Ok(Self::Proto {
                 part: Some(Part::Init(proto::ProposalInit {
-                    height: init.height.as_u64(),
+                    height: init.height.to_proto()?,
                     round: init.round.as_u32().unwrap(),
                     proposer: Some(init.proposer.to_proto()?),
                 })),
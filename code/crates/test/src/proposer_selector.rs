@@ -1,4 +1,8 @@
-use malachitebft_core_types::{Context, Round};
+use core::marker::PhantomData;
+use std::collections::BTreeMap;
+
+use malachitebft_core_types::{Context, Height as _, Round, Validator as _, ValidatorSet as _};
+use sha3::Digest;
 
 use crate::{Address, Height, TestContext, ValidatorSet};
 
@@ -65,3 +69,374 @@ impl ProposerSelector<TestContext> for FixedProposer {
         self.proposer
     }
 }
+
+/// Selects the proposers set for specific heights and rounds, eg. to force a given validator
+/// to be the proposer in a test.
+///
+/// For heights and rounds without a proposer set, falls back to [`RotateProposer`],
+/// ie. to round-robin over the validator set.
+#[derive(Clone, Debug, Default)]
+pub struct FixedProposerSelector {
+    proposers: BTreeMap<(Height, Round), Address>,
+}
+
+impl FixedProposerSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select the given validator as the proposer at the given height and round.
+    pub fn with_proposer(mut self, height: Height, round: Round, proposer: Address) -> Self {
+        self.set_proposer(height, round, proposer);
+        self
+    }
+
+    /// Select the given validator as the proposer at the given height and round.
+    pub fn set_proposer(&mut self, height: Height, round: Round, proposer: Address) {
+        self.proposers.insert((height, round), proposer);
+    }
+}
+
+impl ProposerSelector<TestContext> for FixedProposerSelector {
+    fn select_proposer(
+        &self,
+        height: Height,
+        round: Round,
+        validator_set: &ValidatorSet,
+    ) -> Address {
+        match self.proposers.get(&(height, round)) {
+            Some(proposer) => *proposer,
+            None => RotateProposer.select_proposer(height, round, validator_set),
+        }
+    }
+}
+
+/// Selects proposers proportionally to their voting power, using the accumulated priority
+/// algorithm of Tendermint, also known as smooth weighted round-robin.
+///
+/// At each step, the priority of every validator is increased by its voting power,
+/// the validator with the highest priority is selected, and its priority is decreased
+/// by the total voting power. Ties go to the validator which comes first in the set.
+///
+/// The priorities are all back to zero after as many steps as the total voting power,
+/// so the proposer for a given height and round is computed from scratch over at most
+/// that many steps, which makes the selection deterministic.
+pub struct WeightedRoundRobin<Ctx> {
+    _marker: PhantomData<fn() -> Ctx>,
+}
+
+impl<Ctx> WeightedRoundRobin<Ctx> {
+    pub fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Ctx> Default for WeightedRoundRobin<Ctx> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Ctx> Clone for WeightedRoundRobin<Ctx> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Ctx> Copy for WeightedRoundRobin<Ctx> {}
+
+impl<Ctx> core::fmt::Debug for WeightedRoundRobin<Ctx> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("WeightedRoundRobin")
+    }
+}
+
+impl<Ctx> ProposerSelector<Ctx> for WeightedRoundRobin<Ctx>
+where
+    Ctx: Context,
+{
+    fn select_proposer(
+        &self,
+        height: Ctx::Height,
+        round: Round,
+        validator_set: &Ctx::ValidatorSet,
+    ) -> Ctx::Address {
+        assert!(round != Round::Nil && round.as_i64() >= 0);
+
+        let total_voting_power = validator_set.total_voting_power();
+        assert!(total_voting_power > 0, "validator set has no voting power");
+
+        // Number of steps since the priorities were last all zero, rounds of the first height included
+        let steps = (height.as_u64().saturating_sub(1) % total_voting_power
+            + round.as_i64() as u64 % total_voting_power)
+            % total_voting_power;
+
+        let total_voting_power = i128::from(total_voting_power);
+        let mut priorities = vec![0_i128; validator_set.count()];
+        let mut selected = 0;
+
+        for _ in 0..=steps {
+            for (index, priority) in priorities.iter_mut().enumerate() {
+                let validator = validator_set
+                    .get_by_index(index)
+                    .expect("index is within the validator set");
+
+                *priority += i128::from(validator.voting_power());
+            }
+
+            selected = priorities
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by_key(|(_, priority)| **priority)
+                .map(|(index, _)| index)
+                .expect("validator set is not empty");
+
+            priorities[selected] -= total_voting_power;
+        }
+
+        validator_set
+            .get_by_index(selected)
+            .expect("index is within the validator set")
+            .address()
+            .clone()
+    }
+}
+
+/// Selects proposers pseudo-randomly, with a probability proportional to their voting power.
+///
+/// The proposer for a given height and round is picked by hashing the seed, the height and
+/// the round (each encoded as 8 big-endian bytes, in that order) with the hash function `H`,
+/// reading the first 16 bytes of the digest as a big-endian integer, and mapping that integer
+/// modulo the total voting power onto the cumulative voting power of the validators,
+/// sorted by address.
+///
+/// Since validators are sorted by address, the selection does not depend on the order
+/// of the validators in the set, and all nodes using the same seed and hash function
+/// select the same proposer.
+pub struct HashProposerSelector<Ctx, H = sha3::Keccak256> {
+    seed: u64,
+    _marker: PhantomData<fn() -> (Ctx, H)>,
+}
+
+impl<Ctx, H> HashProposerSelector<Ctx, H> {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl<Ctx, H> Clone for HashProposerSelector<Ctx, H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Ctx, H> Copy for HashProposerSelector<Ctx, H> {}
+
+impl<Ctx, H> core::fmt::Debug for HashProposerSelector<Ctx, H> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("HashProposerSelector")
+            .field("seed", &self.seed)
+            .finish()
+    }
+}
+
+impl<Ctx, H> ProposerSelector<Ctx> for HashProposerSelector<Ctx, H>
+where
+    Ctx: Context,
+    H: Digest,
+{
+    fn select_proposer(
+        &self,
+        height: Ctx::Height,
+        round: Round,
+        validator_set: &Ctx::ValidatorSet,
+    ) -> Ctx::Address {
+        assert!(round != Round::Nil && round.as_i64() >= 0);
+
+        let total_voting_power = validator_set.total_voting_power();
+        assert!(total_voting_power > 0, "validator set has no voting power");
+
+        let digest = H::new()
+            .chain_update(self.seed.to_be_bytes())
+            .chain_update(height.as_u64().to_be_bytes())
+            .chain_update(round.as_i64().to_be_bytes())
+            .finalize();
+
+        let mut bytes = [0; 16];
+        let len = digest.len().min(bytes.len());
+        bytes[..len].copy_from_slice(&digest[..len]);
+
+        let target = u128::from_be_bytes(bytes) % u128::from(total_voting_power);
+
+        let mut validators = (0..validator_set.count())
+            .map(|index| {
+                validator_set
+                    .get_by_index(index)
+                    .expect("index is within the validator set")
+            })
+            .collect::<Vec<_>>();
+
+        validators.sort_by(|a, b| a.address().cmp(b.address()));
+
+        let mut cumulative_voting_power = 0;
+
+        for validator in validators {
+            cumulative_voting_power += u128::from(validator.voting_power());
+
+            if target < cumulative_voting_power {
+                return validator.address().clone();
+            }
+        }
+
+        unreachable!("target is below the total voting power")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::utils::validators::make_validators;
+
+    #[test]
+    fn fixed_proposer_selector_falls_back_to_round_robin() {
+        let [(v1, _), (v2, _), (v3, _)] = make_validators([1, 1, 1]);
+        let validator_set = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+        let height = Height::new(1);
+
+        let selector = FixedProposerSelector::new()
+            .with_proposer(height, Round::new(0), v3.address)
+            .with_proposer(height, Round::new(2), v2.address);
+
+        let proposers = Round::range(Round::new(0), Round::new(4))
+            .map(|round| selector.select_proposer(height, round, &validator_set))
+            .collect::<Vec<_>>();
+
+        // Rounds 1 and 3 are not set, and fall back to round-robin
+        assert_eq!(
+            proposers,
+            vec![v3.address, v2.address, v2.address, v1.address]
+        );
+
+        // Other heights are not affected
+        assert_eq!(
+            selector.select_proposer(Height::new(2), Round::new(0), &validator_set),
+            RotateProposer.select_proposer(Height::new(2), Round::new(0), &validator_set),
+        );
+    }
+
+    #[test]
+    fn weighted_round_robin_follows_voting_power() {
+        let [(v1, _), (v2, _), (v3, _)] = make_validators([1, 2, 3]);
+        let validator_set = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+        let selector = WeightedRoundRobin::<TestContext>::new();
+        let height = Height::new(1);
+
+        let mut counts = HashMap::new();
+
+        for round in Round::range(Round::new(0), Round::new(60)) {
+            let proposer = selector.select_proposer(height, round, &validator_set);
+            *counts.entry(proposer).or_insert(0) += 1;
+        }
+
+        assert_eq!(counts[&v1.address], 10);
+        assert_eq!(counts[&v2.address], 20);
+        assert_eq!(counts[&v3.address], 30);
+    }
+
+    #[test]
+    fn weighted_round_robin_is_deterministic() {
+        let [(v1, _), (v2, _), (v3, _)] = make_validators([5, 1, 3]);
+        let validator_set = ValidatorSet::new(vec![v1, v2, v3]);
+
+        let selector = WeightedRoundRobin::<TestContext>::new();
+
+        for height in 1..5 {
+            for round in Round::range(Round::new(0), Round::new(20)) {
+                let height = Height::new(height);
+
+                assert_eq!(
+                    selector.select_proposer(height, round, &validator_set),
+                    selector.select_proposer(height, round, &validator_set),
+                );
+            }
+        }
+
+        // Moving to the next height advances the selection by one step, like moving to the next round
+        assert_eq!(
+            selector.select_proposer(Height::new(2), Round::new(0), &validator_set),
+            selector.select_proposer(Height::new(1), Round::new(1), &validator_set),
+        );
+    }
+
+    #[test]
+    fn hash_selectors_agree() {
+        let [(v1, _), (v2, _), (v3, _), (v4, _)] = make_validators([1, 2, 3, 4]);
+        let validator_set = ValidatorSet::new(vec![v1, v2, v3, v4]);
+
+        let a = HashProposerSelector::<TestContext>::new(42);
+        let b = HashProposerSelector::<TestContext>::new(42);
+
+        for height in 1..10 {
+            for round in Round::range(Round::new(0), Round::new(10)) {
+                let height = Height::new(height);
+
+                assert_eq!(
+                    a.select_proposer(height, round, &validator_set),
+                    b.select_proposer(height, round, &validator_set),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn hash_selector_ignores_validator_order() {
+        let [(v1, _), (v2, _), (v3, _), (v4, _)] = make_validators([1, 2, 3, 4]);
+        let validator_set = ValidatorSet::new(vec![v1, v2, v3, v4]);
+
+        let mut reversed = validator_set.clone();
+        reversed.validators.reverse();
+
+        let selector = HashProposerSelector::<TestContext>::new(7);
+
+        for round in Round::range(Round::new(0), Round::new(50)) {
+            let height = Height::new(1);
+
+            assert_eq!(
+                selector.select_proposer(height, round, &validator_set),
+                selector.select_proposer(height, round, &reversed),
+            );
+        }
+    }
+
+    #[test]
+    fn hash_selector_follows_voting_power() {
+        let [(v1, _), (v2, _), (v3, _)] = make_validators([0, 1, 9]);
+        let validator_set = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+        let selector = HashProposerSelector::<TestContext>::new(0);
+
+        let mut counts = HashMap::new();
+
+        for round in Round::range(Round::new(0), Round::new(1000)) {
+            let proposer = selector.select_proposer(Height::new(1), round, &validator_set);
+            *counts.entry(proposer).or_insert(0) += 1;
+        }
+
+        assert!(!counts.contains_key(&v1.address));
+        assert!(counts[&v2.address] < counts[&v3.address]);
+    }
+}
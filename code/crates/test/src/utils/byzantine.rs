@@ -0,0 +1,94 @@
+use malachitebft_core_types::{
+    NilOrVal, Round, SignedProposal, SignedVote, SigningProvider, VoteType,
+};
+
+use crate::{
+    Address, Ed25519Provider, Height, PrivateKey, Proposal, TestContext, Validator, Value, ValueId,
+    Vote,
+};
+
+/// A validator which equivocates on demand, ie. which signs two conflicting
+/// proposals or votes for the same height and round.
+///
+/// Each message it produces is properly signed and valid on its own,
+/// but the two messages of each pair contradict each other.
+#[derive(Debug)]
+pub struct ByzantineValidator {
+    validator: Validator,
+    signer: Ed25519Provider,
+}
+
+impl ByzantineValidator {
+    pub fn new(validator: Validator, private_key: PrivateKey) -> Self {
+        Self {
+            validator,
+            signer: Ed25519Provider::new(private_key),
+        }
+    }
+
+    pub fn validator(&self) -> &Validator {
+        &self.validator
+    }
+
+    pub fn address(&self) -> Address {
+        self.validator.address
+    }
+
+    /// Sign two proposals for different values at the same height and round.
+    pub fn conflicting_proposals(
+        &self,
+        height: Height,
+        round: Round,
+        values: (Value, Value),
+    ) -> (SignedProposal<TestContext>, SignedProposal<TestContext>) {
+        assert_ne!(values.0, values.1, "proposals must be for different values");
+
+        let proposal = |value| {
+            let proposal = Proposal::new(height, round, value, Round::Nil, self.address());
+            self.signer.sign_proposal(proposal)
+        };
+
+        (proposal(values.0), proposal(values.1))
+    }
+
+    /// Sign two prevotes for different values at the same height and round.
+    pub fn conflicting_prevotes(
+        &self,
+        height: Height,
+        round: Round,
+        values: (NilOrVal<ValueId>, NilOrVal<ValueId>),
+    ) -> (SignedVote<TestContext>, SignedVote<TestContext>) {
+        self.conflicting_votes(VoteType::Prevote, height, round, values)
+    }
+
+    /// Sign two precommits for different values at the same height and round.
+    pub fn conflicting_precommits(
+        &self,
+        height: Height,
+        round: Round,
+        values: (NilOrVal<ValueId>, NilOrVal<ValueId>),
+    ) -> (SignedVote<TestContext>, SignedVote<TestContext>) {
+        self.conflicting_votes(VoteType::Precommit, height, round, values)
+    }
+
+    fn conflicting_votes(
+        &self,
+        vote_type: VoteType,
+        height: Height,
+        round: Round,
+        values: (NilOrVal<ValueId>, NilOrVal<ValueId>),
+    ) -> (SignedVote<TestContext>, SignedVote<TestContext>) {
+        assert_ne!(values.0, values.1, "votes must be for different values");
+
+        let vote = |value| {
+            let vote = match vote_type {
+                VoteType::Prevote => Vote::new_prevote(height, round, value, self.address()),
+                VoteType::Precommit => Vote::new_precommit(height, round, value, self.address()),
+            };
+
+            self.signer.sign_vote(vote)
+        };
+
+        (vote(values.0), vote(values.1))
+    }
+}
@@ -1 +1,2 @@
+pub mod byzantine;
 pub mod validators;
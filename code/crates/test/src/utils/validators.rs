@@ -5,6 +5,11 @@ use malachitebft_core_types::VotingPower;
 
 use crate::{PrivateKey, Validator};
 
+/// Derive a private key deterministically from the given seed.
+pub fn private_key_from_seed(seed: u64) -> PrivateKey {
+    PrivateKey::generate(&mut StdRng::seed_from_u64(seed))
+}
+
 pub fn make_validators<const N: usize>(
     voting_powers: [VotingPower; N],
 ) -> [(Validator, PrivateKey); N] {
@@ -2,6 +2,7 @@ use malachitebft_core_types::VotingPower;
 use serde::{Deserialize, Serialize};
 
 use crate::signing::PublicKey;
+use crate::utils::validators::private_key_from_seed;
 use crate::{Address, TestContext};
 
 /// A validator is a public key and voting power
@@ -65,6 +66,16 @@ impl ValidatorSet {
         Self { validators }
     }
 
+    /// Create a validator set with the given voting powers, deriving the key of each validator
+    /// deterministically from its seed, eg. `from_powers(&[(1, 10), (2, 20), (3, 1)])`.
+    ///
+    /// The private key of each validator can be obtained with [`private_key_from_seed`].
+    pub fn from_powers(powers: &[(u64, VotingPower)]) -> Self {
+        Self::new(powers.iter().map(|&(seed, voting_power)| {
+            Validator::new(private_key_from_seed(seed).public_key(), voting_power)
+        }))
+    }
+
     /// The total voting power of the validator set
     pub fn total_voting_power(&self) -> VotingPower {
         self.validators.iter().map(|v| v.voting_power).sum()
@@ -193,4 +204,20 @@ mod tests {
         vs.remove(&v6.address); // no effect
         assert_eq!(vs.total_voting_power(), 10);
     }
+
+    #[test]
+    fn from_powers() {
+        let vs = ValidatorSet::from_powers(&[(1, 10), (2, 20), (3, 1)]);
+
+        assert_eq!(vs.validators.len(), 3);
+        assert_eq!(vs.total_voting_power(), 31);
+
+        // Same seeds, same validators
+        assert_eq!(vs, ValidatorSet::from_powers(&[(1, 10), (2, 20), (3, 1)]));
+
+        let sk2 = private_key_from_seed(2);
+        let v2 = vs.get_by_public_key(&sk2.public_key()).unwrap();
+        assert_eq!(v2.voting_power, 20);
+        assert_eq!(v2.address, Address::from_public_key(&sk2.public_key()));
+    }
 }
@@ -1,6 +1,9 @@
 use core::fmt;
+use core::marker::PhantomData;
+
 use malachitebft_proto::{Error as ProtoError, Protobuf};
 use serde::{Deserialize, Serialize};
+use sha3::Digest;
 
 use crate::proto;
 
@@ -74,11 +77,78 @@ impl Value {
         ValueId(self.0)
     }
 
+    /// The id of this value, as derived by the given hasher
+    pub fn id_with(&self, hasher: &impl ValueHasher) -> ValueId {
+        hasher.value_id(self)
+    }
+
     pub fn size_bytes(&self) -> usize {
         8
     }
 }
 
+/// Derives the id of a value from its content.
+pub trait ValueHasher {
+    fn value_id(&self, value: &Value) -> ValueId;
+}
+
+/// The default hasher, which uses the value itself as its id, as [`Value::id`] does.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct IdentityHasher;
+
+impl ValueHasher for IdentityHasher {
+    fn value_id(&self, value: &Value) -> ValueId {
+        value.id()
+    }
+}
+
+/// Derives the id of a value by hashing the value (encoded as 8 big-endian bytes)
+/// with the hash function `H`, and reading the first 8 bytes of the digest
+/// as a big-endian integer.
+pub struct DigestHasher<H = sha3::Keccak256> {
+    _marker: PhantomData<fn() -> H>,
+}
+
+impl<H> DigestHasher<H> {
+    pub fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<H> Default for DigestHasher<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H> Clone for DigestHasher<H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<H> Copy for DigestHasher<H> {}
+
+impl<H> fmt::Debug for DigestHasher<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DigestHasher").finish()
+    }
+}
+
+impl<H: Digest> ValueHasher for DigestHasher<H> {
+    fn value_id(&self, value: &Value) -> ValueId {
+        let digest = H::digest(value.as_u64().to_be_bytes());
+
+        let mut bytes = [0; 8];
+        let len = digest.len().min(bytes.len());
+        bytes[..len].copy_from_slice(&digest[..len]);
+
+        ValueId::new(u64::from_be_bytes(bytes))
+    }
+}
+
 impl malachitebft_core_types::Value for Value {
     type Id = ValueId;
 
@@ -114,3 +184,34 @@ impl Protobuf for Value {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_hasher_is_the_default_id() {
+        for value in [Value::new(0), Value::new(42), Value::new(u64::MAX)] {
+            assert_eq!(value.id_with(&IdentityHasher), value.id());
+        }
+    }
+
+    #[test]
+    fn hashers_derive_distinct_ids() {
+        let keccak = DigestHasher::<sha3::Keccak256>::new();
+        let sha3 = DigestHasher::<sha3::Sha3_256>::new();
+
+        for value in [Value::new(0), Value::new(42), Value::new(u64::MAX)] {
+            let keccak_id = value.id_with(&keccak);
+            let sha3_id = value.id_with(&sha3);
+
+            // Deterministic
+            assert_eq!(keccak_id, value.id_with(&keccak));
+            assert_eq!(sha3_id, value.id_with(&sha3));
+
+            assert_ne!(keccak_id, sha3_id);
+            assert_ne!(keccak_id, value.id());
+            assert_ne!(sha3_id, value.id());
+        }
+    }
+}
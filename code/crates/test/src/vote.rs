@@ -1,14 +1,16 @@
 use bytes::Bytes;
 use malachitebft_core_types::{NilOrVal, Round, SignedExtension, VoteType};
-use malachitebft_proto::{Error as ProtoError, Protobuf};
+use malachitebft_proto::{decode_nil_or_val, encode_nil_or_val, Error as ProtoError, Protobuf};
+use serde::{Deserialize, Serialize};
 
+use crate::codec::proto::{decode_extension, encode_extension};
 use crate::proto;
 use crate::{Address, Height, TestContext, ValueId};
 
 pub use malachitebft_core_types::Extension;
 
 /// A vote for a value in a round
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Vote {
     pub typ: VoteType,
     pub height: Height,
@@ -51,6 +53,29 @@ impl Vote {
         }
     }
 
+    pub fn new_precommit_with_extension(
+        height: Height,
+        round: Round,
+        value: NilOrVal<ValueId>,
+        address: Address,
+        extension: SignedExtension<TestContext>,
+    ) -> Self {
+        Self {
+            typ: VoteType::Precommit,
+            height,
+            round,
+            value,
+            validator_address: address,
+            extension: Some(extension),
+        }
+    }
+
+    /// The data of the extension carried by this vote, if any
+    pub fn extension_bytes(&self) -> Option<&Bytes> {
+        self.extension.as_ref().map(|ext| &ext.message.data)
+    }
+
+    /// Encode the vote, including its extension, to the bytes that get signed
     pub fn to_bytes(&self) -> Bytes {
         Protobuf::to_bytes(self).unwrap()
     }
@@ -102,16 +127,13 @@ impl Protobuf for Vote {
             typ: decode_votetype(proto.vote_type()),
             height: Height::from_proto(proto.height)?,
             round: Round::new(proto.round),
-            value: match proto.value {
-                Some(value) => NilOrVal::Val(ValueId::from_proto(value)?),
-                None => NilOrVal::Nil,
-            },
+            value: decode_nil_or_val(proto.value)?,
             validator_address: Address::from_proto(
                 proto
                     .validator_address
                     .ok_or_else(|| ProtoError::missing_field::<Self::Proto>("validator_address"))?,
             )?,
-            extension: Default::default(),
+            extension: proto.extension.map(decode_extension).transpose()?,
         })
     }
 
@@ -120,12 +142,10 @@ impl Protobuf for Vote {
         Ok(Self::Proto {
             vote_type: encode_votetype(self.typ).into(),
             height: self.height.to_proto()?,
-            round: self.round.as_u32().expect("round should not be nil"),
-            value: match &self.value {
-                NilOrVal::Nil => None,
-                NilOrVal::Val(v) => Some(v.to_proto()?),
-            },
+            round: encode_round(self.round)?,
+            value: encode_nil_or_val(&self.value)?,
             validator_address: Some(self.validator_address.to_proto()?),
+            extension: self.extension.as_ref().map(encode_extension).transpose()?,
         })
     }
 }
@@ -145,3 +165,99 @@ fn decode_votetype(vote_type: proto::VoteType) -> VoteType {
         proto::VoteType::Precommit => VoteType::Precommit,
     }
 }
+
+/// Encode a round which must be defined, ie. the round of a vote or proposal.
+///
+/// Rounds are encoded as `uint32` on the wire, so every encoded value is a valid round
+/// and there is no sentinel for [`Round::Nil`], which must be encoded as an unset
+/// `optional uint32` where allowed, eg. for the POL round of a proposal.
+pub(crate) fn encode_round(round: Round) -> Result<u32, ProtoError> {
+    round
+        .as_u32()
+        .ok_or_else(|| ProtoError::Other("invalid round".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use malachitebft_core_types::SigningProvider;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{Ed25519Provider, PrivateKey, Signature};
+
+    fn prevote(round: Round) -> Vote {
+        Vote::new_prevote(Height::new(1), round, NilOrVal::Nil, Address::new([1; 20]))
+    }
+
+    #[test]
+    fn round_proto_roundtrip() {
+        for round in [Round::new(0), Round::new(1), Round::new(u32::MAX)] {
+            let proto = prevote(round).to_proto().unwrap();
+            assert_eq!(proto.round, round.as_u32().unwrap());
+            assert_eq!(Vote::from_proto(proto).unwrap(), prevote(round));
+        }
+    }
+
+    #[test]
+    fn extension_proto_roundtrip() {
+        let extension = SignedExtension::new(
+            Extension::from(Bytes::from_static(b"ext")),
+            Signature::test(),
+        );
+        let vote = Vote::new_precommit_with_extension(
+            Height::new(1),
+            Round::new(0),
+            NilOrVal::Val(ValueId::new(42)),
+            Address::new([1; 20]),
+            extension,
+        );
+
+        assert_eq!(
+            vote.extension_bytes().map(|b| b.as_ref()),
+            Some(&b"ext"[..])
+        );
+
+        let decoded = Vote::from_proto(vote.to_proto().unwrap()).unwrap();
+        assert_eq!(decoded, vote);
+    }
+
+    #[test]
+    fn extension_is_covered_by_vote_signature() {
+        let private_key = PrivateKey::generate(&mut StdRng::seed_from_u64(0x42));
+        let public_key = private_key.public_key();
+        let provider = Ed25519Provider::new(private_key);
+
+        let extension = SignedExtension::new(
+            Extension::from(Bytes::from_static(b"ext")),
+            provider.sign(b"ext"),
+        );
+
+        let vote = Vote::new_precommit_with_extension(
+            Height::new(1),
+            Round::new(0),
+            NilOrVal::Val(ValueId::new(42)),
+            Address::from_public_key(&public_key),
+            extension,
+        );
+
+        let signed = provider.sign_vote(vote);
+        assert!(provider.verify_signed_vote(&signed.message, &signed.signature, &public_key));
+
+        let mut tampered = signed.message.clone();
+        tampered.extension.as_mut().unwrap().message = Extension::from(Bytes::from_static(b"txe"));
+        assert!(!provider.verify_signed_vote(&tampered, &signed.signature, &public_key));
+
+        let mut stripped = signed.message.clone();
+        stripped.extension = None;
+        assert!(!provider.verify_signed_vote(&stripped, &signed.signature, &public_key));
+    }
+
+    #[test]
+    fn nil_round_is_not_encoded() {
+        assert!(matches!(
+            prevote(Round::Nil).to_proto(),
+            Err(ProtoError::Other(e)) if e == "invalid round"
+        ));
+    }
+}
@@ -3,7 +3,7 @@ use tracing::{error, info};
 
 use malachitebft_app_channel::app::streaming::StreamContent;
 use malachitebft_app_channel::app::types::core::{Round, Validity};
-use malachitebft_app_channel::app::types::ProposedValue;
+use malachitebft_app_channel::app::types::{ProposeDecision, ProposedValue};
 use malachitebft_app_channel::{AppMsg, Channels, ConsensusMsg, NetworkMsg};
 use malachitebft_test::{Genesis, TestContext};
 
@@ -55,11 +55,13 @@ pub async fn run(
                 height,
                 round,
                 timeout: _,
+                cancel: _,
                 reply,
             } => {
                 // NOTE: We can ignore the timeout as we are building the value right away.
                 // If we were let's say reaping as many txes from a mempool and executing them,
-                // then we would need to respect the timeout and stop at a certain point.
+                // then we would need to respect the timeout and stop at a certain point,
+                // as well as stop early once the `cancel` token is cancelled by consensus.
 
                 info!(%height, %round, "Consensus is requesting a value to propose");
 
@@ -103,6 +105,22 @@ pub async fn run(
                 // avoid blowing up the bandwidth requirements by gossiping a single huge message.
             }
 
+            // Right before proposing the value we just built, the engine gives us a final say on it.
+            // We could eg. replace it with a value stamped with a more recent timestamp,
+            // or reject it if a service it depends on is down. Here we just accept it as is.
+            AppMsg::ReadyToPropose {
+                height,
+                round,
+                value: _,
+                reply,
+            } => {
+                info!(%height, %round, "Consensus is ready to propose our value");
+
+                if reply.send(ProposeDecision::Accept).is_err() {
+                    error!("Failed to send ReadyToPropose reply");
+                }
+            }
+
             // On the receiving end of these proposal parts (ie. when we are not the proposer),
             // we need to process these parts and re-assemble the full value.
             // To this end, we store each part that we receive and assemble the full value once we
@@ -1,6 +1,7 @@
 //! Example application using channels
 
 mod app;
+mod metrics;
 
 use eyre::{eyre, Result};
 use tracing::{info, trace};
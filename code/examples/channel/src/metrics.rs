@@ -0,0 +1,30 @@
+use malachitebft_app_channel::app::types::metrics::prometheus::metrics::counter::Counter;
+use malachitebft_app_channel::app::types::metrics::SharedRegistry;
+
+/// Metrics of the application, on top of the ones reported by the engine
+#[derive(Clone, Debug, Default)]
+pub struct Metrics {
+    /// Number of proposals streamed to us which were dropped because
+    /// they were not sent by the proposer of their round or not signed by it
+    pub invalid_proposals: Counter,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(registry: &SharedRegistry) -> Self {
+        let metrics = Self::new();
+
+        registry.with_prefix("malachitebft_example_channel", |registry| {
+            registry.register(
+                "invalid_proposals",
+                "Number of proposals dropped because of an invalid proposer or signature",
+                metrics.invalid_proposals.clone(),
+            );
+        });
+
+        metrics
+    }
+}
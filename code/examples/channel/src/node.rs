@@ -8,16 +8,18 @@ use rand::{CryptoRng, RngCore};
 
 use malachitebft_app_channel::app::types::config::Config;
 use malachitebft_app_channel::app::types::core::VotingPower;
+use malachitebft_app_channel::app::types::metrics::SharedRegistry;
 use malachitebft_app_channel::app::types::Keypair;
 use malachitebft_app_channel::app::Node;
 
 // Use the same types used for integration tests.
 // A real application would use its own types and context instead.
-use malachitebft_test::codec::proto::ProtobufCodec;
+use malachitebft_test::codec::wire::WireCodec;
 use malachitebft_test::{
     Address, Genesis, Height, PrivateKey, PublicKey, TestContext, Validator, ValidatorSet,
 };
 
+use crate::metrics::Metrics;
 use crate::state::State;
 
 /// Main application struct implementing the consensus node functionality
@@ -103,7 +105,7 @@ impl Node for App {
         let genesis = self.load_genesis(self.genesis_file.clone())?;
         let initial_validator_set = genesis.validator_set.clone();
 
-        let codec = ProtobufCodec;
+        let codec = WireCodec::from_config(&self.config.consensus.p2p);
 
         let mut channels = malachitebft_app_channel::run(
             ctx.clone(),
@@ -116,7 +118,16 @@ impl Node for App {
         )
         .await?;
 
-        let mut state = State::new(ctx, address, self.start_height.unwrap_or_default());
+        let registry = SharedRegistry::global().with_moniker(&self.config.moniker);
+        let metrics = Metrics::register(&registry);
+
+        let mut state = State::new(
+            ctx,
+            address,
+            self.start_height.unwrap_or_default(),
+            genesis.validator_set.clone(),
+            metrics,
+        );
 
         crate::app::run(genesis, &mut state, &mut channels).await
     }
@@ -7,27 +7,51 @@ use bytes::Bytes;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 use sha3::Digest;
-use tracing::debug;
+use tracing::{debug, warn};
 
 use malachitebft_app_channel::app::consensus::ProposedValue;
 use malachitebft_app_channel::app::host::LocallyProposedValue;
 use malachitebft_app_channel::app::streaming::{StreamContent, StreamMessage};
 use malachitebft_app_channel::app::types::codec::Codec;
-use malachitebft_app_channel::app::types::core::{CommitCertificate, Round, Validity};
+use malachitebft_app_channel::app::types::core::{CommitCertificate, Context, Round, Validity};
 use malachitebft_app_channel::app::types::sync::DecidedValue;
 use malachitebft_app_channel::app::types::PeerId;
 use malachitebft_test::codec::proto::ProtobufCodec;
 use malachitebft_test::{
-    Address, Height, ProposalData, ProposalFin, ProposalInit, ProposalPart, TestContext, Value,
+    Address, Height, ProposalData, ProposalFin, ProposalInit, ProposalPart, TestContext,
+    ValidatorSet, Value,
 };
 
+use crate::metrics::Metrics;
 use crate::streaming::{PartStreamsMap, ProposalParts};
 
+/// Why a proposal streamed to us was rejected
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum InvalidProposal {
+    /// The proposal is for a round which cannot have a proposer
+    #[error("Invalid round: {0}")]
+    InvalidRound(Round),
+
+    /// The proposal was not sent by the proposer of its round
+    #[error("Proposal sent by {actual} instead of the proposer {expected}")]
+    NotProposer { expected: Address, actual: Address },
+
+    /// The proposal does not contain a `Fin` part holding its signature
+    #[error("Missing signature")]
+    MissingSignature,
+
+    /// The signature in the `Fin` part is not a signature of the proposer over the parts
+    #[error("Invalid signature")]
+    InvalidSignature,
+}
+
 /// Represents the internal state of the application node
 /// Contains information about current height, round, proposals and blocks
 pub struct State {
     ctx: TestContext,
     address: Address,
+    validator_set: ValidatorSet,
+    metrics: Metrics,
 
     pub current_height: Height,
     pub current_round: Round,
@@ -57,9 +81,17 @@ fn seed_from_address(address: &Address) -> u64 {
 
 impl State {
     /// Creates a new State instance with the given validator address and starting height
-    pub fn new(ctx: TestContext, address: Address, height: Height) -> Self {
+    pub fn new(
+        ctx: TestContext,
+        address: Address,
+        height: Height,
+        validator_set: ValidatorSet,
+        metrics: Metrics,
+    ) -> Self {
         Self {
             ctx,
+            validator_set,
+            metrics,
             current_height: height,
             current_round: Round::new(0),
             current_proposer: None,
@@ -108,6 +140,22 @@ impl State {
             return None;
         }
 
+        // Check that the proposal was sent and signed by the proposer of its round,
+        // otherwise a peer could make us assemble a value the proposer never proposed
+        if let Err(reason) = self.verify_proposal_parts(&parts) {
+            warn!(
+                height = %parts.height,
+                round = %parts.round,
+                proposer = %parts.proposer,
+                %reason,
+                "Received invalid proposal, dropping it"
+            );
+
+            self.metrics.invalid_proposals.inc();
+
+            return None;
+        }
+
         // Re-assemble the proposal from its parts
         let value = assemble_value_from_parts(parts);
 
@@ -117,6 +165,50 @@ impl State {
         Some(value)
     }
 
+    /// Verifies that the given parts were sent by the proposer for their height and round,
+    /// and that the signature found in the `Fin` part is the proposer's signature over the parts.
+    fn verify_proposal_parts(&self, parts: &ProposalParts) -> Result<(), InvalidProposal> {
+        if parts.round.as_i64() < 0 {
+            return Err(InvalidProposal::InvalidRound(parts.round));
+        }
+
+        let expected = self
+            .ctx
+            .select_proposer(&self.validator_set, parts.height, parts.round);
+
+        if expected.address != parts.proposer {
+            return Err(InvalidProposal::NotProposer {
+                expected: expected.address,
+                actual: parts.proposer,
+            });
+        }
+
+        let signature = parts
+            .parts
+            .iter()
+            .find_map(|part| part.as_fin())
+            .map(|fin| &fin.signature)
+            .ok_or(InvalidProposal::MissingSignature)?;
+
+        let factors = parts
+            .parts
+            .iter()
+            .filter_map(|part| part.as_data())
+            .map(|data| data.factor);
+
+        let hash = hash_proposal(parts.height, parts.round, factors);
+
+        if !self
+            .ctx
+            .signing_provider
+            .verify(&hash, signature, &expected.public_key)
+        {
+            return Err(InvalidProposal::InvalidSignature);
+        }
+
+        Ok(())
+    }
+
     /// Retrieves a decided block at the given height
     pub fn get_decided_value(&self, height: &Height) -> Option<&DecidedValue<TestContext>> {
         self.decided_values.get(height)
@@ -252,48 +344,49 @@ impl State {
     }
 
     fn value_to_parts(&self, value: LocallyProposedValue<TestContext>) -> Vec<ProposalPart> {
-        let mut hasher = sha3::Keccak256::new();
-        let mut parts = Vec::new();
+        let factors = factor_value(value.value);
+        let mut parts = Vec::with_capacity(factors.len() + 2);
 
         // Init
         // Include metadata about the proposal
-        {
-            parts.push(ProposalPart::Init(ProposalInit::new(
-                value.height,
-                value.round,
-                self.address,
-            )));
-
-            hasher.update(value.height.as_u64().to_be_bytes().as_slice());
-            hasher.update(value.round.as_i64().to_be_bytes().as_slice());
-
-            if let Some(ext) = &value.extension {
-                hasher.update(ext.data.as_ref());
-            }
-        }
+        parts.push(ProposalPart::Init(ProposalInit::new(
+            value.height,
+            value.round,
+            self.address,
+        )));
 
         // Data
         // Include each prime factor of the value as a separate proposal part
-        {
-            for factor in factor_value(value.value) {
-                parts.push(ProposalPart::Data(ProposalData::new(factor)));
-
-                hasher.update(factor.to_be_bytes().as_slice());
-            }
+        for &factor in &factors {
+            parts.push(ProposalPart::Data(ProposalData::new(factor)));
         }
 
         // Fin
         // Sign the hash of the proposal parts
-        {
-            let hash = hasher.finalize().to_vec();
-            let signature = self.ctx.signing_provider.sign(&hash);
-            parts.push(ProposalPart::Fin(ProposalFin::new(signature)));
-        }
+        let hash = hash_proposal(value.height, value.round, factors);
+        let signature = self.ctx.signing_provider.sign(&hash);
+        parts.push(ProposalPart::Fin(ProposalFin::new(signature)));
 
         parts
     }
 }
 
+/// Hashes the height and round of a proposal along with the factors found in its data parts.
+///
+/// This is the message signed by the proposer in the `Fin` part of the proposal.
+fn hash_proposal(height: Height, round: Round, factors: impl IntoIterator<Item = u64>) -> Vec<u8> {
+    let mut hasher = sha3::Keccak256::new();
+
+    hasher.update(height.as_u64().to_be_bytes().as_slice());
+    hasher.update(round.as_i64().to_be_bytes().as_slice());
+
+    for factor in factors {
+        hasher.update(factor.to_be_bytes().as_slice());
+    }
+
+    hasher.finalize().to_vec()
+}
+
 /// Re-assemble a [`ProposedValue`] from its [`ProposalParts`].
 ///
 /// This is done by multiplying all the factors in the parts.
@@ -310,7 +403,7 @@ fn assemble_value_from_parts(parts: ProposalParts) -> ProposedValue<TestContext>
         valid_round: Round::Nil,
         proposer: parts.proposer,
         value: Value::new(value),
-        validity: Validity::Valid, // TODO: Check signature in Fin part
+        validity: Validity::Valid, // The signature was checked in `verify_proposal_parts`
         extension: None,
     }
 }
@@ -350,3 +443,148 @@ fn factor_value(value: Value) -> Vec<u64> {
 
     factors
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use malachitebft_test::utils::validators::make_validators;
+    use malachitebft_test::PrivateKey;
+
+    const HEIGHT: Height = Height::new(1);
+    const ROUND: Round = Round::new(0);
+
+    struct Setup {
+        proposer: (Address, PrivateKey),
+        other: (Address, PrivateKey),
+        validator_set: ValidatorSet,
+    }
+
+    fn setup() -> Setup {
+        let validators = make_validators([1, 1, 1]);
+        let validator_set = ValidatorSet::new(validators.iter().map(|(v, _)| v.clone()));
+
+        let ctx = TestContext::new(validators[0].1.clone());
+        let proposer = ctx.select_proposer(&validator_set, HEIGHT, ROUND).address;
+
+        let (proposer, other): (Vec<_>, Vec<_>) = validators
+            .into_iter()
+            .map(|(v, key)| (v.address, key))
+            .partition(|(address, _)| *address == proposer);
+
+        Setup {
+            proposer: proposer[0].clone(),
+            other: other[0].clone(),
+            validator_set,
+        }
+    }
+
+    fn state(validator: &(Address, PrivateKey), validator_set: &ValidatorSet) -> State {
+        let (address, key) = validator;
+        let ctx = TestContext::new(key.clone());
+        State::new(ctx, *address, HEIGHT, validator_set.clone(), Metrics::new())
+    }
+
+    /// The stream of parts of a proposal made by the given validator
+    fn proposal_stream(
+        validator: &(Address, PrivateKey),
+        validator_set: &ValidatorSet,
+    ) -> Vec<StreamMessage<ProposalPart>> {
+        let mut state = state(validator, validator_set);
+        let value = state.propose_value(HEIGHT, ROUND);
+        state.stream_proposal(value).collect()
+    }
+
+    /// Feed the stream to the given state, returning the assembled value, if any
+    fn receive(
+        state: &mut State,
+        stream: Vec<StreamMessage<ProposalPart>>,
+    ) -> Option<ProposedValue<TestContext>> {
+        let peer_id = PeerId::random();
+
+        stream
+            .into_iter()
+            .filter_map(|msg| state.received_proposal_part(peer_id, msg))
+            .last()
+    }
+
+    /// Apply the given function to the content of every data part of the stream
+    fn map_parts(
+        stream: Vec<StreamMessage<ProposalPart>>,
+        f: impl Fn(ProposalPart) -> ProposalPart,
+    ) -> Vec<StreamMessage<ProposalPart>> {
+        stream
+            .into_iter()
+            .map(|msg| match msg.content {
+                StreamContent::Data(part) => {
+                    StreamMessage::new(msg.stream_id, msg.sequence, StreamContent::Data(f(part)))
+                }
+                content => StreamMessage::new(msg.stream_id, msg.sequence, content),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn proposal_from_proposer_is_accepted() {
+        let setup = setup();
+        let mut receiver = state(&setup.other, &setup.validator_set);
+
+        let stream = proposal_stream(&setup.proposer, &setup.validator_set);
+        let value = receive(&mut receiver, stream).expect("proposal is accepted");
+
+        assert_eq!(value.proposer, setup.proposer.0);
+        assert_eq!(value.validity, Validity::Valid);
+        assert_eq!(receiver.metrics.invalid_proposals.get(), 0);
+    }
+
+    #[test]
+    fn proposal_with_forged_data_is_rejected() {
+        let setup = setup();
+        let mut receiver = state(&setup.other, &setup.validator_set);
+
+        // Replace the factors of the value, keeping the signature of the proposer
+        let stream = map_parts(
+            proposal_stream(&setup.proposer, &setup.validator_set),
+            |part| match part {
+                ProposalPart::Data(_) => ProposalPart::Data(ProposalData::new(7)),
+                part => part,
+            },
+        );
+
+        assert_eq!(receive(&mut receiver, stream), None);
+        assert_eq!(receiver.metrics.invalid_proposals.get(), 1);
+    }
+
+    #[test]
+    fn proposal_with_forged_signature_is_rejected() {
+        let setup = setup();
+        let mut receiver = state(&setup.other, &setup.validator_set);
+
+        // Sign the proposal with a key that is not the proposer's
+        let (_, other_key) = &setup.other;
+        let stream = map_parts(
+            proposal_stream(&setup.proposer, &setup.validator_set),
+            |part| match part {
+                ProposalPart::Fin(_) => {
+                    ProposalPart::Fin(ProposalFin::new(other_key.sign(b"forged")))
+                }
+                part => part,
+            },
+        );
+
+        assert_eq!(receive(&mut receiver, stream), None);
+        assert_eq!(receiver.metrics.invalid_proposals.get(), 1);
+    }
+
+    #[test]
+    fn proposal_from_non_proposer_is_rejected() {
+        let setup = setup();
+        let mut receiver = state(&setup.proposer, &setup.validator_set);
+
+        // The proposal is properly signed, but by a validator who is not the proposer
+        let stream = proposal_stream(&setup.other, &setup.validator_set);
+
+        assert_eq!(receive(&mut receiver, stream), None);
+        assert_eq!(receiver.metrics.invalid_proposals.get(), 1);
+    }
+}